@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tacacs_plus_protocol::accounting::Reply;
+use tacacs_plus_protocol::Deserialize;
+
+fuzz_target!(|body: &[u8]| {
+    // deserialization must never panic, and any body it accepts must agree with its own length fields
+    if Reply::deserialize_from_buffer(body).is_ok() {
+        assert_eq!(Reply::extract_total_length(body), Ok(body.len() as u32));
+    }
+});
@@ -1,16 +1,17 @@
 //! Accounting protocol packet (de)serialization.
 
 use bitflags::bitflags;
-use byteorder::{ByteOrder, NetworkEndian};
 use core::fmt;
 use getset::Getters;
-use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use super::{
     Arguments, AuthenticationContext, AuthenticationMethod, Deserialize, DeserializeError,
-    PacketBody, PacketType, Serialize, SerializeError, UserInformation,
+    KnownLength, PacketBody, PacketType, Serialize, SerializeError, UserInformation,
 };
-use crate::FieldText;
+use crate::body::{BodyReader, BodyWriter};
+use crate::util::{U16_LENGTH_WIRE_SIZE, U8_LENGTH_WIRE_SIZE};
+use crate::{wire, FieldText};
 
 #[cfg(test)]
 mod tests;
@@ -21,12 +22,15 @@ mod owned;
 #[cfg(feature = "std")]
 pub use owned::ReplyOwned;
 
+mod incremental;
+pub use incremental::{IncrementalReply, ReplyChunk};
+
 bitflags! {
     /// Raw bitflags for accounting request packet.
     struct RawFlags: u8 {
-        const START    = 0b00000010;
-        const STOP     = 0b00000100;
-        const WATCHDOG = 0b00001000;
+        const START    = wire::TAC_PLUS_ACCT_FLAG_START;
+        const STOP     = wire::TAC_PLUS_ACCT_FLAG_STOP;
+        const WATCHDOG = wire::TAC_PLUS_ACCT_FLAG_WATCHDOG;
     }
 }
 
@@ -74,7 +78,7 @@ impl From<Flags> for RawFlags {
 
 impl Flags {
     /// The number of bytes occupied by a flag set on the wire.
-    pub(super) const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
 /// An accounting request packet, used to start, stop, or provide progress on a running job.
@@ -97,8 +101,23 @@ pub struct Request<'packet> {
 }
 
 impl<'packet> Request<'packet> {
+    /// Offset of the flags within a request packet body.
+    pub const FLAGS_OFFSET: usize = 0;
+
+    /// Offset of the authentication method within a request packet body.
+    pub const AUTHENTICATION_METHOD_OFFSET: usize = 1;
+
+    /// Offset of the authentication context (privilege level, type & service) within a request packet body.
+    pub const AUTHENTICATION_CONTEXT_OFFSET: usize = 2;
+
+    /// Offset of the user information field lengths within a request packet body.
+    pub const USER_INFORMATION_LENGTHS_OFFSET: usize = 5;
+
+    /// Offset of the argument count within a request packet body.
+    pub const ARGUMENT_COUNT_OFFSET: usize = 8;
+
     /// Argument lengths in a request packet start at index 9, if present.
-    const ARGUMENT_LENGTHS_OFFSET: usize = 9;
+    pub const ARGUMENT_LENGTHS_OFFSET: usize = 9;
 
     /// Assembles a new accounting request packet body.
     pub fn new(
@@ -116,6 +135,11 @@ impl<'packet> Request<'packet> {
             arguments,
         }
     }
+
+    /// Returns the exact number of bytes this request body occupies when serialized, excluding the packet header.
+    pub fn encoded_size(&self) -> usize {
+        Serialize::wire_size(self)
+    }
 }
 
 impl PacketBody for Request<'_> {
@@ -126,6 +150,27 @@ impl PacketBody for Request<'_> {
         Flags::WIRE_SIZE + AuthenticationMethod::WIRE_SIZE + AuthenticationContext::WIRE_SIZE + 4;
 }
 
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(Request::AUTHENTICATION_METHOD_OFFSET == Request::FLAGS_OFFSET + Flags::WIRE_SIZE);
+    assert!(
+        Request::AUTHENTICATION_CONTEXT_OFFSET
+            == Request::AUTHENTICATION_METHOD_OFFSET + AuthenticationMethod::WIRE_SIZE
+    );
+    assert!(
+        Request::USER_INFORMATION_LENGTHS_OFFSET
+            == Request::AUTHENTICATION_CONTEXT_OFFSET + AuthenticationContext::WIRE_SIZE
+    );
+    assert!(
+        Request::ARGUMENT_COUNT_OFFSET
+            == Request::USER_INFORMATION_LENGTHS_OFFSET + UserInformation::HEADER_INFORMATION_SIZE
+    );
+    assert!(
+        Request::ARGUMENT_LENGTHS_OFFSET == Request::ARGUMENT_COUNT_OFFSET + U8_LENGTH_WIRE_SIZE
+    );
+    assert!(Request::REQUIRED_FIELDS_LENGTH == Request::ARGUMENT_LENGTHS_OFFSET);
+};
+
 impl Serialize for Request<'_> {
     fn wire_size(&self) -> usize {
         Flags::WIRE_SIZE
@@ -135,61 +180,27 @@ impl Serialize for Request<'_> {
             + self.arguments.wire_size()
     }
 
-    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    fn serialize_into_writer(&self, mut writer: BodyWriter<'_>) -> Result<usize, SerializeError> {
         let wire_size = self.wire_size();
 
-        if buffer.len() >= wire_size {
-            buffer[0] = RawFlags::from(self.flags).bits();
-            buffer[1] = self.authentication_method as u8;
-
-            // header information (lengths, etc.)
-            self.authentication.serialize(&mut buffer[2..5]);
-            self.user_information
-                .serialize_field_lengths(&mut buffer[5..8])?;
-
-            let argument_count = self.arguments.argument_count() as usize;
-
-            // body starts after the required fields & the argument lengths (1 byte per argument)
-            let body_start = Self::ARGUMENT_LENGTHS_OFFSET + argument_count;
-
-            // actual request content
-            // as below, slice bounds are capped to end of packet body to avoid overflowing
-            let user_information_len = self
-                .user_information
-                .serialize_field_values(&mut buffer[body_start..wire_size])?;
-
-            let arguments_serialized_len =
-                // argument lengths start at index 8
-                // extra byte is included in slice for argument count itself
-                self.arguments.serialize_count_and_lengths(&mut buffer[8..8 + argument_count + 1])?
-                    // argument values go after the user information values in the body
-                    + self
-                        .arguments
-                        .serialize_encoded_values(&mut buffer[body_start + user_information_len..wire_size])?;
-
-            // NOTE: as with authorization, 1 is subtracted from REQUIRED_FIELDS_LENGTH as the argument count would be double counted otherwise
-            let actual_written_len = (Self::REQUIRED_FIELDS_LENGTH - 1)
-                + user_information_len
-                + arguments_serialized_len;
-
-            // ensure expected/actual sizes match
-            if actual_written_len == wire_size {
-                Ok(actual_written_len)
-            } else {
-                Err(SerializeError::LengthMismatch {
-                    expected: wire_size,
-                    actual: actual_written_len,
-                })
-            }
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        writer.write_u8(RawFlags::from(self.flags).bits())?;
+        writer.write_u8(self.authentication_method as u8)?;
+        self.authentication.serialize(&mut writer)?;
+        self.user_information.serialize_field_lengths(&mut writer)?;
+        self.arguments.serialize_count_and_lengths(&mut writer)?;
+
+        // as with authorization, user information values go between the argument lengths and values
+        self.user_information.serialize_field_values(&mut writer)?;
+        self.arguments.serialize_encoded_values(&mut writer)?;
+
+        writer.finish(wire_size)
     }
 }
 
 /// The server's reply status in an accounting session.
+#[non_exhaustive]
 #[repr(u8)]
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, TryFromPrimitive)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, FromPrimitive, IntoPrimitive)]
 pub enum Status {
     /// Task logging succeeded.
     Success = 0x01,
@@ -200,32 +211,64 @@ pub enum Status {
     /// Forward accounting request to an alternative daemon.
     #[deprecated = "Forwarding to an alternative daemon was deprecated in RFC-8907."]
     Follow = 0x21,
+
+    /// A status without a meaning defined by RFC8907, e.g. one from a protocol extension.
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl Status {
     /// The number of bytes an accounting reply status occupies on the wire.
-    pub(super) const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
+/// Formats a description of the status, or its name from RFC8907 (e.g., `TAC_PLUS_ACCT_STATUS_SUCCESS`) with the
+/// alternate flag (`{:#}`).
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Success => "success",
-                Self::Error => "error",
-                #[allow(deprecated)]
-                Self::Follow => "follow",
-            }
-        )
+        let (description, rfc_name) = match self {
+            Self::Success => ("success", "TAC_PLUS_ACCT_STATUS_SUCCESS"),
+            Self::Error => ("error", "TAC_PLUS_ACCT_STATUS_ERROR"),
+            #[allow(deprecated)]
+            Self::Follow => ("follow", "TAC_PLUS_ACCT_STATUS_FOLLOW"),
+            Self::Unknown(status) => return write!(f, "unknown ({status:#04x})"),
+        };
+
+        f.write_str(if f.alternate() { rfc_name } else { description })
     }
 }
 
-#[doc(hidden)]
-impl From<TryFromPrimitiveError<Status>> for DeserializeError {
-    fn from(value: TryFromPrimitiveError<Status>) -> Self {
-        Self::InvalidStatus(value.number)
+/// An accounting status that's defined & not deprecated by RFC8907, for code that shouldn't have to handle
+/// [`Status::Follow`] or statuses from protocol extensions.
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum StrictStatus {
+    /// Task logging succeeded.
+    Success = 0x01,
+
+    /// Something went wrong when logging the task.
+    Error = 0x02,
+}
+
+impl From<StrictStatus> for Status {
+    fn from(status: StrictStatus) -> Self {
+        match status {
+            StrictStatus::Success => Self::Success,
+            StrictStatus::Error => Self::Error,
+        }
+    }
+}
+
+/// Fails with [`DeserializeError::InvalidStatus`] for deprecated & unknown statuses.
+impl TryFrom<Status> for StrictStatus {
+    type Error = DeserializeError;
+
+    fn try_from(status: Status) -> Result<Self, DeserializeError> {
+        match status {
+            Status::Success => Ok(Self::Success),
+            Status::Error => Ok(Self::Error),
+            other => Err(DeserializeError::InvalidStatus(other.into())),
+        }
     }
 }
 
@@ -253,42 +296,45 @@ struct ReplyFieldLengths {
 }
 
 impl Reply<'_> {
+    /// Offset of the server message length within a reply packet body.
+    pub const SERVER_MESSAGE_LENGTH_OFFSET: usize = 0;
+
+    /// Offset of the data length within a reply packet body.
+    pub const DATA_LENGTH_OFFSET: usize = 2;
+
+    /// Offset of the status within a reply packet body.
+    pub const STATUS_OFFSET: usize = 4;
+
     /// Offset of the server message in an accounting reply packet body, if present.
-    const SERVER_MESSAGE_OFFSET: usize = 5;
+    pub const SERVER_MESSAGE_OFFSET: usize = 5;
 
     /// Determines how long a raw reply packet is, if applicable, based on various lengths stored in the body "header."
+    ///
+    /// This is the same as [`KnownLength::extract_total_length()`], for use without importing the trait.
     pub fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
-        if buffer.len() >= Self::REQUIRED_FIELDS_LENGTH {
-            Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
-        } else {
-            Err(DeserializeError::UnexpectedEnd)
-        }
+        <Self as KnownLength>::extract_total_length(buffer)
     }
 
     /// Extracts the server message and data field lengths from a buffer, treating it as if it were a serialized reply packet body.
     fn extract_field_lengths(buffer: &[u8]) -> Result<ReplyFieldLengths, DeserializeError> {
         // ensure buffer is large enough to comprise a valid reply packet
-        if buffer.len() >= Self::REQUIRED_FIELDS_LENGTH {
-            // server message length is at the beginning of the packet
-            let server_message_length = NetworkEndian::read_u16(&buffer[..2]);
-
-            // data length is just after the server message length
-            let data_length = NetworkEndian::read_u16(&buffer[2..4]);
-
-            // full packet has required fields/lengths as well as the field values themselves
-            // SAFETY: REQUIRED_FIELDS_LENGTH is guaranteed to fit in a u32 based on its defined value
-            let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
-                + u32::from(server_message_length)
-                + u32::from(data_length);
-
-            Ok(ReplyFieldLengths {
-                server_message_length,
-                data_length,
-                total_length,
-            })
-        } else {
-            Err(DeserializeError::UnexpectedEnd)
-        }
+        let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
+
+        // server message length is at the beginning of the packet, with the data length just after it
+        let server_message_length = reader.read_u16()?;
+        let data_length = reader.read_u16()?;
+
+        // full packet has required fields/lengths as well as the field values themselves
+        // SAFETY: REQUIRED_FIELDS_LENGTH is guaranteed to fit in a u32 based on its defined value
+        let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
+            + u32::from(server_message_length)
+            + u32::from(data_length);
+
+        Ok(ReplyFieldLengths {
+            server_message_length,
+            data_length,
+            total_length,
+        })
     }
 }
 
@@ -299,6 +345,22 @@ impl PacketBody for Reply<'_> {
     const REQUIRED_FIELDS_LENGTH: usize = Status::WIRE_SIZE + 4;
 }
 
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(
+        Reply::DATA_LENGTH_OFFSET == Reply::SERVER_MESSAGE_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE
+    );
+    assert!(Reply::STATUS_OFFSET == Reply::DATA_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE);
+    assert!(Reply::SERVER_MESSAGE_OFFSET == Reply::STATUS_OFFSET + Status::WIRE_SIZE);
+    assert!(Reply::REQUIRED_FIELDS_LENGTH == Reply::SERVER_MESSAGE_OFFSET);
+};
+
+impl KnownLength for Reply<'_> {
+    fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
+        Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
+    }
+}
+
 impl<'raw> Deserialize<'raw> for Reply<'raw> {
     fn deserialize_from_buffer(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
         let extracted_lengths = Self::extract_field_lengths(buffer)?;
@@ -309,19 +371,15 @@ impl<'raw> Deserialize<'raw> for Reply<'raw> {
 
         // ensure buffer length & calculated length from body fields match
         if extracted_lengths.total_length as usize == length_from_header {
-            // SAFETY: extract_field_lengths() performs a check against REQUIRED_FIELDS_LENGTH (5), so this will not panic
-            let status = Status::try_from(buffer[4])?;
+            let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
 
-            let data_offset =
-                Self::SERVER_MESSAGE_OFFSET + extracted_lengths.server_message_length as usize;
+            // field lengths were already extracted above
+            reader.skip(Self::STATUS_OFFSET)?;
+            let status = Status::from(reader.read_u8()?);
 
             let server_message =
-                FieldText::try_from(&buffer[Self::SERVER_MESSAGE_OFFSET..data_offset])
-                    .map_err(|_| DeserializeError::BadText)?;
-            let data = FieldText::try_from(
-                &buffer[data_offset..data_offset + extracted_lengths.data_length as usize],
-            )
-            .map_err(|_| DeserializeError::BadText)?;
+                reader.read_text(extracted_lengths.server_message_length as usize)?;
+            let data = reader.read_text(extracted_lengths.data_length as usize)?;
 
             Ok(Self {
                 status,
@@ -0,0 +1,141 @@
+use getset::{CopyGetters, Getters};
+
+use super::{Reply, Status};
+use crate::body::BodyReader;
+use crate::{DeserializeError, FieldText, PacketBody};
+
+/// An accounting reply body that is deserialized incrementally, for replies whose
+/// variable-length fields are too large to comfortably hold in a single contiguous buffer.
+///
+/// The fixed-length fields (status & field lengths) are parsed up front from the first
+/// [`REQUIRED_FIELDS_LENGTH`](PacketBody::REQUIRED_FIELDS_LENGTH) bytes of the body, after which
+/// the rest of the body can be fed in arbitrarily sized chunks via [`feed()`](Self::feed).
+///
+/// Note that all bytes passed to this type must already be deobfuscated.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::accounting::{IncrementalReply, Status};
+///
+/// let mut reply = IncrementalReply::begin(&[
+///     0, 5, // server message length
+///     0, 4, // data length
+///     0x01, // status: success
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(reply.status(), Status::Success);
+/// assert_eq!(reply.remaining(), 9);
+///
+/// // chunk boundaries don't have to line up with field boundaries
+/// let first = reply.feed(b"hel").unwrap();
+/// assert_eq!(first.server_message(), &"hel");
+/// assert!(first.data().is_empty());
+///
+/// let second = reply.feed(b"lodata").unwrap();
+/// assert_eq!(second.server_message(), &"lo");
+/// assert_eq!(second.data(), &"data");
+///
+/// assert!(reply.is_complete());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, CopyGetters)]
+pub struct IncrementalReply {
+    /// Gets the status of the accounting reply.
+    #[getset(get_copy = "pub")]
+    status: Status,
+
+    /// Gets the total length of the server message field.
+    #[getset(get_copy = "pub")]
+    server_message_length: u16,
+
+    /// Gets the total length of the data field.
+    #[getset(get_copy = "pub")]
+    data_length: u16,
+
+    /// Number of bytes of the variable-length fields that have been fed so far.
+    consumed: usize,
+}
+
+/// The portions of the variable-length reply fields contained in a single chunk fed to an [`IncrementalReply`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct ReplyChunk<'chunk> {
+    /// The part of the server message contained in the chunk, which may be empty.
+    server_message: FieldText<'chunk>,
+
+    /// The part of the data field contained in the chunk, which may be empty.
+    data: FieldText<'chunk>,
+}
+
+impl IncrementalReply {
+    /// Parses the fixed-length fields of an accounting reply body from the start of a buffer.
+    ///
+    /// Only the first [`REQUIRED_FIELDS_LENGTH`](PacketBody::REQUIRED_FIELDS_LENGTH) bytes are inspected;
+    /// any bytes after that should be passed to [`feed()`](Self::feed).
+    pub fn begin(buffer: &[u8]) -> Result<Self, DeserializeError> {
+        let mut reader = BodyReader::new(buffer, Reply::REQUIRED_FIELDS_LENGTH)?;
+
+        let server_message_length = reader.read_u16()?;
+        let data_length = reader.read_u16()?;
+        let status = Status::from(reader.read_u8()?);
+
+        Ok(Self {
+            status,
+            server_message_length,
+            data_length,
+            consumed: 0,
+        })
+    }
+
+    /// Returns the full length of the reply body, including the fixed-length fields.
+    pub fn total_length(&self) -> usize {
+        Reply::REQUIRED_FIELDS_LENGTH + self.variable_fields_length()
+    }
+
+    /// Returns the number of bytes that have yet to be fed to this reader.
+    pub fn remaining(&self) -> usize {
+        self.variable_fields_length() - self.consumed
+    }
+
+    /// Returns true if both variable-length fields have been fully read.
+    pub fn is_complete(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Consumes the next chunk of the reply body, returning the parts of each field it contains.
+    ///
+    /// An error is returned if the chunk extends beyond the end of the body as reported by the
+    /// field lengths, or if the chunk contains text that isn't printable ASCII.
+    pub fn feed<'chunk>(
+        &mut self,
+        chunk: &'chunk [u8],
+    ) -> Result<ReplyChunk<'chunk>, DeserializeError> {
+        if chunk.len() > self.remaining() {
+            return Err(DeserializeError::WrongBodyBufferSize {
+                expected: self.total_length(),
+                buffer_size: Reply::REQUIRED_FIELDS_LENGTH + self.consumed + chunk.len(),
+            });
+        }
+
+        // split chunk at the server message/data boundary, if it falls within this chunk
+        let server_message_remaining =
+            usize::from(self.server_message_length).saturating_sub(self.consumed);
+        let (server_message, data) = chunk.split_at(server_message_remaining.min(chunk.len()));
+
+        let server_message =
+            FieldText::try_from(server_message).map_err(|_| DeserializeError::BadText)?;
+        let data = FieldText::try_from(data).map_err(|_| DeserializeError::BadText)?;
+
+        self.consumed += chunk.len();
+
+        Ok(ReplyChunk {
+            server_message,
+            data,
+        })
+    }
+
+    fn variable_fields_length(&self) -> usize {
+        usize::from(self.server_message_length) + usize::from(self.data_length)
+    }
+}
@@ -1,10 +1,12 @@
+use core::iter::zip;
+
 use super::*;
 use crate::packet::xor_body_with_pad;
 use crate::FieldText;
 use crate::{
-    Argument, AuthenticationContext, AuthenticationMethod, AuthenticationService,
+    Argument, Arguments, AuthenticationContext, AuthenticationMethod, AuthenticationService,
     AuthenticationType, HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags,
-    PrivilegeLevel, UserInformation, Version,
+    PrivilegeLevel, Serialize, SerializeError, UserInformation, Version,
 };
 
 use tinyvec::array_vec;
@@ -150,6 +152,106 @@ fn serialize_full_request_packet() {
     assert_eq!(&buffer[..packet_size], expected.as_slice());
 }
 
+#[test]
+fn request_serialization_round_trip() {
+    let all_arguments = [
+        Argument::new(
+            FieldText::assert("service"),
+            FieldText::assert("shell"),
+            true,
+        )
+        .unwrap(),
+        Argument::new(FieldText::assert("cmd"), FieldText::assert(""), false).unwrap(),
+        Argument::new(FieldText::assert("priv-lvl"), FieldText::assert("15"), true).unwrap(),
+    ];
+
+    let user_informations = [
+        UserInformation::new("", FieldText::assert(""), FieldText::assert("")).unwrap(),
+        UserInformation::new("user", FieldText::assert("tty0"), FieldText::assert("")).unwrap(),
+        UserInformation::new(
+            "someone",
+            FieldText::assert(""),
+            FieldText::assert("10.0.0.1"),
+        )
+        .unwrap(),
+    ];
+
+    let authentication_context = AuthenticationContext {
+        privilege_level: PrivilegeLevel::new(15).unwrap(),
+        authentication_type: AuthenticationType::NotSet,
+        service: AuthenticationService::Login,
+    };
+
+    for user_information in user_informations.iter() {
+        for argument_count in 0..=all_arguments.len() {
+            let arguments = &all_arguments[..argument_count];
+            let request = Request::new(
+                Flags::StartRecord,
+                AuthenticationMethod::TacacsPlus,
+                authentication_context,
+                user_information.clone(),
+                Arguments::new(&arguments).unwrap(),
+            );
+
+            let mut buffer = [0xff; 100];
+            let written = request
+                .serialize_into_buffer(&mut buffer)
+                .expect("buffer should have been big enough");
+            assert_eq!(written, request.wire_size());
+
+            // a buffer that's just barely too small should be rejected rather than partially written
+            assert_eq!(
+                request.serialize_into_buffer(&mut [0; 100][..written - 1]),
+                Err(SerializeError::NotEnoughSpace)
+            );
+
+            // walking the body using only its encoded lengths should yield back the original fields
+            let body = &buffer[..written];
+            assert_eq!(
+                body[Request::FLAGS_OFFSET],
+                RawFlags::from(Flags::StartRecord).bits()
+            );
+            assert_eq!(
+                body[Request::AUTHENTICATION_METHOD_OFFSET],
+                AuthenticationMethod::TacacsPlus as u8
+            );
+            let user_information_lengths =
+                &body[Request::USER_INFORMATION_LENGTHS_OFFSET..Request::ARGUMENT_COUNT_OFFSET];
+            assert_eq!(
+                body[Request::ARGUMENT_COUNT_OFFSET] as usize,
+                argument_count
+            );
+            let argument_lengths = &body[Request::REQUIRED_FIELDS_LENGTH..][..argument_count];
+
+            let mut rest = &body[Request::REQUIRED_FIELDS_LENGTH + argument_count..];
+            let mut take = |length: u8| {
+                let (field, remaining) = rest.split_at(length as usize);
+                rest = remaining;
+                field
+            };
+
+            assert_eq!(
+                take(user_information_lengths[0]),
+                user_information.user().as_bytes()
+            );
+            assert_eq!(
+                take(user_information_lengths[1]),
+                user_information.port().as_bytes()
+            );
+            assert_eq!(
+                take(user_information_lengths[2]),
+                user_information.remote_address().as_bytes()
+            );
+
+            for (argument, &length) in zip(arguments, argument_lengths) {
+                assert_eq!(Argument::deserialize(take(length)).as_ref(), Ok(argument));
+            }
+
+            assert!(rest.is_empty(), "no bytes should be left over");
+        }
+    }
+}
+
 #[test]
 fn deserialize_reply_all_fields() {
     let mut body_raw = array_vec!([u8; 70]);
@@ -312,3 +414,77 @@ fn serialize_request_packet_obfuscated() {
     // ensure obfuscation is correct
     assert_eq!(&buffer[..serialized_length], &expected[..serialized_length]);
 }
+
+#[test]
+fn incremental_reply_chunks_across_field_boundary() {
+    let body = [
+        0, 4, // server message length
+        0, 6,    // data length
+        0x02, // status: error
+    ];
+
+    let mut reply = IncrementalReply::begin(&body).expect("fixed fields should be valid");
+    assert_eq!(reply.status(), Status::Error);
+    assert_eq!(reply.total_length(), 15);
+    assert_eq!(reply.remaining(), 10);
+
+    // chunk ending partway through server message
+    let chunk = reply.feed(b"mes").unwrap();
+    assert_eq!(chunk.server_message(), &"mes");
+    assert!(chunk.data().is_empty());
+
+    // chunk straddling both fields
+    let chunk = reply.feed(b"sage").unwrap();
+    assert_eq!(chunk.server_message(), &"s");
+    assert_eq!(chunk.data(), &"age");
+
+    // chunk completely within data
+    let chunk = reply.feed(b"abc").unwrap();
+    assert!(chunk.server_message().is_empty());
+    assert_eq!(chunk.data(), &"abc");
+
+    assert!(reply.is_complete());
+}
+
+#[test]
+fn incremental_reply_too_much_data() {
+    let mut reply = IncrementalReply::begin(&[0, 1, 0, 1, 0x01]).unwrap();
+
+    assert_eq!(
+        reply.feed(b"abc"),
+        Err(DeserializeError::WrongBodyBufferSize {
+            expected: 7,
+            buffer_size: 8
+        })
+    );
+
+    // a failed feed shouldn't consume anything
+    assert_eq!(reply.remaining(), 2);
+}
+
+#[test]
+fn incremental_reply_bad_text() {
+    let mut reply = IncrementalReply::begin(&[0, 0, 0, 2, 0x01]).unwrap();
+    assert_eq!(reply.feed(b"\t\n"), Err(DeserializeError::BadText));
+}
+
+#[test]
+fn incremental_reply_header_too_short() {
+    assert_eq!(
+        IncrementalReply::begin(&[0, 1, 0]),
+        Err(DeserializeError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn strict_status_rejects_follow_and_unknown() {
+    assert_eq!(Status::from(StrictStatus::Success), Status::Success);
+    assert_eq!(
+        StrictStatus::try_from(Status::Error),
+        Ok(StrictStatus::Error)
+    );
+    assert_eq!(
+        StrictStatus::try_from(Status::from(0x21)),
+        Err(DeserializeError::InvalidStatus(0x21))
+    );
+}
@@ -1,9 +1,8 @@
 use core::fmt;
-use core::iter::zip;
 
 use getset::{CopyGetters, Getters, Setters};
 
-use super::{DeserializeError, SerializeError};
+use super::{BodyWriter, DeserializeError, SerializeError};
 use crate::FieldText;
 
 #[cfg(test)]
@@ -69,6 +68,47 @@ impl fmt::Display for InvalidArgument {
     }
 }
 
+/// An ambiguous set of arguments, as reported by [`Arguments::new_strict()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InvalidArguments {
+    /// There were more than `u8::MAX` arguments, which don't fit in a packet.
+    TooMany,
+
+    /// An argument name appeared as both mandatory & optional.
+    MixedMandatory {
+        /// The index of the first argument with the name.
+        first: usize,
+
+        /// The index of the argument with the same name but the other delimiter.
+        second: usize,
+    },
+
+    /// An argument name that isn't meant to be repeated appeared with different values.
+    ConflictingValues {
+        /// The index of the first argument with the name.
+        first: usize,
+
+        /// The index of the argument with the same name but a different value.
+        second: usize,
+    },
+}
+
+impl fmt::Display for InvalidArguments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooMany => write!(f, "only up to 255 (i.e., `u8::MAX`) arguments fit in a packet"),
+            Self::MixedMandatory { first, second } => write!(
+                f,
+                "arguments {first} and {second} have the same name but differ in whether they're mandatory"
+            ),
+            Self::ConflictingValues { first, second } => write!(
+                f,
+                "arguments {first} and {second} have the same name but different values"
+            ),
+        }
+    }
+}
+
 impl From<InvalidArgument> for DeserializeError {
     fn from(value: InvalidArgument) -> Self {
         Self::InvalidArgument(value)
@@ -108,6 +148,31 @@ impl<'data> Argument<'data> {
         }
     }
 
+    /// Constructs an optional argument with an empty value, which RFC8907 treats as the value being unspecified rather
+    /// than an empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::{Argument, FieldText};
+    ///
+    /// let unspecified = Argument::unspecified(FieldText::try_from("addr").unwrap()).unwrap();
+    /// assert!(unspecified.is_unspecified());
+    /// assert_eq!(unspecified.to_string(), "addr*");
+    ///
+    /// // a mandatory argument with an empty value is an empty string instead
+    /// let empty = Argument::new(FieldText::try_from("addr").unwrap(), FieldText::try_from("").unwrap(), true).unwrap();
+    /// assert!(!empty.is_unspecified());
+    /// ```
+    pub fn unspecified(name: FieldText<'data>) -> Result<Self, InvalidArgument> {
+        Self::new(name, FieldText::default(), false)
+    }
+
+    /// Whether this argument's value is unspecified, i.e. it's optional with an empty value.
+    pub fn is_unspecified(&self) -> bool {
+        !self.mandatory && self.value.is_empty()
+    }
+
     /// Converts this `Argument` to one which owns its fields.
     #[cfg(feature = "std")]
     pub fn into_owned<'out>(self) -> Argument<'out> {
@@ -119,37 +184,23 @@ impl<'data> Argument<'data> {
     }
 
     /// The encoded length of an argument, including the name/value/delimiter but not the byte holding its length earlier on in a packet.
-    fn encoded_length(&self) -> u8 {
+    pub(crate) fn encoded_length(&self) -> u8 {
         // SAFETY: this should never panic due to length checks in new()
         // length includes delimiter
         (self.name.len() + 1 + self.value.len()).try_into().unwrap()
     }
 
     /// Serializes an argument's name-value encoding, as done in the body of a packet.
-    fn serialize(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
-        let name_len = self.name.len();
-        let value_len = self.value.len();
-
-        // delimiter is placed just after name, meaning its index is exactly the name length
-        let delimiter_index = name_len;
+    fn serialize(&self, writer: &mut BodyWriter<'_>) -> Result<usize, SerializeError> {
+        writer.write_bytes(self.name.as_bytes())?;
 
-        // name + value + 1 extra byte for delimiter
-        let encoded_len = name_len + 1 + value_len;
-
-        // buffer must be large enough to hold name, value, and delimiter
-        if buffer.len() >= encoded_len {
-            buffer[..delimiter_index].copy_from_slice(self.name.as_bytes());
+        // choose delimiter based on whether argument is required
+        writer.write_u8(self.delimiter() as u8)?;
 
-            // choose delimiter based on whether argument is required
-            buffer[delimiter_index] = self.delimiter() as u8;
+        writer.write_bytes(self.value.as_bytes())?;
 
-            // value goes just after delimiter
-            buffer[delimiter_index + 1..encoded_len].copy_from_slice(self.value.as_bytes());
-
-            Ok(encoded_len)
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        // name + value + 1 extra byte for delimiter
+        Ok(self.name.len() + 1 + self.value.len())
     }
 
     /// Returns the delimiter that will be used for this argument when it's encoded on the wire,
@@ -190,6 +241,11 @@ impl<'data> Argument<'data> {
     }
 }
 
+/// Names of arguments that are meant to appear more than once with different values, per [RFC8907 section 8.2].
+///
+/// [RFC8907 section 8.2]: https://www.rfc-editor.org/rfc/rfc8907.html#name-authorization-arguments
+const REPEATABLE_ARGUMENTS: &[&str] = &["cmd-arg"];
+
 /// A set of arguments known to be of valid length for use in a TACACS+ packet.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Arguments<'args>(&'args [Argument<'args>]);
@@ -206,6 +262,63 @@ impl<'args> Arguments<'args> {
         }
     }
 
+    /// Constructs a new `Arguments` like [`new()`](Self::new), but also rejects sets of arguments whose meaning is ambiguous.
+    ///
+    /// RFC8907 doesn't specify how a name appearing more than once should be handled, and servers differ in whether
+    /// they use the first or last occurrence (or reject the request), so this flags names that appear as both mandatory
+    /// & optional, or with different values. Names that are meant to be repeated (i.e., `cmd-arg`, which holds one
+    /// command argument each) can still have different values, as long as their delimiters match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::{Argument, Arguments, FieldText, InvalidArguments};
+    ///
+    /// let argument = |name, value, mandatory| {
+    ///     Argument::new(FieldText::try_from(name).unwrap(), FieldText::try_from(value).unwrap(), mandatory).unwrap()
+    /// };
+    ///
+    /// let command = [
+    ///     argument("service", "shell", true),
+    ///     argument("cmd", "show", true),
+    ///     argument("cmd-arg", "running-config", true),
+    ///     argument("cmd-arg", "interface", true),
+    /// ];
+    /// assert!(Arguments::new_strict(&command).is_ok());
+    ///
+    /// let ambiguous = [argument("priv-lvl", "1", true), argument("priv-lvl", "15", false)];
+    /// assert_eq!(
+    ///     Arguments::new_strict(&ambiguous),
+    ///     Err(InvalidArguments::MixedMandatory { first: 0, second: 1 })
+    /// );
+    /// ```
+    pub fn new_strict<T: AsRef<[Argument<'args>]>>(
+        arguments: &'args T,
+    ) -> Result<Self, InvalidArguments> {
+        let checked = Self::new(arguments).ok_or(InvalidArguments::TooMany)?;
+
+        // there are at most 255 arguments, so comparing each pair is cheap enough & avoids needing an allocator
+        for (first, earlier) in checked.0.iter().enumerate() {
+            for (offset, later) in checked.0[first + 1..].iter().enumerate() {
+                if later.name != earlier.name {
+                    continue;
+                }
+
+                let second = first + 1 + offset;
+
+                if later.mandatory != earlier.mandatory {
+                    return Err(InvalidArguments::MixedMandatory { first, second });
+                } else if later.value != earlier.value
+                    && !REPEATABLE_ARGUMENTS.contains(&earlier.name.as_ref())
+                {
+                    return Err(InvalidArguments::ConflictingValues { first, second });
+                }
+            }
+        }
+
+        Ok(checked)
+    }
+
     /// Returns the number of arguments an `Arguments` object contains.
     pub fn argument_count(&self) -> u8 {
         // SAFETY: this should not panic as the argument count is verified to fit in a u8 in the constructor
@@ -228,68 +341,28 @@ impl<'args> Arguments<'args> {
     /// Serializes the argument count & lengths of the stored arguments into a buffer.
     pub(super) fn serialize_count_and_lengths(
         &self,
-        buffer: &mut [u8],
+        writer: &mut BodyWriter<'_>,
     ) -> Result<usize, SerializeError> {
         let argument_count = self.argument_count();
+        writer.write_u8(argument_count)?;
 
-        // strict greater than to allow room for encoded argument count itself
-        if buffer.len() > argument_count as usize {
-            buffer[0] = argument_count;
-
-            // fill in argument lengths after argument count
-            for (position, argument) in zip(&mut buffer[1..1 + argument_count as usize], self.0) {
-                *position = argument.encoded_length();
-            }
-
-            // total bytes written: number of arguments + one extra byte for argument count itself
-            Ok(1 + argument_count as usize)
-        } else {
-            Err(SerializeError::NotEnoughSpace)
+        // fill in argument lengths after argument count
+        for argument in self.0 {
+            writer.write_u8(argument.encoded_length())?;
         }
+
+        // total bytes written: number of arguments + one extra byte for argument count itself
+        Ok(1 + argument_count as usize)
     }
 
     /// Serializes the stored arguments in their proper encoding to a buffer.
     pub(super) fn serialize_encoded_values(
         &self,
-        buffer: &mut [u8],
+        writer: &mut BodyWriter<'_>,
     ) -> Result<usize, SerializeError> {
-        let full_encoded_length = self
-            .0
+        self.0
             .iter()
-            .map(|argument| argument.encoded_length() as usize)
-            .sum();
-
-        if buffer.len() >= full_encoded_length {
-            let mut argument_start = 0;
-            let mut total_written = 0;
-
-            for argument in self.0.iter() {
-                let argument_length = argument.encoded_length() as usize;
-                let next_argument_start = argument_start + argument_length;
-                let written_length =
-                    argument.serialize(&mut buffer[argument_start..next_argument_start])?;
-
-                // update loop state
-                argument_start = next_argument_start;
-
-                // this is technically redundant with the initial full_encoded_length calculation above
-                // but better to be safe than sorry right?
-                total_written += written_length;
-            }
-
-            // this case shouldn't happen since argument serialization is basically just direct slice copying
-            // but on the off chance that it does this makes it easier to debug
-            if total_written != full_encoded_length {
-                Err(SerializeError::LengthMismatch {
-                    expected: full_encoded_length,
-                    actual: total_written,
-                })
-            } else {
-                Ok(total_written)
-            }
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+            .try_fold(0, |total, argument| Ok(total + argument.serialize(writer)?))
     }
 }
 
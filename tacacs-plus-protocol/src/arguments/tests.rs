@@ -25,12 +25,12 @@ fn arguments_two_required() {
 
     // ensure header information is serialized correctly
     let header_serialized_len = arguments
-        .serialize_count_and_lengths(&mut buffer)
+        .serialize_count_and_lengths(&mut BodyWriter::new(&mut buffer))
         .expect("buffer should be big enough for argument lengths");
     assert_eq!(buffer[..header_serialized_len], [2, 12, 16]);
 
     let body_serialized_len = arguments
-        .serialize_encoded_values(&mut buffer)
+        .serialize_encoded_values(&mut BodyWriter::new(&mut buffer))
         .expect("buffer should be large enough for argument values");
     assert_eq!(
         &buffer[..body_serialized_len],
@@ -52,12 +52,12 @@ fn arguments_one_optional() {
 
     let mut buffer = [0u8; 30];
     let header_serialized_len = arguments
-        .serialize_count_and_lengths(&mut buffer)
+        .serialize_count_and_lengths(&mut BodyWriter::new(&mut buffer))
         .expect("buffer should be large enough to hold argument lengths");
     assert_eq!(buffer[..header_serialized_len], [1, 24]);
 
     let body_serialized_len = arguments
-        .serialize_encoded_values(&mut buffer)
+        .serialize_encoded_values(&mut BodyWriter::new(&mut buffer))
         .expect("buffer should be large enough for argument values");
     assert_eq!(&buffer[..body_serialized_len], b"optional-arg*unimportant");
 }
@@ -74,7 +74,7 @@ fn construct_and_serialize_valid_optional_argument() {
 
     let mut buffer = [0xffu8; 70];
     argument
-        .serialize(&mut buffer)
+        .serialize(&mut BodyWriter::new(&mut buffer))
         .expect("argument serialization should succeed");
 
     assert_eq!(
@@ -157,3 +157,95 @@ fn deserialize_both_delims_star_first() {
         })
     );
 }
+
+#[test]
+fn strict_arguments_accept_repeated_command_arguments() {
+    let argument_array = [
+        Argument::new(FieldText::assert("cmd"), FieldText::assert("show"), true).unwrap(),
+        Argument::new(FieldText::assert("cmd-arg"), FieldText::assert("ip"), true).unwrap(),
+        Argument::new(
+            FieldText::assert("cmd-arg"),
+            FieldText::assert("route"),
+            true,
+        )
+        .unwrap(),
+        // exact duplicates aren't ambiguous either
+        Argument::new(FieldText::assert("cmd"), FieldText::assert("show"), true).unwrap(),
+    ];
+
+    assert!(Arguments::new_strict(&argument_array).is_ok());
+}
+
+#[test]
+fn strict_arguments_reject_conflicting_values() {
+    let argument_array = [
+        Argument::new(
+            FieldText::assert("service"),
+            FieldText::assert("shell"),
+            true,
+        )
+        .unwrap(),
+        Argument::new(FieldText::assert("priv-lvl"), FieldText::assert("1"), false).unwrap(),
+        Argument::new(
+            FieldText::assert("priv-lvl"),
+            FieldText::assert("15"),
+            false,
+        )
+        .unwrap(),
+    ];
+
+    assert_eq!(
+        Arguments::new_strict(&argument_array),
+        Err(InvalidArguments::ConflictingValues {
+            first: 1,
+            second: 2
+        })
+    );
+
+    // the non-strict constructor doesn't care
+    assert!(Arguments::new(&argument_array).is_some());
+}
+
+#[test]
+fn strict_arguments_reject_mixed_delimiters() {
+    let argument_array = [
+        Argument::new(FieldText::assert("cmd-arg"), FieldText::assert("ip"), true).unwrap(),
+        Argument::new(FieldText::assert("cmd-arg"), FieldText::assert("ip"), false).unwrap(),
+    ];
+
+    assert_eq!(
+        Arguments::new_strict(&argument_array),
+        Err(InvalidArguments::MixedMandatory {
+            first: 0,
+            second: 1
+        })
+    );
+}
+
+#[test]
+fn unspecified_argument_round_trip() {
+    let argument = Argument::unspecified(FieldText::assert("timeout")).unwrap();
+    assert!(argument.is_unspecified());
+
+    let mut buffer = [0u8; 8];
+    let length = argument
+        .serialize(&mut BodyWriter::new(&mut buffer))
+        .unwrap();
+    assert_eq!(&buffer[..length], b"timeout*");
+
+    let deserialized = Argument::deserialize(&buffer[..length]).unwrap();
+    assert!(deserialized.is_unspecified());
+    assert_eq!(deserialized, argument);
+}
+
+#[test]
+fn empty_mandatory_argument_is_specified() {
+    let argument =
+        Argument::new(FieldText::assert("timeout"), FieldText::assert(""), true).unwrap();
+    assert!(!argument.is_unspecified());
+
+    // optional arguments with values aren't unspecified either
+    let argument =
+        Argument::new(FieldText::assert("timeout"), FieldText::assert("5"), false).unwrap();
+    assert!(!argument.is_unspecified());
+}
@@ -3,14 +3,15 @@
 use core::fmt;
 
 use bitflags::bitflags;
-use byteorder::{ByteOrder, NetworkEndian};
 use getset::{CopyGetters, Getters};
-use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use super::{
-    AuthenticationContext, AuthenticationType, DeserializeError, MinorVersion, PacketBody,
-    PacketType, Serialize, SerializeError, UserInformation,
+    AuthenticationContext, AuthenticationService, AuthenticationType, DeserializeError,
+    KnownLength, MinorVersion, PacketBody, PacketType, Serialize, SerializeError, UserInformation,
 };
+use crate::body::{BodyReader, BodyWriter};
+use crate::util::{U16_LENGTH_WIRE_SIZE, U8_LENGTH_WIRE_SIZE};
 use crate::{Deserialize, FieldText};
 
 #[cfg(test)]
@@ -20,8 +21,15 @@ mod tests;
 mod owned;
 
 mod data;
+use data::Redacted;
 pub use data::{DataTooLong, PacketData};
 
+mod prompt;
+pub use prompt::PromptKind;
+
+pub mod chap;
+pub mod pap;
+
 #[cfg(feature = "std")]
 pub use owned::ReplyOwned;
 
@@ -45,12 +53,47 @@ pub enum Action {
 
 impl Action {
     /// The number of bytes an `Action` occupies on the wire.
-    const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
+}
+
+/// An authentication action that isn't discouraged by RFC8907, for code that should never start outbound
+/// authentication via [`Action::SendAuth`].
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub enum StrictAction {
+    /// Login request.
+    Login = 0x01,
+
+    /// Password change request.
+    ChangePassword = 0x02,
+}
+
+impl From<StrictAction> for Action {
+    fn from(action: StrictAction) -> Self {
+        match action {
+            StrictAction::Login => Self::Login,
+            StrictAction::ChangePassword => Self::ChangePassword,
+        }
+    }
+}
+
+/// Fails with the original action if it's [`Action::SendAuth`].
+impl TryFrom<Action> for StrictAction {
+    type Error = Action;
+
+    fn try_from(action: Action) -> Result<Self, Self::Error> {
+        match action {
+            Action::Login => Ok(Self::Login),
+            Action::ChangePassword => Ok(Self::ChangePassword),
+            Action::SendAuth => Err(action),
+        }
+    }
 }
 
 /// The authentication status, as returned by a TACACS+ server.
+#[non_exhaustive]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, TryFromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, IntoPrimitive)]
 pub enum Status {
     /// Authentication succeeded.
     Pass = 0x01,
@@ -76,22 +119,105 @@ pub enum Status {
     /// Forward authentication request to an alternative daemon.
     #[deprecated = "Forwarding to an alternative daemon was deprecated in RFC-8907."]
     Follow = 0x21,
+
+    /// A status without a meaning defined by RFC8907, e.g. one from a protocol extension.
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl Status {
     /// Number of bytes an authentication reply status occupies on the wire.
-    const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
-#[doc(hidden)]
-impl From<TryFromPrimitiveError<Status>> for DeserializeError {
-    fn from(value: TryFromPrimitiveError<Status>) -> Self {
-        Self::InvalidStatus(value.number)
+/// Formats a description of the status, or its name from RFC8907 (e.g., `TAC_PLUS_AUTHEN_STATUS_PASS`) with the
+/// alternate flag (`{:#}`).
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (description, rfc_name) = match self {
+            Self::Pass => ("pass", "TAC_PLUS_AUTHEN_STATUS_PASS"),
+            Self::Fail => ("fail", "TAC_PLUS_AUTHEN_STATUS_FAIL"),
+            Self::GetData => ("get data", "TAC_PLUS_AUTHEN_STATUS_GETDATA"),
+            Self::GetUser => ("get user", "TAC_PLUS_AUTHEN_STATUS_GETUSER"),
+            Self::GetPassword => ("get password", "TAC_PLUS_AUTHEN_STATUS_GETPASS"),
+            Self::Restart => ("restart", "TAC_PLUS_AUTHEN_STATUS_RESTART"),
+            Self::Error => ("server-side error", "TAC_PLUS_AUTHEN_STATUS_ERROR"),
+            #[allow(deprecated)]
+            Self::Follow => (
+                "redirect to alternative daemon",
+                "TAC_PLUS_AUTHEN_STATUS_FOLLOW",
+            ),
+            Self::Unknown(status) => return write!(f, "unknown ({status:#04x})"),
+        };
+
+        f.write_str(if f.alternate() { rfc_name } else { description })
+    }
+}
+
+/// An authentication status that's defined & not deprecated by RFC8907, for code that shouldn't have to handle
+/// [`Status::Follow`] or statuses from protocol extensions.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrictStatus {
+    /// Authentication succeeded.
+    Pass = 0x01,
+
+    /// Authentication failed.
+    Fail = 0x02,
+
+    /// Request for more domain-specific data.
+    GetData = 0x03,
+
+    /// Request for username.
+    GetUser = 0x04,
+
+    /// Request for password.
+    GetPassword = 0x05,
+
+    /// Restart session, discarding current one.
+    Restart = 0x06,
+
+    /// Server-side error while authenticating.
+    Error = 0x07,
+}
+
+impl From<StrictStatus> for Status {
+    fn from(status: StrictStatus) -> Self {
+        match status {
+            StrictStatus::Pass => Self::Pass,
+            StrictStatus::Fail => Self::Fail,
+            StrictStatus::GetData => Self::GetData,
+            StrictStatus::GetUser => Self::GetUser,
+            StrictStatus::GetPassword => Self::GetPassword,
+            StrictStatus::Restart => Self::Restart,
+            StrictStatus::Error => Self::Error,
+        }
+    }
+}
+
+/// Fails with [`DeserializeError::InvalidStatus`] for deprecated & unknown statuses.
+impl TryFrom<Status> for StrictStatus {
+    type Error = DeserializeError;
+
+    fn try_from(status: Status) -> Result<Self, DeserializeError> {
+        match status {
+            Status::Pass => Ok(Self::Pass),
+            Status::Fail => Ok(Self::Fail),
+            Status::GetData => Ok(Self::GetData),
+            Status::GetUser => Ok(Self::GetUser),
+            Status::GetPassword => Ok(Self::GetPassword),
+            Status::Restart => Ok(Self::Restart),
+            Status::Error => Ok(Self::Error),
+            other => Err(DeserializeError::InvalidStatus(other.into())),
+        }
     }
 }
 
 /// An authentication start packet, used to initiate an authentication session.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// The [`Debug`](fmt::Debug) output of a start packet only includes the length of its data, which usually holds
+/// credentials.
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Start<'packet> {
     action: Action,
     authentication: AuthenticationContext,
@@ -112,6 +238,9 @@ pub enum BadStart {
     ///
     /// [Table 1]: https://www.rfc-editor.org/rfc/rfc8907.html#name-tacacs-protocol-versioning
     IncompatibleActionAndType,
+
+    /// Action & authentication service were incompatible, as checked by [`Start::new_strict()`].
+    IncompatibleActionAndService,
 }
 
 impl fmt::Display for BadStart {
@@ -124,11 +253,29 @@ impl fmt::Display for BadStart {
             Self::IncompatibleActionAndType => {
                 write!(f, "authentication action & type are incompatible")
             }
+            Self::IncompatibleActionAndService => {
+                write!(f, "authentication action & service are incompatible")
+            }
         }
     }
 }
 
 impl<'packet> Start<'packet> {
+    /// Offset of the action within a start packet body.
+    pub const ACTION_OFFSET: usize = 0;
+
+    /// Offset of the authentication context (privilege level, type & service) within a start packet body.
+    pub const AUTHENTICATION_CONTEXT_OFFSET: usize = 1;
+
+    /// Offset of the user information field lengths within a start packet body.
+    pub const USER_INFORMATION_LENGTHS_OFFSET: usize = 4;
+
+    /// Offset of the data length within a start packet body.
+    pub const DATA_LENGTH_OFFSET: usize = 7;
+
+    /// Offset of the user information values within a start packet body, which directly follow the required fields.
+    pub const USER_INFORMATION_OFFSET: usize = 8;
+
     /// Initializes a new start packet with the provided fields and an empty data field.
     pub fn new(
         action: Action,
@@ -151,6 +298,66 @@ impl<'packet> Start<'packet> {
         }
     }
 
+    /// Returns the exact number of bytes this start body occupies when serialized, excluding the packet header.
+    pub fn encoded_size(&self) -> usize {
+        Serialize::wire_size(self)
+    }
+
+    /// Initializes a new start packet like [`new()`](Self::new), but additionally checks that the authentication service
+    /// makes sense for the provided action.
+    ///
+    /// RFC8907 doesn't explicitly forbid any combinations of service & action, but some are nonsensical; for example,
+    /// the [`Enable`](AuthenticationService::Enable) service is only described for [`Login`](Action::Login) requests in
+    /// [section 5.4.2.6], and [`None`](AuthenticationService::None) is only meant for authorization ([section 6.1]).
+    ///
+    /// [section 5.4.2.6]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.6
+    /// [section 6.1]: https://www.rfc-editor.org/rfc/rfc8907.html#section-6.1-13.12.1
+    pub fn new_strict(
+        action: Action,
+        authentication: AuthenticationContext,
+        user_information: UserInformation<'packet>,
+        data: Option<PacketData<'packet>>,
+    ) -> Result<Self, BadStart> {
+        if Self::action_and_service_compatible(authentication.service, action) {
+            Self::new(action, authentication, user_information, data)
+        } else {
+            Err(BadStart::IncompatibleActionAndService)
+        }
+    }
+
+    /// The actions that are sensible for each authentication service, used for validation in strict mode.
+    ///
+    /// Derived from [section 5.1] & [section 5.4] of RFC8907. Outbound authentication ([`SendAuth`](Action::SendAuth))
+    /// is only meaningful for PPP, since that's where a client authenticates to a remote peer.
+    ///
+    /// [section 5.1]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.1
+    /// [section 5.4]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4
+    const SERVICE_ACTIONS: &'static [(AuthenticationService, &'static [Action])] = &[
+        (AuthenticationService::None, &[]),
+        (
+            AuthenticationService::Login,
+            &[Action::Login, Action::ChangePassword],
+        ),
+        (AuthenticationService::Enable, &[Action::Login]),
+        (
+            AuthenticationService::Ppp,
+            &[Action::Login, Action::SendAuth],
+        ),
+        (AuthenticationService::Pt, &[Action::Login]),
+        (AuthenticationService::RCommand, &[Action::Login]),
+        (AuthenticationService::X25, &[Action::Login]),
+        (AuthenticationService::Nasi, &[Action::Login]),
+        (AuthenticationService::FwProxy, &[Action::Login]),
+    ];
+
+    /// Predicate for whether an authentication service & action are compatible, according to [`SERVICE_ACTIONS`](Self::SERVICE_ACTIONS).
+    fn action_and_service_compatible(service: AuthenticationService, action: Action) -> bool {
+        Self::SERVICE_ACTIONS
+            .iter()
+            .find(|(table_service, _)| *table_service == service)
+            .is_some_and(|(_, actions)| actions.contains(&action))
+    }
+
     /// Predicate for whether authentication type & authentication are compatible.
     ///
     /// NOTE: `NotSet` should not be passed to this function, as it is not allowed in authentication packets.
@@ -178,6 +385,17 @@ impl<'packet> Start<'packet> {
     }
 }
 
+impl fmt::Debug for Start<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Start")
+            .field("action", &self.action)
+            .field("authentication", &self.authentication)
+            .field("user_information", &self.user_information)
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
 impl PacketBody for Start<'_> {
     const TYPE: PacketType = PacketType::Authentication;
 
@@ -196,6 +414,21 @@ impl PacketBody for Start<'_> {
     }
 }
 
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(Start::AUTHENTICATION_CONTEXT_OFFSET == Start::ACTION_OFFSET + Action::WIRE_SIZE);
+    assert!(
+        Start::USER_INFORMATION_LENGTHS_OFFSET
+            == Start::AUTHENTICATION_CONTEXT_OFFSET + AuthenticationContext::WIRE_SIZE
+    );
+    assert!(
+        Start::DATA_LENGTH_OFFSET
+            == Start::USER_INFORMATION_LENGTHS_OFFSET + UserInformation::HEADER_INFORMATION_SIZE
+    );
+    assert!(Start::USER_INFORMATION_OFFSET == Start::DATA_LENGTH_OFFSET + U8_LENGTH_WIRE_SIZE);
+    assert!(Start::REQUIRED_FIELDS_LENGTH == Start::USER_INFORMATION_OFFSET);
+};
+
 impl Serialize for Start<'_> {
     fn wire_size(&self) -> usize {
         Action::WIRE_SIZE
@@ -205,55 +438,23 @@ impl Serialize for Start<'_> {
             + self.data.as_ref().map_or(0, |data| data.as_bytes().len())
     }
 
-    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    fn serialize_into_writer(&self, mut writer: BodyWriter<'_>) -> Result<usize, SerializeError> {
         let wire_size = self.wire_size();
 
-        if buffer.len() >= self.wire_size() {
-            buffer[0] = self.action as u8;
-
-            self.authentication.serialize(&mut buffer[1..4]);
-
-            self.user_information
-                .serialize_field_lengths(&mut buffer[4..7])?;
-
-            // information written before this occupies 8 bytes
-            let mut total_bytes_written = 8;
-
-            // user information values start at index 8
-            // cap slice with wire size to avoid overflows, although that shouldn't happen
-            let user_info_written_len = self
-                .user_information
-                .serialize_field_values(&mut buffer[8..wire_size])?;
-            total_bytes_written += user_info_written_len;
-
-            // data starts after the end of the user information values
-            let data_start = 8 + user_info_written_len;
-            if let Some(data) = self.data.as_ref() {
-                let data_len = data.len();
+        writer.write_u8(self.action as u8)?;
+        self.authentication.serialize(&mut writer)?;
+        self.user_information.serialize_field_lengths(&mut writer)?;
 
-                // length is verified to fit in a u8 in new(), but verify anyways
-                buffer[7] = data.len();
+        // data length is verified to fit in a u8 in PacketData::new()
+        writer.write_u8(self.data.as_ref().map_or(0, PacketData::len))?;
+        debug_assert_eq!(writer.position(), Self::REQUIRED_FIELDS_LENGTH);
 
-                // copy over packet data
-                buffer[data_start..data_start + data_len as usize].copy_from_slice(data.as_bytes());
-
-                total_bytes_written += data_len as usize;
-            } else {
-                // set data_len field to 0; no data has to be copied to the data section of the packet
-                buffer[7] = 0;
-            }
-
-            if total_bytes_written == wire_size {
-                Ok(total_bytes_written)
-            } else {
-                Err(SerializeError::LengthMismatch {
-                    expected: wire_size,
-                    actual: total_bytes_written,
-                })
-            }
-        } else {
-            Err(SerializeError::NotEnoughSpace)
+        self.user_information.serialize_field_values(&mut writer)?;
+        if let Some(data) = self.data.as_ref() {
+            writer.write_bytes(data.as_bytes())?;
         }
+
+        writer.finish(wire_size)
     }
 }
 
@@ -269,11 +470,25 @@ bitflags! {
 
 impl ReplyFlags {
     /// Number of bytes reply flags occupy on the wire.
-    const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
 crate::util::bitflags_display_impl!(ReplyFlags);
 
+/// How flag bits without a known meaning are treated when deserializing an authentication reply.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnknownFlags {
+    /// Replies with unknown flag bits are rejected with [`DeserializeError::InvalidBodyFlags`], as RFC8907 doesn't define any.
+    #[default]
+    Reject,
+
+    /// Unknown flag bits are left out of the parsed [`ReplyFlags`], e.g. to tolerate flags defined by future extensions.
+    ///
+    /// The flags byte as received is still available via [`Reply::raw_flags()`].
+    Ignore,
+}
+
 /// An authentication reply packet received from a server.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Getters, CopyGetters)]
 pub struct Reply<'packet> {
@@ -292,6 +507,10 @@ pub struct Reply<'packet> {
     /// Gets the flags returned from the server as part of this authentication exchange.
     #[getset(get = "pub")]
     flags: ReplyFlags,
+
+    /// Returns the flags byte exactly as received, including any bits that were ignored during deserialization.
+    #[getset(get_copy = "pub")]
+    raw_flags: u8,
 }
 
 struct ReplyFieldLengths {
@@ -301,49 +520,50 @@ struct ReplyFieldLengths {
 }
 
 impl Reply<'_> {
+    /// Offset of the status within a reply packet body.
+    pub const STATUS_OFFSET: usize = 0;
+
+    /// Offset of the flags within a reply packet body.
+    pub const FLAGS_OFFSET: usize = 1;
+
+    /// Offset of the server message length within a reply packet body.
+    pub const SERVER_MESSAGE_LENGTH_OFFSET: usize = 2;
+
+    /// Offset of the data length within a reply packet body.
+    pub const DATA_LENGTH_OFFSET: usize = 4;
+
     /// Server message offset within packet body as a zero-based index.
-    const SERVER_MESSAGE_OFFSET: usize = 6;
+    pub const SERVER_MESSAGE_OFFSET: usize = 6;
 
     /// Attempts to extract the claimed reply packed body length from a buffer.
+    ///
+    /// This is the same as [`KnownLength::extract_total_length()`], for use without importing the trait.
     pub fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
-        Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
-    }
-
-    /// Extracts the server message and data field lengths from a buffer, treating it as if it were a serialized reply packet body.
-    fn extract_field_lengths(buffer: &[u8]) -> Result<ReplyFieldLengths, DeserializeError> {
-        // data length is the last required field
-        if buffer.len() >= Self::REQUIRED_FIELDS_LENGTH {
-            let server_message_length = NetworkEndian::read_u16(&buffer[2..4]);
-            let data_length = NetworkEndian::read_u16(&buffer[4..6]);
-
-            // total length is just the sum of field lengths & the encoded lengths themselves
-            // SAFETY: REQUIRED_FIELDS_LENGTH as defined is guaranteed to fit in a u32
-            let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
-                + u32::from(server_message_length)
-                + u32::from(data_length);
-
-            Ok(ReplyFieldLengths {
-                server_message_length,
-                data_length,
-                total_length,
-            })
-        } else {
-            Err(DeserializeError::UnexpectedEnd)
-        }
+        <Self as KnownLength>::extract_total_length(buffer)
     }
-}
-
-impl PacketBody for Reply<'_> {
-    const TYPE: PacketType = PacketType::Authentication;
-
-    // extra 2 bytes each for lengths of server message & data
-    const REQUIRED_FIELDS_LENGTH: usize = Status::WIRE_SIZE + ReplyFlags::WIRE_SIZE + 4;
-}
 
-// Hide from docs, as this is meant for internal use only
-#[doc(hidden)]
-impl<'raw> Deserialize<'raw> for Reply<'raw> {
-    fn deserialize_from_buffer(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
+    /// Deserializes a reply body, treating any unknown bits in its flags as specified.
+    ///
+    /// Deserializing a reply any other way (e.g., as part of a [`Packet`](crate::Packet)) uses [`UnknownFlags::Reject`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::authentication::{Reply, ReplyFlags, UnknownFlags};
+    ///
+    /// // GETPASS status, NO_ECHO flag along with an unknown flag, & empty server message/data
+    /// let body = [0x05, 0b10000001, 0, 0, 0, 0];
+    ///
+    /// assert!(Reply::deserialize_with(&body, UnknownFlags::Reject).is_err());
+    ///
+    /// let reply = Reply::deserialize_with(&body, UnknownFlags::Ignore).unwrap();
+    /// assert_eq!(*reply.flags(), ReplyFlags::NO_ECHO);
+    /// assert_eq!(reply.raw_flags(), 0b10000001);
+    /// ```
+    pub fn deserialize_with(
+        buffer: &[u8],
+        unknown_flags: UnknownFlags,
+    ) -> Result<Reply<'_>, DeserializeError> {
         let field_lengths = Self::extract_field_lengths(buffer)?;
 
         // buffer is sliced to length reported in packet header in Packet::deserialize_body(), so we can compare against
@@ -352,24 +572,28 @@ impl<'raw> Deserialize<'raw> for Reply<'raw> {
 
         // ensure buffer is large enough to contain entire packet
         if field_lengths.total_length as usize == length_from_header {
-            let status = Status::try_from(buffer[0])?;
-            let flag_byte = buffer[1];
-            let flags = ReplyFlags::from_bits(flag_byte)
-                .ok_or(DeserializeError::InvalidBodyFlags(flag_byte))?;
+            let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
+
+            let status = Status::from(reader.read_u8()?);
+            let raw_flags = reader.read_u8()?;
+            let flags = match unknown_flags {
+                UnknownFlags::Reject => ReplyFlags::from_bits(raw_flags)
+                    .ok_or(DeserializeError::InvalidBodyFlags(raw_flags))?,
+                UnknownFlags::Ignore => ReplyFlags::from_bits_truncate(raw_flags),
+            };
 
-            let data_begin =
-                Self::SERVER_MESSAGE_OFFSET + field_lengths.server_message_length as usize;
+            // field lengths were already extracted above
+            reader.skip(Self::SERVER_MESSAGE_OFFSET - Self::SERVER_MESSAGE_LENGTH_OFFSET)?;
 
-            let server_message =
-                FieldText::try_from(&buffer[Self::SERVER_MESSAGE_OFFSET..data_begin])
-                    .map_err(|_| DeserializeError::BadText)?;
-            let data = &buffer[data_begin..data_begin + field_lengths.data_length as usize];
+            let server_message = reader.read_text(field_lengths.server_message_length as usize)?;
+            let data = reader.read_bytes(field_lengths.data_length as usize)?;
 
             Ok(Reply {
                 status,
                 server_message,
                 data,
                 flags,
+                raw_flags,
             })
         } else {
             Err(DeserializeError::WrongBodyBufferSize {
@@ -378,6 +602,61 @@ impl<'raw> Deserialize<'raw> for Reply<'raw> {
             })
         }
     }
+
+    /// Extracts the server message and data field lengths from a buffer, treating it as if it were a serialized reply packet body.
+    fn extract_field_lengths(buffer: &[u8]) -> Result<ReplyFieldLengths, DeserializeError> {
+        // data length is the last required field
+        let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
+
+        // status & flags are validated later, once the body is known to be complete
+        reader.skip(Self::SERVER_MESSAGE_LENGTH_OFFSET)?;
+        let server_message_length = reader.read_u16()?;
+        let data_length = reader.read_u16()?;
+
+        // total length is just the sum of field lengths & the encoded lengths themselves
+        // SAFETY: REQUIRED_FIELDS_LENGTH as defined is guaranteed to fit in a u32
+        let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
+            + u32::from(server_message_length)
+            + u32::from(data_length);
+
+        Ok(ReplyFieldLengths {
+            server_message_length,
+            data_length,
+            total_length,
+        })
+    }
+}
+
+impl PacketBody for Reply<'_> {
+    const TYPE: PacketType = PacketType::Authentication;
+
+    // extra 2 bytes each for lengths of server message & data
+    const REQUIRED_FIELDS_LENGTH: usize = Status::WIRE_SIZE + ReplyFlags::WIRE_SIZE + 4;
+}
+
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(Reply::FLAGS_OFFSET == Reply::STATUS_OFFSET + Status::WIRE_SIZE);
+    assert!(Reply::SERVER_MESSAGE_LENGTH_OFFSET == Reply::FLAGS_OFFSET + ReplyFlags::WIRE_SIZE);
+    assert!(
+        Reply::DATA_LENGTH_OFFSET == Reply::SERVER_MESSAGE_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE
+    );
+    assert!(Reply::SERVER_MESSAGE_OFFSET == Reply::DATA_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE);
+    assert!(Reply::REQUIRED_FIELDS_LENGTH == Reply::SERVER_MESSAGE_OFFSET);
+};
+
+impl KnownLength for Reply<'_> {
+    fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
+        Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
+    }
+}
+
+// Hide from docs, as this is meant for internal use only
+#[doc(hidden)]
+impl<'raw> Deserialize<'raw> for Reply<'raw> {
+    fn deserialize_from_buffer(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
+        Self::deserialize_with(buffer, UnknownFlags::Reject)
+    }
 }
 
 bitflags! {
@@ -390,10 +669,18 @@ bitflags! {
     }
 }
 
+impl ContinueFlags {
+    /// Number of bytes continue flags occupy on the wire.
+    pub const WIRE_SIZE: usize = 1;
+}
+
 crate::util::bitflags_display_impl!(ContinueFlags);
 
 /// A continue packet potentially sent as part of an authentication session.
-#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+///
+/// The [`Debug`](fmt::Debug) output of a continue packet only includes the lengths of the user message & data, since
+/// they usually hold a password or other user input.
+#[derive(PartialEq, Eq, Clone, Hash)]
 pub struct Continue<'packet> {
     user_message: Option<&'packet [u8]>,
     data: Option<&'packet [u8]>,
@@ -401,8 +688,17 @@ pub struct Continue<'packet> {
 }
 
 impl<'packet> Continue<'packet> {
+    /// Offset of the user message length within a continue packet body.
+    pub const USER_MESSAGE_LENGTH_OFFSET: usize = 0;
+
+    /// Offset of the data length within a continue packet body.
+    pub const DATA_LENGTH_OFFSET: usize = 2;
+
+    /// Offset of the flags within a continue packet body.
+    pub const FLAGS_OFFSET: usize = 4;
+
     /// Offset of the user message within a continue packet body, if present.
-    const USER_MESSAGE_OFFSET: usize = 5;
+    pub const USER_MESSAGE_OFFSET: usize = 5;
 
     /// Constructs a continue packet, performing length checks on the user message and data fields to ensure encodable lengths.
     pub fn new(
@@ -422,15 +718,40 @@ impl<'packet> Continue<'packet> {
             None
         }
     }
+
+    /// Returns the exact number of bytes this continue body occupies when serialized, excluding the packet header.
+    pub fn encoded_size(&self) -> usize {
+        Serialize::wire_size(self)
+    }
+}
+
+impl fmt::Debug for Continue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Continue")
+            .field("user_message", &self.user_message.map(Redacted))
+            .field("data", &self.data.map(Redacted))
+            .field("flags", &self.flags)
+            .finish()
+    }
 }
 
 impl PacketBody for Continue<'_> {
     const TYPE: PacketType = PacketType::Authentication;
 
     // 2 bytes each for user message & data length; 1 byte for flags
-    const REQUIRED_FIELDS_LENGTH: usize = 5;
+    const REQUIRED_FIELDS_LENGTH: usize = 2 * U16_LENGTH_WIRE_SIZE + ContinueFlags::WIRE_SIZE;
 }
 
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(
+        Continue::DATA_LENGTH_OFFSET == Continue::USER_MESSAGE_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE
+    );
+    assert!(Continue::FLAGS_OFFSET == Continue::DATA_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE);
+    assert!(Continue::USER_MESSAGE_OFFSET == Continue::FLAGS_OFFSET + ContinueFlags::WIRE_SIZE);
+    assert!(Continue::REQUIRED_FIELDS_LENGTH == Continue::USER_MESSAGE_OFFSET);
+};
+
 impl Serialize for Continue<'_> {
     fn wire_size(&self) -> usize {
         Self::REQUIRED_FIELDS_LENGTH
@@ -438,46 +759,21 @@ impl Serialize for Continue<'_> {
             + self.data.map_or(0, <[u8]>::len)
     }
 
-    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    fn serialize_into_writer(&self, mut writer: BodyWriter<'_>) -> Result<usize, SerializeError> {
         let wire_size = self.wire_size();
 
-        if buffer.len() >= wire_size {
-            // write field lengths into beginning of body
-            let user_message_len = self.user_message.map_or(0, <[u8]>::len).try_into()?;
-            NetworkEndian::write_u16(&mut buffer[..2], user_message_len);
+        let user_message = self.user_message.unwrap_or_default();
+        let data = self.data.unwrap_or_default();
 
-            let data_len = self.data.map_or(0, <[u8]>::len).try_into()?;
-            NetworkEndian::write_u16(&mut buffer[2..4], data_len);
+        // lengths are verified to fit in a u16 in new(), but check again anyways
+        writer.write_u16(user_message.len().try_into()?)?;
+        writer.write_u16(data.len().try_into()?)?;
+        writer.write_u8(self.flags.bits())?;
+        debug_assert_eq!(writer.position(), Self::REQUIRED_FIELDS_LENGTH);
 
-            let data_offset = Self::USER_MESSAGE_OFFSET + user_message_len as usize;
-
-            // set abort flag if needed
-            buffer[4] = self.flags.bits();
-
-            // copy user message into buffer, if present
-            if let Some(message) = self.user_message {
-                buffer[Self::USER_MESSAGE_OFFSET..data_offset].copy_from_slice(message);
-            }
+        writer.write_bytes(user_message)?;
+        writer.write_bytes(data)?;
 
-            // copy data into buffer, again if present
-            if let Some(data) = self.data {
-                buffer[data_offset..data_offset + data_len as usize].copy_from_slice(data);
-            }
-
-            // total number of bytes written includes required "header" fields & two variable length fields
-            let actual_written_len =
-                Self::REQUIRED_FIELDS_LENGTH + user_message_len as usize + data_len as usize;
-
-            if actual_written_len == wire_size {
-                Ok(actual_written_len)
-            } else {
-                Err(SerializeError::LengthMismatch {
-                    expected: wire_size,
-                    actual: actual_written_len,
-                })
-            }
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        writer.finish(wire_size)
     }
 }
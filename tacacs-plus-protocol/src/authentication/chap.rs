@@ -0,0 +1,150 @@
+//! Encoding of the data field of CHAP authentication start packets, as described in [RFC8907 section 5.4.2.3].
+//!
+//! [RFC8907 section 5.4.2.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3
+
+use md5::{Digest, Md5};
+
+use crate::{DeserializeError, SerializeError};
+
+#[cfg(test)]
+mod tests;
+
+/// The length of a CHAP response, which is always an MD5 hash.
+pub const RESPONSE_LENGTH: usize = 16;
+
+/// Computes the response to a CHAP challenge from the PPP ID & secret (i.e., the user's password).
+pub fn compute_response(ppp_id: u8, secret: &[u8], challenge: &[u8]) -> [u8; RESPONSE_LENGTH] {
+    // "The Response Value is the one-way hash calculated over a stream of octets consisting of the Identifier,
+    // followed by (concatenated with) the "secret", followed by (concatenated with) the Challenge Value."
+    // RFC1334 section 3.2.1 ("Value" subheading): https://www.rfc-editor.org/rfc/rfc1334.html#section-3.2.1
+    //
+    // "The MD5 algorithm option is always used." (RFC8907 section 5.4.2.3)
+    // https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3-4
+    let mut hasher = Md5::new();
+    hasher.update([ppp_id]);
+    hasher.update(secret);
+    hasher.update(challenge);
+
+    hasher.finalize().into()
+}
+
+/// Returns the length of the login data for a challenge of the provided length.
+pub const fn login_data_length(challenge_length: usize) -> usize {
+    1 + challenge_length + RESPONSE_LENGTH
+}
+
+/// Encodes the data field of a CHAP start packet into a buffer, returning the number of bytes written.
+///
+/// The data field is the PPP ID, followed by the challenge & the [response](compute_response) to it.
+/// [`SerializeError::LengthOverflow`] is returned if the encoded data wouldn't fit in the data field of a start packet,
+/// and [`SerializeError::NotEnoughSpace`] if the buffer is too small to hold it.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::authentication::chap;
+///
+/// let challenge = [0x42; 16];
+/// let mut buffer = [0; chap::login_data_length(16)];
+///
+/// let length = chap::encode_login_data(1, &challenge, b"password", &mut buffer).unwrap();
+/// assert_eq!(length, buffer.len());
+///
+/// assert_eq!(buffer[0], 1);
+/// assert_eq!(buffer[1..17], challenge);
+/// assert_eq!(buffer[17..], chap::compute_response(1, b"password", &challenge));
+/// ```
+pub fn encode_login_data(
+    ppp_id: u8,
+    challenge: &[u8],
+    secret: &[u8],
+    buffer: &mut [u8],
+) -> Result<usize, SerializeError> {
+    let length = login_data_length(challenge.len());
+
+    // the data field length is encoded as a single byte
+    if u8::try_from(length).is_err() {
+        return Err(SerializeError::LengthOverflow);
+    }
+
+    let buffer = buffer
+        .get_mut(..length)
+        .ok_or(SerializeError::NotEnoughSpace)?;
+
+    // "the data field is a concatenation of the PPP id, the challenge, and the response"
+    // RFC8907 section 5.4.2.3: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3-2
+    let (ppp_id_byte, rest) = buffer.split_at_mut(1);
+    let (challenge_bytes, response_bytes) = rest.split_at_mut(challenge.len());
+
+    ppp_id_byte[0] = ppp_id;
+    challenge_bytes.copy_from_slice(challenge);
+    response_bytes.copy_from_slice(&compute_response(ppp_id, secret, challenge));
+
+    Ok(length)
+}
+
+/// The fields of the data field of a CHAP start packet, as split by [`decode_login_data()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoginData<'data> {
+    /// The PPP ID the response was computed with.
+    pub ppp_id: u8,
+
+    /// The challenge generated by the client.
+    pub challenge: &'data [u8],
+
+    /// The client's response to the challenge.
+    pub response: &'data [u8; RESPONSE_LENGTH],
+}
+
+/// Splits the data field of a CHAP start packet into its PPP ID, challenge & response.
+///
+/// [`DeserializeError::UnexpectedEnd`] is returned if the data is too short to hold a PPP ID, a non-empty challenge
+/// and a response.
+pub fn decode_login_data(data: &[u8]) -> Result<LoginData<'_>, DeserializeError> {
+    // the challenge must be at least one byte long
+    if data.len() < login_data_length(1) {
+        return Err(DeserializeError::UnexpectedEnd);
+    }
+
+    let (challenge, response) = data[1..].split_at(data.len() - 1 - RESPONSE_LENGTH);
+
+    Ok(LoginData {
+        ppp_id: data[0],
+        challenge,
+        // SAFETY: the response is split off to be exactly RESPONSE_LENGTH bytes long
+        response: response.try_into().unwrap(),
+    })
+}
+
+/// Verifies the response in the data field of a CHAP start packet against a user's secret (i.e., their password),
+/// for use by servers.
+///
+/// The response is compared in constant time, so as to not leak how much of it was correct. Errors are returned
+/// as in [`decode_login_data()`].
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::authentication::chap;
+///
+/// let mut data = [0; chap::login_data_length(16)];
+/// chap::encode_login_data(7, &[0x42; 16], b"password", &mut data).unwrap();
+///
+/// assert_eq!(chap::verify(&data, b"password"), Ok(true));
+/// assert_eq!(chap::verify(&data, b"wrong password"), Ok(false));
+/// ```
+pub fn verify(data: &[u8], secret: &[u8]) -> Result<bool, DeserializeError> {
+    let login_data = decode_login_data(data)?;
+    let expected = compute_response(login_data.ppp_id, secret, login_data.challenge);
+
+    // accumulate differences over the whole response instead of returning at the first mismatch
+    let difference = expected
+        .iter()
+        .zip(login_data.response)
+        .fold(0, |difference, (expected, actual)| {
+            difference | (expected ^ actual)
+        });
+
+    // keep the compiler from short-circuiting the comparison
+    Ok(core::hint::black_box(difference) == 0)
+}
@@ -0,0 +1,75 @@
+use super::{
+    compute_response, decode_login_data, encode_login_data, login_data_length, verify,
+    RESPONSE_LENGTH,
+};
+use crate::{DeserializeError, SerializeError};
+
+#[test]
+fn response_matches_manual_md5() {
+    // MD5(0x01 || "password" || "challenge"), computed independently
+    let expected = [
+        0xc9, 0xd7, 0x4f, 0xd2, 0x7e, 0x93, 0x16, 0xad, 0xca, 0x50, 0xd2, 0x46, 0x17, 0x8a, 0x1f,
+        0x84,
+    ];
+
+    assert_eq!(compute_response(1, b"password", b"challenge"), expected);
+}
+
+#[test]
+fn encode_too_long_challenge() {
+    // challenge is one byte too long for the data length to fit in a u8
+    let challenge = [0; u8::MAX as usize - RESPONSE_LENGTH];
+    let mut buffer = [0; 512];
+
+    assert_eq!(
+        encode_login_data(0, &challenge, b"password", &mut buffer),
+        Err(SerializeError::LengthOverflow)
+    );
+}
+
+#[test]
+fn encode_buffer_too_small() {
+    let challenge = [0; 16];
+    let mut buffer = [0; login_data_length(16) - 1];
+
+    assert_eq!(
+        encode_login_data(0, &challenge, b"password", &mut buffer),
+        Err(SerializeError::NotEnoughSpace)
+    );
+}
+
+#[test]
+fn decode_round_trip() {
+    let challenge = [0x11; 32];
+    let mut buffer = [0; login_data_length(32)];
+    encode_login_data(9, &challenge, b"secret", &mut buffer).unwrap();
+
+    let decoded = decode_login_data(&buffer).unwrap();
+    assert_eq!(decoded.ppp_id, 9);
+    assert_eq!(decoded.challenge, challenge);
+    assert_eq!(
+        *decoded.response,
+        compute_response(9, b"secret", &challenge)
+    );
+}
+
+#[test]
+fn verify_rejects_tampered_data() {
+    let mut buffer = [0; login_data_length(16)];
+    encode_login_data(3, &[0x22; 16], b"secret", &mut buffer).unwrap();
+    assert_eq!(verify(&buffer, b"secret"), Ok(true));
+
+    // a different PPP ID changes the expected response
+    buffer[0] = 4;
+    assert_eq!(verify(&buffer, b"secret"), Ok(false));
+}
+
+#[test]
+fn verify_data_too_short() {
+    // PPP ID & response, but no challenge
+    let data = [0; 1 + RESPONSE_LENGTH];
+    assert_eq!(
+        verify(&data, b"secret"),
+        Err(DeserializeError::UnexpectedEnd)
+    );
+}
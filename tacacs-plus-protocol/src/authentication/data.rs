@@ -1,6 +1,6 @@
 use core::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Ord, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 enum PacketDataInner<'data> {
     Borrowed(&'data [u8]),
 
@@ -10,12 +10,22 @@ enum PacketDataInner<'data> {
 
 impl PartialOrd for PacketDataInner<'_> {
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
-        Some(self.as_ref().cmp(other.as_ref()))
+        Some(self.cmp(other))
+    }
+}
+
+// ordering should be consistent regardless of owned/borrowed status
+impl Ord for PacketDataInner<'_> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_ref().cmp(other.as_ref())
     }
 }
 
 /// Supplementary authentication data included in an authentication start packet.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+///
+/// Since this usually holds credentials (e.g. a PAP password or CHAP response), its [`Debug`](fmt::Debug) output only
+/// includes the length of the data.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PacketData<'data>(PacketDataInner<'data>);
 
 impl PacketData<'_> {
@@ -25,6 +35,23 @@ impl PacketData<'_> {
     }
 }
 
+impl fmt::Debug for PacketData<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PacketData")
+            .field(&Redacted(self.as_bytes()))
+            .finish()
+    }
+}
+
+/// Debug formatting for potentially sensitive bytes that only shows their length.
+pub(super) struct Redacted<'bytes>(pub(super) &'bytes [u8]);
+
+impl fmt::Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes redacted>", self.0.len())
+    }
+}
+
 impl Default for PacketData<'_> {
     fn default() -> Self {
         Self(PacketDataInner::Borrowed(&[]))
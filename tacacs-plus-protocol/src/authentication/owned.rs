@@ -17,6 +17,9 @@ pub struct ReplyOwned {
     /// The flags set in the server response.
     pub flags: ReplyFlags,
 
+    /// The flags byte exactly as received, including any bits left out of `flags`.
+    pub raw_flags: u8,
+
     /// The message to be displayed to the user.
     pub server_message: String,
 
@@ -33,6 +36,7 @@ impl FromBorrowedBody for ReplyOwned {
         ReplyOwned {
             status: borrowed.status,
             flags: borrowed.flags,
+            raw_flags: borrowed.raw_flags,
             server_message: borrowed.server_message.to_string(),
             data: borrowed.data.to_owned(),
         }
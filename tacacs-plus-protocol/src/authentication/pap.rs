@@ -0,0 +1,35 @@
+//! Encoding of the data field of PAP authentication start packets, as described in [RFC8907 section 5.4.2.2].
+//!
+//! [RFC8907 section 5.4.2.2]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.2
+
+use crate::SerializeError;
+
+/// Encodes the data field of a PAP start packet into a buffer, returning the number of bytes written.
+///
+/// The data field is just the password, so this mostly checks that it fits: [`SerializeError::LengthOverflow`] is
+/// returned if the password is too long for the data field of a start packet, and [`SerializeError::NotEnoughSpace`]
+/// if the buffer is too small to hold it.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::authentication::pap;
+///
+/// let mut buffer = [0; 16];
+/// let length = pap::encode_login_data(b"hunter2", &mut buffer).unwrap();
+/// assert_eq!(&buffer[..length], b"hunter2");
+/// ```
+pub fn encode_login_data(password: &[u8], buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    // "The data field contains the password" (RFC8907 section 5.4.2.2)
+    // https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.2-2
+    if u8::try_from(password.len()).is_err() {
+        return Err(SerializeError::LengthOverflow);
+    }
+
+    buffer
+        .get_mut(..password.len())
+        .ok_or(SerializeError::NotEnoughSpace)?
+        .copy_from_slice(password);
+
+    Ok(password.len())
+}
@@ -0,0 +1,136 @@
+use core::fmt;
+
+use super::{Reply, Status};
+
+/// The kind of input a server is prompting for during an authentication session.
+///
+/// This is determined from a reply's status along with well-known phrasings of server messages used by
+/// common TACACS+ daemons (e.g., Cisco, Shrubbery's `tac_plus`), which allows automated clients to respond
+/// to prompts without having to match against the text themselves. Phrases are only matched as whole words,
+/// case-insensitively.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PromptKind {
+    /// A username is being requested.
+    Username,
+
+    /// A (static) password is being requested.
+    Password,
+
+    /// A one-time token or passcode is being requested, e.g. from a hardware/software token.
+    Token,
+
+    /// A domain or realm is being requested.
+    Domain,
+
+    /// The prompt could not be classified.
+    Unknown,
+}
+
+/// Phrases that indicate a prompt for a one-time token/passcode.
+const TOKEN_PHRASES: &[&str] = &["passcode", "token", "otp", "one-time", "one time", "pin"];
+
+/// Phrases that indicate a prompt for a domain/realm.
+const DOMAIN_PHRASES: &[&str] = &["domain", "realm"];
+
+/// Phrases that indicate a prompt for a password.
+const PASSWORD_PHRASES: &[&str] = &["password", "passwd", "secret"];
+
+/// Phrases that indicate a prompt for a username.
+const USERNAME_PHRASES: &[&str] = &["username", "user name", "user", "login"];
+
+impl PromptKind {
+    /// Classifies a server prompt based on the status & server message of an authentication reply.
+    ///
+    /// `None` is returned if the status doesn't request input from the client, i.e. it is not one of
+    /// [`GetUser`](Status::GetUser), [`GetPassword`](Status::GetPassword) or [`GetData`](Status::GetData).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::authentication::{PromptKind, Status};
+    ///
+    /// assert_eq!(PromptKind::classify(Status::GetUser, "Username: "), Some(PromptKind::Username));
+    /// assert_eq!(PromptKind::classify(Status::GetPassword, "Password: "), Some(PromptKind::Password));
+    /// assert_eq!(PromptKind::classify(Status::GetPassword, "Enter PASSCODE: "), Some(PromptKind::Token));
+    /// assert_eq!(PromptKind::classify(Status::GetData, "Domain: "), Some(PromptKind::Domain));
+    /// assert_eq!(PromptKind::classify(Status::Pass, ""), None);
+    /// ```
+    pub fn classify(status: Status, server_message: &str) -> Option<Self> {
+        let matches_any = |phrases: &[&str]| {
+            phrases
+                .iter()
+                .any(|phrase| contains_word_ignore_ascii_case(server_message, phrase))
+        };
+
+        match status {
+            // the status itself is authoritative here, although some servers use GETPASS for token prompts as well
+            Status::GetUser => Some(Self::Username),
+            Status::GetPassword if matches_any(TOKEN_PHRASES) => Some(Self::Token),
+            Status::GetPassword => Some(Self::Password),
+
+            // GETDATA is generic, so we have to rely on the message entirely
+            // NOTE: order matters here, since e.g. "user password" should be treated as a password prompt
+            Status::GetData => Some(if matches_any(TOKEN_PHRASES) {
+                Self::Token
+            } else if matches_any(DOMAIN_PHRASES) {
+                Self::Domain
+            } else if matches_any(PASSWORD_PHRASES) {
+                Self::Password
+            } else if matches_any(USERNAME_PHRASES) {
+                Self::Username
+            } else {
+                Self::Unknown
+            }),
+
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PromptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Username => "username",
+                Self::Password => "password",
+                Self::Token => "token",
+                Self::Domain => "domain",
+                Self::Unknown => "unknown",
+            }
+        )
+    }
+}
+
+impl Reply<'_> {
+    /// Classifies the prompt in this reply, if the server is requesting input.
+    ///
+    /// See [`PromptKind::classify()`] for more information.
+    pub fn prompt_kind(&self) -> Option<PromptKind> {
+        PromptKind::classify(self.status, self.server_message.as_ref())
+    }
+}
+
+/// Case-insensitive (ASCII-only) search for a whole word or phrase, which avoids allocating a lowercased copy of the
+/// haystack.
+///
+/// Matches have to start & end at word boundaries, so e.g. "pin" isn't found in "typing" or "mapping".
+fn contains_word_ignore_ascii_case(haystack: &str, needle: &str) -> bool {
+    let haystack = haystack.as_bytes();
+    let is_boundary = |index: Option<usize>| {
+        index
+            .and_then(|index| haystack.get(index))
+            .map_or(true, |byte| !byte.is_ascii_alphanumeric())
+    };
+
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .any(|(start, window)| {
+            window.eq_ignore_ascii_case(needle.as_bytes())
+                && is_boundary(start.checked_sub(1))
+                && is_boundary(Some(start + needle.len()))
+        })
+}
@@ -1,3 +1,5 @@
+use byteorder::{ByteOrder, NetworkEndian};
+
 use super::*;
 use crate::FieldText;
 use crate::{
@@ -114,6 +116,117 @@ fn start_data_too_long() {
     );
 }
 
+#[test]
+fn strict_start_service_validation() {
+    let user_information = UserInformation::new(
+        "user",
+        FieldText::assert("tty0"),
+        FieldText::assert("127.0.0.1"),
+    )
+    .unwrap();
+
+    let context = |service, authentication_type| AuthenticationContext {
+        privilege_level: PrivilegeLevel::new(1).unwrap(),
+        authentication_type,
+        service,
+    };
+
+    // (service, action, authentication type, whether the combination should be accepted)
+    let cases = [
+        (
+            AuthenticationService::Login,
+            Action::Login,
+            AuthenticationType::Pap,
+            true,
+        ),
+        (
+            AuthenticationService::Login,
+            Action::ChangePassword,
+            AuthenticationType::Ascii,
+            true,
+        ),
+        (
+            AuthenticationService::Enable,
+            Action::Login,
+            AuthenticationType::Ascii,
+            true,
+        ),
+        (
+            AuthenticationService::Enable,
+            Action::ChangePassword,
+            AuthenticationType::Ascii,
+            false,
+        ),
+        (
+            AuthenticationService::Ppp,
+            Action::SendAuth,
+            AuthenticationType::Chap,
+            true,
+        ),
+        (
+            AuthenticationService::Login,
+            Action::SendAuth,
+            AuthenticationType::Chap,
+            false,
+        ),
+        (
+            AuthenticationService::RCommand,
+            Action::Login,
+            AuthenticationType::Ascii,
+            true,
+        ),
+        (
+            AuthenticationService::None,
+            Action::Login,
+            AuthenticationType::Pap,
+            false,
+        ),
+    ];
+
+    for (service, action, authentication_type, valid) in cases {
+        let result = Start::new_strict(
+            action,
+            context(service, authentication_type),
+            user_information.clone(),
+            None,
+        );
+
+        if valid {
+            assert!(
+                result.is_ok(),
+                "{service} service with {action:?} action should be valid"
+            );
+        } else {
+            assert_eq!(
+                result,
+                Err(BadStart::IncompatibleActionAndService),
+                "{service} service with {action:?} action should be invalid"
+            );
+        }
+    }
+}
+
+#[test]
+fn strict_start_still_checks_authentication_type() {
+    let result = Start::new_strict(
+        Action::ChangePassword,
+        AuthenticationContext {
+            privilege_level: PrivilegeLevel::new(1).unwrap(),
+            authentication_type: AuthenticationType::Pap,
+            service: AuthenticationService::Login,
+        },
+        UserInformation::new(
+            "user",
+            FieldText::assert("tty0"),
+            FieldText::assert("127.0.0.1"),
+        )
+        .unwrap(),
+        None,
+    );
+
+    assert_eq!(result, Err(BadStart::IncompatibleActionAndType));
+}
+
 #[test]
 fn serialize_full_start_packet() {
     let session_id = 123457;
@@ -214,7 +327,8 @@ fn deserialize_reply_pass_both_data_fields() {
             status: Status::Pass,
             server_message: FieldText::assert("login successful"),
             data: b"\x12\x77\xfa\xcc",
-            flags: ReplyFlags::empty()
+            flags: ReplyFlags::empty(),
+            raw_flags: 0,
         })
     );
 }
@@ -257,19 +371,19 @@ fn deserialize_reply_shorter_than_header() {
 }
 
 #[test]
-fn deserialize_reply_bad_status() {
+fn deserialize_reply_unknown_status() {
     let packet_data = [
-        42, // invalid status
+        42, // status not defined in RFC8907
         0,  // no flags set
         0, 1, // server message length
         0, 0,    // data length
         b'a', // server message
     ];
 
-    assert_eq!(
-        Reply::deserialize_from_buffer(&packet_data),
-        Err(DeserializeError::InvalidStatus(42))
-    );
+    let reply = Reply::deserialize_from_buffer(&packet_data)
+        .expect("unknown status should still be deserialized");
+    assert_eq!(reply.status, Status::Unknown(42));
+    assert_eq!(u8::from(reply.status), 42);
 }
 
 #[test]
@@ -288,6 +402,28 @@ fn deserialize_reply_bad_flags() {
     );
 }
 
+#[test]
+fn deserialize_reply_ignoring_unknown_flags() {
+    let packet_data = [
+        0x05,       // status: getpass
+        0b00000011, // NO_ECHO & an unknown flag
+        0, 0, // server message length
+        0, 1,    // data length
+        b'*', // data
+    ];
+
+    let reply = Reply::deserialize_with(&packet_data, UnknownFlags::Ignore)
+        .expect("unknown flags should be ignored");
+    assert_eq!(reply.flags, ReplyFlags::NO_ECHO);
+    assert_eq!(reply.raw_flags, 0b00000011);
+    assert_eq!(reply.data, b"*");
+
+    assert_eq!(
+        Reply::deserialize_with(&packet_data, UnknownFlags::Reject),
+        Err(DeserializeError::InvalidBodyFlags(0b00000011))
+    );
+}
+
 #[test]
 fn deserialize_reply_full_packet() {
     let session_id: u32 = 983274929;
@@ -326,6 +462,7 @@ fn deserialize_reply_full_packet() {
         server_message: FieldText::assert("try again"),
         data: &[1, 1, 2, 3, 5, 8, 13],
         flags: ReplyFlags::empty(),
+        raw_flags: 0,
     };
 
     let expected_packet = Packet::new(expected_header, expected_body);
@@ -430,6 +567,43 @@ fn serialize_continue_both_valid_data_fields() {
     );
 }
 
+#[test]
+fn continue_serialization_round_trip() {
+    let fields: [Option<&[u8]>; 3] = [None, Some(b""), Some(b"\x00some-field\xff")];
+
+    for user_message in fields {
+        for data in fields {
+            for flags in [ContinueFlags::empty(), ContinueFlags::ABORT] {
+                let continue_body = Continue::new(user_message, data, flags).unwrap();
+
+                let mut buffer = [0xff; 40];
+                let written = continue_body
+                    .serialize_into_buffer(&mut buffer)
+                    .expect("buffer should be big enough");
+                assert_eq!(written, continue_body.wire_size());
+
+                // a buffer that's just barely too small should be rejected rather than partially written
+                assert_eq!(
+                    continue_body.serialize_into_buffer(&mut [0; 40][..written - 1]),
+                    Err(SerializeError::NotEnoughSpace)
+                );
+
+                // walking the body using only its encoded lengths should yield back the original fields
+                let body = &buffer[..written];
+                let user_message_length = usize::from(u16::from_be_bytes([body[0], body[1]]));
+                let data_length = usize::from(u16::from_be_bytes([body[2], body[3]]));
+                assert_eq!(body[Continue::FLAGS_OFFSET], flags.bits());
+
+                let (user_message_field, rest) =
+                    body[Continue::USER_MESSAGE_OFFSET..].split_at(user_message_length);
+                assert_eq!(user_message_field, user_message.unwrap_or_default());
+                assert_eq!(rest, data.unwrap_or_default());
+                assert_eq!(rest.len(), data_length);
+            }
+        }
+    }
+}
+
 #[test]
 fn serialize_continue_only_data_field() {
     let data = b"textand\x2abinary\x11";
@@ -505,3 +679,185 @@ fn serialize_continue_full_packet() {
 
     assert_eq!(&buffer[..serialized_length], expected.as_slice());
 }
+
+#[test]
+fn classify_prompts_by_status() {
+    assert_eq!(
+        PromptKind::classify(Status::GetUser, "login:"),
+        Some(PromptKind::Username)
+    );
+    assert_eq!(
+        PromptKind::classify(Status::GetPassword, "Password: "),
+        Some(PromptKind::Password)
+    );
+
+    // non-prompt statuses shouldn't be classified
+    assert_eq!(PromptKind::classify(Status::Fail, "Password: "), None);
+    assert_eq!(PromptKind::classify(Status::Error, "Username: "), None);
+}
+
+#[test]
+fn classify_token_prompts() {
+    for message in [
+        "Enter PASSCODE:",
+        "OTP: ",
+        "Token code",
+        "Enter one-time password",
+    ] {
+        assert_eq!(
+            PromptKind::classify(Status::GetPassword, message),
+            Some(PromptKind::Token),
+            "message: {message:?}"
+        );
+    }
+}
+
+#[test]
+fn classify_getdata_prompts() {
+    let cases = [
+        ("Username: ", PromptKind::Username),
+        ("Enter user password", PromptKind::Password),
+        ("Windows Domain:", PromptKind::Domain),
+        ("Kerberos REALM", PromptKind::Domain),
+        ("PIN: ", PromptKind::Token),
+        ("Favorite color?", PromptKind::Unknown),
+        ("", PromptKind::Unknown),
+    ];
+
+    for (message, expected) in cases {
+        assert_eq!(
+            PromptKind::classify(Status::GetData, message),
+            Some(expected),
+            "message: {message:?}"
+        );
+    }
+}
+
+#[test]
+fn classify_only_matches_whole_words() {
+    let cases = [
+        // "pin" & "otp" within other words aren't token prompts
+        ("Stop typing and enter your password", PromptKind::Password),
+        ("Password mapping:", PromptKind::Password),
+        ("Favorite hotpot?", PromptKind::Unknown),
+        // "user" within other words isn't a username prompt
+        ("Superuser confirmation:", PromptKind::Unknown),
+        ("Enter users group", PromptKind::Unknown),
+        // punctuation still delimits words
+        ("(PIN)", PromptKind::Token),
+        ("user:", PromptKind::Username),
+    ];
+
+    for (message, expected) in cases {
+        assert_eq!(
+            PromptKind::classify(Status::GetData, message),
+            Some(expected),
+            "message: {message:?}"
+        );
+    }
+
+    assert_eq!(
+        PromptKind::classify(Status::GetPassword, "Password (no typing errors):"),
+        Some(PromptKind::Password)
+    );
+}
+
+#[test]
+fn reply_prompt_kind() {
+    let raw_reply = [
+        0x05, // status: getpass
+        1,    // flags: no echo
+        0, 9, // server message length
+        0, 0, // data length
+    ];
+
+    let mut body = array_vec!([u8; 20]);
+    body.extend_from_slice(&raw_reply);
+    body.extend_from_slice(b"Passcode:");
+
+    let reply = Reply::deserialize_from_buffer(&body).expect("reply should be valid");
+    assert_eq!(reply.prompt_kind(), Some(PromptKind::Token));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn status_display_with_rfc_names() {
+    use std::format;
+
+    assert_eq!(format!("{}", Status::GetPassword), "get password");
+    assert_eq!(
+        format!("{:#}", Status::GetPassword),
+        "TAC_PLUS_AUTHEN_STATUS_GETPASS"
+    );
+
+    // unknown statuses have no RFC name
+    assert_eq!(format!("{:#}", Status::Unknown(0x42)), "unknown (0x42)");
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn debug_output_redacts_secrets() {
+    use std::format;
+
+    let start = Start::new(
+        Action::Login,
+        AuthenticationContext {
+            privilege_level: PrivilegeLevel::new(1).unwrap(),
+            authentication_type: AuthenticationType::Pap,
+            service: AuthenticationService::Login,
+        },
+        UserInformation::new(
+            "someuser",
+            FieldText::assert("tty0"),
+            FieldText::assert("127.0.0.1"),
+        )
+        .unwrap(),
+        Some(b"hunter2".as_slice().try_into().unwrap()),
+    )
+    .unwrap();
+
+    let start_debug = format!("{start:?}");
+    assert!(start_debug.contains("someuser"));
+    assert!(start_debug.contains("PacketData(<7 bytes redacted>)"));
+    assert!(!start_debug.contains("hunter2"));
+    assert!(!start_debug.contains("104")); // b'h'
+
+    let continue_body = Continue::new(
+        Some(b"correct horse".as_slice()),
+        Some(b"battery".as_slice()),
+        ContinueFlags::empty(),
+    )
+    .unwrap();
+
+    let continue_debug = format!("{continue_body:?}");
+    assert!(continue_debug.contains("user_message: Some(<13 bytes redacted>)"));
+    assert!(continue_debug.contains("data: Some(<7 bytes redacted>)"));
+    assert!(!continue_debug.contains("horse"));
+}
+
+#[test]
+fn strict_action_rejects_send_auth() {
+    assert_eq!(Action::from(StrictAction::Login), Action::Login);
+    assert_eq!(
+        StrictAction::try_from(Action::ChangePassword),
+        Ok(StrictAction::ChangePassword)
+    );
+    assert_eq!(
+        StrictAction::try_from(Action::SendAuth),
+        Err(Action::SendAuth)
+    );
+}
+
+#[test]
+fn strict_status_rejects_follow_and_unknown() {
+    assert_eq!(Status::from(StrictStatus::GetPassword), Status::GetPassword);
+    assert_eq!(StrictStatus::try_from(Status::Pass), Ok(StrictStatus::Pass));
+    assert_eq!(
+        StrictStatus::try_from(Status::from(0x21)),
+        Err(DeserializeError::InvalidStatus(0x21))
+    );
+    assert_eq!(
+        StrictStatus::try_from(Status::Unknown(0x42)),
+        Err(DeserializeError::InvalidStatus(0x42))
+    );
+}
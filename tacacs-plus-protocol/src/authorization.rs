@@ -1,15 +1,18 @@
 //! Authorization features/packets of the TACACS+ protocol.
 
 use core::fmt;
+use core::iter::{zip, FusedIterator};
 
-use byteorder::{ByteOrder, NetworkEndian};
 use getset::Getters;
-use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use super::{
     Argument, Arguments, AuthenticationContext, AuthenticationMethod, DeserializeError,
-    InvalidArgument, PacketBody, PacketType, Serialize, SerializeError, UserInformation,
+    InvalidArgument, KnownLength, PacketBody, PacketType, Serialize, SerializeError,
+    UserInformation,
 };
+use crate::body::{BodyReader, BodyWriter};
+use crate::util::{U16_LENGTH_WIRE_SIZE, U8_LENGTH_WIRE_SIZE};
 use crate::{Deserialize, FieldText};
 
 #[cfg(test)]
@@ -21,6 +24,16 @@ mod owned;
 #[cfg(feature = "std")]
 pub use owned::ReplyOwned;
 
+#[cfg(feature = "std")]
+mod decision;
+#[cfg(feature = "std")]
+pub use decision::Decision;
+
+#[cfg(feature = "std")]
+mod budget;
+#[cfg(feature = "std")]
+pub use budget::{BudgetExceeded, BudgetPolicy, ReplyBudget};
+
 /// An authorization request packet body, including arguments.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Request<'packet> {
@@ -38,6 +51,18 @@ pub struct Request<'packet> {
 }
 
 impl<'packet> Request<'packet> {
+    /// Offset of the authentication method within a request packet body.
+    pub const AUTHENTICATION_METHOD_OFFSET: usize = 0;
+
+    /// Offset of the authentication context (privilege level, type & service) within a request packet body.
+    pub const AUTHENTICATION_CONTEXT_OFFSET: usize = 1;
+
+    /// Offset of the user information field lengths within a request packet body.
+    pub const USER_INFORMATION_LENGTHS_OFFSET: usize = 4;
+
+    /// Offset of the argument count within a request packet body, which is directly followed by the argument lengths.
+    pub const ARGUMENT_COUNT_OFFSET: usize = 7;
+
     /// Assembles an authorization request packet from its fields.
     pub fn new(
         method: AuthenticationMethod,
@@ -52,6 +77,11 @@ impl<'packet> Request<'packet> {
             arguments,
         }
     }
+
+    /// Returns the exact number of bytes this request body occupies when serialized, excluding the packet header.
+    pub fn encoded_size(&self) -> usize {
+        Serialize::wire_size(self)
+    }
 }
 
 impl PacketBody for Request<'_> {
@@ -62,6 +92,25 @@ impl PacketBody for Request<'_> {
         AuthenticationMethod::WIRE_SIZE + AuthenticationContext::WIRE_SIZE + 4;
 }
 
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(
+        Request::AUTHENTICATION_CONTEXT_OFFSET
+            == Request::AUTHENTICATION_METHOD_OFFSET + AuthenticationMethod::WIRE_SIZE
+    );
+    assert!(
+        Request::USER_INFORMATION_LENGTHS_OFFSET
+            == Request::AUTHENTICATION_CONTEXT_OFFSET + AuthenticationContext::WIRE_SIZE
+    );
+    assert!(
+        Request::ARGUMENT_COUNT_OFFSET
+            == Request::USER_INFORMATION_LENGTHS_OFFSET + UserInformation::HEADER_INFORMATION_SIZE
+    );
+    assert!(
+        Request::REQUIRED_FIELDS_LENGTH == Request::ARGUMENT_COUNT_OFFSET + U8_LENGTH_WIRE_SIZE
+    );
+};
+
 impl Serialize for Request<'_> {
     fn wire_size(&self) -> usize {
         AuthenticationMethod::WIRE_SIZE
@@ -70,54 +119,26 @@ impl Serialize for Request<'_> {
             + self.arguments.wire_size()
     }
 
-    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    fn serialize_into_writer(&self, mut writer: BodyWriter<'_>) -> Result<usize, SerializeError> {
         let wire_size = self.wire_size();
 
-        if buffer.len() >= wire_size {
-            buffer[0] = self.method as u8;
-            self.authentication_context.serialize(&mut buffer[1..4]);
-            self.user_information
-                .serialize_field_lengths(&mut buffer[4..7])?;
-
-            let argument_count = self.arguments.argument_count() as usize;
-
-            // the user information fields start after all of the required fields and also the argument lengths, the latter of which take up 1 byte each
-            let user_info_start = Self::REQUIRED_FIELDS_LENGTH + argument_count;
-
-            // cap slice with wire slice to avoid overflowing beyond end of packet body
-            let user_info_written_len = self
-                .user_information
-                .serialize_field_values(&mut buffer[user_info_start..wire_size])?;
-
-            // argument lengths start at index 7, just after the argument count
-            // extra 1 added to allow room for argument count itself
-            let arguments_wire_len = self.arguments.serialize_count_and_lengths(&mut buffer[7..7 + argument_count + 1])?
-                // argument values go after all of the user information, and until the end of the packet
-                + self
-                    .arguments
-                    .serialize_encoded_values(&mut buffer[user_info_start + user_info_written_len..wire_size])?;
-
-            // NOTE: 1 is subtracted from REQUIRED_FIELDS_LENGTH since otherwise the argument count field is double counted (from Arguments::wire_size())
-            let actual_written_len =
-                (Self::REQUIRED_FIELDS_LENGTH - 1) + user_info_written_len + arguments_wire_len;
-
-            if actual_written_len == wire_size {
-                Ok(actual_written_len)
-            } else {
-                Err(SerializeError::LengthMismatch {
-                    expected: wire_size,
-                    actual: actual_written_len,
-                })
-            }
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        writer.write_u8(self.method as u8)?;
+        self.authentication_context.serialize(&mut writer)?;
+        self.user_information.serialize_field_lengths(&mut writer)?;
+        self.arguments.serialize_count_and_lengths(&mut writer)?;
+
+        // user information values go between the argument lengths and values
+        self.user_information.serialize_field_values(&mut writer)?;
+        self.arguments.serialize_encoded_values(&mut writer)?;
+
+        writer.finish(wire_size)
     }
 }
 
 /// The status of an authorization operation, as returned by the server.
+#[non_exhaustive]
 #[repr(u8)]
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, TryFromPrimitive)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, FromPrimitive, IntoPrimitive)]
 pub enum Status {
     /// Authorization passed; server may have additional arguments for the client.
     PassAdd = 0x01,
@@ -134,44 +155,111 @@ pub enum Status {
     /// Forward authorization request to an alternative daemon.
     #[deprecated = "Forwarding to an alternative daemon was deprecated in RFC 8907."]
     Follow = 0x21,
+
+    /// A status without a meaning defined by RFC8907, e.g. one from a protocol extension.
+    #[num_enum(catch_all)]
+    Unknown(u8),
 }
 
 impl Status {
     /// The wire size of an authorization reply status in bytes.
-    const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
+/// Formats a description of the status, or its name from RFC8907 (e.g., `TAC_PLUS_AUTHOR_STATUS_PASS_ADD`) with the
+/// alternate flag (`{:#}`).
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::PassAdd => "pass, arguments added",
-                Self::PassReplace => "pass, arguments replaced",
-                Self::Fail => "fail",
-                Self::Error => "server-side error",
-                #[allow(deprecated)]
-                Self::Follow => "redirect to alternative daemon",
-            }
-        )
+        let (description, rfc_name) = match self {
+            Self::PassAdd => ("pass, arguments added", "TAC_PLUS_AUTHOR_STATUS_PASS_ADD"),
+            Self::PassReplace => (
+                "pass, arguments replaced",
+                "TAC_PLUS_AUTHOR_STATUS_PASS_REPL",
+            ),
+            Self::Fail => ("fail", "TAC_PLUS_AUTHOR_STATUS_FAIL"),
+            Self::Error => ("server-side error", "TAC_PLUS_AUTHOR_STATUS_ERROR"),
+            #[allow(deprecated)]
+            Self::Follow => (
+                "redirect to alternative daemon",
+                "TAC_PLUS_AUTHOR_STATUS_FOLLOW",
+            ),
+            Self::Unknown(status) => return write!(f, "unknown ({status:#04x})"),
+        };
+
+        f.write_str(if f.alternate() { rfc_name } else { description })
     }
 }
 
-// Implementation detail for num_enum, which is why it's hidden
-#[doc(hidden)]
-impl From<TryFromPrimitiveError<Status>> for DeserializeError {
-    fn from(value: TryFromPrimitiveError<Status>) -> Self {
-        Self::InvalidStatus(value.number)
+/// An authorization status that's defined & not deprecated by RFC8907, for code that shouldn't have to handle
+/// [`Status::Follow`] or statuses from protocol extensions.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash)]
+pub enum StrictStatus {
+    /// Authorization passed; server may have additional arguments for the client.
+    PassAdd = 0x01,
+
+    /// Authorization passed; server provides argument values to override those provided in the request.
+    PassReplace = 0x02,
+
+    /// Authorization request was denied.
+    Fail = 0x10,
+
+    /// An error ocurred on the server.
+    Error = 0x11,
+}
+
+impl From<StrictStatus> for Status {
+    fn from(status: StrictStatus) -> Self {
+        match status {
+            StrictStatus::PassAdd => Self::PassAdd,
+            StrictStatus::PassReplace => Self::PassReplace,
+            StrictStatus::Fail => Self::Fail,
+            StrictStatus::Error => Self::Error,
+        }
+    }
+}
+
+/// Fails with [`DeserializeError::InvalidStatus`] for deprecated & unknown statuses.
+impl TryFrom<Status> for StrictStatus {
+    type Error = DeserializeError;
+
+    fn try_from(status: Status) -> Result<Self, DeserializeError> {
+        match status {
+            Status::PassAdd => Ok(Self::PassAdd),
+            Status::PassReplace => Ok(Self::PassReplace),
+            Status::Fail => Ok(Self::Fail),
+            Status::Error => Ok(Self::Error),
+            other => Err(DeserializeError::InvalidStatus(other.into())),
+        }
     }
 }
 
 /// Information about a reply packet's arguments.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct ArgumentsInfo<'raw> {
     argument_count: u8,
     argument_lengths: &'raw [u8],
     arguments_buffer: &'raw [u8],
+
+    /// Offsets of each argument within `arguments_buffer`, computed during deserialization to allow for random access.
+    ///
+    /// The combined length of all arguments is at most `u8::MAX * u8::MAX`, so a `u16` is enough to hold any offset.
+    argument_offsets: [u16; u8::MAX as usize],
+}
+
+// manual impl to avoid printing all of the unused argument offsets
+impl fmt::Debug for ArgumentsInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArgumentsInfo")
+            .field("argument_count", &self.argument_count)
+            .field("argument_lengths", &self.argument_lengths)
+            .field("arguments_buffer", &self.arguments_buffer)
+            .field(
+                "argument_offsets",
+                &&self.argument_offsets[..self.argument_count as usize],
+            )
+            .finish()
+    }
 }
 
 /// The body of an authorization reply packet.
@@ -201,6 +289,16 @@ struct ReplyFieldLengths {
     total_length: u32,
 }
 
+/// The fields of a raw authorization reply body, before its arguments are validated.
+struct ReplyFields<'raw> {
+    status: Status,
+    argument_count: u8,
+    argument_lengths: &'raw [u8],
+    server_message: FieldText<'raw>,
+    data: FieldText<'raw>,
+    argument_values: &'raw [u8],
+}
+
 /// An iterator over the arguments in an authorization reply packet.
 #[derive(Debug, Clone)]
 pub struct ArgumentsIterator<'iter> {
@@ -212,13 +310,19 @@ pub struct ArgumentsIterator<'iter> {
 
     /// Offset of an argument within the buffer.
     next_offset: usize,
+
+    /// Position just past the last argument yet to be yielded from the back of the iterator.
+    back_argument_number: usize,
+
+    /// Offset just past the end of the last argument yet to be yielded from the back of the iterator.
+    back_offset: usize,
 }
 
 impl<'iter> Iterator for ArgumentsIterator<'iter> {
     type Item = Argument<'iter>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next_argument_number < self.arguments_info.argument_count as usize {
+        if self.next_argument_number < self.back_argument_number {
             // get encoded argument from buffer based on stored offset into buffer/length
             let next_length =
                 self.arguments_info.argument_lengths[self.next_argument_number] as usize;
@@ -238,81 +342,202 @@ impl<'iter> Iterator for ArgumentsIterator<'iter> {
 
     // required for ExactSizeIterator impl
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let total_size = self.arguments_info.argument_count as usize;
-        let remaining_size = total_size - self.next_argument_number;
+        let remaining_size = self.back_argument_number - self.next_argument_number;
 
         // these are asserted to be equal in the default ExactSizeIterator::len() implementation
         (remaining_size, Some(remaining_size))
     }
 }
 
+impl DoubleEndedIterator for ArgumentsIterator<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.next_argument_number < self.back_argument_number {
+            // arguments are walked backwards from the end of the buffer using their lengths
+            let back_length =
+                self.arguments_info.argument_lengths[self.back_argument_number - 1] as usize;
+            let raw_argument = &self.arguments_info.arguments_buffer
+                [self.back_offset - back_length..self.back_offset];
+
+            self.back_argument_number -= 1;
+            self.back_offset -= back_length;
+
+            // NOTE: as in next(), argument validity is checked when deserializing the reply
+            Argument::deserialize(raw_argument).ok()
+        } else {
+            None
+        }
+    }
+}
+
 // Gives ArgumentsIterator a .len() method
 impl ExactSizeIterator for ArgumentsIterator<'_> {}
 
+// once the front & back positions meet, they never move again
+impl FusedIterator for ArgumentsIterator<'_> {}
+
 impl<'packet> Reply<'packet> {
-    const ARGUMENT_LENGTHS_START: usize = 6;
+    /// Offset of the status within a reply packet body.
+    pub const STATUS_OFFSET: usize = 0;
+
+    /// Offset of the argument count within a reply packet body.
+    pub const ARGUMENT_COUNT_OFFSET: usize = 1;
+
+    /// Offset of the server message length within a reply packet body.
+    pub const SERVER_MESSAGE_LENGTH_OFFSET: usize = 2;
+
+    /// Offset of the data length within a reply packet body.
+    pub const DATA_LENGTH_OFFSET: usize = 4;
+
+    /// Offset of the argument lengths within a reply packet body, which directly follow the required fields.
+    pub const ARGUMENT_LENGTHS_OFFSET: usize = 6;
 
     /// Determines the length of a reply packet based on encoded lengths at the beginning of the packet body, if possible.
+    ///
+    /// This is the same as [`KnownLength::extract_total_length()`], for use without importing the trait.
     pub fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
-        Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
+        <Self as KnownLength>::extract_total_length(buffer)
     }
 
     /// Extracts the server message and data lengths from a raw reply packet, if possible.
     fn extract_field_lengths(buffer: &[u8]) -> Result<ReplyFieldLengths, DeserializeError> {
         // data length is the last field in the required part of the header, so we need a full (minimal) header
-        if buffer.len() >= Self::REQUIRED_FIELDS_LENGTH {
-            let argument_count = buffer[1];
-
-            // also ensure that all argument lengths are present
-            if buffer.len() >= Self::REQUIRED_FIELDS_LENGTH + argument_count as usize {
-                let server_message_length = NetworkEndian::read_u16(&buffer[2..4]);
-                let data_length = NetworkEndian::read_u16(&buffer[4..6]);
-
-                let encoded_arguments_length: u32 = buffer[Self::ARGUMENT_LENGTHS_START
-                    ..Self::ARGUMENT_LENGTHS_START + argument_count as usize]
-                    .iter()
-                    .map(|&length| u32::from(length))
-                    .sum();
-
-                // SAFETY: REQUIRED_FIELDS_LENGTH is guaranteed to fit in a u32 by how it's defined
-                let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
-                    + u32::from(argument_count) // argument lengths in "header"
-                    + u32::from(server_message_length)
-                    + u32::from(data_length)
-                    + encoded_arguments_length;
-
-                Ok(ReplyFieldLengths {
-                    data_length,
-                    server_message_length,
-                    total_length,
-                })
-            } else {
-                Err(DeserializeError::UnexpectedEnd)
-            }
+        let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
+
+        // status is validated later, once the body is known to be complete
+        reader.skip(Self::ARGUMENT_COUNT_OFFSET)?;
+        let argument_count = reader.read_u8()?;
+        let server_message_length = reader.read_u16()?;
+        let data_length = reader.read_u16()?;
+
+        // also ensure that all argument lengths are present
+        let encoded_arguments_length: u32 = reader
+            .read_bytes(argument_count as usize)?
+            .iter()
+            .map(|&length| u32::from(length))
+            .sum();
+
+        // SAFETY: REQUIRED_FIELDS_LENGTH is guaranteed to fit in a u32 by how it's defined
+        let total_length = u32::try_from(Self::REQUIRED_FIELDS_LENGTH).unwrap()
+            + u32::from(argument_count) // argument lengths in "header"
+            + u32::from(server_message_length)
+            + u32::from(data_length)
+            + encoded_arguments_length;
+
+        Ok(ReplyFieldLengths {
+            data_length,
+            server_message_length,
+            total_length,
+        })
+    }
+
+    /// Reads the fields of a raw reply body, after checking that its length matches the lengths of those fields.
+    ///
+    /// Arguments are left unvalidated, since the borrowed & owned variants of a reply handle them differently.
+    fn read_fields(buffer: &[u8]) -> Result<ReplyFields<'_>, DeserializeError> {
+        let ReplyFieldLengths {
+            data_length,
+            server_message_length,
+            total_length,
+        } = Self::extract_field_lengths(buffer)?;
+
+        // buffer argument is sliced to proper length in Packet::deserialize_body(), so we can compare against that header length indirectly like this
+        let length_from_header = buffer.len();
+
+        if total_length as usize == length_from_header {
+            let mut reader = BodyReader::new(buffer, Self::REQUIRED_FIELDS_LENGTH)?;
+
+            let status = Status::from(reader.read_u8()?);
+            let argument_count = reader.read_u8()?;
+
+            // server message & data lengths were already extracted above
+            reader.skip(Self::ARGUMENT_LENGTHS_OFFSET - Self::SERVER_MESSAGE_LENGTH_OFFSET)?;
+
+            let argument_lengths = reader.read_bytes(argument_count as usize)?;
+            let server_message = reader.read_text(server_message_length as usize)?;
+            let data = reader.read_text(data_length as usize)?;
+
+            // arguments occupy the rest of the buffer
+            let argument_values = reader.read_bytes(reader.remaining_len())?;
+
+            Ok(ReplyFields {
+                status,
+                argument_count,
+                argument_lengths,
+                server_message,
+                data,
+                argument_values,
+            })
         } else {
-            Err(DeserializeError::UnexpectedEnd)
+            Err(DeserializeError::WrongBodyBufferSize {
+                expected: total_length as usize,
+                buffer_size: length_from_header,
+            })
         }
     }
 
-    /// Ensures a list of argument lengths and their raw values represent a valid set of arguments.
-    fn ensure_arguments_valid(lengths: &[u8], values: &[u8]) -> Result<(), InvalidArgument> {
+    /// Ensures a list of argument lengths and their raw values represent a valid set of arguments,
+    /// returning the offset of each argument within the raw values on success.
+    fn validate_arguments(
+        lengths: &[u8],
+        values: &[u8],
+    ) -> Result<[u16; u8::MAX as usize], InvalidArgument> {
+        let mut offsets = [0; u8::MAX as usize];
         let mut argument_start = 0;
 
-        lengths.iter().try_fold((), |_, &length| {
+        for (offset, &length) in zip(&mut offsets, lengths) {
             let raw_argument = &values[argument_start..argument_start + length as usize];
-            argument_start += length as usize;
 
             // we don't care about the actual argument here, but the specific error should be kept
-            Argument::deserialize(raw_argument).map(|_| ())
-        })
+            Argument::deserialize(raw_argument)?;
+
+            // SAFETY: there are at most u8::MAX arguments of at most u8::MAX bytes each, so this fits in a u16
+            *offset = argument_start.try_into().unwrap();
+            argument_start += length as usize;
+        }
+
+        Ok(offsets)
+    }
+
+    /// Returns the number of arguments included in this reply packet.
+    pub fn argument_count(&self) -> u8 {
+        self.arguments_info.argument_count
+    }
+
+    /// Returns the argument at the provided index, or `None` if the index is out of bounds.
+    ///
+    /// This takes O(1) time regardless of the index: the offset of every argument is computed once when the reply is
+    /// deserialized and kept in a fixed-size table, so neither the preceding arguments nor their lengths are visited.
+    pub fn argument(&self, index: usize) -> Option<Argument<'_>> {
+        if index < self.arguments_info.argument_count as usize {
+            let start = self.arguments_info.argument_offsets[index] as usize;
+            let length = self.arguments_info.argument_lengths[index] as usize;
+
+            // NOTE: argument validity is checked when deserializing the reply, so this should always be Some
+            Argument::deserialize(&self.arguments_info.arguments_buffer[start..start + length]).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the first argument with the provided name, if present.
+    ///
+    /// This performs a linear scan over the arguments of this reply.
+    pub fn argument_by_name(&self, name: &str) -> Option<Argument<'_>> {
+        self.iter_arguments()
+            .find(|argument| argument.name().as_ref() == name)
     }
 
     /// Returns an iterator over the arguments included in this reply packet.
+    ///
+    /// The returned iterator can be traversed from either end, e.g. via [`Iterator::rev()`]
+    /// or [`DoubleEndedIterator::nth_back()`].
     pub fn iter_arguments(&self) -> ArgumentsIterator<'_> {
         ArgumentsIterator {
             arguments_info: &self.arguments_info,
             next_argument_number: 0,
             next_offset: 0,
+            back_argument_number: self.arguments_info.argument_count as usize,
+            back_offset: self.arguments_info.arguments_buffer.len(),
         }
     }
 }
@@ -324,55 +549,51 @@ impl PacketBody for Reply<'_> {
     const REQUIRED_FIELDS_LENGTH: usize = Status::WIRE_SIZE + 1 + 4;
 }
 
-impl<'raw> Deserialize<'raw> for Reply<'raw> {
-    fn deserialize_from_buffer(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
-        let ReplyFieldLengths {
-            data_length,
-            server_message_length,
-            total_length,
-        } = Self::extract_field_lengths(buffer)?;
-
-        // buffer argument is sliced to proper length in Packet::deserialize_body(), so we can compare against that header length indirectly like this
-        let length_from_header = buffer.len();
-
-        if total_length as usize == length_from_header {
-            let status = Status::try_from(buffer[0])?;
-            let argument_count = buffer[1];
-
-            // figure out field offsets
-            let body_start = Self::ARGUMENT_LENGTHS_START + argument_count as usize;
-            let data_start = body_start + server_message_length as usize;
-            let arguments_start = data_start + data_length as usize;
-
-            let server_message = FieldText::try_from(&buffer[body_start..data_start])
-                .map_err(|_| DeserializeError::BadText)?;
-            let data = FieldText::try_from(&buffer[data_start..arguments_start])
-                .map_err(|_| DeserializeError::BadText)?;
-
-            // arguments occupy the rest of the buffer
-            let argument_lengths = &buffer[Self::ARGUMENT_LENGTHS_START..body_start];
-            let argument_values = &buffer[arguments_start..total_length as usize];
-
-            Self::ensure_arguments_valid(argument_lengths, argument_values)?;
+// ensure the hand-written offsets line up with the sizes of the fields before them
+const _: () = {
+    assert!(Reply::ARGUMENT_COUNT_OFFSET == Reply::STATUS_OFFSET + Status::WIRE_SIZE);
+    assert!(
+        Reply::SERVER_MESSAGE_LENGTH_OFFSET == Reply::ARGUMENT_COUNT_OFFSET + U8_LENGTH_WIRE_SIZE
+    );
+    assert!(
+        Reply::DATA_LENGTH_OFFSET == Reply::SERVER_MESSAGE_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE
+    );
+    assert!(Reply::ARGUMENT_LENGTHS_OFFSET == Reply::DATA_LENGTH_OFFSET + U16_LENGTH_WIRE_SIZE);
+    assert!(Reply::REQUIRED_FIELDS_LENGTH == Reply::ARGUMENT_LENGTHS_OFFSET);
+};
 
-            // bundle some information about arguments for iterator purposes
-            let arguments_info = ArgumentsInfo {
-                argument_count,
-                argument_lengths,
-                arguments_buffer: argument_values,
-            };
+impl KnownLength for Reply<'_> {
+    fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
+        Self::extract_field_lengths(buffer).map(|lengths| lengths.total_length)
+    }
+}
 
-            Ok(Self {
-                status,
-                server_message,
-                data,
-                arguments_info,
-            })
-        } else {
-            Err(DeserializeError::WrongBodyBufferSize {
-                expected: total_length as usize,
-                buffer_size: length_from_header,
-            })
-        }
+impl<'raw> Deserialize<'raw> for Reply<'raw> {
+    fn deserialize_from_buffer(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
+        let ReplyFields {
+            status,
+            argument_count,
+            argument_lengths,
+            server_message,
+            data,
+            argument_values,
+        } = Self::read_fields(buffer)?;
+
+        let argument_offsets = Self::validate_arguments(argument_lengths, argument_values)?;
+
+        // bundle some information about arguments for iterator/random access purposes
+        let arguments_info = ArgumentsInfo {
+            argument_count,
+            argument_lengths,
+            arguments_buffer: argument_values,
+            argument_offsets,
+        };
+
+        Ok(Self {
+            status,
+            server_message,
+            data,
+            arguments_info,
+        })
     }
 }
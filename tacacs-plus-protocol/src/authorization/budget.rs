@@ -0,0 +1,223 @@
+//! Construction of authorization replies that are guaranteed to fit the protocol's length limits, for use by servers.
+
+use core::fmt;
+use std::string::String;
+use std::vec::Vec;
+
+use super::{ReplyOwned, Status};
+use crate::{Argument, FieldText, PacketBody};
+
+/// What a [`ReplyBudget`] does with a field or argument that doesn't fit in the remaining space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BudgetPolicy {
+    /// Leave the reply as is and return a [`BudgetExceeded`] error.
+    #[default]
+    Reject,
+
+    /// Truncate messages to the remaining space and omit arguments that don't fit.
+    ///
+    /// Whether anything was cut can be checked via [`ReplyBudget::truncated()`].
+    Truncate,
+}
+
+/// An error returned from a [`ReplyBudget`] when adding a field or argument would exceed a limit.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BudgetExceeded {
+    /// The server message was longer than `u16::MAX` bytes.
+    ServerMessageTooLong,
+
+    /// The data field was longer than `u16::MAX` bytes.
+    DataTooLong,
+
+    /// The reply already had `u8::MAX` arguments.
+    TooManyArguments,
+
+    /// The reply body would have exceeded the configured maximum size.
+    MaxSizeExceeded {
+        /// The number of bytes that were left in the budget.
+        remaining: usize,
+
+        /// The number of bytes the field or argument would have taken up.
+        required: usize,
+    },
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ServerMessageTooLong => {
+                write!(f, "server message must fit in {} bytes", u16::MAX)
+            }
+            Self::DataTooLong => write!(f, "data field must fit in {} bytes", u16::MAX),
+            Self::TooManyArguments => write!(f, "replies can have at most {} arguments", u8::MAX),
+            Self::MaxSizeExceeded {
+                remaining,
+                required,
+            } => write!(
+                f,
+                "reply budget exceeded: {required} bytes required but only {remaining} left"
+            ),
+        }
+    }
+}
+
+/// A builder for authorization replies that tracks the remaining space as fields & arguments are added, so the resulting
+/// reply is always serializable.
+///
+/// Besides the limits imposed by the packet format (`u16` message lengths & at most `u8::MAX` arguments, each of which
+/// is already limited to `u8::MAX` bytes by [`Argument::new()`]), an overall maximum body size can be set with
+/// [`with_max_size()`](Self::with_max_size), e.g. for clients known to have small receive buffers.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::authorization::{BudgetPolicy, ReplyBudget, Status};
+/// use tacacs_plus_protocol::FieldText;
+///
+/// let mut budget = ReplyBudget::new(Status::PassAdd, BudgetPolicy::Truncate).with_max_size(32);
+///
+/// let message = FieldText::try_from("welcome back, this message is a bit too long").unwrap();
+/// budget.set_server_message(message).unwrap();
+/// assert!(budget.truncated());
+/// assert_eq!(budget.remaining(), 0);
+///
+/// let reply = budget.finish();
+/// assert_eq!(reply.encoded_size(), 32);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplyBudget {
+    reply: ReplyOwned,
+    policy: BudgetPolicy,
+    max_size: usize,
+    truncated: bool,
+}
+
+impl ReplyBudget {
+    /// The largest possible size of a reply body, with both messages & every argument at their maximum lengths.
+    const LARGEST_BODY: usize = <ReplyOwned as PacketBody>::REQUIRED_FIELDS_LENGTH
+        + 2 * u16::MAX as usize
+        + u8::MAX as usize * (1 + u8::MAX as usize);
+
+    /// Starts an empty reply with the provided status.
+    pub fn new(status: Status, policy: BudgetPolicy) -> Self {
+        Self {
+            reply: ReplyOwned {
+                status,
+                server_message: String::new(),
+                data: String::new(),
+                arguments: Vec::new(),
+            },
+            policy,
+            max_size: Self::LARGEST_BODY,
+            truncated: false,
+        }
+    }
+
+    /// Limits the serialized size of the reply body to `max_size` bytes.
+    ///
+    /// The fixed fields of a reply always take up 6 bytes, so a smaller limit leaves no room for anything else.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// The number of bytes that can still be added to the reply body.
+    pub fn remaining(&self) -> usize {
+        self.max_size.saturating_sub(self.reply.encoded_size())
+    }
+
+    /// Whether any field was truncated or argument omitted under [`BudgetPolicy::Truncate`].
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Sets the message to present to the user, replacing any previous one.
+    ///
+    /// Messages are taken as [`FieldText`] so they're guaranteed to be printable ASCII, like the other text fields of
+    /// a packet.
+    pub fn set_server_message(
+        &mut self,
+        message: FieldText<'_>,
+    ) -> Result<&mut Self, BudgetExceeded> {
+        let available = self.remaining() + self.reply.server_message.len();
+        let message = self.fit_text(
+            message.as_ref(),
+            available,
+            BudgetExceeded::ServerMessageTooLong,
+        )?;
+        self.reply.server_message = String::from(message);
+        Ok(self)
+    }
+
+    /// Sets the administrative/console log message, replacing any previous one.
+    ///
+    /// As with [`set_server_message()`](Self::set_server_message), the message has to be printable ASCII.
+    pub fn set_data(&mut self, data: FieldText<'_>) -> Result<&mut Self, BudgetExceeded> {
+        let available = self.remaining() + self.reply.data.len();
+        let data = self.fit_text(data.as_ref(), available, BudgetExceeded::DataTooLong)?;
+        self.reply.data = String::from(data);
+        Ok(self)
+    }
+
+    /// Adds an argument to the reply.
+    pub fn push_argument(&mut self, argument: Argument<'_>) -> Result<&mut Self, BudgetExceeded> {
+        let error = if self.reply.arguments.len() >= usize::from(u8::MAX) {
+            BudgetExceeded::TooManyArguments
+        } else {
+            // each argument also takes up a byte for its length
+            let required = 1 + usize::from(argument.encoded_length());
+            let remaining = self.remaining();
+
+            if required <= remaining {
+                self.reply.arguments.push(argument.into_owned());
+                return Ok(self);
+            }
+
+            BudgetExceeded::MaxSizeExceeded {
+                remaining,
+                required,
+            }
+        };
+
+        match self.policy {
+            BudgetPolicy::Reject => Err(error),
+            BudgetPolicy::Truncate => {
+                self.truncated = true;
+                Ok(self)
+            }
+        }
+    }
+
+    /// Returns the finished reply.
+    pub fn finish(self) -> ReplyOwned {
+        self.reply
+    }
+
+    /// Fits a (printable ASCII) message in the provided space & a `u16` length, truncating it if allowed.
+    fn fit_text<'text>(
+        &mut self,
+        text: &'text str,
+        available: usize,
+        too_long: BudgetExceeded,
+    ) -> Result<&'text str, BudgetExceeded> {
+        let limit = available.min(u16::MAX.into());
+        if text.len() <= limit {
+            return Ok(text);
+        }
+
+        match self.policy {
+            BudgetPolicy::Reject if text.len() > u16::MAX.into() => Err(too_long),
+            BudgetPolicy::Reject => Err(BudgetExceeded::MaxSizeExceeded {
+                remaining: available,
+                required: text.len(),
+            }),
+            BudgetPolicy::Truncate => {
+                self.truncated = true;
+
+                // every character of an ASCII string is a single byte, so it can be cut anywhere
+                Ok(&text[..limit])
+            }
+        }
+    }
+}
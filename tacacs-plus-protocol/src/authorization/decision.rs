@@ -0,0 +1,135 @@
+//! Construction of authorization replies from policy decisions, for use by servers.
+
+use std::string::String;
+use std::vec::Vec;
+
+use super::{ReplyOwned, Status};
+use crate::Argument;
+
+/// The outcome of evaluating an authorization request against a server's policy.
+///
+/// A decision can be converted into a reply via [`into_reply()`](Self::into_reply), which picks between
+/// [`PassAdd`](Status::PassAdd) & [`PassReplace`](Status::PassReplace) as appropriate.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::authorization::{Decision, Status};
+/// use tacacs_plus_protocol::{Argument, FieldText};
+///
+/// let argument = |name, value| {
+///     Argument::new(FieldText::try_from(name).unwrap(), FieldText::try_from(value).unwrap(), true).unwrap()
+/// };
+///
+/// let request_arguments = [argument("service", "shell"), argument("priv-lvl", "1")];
+///
+/// // adding an argument doesn't modify any of those sent by the client
+/// let decision = Decision::Permit {
+///     add: vec![argument("idletime", "15")],
+///     replace: vec![],
+/// };
+/// let reply = decision.into_reply(&request_arguments);
+/// assert_eq!(reply.status, Status::PassAdd);
+/// assert_eq!(reply.arguments, [argument("idletime", "15")]);
+///
+/// // but changing the privilege level does, so the full argument set is sent back
+/// let decision = Decision::Permit {
+///     add: vec![],
+///     replace: vec![argument("priv-lvl", "15")],
+/// };
+/// let reply = decision.into_reply(&request_arguments);
+/// assert_eq!(reply.status, Status::PassReplace);
+/// assert_eq!(reply.arguments, [argument("service", "shell"), argument("priv-lvl", "15")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Decision<'args> {
+    /// The request is permitted, possibly with changes to its arguments.
+    Permit {
+        /// Arguments to add to those sent by the client.
+        add: Vec<Argument<'args>>,
+
+        /// Arguments that replace those of the same name sent by the client.
+        ///
+        /// Replacements for arguments the client didn't send are added instead.
+        replace: Vec<Argument<'args>>,
+    },
+
+    /// The request is denied.
+    Deny {
+        /// A message to display to the user.
+        server_message: String,
+
+        /// An administrative message for logging on the client.
+        data: String,
+    },
+}
+
+impl Decision<'_> {
+    /// Converts this decision into a reply to an authorization request with the provided arguments.
+    ///
+    /// A permitted request results in a [`PassReplace`](Status::PassReplace) reply with the full set of arguments if any
+    /// argument sent by the client was modified, and a [`PassAdd`](Status::PassAdd) reply with only the new arguments
+    /// otherwise. A denied request results in a [`Fail`](Status::Fail) reply.
+    pub fn into_reply(self, request_arguments: &[Argument<'_>]) -> ReplyOwned {
+        match self {
+            Self::Permit { add, replace } => permit_reply(request_arguments, add, replace),
+            Self::Deny {
+                server_message,
+                data,
+            } => ReplyOwned {
+                status: Status::Fail,
+                server_message,
+                data,
+                arguments: Vec::new(),
+            },
+        }
+    }
+}
+
+/// Builds the reply for a permitted request.
+fn permit_reply(
+    request_arguments: &[Argument<'_>],
+    add: Vec<Argument<'_>>,
+    replace: Vec<Argument<'_>>,
+) -> ReplyOwned {
+    let sent_argument = |name: &str| {
+        request_arguments
+            .iter()
+            .find(|argument| argument.name().as_ref() == name)
+    };
+
+    // replacements that are identical to what was sent don't actually modify anything
+    let modifies_request = replace.iter().any(|replacement| {
+        sent_argument(replacement.name().as_ref()).is_some_and(|sent| sent != replacement)
+    });
+
+    let mut arguments = Vec::new();
+    let status = if modifies_request {
+        // PASS_REPL requires the client to use the reply arguments instead of its own, so all of them have to be sent
+        arguments.extend(request_arguments.iter().map(|sent| {
+            replace
+                .iter()
+                .find(|replacement| replacement.name() == sent.name())
+                .unwrap_or(sent)
+                .clone()
+                .into_owned()
+        }));
+
+        Status::PassReplace
+    } else {
+        // with PASS_ADD, only arguments the client doesn't already have are sent
+        Status::PassAdd
+    };
+
+    let new_replacements = replace
+        .into_iter()
+        .filter(|replacement| sent_argument(replacement.name().as_ref()).is_none());
+    arguments.extend(new_replacements.chain(add).map(Argument::into_owned));
+
+    ReplyOwned {
+        status,
+        server_message: String::new(),
+        data: String::new(),
+        arguments,
+    }
+}
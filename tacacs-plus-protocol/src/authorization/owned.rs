@@ -1,10 +1,13 @@
 use std::string::{String, ToString};
 use std::vec::Vec;
 
-use super::{Reply, Status};
+use super::{Reply, ReplyFields, Status};
+use crate::body::BodyWriter;
 use crate::owned::FromBorrowedBody;
 use crate::sealed::Sealed;
-use crate::Argument;
+use crate::{
+    Argument, Arguments, Deserialize, DeserializeError, PacketBody, Serialize, SerializeError,
+};
 
 /// An authorization reply packet with owned fields.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -22,6 +25,59 @@ pub struct ReplyOwned {
     pub arguments: Vec<Argument<'static>>,
 }
 
+impl ReplyOwned {
+    /// Returns the exact number of bytes this reply body occupies when serialized, excluding the packet header.
+    pub fn encoded_size(&self) -> usize {
+        Serialize::wire_size(self)
+    }
+
+    /// Deserializes an authorization reply body directly into its owned form.
+    ///
+    /// This is equivalent to deserializing a [`Reply`] and converting it, except that each argument is only parsed once
+    /// and copied as it's validated, rather than being validated up front and then parsed again to be copied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::authorization::{ReplyOwned, Status};
+    ///
+    /// // PASS_ADD status, one argument, empty server message/data & a single argument
+    /// let body = [0x01, 1, 0, 0, 0, 0, 11, b's', b'e', b'r', b'v', b'i', b'c', b'e', b'=', b's', b'h', b'l'];
+    ///
+    /// let reply = ReplyOwned::deserialize_from(&body).unwrap();
+    /// assert_eq!(reply.status, Status::PassAdd);
+    /// assert_eq!(reply.arguments[0].name().as_ref(), "service");
+    /// ```
+    pub fn deserialize_from(buffer: &[u8]) -> Result<Self, DeserializeError> {
+        let ReplyFields {
+            status,
+            argument_count,
+            argument_lengths,
+            server_message,
+            data,
+            argument_values,
+        } = Reply::read_fields(buffer)?;
+
+        let mut arguments = Vec::with_capacity(argument_count.into());
+        let mut argument_start = 0;
+
+        for &length in argument_lengths {
+            // the total length of the arguments was checked against the buffer when reading the fields
+            let raw_argument = &argument_values[argument_start..argument_start + length as usize];
+            arguments.push(Argument::deserialize(raw_argument)?.into_owned());
+
+            argument_start += length as usize;
+        }
+
+        Ok(Self {
+            status,
+            server_message: String::from(server_message.as_ref()),
+            data: String::from(data.as_ref()),
+            arguments,
+        })
+    }
+}
+
 impl Sealed for ReplyOwned {}
 
 impl FromBorrowedBody for ReplyOwned {
@@ -40,4 +96,50 @@ impl FromBorrowedBody for ReplyOwned {
             arguments: arguments_vec,
         }
     }
+
+    fn deserialize_owned<'b>(buffer: &'b [u8]) -> Result<Self, DeserializeError>
+    where
+        Reply<'b>: Deserialize<'b>,
+    {
+        Self::deserialize_from(buffer)
+    }
+}
+
+// replies are only serialized by servers, which is why only the owned variant supports it
+impl Serialize for ReplyOwned {
+    fn wire_size(&self) -> usize {
+        let arguments_length: usize = self
+            .arguments
+            .iter()
+            .map(|argument| 1 + usize::from(argument.encoded_length()))
+            .sum();
+
+        <Self as PacketBody>::REQUIRED_FIELDS_LENGTH
+            + self.server_message.len()
+            + self.data.len()
+            + arguments_length
+    }
+
+    fn serialize_into_writer(&self, mut writer: BodyWriter<'_>) -> Result<usize, SerializeError> {
+        let arguments = Arguments::new(&self.arguments).ok_or(SerializeError::LengthOverflow)?;
+        let server_message_length = u16::try_from(self.server_message.len())?;
+        let data_length = u16::try_from(self.data.len())?;
+
+        let wire_size = self.wire_size();
+
+        writer.write_u8(self.status.into())?;
+        writer.write_u8(arguments.argument_count())?;
+        writer.write_u16(server_message_length)?;
+        writer.write_u16(data_length)?;
+
+        for argument in &self.arguments {
+            writer.write_u8(argument.encoded_length())?;
+        }
+
+        writer.write_bytes(self.server_message.as_bytes())?;
+        writer.write_bytes(self.data.as_bytes())?;
+        arguments.serialize_encoded_values(&mut writer)?;
+
+        writer.finish(wire_size)
+    }
 }
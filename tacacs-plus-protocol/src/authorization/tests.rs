@@ -1,10 +1,14 @@
+use core::iter::zip;
+#[cfg(feature = "std")]
+use std::{string::String, vec};
+
 use super::*;
 use crate::packet::xor_body_with_pad;
 use crate::FieldText;
 use crate::{
     Arguments, AuthenticationContext, AuthenticationMethod, AuthenticationService,
     AuthenticationType, HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags,
-    PrivilegeLevel, Serialize, UserInformation, Version,
+    PrivilegeLevel, Serialize, SerializeError, UserInformation, Version,
 };
 
 use tinyvec::array_vec;
@@ -194,6 +198,100 @@ fn serialize_full_request_packet() {
     assert_eq!(&buffer[..serialized_length], expected.as_slice());
 }
 
+#[test]
+fn request_serialization_round_trip() {
+    let all_arguments = [
+        Argument::new(
+            FieldText::assert("service"),
+            FieldText::assert("shell"),
+            true,
+        )
+        .unwrap(),
+        Argument::new(FieldText::assert("cmd"), FieldText::assert(""), false).unwrap(),
+        Argument::new(FieldText::assert("priv-lvl"), FieldText::assert("15"), true).unwrap(),
+    ];
+
+    let user_informations = [
+        UserInformation::new("", FieldText::assert(""), FieldText::assert("")).unwrap(),
+        UserInformation::new("user", FieldText::assert("tty0"), FieldText::assert("")).unwrap(),
+        UserInformation::new(
+            "someone",
+            FieldText::assert(""),
+            FieldText::assert("10.0.0.1"),
+        )
+        .unwrap(),
+    ];
+
+    let authentication_context = AuthenticationContext {
+        privilege_level: PrivilegeLevel::new(15).unwrap(),
+        authentication_type: AuthenticationType::NotSet,
+        service: AuthenticationService::Login,
+    };
+
+    for user_information in user_informations.iter() {
+        for argument_count in 0..=all_arguments.len() {
+            let arguments = &all_arguments[..argument_count];
+            let request = Request::new(
+                AuthenticationMethod::TacacsPlus,
+                authentication_context,
+                user_information.clone(),
+                Arguments::new(&arguments).unwrap(),
+            );
+
+            let method_byte = AuthenticationMethod::TacacsPlus as u8;
+
+            let mut buffer = [0xff; 100];
+            let written = request
+                .serialize_into_buffer(&mut buffer)
+                .expect("buffer should have been big enough");
+            assert_eq!(written, request.wire_size());
+
+            // a buffer that's just barely too small should be rejected rather than partially written
+            assert_eq!(
+                request.serialize_into_buffer(&mut [0; 100][..written - 1]),
+                Err(SerializeError::NotEnoughSpace)
+            );
+
+            // walking the body using only its encoded lengths should yield back the original fields
+            let body = &buffer[..written];
+            assert_eq!(body[Request::AUTHENTICATION_METHOD_OFFSET], method_byte);
+            let user_information_lengths =
+                &body[Request::USER_INFORMATION_LENGTHS_OFFSET..Request::ARGUMENT_COUNT_OFFSET];
+            assert_eq!(
+                body[Request::ARGUMENT_COUNT_OFFSET] as usize,
+                argument_count
+            );
+            let argument_lengths = &body[Request::REQUIRED_FIELDS_LENGTH..][..argument_count];
+
+            let mut rest = &body[Request::REQUIRED_FIELDS_LENGTH + argument_count..];
+            let mut take = |length: u8| {
+                let (field, remaining) = rest.split_at(length as usize);
+                rest = remaining;
+                field
+            };
+
+            assert_eq!(
+                take(user_information_lengths[0]),
+                user_information.user().as_bytes()
+            );
+            assert_eq!(
+                take(user_information_lengths[1]),
+                user_information.port().as_bytes()
+            );
+            assert_eq!(
+                take(user_information_lengths[2]),
+                user_information.remote_address().as_bytes()
+            );
+
+            for (argument, &length) in zip(arguments, argument_lengths) {
+                assert_eq!(Argument::deserialize(take(length)).as_ref(), Ok(argument));
+            }
+
+            assert!(rest.is_empty(), "no bytes should be left over");
+        }
+    }
+}
+
 #[test]
 fn deserialize_reply_no_arguments() {
     let mut raw_bytes = array_vec!([u8; 50]);
@@ -280,6 +378,32 @@ fn deserialize_reply_two_arguments() {
     assert_eq!(arguments_iter.next(), None);
 }
 
+#[test]
+fn reply_length_known_from_fixed_fields() {
+    use crate::KnownLength;
+
+    let fixed_fields = [
+        0x01, // status: pass/add
+        2,    // two arguments
+        0, 5, // server message length
+        0, 5,  // data length
+        13, // argument 1 length
+        20, // argument 2 length
+    ];
+
+    // the variable-length fields don't have to be present to know the full length
+    assert_eq!(
+        <Reply as KnownLength>::extract_total_length(&fixed_fields),
+        Ok(8 + 5 + 5 + 13 + 20)
+    );
+
+    // but all of the argument lengths do
+    assert_eq!(
+        <Reply as KnownLength>::extract_total_length(&fixed_fields[..7]),
+        Err(DeserializeError::UnexpectedEnd)
+    );
+}
+
 #[test]
 fn deserialize_full_reply_packet() {
     let mut raw_packet = array_vec!([u8; 60]);
@@ -506,3 +630,332 @@ fn full_unobfuscated_reply_packet_to_owned() {
         .unwrap()]
     );
 }
+
+#[test]
+fn reply_arguments_double_ended_iteration() {
+    let mut raw_body = array_vec!([u8; 60]);
+    raw_body.extend_from_slice(&[
+        0x01, // status: pass/add
+        3,    // argument count
+        0, 0, // server message length
+        0, 0, // data length
+        5, // argument 1 length
+        6, // argument 2 length
+        3, // argument 3 length
+    ]);
+    raw_body.extend_from_slice(b"one=1");
+    raw_body.extend_from_slice(b"two*22");
+    raw_body.extend_from_slice(b"3=3");
+
+    let reply = Reply::deserialize_from_buffer(&raw_body).expect("reply should be valid");
+    assert_eq!(reply.argument_count(), 3);
+
+    let one = Argument::new(FieldText::assert("one"), FieldText::assert("1"), true).unwrap();
+    let two = Argument::new(FieldText::assert("two"), FieldText::assert("22"), false).unwrap();
+    let three = Argument::new(FieldText::assert("3"), FieldText::assert("3"), true).unwrap();
+
+    // reversed iteration
+    let mut reversed = reply.iter_arguments().rev();
+    assert_eq!(reversed.next(), Some(three.clone()));
+    assert_eq!(reversed.next(), Some(two.clone()));
+    assert_eq!(reversed.next(), Some(one.clone()));
+    assert_eq!(reversed.next(), None);
+
+    // alternating ends should meet in the middle
+    let mut iter = reply.iter_arguments();
+    assert_eq!(iter.next(), Some(one));
+    assert_eq!(iter.next_back(), Some(three.clone()));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next_back(), Some(two));
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    assert_eq!(reply.iter_arguments().nth_back(0), Some(three));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn reply_argument_offsets_cover_largest_reply() {
+    use std::format;
+
+    // the most arguments a reply can hold, each as long as an argument can be
+    let mut raw_body = vec![0x01, u8::MAX, 0, 0, 0, 0];
+    raw_body.extend([u8::MAX; u8::MAX as usize]);
+    for index in 0..u8::MAX {
+        raw_body.extend(format!("{index:03}={:0>251}", index).as_bytes());
+    }
+
+    let reply = Reply::deserialize_from_buffer(&raw_body).expect("reply should be valid");
+
+    for index in 0..u8::MAX {
+        let argument = reply
+            .argument(index.into())
+            .expect("argument should be present");
+        assert_eq!(argument.name().as_ref(), format!("{index:03}"));
+        assert_eq!(argument.value().as_ref(), format!("{:0>251}", index));
+    }
+    assert_eq!(reply.argument(u8::MAX.into()), None);
+}
+
+#[test]
+fn reply_argument_random_access() {
+    let mut raw_body = array_vec!([u8; 60]);
+    raw_body.extend_from_slice(&[
+        0x02, // status: pass/replace
+        3,    // argument count
+        0, 2, // server message length
+        0, 0,  // data length
+        10, // argument 1 length
+        5,  // argument 2 length
+        9,  // argument 3 length
+    ]);
+    raw_body.extend_from_slice(b"hi");
+    raw_body.extend_from_slice(b"priv-lvl=7");
+    raw_body.extend_from_slice(b"acl*1");
+    raw_body.extend_from_slice(b"timeout=5");
+
+    let reply = Reply::deserialize_from_buffer(&raw_body).expect("reply should be valid");
+
+    let acl = Argument::new(FieldText::assert("acl"), FieldText::assert("1"), false).unwrap();
+    let timeout =
+        Argument::new(FieldText::assert("timeout"), FieldText::assert("5"), true).unwrap();
+
+    assert_eq!(reply.argument(1), Some(acl.clone()));
+    assert_eq!(reply.argument(2), Some(timeout));
+    assert_eq!(reply.argument(3), None);
+
+    assert_eq!(reply.argument_by_name("acl"), Some(acl));
+    assert_eq!(
+        reply
+            .argument_by_name("priv-lvl")
+            .map(|arg| arg.value().clone()),
+        Some(FieldText::assert("7"))
+    );
+    assert_eq!(reply.argument_by_name("priv"), None);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn owned_reply_serialization_round_trip() {
+    let reply = ReplyOwned {
+        status: Status::PassReplace,
+        server_message: String::from("welcome"),
+        data: String::from("admin note"),
+        arguments: vec![
+            Argument::new(FieldText::assert("priv-lvl"), FieldText::assert("15"), true).unwrap(),
+            Argument::new(FieldText::assert("acl"), FieldText::assert("3"), false).unwrap(),
+        ],
+    };
+
+    let mut buffer = [0; 64];
+    let length = reply
+        .serialize_into_buffer(&mut buffer)
+        .expect("serialization should succeed");
+    assert_eq!(length, reply.wire_size());
+
+    let deserialized = ReplyOwned::deserialize_from_buffer(&buffer[..length])
+        .expect("serialized reply should be valid");
+    assert_eq!(deserialized, reply);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn owned_reply_deserialized_directly_matches_converted() {
+    use crate::owned::FromBorrowedBody;
+
+    let mut raw_bytes = array_vec!([u8; 50]);
+    raw_bytes.extend_from_slice(&[
+        0x02, // status: pass/replace
+        2,    // two arguments
+        0, 5, // server message length
+        0, 4,  // data length
+        11, // argument 1 length
+        8,  // argument 2 length
+    ]);
+    raw_bytes.extend_from_slice(b"hello");
+    raw_bytes.extend_from_slice(b"note");
+    raw_bytes.extend_from_slice(b"priv-lvl=15");
+    raw_bytes.extend_from_slice(b"timeout*");
+
+    let direct = ReplyOwned::deserialize_from(&raw_bytes).expect("reply should be valid");
+    let converted = ReplyOwned::from_borrowed(
+        &Reply::deserialize_from_buffer(&raw_bytes).expect("reply should be valid"),
+    );
+    assert_eq!(direct, converted);
+
+    // invalid arguments should be rejected the same way as when borrowing
+    let invalid_bytes = [0x01, 1, 0, 0, 0, 0, 3, b'=', b'1', b'5'];
+    assert_eq!(
+        ReplyOwned::deserialize_from(&invalid_bytes),
+        Err(DeserializeError::InvalidArgument(
+            InvalidArgument::EmptyName
+        ))
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn denied_decision_fails_without_arguments() {
+    let sent = [Argument::new(
+        FieldText::assert("service"),
+        FieldText::assert("shell"),
+        true,
+    )
+    .unwrap()];
+    let reply = Decision::Deny {
+        server_message: String::from("not allowed"),
+        data: String::new(),
+    }
+    .into_reply(&sent);
+
+    assert_eq!(reply.status, Status::Fail);
+    assert_eq!(reply.server_message, "not allowed");
+    assert!(reply.arguments.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn unchanged_replacement_keeps_pass_add() {
+    let priv_lvl =
+        Argument::new(FieldText::assert("priv-lvl"), FieldText::assert("1"), true).unwrap();
+    let timeout =
+        Argument::new(FieldText::assert("timeout"), FieldText::assert("5"), false).unwrap();
+
+    let reply = Decision::Permit {
+        add: vec![],
+        replace: vec![priv_lvl.clone(), timeout.clone()],
+    }
+    .into_reply(&[priv_lvl]);
+
+    // replacing an argument with the same value isn't a modification, but new "replacements" are still sent
+    assert_eq!(reply.status, Status::PassAdd);
+    assert_eq!(reply.arguments, [timeout]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn budget_rejects_what_does_not_fit() {
+    let mut budget = ReplyBudget::new(Status::PassAdd, BudgetPolicy::Reject).with_max_size(20);
+    let argument = Argument::new(
+        FieldText::assert("service"),
+        FieldText::assert("shell"),
+        true,
+    )
+    .unwrap();
+
+    // 6 fixed bytes + 1 length byte + 13 bytes for the argument
+    budget.push_argument(argument.clone()).unwrap();
+    assert_eq!(budget.remaining(), 0);
+
+    assert_eq!(
+        budget.push_argument(argument).map(|_| ()),
+        Err(BudgetExceeded::MaxSizeExceeded {
+            remaining: 0,
+            required: 14
+        })
+    );
+    assert_eq!(
+        budget.set_data(FieldText::assert("x")).map(|_| ()),
+        Err(BudgetExceeded::MaxSizeExceeded {
+            remaining: 0,
+            required: 1
+        })
+    );
+
+    let reply = budget.finish();
+    assert_eq!(reply.arguments.len(), 1);
+    assert!(reply.data.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn budget_enforces_field_limits() {
+    let mut budget = ReplyBudget::new(Status::Fail, BudgetPolicy::Reject);
+    let long_message = "a".repeat(usize::from(u16::MAX) + 1);
+
+    assert_eq!(
+        budget
+            .set_server_message(FieldText::assert(&long_message))
+            .map(|_| ()),
+        Err(BudgetExceeded::ServerMessageTooLong)
+    );
+    assert_eq!(
+        budget
+            .set_data(FieldText::assert(&long_message))
+            .map(|_| ()),
+        Err(BudgetExceeded::DataTooLong)
+    );
+
+    let argument = Argument::new(FieldText::assert("a"), FieldText::assert("b"), false).unwrap();
+    for _ in 0..u8::MAX {
+        budget.push_argument(argument.clone()).unwrap();
+    }
+    assert_eq!(
+        budget.push_argument(argument).map(|_| ()),
+        Err(BudgetExceeded::TooManyArguments)
+    );
+
+    // whatever fit can still be serialized
+    let reply = budget.finish();
+    let mut buffer = vec![0; reply.encoded_size()];
+    reply.serialize_into_buffer(&mut buffer).unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn budget_truncates_when_allowed() {
+    let mut budget = ReplyBudget::new(Status::PassAdd, BudgetPolicy::Truncate).with_max_size(12);
+
+    budget
+        .set_data(FieldText::assert("administrative message"))
+        .unwrap();
+    assert_eq!(budget.remaining(), 0);
+    assert!(budget.truncated());
+
+    // replacing a field frees up its space again
+    budget.set_data(FieldText::assert("log")).unwrap();
+    assert_eq!(budget.remaining(), 3);
+
+    let argument = Argument::new(
+        FieldText::assert("service"),
+        FieldText::assert("shell"),
+        true,
+    )
+    .unwrap();
+    budget.push_argument(argument).unwrap();
+
+    let reply = budget.finish();
+    assert_eq!(reply.data, "log");
+    assert!(reply.arguments.is_empty());
+    assert_eq!(reply.encoded_size(), 9);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn status_display_with_rfc_names() {
+    use std::format;
+
+    assert_eq!(
+        format!("{}", Status::PassReplace),
+        "pass, arguments replaced"
+    );
+    assert_eq!(
+        format!("{:#}", Status::PassReplace),
+        "TAC_PLUS_AUTHOR_STATUS_PASS_REPL"
+    );
+}
+
+#[test]
+fn strict_status_rejects_follow_and_unknown() {
+    assert_eq!(Status::from(StrictStatus::PassReplace), Status::PassReplace);
+    assert_eq!(StrictStatus::try_from(Status::Fail), Ok(StrictStatus::Fail));
+    assert_eq!(
+        StrictStatus::try_from(Status::from(0x21)),
+        Err(DeserializeError::InvalidStatus(0x21))
+    );
+    assert_eq!(
+        StrictStatus::try_from(Status::Unknown(0x42)),
+        Err(DeserializeError::InvalidStatus(0x42))
+    );
+}
@@ -0,0 +1,171 @@
+//! Cursors for (de)serializing the fields of packet bodies in order.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::{DeserializeError, FieldText, SerializeError};
+
+#[cfg(test)]
+mod tests;
+
+/// A cursor that writes the fields of a packet body into a buffer sequentially.
+///
+/// Every write is bounds-checked against the buffer, so callers don't have to compute any offsets themselves;
+/// the position of each field is determined solely by the order of the writes.
+///
+/// This is only `pub` so it can appear in the (sealed & hidden) [`Serialize`](crate::Serialize) trait, and can't be
+/// constructed outside of this crate.
+pub struct BodyWriter<'buf> {
+    target: Target<'buf>,
+    position: usize,
+}
+
+/// Where a [`BodyWriter`] puts the bytes it writes.
+enum Target<'buf> {
+    /// A preallocated buffer, which is overwritten in place.
+    Slice(&'buf mut [u8]),
+
+    /// A vector the bytes are appended to, up to a maximum number of bytes.
+    ///
+    /// Nothing is written to the vector beforehand, so its spare capacity doesn't have to be initialized.
+    #[cfg(feature = "std")]
+    Vec {
+        vec: &'buf mut std::vec::Vec<u8>,
+        limit: usize,
+    },
+}
+
+impl<'buf> BodyWriter<'buf> {
+    /// Creates a writer that starts at the beginning of a buffer.
+    pub(crate) fn new(buffer: &'buf mut [u8]) -> Self {
+        Self {
+            target: Target::Slice(buffer),
+            position: 0,
+        }
+    }
+
+    /// Creates a writer that appends at most `limit` bytes to the end of a vector.
+    #[cfg(feature = "std")]
+    pub(crate) fn appending(vec: &'buf mut std::vec::Vec<u8>, limit: usize) -> Self {
+        vec.reserve(limit);
+
+        Self {
+            target: Target::Vec { vec, limit },
+            position: 0,
+        }
+    }
+
+    /// Returns the number of bytes written so far.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Writes a single byte.
+    pub(crate) fn write_u8(&mut self, value: u8) -> Result<(), SerializeError> {
+        self.write_bytes(&[value])
+    }
+
+    /// Writes a two-byte value in network byte order.
+    pub(crate) fn write_u16(&mut self, value: u16) -> Result<(), SerializeError> {
+        let mut bytes = [0; 2];
+        NetworkEndian::write_u16(&mut bytes, value);
+        self.write_bytes(&bytes)
+    }
+
+    /// Copies a slice of bytes verbatim.
+    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), SerializeError> {
+        match &mut self.target {
+            Target::Slice(buffer) => buffer
+                .get_mut(self.position..)
+                .and_then(|remaining| remaining.get_mut(..bytes.len()))
+                .ok_or(SerializeError::NotEnoughSpace)?
+                .copy_from_slice(bytes),
+
+            #[cfg(feature = "std")]
+            Target::Vec { vec, limit } => {
+                if self.position + bytes.len() > *limit {
+                    return Err(SerializeError::NotEnoughSpace);
+                }
+
+                vec.extend_from_slice(bytes);
+            }
+        }
+
+        self.position += bytes.len();
+        Ok(())
+    }
+
+    /// Finishes writing, ensuring the expected number of bytes were written.
+    pub(crate) fn finish(self, expected_length: usize) -> Result<usize, SerializeError> {
+        if self.position == expected_length {
+            Ok(self.position)
+        } else {
+            Err(SerializeError::LengthMismatch {
+                expected: expected_length,
+                actual: self.position,
+            })
+        }
+    }
+}
+
+/// A cursor that reads the fields of a packet body from a buffer sequentially.
+///
+/// As with [`BodyWriter`], every read is bounds-checked, so a truncated or malformed body results in an error
+/// rather than a panic.
+pub(crate) struct BodyReader<'buf> {
+    buffer: &'buf [u8],
+    position: usize,
+}
+
+impl<'buf> BodyReader<'buf> {
+    /// Creates a reader that starts at the beginning of a buffer, which must hold at least the provided number of bytes.
+    ///
+    /// The minimum length is usually the length of the required fields of a body, so that truncated bodies are rejected upfront.
+    pub(crate) fn new(buffer: &'buf [u8], minimum_length: usize) -> Result<Self, DeserializeError> {
+        if buffer.len() >= minimum_length {
+            Ok(Self {
+                buffer,
+                position: 0,
+            })
+        } else {
+            Err(DeserializeError::UnexpectedEnd)
+        }
+    }
+
+    /// Returns the number of bytes that haven't been read yet.
+    pub(crate) fn remaining_len(&self) -> usize {
+        self.buffer.len() - self.position
+    }
+
+    /// Reads a single byte.
+    pub(crate) fn read_u8(&mut self) -> Result<u8, DeserializeError> {
+        self.read_bytes(1).map(|bytes| bytes[0])
+    }
+
+    /// Reads a two-byte value in network byte order.
+    pub(crate) fn read_u16(&mut self) -> Result<u16, DeserializeError> {
+        self.read_bytes(2).map(NetworkEndian::read_u16)
+    }
+
+    /// Reads a field of raw bytes with the provided length.
+    pub(crate) fn read_bytes(&mut self, length: usize) -> Result<&'buf [u8], DeserializeError> {
+        let bytes = self
+            .buffer
+            .get(self.position..)
+            .and_then(|remaining| remaining.get(..length))
+            .ok_or(DeserializeError::UnexpectedEnd)?;
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    /// Reads a (printable ASCII) text field with the provided length.
+    pub(crate) fn read_text(&mut self, length: usize) -> Result<FieldText<'buf>, DeserializeError> {
+        self.read_bytes(length)
+            .and_then(|bytes| FieldText::try_from(bytes).map_err(|_| DeserializeError::BadText))
+    }
+
+    /// Skips over fields that were already read some other way, e.g. lengths read while checking the size of a body.
+    pub(crate) fn skip(&mut self, length: usize) -> Result<(), DeserializeError> {
+        self.read_bytes(length).map(|_| ())
+    }
+}
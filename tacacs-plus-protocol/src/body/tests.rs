@@ -0,0 +1,114 @@
+use super::*;
+
+#[test]
+fn fields_written_sequentially() {
+    let mut buffer = [0xff; 8];
+    let mut writer = BodyWriter::new(&mut buffer);
+
+    writer.write_u8(0x12).unwrap();
+    writer.write_u16(0x3456).unwrap();
+    writer.write_bytes(b"abc").unwrap();
+
+    assert_eq!(writer.position(), 6);
+    assert_eq!(writer.finish(6), Ok(6));
+    assert_eq!(buffer, [0x12, 0x34, 0x56, b'a', b'b', b'c', 0xff, 0xff]);
+}
+
+#[test]
+fn write_past_end_rejected() {
+    let mut buffer = [0; 3];
+    let mut writer = BodyWriter::new(&mut buffer);
+
+    writer.write_u16(1).unwrap();
+    assert_eq!(writer.write_u16(2), Err(SerializeError::NotEnoughSpace));
+    assert_eq!(
+        writer.write_bytes(b"xy"),
+        Err(SerializeError::NotEnoughSpace)
+    );
+
+    // failed writes don't move the cursor
+    assert_eq!(writer.position(), 2);
+    writer.write_u8(3).unwrap();
+    assert_eq!(writer.write_u8(4), Err(SerializeError::NotEnoughSpace));
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn appending_writer_extends_vector_up_to_limit() {
+    let mut vec = std::vec![0xaa];
+    let mut writer = BodyWriter::appending(&mut vec, 3);
+
+    writer.write_u16(0x1234).unwrap();
+    assert_eq!(
+        writer.write_bytes(b"xy"),
+        Err(SerializeError::NotEnoughSpace)
+    );
+    writer.write_u8(0x56).unwrap();
+    assert_eq!(writer.write_u8(0x78), Err(SerializeError::NotEnoughSpace));
+    assert_eq!(writer.finish(3), Ok(3));
+
+    // only the bytes actually written are appended
+    assert_eq!(vec, [0xaa, 0x12, 0x34, 0x56]);
+}
+
+#[test]
+fn finish_with_wrong_length_is_mismatch() {
+    let mut buffer = [0; 4];
+    let mut writer = BodyWriter::new(&mut buffer);
+    writer.write_u16(0xabcd).unwrap();
+
+    assert_eq!(
+        writer.finish(4),
+        Err(SerializeError::LengthMismatch {
+            expected: 4,
+            actual: 2
+        })
+    );
+}
+
+#[test]
+fn fields_read_sequentially() {
+    let buffer = [0x12, 0x34, 0x56, b'a', b'b', 0x00, 0xff];
+    let mut reader = BodyReader::new(&buffer, 3).unwrap();
+
+    assert_eq!(reader.read_u8(), Ok(0x12));
+    assert_eq!(reader.read_u16(), Ok(0x3456));
+    assert_eq!(reader.read_text(2), Ok(FieldText::assert("ab")));
+    reader.skip(1).unwrap();
+    assert_eq!(reader.read_bytes(1), Ok(&[0xff][..]));
+    assert_eq!(reader.remaining_len(), 0);
+}
+
+#[test]
+fn buffer_shorter_than_minimum_rejected() {
+    assert!(matches!(
+        BodyReader::new(&[0; 4], 5),
+        Err(DeserializeError::UnexpectedEnd)
+    ));
+}
+
+#[test]
+fn read_past_end_rejected() {
+    let buffer = [1, 2, 3];
+    let mut reader = BodyReader::new(&buffer, 0).unwrap();
+
+    assert_eq!(reader.read_bytes(4), Err(DeserializeError::UnexpectedEnd));
+    assert_eq!(
+        reader.read_bytes(usize::MAX),
+        Err(DeserializeError::UnexpectedEnd)
+    );
+
+    // failed reads don't move the cursor
+    assert_eq!(reader.read_u16(), Ok(0x0102));
+    assert_eq!(reader.read_u16(), Err(DeserializeError::UnexpectedEnd));
+    assert_eq!(reader.skip(2), Err(DeserializeError::UnexpectedEnd));
+    assert_eq!(reader.read_u8(), Ok(3));
+}
+
+#[test]
+fn non_printable_text_rejected() {
+    let buffer = *b"ok\x07";
+    let mut reader = BodyReader::new(&buffer, 0).unwrap();
+
+    assert_eq!(reader.read_text(3), Err(DeserializeError::BadText));
+}
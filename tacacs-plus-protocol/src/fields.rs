@@ -4,7 +4,7 @@ use getset::{CopyGetters, Getters};
 use crate::FieldText;
 use crate::MinorVersion;
 
-use super::SerializeError;
+use super::{BodyWriter, SerializeError};
 
 #[cfg(test)]
 mod tests;
@@ -49,7 +49,7 @@ pub enum AuthenticationMethod {
 
 impl AuthenticationMethod {
     /// The number of bytes an `AuthenticationMethod` occupies on the wire.
-    pub(super) const WIRE_SIZE: usize = 1;
+    pub const WIRE_SIZE: usize = 1;
 }
 
 impl fmt::Display for AuthenticationMethod {
@@ -242,13 +242,15 @@ pub struct AuthenticationContext {
 
 impl AuthenticationContext {
     /// Size of authentication context information on the wire, in bytes.
-    pub(super) const WIRE_SIZE: usize = 3;
+    pub const WIRE_SIZE: usize = 3;
 
     /// Serializes authentication context information into a packet body "header."
-    pub(super) fn serialize(&self, buffer: &mut [u8]) {
-        buffer[0] = self.privilege_level.0;
-        buffer[1] = self.authentication_type as u8;
-        buffer[2] = self.service as u8;
+    pub(super) fn serialize(&self, writer: &mut BodyWriter<'_>) -> Result<usize, SerializeError> {
+        writer.write_u8(self.privilege_level.0)?;
+        writer.write_u8(self.authentication_type as u8)?;
+        writer.write_u8(self.service as u8)?;
+
+        Ok(Self::WIRE_SIZE)
     }
 }
 
@@ -306,40 +308,26 @@ impl<'info> UserInformation<'info> {
     /// Serializes the lengths of the contained fields in the proper order, as to be done in the "header" of a client-sent packet body.
     pub(super) fn serialize_field_lengths(
         &self,
-        buffer: &mut [u8],
+        writer: &mut BodyWriter<'_>,
     ) -> Result<usize, SerializeError> {
-        if buffer.len() >= Self::HEADER_INFORMATION_SIZE {
-            buffer[0] = self.user.len().try_into()?;
-            buffer[1] = self.port.len().try_into()?;
-            buffer[2] = self.remote_address.len().try_into()?;
+        writer.write_u8(self.user.len().try_into()?)?;
+        writer.write_u8(self.port.len().try_into()?)?;
+        writer.write_u8(self.remote_address.len().try_into()?)?;
 
-            // 3 bytes serialized as part of "header" information
-            Ok(3)
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        // 3 bytes serialized as part of "header" information
+        Ok(Self::HEADER_INFORMATION_SIZE)
     }
 
     /// Copies client information fields into their proper locations within a packet body.
     pub(super) fn serialize_field_values(
         &self,
-        buffer: &mut [u8],
+        writer: &mut BodyWriter<'_>,
     ) -> Result<usize, SerializeError> {
-        // ensure buffer is large enough to serialize field values into (i.e., excluding the header lengths)
-        if buffer.len() >= self.wire_size() - Self::HEADER_INFORMATION_SIZE {
-            let user_len = self.user.len();
-            let port_len = self.port.len();
-            let remote_address_len = self.remote_address.len();
-            let total_len = user_len + port_len + remote_address_len;
-
-            // three fields are serialized contiguously without any delimiters, as lengths are stored elsewhere
-            buffer[..user_len].copy_from_slice(self.user.as_bytes());
-            buffer[user_len..user_len + port_len].copy_from_slice(self.port.as_bytes());
-            buffer[user_len + port_len..total_len].copy_from_slice(self.remote_address.as_bytes());
-
-            Ok(total_len)
-        } else {
-            Err(SerializeError::NotEnoughSpace)
-        }
+        // three fields are serialized contiguously without any delimiters, as lengths are stored elsewhere
+        writer.write_bytes(self.user.as_bytes())?;
+        writer.write_bytes(self.port.as_bytes())?;
+        writer.write_bytes(self.remote_address.as_bytes())?;
+
+        Ok(self.wire_size() - Self::HEADER_INFORMATION_SIZE)
     }
 }
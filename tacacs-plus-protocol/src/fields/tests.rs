@@ -11,7 +11,10 @@ fn serialize_authentication_context() {
     };
 
     let mut buffer = [0xff; 3];
-    authentication_context.serialize(&mut buffer);
+    assert_eq!(
+        authentication_context.serialize(&mut BodyWriter::new(&mut buffer)),
+        Ok(3)
+    );
 
     assert_eq!(
         buffer,
@@ -36,7 +39,7 @@ fn serialize_user_information() {
 
     // test lengths serialization
     let field_lengths_len = user_info
-        .serialize_field_lengths(&mut buffer)
+        .serialize_field_lengths(&mut BodyWriter::new(&mut buffer))
         .expect("length serialization should have succeeded");
     assert_eq!(
         buffer[..field_lengths_len],
@@ -49,7 +52,7 @@ fn serialize_user_information() {
 
     // test body/field values serialization
     let field_values_len = user_info
-        .serialize_field_values(&mut buffer)
+        .serialize_field_values(&mut BodyWriter::new(&mut buffer))
         .expect("value serialization should have succeeded");
 
     let mut expected_values = array_vec!([u8; 40]);
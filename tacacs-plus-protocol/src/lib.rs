@@ -16,17 +16,29 @@ use core::{fmt, num::TryFromIntError};
 
 mod util;
 
+mod body;
+use body::BodyWriter;
+
 pub mod accounting;
 pub mod authentication;
 pub mod authorization;
 
+pub mod obfuscation;
+pub mod wire;
+
 mod packet;
 use getset::CopyGetters;
-pub use packet::header::HeaderInfo;
-pub use packet::{Packet, PacketFlags, PacketType};
+pub use packet::header::{HeaderInfo, RawHeader};
+pub use packet::{Packet, PacketFlags, PacketType, VersionAdjustment};
+
+#[cfg(feature = "std")]
+pub use packet::stream::{AnyBody, PacketStreamParser};
+
+#[cfg(feature = "codec")]
+pub use packet::codec::{CodecError, TacacsCodec};
 
 mod arguments;
-pub use arguments::{Argument, Arguments, InvalidArgument};
+pub use arguments::{Argument, Arguments, InvalidArgument, InvalidArguments};
 
 mod fields;
 pub use fields::*;
@@ -34,9 +46,17 @@ pub use fields::*;
 mod text;
 pub use text::{FieldText, InvalidText};
 
+#[cfg(feature = "std")]
+pub use text::InvalidRemoteAddress;
+
 #[cfg(feature = "std")]
 mod owned;
 
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+pub use replay::{ReplayCache, ReplayError, ReplayPolicy};
+
 /// An error that occurred when serializing a packet or any of its components into their binary format.
 #[non_exhaustive]
 #[derive(Debug, PartialEq, Eq)]
@@ -80,14 +100,14 @@ impl From<TryFromIntError> for SerializeError {
 #[non_exhaustive]
 #[derive(Debug, PartialEq, Eq)]
 pub enum DeserializeError {
-    /// Invalid binary status representation in response.
+    /// A status that isn't defined (or is deprecated) by RFC8907, e.g. when converting into a `StrictStatus`.
     InvalidStatus(u8),
 
     /// Invalid packet type number on the wire.
     InvalidPacketType(u8),
 
-    /// Invalid header flag byte.
-    InvalidHeaderFlags(u8),
+    /// Invalid header flag byte, along with the rest of the header as it appeared on the wire.
+    InvalidHeaderFlags(RawHeader),
 
     /// Invalid body flag byte.
     InvalidBodyFlags(u8),
@@ -123,6 +143,15 @@ pub enum DeserializeError {
 
     /// Object representation was cut off in some way.
     UnexpectedEnd,
+
+    /// A packet header announced a body longer than the maximum accepted when framing packets from a stream.
+    BodyTooLong {
+        /// The body length from the header.
+        length: usize,
+
+        /// The maximum body length.
+        max: usize,
+    },
 }
 
 impl fmt::Display for DeserializeError {
@@ -130,7 +159,12 @@ impl fmt::Display for DeserializeError {
         match self {
             Self::InvalidStatus(num) => write!(f, "invalid status byte in raw packet: {num:#x}"),
             Self::InvalidPacketType(num) => write!(f, "invalid packet type byte: {num:#x}"),
-            Self::InvalidHeaderFlags(num) => write!(f, "invalid header flags: {num:#x}"),
+            Self::InvalidHeaderFlags(header) => write!(
+                f,
+                "invalid header flags: {:#x} (unknown bits: {:#x})",
+                header.flags(),
+                header.unknown_flags()
+            ),
             Self::InvalidBodyFlags(num) => write!(f, "invalid body flags: {num:#x}"),
             Self::InvalidVersion(num) => write!(
                 f,
@@ -144,6 +178,7 @@ impl fmt::Display for DeserializeError {
             Self::PacketTypeMismatch { expected, actual } => write!(f, "packet type mismatch: expected {expected:?} but got {actual:?}"),
             Self::WrongBodyBufferSize { expected, buffer_size } => write!(f, "body buffer size didn't match length fields: expected {expected} bytes, but buffer was actually {buffer_size}"),
             Self::UnexpectedEnd => write!(f, "unexpected end of buffer when deserializing object"),
+            Self::BodyTooLong { length, max } => write!(f, "packet body of {length} bytes exceeds maximum of {max} bytes"),
         }
     }
 }
@@ -155,14 +190,19 @@ mod error_impls {
     use std::fmt;
 
     use super::text::InvalidText;
-    use super::{DeserializeError, InvalidArgument, SerializeError};
+    use super::{DeserializeError, InvalidArgument, InvalidArguments, SerializeError};
 
     impl Error for DeserializeError {}
     impl Error for SerializeError {}
     impl Error for InvalidArgument {}
+    impl Error for InvalidArguments {}
     impl Error for super::authentication::BadStart {}
     impl Error for super::authentication::DataTooLong {}
+    impl Error for super::authorization::BudgetExceeded {}
     impl<T> Error for InvalidText<T> where InvalidText<T>: fmt::Debug + fmt::Display {}
+    impl Error for super::InvalidRemoteAddress {}
+    impl Error for super::ReplayError {}
+    impl Error for super::VersionAdjustment {}
 }
 
 // suggestion from Rust API guidelines: https://rust-lang.github.io/api-guidelines/future-proofing.html#sealed-traits-protect-against-downstream-implementations-c-sealed
@@ -331,6 +371,19 @@ pub trait PacketBody: sealed::Sealed {
     }
 }
 
+/// A packet body whose total length can be determined from the fields at its start.
+///
+/// This can be used to frame (deobfuscated) bodies read from a transport, e.g. to allocate an exactly-sized buffer once
+/// the beginning of a body has been read.
+pub trait KnownLength: PacketBody {
+    /// Determines the total length of a packet body from the beginning of a buffer containing it.
+    ///
+    /// Only the fixed-length fields at the start of the body (along with the argument lengths, for
+    /// [authorization replies](authorization::Reply)) have to be present in the buffer; the variable-length
+    /// fields are not inspected. [`DeserializeError::UnexpectedEnd`] is returned if the buffer is too short.
+    fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError>;
+}
+
 /// Something that can be serialized into a binary format.
 #[doc(hidden)]
 pub trait Serialize: sealed::Sealed {
@@ -338,7 +391,17 @@ pub trait Serialize: sealed::Sealed {
     fn wire_size(&self) -> usize;
 
     /// Serializes data into a buffer, returning the resulting length on success.
-    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError>;
+    fn serialize_into_buffer(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+        // cap the buffer at the wire size, so nothing past the end of the body is touched
+        let buffer = buffer
+            .get_mut(..self.wire_size())
+            .ok_or(SerializeError::NotEnoughSpace)?;
+
+        self.serialize_into_writer(BodyWriter::new(buffer))
+    }
+
+    /// Serializes data through a writer, returning the resulting length on success.
+    fn serialize_into_writer(&self, writer: BodyWriter<'_>) -> Result<usize, SerializeError>;
 }
 
 /// Something that can be deserialized from a binary format.
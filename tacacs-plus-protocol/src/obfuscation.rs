@@ -0,0 +1,118 @@
+//! The pseudo-pad used to obfuscate packet bodies, as specified in [RFC8907 section 4.5].
+//!
+//! (De)obfuscation is handled transparently by the serialization & deserialization methods on [`Packet`](crate::Packet),
+//! so this module is only needed when working with the pad directly, e.g. to validate another implementation's obfuscation
+//! or to (de)obfuscate a body incrementally as it arrives.
+//!
+//! [RFC8907 section 4.5]: https://www.rfc-editor.org/rfc/rfc8907.html#name-data-obfuscation
+
+use core::iter::FusedIterator;
+
+use md5::{Digest, Md5};
+
+use crate::HeaderInfo;
+
+#[cfg(test)]
+mod tests;
+
+/// MD5 hash output size, in bytes.
+const MD5_OUTPUT_SIZE: usize = 16;
+
+/// Returns an iterator over the bytes of the pseudo-pad for a packet with the provided header.
+///
+/// The pad is generated lazily, one MD5 output at a time, and never ends; it should be zipped with or truncated to the
+/// length of the packet body.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::obfuscation::pseudo_pad_iter;
+/// use tacacs_plus_protocol::{HeaderInfo, MajorVersion, MinorVersion, PacketFlags, Version};
+///
+/// let header = HeaderInfo::new(
+///     Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+///     1,
+///     PacketFlags::SINGLE_CONNECTION,
+///     0xdeadbeef,
+/// );
+///
+/// let mut body = *b"some cleartext body";
+/// for (byte, pad) in body.iter_mut().zip(pseudo_pad_iter(&header, b"secret")) {
+///     *byte ^= pad;
+/// }
+/// assert_ne!(&body, b"some cleartext body");
+///
+/// // since obfuscation is done by XOR, applying the same pad again restores the body
+/// for (byte, pad) in body.iter_mut().zip(pseudo_pad_iter(&header, b"secret")) {
+///     *byte ^= pad;
+/// }
+/// assert_eq!(&body, b"some cleartext body");
+/// ```
+pub fn pseudo_pad_iter<K: AsRef<[u8]>>(header: &HeaderInfo, secret_key: K) -> PseudoPad {
+    // prehash common prefix for all hash invocations
+    // prefix: session id -> key -> version -> sequence number
+    let mut prefix_hasher = Md5::new();
+    prefix_hasher.update(header.session_id().to_be_bytes());
+    prefix_hasher.update(secret_key.as_ref());
+
+    // technically these to_be_bytes calls don't do anything since both fields end up as `u8`s but still
+    prefix_hasher.update(u8::from(header.version()).to_be_bytes());
+    prefix_hasher.update(header.sequence_number().to_be_bytes());
+
+    PseudoPad {
+        prefix_hasher,
+        block: [0; MD5_OUTPUT_SIZE],
+        position: None,
+    }
+}
+
+/// An endless iterator over the bytes of a packet's pseudo-pad, returned from [`pseudo_pad_iter()`].
+#[derive(Debug, Clone)]
+pub struct PseudoPad {
+    /// The hasher state after the prefix shared by every block (session id, key, version & sequence number).
+    prefix_hasher: Md5,
+
+    /// The most recently generated block of the pad.
+    block: [u8; MD5_OUTPUT_SIZE],
+
+    /// The position of the next byte within `block`, or `None` if no block has been generated yet.
+    position: Option<usize>,
+}
+
+impl PseudoPad {
+    /// Generates the next block of the pad, which is chained from the previous one.
+    fn next_block(&mut self) {
+        let mut hasher = self.prefix_hasher.clone();
+
+        // first block just uses hashed prefix; the previous block is appended for the rest
+        if self.position.is_some() {
+            hasher.update(self.block);
+        }
+
+        hasher.finalize_into((&mut self.block).into());
+        self.position = Some(0);
+    }
+}
+
+impl Iterator for PseudoPad {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = match self.position {
+            Some(position) if position < MD5_OUTPUT_SIZE => position,
+            _ => {
+                self.next_block();
+                0
+            }
+        };
+
+        self.position = Some(position + 1);
+        Some(self.block[position])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl FusedIterator for PseudoPad {}
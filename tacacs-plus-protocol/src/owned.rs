@@ -1,6 +1,6 @@
 use super::DeserializeError;
 use super::PacketType;
-use super::{sealed::Sealed, Deserialize, PacketBody};
+use super::{sealed::Sealed, Deserialize, KnownLength, PacketBody};
 
 /// Converts a reference-based packet to a packet that owns its fields.
 ///
@@ -14,6 +14,19 @@ pub trait FromBorrowedBody: Sealed {
 
     /// Converts the borrowed variant of this packet body to its owned variant.
     fn from_borrowed(borrowed: &Self::Borrowed<'_>) -> Self;
+
+    /// Deserializes this packet body from a buffer, which is used for the blanket [`Deserialize`] impl.
+    ///
+    /// By default the borrowed variant is deserialized and then converted, although bodies can build their
+    /// owned fields directly if that avoids extra work.
+    fn deserialize_owned<'b>(buffer: &'b [u8]) -> Result<Self, DeserializeError>
+    where
+        Self: Sized,
+        Self::Borrowed<'b>: Deserialize<'b>,
+    {
+        let borrowed = <Self as FromBorrowedBody>::Borrowed::deserialize_from_buffer(buffer)?;
+        Ok(Self::from_borrowed(&borrowed))
+    }
 }
 
 impl<'b, B: FromBorrowedBody> Deserialize<'b> for B
@@ -21,8 +34,7 @@ where
     B::Borrowed<'b>: Deserialize<'b>,
 {
     fn deserialize_from_buffer(buffer: &'b [u8]) -> Result<Self, DeserializeError> {
-        let borrowed = <B as FromBorrowedBody>::Borrowed::deserialize_from_buffer(buffer)?;
-        Ok(Self::from_borrowed(&borrowed))
+        B::deserialize_owned(buffer)
     }
 }
 
@@ -34,3 +46,12 @@ impl<B: FromBorrowedBody> PacketBody for B {
     const REQUIRED_FIELDS_LENGTH: usize =
         <<B as FromBorrowedBody>::Borrowed<'_> as PacketBody>::REQUIRED_FIELDS_LENGTH;
 }
+
+impl<B: FromBorrowedBody> KnownLength for B
+where
+    for<'b> B::Borrowed<'b>: KnownLength,
+{
+    fn extract_total_length(buffer: &[u8]) -> Result<u32, DeserializeError> {
+        <<B as FromBorrowedBody>::Borrowed<'_> as KnownLength>::extract_total_length(buffer)
+    }
+}
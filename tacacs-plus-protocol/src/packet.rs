@@ -4,15 +4,30 @@ use core::iter::zip;
 use bitflags::bitflags;
 use byteorder::{ByteOrder, NetworkEndian};
 use getset::Getters;
-use md5::{Digest, Md5};
 use num_enum::{TryFromPrimitive, TryFromPrimitiveError};
 
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(feature = "std")]
+use crate::BodyWriter;
+
 use super::{Deserialize, PacketBody, Serialize};
-use super::{DeserializeError, SerializeError};
+use super::{DeserializeError, SerializeError, Version};
+use crate::obfuscation::pseudo_pad_iter;
 
 pub(super) mod header;
 use header::HeaderInfo;
 
+#[cfg(feature = "std")]
+pub(super) mod stream;
+
+#[cfg(feature = "bytes")]
+mod bytes;
+
+#[cfg(feature = "codec")]
+pub(super) mod codec;
+
 #[cfg(test)]
 mod tests;
 
@@ -84,6 +99,30 @@ impl From<TryFromPrimitiveError<PacketType>> for DeserializeError {
     }
 }
 
+/// A change to the protocol version in a packet header required by its body, e.g. minor version 1 for PAP
+/// authentication.
+///
+/// This is returned by [`Packet::new_adjusted()`] when the header was updated, and as an error from
+/// [`Packet::new_strict()`] when it would have had to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersionAdjustment {
+    /// The version in the provided header.
+    pub original: Version,
+
+    /// The version required by the body.
+    pub adjusted: Version,
+}
+
+impl fmt::Display for VersionAdjustment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "packet body requires version ({}) but header has version ({})",
+            self.adjusted, self.original
+        )
+    }
+}
+
 /// A full TACACS+ protocol packet.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Getters)]
 #[getset(get = "pub")]
@@ -105,18 +144,48 @@ impl<B: PacketBody> Packet<B> {
     /// These may include:
     /// - The protocol minor version, depending on authentication method choice
     /// - The [`UNENCRYPTED`](PacketFlags::UNENCRYPTED) flag, depending on if a key is specified
-    pub fn new(mut header: HeaderInfo, body: B) -> Self {
+    ///
+    /// See [`new_adjusted()`](Self::new_adjusted) to find out whether the version was changed, or
+    /// [`new_strict()`](Self::new_strict) to reject mismatched versions instead.
+    pub fn new(header: HeaderInfo, body: B) -> Self {
+        Self::new_adjusted(header, body).0
+    }
+
+    /// Assembles a header and body into a full packet like [`new()`](Self::new), also returning how the protocol
+    /// version in the header was updated to match the body, if it was.
+    pub fn new_adjusted(mut header: HeaderInfo, body: B) -> (Self, Option<VersionAdjustment>) {
+        let adjustment = Self::version_adjustment(&header, &body);
+
         // update minor version to what is required by the body, if applicable
-        if let Some(minor) = body.required_minor_version() {
-            header.version_mut().minor = minor;
+        if let Some(adjustment) = adjustment {
+            *header.version_mut() = adjustment.adjusted;
         }
 
-        Self { header, body }
+        (Self { header, body }, adjustment)
+    }
+
+    /// Assembles a header and body into a full packet without modifying the header, e.g. for proxies that must
+    /// forward packets exactly as they were received.
+    ///
+    /// An error is returned if the body requires a different protocol version than the one in the header.
+    pub fn new_strict(header: HeaderInfo, body: B) -> Result<Self, VersionAdjustment> {
+        match Self::version_adjustment(&header, &body) {
+            Some(adjustment) => Err(adjustment),
+            None => Ok(Self { header, body }),
+        }
     }
-}
 
-/// MD5 hash output size, in bytes.
-const MD5_OUTPUT_SIZE: usize = 16;
+    /// Determines the version a body requires, if it differs from the one in a header.
+    fn version_adjustment(header: &HeaderInfo, body: &B) -> Option<VersionAdjustment> {
+        let original = header.version();
+        let minor = body.required_minor_version()?;
+
+        (original.minor() != minor).then(|| VersionAdjustment {
+            original,
+            adjusted: Version::new(original.major(), minor),
+        })
+    }
+}
 
 /// (De)obfuscates the body of a packet as specified in [RFC8907 section 4.5].
 ///
@@ -124,54 +193,24 @@ const MD5_OUTPUT_SIZE: usize = 16;
 ///
 /// [RFC8907 section 4.5]: https://www.rfc-editor.org/rfc/rfc8907.html#name-data-obfuscation
 pub(super) fn xor_body_with_pad(header: &HeaderInfo, secret_key: &[u8], body_buffer: &mut [u8]) {
-    let mut pseudo_pad = [0; MD5_OUTPUT_SIZE];
-
-    // prehash common prefix for all hash invocations
-    // prefix: session id -> key -> version -> sequence number
-    let mut prefix_hasher = Md5::new();
-    prefix_hasher.update(header.session_id().to_be_bytes());
-    prefix_hasher.update(secret_key);
-
-    // technically these to_be_bytes calls don't do anything since both fields end up as `u8`s but still
-    prefix_hasher.update(u8::from(header.version()).to_be_bytes());
-    prefix_hasher.update(header.sequence_number().to_be_bytes());
-
-    let mut chunks_iter = body_buffer.chunks_mut(MD5_OUTPUT_SIZE);
-
-    // first chunk just uses hashed prefix
-    prefix_hasher
-        .clone()
-        .finalize_into((&mut pseudo_pad).into());
-
-    // SAFETY: the body of a packet is guaranteed to be nonempty due to checks against REQUIRED_FIELD_SIZE,
-    // so this unwrap won't panic
-    let first_chunk = chunks_iter.next().unwrap();
-
-    // xor pseudo-pad with chunk
-    xor_slices(first_chunk, &pseudo_pad);
-
-    for chunk in chunks_iter {
-        // previous pad chunk is appended to prefix prehashed above
-        let mut hasher = prefix_hasher.clone();
-        hasher.update(pseudo_pad);
-        hasher.finalize_into((&mut pseudo_pad).into());
-
-        // xor pseudo-pad with chunk
-        xor_slices(chunk, &pseudo_pad);
-    }
-}
-
-/// XORs two byte slices together, truncating to the shorter of the two argument lengths.
-fn xor_slices(output: &mut [u8], pseudo_pad: &[u8]) {
-    for (out, pad) in zip(output, pseudo_pad) {
-        *out ^= pad;
+    for (byte, pad) in zip(body_buffer, pseudo_pad_iter(header, secret_key)) {
+        *byte ^= pad;
     }
 }
 
 // The Serialize trait is not meant to be exposed publicly, but we still use it internally for serializing packet bodies so we silence the lint here
 impl<B: PacketBody + Serialize> Packet<B> {
     /// Calculates the size of this packet as encoded into its binary format.
+    #[deprecated(note = "use `encoded_size()` instead")]
     pub fn wire_size(&self) -> usize {
+        self.encoded_size()
+    }
+
+    /// Returns the exact number of bytes this packet occupies when serialized, including the header.
+    ///
+    /// A buffer of this size is guaranteed to be large enough for any of the serialization methods,
+    /// e.g. when preallocating buffers or implementing custom framing.
+    pub fn encoded_size(&self) -> usize {
         HeaderInfo::HEADER_SIZE_BYTES + self.body.wire_size()
     }
 
@@ -211,8 +250,99 @@ impl<B: PacketBody + Serialize> Packet<B> {
         self.serialize_packet(buffer)
     }
 
+    /// Serializes the packet into a buffer for use over a TLS connection, leaving the body as cleartext.
+    ///
+    /// Obfuscation is redundant when TACACS+ is carried over TLS, but unlike [`serialize_unobfuscated()`](Self::serialize_unobfuscated)
+    /// this ensures the [`UNENCRYPTED`](PacketFlags::UNENCRYPTED) flag is unset, as the body is still protected in transit.
+    ///
+    /// This consumes the packet.
+    pub fn serialize_for_tls(mut self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+        // TLS provides confidentiality, so the body isn't actually sent "unencrypted"
+        self.header.flags_mut().remove(PacketFlags::UNENCRYPTED);
+
+        self.serialize_packet(buffer)
+    }
+
+    /// Serializes the packet onto the end of a vector, obfuscating the body as in [`serialize()`](Self::serialize).
+    ///
+    /// Only as much space as the packet needs is reserved, so reusing a cleared vector across packets avoids
+    /// allocating a new buffer for each one. The vector is left unchanged if serialization fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use tacacs_plus_protocol::{HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags, Version};
+    /// use tacacs_plus_protocol::authentication::{Continue, ContinueFlags};
+    ///
+    /// let mut buffer = Vec::new();
+    ///
+    /// for sequence_number in [1, 3, 5] {
+    ///     let header = HeaderInfo::new(
+    ///         Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+    ///         sequence_number,
+    ///         PacketFlags::empty(),
+    ///         123,
+    ///     );
+    ///     let packet = Packet::new(header, Continue::new(None, None, ContinueFlags::empty()).unwrap());
+    ///     let expected_length = packet.encoded_size();
+    ///
+    ///     buffer.clear();
+    ///     let length = packet.serialize_into_vec(b"secret", &mut buffer).unwrap();
+    ///     assert_eq!(length, expected_length);
+    ///     assert_eq!(buffer.len(), expected_length);
+    /// }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn serialize_into_vec<K: AsRef<[u8]>>(
+        mut self,
+        secret_key: K,
+        buffer: &mut Vec<u8>,
+    ) -> Result<usize, SerializeError> {
+        // remove unencrypted flag from header
+        self.header.flags_mut().remove(PacketFlags::UNENCRYPTED);
+
+        let start = buffer.len();
+        let packet_length = self.serialize_packet_into_vec(buffer)?;
+
+        xor_body_with_pad(
+            &self.header,
+            secret_key.as_ref(),
+            &mut buffer[start + Self::BODY_START..],
+        );
+
+        Ok(packet_length)
+    }
+
+    /// Serializes the packet onto the end of a vector, leaving the body as cleartext as in [`serialize_unobfuscated()`](Self::serialize_unobfuscated).
+    ///
+    /// See [`serialize_into_vec()`](Self::serialize_into_vec) for details on how the vector is used.
+    #[cfg(feature = "std")]
+    pub fn serialize_unobfuscated_into_vec(
+        mut self,
+        buffer: &mut Vec<u8>,
+    ) -> Result<usize, SerializeError> {
+        // ensure unencrypted flag is set
+        self.header.flags_mut().insert(PacketFlags::UNENCRYPTED);
+
+        self.serialize_packet_into_vec(buffer)
+    }
+
+    /// Serializes the packet onto the end of a vector for use over a TLS connection, as in [`serialize_for_tls()`](Self::serialize_for_tls).
+    ///
+    /// See [`serialize_into_vec()`](Self::serialize_into_vec) for details on how the vector is used.
+    #[cfg(feature = "std")]
+    pub fn serialize_for_tls_into_vec(
+        mut self,
+        buffer: &mut Vec<u8>,
+    ) -> Result<usize, SerializeError> {
+        // TLS provides confidentiality, so the body isn't actually sent "unencrypted"
+        self.header.flags_mut().remove(PacketFlags::UNENCRYPTED);
+
+        self.serialize_packet_into_vec(buffer)
+    }
+
     fn serialize_packet(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
-        let wire_size = self.wire_size();
+        let wire_size = self.encoded_size();
 
         if buffer.len() >= wire_size {
             // serialize body first to get its length, which is stored in the header
@@ -233,6 +363,35 @@ impl<B: PacketBody + Serialize> Packet<B> {
             Err(SerializeError::NotEnoughSpace)
         }
     }
+
+    /// Appends the packet to a vector, writing the header first since the body's length is known upfront.
+    ///
+    /// Bytes are only ever appended, so no space has to be zeroed beforehand; the vector is truncated back to its
+    /// original length if serialization fails.
+    #[cfg(feature = "std")]
+    fn serialize_packet_into_vec(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        let start = buffer.len();
+        let body_length = self.body.wire_size();
+
+        let mut header = [0; HeaderInfo::HEADER_SIZE_BYTES];
+        let result = self
+            .header
+            .serialize(&mut header, B::TYPE, body_length.try_into()?)
+            .and_then(|header_bytes| {
+                buffer.extend_from_slice(&header[..header_bytes]);
+
+                let body_bytes = self
+                    .body
+                    .serialize_into_writer(BodyWriter::appending(buffer, body_length))?;
+                Ok(header_bytes + body_bytes)
+            });
+
+        if result.is_err() {
+            buffer.truncate(start);
+        }
+
+        result
+    }
 }
 
 impl<'raw, B: PacketBody + Deserialize<'raw>> Packet<B> {
@@ -278,6 +437,17 @@ impl<'raw, B: PacketBody + Deserialize<'raw>> Packet<B> {
         }
     }
 
+    /// Attempts to deserialize a packet received over a TLS connection, whose body is not obfuscated.
+    ///
+    /// The [`UNENCRYPTED`](PacketFlags::UNENCRYPTED) flag is not checked, since revisions of the TACACS+ over TLS draft
+    /// disagree on whether it should be set; either way, the body is treated as cleartext.
+    pub fn deserialize_for_tls(buffer: &'raw [u8]) -> Result<Self, DeserializeError> {
+        let header = HeaderInfo::try_from(&buffer[..HeaderInfo::HEADER_SIZE_BYTES])?;
+        let body = Self::deserialize_body(buffer)?;
+
+        Ok(Self::new(header, body))
+    }
+
     fn deserialize_body(buffer: &'raw [u8]) -> Result<B, DeserializeError> {
         if buffer.len() > HeaderInfo::HEADER_SIZE_BYTES {
             let actual_packet_type = PacketType::try_from(buffer[1])?;
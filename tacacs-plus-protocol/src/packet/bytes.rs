@@ -0,0 +1,70 @@
+//! Conversions between packets & buffers from the [`bytes`](::bytes) crate, e.g. for use with codecs.
+
+use ::bytes::{Bytes, BytesMut};
+use std::vec::Vec;
+
+use super::Packet;
+use crate::{Deserialize, DeserializeError, PacketBody, Serialize, SerializeError};
+
+#[cfg(test)]
+mod tests;
+
+impl<B: PacketBody + Serialize> Packet<B> {
+    /// Serializes the packet into a new [`Bytes`] buffer, obfuscating the body as in [`serialize()`](Self::serialize).
+    ///
+    /// The packet is appended to an allocation of exactly its size as with [`serialize_into_vec()`](Self::serialize_into_vec),
+    /// so the buffer is neither zeroed beforehand nor copied afterwards.
+    pub fn to_bytes<K: AsRef<[u8]>>(self, secret_key: K) -> Result<Bytes, SerializeError> {
+        let mut buffer = Vec::with_capacity(self.encoded_size());
+        self.serialize_into_vec(secret_key, &mut buffer)?;
+
+        Ok(Bytes::from(buffer))
+    }
+
+    /// Serializes the packet into a new [`Bytes`] buffer, leaving the body as cleartext as in
+    /// [`serialize_unobfuscated()`](Self::serialize_unobfuscated).
+    pub fn to_bytes_unobfuscated(self) -> Result<Bytes, SerializeError> {
+        let mut buffer = Vec::with_capacity(self.encoded_size());
+        self.serialize_unobfuscated_into_vec(&mut buffer)?;
+
+        Ok(Bytes::from(buffer))
+    }
+}
+
+impl<B: PacketBody + for<'raw> Deserialize<'raw>> Packet<B> {
+    /// Deserializes an obfuscated packet from a buffer, as in [`deserialize()`](Self::deserialize).
+    ///
+    /// Since the buffer is consumed, this is only available for bodies that own their fields (e.g.,
+    /// [`authentication::ReplyOwned`](crate::authentication::ReplyOwned)).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bytes::BytesMut;
+    /// use tacacs_plus_protocol::accounting::{ReplyOwned, Status};
+    /// use tacacs_plus_protocol::Packet;
+    ///
+    /// let raw = [
+    ///     // header: version 12.0, accounting, sequence number 2, unencrypted, session id 1, body length 5
+    ///     0xc0, 0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05,
+    ///     // body: empty server message & data, success status
+    ///     0x00, 0x00, 0x00, 0x00, 0x01,
+    /// ];
+    ///
+    /// let packet: Packet<ReplyOwned> = Packet::from_bytes_unobfuscated(BytesMut::from(&raw[..])).unwrap();
+    /// assert_eq!(packet.body().status, Status::Success);
+    /// ```
+    pub fn from_bytes<K: AsRef<[u8]>>(
+        secret_key: K,
+        mut bytes: BytesMut,
+    ) -> Result<Self, DeserializeError> {
+        Self::deserialize(secret_key, &mut bytes)
+    }
+
+    /// Deserializes a cleartext packet from a buffer, as in [`deserialize_unobfuscated()`](Self::deserialize_unobfuscated).
+    ///
+    /// See [`from_bytes()`](Self::from_bytes) for details on which bodies are supported.
+    pub fn from_bytes_unobfuscated(bytes: BytesMut) -> Result<Self, DeserializeError> {
+        Self::deserialize_unobfuscated(&bytes)
+    }
+}
@@ -0,0 +1,80 @@
+use ::bytes::BytesMut;
+use std::vec;
+
+use crate::authentication::{Continue, ContinueFlags};
+use crate::authorization::{ReplyOwned, Status};
+use crate::{Argument, HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags, Version};
+
+fn header(sequence_number: u8) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        sequence_number,
+        PacketFlags::SINGLE_CONNECTION,
+        0x01020304,
+    )
+}
+
+#[test]
+fn to_bytes_matches_serialize() {
+    let body = Continue::new(Some(b"message"), None, ContinueFlags::empty()).unwrap();
+
+    let mut expected = [0; 24];
+    let expected_length = Packet::new(header(3), body.clone())
+        .serialize(b"key", &mut expected)
+        .unwrap();
+
+    let bytes = Packet::new(header(3), body)
+        .to_bytes(b"key")
+        .expect("packet serialization should succeed");
+    assert_eq!(bytes, expected[..expected_length]);
+}
+
+#[test]
+fn to_bytes_unobfuscated_matches_serialize() {
+    let body = Continue::new(Some(b"message"), None, ContinueFlags::empty()).unwrap();
+
+    let mut expected = [0; 24];
+    let expected_length = Packet::new(header(3), body.clone())
+        .serialize_unobfuscated(&mut expected)
+        .unwrap();
+
+    let bytes = Packet::new(header(3), body)
+        .to_bytes_unobfuscated()
+        .expect("packet serialization should succeed");
+    assert_eq!(bytes, expected[..expected_length]);
+}
+
+#[test]
+fn bytes_round_trip() {
+    let reply = ReplyOwned {
+        status: Status::PassAdd,
+        server_message: "message".into(),
+        data: "data".into(),
+        arguments: vec![Argument::new(
+            "priv-lvl".try_into().unwrap(),
+            "15".try_into().unwrap(),
+            true,
+        )
+        .unwrap()],
+    };
+
+    let obfuscated = Packet::new(header(2), reply.clone())
+        .to_bytes(b"key")
+        .unwrap();
+    let packet: Packet<ReplyOwned> = Packet::from_bytes(b"key", BytesMut::from(&obfuscated[..]))
+        .expect("packet deserialization should succeed");
+    assert_eq!(packet.body(), &reply);
+
+    let cleartext = Packet::new(header(2), reply.clone())
+        .to_bytes_unobfuscated()
+        .unwrap();
+    let packet: Packet<ReplyOwned> =
+        Packet::from_bytes_unobfuscated(BytesMut::from(&cleartext[..]))
+            .expect("packet deserialization should succeed");
+    assert_eq!(packet.body(), &reply);
+
+    // obfuscated packets aren't accepted as cleartext
+    assert!(
+        Packet::<ReplyOwned>::from_bytes_unobfuscated(BytesMut::from(&obfuscated[..])).is_err()
+    );
+}
@@ -0,0 +1,244 @@
+//! A [`tokio_util::codec`] implementation for framing packets over a byte stream.
+
+use std::fmt;
+use std::io;
+use std::vec::Vec;
+
+use ::bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::stream::{frame_packet, AnyBody, PacketStreamParser};
+use super::{xor_body_with_pad, HeaderInfo, Packet, PacketFlags};
+use crate::{DeserializeError, PacketBody, Serialize, SerializeError};
+
+#[cfg(test)]
+mod tests;
+
+/// An error encountered while encoding or decoding packets with a [`TacacsCodec`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum CodecError {
+    /// An error occurred on the underlying transport.
+    Io(io::Error),
+
+    /// A packet couldn't be framed from the stream.
+    Deserialize(DeserializeError),
+
+    /// A packet couldn't be serialized.
+    Serialize(SerializeError),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(_) => write!(f, "I/O error in packet stream"),
+            Self::Deserialize(_) => write!(f, "failed to frame packet"),
+            Self::Serialize(_) => write!(f, "failed to serialize packet"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Deserialize(error) => Some(error),
+            Self::Serialize(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<DeserializeError> for CodecError {
+    fn from(error: DeserializeError) -> Self {
+        Self::Deserialize(error)
+    }
+}
+
+impl From<SerializeError> for CodecError {
+    fn from(error: SerializeError) -> Self {
+        Self::Serialize(error)
+    }
+}
+
+/// A codec for framing TACACS+ packets, e.g. for use with [`Framed`](tokio_util::codec::Framed) streams in servers & proxies.
+///
+/// Decoded packets are deobfuscated if the codec has a secret key, and are otherwise left as they were sent.
+/// Packets are encoded with a cleartext body ([`UNENCRYPTED`](PacketFlags::UNENCRYPTED) flag set) unless the codec
+/// has a secret key, in which case they are obfuscated; bodies that are already obfuscated are sent as is.
+///
+/// Both [`AnyBody`] packets and packets with typed bodies (e.g., [`authorization::ReplyOwned`](crate::authorization::ReplyOwned))
+/// can be encoded.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// use tacacs_plus_protocol::authorization::{Reply, ReplyOwned, Status};
+/// use tacacs_plus_protocol::{HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags, PacketType, Version};
+/// use tacacs_plus_protocol::TacacsCodec;
+///
+/// let mut codec = TacacsCodec::with_secret_key(b"secret");
+/// let mut buffer = BytesMut::new();
+///
+/// let header = HeaderInfo::new(
+///     Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+///     2,
+///     PacketFlags::empty(),
+///     123,
+/// );
+/// let reply = ReplyOwned {
+///     status: Status::Fail,
+///     server_message: String::from("permission denied"),
+///     data: String::new(),
+///     arguments: Vec::new(),
+/// };
+/// codec.encode(Packet::new(header, reply), &mut buffer).unwrap();
+///
+/// let packet = codec.decode(&mut buffer).unwrap().expect("packet should be complete");
+/// assert_eq!(packet.body().packet_type(), PacketType::Authorization);
+/// assert!(!packet.body().is_obfuscated());
+///
+/// let reply: Reply<'_> = packet.parse_body().unwrap();
+/// assert_eq!(*reply.status(), Status::Fail);
+/// ```
+#[derive(Clone)]
+pub struct TacacsCodec {
+    secret_key: Option<Vec<u8>>,
+    max_body_length: usize,
+}
+
+impl Default for TacacsCodec {
+    fn default() -> Self {
+        Self {
+            secret_key: None,
+            max_body_length: PacketStreamParser::DEFAULT_MAX_BODY_LENGTH,
+        }
+    }
+}
+
+impl fmt::Debug for TacacsCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the secret key is omitted to avoid exposing it
+        f.debug_struct("TacacsCodec")
+            .field("has_secret_key", &self.secret_key.is_some())
+            .field("max_body_length", &self.max_body_length)
+            .finish()
+    }
+}
+
+impl TacacsCodec {
+    /// Creates a codec without a secret key, which leaves packet bodies in cleartext.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a codec that (de)obfuscates packet bodies with the provided secret key.
+    pub fn with_secret_key<K: AsRef<[u8]>>(secret_key: K) -> Self {
+        Self {
+            secret_key: Some(secret_key.as_ref().to_vec()),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the maximum length of a decoded packet body, which defaults to
+    /// [`PacketStreamParser::DEFAULT_MAX_BODY_LENGTH`].
+    ///
+    /// Decoding a packet whose header announces a longer body fails with [`DeserializeError::BodyTooLong`] as soon as
+    /// the header is available, without waiting for the body to be buffered.
+    pub fn with_max_body_length(mut self, max_body_length: usize) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    /// The maximum length of a decoded packet body.
+    pub fn max_body_length(&self) -> usize {
+        self.max_body_length
+    }
+}
+
+impl Decoder for TacacsCodec {
+    type Item = Packet<AnyBody>;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let Some((mut packet, packet_length)) = frame_packet(src, self.max_body_length)? else {
+            return Ok(None);
+        };
+
+        src.advance(packet_length);
+
+        if let Some(key) = &self.secret_key {
+            packet.deobfuscate(key);
+        }
+
+        Ok(Some(packet))
+    }
+}
+
+impl Encoder<Packet<AnyBody>> for TacacsCodec {
+    type Error = CodecError;
+
+    fn encode(
+        &mut self,
+        mut packet: Packet<AnyBody>,
+        dst: &mut BytesMut,
+    ) -> Result<(), Self::Error> {
+        let body_length = u32::try_from(packet.body.data.len()).map_err(SerializeError::from)?;
+
+        if !packet.body.obfuscated {
+            if let Some(key) = &self.secret_key {
+                packet.header.flags_mut().remove(PacketFlags::UNENCRYPTED);
+
+                // the pad can't be applied to an empty body, although there's nothing to obfuscate there anyways
+                if !packet.body.data.is_empty() {
+                    xor_body_with_pad(&packet.header, key, &mut packet.body.data);
+                }
+            } else {
+                packet.header.flags_mut().insert(PacketFlags::UNENCRYPTED);
+            }
+        }
+
+        let start = dst.len();
+        dst.resize(start + HeaderInfo::HEADER_SIZE_BYTES, 0);
+        packet
+            .header
+            .serialize(&mut dst[start..], packet.body.packet_type, body_length)?;
+        dst.extend_from_slice(&packet.body.data);
+
+        Ok(())
+    }
+}
+
+impl<B: PacketBody + Serialize> Encoder<Packet<B>> for TacacsCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, packet: Packet<B>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
+        dst.resize(start + packet.encoded_size(), 0);
+
+        let serialize_result = if let Some(key) = &self.secret_key {
+            packet.serialize(key, &mut dst[start..])
+        } else {
+            packet.serialize_unobfuscated(&mut dst[start..])
+        };
+
+        match serialize_result {
+            Ok(length) => {
+                dst.truncate(start + length);
+                Ok(())
+            }
+            Err(error) => {
+                dst.truncate(start);
+                Err(error.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use ::bytes::BytesMut;
+use std::vec;
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::{CodecError, TacacsCodec};
+use crate::accounting::{Reply, Status};
+use crate::authentication::{Continue, ContinueFlags};
+use crate::{
+    AnyBody, DeserializeError, HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags,
+    PacketType, Version,
+};
+
+fn header(flags: PacketFlags) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        2,
+        flags,
+        0x01020304,
+    )
+}
+
+/// The body of an accounting reply with a server message of "hi" and a success status.
+fn accounting_reply_body() -> AnyBody {
+    AnyBody::new(PacketType::Accounting, vec![0, 2, 0, 0, 1, b'h', b'i'])
+}
+
+#[test]
+fn decode_waits_for_complete_packet() {
+    let mut encoded = BytesMut::new();
+    TacacsCodec::new()
+        .encode(
+            Packet::with_any_body(header(PacketFlags::empty()), accounting_reply_body()),
+            &mut encoded,
+        )
+        .unwrap();
+
+    let mut codec = TacacsCodec::new();
+    let mut buffer = BytesMut::from(&encoded[..10]);
+    assert!(codec.decode(&mut buffer).unwrap().is_none());
+
+    buffer.extend_from_slice(&encoded[10..]);
+    let packet = codec
+        .decode(&mut buffer)
+        .unwrap()
+        .expect("packet should be complete");
+    assert!(buffer.is_empty());
+
+    assert!(packet.header().flags().contains(PacketFlags::UNENCRYPTED));
+    let reply: Reply<'_> = packet.parse_body().unwrap();
+    assert_eq!(*reply.status(), Status::Success);
+}
+
+#[test]
+fn obfuscated_round_trip() {
+    let mut codec = TacacsCodec::with_secret_key(b"key");
+    let mut buffer = BytesMut::new();
+
+    // the unencrypted flag is cleared when obfuscating
+    codec
+        .encode(
+            Packet::with_any_body(header(PacketFlags::UNENCRYPTED), accounting_reply_body()),
+            &mut buffer,
+        )
+        .unwrap();
+    assert_ne!(buffer[12..], accounting_reply_body().data()[..]);
+
+    // a codec without the key leaves the body obfuscated
+    let packet = TacacsCodec::new()
+        .decode(&mut buffer.clone())
+        .unwrap()
+        .unwrap();
+    assert!(packet.body().is_obfuscated());
+
+    let packet = codec.decode(&mut buffer).unwrap().unwrap();
+    assert!(!packet.header().flags().contains(PacketFlags::UNENCRYPTED));
+    assert_eq!(packet.body(), &accounting_reply_body());
+}
+
+#[test]
+fn encode_typed_body_matches_serialize() {
+    let body = Continue::new(Some(b"message"), None, ContinueFlags::empty()).unwrap();
+
+    let mut expected = [0; 24];
+    let length = Packet::new(header(PacketFlags::empty()), body.clone())
+        .serialize(b"key", &mut expected)
+        .unwrap();
+
+    // existing contents of the buffer are left in place
+    let mut buffer = BytesMut::from(&b"prefix"[..]);
+    TacacsCodec::with_secret_key(b"key")
+        .encode(Packet::new(header(PacketFlags::empty()), body), &mut buffer)
+        .unwrap();
+
+    assert_eq!(buffer[..6], b"prefix"[..]);
+    assert_eq!(buffer[6..], expected[..length]);
+}
+
+#[test]
+fn decode_invalid_header() {
+    let mut buffer = BytesMut::from(&[0xc0, 42, 2, 1, 0, 0, 0, 1, 0, 0, 0, 0][..]);
+    assert!(TacacsCodec::new().decode(&mut buffer).is_err());
+}
+
+#[test]
+fn decode_body_over_max_length() {
+    let mut encoded = BytesMut::new();
+    TacacsCodec::new()
+        .encode(
+            Packet::with_any_body(header(PacketFlags::UNENCRYPTED), accounting_reply_body()),
+            &mut encoded,
+        )
+        .unwrap();
+
+    // the header alone is enough to reject the packet
+    let mut header_only = BytesMut::from(&encoded[..HeaderInfo::HEADER_SIZE_BYTES]);
+    let mut codec = TacacsCodec::new().with_max_body_length(6);
+    assert!(matches!(
+        codec.decode(&mut header_only),
+        Err(CodecError::Deserialize(DeserializeError::BodyTooLong {
+            length: 7,
+            max: 6
+        }))
+    ));
+
+    let mut codec = TacacsCodec::new().with_max_body_length(7);
+    assert!(codec.decode(&mut encoded).unwrap().is_some());
+}
@@ -69,14 +69,86 @@ impl TryFrom<&[u8]> for HeaderInfo {
     type Error = DeserializeError;
 
     fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        let raw = RawHeader::try_from(buffer)?;
+
         let header = Self {
-            version: buffer[0].try_into()?,
-            sequence_number: buffer[2],
-            flags: PacketFlags::from_bits(buffer[3])
-                .ok_or(DeserializeError::InvalidHeaderFlags(buffer[3]))?,
-            session_id: NetworkEndian::read_u32(&buffer[4..8]),
+            version: raw.version.try_into()?,
+            sequence_number: raw.sequence_number,
+            flags: PacketFlags::from_bits(raw.flags)
+                .ok_or(DeserializeError::InvalidHeaderFlags(raw))?,
+            session_id: raw.session_id,
         };
 
         Ok(header)
     }
 }
+
+/// The fields of a TACACS+ packet header exactly as they appeared on the wire, without any validation.
+///
+/// This is useful for diagnostics, since it can be parsed from packets with e.g. unknown flag bits or an unknown
+/// packet type that a [`HeaderInfo`] can't represent. It's also included in
+/// [`DeserializeError::InvalidHeaderFlags`], so the rest of a header with invalid flags can still be inspected.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::{DeserializeError, HeaderInfo, RawHeader};
+///
+/// // an authorization reply with an unknown flag bit (0x10) set alongside the unencrypted flag
+/// let header = [0xc0, 0x02, 0x02, 0x11, 0, 0, 0, 42, 0, 0, 0, 6];
+///
+/// let raw = RawHeader::try_from(&header[..]).unwrap();
+/// assert_eq!(raw.packet_type(), 0x02);
+/// assert_eq!(raw.unknown_flags(), 0x10);
+/// assert_eq!(raw.session_id(), 42);
+///
+/// assert_eq!(HeaderInfo::try_from(&header[..]), Err(DeserializeError::InvalidHeaderFlags(raw)));
+/// ```
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct RawHeader {
+    /// The version byte, holding the major version in its upper 4 bits & the minor version in its lower 4 bits.
+    version: u8,
+
+    /// The packet type byte.
+    packet_type: u8,
+
+    /// The sequence number of the packet.
+    sequence_number: u8,
+
+    /// The flags byte, including any bits that aren't defined by RFC8907.
+    flags: u8,
+
+    /// ID of the session the packet belongs to.
+    session_id: u32,
+
+    /// The length of the packet body, as reported in the header.
+    body_length: u32,
+}
+
+impl RawHeader {
+    /// The bits of the flags byte that don't correspond to any flag in [`PacketFlags`].
+    pub fn unknown_flags(&self) -> u8 {
+        self.flags & !PacketFlags::all().bits()
+    }
+}
+
+impl TryFrom<&[u8]> for RawHeader {
+    type Error = DeserializeError;
+
+    /// Reads the fields of a header from the start of a buffer, which only fails if the buffer is too short.
+    fn try_from(buffer: &[u8]) -> Result<Self, Self::Error> {
+        let buffer = buffer
+            .get(..HeaderInfo::HEADER_SIZE_BYTES)
+            .ok_or(DeserializeError::UnexpectedEnd)?;
+
+        Ok(Self {
+            version: buffer[0],
+            packet_type: buffer[1],
+            sequence_number: buffer[2],
+            flags: buffer[3],
+            session_id: NetworkEndian::read_u32(&buffer[4..8]),
+            body_length: NetworkEndian::read_u32(&buffer[8..12]),
+        })
+    }
+}
@@ -0,0 +1,254 @@
+//! Incremental framing of packets from a byte stream.
+
+use std::vec::Vec;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{xor_body_with_pad, HeaderInfo, Packet, PacketFlags, PacketType};
+use crate::{Deserialize, DeserializeError, PacketBody};
+
+#[cfg(test)]
+mod tests;
+
+/// The body of a packet of any type, kept as the raw bytes read from the wire.
+///
+/// Bodies are framed without regard to whether they were sent by a client or a server, and are left obfuscated
+/// until [`Packet::deobfuscate()`] is called. A typed body can then be parsed via [`Packet::parse_body()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnyBody {
+    pub(super) packet_type: PacketType,
+    pub(super) data: Vec<u8>,
+    pub(super) obfuscated: bool,
+}
+
+impl AnyBody {
+    /// Creates a cleartext body of the provided type from its raw bytes.
+    pub fn new(packet_type: PacketType, data: Vec<u8>) -> Self {
+        Self {
+            packet_type,
+            data,
+            obfuscated: false,
+        }
+    }
+
+    /// The type of the packet this body belongs to.
+    pub fn packet_type(&self) -> PacketType {
+        self.packet_type
+    }
+
+    /// The raw bytes of the body, which are obfuscated if [`is_obfuscated()`](Self::is_obfuscated) returns true.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Whether the body is still obfuscated.
+    pub fn is_obfuscated(&self) -> bool {
+        self.obfuscated
+    }
+}
+
+impl Packet<AnyBody> {
+    /// Assembles a header and a body of any type into a full packet.
+    ///
+    /// Unlike [`Packet::new()`], the header is used as is, since the requirements of the body aren't known.
+    pub fn with_any_body(header: HeaderInfo, body: AnyBody) -> Self {
+        Self { header, body }
+    }
+
+    /// Deobfuscates the body of this packet with the provided secret key, if it is obfuscated.
+    ///
+    /// Bodies of packets with the [`UNENCRYPTED`](PacketFlags::UNENCRYPTED) flag set are left as is, as are bodies
+    /// that were already deobfuscated. Note that a wrong key can't be detected here, but will likely cause
+    /// [`parse_body()`](Self::parse_body) to fail.
+    pub fn deobfuscate<K: AsRef<[u8]>>(&mut self, secret_key: K) {
+        // the pad can't be applied to an empty body, although there's nothing to deobfuscate there anyways
+        if self.body.obfuscated && !self.body.data.is_empty() {
+            xor_body_with_pad(&self.header, secret_key.as_ref(), &mut self.body.data);
+        }
+
+        self.body.obfuscated = false;
+    }
+
+    /// Parses the (deobfuscated) body of this packet as a specific body type.
+    ///
+    /// [`DeserializeError::IncorrectUnencryptedFlag`] is returned if the body is still obfuscated, and
+    /// [`DeserializeError::PacketTypeMismatch`] if the packet is of a different type than the body.
+    pub fn parse_body<'raw, B: PacketBody + Deserialize<'raw>>(
+        &'raw self,
+    ) -> Result<B, DeserializeError> {
+        if self.body.obfuscated {
+            Err(DeserializeError::IncorrectUnencryptedFlag)
+        } else if self.body.packet_type != B::TYPE {
+            Err(DeserializeError::PacketTypeMismatch {
+                expected: B::TYPE,
+                actual: self.body.packet_type,
+            })
+        } else {
+            B::deserialize_from_buffer(&self.body.data)
+        }
+    }
+}
+
+/// A parser that frames complete packets from a stream of bytes that arrives in arbitrary chunks,
+/// such as from a packet capture or a proxy buffer.
+///
+/// Bytes are buffered with [`feed()`](Self::feed) until a whole packet is available, at which point it can be taken
+/// via [`next_packet()`](Self::next_packet) or by iterating over the parser. Note that iteration ends as soon as no
+/// complete packet is buffered, but can be resumed after feeding more bytes.
+///
+/// If an error is returned, the stream can no longer be framed reliably and the parser should be discarded; iterating
+/// over the parser ends for good after the first error. Packets whose body is longer than a
+/// [maximum length](Self::with_max_body_length) are rejected as soon as their header is available, so a corrupt
+/// length can't make the parser buffer an arbitrary amount of bytes.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::accounting::{Reply, Status};
+/// use tacacs_plus_protocol::{PacketStreamParser, PacketType};
+///
+/// let packet = [
+///     // header: version 12.0, accounting, sequence number 2, unencrypted, session id 1, body length 5
+///     0xc0, 0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05,
+///     // body: empty server message & data, success status
+///     0x00, 0x00, 0x00, 0x00, 0x01,
+/// ];
+///
+/// let mut parser = PacketStreamParser::new();
+///
+/// // the packet is split across chunks at an arbitrary point
+/// parser.feed(&packet[..7]);
+/// assert_eq!(parser.next_packet(), Ok(None));
+///
+/// parser.feed(&packet[7..]);
+/// let packet = parser.next_packet().unwrap().expect("packet should be complete");
+/// assert_eq!(packet.body().packet_type(), PacketType::Accounting);
+///
+/// let reply: Reply<'_> = packet.parse_body().unwrap();
+/// assert_eq!(*reply.status(), Status::Success);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PacketStreamParser {
+    /// Bytes fed into the parser, some of which may have already been consumed.
+    buffer: Vec<u8>,
+
+    /// The index of the first byte in the buffer that hasn't been consumed yet.
+    start: usize,
+
+    /// The longest packet body that is framed.
+    max_body_length: usize,
+
+    /// Whether iteration returned an error, after which it yields nothing else.
+    failed: bool,
+}
+
+impl Default for PacketStreamParser {
+    fn default() -> Self {
+        Self {
+            buffer: Vec::new(),
+            start: 0,
+            max_body_length: Self::DEFAULT_MAX_BODY_LENGTH,
+            failed: false,
+        }
+    }
+}
+
+impl PacketStreamParser {
+    /// The default maximum length of a packet body, which is well above the length of the largest valid body.
+    pub const DEFAULT_MAX_BODY_LENGTH: usize = 1 << 20;
+
+    /// Creates a parser with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum length of a packet body, above which [`DeserializeError::BodyTooLong`] is returned.
+    pub fn with_max_body_length(mut self, max_body_length: usize) -> Self {
+        self.max_body_length = max_body_length;
+        self
+    }
+
+    /// The maximum length of a packet body this parser frames.
+    pub fn max_body_length(&self) -> usize {
+        self.max_body_length
+    }
+
+    /// Appends a chunk of bytes from the stream to the buffer of this parser.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        // consumed bytes are only discarded here, so each packet doesn't incur a shift of the rest of the buffer
+        self.buffer.drain(..self.start);
+        self.start = 0;
+
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// The number of bytes that were fed into the parser, but are not yet part of a returned packet.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Takes the next complete packet from the buffer, returning `Ok(None)` if more bytes are required for one.
+    ///
+    /// The header of a packet is validated as soon as it is available, while its body is only framed (and not parsed).
+    pub fn next_packet(&mut self) -> Result<Option<Packet<AnyBody>>, DeserializeError> {
+        let framed = frame_packet(&self.buffer[self.start..], self.max_body_length)?;
+
+        Ok(framed.map(|(packet, packet_length)| {
+            self.start += packet_length;
+            packet
+        }))
+    }
+}
+
+/// Offset of the body length in a packet header.
+const BODY_LENGTH_OFFSET: usize = 8;
+
+/// Frames the packet at the start of a buffer, returning it along with its length on the wire.
+///
+/// `Ok(None)` is returned if the buffer doesn't hold a complete packet yet, and [`DeserializeError::BodyTooLong`] if the
+/// header announces a body longer than `max_body_length`.
+pub(super) fn frame_packet(
+    bytes: &[u8],
+    max_body_length: usize,
+) -> Result<Option<(Packet<AnyBody>, usize)>, DeserializeError> {
+    let Some(header_bytes) = bytes.get(..HeaderInfo::HEADER_SIZE_BYTES) else {
+        return Ok(None);
+    };
+
+    let header = HeaderInfo::try_from(header_bytes)?;
+    let packet_type = PacketType::try_from(header_bytes[1])?;
+    let body_length = NetworkEndian::read_u32(&header_bytes[BODY_LENGTH_OFFSET..]) as usize;
+    if body_length > max_body_length {
+        return Err(DeserializeError::BodyTooLong {
+            length: body_length,
+            max: max_body_length,
+        });
+    }
+
+    let packet_length = HeaderInfo::HEADER_SIZE_BYTES + body_length;
+    let Some(packet_bytes) = bytes.get(..packet_length) else {
+        return Ok(None);
+    };
+
+    let body = AnyBody {
+        packet_type,
+        data: packet_bytes[HeaderInfo::HEADER_SIZE_BYTES..].to_vec(),
+        obfuscated: !header.flags().contains(PacketFlags::UNENCRYPTED),
+    };
+
+    Ok(Some((Packet { header, body }, packet_length)))
+}
+
+impl Iterator for PacketStreamParser {
+    type Item = Result<Packet<AnyBody>, DeserializeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let next = self.next_packet().transpose();
+        self.failed = matches!(next, Some(Err(_)));
+        next
+    }
+}
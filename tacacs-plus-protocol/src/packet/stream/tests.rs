@@ -0,0 +1,206 @@
+use std::vec::Vec;
+
+use super::{AnyBody, PacketStreamParser};
+use crate::accounting::{Reply, Status};
+use crate::packet::xor_body_with_pad;
+use crate::{authorization, DeserializeError, HeaderInfo, PacketType};
+
+/// An unobfuscated accounting reply with a server message of "hi" and a success status.
+const ACCOUNTING_REPLY: [u8; 19] = [
+    0xc0, // version
+    3,    // accounting packet
+    2,    // sequence number
+    1,    // unencrypted flag
+    // session id
+    0, 0, 0, 1, //
+    // body length
+    0, 0, 0, 7, //
+    // server message length
+    0, 2, //
+    // data length
+    0, 0, //
+    // status: success
+    1, //
+    // server message
+    b'h', b'i',
+];
+
+/// Converts a packet with the unencrypted flag set into an obfuscated one.
+fn obfuscate(mut packet: Vec<u8>, secret_key: &[u8]) -> Vec<u8> {
+    packet[3] &= !1;
+
+    let header = HeaderInfo::try_from(&packet[..HeaderInfo::HEADER_SIZE_BYTES]).unwrap();
+    xor_body_with_pad(
+        &header,
+        secret_key,
+        &mut packet[HeaderInfo::HEADER_SIZE_BYTES..],
+    );
+
+    packet
+}
+
+#[test]
+fn packets_framed_across_chunk_boundaries() {
+    let stream = [ACCOUNTING_REPLY, ACCOUNTING_REPLY].concat();
+
+    let mut parser = PacketStreamParser::new();
+    let mut packets = Vec::new();
+
+    // worst case: every byte arrives on its own
+    for byte in stream.iter() {
+        parser.feed(core::slice::from_ref(byte));
+
+        while let Some(packet) = parser
+            .next_packet()
+            .expect("stream should contain valid packets")
+        {
+            packets.push(packet);
+        }
+    }
+
+    assert_eq!(packets.len(), 2);
+    assert_eq!(parser.buffered_len(), 0);
+
+    for packet in packets {
+        assert_eq!(packet.header().session_id(), 1);
+        assert_eq!(packet.body().packet_type(), PacketType::Accounting);
+        assert_eq!(packet.body().data(), &ACCOUNTING_REPLY[12..]);
+        assert!(!packet.body().is_obfuscated());
+
+        let reply: Reply<'_> = packet.parse_body().expect("body should be a valid reply");
+        assert_eq!(*reply.status(), Status::Success);
+        assert_eq!(reply.server_message(), &"hi");
+    }
+}
+
+#[test]
+fn iteration_resumes_after_feeding_more_bytes() {
+    let mut parser = PacketStreamParser::new();
+
+    // one and a half packets
+    parser.feed(&ACCOUNTING_REPLY);
+    parser.feed(&ACCOUNTING_REPLY[..10]);
+
+    let packets: Result<Vec<_>, _> = parser.by_ref().collect();
+    assert_eq!(packets.map(|packets| packets.len()), Ok(1));
+    assert_eq!(parser.buffered_len(), 10);
+
+    parser.feed(&ACCOUNTING_REPLY[10..]);
+    assert!(matches!(parser.next(), Some(Ok(_))));
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn obfuscated_body_parsed_after_deobfuscation() {
+    let secret_key = b"supersecret";
+
+    let mut parser = PacketStreamParser::new();
+    parser.feed(&obfuscate(ACCOUNTING_REPLY.to_vec(), secret_key));
+
+    let mut packet = parser
+        .next_packet()
+        .expect("packet should be valid")
+        .expect("packet should be complete");
+    assert!(packet.body().is_obfuscated());
+    assert_eq!(
+        packet.parse_body::<Reply<'_>>(),
+        Err(DeserializeError::IncorrectUnencryptedFlag)
+    );
+
+    packet.deobfuscate(secret_key);
+    assert_eq!(packet.body().data(), &ACCOUNTING_REPLY[12..]);
+
+    // deobfuscating again shouldn't reobfuscate the body
+    packet.deobfuscate(secret_key);
+    assert_eq!(packet.body().data(), &ACCOUNTING_REPLY[12..]);
+
+    let reply: Reply<'_> = packet.parse_body().expect("body should be a valid reply");
+    assert_eq!(reply.server_message(), &"hi");
+}
+
+#[test]
+fn body_parsed_as_wrong_type() {
+    let mut parser = PacketStreamParser::new();
+    parser.feed(&ACCOUNTING_REPLY);
+
+    let packet = parser.next_packet().unwrap().unwrap();
+    assert_eq!(
+        packet.parse_body::<authorization::Reply<'_>>(),
+        Err(DeserializeError::PacketTypeMismatch {
+            expected: PacketType::Authorization,
+            actual: PacketType::Accounting
+        })
+    );
+}
+
+#[test]
+fn invalid_header_reported() {
+    let mut packet = ACCOUNTING_REPLY;
+    packet[1] = 42;
+
+    let mut parser = PacketStreamParser::new();
+    parser.feed(&packet);
+
+    assert_eq!(
+        parser.next_packet(),
+        Err(DeserializeError::InvalidPacketType(42))
+    );
+}
+
+#[test]
+fn empty_body_framed() {
+    // an obfuscated header with no body
+    let mut packet = ACCOUNTING_REPLY;
+    packet[3] = 0;
+    packet[11] = 0;
+
+    let mut parser = PacketStreamParser::new();
+    parser.feed(&packet[..12]);
+
+    let mut packet = parser.next_packet().unwrap().unwrap();
+    packet.deobfuscate(b"key");
+
+    assert_eq!(
+        packet.body(),
+        &AnyBody {
+            packet_type: PacketType::Accounting,
+            data: Vec::new(),
+            obfuscated: false
+        }
+    );
+}
+
+#[test]
+fn iteration_stops_after_error() {
+    let mut invalid = ACCOUNTING_REPLY;
+    invalid[1] = 42;
+
+    let mut parser = PacketStreamParser::new();
+    parser.feed(&[invalid, ACCOUNTING_REPLY].concat());
+
+    assert_eq!(
+        parser.next().map(|packet| packet.map(|_| ())),
+        Some(Err(DeserializeError::InvalidPacketType(42)))
+    );
+    assert!(parser.next().is_none());
+
+    // more bytes don't resume iteration either, since the stream can't be framed reliably anymore
+    parser.feed(&ACCOUNTING_REPLY);
+    assert!(parser.next().is_none());
+}
+
+#[test]
+fn body_over_max_length_rejected_before_buffering() {
+    let mut parser = PacketStreamParser::new().with_max_body_length(6);
+
+    // only the header is needed to tell the body is too long
+    parser.feed(&ACCOUNTING_REPLY[..HeaderInfo::HEADER_SIZE_BYTES]);
+    assert_eq!(
+        parser.next_packet(),
+        Err(DeserializeError::BodyTooLong { length: 7, max: 6 })
+    );
+
+    let mut parser = PacketStreamParser::new().with_max_body_length(7);
+    parser.feed(&ACCOUNTING_REPLY);
+    assert!(parser.next_packet().unwrap().is_some());
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 use crate::accounting::Reply;
-use crate::{MajorVersion, MinorVersion, Version};
+use crate::{MajorVersion, MinorVersion, RawHeader, Version};
 
 #[test]
 fn obfuscated_packet_wrong_unencrypted_flag() {
@@ -58,6 +58,161 @@ fn unobfuscated_packet_wrong_unencrypted_flag() {
     );
 }
 
+#[test]
+fn unknown_header_flags_reported_with_raw_header() {
+    let raw_packet = [
+        0xc << 4, // version (minor v0)
+        3,        // accounting packet
+        4,        // sequence number
+        0x81,     // unencrypted flag, along with an undefined bit
+        // session id
+        0,
+        0,
+        0x12,
+        0x34,
+        // body length (doesn't matter)
+        0,
+        0,
+        0,
+        5,
+    ];
+
+    let deserialize_error = Packet::<Reply>::deserialize_unobfuscated(&raw_packet)
+        .expect_err("packet deserialization should have failed");
+    let DeserializeError::InvalidHeaderFlags(header) = deserialize_error else {
+        panic!("unexpected error: {deserialize_error:?}");
+    };
+
+    assert_eq!(header.flags(), 0x81);
+    assert_eq!(header.unknown_flags(), 0x80);
+    assert_eq!(header.packet_type(), 3);
+    assert_eq!(header.sequence_number(), 4);
+    assert_eq!(header.session_id(), 0x1234);
+    assert_eq!(header.body_length(), 5);
+}
+
+#[test]
+fn raw_header_parsed_leniently() {
+    // neither the version, packet type nor flags are valid here
+    let raw = RawHeader::try_from(&[0xff; 12][..]).unwrap();
+    assert_eq!(raw.version(), 0xff);
+    assert_eq!(raw.packet_type(), 0xff);
+    assert_eq!(raw.unknown_flags(), 0xfa);
+
+    assert_eq!(
+        RawHeader::try_from(&[0; 11][..]),
+        Err(DeserializeError::UnexpectedEnd)
+    );
+}
+
+#[test]
+fn serialize_for_tls_clears_unencrypted_flag() {
+    use crate::authentication::{Continue, ContinueFlags};
+
+    let packet = Packet::new(
+        HeaderInfo::new(
+            Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+            3,
+            PacketFlags::UNENCRYPTED | PacketFlags::SINGLE_CONNECTION,
+            0x01020304,
+        ),
+        Continue::new(None, None, ContinueFlags::ABORT).unwrap(),
+    );
+
+    let mut buffer = [0; 17];
+    let serialized_length = packet
+        .serialize_for_tls(&mut buffer)
+        .expect("packet serialization should succeed");
+
+    assert_eq!(
+        buffer[..serialized_length],
+        [
+            0xc << 4, // version (minor v0)
+            1,        // authentication packet
+            3,        // sequence number
+            4,        // single connection flag (unencrypted flag cleared)
+            // session id
+            1,
+            2,
+            3,
+            4,
+            // body length
+            0,
+            0,
+            0,
+            5,
+            // body (left as cleartext)
+            0,
+            0, // user message length
+            0,
+            0, // data length
+            1, // abort flag
+        ]
+    );
+}
+
+#[test]
+fn encoded_size_matches_serialized_length() {
+    use crate::authentication::{Continue, ContinueFlags};
+
+    let body = Continue::new(Some(b"user message"), Some(b"data"), ContinueFlags::empty()).unwrap();
+    let body_size = body.encoded_size();
+    assert_eq!(body_size, 5 + 12 + 4);
+
+    let packet = Packet::new(
+        HeaderInfo::new(
+            Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+            3,
+            PacketFlags::SINGLE_CONNECTION,
+            0x01020304,
+        ),
+        body,
+    );
+    let encoded_size = packet.encoded_size();
+    assert_eq!(encoded_size, HeaderInfo::HEADER_SIZE_BYTES + body_size);
+
+    // a buffer of exactly the encoded size should suffice
+    let mut buffer = [0; 33];
+    assert_eq!(encoded_size, buffer.len());
+
+    let serialized_length = packet
+        .serialize(b"key", &mut buffer)
+        .expect("packet serialization should succeed");
+    assert_eq!(serialized_length, encoded_size);
+}
+
+#[test]
+fn deserialize_for_tls_ignores_unencrypted_flag() {
+    for flags in [0, 1] {
+        let raw_packet = [
+            0xc << 4, // version (minor v0)
+            3,        // accounting packet
+            2,        // sequence number
+            flags,    // unencrypted flag shouldn't matter
+            // session id
+            0,
+            0,
+            0,
+            1,
+            // body length
+            0,
+            0,
+            0,
+            5,
+            // body
+            0,
+            0, // server message length
+            0,
+            0,    // data length
+            0x01, // status: success
+        ];
+
+        let packet = Packet::<Reply>::deserialize_for_tls(&raw_packet)
+            .expect("packet deserialization should succeed");
+        assert_eq!(*packet.body().status(), crate::accounting::Status::Success);
+    }
+}
+
 #[test]
 fn obfuscate_correct_pad_generated() {
     let header = HeaderInfo::new(
@@ -80,3 +235,126 @@ fn obfuscate_correct_pad_generated() {
         ]
     );
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn serialize_into_vec_appends_packet() {
+    use crate::authentication::{Continue, ContinueFlags};
+    use std::vec::Vec;
+
+    let header = HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        3,
+        PacketFlags::SINGLE_CONNECTION,
+        0x01020304,
+    );
+    let body = Continue::new(None, None, ContinueFlags::ABORT).unwrap();
+
+    let mut expected = [0; 17];
+    Packet::new(header, body.clone())
+        .serialize_for_tls(&mut expected)
+        .unwrap();
+
+    // existing contents of the vector are left in place
+    let mut buffer = Vec::from([0xff; 3]);
+    let length = Packet::new(header, body)
+        .serialize_for_tls_into_vec(&mut buffer)
+        .expect("packet serialization should succeed");
+
+    assert_eq!(length, expected.len());
+    assert_eq!(buffer[..3], [0xff; 3]);
+    assert_eq!(buffer[3..], expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn obfuscated_vec_serialization_matches_buffer_serialization() {
+    use std::vec::Vec;
+
+    let header = header_with_minor_version(MinorVersion::V1);
+
+    let mut expected = [0; 64];
+    let expected_length = Packet::new(header, pap_start())
+        .serialize(b"secret", &mut expected)
+        .unwrap();
+
+    // only the appended body is obfuscated, not what was in the vector beforehand
+    let mut buffer = Vec::from([0xff; 2]);
+    let length = Packet::new(header, pap_start())
+        .serialize_into_vec(b"secret", &mut buffer)
+        .expect("packet serialization should succeed");
+
+    assert_eq!(length, expected_length);
+    assert_eq!(buffer[..2], [0xff; 2]);
+    assert_eq!(buffer[2..], expected[..expected_length]);
+}
+
+fn pap_start() -> crate::authentication::Start<'static> {
+    use crate::authentication::{Action, Start};
+    use crate::{AuthenticationContext, AuthenticationService, AuthenticationType};
+    use crate::{FieldText, PrivilegeLevel, UserInformation};
+
+    Start::new(
+        Action::Login,
+        AuthenticationContext {
+            privilege_level: PrivilegeLevel::new(1).unwrap(),
+            authentication_type: AuthenticationType::Pap,
+            service: AuthenticationService::Login,
+        },
+        UserInformation::new(
+            "someuser",
+            FieldText::assert("tty0"),
+            FieldText::assert("127.0.0.1"),
+        )
+        .unwrap(),
+        None,
+    )
+    .unwrap()
+}
+
+fn header_with_minor_version(minor: MinorVersion) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, minor),
+        1,
+        PacketFlags::UNENCRYPTED,
+        0x01020304,
+    )
+}
+
+#[test]
+fn version_adjustment_reported() {
+    let (packet, adjustment) = Packet::new_adjusted(
+        header_with_minor_version(MinorVersion::Default),
+        pap_start(),
+    );
+
+    assert_eq!(packet.header().version().minor(), MinorVersion::V1);
+    assert_eq!(
+        adjustment,
+        Some(VersionAdjustment {
+            original: Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+            adjusted: Version::new(MajorVersion::RFC8907, MinorVersion::V1),
+        })
+    );
+
+    // nothing is reported if the header already matches
+    let (_, adjustment) =
+        Packet::new_adjusted(header_with_minor_version(MinorVersion::V1), pap_start());
+    assert_eq!(adjustment, None);
+}
+
+#[test]
+fn strict_packet_version_mismatch_rejected() {
+    let header = header_with_minor_version(MinorVersion::Default);
+    assert_eq!(
+        Packet::new_strict(header, pap_start()),
+        Err(VersionAdjustment {
+            original: header.version(),
+            adjusted: Version::new(MajorVersion::RFC8907, MinorVersion::V1),
+        })
+    );
+
+    let header = header_with_minor_version(MinorVersion::V1);
+    let packet = Packet::new_strict(header, pap_start()).unwrap();
+    assert_eq!(*packet.header(), header);
+}
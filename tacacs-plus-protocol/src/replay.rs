@@ -0,0 +1,202 @@
+//! Detection of replayed packets on a connection, for use by servers.
+
+use core::fmt;
+use std::collections::{HashMap, VecDeque};
+
+use crate::HeaderInfo;
+
+#[cfg(test)]
+mod tests;
+
+/// Which client packets a [`ReplayCache`] rejects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReplayPolicy {
+    /// Reject packets whose sequence number isn't higher than that of an earlier packet in the same session, i.e.
+    /// duplicates & replays of earlier packets.
+    #[default]
+    Replays,
+
+    /// Additionally reject packets that skip ahead, so that each session has to start at sequence number 1 and
+    /// continue with the next odd number after each packet.
+    Strict,
+}
+
+/// An error returned from a [`ReplayCache`] when a client packet is rejected.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReplayError {
+    /// A packet with the same or a higher sequence number was already seen in the session.
+    Replayed {
+        /// The id of the session the packet was sent in.
+        session_id: u32,
+
+        /// The sequence number of the packet.
+        sequence_number: u8,
+
+        /// The highest sequence number seen in the session so far.
+        highest_seen: u8,
+    },
+
+    /// The sequence number of the packet didn't follow the previous one in the session, as checked under
+    /// [`ReplayPolicy::Strict`].
+    OutOfSequence {
+        /// The id of the session the packet was sent in.
+        session_id: u32,
+
+        /// The sequence number the packet should have had.
+        expected: u8,
+
+        /// The actual sequence number of the packet.
+        actual: u8,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Replayed {
+                session_id,
+                sequence_number,
+                highest_seen,
+            } => write!(
+                f,
+                "replayed packet in session {session_id:#010x}: sequence number {sequence_number} but {highest_seen} was already seen"
+            ),
+            Self::OutOfSequence {
+                session_id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "out-of-sequence packet in session {session_id:#010x}: expected sequence number {expected}, got {actual}"
+            ),
+        }
+    }
+}
+
+/// Tracks the sequence numbers of recently seen sessions on a connection, so that servers can detect clients
+/// replaying or duplicating packets.
+///
+/// RFC8907 requires sequence numbers to increase by one with each packet in a session, so a client packet whose
+/// sequence number isn't higher than one already seen in its session is either a duplicate or a replay. Only the most
+/// recently started sessions are remembered, up to the capacity of the cache, after which the oldest session is
+/// forgotten; a session id that is reused after being forgotten is treated as a new session.
+///
+/// A cache should be kept per connection, and every client packet checked with [`check()`](Self::check) before its
+/// body is processed.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus_protocol::{HeaderInfo, PacketFlags, ReplayCache, ReplayError, ReplayPolicy};
+/// use tacacs_plus_protocol::{MajorVersion, MinorVersion, Version};
+///
+/// let version = Version::new(MajorVersion::RFC8907, MinorVersion::Default);
+/// let header = |sequence_number| HeaderInfo::new(version, sequence_number, PacketFlags::empty(), 0xdeadbeef);
+///
+/// let mut cache = ReplayCache::new(64, ReplayPolicy::Replays);
+/// assert_eq!(cache.check(&header(1)), Ok(()));
+/// assert_eq!(cache.check(&header(3)), Ok(()));
+///
+/// // the first packet of the session is sent again
+/// assert!(matches!(cache.check(&header(1)), Err(ReplayError::Replayed { .. })));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayCache {
+    policy: ReplayPolicy,
+    capacity: usize,
+
+    /// The highest sequence number seen in each remembered session.
+    sessions: HashMap<u32, u8>,
+
+    /// The remembered sessions, in the order they were first seen.
+    order: VecDeque<u32>,
+}
+
+impl ReplayCache {
+    /// Creates an empty cache that remembers up to `capacity` sessions.
+    ///
+    /// A capacity of zero means no sessions are remembered, so only the first sequence number of a session is checked
+    /// (and only under [`ReplayPolicy::Strict`]).
+    pub fn new(capacity: usize, policy: ReplayPolicy) -> Self {
+        Self {
+            policy,
+            capacity,
+            sessions: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// The maximum number of sessions remembered by this cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of sessions currently remembered.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Whether no sessions are currently remembered.
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Checks the header of a packet received from a client, recording its sequence number if it's accepted.
+    ///
+    /// Rejected packets leave the cache unchanged.
+    pub fn check(&mut self, header: &HeaderInfo) -> Result<(), ReplayError> {
+        let session_id = header.session_id();
+        let sequence_number = header.sequence_number();
+
+        match self.sessions.get_mut(&session_id) {
+            Some(highest_seen) if sequence_number <= *highest_seen => Err(ReplayError::Replayed {
+                session_id,
+                sequence_number,
+                highest_seen: *highest_seen,
+            }),
+            Some(highest_seen) => {
+                // client packets are every other packet of a session, with the server's replies in between
+                let expected = highest_seen.saturating_add(2);
+                if self.policy == ReplayPolicy::Strict && sequence_number != expected {
+                    return Err(ReplayError::OutOfSequence {
+                        session_id,
+                        expected,
+                        actual: sequence_number,
+                    });
+                }
+
+                *highest_seen = sequence_number;
+                Ok(())
+            }
+            None => {
+                if self.policy == ReplayPolicy::Strict && sequence_number != 1 {
+                    return Err(ReplayError::OutOfSequence {
+                        session_id,
+                        expected: 1,
+                        actual: sequence_number,
+                    });
+                }
+
+                self.remember(session_id, sequence_number);
+                Ok(())
+            }
+        }
+    }
+
+    /// Starts tracking a new session, forgetting the oldest one if the cache is full.
+    fn remember(&mut self, session_id: u32, sequence_number: u8) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.sessions.remove(&oldest);
+            }
+        }
+
+        self.order.push_back(session_id);
+        self.sessions.insert(session_id, sequence_number);
+    }
+}
@@ -0,0 +1,94 @@
+use super::*;
+use crate::{MajorVersion, MinorVersion, PacketFlags, Version};
+
+fn header(session_id: u32, sequence_number: u8) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        sequence_number,
+        PacketFlags::empty(),
+        session_id,
+    )
+}
+
+#[test]
+fn duplicate_packet_rejected() {
+    let mut cache = ReplayCache::new(8, ReplayPolicy::Replays);
+    cache.check(&header(1, 1)).unwrap();
+    cache.check(&header(1, 3)).unwrap();
+
+    assert_eq!(
+        cache.check(&header(1, 3)),
+        Err(ReplayError::Replayed {
+            session_id: 1,
+            sequence_number: 3,
+            highest_seen: 3
+        })
+    );
+
+    // the rejected packet didn't affect the session
+    cache.check(&header(1, 5)).unwrap();
+}
+
+#[test]
+fn sessions_tracked_separately() {
+    let mut cache = ReplayCache::new(8, ReplayPolicy::Replays);
+    cache.check(&header(1, 1)).unwrap();
+    cache.check(&header(2, 1)).unwrap();
+    cache.check(&header(1, 3)).unwrap();
+    cache.check(&header(2, 3)).unwrap();
+
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn skipped_sequence_numbers_only_rejected_when_strict() {
+    let mut lenient = ReplayCache::new(8, ReplayPolicy::Replays);
+    lenient.check(&header(1, 3)).unwrap();
+    lenient.check(&header(1, 7)).unwrap();
+
+    let mut strict = ReplayCache::new(8, ReplayPolicy::Strict);
+    assert_eq!(
+        strict.check(&header(1, 3)),
+        Err(ReplayError::OutOfSequence {
+            session_id: 1,
+            expected: 1,
+            actual: 3
+        })
+    );
+    assert!(strict.is_empty());
+
+    strict.check(&header(1, 1)).unwrap();
+    assert_eq!(
+        strict.check(&header(1, 5)),
+        Err(ReplayError::OutOfSequence {
+            session_id: 1,
+            expected: 3,
+            actual: 5
+        })
+    );
+    strict.check(&header(1, 3)).unwrap();
+}
+
+#[test]
+fn oldest_session_forgotten_when_full() {
+    let mut cache = ReplayCache::new(2, ReplayPolicy::Replays);
+    cache.check(&header(1, 1)).unwrap();
+    cache.check(&header(2, 1)).unwrap();
+    cache.check(&header(3, 1)).unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // the first session is no longer remembered, while the second one still is
+    cache.check(&header(1, 1)).unwrap();
+    assert!(matches!(
+        cache.check(&header(3, 1)),
+        Err(ReplayError::Replayed { .. })
+    ));
+}
+
+#[test]
+fn zero_capacity_remembers_nothing() {
+    let mut cache = ReplayCache::new(0, ReplayPolicy::Replays);
+    cache.check(&header(1, 1)).unwrap();
+    cache.check(&header(1, 1)).unwrap();
+    assert!(cache.is_empty());
+}
@@ -5,6 +5,11 @@ use core::fmt;
 mod inner;
 use inner::FieldTextInner;
 
+#[cfg(feature = "std")]
+mod address;
+#[cfg(feature = "std")]
+pub use address::InvalidRemoteAddress;
+
 #[cfg(test)]
 mod tests;
 
@@ -115,6 +120,12 @@ impl<'string> FieldText<'string> {
         self.0.is_empty()
     }
 
+    /// Borrows this [`FieldText`] without copying its underlying data, e.g. to use an owned value somewhere
+    /// a borrowed one is expected.
+    pub fn as_borrowed(&self) -> FieldText<'_> {
+        FieldText(FieldTextInner::Borrowed(&self.0))
+    }
+
     /// Returns `true` if the underlying `&str` contains any of the provided characters, or false otherwise.
     pub fn contains_any(&self, characters: &[char]) -> bool {
         self.0.contains(characters)
@@ -186,7 +197,11 @@ impl<'string> TryFrom<&'string str> for FieldText<'string> {
     type Error = InvalidText<&'string str>;
 
     fn try_from(value: &'string str) -> Result<Self, Self::Error> {
-        Ok(Self(FieldTextInner::Borrowed(value)))
+        if is_printable_ascii(value) {
+            Ok(Self(FieldTextInner::Borrowed(value)))
+        } else {
+            Err(InvalidText(value))
+        }
     }
 }
 
@@ -219,7 +234,11 @@ impl TryFrom<std::string::String> for FieldText<'_> {
     type Error = InvalidText<std::string::String>;
 
     fn try_from(value: std::string::String) -> Result<Self, Self::Error> {
-        Ok(Self(FieldTextInner::Owned(value)))
+        if is_printable_ascii(&value) {
+            Ok(Self(FieldTextInner::Owned(value)))
+        } else {
+            Err(InvalidText(value))
+        }
     }
 }
 
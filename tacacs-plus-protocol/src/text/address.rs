@@ -0,0 +1,104 @@
+//! Validated remote addresses (the `rem_addr` field of packet bodies), along with conversions from socket types.
+
+use core::fmt;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::string::ToString;
+
+use super::{FieldText, FieldTextInner};
+
+/// The maximum length of a hostname, excluding a trailing dot.
+const MAX_HOSTNAME_LENGTH: usize = 253;
+
+/// The maximum length of each label (dot-separated part) of a hostname.
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// The error returned by [`FieldText::remote_address()`] for a string that isn't an IP address or hostname.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidRemoteAddress(());
+
+impl fmt::Display for InvalidRemoteAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "remote address was not an IPv4 address, IPv6 address or hostname"
+        )
+    }
+}
+
+impl<'string> FieldText<'string> {
+    /// Validates a remote address for the `rem_addr` field of a packet body, returning it as is if it's valid.
+    ///
+    /// The following forms are accepted:
+    /// - IPv4 addresses, e.g. `192.0.2.1`
+    /// - IPv6 addresses, with or without brackets, e.g. `2001:db8::1` or `[2001:db8::1]`
+    /// - socket addresses, as formatted by the `From<SocketAddr>` implementation, e.g. `192.0.2.1:49` or
+    ///   `[2001:db8::1]:49`
+    /// - hostnames made up of letters, digits & hyphens (e.g. `client.example.com`), whose last label isn't purely
+    ///   numeric so malformed IPv4 addresses aren't mistaken for hostnames
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus_protocol::FieldText;
+    ///
+    /// assert!(FieldText::remote_address("192.0.2.1").is_ok());
+    /// assert!(FieldText::remote_address("[2001:db8::1]").is_ok());
+    /// assert!(FieldText::remote_address("client.example.com").is_ok());
+    ///
+    /// assert!(FieldText::remote_address("192.0.2.256").is_err());
+    /// assert!(FieldText::remote_address("not a hostname").is_err());
+    /// ```
+    pub fn remote_address(address: &'string str) -> Result<Self, InvalidRemoteAddress> {
+        let unbracketed = address
+            .strip_prefix('[')
+            .and_then(|address| address.strip_suffix(']'));
+
+        let is_ip_address = match unbracketed {
+            Some(inner) => inner.parse::<Ipv6Addr>().is_ok(),
+            None => address.parse::<IpAddr>().is_ok() || address.parse::<SocketAddr>().is_ok(),
+        };
+
+        if is_ip_address || is_hostname(address) {
+            Ok(Self(FieldTextInner::Borrowed(address)))
+        } else {
+            Err(InvalidRemoteAddress(()))
+        }
+    }
+}
+
+/// Checks whether a string is a valid hostname per RFC 1123, allowing a single trailing dot.
+fn is_hostname(hostname: &str) -> bool {
+    let hostname = hostname.strip_suffix('.').unwrap_or(hostname);
+    if hostname.is_empty() || hostname.len() > MAX_HOSTNAME_LENGTH {
+        return false;
+    }
+
+    let labels_valid = hostname.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= MAX_LABEL_LENGTH
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+
+    // SAFETY: split always yields at least one item
+    let last_label = hostname.rsplit('.').next().unwrap();
+
+    labels_valid && !last_label.chars().all(|c| c.is_ascii_digit())
+}
+
+impl From<IpAddr> for FieldText<'static> {
+    /// Formats an IP address for the `rem_addr` field, without brackets around IPv6 addresses.
+    fn from(address: IpAddr) -> Self {
+        Self(FieldTextInner::Owned(address.to_string()))
+    }
+}
+
+impl From<SocketAddr> for FieldText<'static> {
+    /// Formats a socket address for the `rem_addr` field, e.g. `192.0.2.1:49` or `[2001:db8::1]:49`.
+    ///
+    /// To leave out the port, convert the [`ip()`](SocketAddr::ip) of the address instead.
+    fn from(address: SocketAddr) -> Self {
+        Self(FieldTextInner::Owned(address.to_string()))
+    }
+}
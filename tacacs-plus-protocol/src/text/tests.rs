@@ -17,3 +17,76 @@ fn text_partialeq_str_impl() {
     assert_eq!(string, text);
     assert_eq!(text, string);
 }
+
+#[test]
+#[cfg(feature = "std")]
+fn owned_text_borrowed_without_copying() {
+    let owned = FieldText::try_from(std::string::String::from("string")).unwrap();
+    let borrowed = owned.as_borrowed();
+
+    assert_eq!(borrowed, owned);
+    assert_eq!(borrowed.as_ref().as_ptr(), owned.as_ref().as_ptr());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn remote_addresses_validated() {
+    let valid = [
+        "192.0.2.1",
+        "2001:db8::1",
+        "[2001:db8::1]",
+        "::ffff:192.0.2.1",
+        "192.0.2.1:49",
+        "[2001:db8::1]:49",
+        "localhost",
+        "client-1.example.com",
+        "client.example.com.",
+    ];
+    for address in valid {
+        assert_eq!(
+            FieldText::remote_address(address).map(|text| text.as_ref() == address),
+            Ok(true),
+            "{address} should be valid"
+        );
+    }
+
+    let invalid = [
+        "",
+        "192.0.2.256",
+        "[192.0.2.1]",
+        "2001:db8::1]",
+        "-client.example.com",
+        "client..example.com",
+        "client_1.example.com",
+        "not a hostname",
+    ];
+    for address in invalid {
+        assert!(
+            FieldText::remote_address(address).is_err(),
+            "{address} should be invalid"
+        );
+    }
+
+    let long_label = "a".repeat(64);
+    assert!(FieldText::remote_address(&long_label).is_err());
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn socket_addresses_converted() {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+    let ipv6 = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1));
+    assert_eq!(FieldText::from(ipv6), "2001:db8::1");
+    assert_eq!(
+        FieldText::from(SocketAddr::new(ipv6, 49)),
+        "[2001:db8::1]:49"
+    );
+
+    let ipv4 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+    assert_eq!(FieldText::from(SocketAddr::new(ipv4, 49)), "192.0.2.1:49");
+
+    // converted addresses are always accepted as remote addresses
+    let converted = FieldText::from(SocketAddr::new(ipv6, 49));
+    assert!(FieldText::remote_address(converted.as_ref()).is_ok());
+}
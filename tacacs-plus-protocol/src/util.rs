@@ -1,3 +1,9 @@
+/// Number of bytes occupied by a single-byte length or count field, e.g. an argument length.
+pub(crate) const U8_LENGTH_WIRE_SIZE: usize = 1;
+
+/// Number of bytes occupied by a two-byte length field, e.g. the length of a server message.
+pub(crate) const U16_LENGTH_WIRE_SIZE: usize = 2;
+
 /// Generates a display implementation for a bitflag struct that uses flag names.
 macro_rules! bitflags_display_impl {
     ($flag_struct:ty) => {
@@ -0,0 +1,212 @@
+//! Raw wire values of the protocol's enumerated fields & flags, named as in [RFC8907].
+//!
+//! The typed representations elsewhere in this crate (e.g. [`authentication::Status`](crate::authentication::Status) or
+//! [`PacketFlags`](crate::PacketFlags)) should generally be preferred; these constants are meant for code that handles raw
+//! bytes directly, such as test harnesses building malformed packets or tools dissecting captures.
+//!
+//! [RFC8907]: https://www.rfc-editor.org/rfc/rfc8907.html
+
+#[cfg(test)]
+mod tests;
+
+// Header (section 4.1)
+
+/// The major protocol version, which makes up the high nibble of the version byte.
+pub const TAC_PLUS_MAJOR_VER: u8 = 0xc;
+
+/// The default minor protocol version, which makes up the low nibble of the version byte.
+pub const TAC_PLUS_MINOR_VER_DEFAULT: u8 = 0x0;
+
+/// Minor protocol version 1, used by some authentication types.
+pub const TAC_PLUS_MINOR_VER_ONE: u8 = 0x1;
+
+/// The packet type of authentication packets.
+pub const TAC_PLUS_AUTHEN: u8 = 0x01;
+
+/// The packet type of authorization packets.
+pub const TAC_PLUS_AUTHOR: u8 = 0x02;
+
+/// The packet type of accounting packets.
+pub const TAC_PLUS_ACCT: u8 = 0x03;
+
+/// Header flag indicating that the packet body is not obfuscated.
+pub const TAC_PLUS_UNENCRYPTED_FLAG: u8 = 0x01;
+
+/// Header flag indicating that the sender supports single connection mode.
+pub const TAC_PLUS_SINGLE_CONNECT_FLAG: u8 = 0x04;
+
+// Authentication start (section 5.1)
+
+/// Authentication action: login.
+pub const TAC_PLUS_AUTHEN_LOGIN: u8 = 0x01;
+
+/// Authentication action: change password.
+pub const TAC_PLUS_AUTHEN_CHPASS: u8 = 0x02;
+
+/// Authentication action: send authentication information to the server.
+pub const TAC_PLUS_AUTHEN_SENDAUTH: u8 = 0x04;
+
+/// Minimum privilege level.
+pub const TAC_PLUS_PRIV_LVL_MIN: u8 = 0x00;
+
+/// Privilege level of a regular user.
+pub const TAC_PLUS_PRIV_LVL_USER: u8 = 0x01;
+
+/// Privilege level of the root user.
+pub const TAC_PLUS_PRIV_LVL_ROOT: u8 = 0x0f;
+
+/// Maximum privilege level.
+pub const TAC_PLUS_PRIV_LVL_MAX: u8 = 0x0f;
+
+/// Authentication type: not set (only valid outside of authentication).
+pub const TAC_PLUS_AUTHEN_TYPE_NOT_SET: u8 = 0x00;
+
+/// Authentication type: ASCII.
+pub const TAC_PLUS_AUTHEN_TYPE_ASCII: u8 = 0x01;
+
+/// Authentication type: PAP.
+pub const TAC_PLUS_AUTHEN_TYPE_PAP: u8 = 0x02;
+
+/// Authentication type: CHAP.
+pub const TAC_PLUS_AUTHEN_TYPE_CHAP: u8 = 0x03;
+
+/// Authentication type: MS-CHAP v1.
+pub const TAC_PLUS_AUTHEN_TYPE_MSCHAP: u8 = 0x05;
+
+/// Authentication type: MS-CHAP v2.
+pub const TAC_PLUS_AUTHEN_TYPE_MSCHAPV2: u8 = 0x06;
+
+/// Authentication service: none.
+pub const TAC_PLUS_AUTHEN_SVC_NONE: u8 = 0x00;
+
+/// Authentication service: login.
+pub const TAC_PLUS_AUTHEN_SVC_LOGIN: u8 = 0x01;
+
+/// Authentication service: enable (privilege escalation).
+pub const TAC_PLUS_AUTHEN_SVC_ENABLE: u8 = 0x02;
+
+/// Authentication service: PPP.
+pub const TAC_PLUS_AUTHEN_SVC_PPP: u8 = 0x03;
+
+/// Authentication service: PT.
+pub const TAC_PLUS_AUTHEN_SVC_PT: u8 = 0x05;
+
+/// Authentication service: R-command.
+pub const TAC_PLUS_AUTHEN_SVC_RCMD: u8 = 0x06;
+
+/// Authentication service: X.25.
+pub const TAC_PLUS_AUTHEN_SVC_X25: u8 = 0x07;
+
+/// Authentication service: NASI.
+pub const TAC_PLUS_AUTHEN_SVC_NASI: u8 = 0x08;
+
+/// Authentication service: firewall proxy.
+pub const TAC_PLUS_AUTHEN_SVC_FWPROXY: u8 = 0x09;
+
+// Authentication reply (section 5.2)
+
+/// Authentication status: authentication succeeded.
+pub const TAC_PLUS_AUTHEN_STATUS_PASS: u8 = 0x01;
+
+/// Authentication status: authentication failed.
+pub const TAC_PLUS_AUTHEN_STATUS_FAIL: u8 = 0x02;
+
+/// Authentication status: the server requests arbitrary data from the user.
+pub const TAC_PLUS_AUTHEN_STATUS_GETDATA: u8 = 0x03;
+
+/// Authentication status: the server requests a username.
+pub const TAC_PLUS_AUTHEN_STATUS_GETUSER: u8 = 0x04;
+
+/// Authentication status: the server requests a password.
+pub const TAC_PLUS_AUTHEN_STATUS_GETPASS: u8 = 0x05;
+
+/// Authentication status: authentication should be restarted with a different type.
+pub const TAC_PLUS_AUTHEN_STATUS_RESTART: u8 = 0x06;
+
+/// Authentication status: an error occurred on the server.
+pub const TAC_PLUS_AUTHEN_STATUS_ERROR: u8 = 0x07;
+
+/// Authentication status: forward to an alternative daemon (deprecated).
+pub const TAC_PLUS_AUTHEN_STATUS_FOLLOW: u8 = 0x21;
+
+/// Authentication reply flag indicating that user input should not be echoed.
+pub const TAC_PLUS_REPLY_FLAG_NOECHO: u8 = 0x01;
+
+// Authentication continue (section 5.3)
+
+/// Authentication continue flag indicating that the client is aborting the session.
+pub const TAC_PLUS_CONTINUE_FLAG_ABORT: u8 = 0x01;
+
+// Authorization request (section 6.1)
+
+/// Authentication method: not set.
+pub const TAC_PLUS_AUTHEN_METH_NOT_SET: u8 = 0x00;
+
+/// Authentication method: none.
+pub const TAC_PLUS_AUTHEN_METH_NONE: u8 = 0x01;
+
+/// Authentication method: Kerberos 5.
+pub const TAC_PLUS_AUTHEN_METH_KRB5: u8 = 0x02;
+
+/// Authentication method: line password.
+pub const TAC_PLUS_AUTHEN_METH_LINE: u8 = 0x03;
+
+/// Authentication method: enable password.
+pub const TAC_PLUS_AUTHEN_METH_ENABLE: u8 = 0x04;
+
+/// Authentication method: local user database.
+pub const TAC_PLUS_AUTHEN_METH_LOCAL: u8 = 0x05;
+
+/// Authentication method: TACACS+.
+pub const TAC_PLUS_AUTHEN_METH_TACACSPLUS: u8 = 0x06;
+
+/// Authentication method: guest.
+pub const TAC_PLUS_AUTHEN_METH_GUEST: u8 = 0x08;
+
+/// Authentication method: RADIUS.
+pub const TAC_PLUS_AUTHEN_METH_RADIUS: u8 = 0x10;
+
+/// Authentication method: Kerberos 4.
+pub const TAC_PLUS_AUTHEN_METH_KRB4: u8 = 0x11;
+
+/// Authentication method: R-command.
+pub const TAC_PLUS_AUTHEN_METH_RCMD: u8 = 0x20;
+
+// Authorization reply (section 6.2)
+
+/// Authorization status: authorized, with arguments added to the request.
+pub const TAC_PLUS_AUTHOR_STATUS_PASS_ADD: u8 = 0x01;
+
+/// Authorization status: authorized, with the request arguments replaced.
+pub const TAC_PLUS_AUTHOR_STATUS_PASS_REPL: u8 = 0x02;
+
+/// Authorization status: authorization denied.
+pub const TAC_PLUS_AUTHOR_STATUS_FAIL: u8 = 0x10;
+
+/// Authorization status: an error occurred on the server.
+pub const TAC_PLUS_AUTHOR_STATUS_ERROR: u8 = 0x11;
+
+/// Authorization status: forward to an alternative daemon (deprecated).
+pub const TAC_PLUS_AUTHOR_STATUS_FOLLOW: u8 = 0x21;
+
+// Accounting request (section 7.1)
+
+/// Accounting flag indicating the start of a task.
+pub const TAC_PLUS_ACCT_FLAG_START: u8 = 0x02;
+
+/// Accounting flag indicating the end of a task.
+pub const TAC_PLUS_ACCT_FLAG_STOP: u8 = 0x04;
+
+/// Accounting flag indicating an update for an ongoing task.
+pub const TAC_PLUS_ACCT_FLAG_WATCHDOG: u8 = 0x08;
+
+// Accounting reply (section 7.2)
+
+/// Accounting status: the record was accepted.
+pub const TAC_PLUS_ACCT_STATUS_SUCCESS: u8 = 0x01;
+
+/// Accounting status: an error occurred on the server.
+pub const TAC_PLUS_ACCT_STATUS_ERROR: u8 = 0x02;
+
+/// Accounting status: forward to an alternative daemon (deprecated).
+pub const TAC_PLUS_ACCT_STATUS_FOLLOW: u8 = 0x21;
@@ -0,0 +1,140 @@
+#![allow(deprecated)]
+
+use super::*;
+use crate::accounting;
+use crate::authentication::{self, Action, ContinueFlags, ReplyFlags};
+use crate::authorization;
+use crate::{AuthenticationMethod, AuthenticationService, AuthenticationType};
+use crate::{MajorVersion, MinorVersion, PacketFlags, PacketType};
+
+#[test]
+fn header_values_match_types() {
+    assert_eq!(MajorVersion::RFC8907 as u8, TAC_PLUS_MAJOR_VER);
+    assert_eq!(MinorVersion::Default as u8, TAC_PLUS_MINOR_VER_DEFAULT);
+    assert_eq!(MinorVersion::V1 as u8, TAC_PLUS_MINOR_VER_ONE);
+
+    assert_eq!(PacketType::Authentication as u8, TAC_PLUS_AUTHEN);
+    assert_eq!(PacketType::Authorization as u8, TAC_PLUS_AUTHOR);
+    assert_eq!(PacketType::Accounting as u8, TAC_PLUS_ACCT);
+
+    assert_eq!(PacketFlags::UNENCRYPTED.bits(), TAC_PLUS_UNENCRYPTED_FLAG);
+    assert_eq!(
+        PacketFlags::SINGLE_CONNECTION.bits(),
+        TAC_PLUS_SINGLE_CONNECT_FLAG
+    );
+}
+
+#[test]
+fn authentication_values_match_types() {
+    assert_eq!(Action::Login as u8, TAC_PLUS_AUTHEN_LOGIN);
+    assert_eq!(Action::ChangePassword as u8, TAC_PLUS_AUTHEN_CHPASS);
+    assert_eq!(Action::SendAuth as u8, TAC_PLUS_AUTHEN_SENDAUTH);
+
+    assert!(crate::PrivilegeLevel::new(TAC_PLUS_PRIV_LVL_MAX).is_some());
+    assert!(crate::PrivilegeLevel::new(TAC_PLUS_PRIV_LVL_MAX + 1).is_none());
+
+    for (authentication_type, value) in [
+        (AuthenticationType::NotSet, TAC_PLUS_AUTHEN_TYPE_NOT_SET),
+        (AuthenticationType::Ascii, TAC_PLUS_AUTHEN_TYPE_ASCII),
+        (AuthenticationType::Pap, TAC_PLUS_AUTHEN_TYPE_PAP),
+        (AuthenticationType::Chap, TAC_PLUS_AUTHEN_TYPE_CHAP),
+        (AuthenticationType::MsChap, TAC_PLUS_AUTHEN_TYPE_MSCHAP),
+        (AuthenticationType::MsChapV2, TAC_PLUS_AUTHEN_TYPE_MSCHAPV2),
+    ] {
+        assert_eq!(authentication_type as u8, value);
+    }
+
+    for (service, value) in [
+        (AuthenticationService::None, TAC_PLUS_AUTHEN_SVC_NONE),
+        (AuthenticationService::Login, TAC_PLUS_AUTHEN_SVC_LOGIN),
+        (AuthenticationService::Enable, TAC_PLUS_AUTHEN_SVC_ENABLE),
+        (AuthenticationService::Ppp, TAC_PLUS_AUTHEN_SVC_PPP),
+        (AuthenticationService::Pt, TAC_PLUS_AUTHEN_SVC_PT),
+        (AuthenticationService::RCommand, TAC_PLUS_AUTHEN_SVC_RCMD),
+        (AuthenticationService::X25, TAC_PLUS_AUTHEN_SVC_X25),
+        (AuthenticationService::Nasi, TAC_PLUS_AUTHEN_SVC_NASI),
+        (AuthenticationService::FwProxy, TAC_PLUS_AUTHEN_SVC_FWPROXY),
+    ] {
+        assert_eq!(service as u8, value);
+    }
+
+    for (status, value) in [
+        (authentication::Status::Pass, TAC_PLUS_AUTHEN_STATUS_PASS),
+        (authentication::Status::Fail, TAC_PLUS_AUTHEN_STATUS_FAIL),
+        (
+            authentication::Status::GetData,
+            TAC_PLUS_AUTHEN_STATUS_GETDATA,
+        ),
+        (
+            authentication::Status::GetUser,
+            TAC_PLUS_AUTHEN_STATUS_GETUSER,
+        ),
+        (
+            authentication::Status::GetPassword,
+            TAC_PLUS_AUTHEN_STATUS_GETPASS,
+        ),
+        (
+            authentication::Status::Restart,
+            TAC_PLUS_AUTHEN_STATUS_RESTART,
+        ),
+        (authentication::Status::Error, TAC_PLUS_AUTHEN_STATUS_ERROR),
+        (
+            authentication::Status::Follow,
+            TAC_PLUS_AUTHEN_STATUS_FOLLOW,
+        ),
+    ] {
+        assert_eq!(u8::from(status), value);
+    }
+
+    assert_eq!(ReplyFlags::NO_ECHO.bits(), TAC_PLUS_REPLY_FLAG_NOECHO);
+    assert_eq!(ContinueFlags::ABORT.bits(), TAC_PLUS_CONTINUE_FLAG_ABORT);
+}
+
+#[test]
+fn authorization_values_match_types() {
+    for (method, value) in [
+        (AuthenticationMethod::NotSet, TAC_PLUS_AUTHEN_METH_NOT_SET),
+        (AuthenticationMethod::None, TAC_PLUS_AUTHEN_METH_NONE),
+        (AuthenticationMethod::Kerberos5, TAC_PLUS_AUTHEN_METH_KRB5),
+        (AuthenticationMethod::Line, TAC_PLUS_AUTHEN_METH_LINE),
+        (AuthenticationMethod::Enable, TAC_PLUS_AUTHEN_METH_ENABLE),
+        (AuthenticationMethod::Local, TAC_PLUS_AUTHEN_METH_LOCAL),
+        (
+            AuthenticationMethod::TacacsPlus,
+            TAC_PLUS_AUTHEN_METH_TACACSPLUS,
+        ),
+        (AuthenticationMethod::Guest, TAC_PLUS_AUTHEN_METH_GUEST),
+        (AuthenticationMethod::Radius, TAC_PLUS_AUTHEN_METH_RADIUS),
+        (AuthenticationMethod::Kerberos4, TAC_PLUS_AUTHEN_METH_KRB4),
+        (AuthenticationMethod::RCommand, TAC_PLUS_AUTHEN_METH_RCMD),
+    ] {
+        assert_eq!(method as u8, value);
+    }
+
+    for (status, value) in [
+        (
+            authorization::Status::PassAdd,
+            TAC_PLUS_AUTHOR_STATUS_PASS_ADD,
+        ),
+        (
+            authorization::Status::PassReplace,
+            TAC_PLUS_AUTHOR_STATUS_PASS_REPL,
+        ),
+        (authorization::Status::Fail, TAC_PLUS_AUTHOR_STATUS_FAIL),
+        (authorization::Status::Error, TAC_PLUS_AUTHOR_STATUS_ERROR),
+        (authorization::Status::Follow, TAC_PLUS_AUTHOR_STATUS_FOLLOW),
+    ] {
+        assert_eq!(u8::from(status), value);
+    }
+}
+
+#[test]
+fn accounting_statuses_match_types() {
+    for (status, value) in [
+        (accounting::Status::Success, TAC_PLUS_ACCT_STATUS_SUCCESS),
+        (accounting::Status::Error, TAC_PLUS_ACCT_STATUS_ERROR),
+        (accounting::Status::Follow, TAC_PLUS_ACCT_STATUS_FOLLOW),
+    ] {
+        assert_eq!(u8::from(status), value);
+    }
+}
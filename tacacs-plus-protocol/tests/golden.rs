@@ -0,0 +1,469 @@
+//! Golden tests against packets captured from exchanges with a real TACACS+ server.
+//!
+//! The fixtures in `tests/golden/` were recorded between the `tacacs-plus` client (or, for the ASCII login, a small
+//! script) and the Shrubbery `tac_plus` daemon (F4.0.4.28, as bundled in `test-assets/`) running the configuration
+//! written to `/srv/tac_plus/tac_plus.conf` by the `tacacs-shrubbery-configured` stage of `test-assets/Dockerfile`.
+//! Each fixture holds the packets of one session in order, one per line, prefixed with the side that sent it.
+//!
+//! Server packets are checked to parse into the expected fields, and client packets are rebuilt from their expected fields
+//! and checked to serialize to exactly the captured bytes. Authorization replies are additionally re-serialized, since
+//! they can be sent as well with the `std` feature.
+
+use tacacs_plus_protocol::authentication::{self, Action, PacketData, ReplyFlags};
+use tacacs_plus_protocol::{accounting, authorization};
+use tacacs_plus_protocol::{
+    Argument, Arguments, AuthenticationContext, AuthenticationMethod, AuthenticationService,
+    AuthenticationType, DeserializeError, FieldText, HeaderInfo, MajorVersion, MinorVersion,
+    Packet, PacketFlags, PrivilegeLevel, UserInformation, Version,
+};
+
+/// The shared secret in the `tac_plus` configuration from `test-assets/Dockerfile`.
+const KEY: &[u8] = b"very secure key that is super secret";
+
+/// The packets of a captured session, split by sender.
+struct Fixture {
+    client: Vec<Vec<u8>>,
+    server: Vec<Vec<u8>>,
+}
+
+impl Fixture {
+    fn parse(contents: &str) -> Self {
+        let mut fixture = Self {
+            client: Vec::new(),
+            server: Vec::new(),
+        };
+
+        for line in contents.lines().filter(|line| !line.starts_with('#')) {
+            let (sender, hex) = line
+                .split_once(' ')
+                .expect("fixture lines should have a sender & packet");
+            let packet = decode_hex(hex);
+
+            match sender {
+                "client" => fixture.client.push(packet),
+                "server" => fixture.server.push(packet),
+                _ => panic!("unknown packet sender {sender}"),
+            }
+        }
+
+        fixture
+    }
+}
+
+macro_rules! fixture {
+    ($name:literal) => {
+        Fixture::parse(include_str!(concat!("golden/", $name, ".hex")))
+    };
+}
+
+/// Serializes a packet with [`KEY`] and checks that it matches the captured bytes exactly.
+macro_rules! assert_serializes_to {
+    ($packet:expr, $expected:expr) => {{
+        let packet = $packet;
+        let mut buffer = vec![0; packet.encoded_size()];
+        let length = packet
+            .serialize(KEY, &mut buffer)
+            .expect("packet should have been serialized");
+        assert_eq!(buffer[..length], $expected[..]);
+    }};
+}
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    assert!(hex.len() % 2 == 0, "hex should have an even length");
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).expect("hex should be valid"))
+        .collect()
+}
+
+/// Builds a header with the default version, which is updated by [`Packet::new()`] as needed by the body.
+fn header(sequence_number: u8, flags: PacketFlags, session_id: u32) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        sequence_number,
+        flags,
+        session_id,
+    )
+}
+
+fn text(text: &str) -> FieldText<'_> {
+    FieldText::try_from(text).expect("text should be printable ASCII")
+}
+
+fn argument<'data>(name: &'data str, value: &'data str, mandatory: bool) -> Argument<'data> {
+    Argument::new(text(name), text(value), mandatory).expect("argument should be valid")
+}
+
+/// The user information sent by the `tacacs-plus` client with its default context.
+fn client_user_information(user: &str) -> UserInformation<'_> {
+    UserInformation::new(user, text("rust_client"), text("tacacs_plus_rs"))
+        .expect("user information should be valid")
+}
+
+fn context(
+    privilege_level: u8,
+    authentication_type: AuthenticationType,
+    service: AuthenticationService,
+) -> AuthenticationContext {
+    AuthenticationContext {
+        privilege_level: PrivilegeLevel::new(privilege_level)
+            .expect("privilege level should be valid"),
+        authentication_type,
+        service,
+    }
+}
+
+fn chap_start<'packet>(user: &'packet str, data: &'packet [u8]) -> authentication::Start<'packet> {
+    authentication::Start::new(
+        Action::Login,
+        context(0, AuthenticationType::Chap, AuthenticationService::Login),
+        client_user_information(user),
+        Some(PacketData::try_from(data).expect("CHAP data should fit in a packet")),
+    )
+    .expect("start should be valid")
+}
+
+fn assert_authentication_reply(
+    packet: &mut [u8],
+    status: authentication::Status,
+    flags: ReplyFlags,
+    server_message: &str,
+) {
+    let reply = Packet::<authentication::Reply>::deserialize(KEY, packet)
+        .expect("reply should have been parsed");
+
+    assert_eq!(reply.header().sequence_number(), 2);
+    assert_eq!(*reply.body().status(), status);
+    assert_eq!(*reply.body().flags(), flags);
+    assert_eq!(*reply.body().server_message(), server_message);
+    assert_eq!(reply.body().data(), b"");
+
+    // the body was deobfuscated in place, so its fields can be found through the public offsets too
+    let body = &packet[HeaderInfo::HEADER_SIZE_BYTES..];
+    assert_eq!(body[authentication::Reply::STATUS_OFFSET], u8::from(status));
+    assert_eq!(
+        body[authentication::Reply::SERVER_MESSAGE_LENGTH_OFFSET..][..2],
+        u16::try_from(server_message.len()).unwrap().to_be_bytes()
+    );
+}
+
+/// Checks an authorization reply's fields, and that it's re-serialized exactly as received.
+fn assert_authorization_reply(packet: &[u8], status: authorization::Status, arguments: &[&str]) {
+    let mut buffer = packet.to_vec();
+    let reply = Packet::<authorization::Reply>::deserialize(KEY, &mut buffer)
+        .expect("reply should have been parsed");
+
+    assert_eq!(*reply.body().status(), status);
+    assert_eq!(*reply.body().server_message(), "");
+    assert_eq!(*reply.body().data(), "");
+
+    let parsed_arguments: Vec<_> = reply
+        .body()
+        .iter_arguments()
+        .map(|argument| argument.to_string())
+        .collect();
+    assert_eq!(parsed_arguments, arguments);
+
+    #[cfg(feature = "std")]
+    {
+        let mut reserialized = Vec::new();
+        reply
+            .to_owned::<authorization::ReplyOwned>()
+            .serialize_into_vec(KEY, &mut reserialized)
+            .expect("owned reply should have been serialized");
+        assert_eq!(reserialized, packet);
+    }
+}
+
+#[test]
+fn pap_login_pass() {
+    let mut fixture = fixture!("pap-login-pass");
+
+    let start = authentication::Start::new(
+        Action::Login,
+        context(0, AuthenticationType::Pap, AuthenticationService::Login),
+        client_user_information("someuser"),
+        Some(PacketData::try_from(b"hunter2".as_slice()).expect("password should fit")),
+    )
+    .expect("start should be valid");
+    assert_serializes_to!(
+        Packet::new(header(1, PacketFlags::SINGLE_CONNECTION, 0x6e436dfa), start),
+        fixture.client[0]
+    );
+
+    assert_authentication_reply(
+        &mut fixture.server[0],
+        authentication::Status::Pass,
+        ReplyFlags::empty(),
+        "",
+    );
+}
+
+#[test]
+fn chap_login_pass() {
+    let mut fixture = fixture!("chap-login-pass");
+
+    // PPP id, challenge & MD5 response
+    let data = decode_hex("9a087a29df5e6ade46550bdc9495db3be54428fdcc929263fe3e81244f208908fb");
+    assert_serializes_to!(
+        Packet::new(
+            header(1, PacketFlags::SINGLE_CONNECTION, 0xd189534f),
+            chap_start("someuser", &data)
+        ),
+        fixture.client[0]
+    );
+
+    assert_authentication_reply(
+        &mut fixture.server[0],
+        authentication::Status::Pass,
+        ReplyFlags::empty(),
+        "",
+    );
+}
+
+#[test]
+fn chap_login_fail() {
+    let mut fixture = fixture!("chap-login-fail");
+
+    let data = decode_hex("b653634449632fbba414c15b796b4c035d8f050d632e8340cf75a12b32923d9030");
+    assert_serializes_to!(
+        Packet::new(
+            header(1, PacketFlags::SINGLE_CONNECTION, 0xaa890103),
+            chap_start("paponly", &data)
+        ),
+        fixture.client[0]
+    );
+
+    assert_authentication_reply(
+        &mut fixture.server[0],
+        authentication::Status::Fail,
+        ReplyFlags::empty(),
+        "",
+    );
+}
+
+#[test]
+fn ascii_login_fail() {
+    let mut fixture = fixture!("ascii-login-fail");
+    let session_id = 0x5eed1234;
+
+    let start = authentication::Start::new(
+        Action::Login,
+        context(1, AuthenticationType::Ascii, AuthenticationService::Login),
+        UserInformation::new("", text("tty0"), text("192.0.2.10"))
+            .expect("user information should be valid"),
+        None,
+    )
+    .expect("start should be valid");
+    assert_serializes_to!(
+        Packet::new(header(1, PacketFlags::empty(), session_id), start),
+        fixture.client[0]
+    );
+
+    // the daemon's default username prompt contains newlines, which aren't printable ASCII & thus rejected
+    assert!(matches!(
+        Packet::<authentication::Reply>::deserialize(KEY, &mut fixture.server[0]),
+        Err(DeserializeError::BadText)
+    ));
+
+    let username = authentication::Continue::new(
+        Some(b"paponly"),
+        None,
+        authentication::ContinueFlags::empty(),
+    )
+    .expect("continue should be valid");
+    assert_serializes_to!(
+        Packet::new(header(3, PacketFlags::empty(), session_id), username),
+        fixture.client[1]
+    );
+
+    let reply = Packet::<authentication::Reply>::deserialize(KEY, &mut fixture.server[1])
+        .expect("password prompt should have been parsed");
+    assert_eq!(reply.header().sequence_number(), 4);
+    assert_eq!(*reply.body().status(), authentication::Status::GetPassword);
+    assert_eq!(*reply.body().flags(), ReplyFlags::NO_ECHO);
+    assert_eq!(*reply.body().server_message(), "Password: ");
+
+    let password = authentication::Continue::new(
+        Some(b"pass-word"),
+        None,
+        authentication::ContinueFlags::empty(),
+    )
+    .expect("continue should be valid");
+    assert_serializes_to!(
+        Packet::new(header(5, PacketFlags::empty(), session_id), password),
+        fixture.client[2]
+    );
+
+    let reply = Packet::<authentication::Reply>::deserialize(KEY, &mut fixture.server[2])
+        .expect("final reply should have been parsed");
+    assert_eq!(reply.header().sequence_number(), 6);
+    assert_eq!(*reply.body().status(), authentication::Status::Fail);
+}
+
+#[test]
+fn unobfuscated_start_error() {
+    let mut fixture = fixture!("unobfuscated-start-error");
+
+    let data = decode_hex("e0c46593eeffb030873ac198e818262403ef8c8caaab7e318ec68823c282f330b6");
+    let packet = Packet::new(
+        header(1, PacketFlags::SINGLE_CONNECTION, 0xe3957404),
+        chap_start("someuser", &data),
+    );
+    let mut buffer = vec![0; packet.encoded_size()];
+    let length = packet
+        .serialize_unobfuscated(&mut buffer)
+        .expect("packet should have been serialized");
+    assert_eq!(buffer[..length], fixture.client[0]);
+
+    // the server still obfuscates its reply, and doesn't echo the UNENCRYPTED flag
+    assert_authentication_reply(
+        &mut fixture.server[0],
+        authentication::Status::Error,
+        ReplyFlags::empty(),
+        "127.0.0.1 : Invalid AUTHEN/START packet (check keys)",
+    );
+}
+
+#[test]
+fn authorization_fail() {
+    let fixture = fixture!("authorization-fail");
+
+    let arguments = [
+        argument("service", "authorizeme", true),
+        argument("number", "3", true),
+    ];
+    let request = authorization::Request::new(
+        AuthenticationMethod::NotSet,
+        context(0, AuthenticationType::NotSet, AuthenticationService::Login),
+        client_user_information("someuser"),
+        Arguments::new(&arguments).expect("arguments should be valid"),
+    );
+    assert_serializes_to!(
+        Packet::new(
+            header(1, PacketFlags::SINGLE_CONNECTION, 0x2bcd7735),
+            request
+        ),
+        fixture.client[0]
+    );
+
+    assert_authorization_reply(&fixture.server[0], authorization::Status::Fail, &[]);
+}
+
+#[test]
+fn authorization_pass_add() {
+    let fixture = fixture!("authorization-pass-add");
+
+    let arguments = [argument("service", "guest", true)];
+    let request = authorization::Request::new(
+        AuthenticationMethod::Guest,
+        context(0, AuthenticationType::NotSet, AuthenticationService::Login),
+        client_user_information(""),
+        Arguments::new(&arguments).expect("arguments should be valid"),
+    );
+    assert_serializes_to!(
+        Packet::new(
+            header(1, PacketFlags::SINGLE_CONNECTION, 0x389d46f6),
+            request
+        ),
+        fixture.client[0]
+    );
+
+    assert_authorization_reply(
+        &fixture.server[0],
+        authorization::Status::PassAdd,
+        &["priv-lvl=0", "authenticated=false"],
+    );
+}
+
+#[test]
+fn authorization_pass_replace() {
+    let fixture = fixture!("authorization-pass-replace");
+
+    let arguments = [
+        argument("service", "authorizeme", true),
+        argument("thing", "this will be replaced", false),
+    ];
+    let request = authorization::Request::new(
+        AuthenticationMethod::NotSet,
+        context(0, AuthenticationType::NotSet, AuthenticationService::Login),
+        client_user_information("someuser"),
+        Arguments::new(&arguments).expect("arguments should be valid"),
+    );
+    assert_serializes_to!(
+        Packet::new(
+            header(1, PacketFlags::SINGLE_CONNECTION, 0x75b1ef91),
+            request
+        ),
+        fixture.client[0]
+    );
+
+    assert_authorization_reply(
+        &fixture.server[0],
+        authorization::Status::PassReplace,
+        &["service=authorizeme", "thing*not important", "number=42"],
+    );
+}
+
+#[test]
+fn accounting_task() {
+    let mut fixture = fixture!("accounting-task");
+    let task_id = "e9244e36-ca81-45b8-be1c-de20c36cbed8";
+
+    let records = [
+        (
+            accounting::Flags::StartRecord,
+            0x97f3eb26,
+            [
+                argument("task_id", task_id, true),
+                argument("start_time", "1792154655", true),
+                argument("custom", "something", true),
+            ]
+            .to_vec(),
+        ),
+        (
+            accounting::Flags::WatchdogUpdate,
+            0x21371102,
+            [
+                argument("task_id", task_id, true),
+                argument("elapsed_time", "1", true),
+                argument("custom2", "", false),
+            ]
+            .to_vec(),
+        ),
+        (
+            accounting::Flags::StopRecord,
+            0x4cafe017,
+            [
+                argument("task_id", task_id, true),
+                argument("stop_time", "1792154657", true),
+            ]
+            .to_vec(),
+        ),
+    ];
+
+    for (index, (flags, session_id, arguments)) in records.into_iter().enumerate() {
+        let request = accounting::Request::new(
+            flags,
+            AuthenticationMethod::NotSet,
+            context(0, AuthenticationType::NotSet, AuthenticationService::Login),
+            client_user_information("account"),
+            Arguments::new(&arguments).expect("arguments should be valid"),
+        );
+        assert_serializes_to!(
+            Packet::new(
+                header(1, PacketFlags::SINGLE_CONNECTION, session_id),
+                request
+            ),
+            fixture.client[index]
+        );
+
+        let reply = Packet::<accounting::Reply>::deserialize(KEY, &mut fixture.server[index])
+            .expect("reply should have been parsed");
+        assert_eq!(reply.header().session_id(), session_id);
+        assert_eq!(*reply.body().status(), accounting::Status::Success);
+        assert_eq!(*reply.body().server_message(), "");
+        assert_eq!(*reply.body().data(), "");
+    }
+}
@@ -0,0 +1,92 @@
+//! Benchmarks for authorization sessions with large numbers of arguments, e.g. command authorization
+//! where a server replies with dozens of `cmd-arg`s.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::executor::block_on;
+use tacacs_plus::protocol::{AnyBody, Argument, FieldText, Packet};
+use tacacs_plus::{Client, ClientBuilder, ContextBuilder, ScriptedServer};
+
+/// Replies to an authorization request with PASS_REPL, replacing the value of each sent argument.
+fn replace_arguments(request: &Packet<AnyBody>) -> Option<Vec<u8>> {
+    // request body: fixed fields & lengths, then user/port/remote address & arguments
+    let body = request.body().data();
+    let argument_count = usize::from(body[7]);
+    let argument_lengths = &body[8..8 + argument_count];
+
+    let mut offset = 8 + argument_count;
+    offset += body[4..7]
+        .iter()
+        .map(|&len| usize::from(len))
+        .sum::<usize>();
+
+    let replaced: Vec<String> = argument_lengths
+        .iter()
+        .map(|&length| {
+            let argument = &body[offset..offset + usize::from(length)];
+            offset += usize::from(length);
+
+            let separator = argument
+                .iter()
+                .position(|&c| c == b'=' || c == b'*')
+                .expect("argument should have a separator");
+            format!(
+                "{}=replaced",
+                std::str::from_utf8(&argument[..separator]).unwrap()
+            )
+        })
+        .collect();
+
+    // PASS_REPL status, argument count & empty server message/data, with the arguments in reverse order
+    let mut reply = vec![0x02, replaced.len() as u8, 0x00, 0x00, 0x00, 0x00];
+    reply.extend(replaced.iter().rev().map(|arg| arg.len() as u8));
+    for argument in replaced.iter().rev() {
+        reply.extend(argument.as_bytes());
+    }
+
+    Some(reply)
+}
+
+type ReplacingServer = ScriptedServer<fn(&Packet<AnyBody>) -> Option<Vec<u8>>>;
+
+fn arguments(count: usize) -> Vec<Argument<'static>> {
+    (0..count)
+        .map(|index| {
+            Argument::new(
+                FieldText::try_from(format!("cmd-arg{index}")).unwrap(),
+                FieldText::try_from(format!("value{index}")).unwrap(),
+                false,
+            )
+            .unwrap()
+        })
+        .collect()
+}
+
+fn authorization_pass_replace(c: &mut Criterion) {
+    let mut group = c.benchmark_group("authorize_pass_replace");
+
+    for count in [8, 64, 200, 250] {
+        let client: Client<ReplacingServer> = ClientBuilder::new().build(Box::new(|| {
+            Box::pin(async { Ok(ScriptedServer::new(replace_arguments as _)) })
+        }));
+        let context = ContextBuilder::new(String::from("user")).build().unwrap();
+        let arguments = arguments(count);
+
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(count),
+            &arguments,
+            |b, arguments| {
+                b.iter(|| {
+                    let response = block_on(client.authorize(context.clone(), arguments))
+                        .expect("authorization should succeed");
+                    assert_eq!(response.arguments.len(), count);
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, authorization_pass_replace);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+//! The full lifecycle of an accounting task against the mock server in `common`.
+//!
+//! A task is started with a START record, optionally updated with WATCHDOG records while it runs, and finished with a
+//! STOP record. Each record is its own session with a single request & reply, and all of them share a `task_id`.
+//!
+//! ```text
+//! client                                          server
+//!   | -- REQUEST (START, task_id, start_time) ------> |
+//!   | <----------------- REPLY (SUCCESS) ------------ |
+//!   | -- REQUEST (WATCHDOG, task_id, elapsed_time) -> |
+//!   | <----------------- REPLY (SUCCESS) ------------ |
+//!   | -- REQUEST (STOP, task_id, stop_time) --------> |
+//!   | <----------------- REPLY (SUCCESS) ------------ |
+//! ```
+//!
+//! Run with `cargo run --example accounting_lifecycle`.
+
+use tacacs_plus::protocol::PrivilegeLevel;
+use tacacs_plus::{Argument, ContextBuilder, FieldText};
+
+mod common;
+use common::{AccountingLog, MockServer, USER};
+
+/// Builds a mandatory argument.
+fn argument(name: &'static str, value: &'static str) -> Argument<'static> {
+    Argument::new(
+        FieldText::try_from(name).unwrap(),
+        FieldText::try_from(value).unwrap(),
+        true,
+    )
+    .expect("argument should be valid")
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let log = AccountingLog::default();
+    let client = MockServer::client(log.clone());
+
+    let context = ContextBuilder::new(USER.to_owned())
+        .port("tty0")
+        .remote_address("192.0.2.1")
+        .build()
+        .expect("context should be valid");
+
+    // the task ID & start time are added by the client
+    let (mut task, _) = client
+        .account_begin(context, [argument("service", "shell")])
+        .await
+        .expect("start record should be accepted");
+
+    // a progress update, e.g. sent periodically for long-running tasks
+    task.update([argument("cmd", "show running-config")])
+        .await
+        .expect("watchdog record should be accepted");
+
+    // the user escalated their privileges partway through the task
+    task.record_priv_change(
+        PrivilegeLevel::new(1).unwrap(),
+        PrivilegeLevel::new(15).unwrap(),
+    )
+    .await
+    .expect("watchdog record should be accepted");
+
+    task.stop([argument("status", "completed")])
+        .await
+        .expect("stop record should be accepted");
+
+    let records = log.lock().unwrap();
+    for record in records.iter() {
+        println!("{}: {}", record.kind, record.arguments.join(" "));
+    }
+
+    let kinds: Vec<_> = records.iter().map(|record| record.kind).collect();
+    assert_eq!(kinds, ["start", "watchdog", "watchdog", "stop"]);
+
+    // every record belongs to the same task
+    let task_id = records[0].argument("task_id");
+    assert!(task_id.is_some());
+    assert!(records
+        .iter()
+        .all(|record| record.argument("task_id") == task_id));
+
+    assert_eq!(records[2].argument("priv-lvl"), Some("15"));
+    assert_eq!(records[2].argument("old-priv-lvl"), Some("1"));
+}
+
+#[test]
+fn example_runs() {
+    main();
+}
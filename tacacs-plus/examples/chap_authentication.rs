@@ -0,0 +1,49 @@
+//! CHAP authentication against the mock server in `common`.
+//!
+//! A CHAP authentication session is a single exchange: the client sends a start packet with the PPP ID, a random
+//! challenge & the MD5 response computed from them and the password, and the server replies with PASS or FAIL.
+//!
+//! ```text
+//! client                                  server
+//!   | -- START (CHAP, id|challenge|response) -> |
+//!   | <------------ REPLY (PASS/FAIL) --------- |
+//! ```
+//!
+//! Run with `cargo run --example chap_authentication`.
+
+use tacacs_plus::{AuthenticationType, ContextBuilder, ResponseStatus};
+
+mod common;
+use common::{AccountingLog, MockServer, PASSWORD, USER};
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let client = MockServer::client(AccountingLog::default());
+
+    let context = ContextBuilder::new(USER.to_owned())
+        .port("tty0")
+        .remote_address("192.0.2.1")
+        .build()
+        .expect("context should be valid");
+
+    // the password itself is never sent, only the response derived from it
+    let response = client
+        .authenticate(context.clone(), PASSWORD, AuthenticationType::Chap)
+        .await
+        .expect("authentication session should complete");
+    println!("correct password: {:?}", response.status);
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    // a wrong password results in a failure, rather than an error
+    let response = client
+        .authenticate(context, "not the password", AuthenticationType::Chap)
+        .await
+        .expect("authentication session should complete");
+    println!("wrong password: {:?}", response.status);
+    assert_eq!(response.status, ResponseStatus::Failure);
+}
+
+#[test]
+fn example_runs() {
+    main();
+}
@@ -0,0 +1,129 @@
+//! Command authorization with typed arguments against the mock server in `common`.
+//!
+//! Commands are authorized by sending the `service`, `cmd` & `cmd-arg` arguments from RFC8907 section 8.2. The server
+//! either permits the command (optionally adding arguments of its own) or denies it.
+//!
+//! ```text
+//! client                                                server
+//!   | -- REQUEST (service=shell, cmd=show, cmd-arg=...) ---> |
+//!   | <----- REPLY (PASS_ADD, priv-lvl=15, timeout=30, ...) - |
+//! ```
+//!
+//! An [`ArgumentSchema`] configured on the client checks the types of outgoing arguments before anything is sent, and
+//! the same kind of schema can check the arguments the server replied with.
+//!
+//! Run with `cargo run --example command_authorization`.
+
+use tacacs_plus::{
+    Argument, ArgumentSchema, ClientBuilder, ClientError, ContextBuilder, FieldText,
+    ResponseStatus, SchemaIssue, ValueKind,
+};
+
+mod common;
+use common::{AccountingLog, MockServer, USER};
+
+/// Builds an argument, which is mandatory unless `optional` is set.
+fn argument(name: &str, value: &str, optional: bool) -> Argument<'static> {
+    Argument::new(
+        FieldText::try_from(name.to_owned()).expect("name should be printable ASCII"),
+        FieldText::try_from(value.to_owned()).expect("value should be printable ASCII"),
+        !optional,
+    )
+    .expect("argument should be valid")
+}
+
+/// Builds the arguments for authorizing a shell command.
+fn command(command: &str, arguments: &[&str]) -> Vec<Argument<'static>> {
+    let mut command_arguments = vec![
+        argument("service", "shell", false),
+        argument("cmd", command, false),
+    ];
+    command_arguments.extend(
+        arguments
+            .iter()
+            .map(|command_argument| argument("cmd-arg", command_argument, false)),
+    );
+
+    command_arguments
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let mut request_schema = ArgumentSchema::new();
+    request_schema
+        .required("service", ValueKind::Text)
+        .required("cmd", ValueKind::Text)
+        .optional("cmd-arg", ValueKind::Text)
+        .optional("priv-lvl", ValueKind::Integer)
+        .strict();
+
+    let client = ClientBuilder::new()
+        .authorization_schema(request_schema)
+        .build(MockServer::factory(AccountingLog::default()));
+
+    let context = ContextBuilder::new(USER.to_owned())
+        .port("tty0")
+        .build()
+        .expect("context should be valid");
+
+    // a permitted command, to which the server adds arguments
+    let response = client
+        .authorize(context.clone(), command("show", &["running-config"]))
+        .await
+        .expect("authorization session should complete");
+    println!("show running-config: {:?}", response.status);
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let mut reply_schema = ArgumentSchema::new();
+    reply_schema
+        .required("priv-lvl", ValueKind::Integer)
+        .optional("timeout", ValueKind::Integer)
+        .optional("idletime", ValueKind::Integer);
+    let issues = reply_schema.validate(&response.changes.added);
+    assert!(issues.is_empty(), "unexpected reply arguments: {issues:?}");
+
+    // having been validated, the values can be parsed without worrying about errors
+    for added in &response.changes.added {
+        let value: u32 = added.value().as_ref().parse().unwrap();
+        let kind = if added.mandatory() {
+            "mandatory"
+        } else {
+            "optional"
+        };
+        println!("  {} = {value} ({kind})", added.name());
+    }
+
+    // a command the server doesn't permit
+    let response = client
+        .authorize(context.clone(), command("reload", &[]))
+        .await
+        .expect("authorization session should complete");
+    println!("reload: {:?} ({})", response.status, response.user_message);
+    assert_eq!(response.status, ResponseStatus::Failure);
+
+    // the arguments of a denial are only those returned by the server, which may explain it
+    for returned in &response.arguments {
+        println!("  {returned}");
+    }
+    assert_eq!(*response.arguments[0].name(), "reason");
+
+    // a mistyped argument is caught by the client's strict schema, without contacting the server
+    let mut arguments = command("show", &["version"]);
+    arguments.push(argument("priv-lvl", "high", true));
+
+    let error = client
+        .authorize(context, arguments)
+        .await
+        .expect_err("arguments shouldn't conform to schema");
+    println!("mistyped priv-lvl: {error}");
+    assert!(matches!(
+        error,
+        ClientError::ArgumentSchemaViolation(issues)
+            if matches!(&issues[..], [SchemaIssue::WrongType { name, .. }] if name == "priv-lvl")
+    ));
+}
+
+#[test]
+fn example_runs() {
+    main();
+}
@@ -0,0 +1,195 @@
+//! An in-memory TACACS+ server shared by the examples, so they can be run without setting up a real server.
+//!
+//! Packets are exchanged unobfuscated, so clients connecting to it shouldn't be configured with a secret key.
+
+// each example only uses some of the server's behavior
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use md5::{Digest, Md5};
+use tacacs_plus::protocol::{AnyBody, Packet, PacketType};
+use tacacs_plus::{Client, ConnectionFactory, ReplyScript, ScriptedServer};
+
+/// The only user known to the server.
+pub const USER: &str = "someuser";
+
+/// The password of [`USER`], as checked in CHAP authentication.
+pub const PASSWORD: &str = "hunter2";
+
+/// The commands users are permitted to run, as checked in command authorization.
+pub const PERMITTED_COMMANDS: &[&str] = &["show", "ping"];
+
+/// The arguments added to the reply to a permitted command authorization request.
+pub const AUTHORIZED_ARGUMENTS: &[&str] = &["priv-lvl=15", "timeout=30", "idletime*10"];
+
+/// The arguments included in the reply to a denied command authorization request.
+pub const DENIAL_ARGUMENTS: &[&str] = &["reason=command-not-permitted"];
+
+/// The accounting records a [`MockServer`] received, shared with the example that started it.
+pub type AccountingLog = Arc<Mutex<Vec<AccountingRecord>>>;
+
+/// An accounting record received by a [`MockServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountingRecord {
+    /// The kind of record, i.e. `start`, `watchdog` or `stop`.
+    pub kind: &'static str,
+
+    /// The arguments of the record, as they were sent on the wire (e.g. `task_id=...`).
+    pub arguments: Vec<String>,
+}
+
+impl AccountingRecord {
+    /// Gets the value of the first argument with the provided name, if any.
+    pub fn argument(&self, name: &str) -> Option<&str> {
+        self.arguments.iter().find_map(|argument| {
+            let (argument_name, value) = argument.split_at(argument.find(['=', '*'])?);
+            (argument_name == name).then(|| &value[1..])
+        })
+    }
+}
+
+/// The script of a [`ScriptedServer`] that answers requests based on the constants in this module, as a real server
+/// would with a matching configuration.
+///
+/// - CHAP authentication passes for [`USER`] with [`PASSWORD`]; all other authentication fails.
+/// - Authorization passes for the [`PERMITTED_COMMANDS`], adding the [`AUTHORIZED_ARGUMENTS`], and fails with the
+///   [`DENIAL_ARGUMENTS`] otherwise.
+/// - Accounting records are always accepted, and added to the server's [`AccountingLog`].
+pub struct MockServer {
+    accounting_log: AccountingLog,
+}
+
+impl MockServer {
+    /// Creates a connection factory that opens connections to new servers sharing the provided accounting log.
+    pub fn factory(accounting_log: AccountingLog) -> ConnectionFactory<ScriptedServer<MockServer>> {
+        Box::new(move || {
+            let server = ScriptedServer::new(MockServer {
+                accounting_log: accounting_log.clone(),
+            });
+            Box::pin(async move { Ok(server) })
+        })
+    }
+
+    /// Creates a client without a secret key that connects to new servers, as with [`factory()`](Self::factory).
+    pub fn client(accounting_log: AccountingLog) -> Client<ScriptedServer<MockServer>> {
+        Client::new(Self::factory(accounting_log), None::<&[u8]>)
+    }
+
+    /// Checks an authentication start packet body, returning the body of a PASS or FAIL reply.
+    fn authenticate(body: &[u8]) -> Vec<u8> {
+        // start body: action, privilege level, type, service & field lengths, then user/port/remote address & data
+        let [user, _, _, data] = fields(&body[8..], &body[4..8]);
+        let authentication_type = body[2];
+
+        // CHAP data is the PPP ID, challenge & MD5 response, with the response computed over the first two along
+        // with the password
+        let passed = authentication_type == 0x03 && user == USER.as_bytes() && data.len() > 17 && {
+            let (id, rest) = data.split_at(1);
+            let (challenge, response) = rest.split_at(rest.len() - 16);
+
+            let expected = Md5::new()
+                .chain_update(id)
+                .chain_update(PASSWORD)
+                .chain_update(challenge)
+                .finalize();
+            expected.as_slice() == response
+        };
+
+        // PASS/FAIL status, no flags, empty server message & data
+        vec![if passed { 0x01 } else { 0x02 }, 0, 0, 0, 0, 0]
+    }
+
+    /// Checks the command of an authorization request body, returning the body of a PASS_ADD or FAIL reply.
+    fn authorize(body: &[u8]) -> Vec<u8> {
+        let arguments = arguments(body, 4);
+        let permitted = arguments.iter().any(|argument| {
+            argument
+                .strip_prefix("cmd=")
+                .is_some_and(|command| PERMITTED_COMMANDS.contains(&command))
+        });
+
+        // status, argument count, server message & data lengths, then argument lengths & the fields themselves
+        let (status, message, arguments): (_, &[u8], _) = if permitted {
+            (0x01, b"", AUTHORIZED_ARGUMENTS)
+        } else {
+            (0x10, b"command not permitted", DENIAL_ARGUMENTS)
+        };
+
+        let mut reply = vec![status, arguments.len() as u8];
+        reply.extend((message.len() as u16).to_be_bytes());
+        reply.extend([0, 0]);
+        reply.extend(arguments.iter().map(|argument| argument.len() as u8));
+        reply.extend(message);
+        for argument in arguments {
+            reply.extend(argument.as_bytes());
+        }
+
+        reply
+    }
+
+    /// Records an accounting request body, returning the body of a SUCCESS reply.
+    fn account(&self, body: &[u8]) -> Vec<u8> {
+        let kind = match body[0] {
+            0x02 => "start",
+            0x04 => "stop",
+            // watchdog flag, possibly along with the start flag
+            _ => "watchdog",
+        };
+
+        self.accounting_log.lock().unwrap().push(AccountingRecord {
+            kind,
+            arguments: arguments(body, 5),
+        });
+
+        // empty server message & data, success status
+        vec![0, 0, 0, 0, 0x01]
+    }
+}
+
+/// Splits the variable-length fields off the end of a request body, given their lengths.
+fn fields<'body, const N: usize>(mut body: &'body [u8], lengths: &[u8]) -> [&'body [u8]; N] {
+    let mut fields = [&[][..]; N];
+    for (field, &length) in fields.iter_mut().zip(lengths) {
+        (*field, body) = body.split_at(usize::from(length));
+    }
+
+    fields
+}
+
+/// Gets the arguments of an authorization or accounting request body, given the offset of its user length.
+///
+/// In both, the user, port & remote address lengths are followed by the argument count & lengths, and then the fields
+/// themselves.
+fn arguments(body: &[u8], user_length_offset: usize) -> Vec<String> {
+    let count_offset = user_length_offset + 3;
+    let argument_count = usize::from(body[count_offset]);
+    let argument_lengths = &body[count_offset + 1..count_offset + 1 + argument_count];
+
+    let mut offset = count_offset + 1 + argument_count;
+    offset += body[user_length_offset..count_offset]
+        .iter()
+        .map(|&length| usize::from(length))
+        .sum::<usize>();
+
+    argument_lengths
+        .iter()
+        .map(|&length| {
+            let argument = &body[offset..offset + usize::from(length)];
+            offset += usize::from(length);
+            String::from_utf8(argument.to_vec()).expect("argument should be valid UTF-8")
+        })
+        .collect()
+}
+
+impl ReplyScript for MockServer {
+    fn reply(&mut self, request: &Packet<AnyBody>) -> Option<Vec<u8>> {
+        let body = request.body().data();
+
+        Some(match request.body().packet_type() {
+            PacketType::Authentication => Self::authenticate(body),
+            PacketType::Authorization => Self::authorize(body),
+            PacketType::Accounting => self.account(body),
+        })
+    }
+}
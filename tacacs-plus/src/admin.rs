@@ -0,0 +1,53 @@
+use std::fmt;
+use std::sync::Arc;
+
+use super::SessionInfo;
+
+/// The kind of TACACS+ operation performed in a session.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationKind {
+    /// Authentication, i.e. [`Client::authenticate()`](super::Client::authenticate) or [`Client::change_password()`](super::Client::change_password).
+    Authentication,
+
+    /// Authorization, i.e. [`Client::authorize()`](super::Client::authorize).
+    Authorization,
+
+    /// Accounting, i.e. [`Client::account_begin()`](super::Client::account_begin) and the methods of the resulting task.
+    Accounting,
+}
+
+impl fmt::Display for OperationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Authentication => "authentication",
+                Self::Authorization => "authorization",
+                Self::Accounting => "accounting",
+            }
+        )
+    }
+}
+
+/// An administrative log message received from a server, along with some metadata about the session it was received in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdminMessage {
+    /// The operation being performed in the session.
+    pub operation: OperationKind,
+
+    /// The message itself (the `data` field of the reply).
+    pub message: String,
+
+    /// Information about the session the message was received in.
+    pub session: SessionInfo,
+}
+
+/// A callback that receives every administrative message from a server, configured via
+/// [`ClientBuilder::admin_message_sink()`](super::ClientBuilder::admin_message_sink).
+pub(super) type AdminMessageSink = Arc<dyn Fn(&AdminMessage) + Send + Sync>;
+
+/// A rewrite applied to every user message from a server before it's returned, configured via
+/// [`ClientBuilder::user_message_processor()`](super::ClientBuilder::user_message_processor).
+pub(super) type UserMessageProcessor = Arc<dyn Fn(OperationKind, String) -> String + Send + Sync>;
@@ -0,0 +1,734 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::lock::Mutex;
+use futures::{AsyncRead, AsyncWrite};
+use rand::{CryptoRng, RngCore};
+
+use super::admin::{AdminMessageSink, UserMessageProcessor};
+use super::degraded::{AccountingFallback, LocalAccountingLog};
+use super::dynamic::{self, Connection, DynClient};
+use super::encryption::ArgumentEncryption;
+use super::idempotency::AuthorizationDeduplicator;
+use super::inner::{ClientInner, ConnectionFactory};
+use super::peer::{PeerConnectionFactory, PeerSecrets};
+use super::schema::SchemaIssueHandler;
+use super::task::AbandonHandler;
+use super::{
+    AbandonedTask, AdminMessage, ArgumentCipher, ArgumentSchema, ChapStartData, Client, Clock,
+    CompatibilityProfile, LocalAccountingRecord, Middleware, OperationKind, PasswordPolicy,
+    SchemaIssue, SharedRng, SingleConnectionNegotiation, StartDataBuilder, SystemClock,
+    UserNameNormalizer,
+};
+
+#[cfg(feature = "locale-attributes")]
+use super::LocaleAttributes;
+
+/// Builder for [`Client`]s, for configuring behavior beyond what [`Client::new()`] allows.
+///
+/// # Examples
+///
+/// ```
+/// use futures::io::Cursor;
+///
+/// use tacacs_plus::{ClientBuilder, CompatibilityProfile};
+///
+/// let client = ClientBuilder::new()
+///     .secret("very secure key that is super secret")
+///     .compatibility_profile(CompatibilityProfile::Rfc8907)
+///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+/// # let _ = client;
+/// ```
+#[derive(Clone, Default)]
+pub struct ClientBuilder {
+    secret: Option<Vec<u8>>,
+    compatibility_profile: CompatibilityProfile,
+    single_connection_negotiation: SingleConnectionNegotiation,
+    full_duplex: bool,
+    max_body_length: Option<usize>,
+    idle_timeout: Option<Duration>,
+    max_connection_age: Option<Duration>,
+    password_policy: PasswordPolicy,
+    authorization_schema: Option<Arc<ArgumentSchema>>,
+    accounting_schema: Option<Arc<ArgumentSchema>>,
+    schema_issue_handler: Option<SchemaIssueHandler>,
+    authorization_deduplication: Option<Duration>,
+    argument_encryption: Option<ArgumentEncryption>,
+    max_authentication_restarts: u8,
+    admin_message_sink: Option<AdminMessageSink>,
+    user_message_processor: Option<UserMessageProcessor>,
+    abandon_handler: Option<AbandonHandler>,
+    accounting_fallback: Option<(u32, LocalAccountingLog)>,
+    clock: Option<Arc<dyn Clock>>,
+    chap_start_data: ChapStartData,
+    start_data_builders: BTreeMap<&'static str, Arc<dyn StartDataBuilder>>,
+    rng: Option<SharedRng>,
+    user_name_normalizer: Option<Arc<dyn UserNameNormalizer>>,
+    peer_secrets: PeerSecrets,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    #[cfg(feature = "locale-attributes")]
+    locale_attributes: Option<LocaleAttributes>,
+}
+
+// the secret key is omitted so it doesn't end up in logs
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("ClientBuilder");
+        debug
+            .field("compatibility_profile", &self.compatibility_profile)
+            .field(
+                "single_connection_negotiation",
+                &self.single_connection_negotiation,
+            )
+            .field("full_duplex", &self.full_duplex)
+            .field("max_body_length", &self.max_body_length)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("max_connection_age", &self.max_connection_age)
+            .field("password_policy", &self.password_policy)
+            .field("authorization_schema", &self.authorization_schema)
+            .field("accounting_schema", &self.accounting_schema)
+            .field("schema_issue_handler", &self.schema_issue_handler.is_some())
+            .field(
+                "authorization_deduplication",
+                &self.authorization_deduplication,
+            )
+            .field("argument_encryption", &self.argument_encryption)
+            .field(
+                "max_authentication_restarts",
+                &self.max_authentication_restarts,
+            )
+            .field("admin_message_sink", &self.admin_message_sink.is_some())
+            .field(
+                "user_message_processor",
+                &self.user_message_processor.is_some(),
+            )
+            .field("abandon_handler", &self.abandon_handler.is_some())
+            .field(
+                "accounting_fallback",
+                &self
+                    .accounting_fallback
+                    .as_ref()
+                    .map(|(threshold, _)| threshold),
+            )
+            .field("clock", &self.clock)
+            .field("chap_start_data", &self.chap_start_data)
+            .field(
+                "start_data_builders",
+                &self.start_data_builders.keys().collect::<Vec<_>>(),
+            )
+            .field("rng", &self.rng.is_some())
+            .field("user_name_normalizer", &self.user_name_normalizer)
+            .field("peer_secrets", &self.peer_secrets)
+            .field("middlewares", &self.middlewares.len());
+
+        #[cfg(feature = "locale-attributes")]
+        debug.field("locale_attributes", &self.locale_attributes);
+
+        debug.finish_non_exhaustive()
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a new builder with default settings, i.e. no secret key and RFC8907 behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the shared secret used to obfuscate packets.
+    ///
+    /// See [`Client::new()`] for caveats regarding secret keys.
+    pub fn secret<K: AsRef<[u8]>>(&mut self, secret: K) -> &mut Self {
+        self.secret = Some(secret.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the compatibility profile of the resulting client.
+    pub fn compatibility_profile(&mut self, profile: CompatibilityProfile) -> &mut Self {
+        self.compatibility_profile = profile;
+        self
+    }
+
+    /// Sets which replies a server can agree to single connection mode in.
+    ///
+    /// The default follows RFC8907; see [`SingleConnectionNegotiation`] for when to relax it.
+    pub fn single_connection_negotiation(
+        &mut self,
+        negotiation: SingleConnectionNegotiation,
+    ) -> &mut Self {
+        self.single_connection_negotiation = negotiation;
+        self
+    }
+
+    /// Sets whether a connection kept open in single connection mode is shared between concurrent sessions.
+    ///
+    /// By default, sessions are performed one at a time even over a single connection, with each one waiting for the
+    /// previous one to finish. In full duplex mode, the connection is split into independent read & write halves once
+    /// the server agrees to single connection mode, so requests of several sessions can be in flight at once and each
+    /// reply is handed to the session with the matching session id, in whatever order the server sends them.
+    ///
+    /// RFC8907 allows multiple sessions to be interleaved over a single connection, but some servers that agree to
+    /// single connection mode still handle one session at a time, so this is disabled by default.
+    pub fn full_duplex(&mut self, enabled: bool) -> &mut Self {
+        self.full_duplex = enabled;
+        self
+    }
+
+    /// Sets the longest packet body the client accepts from a server, which defaults to
+    /// [`PacketStreamParser::DEFAULT_MAX_BODY_LENGTH`](tacacs_plus_protocol::PacketStreamParser::DEFAULT_MAX_BODY_LENGTH).
+    ///
+    /// A reply whose header announces a longer body fails with
+    /// [`DeserializeError::BodyTooLong`](tacacs_plus_protocol::DeserializeError::BodyTooLong) before any space is
+    /// allocated for it, and its connection is dropped, since the rest of it can't be skipped without reading it.
+    pub fn max_body_length(&mut self, max_body_length: usize) -> &mut Self {
+        self.max_body_length = Some(max_body_length);
+        self
+    }
+
+    /// Sets how long a connection kept open in single connection mode can sit idle between sessions before it's closed,
+    /// with a new one being opened for the next session instead.
+    ///
+    /// This avoids reusing connections that a firewall or NAT device dropped silently after a period of inactivity,
+    /// which would otherwise only be noticed once a session stalls. By default, idle connections are kept indefinitely.
+    pub fn idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long after being opened a connection kept open in single connection mode is replaced, regardless of
+    /// how often it's used.
+    ///
+    /// The age is only checked between sessions, so a session that starts before the limit is reached isn't
+    /// interrupted. By default, connections are reused for as long as they stay open.
+    pub fn max_connection_age(&mut self, age: Duration) -> &mut Self {
+        self.max_connection_age = Some(age);
+        self
+    }
+
+    /// Sets the policy that new passwords are checked against in [`Client::change_password()`].
+    pub fn password_policy(&mut self, policy: PasswordPolicy) -> &mut Self {
+        self.password_policy = policy;
+        self
+    }
+
+    /// Sets a schema that arguments passed to [`Client::authorize()`] are checked against.
+    ///
+    /// Issues with the arguments are reported to the [issue handler](Self::schema_issue_handler) without affecting the
+    /// request, unless the schema is [strict](ArgumentSchema::strict).
+    pub fn authorization_schema(&mut self, schema: ArgumentSchema) -> &mut Self {
+        self.authorization_schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Sets a schema that arguments passed to accounting operations are checked against.
+    ///
+    /// Only the arguments provided by the caller are validated, not the ones added internally (e.g., `task_id`).
+    /// Issues are handled as with [`authorization_schema()`](Self::authorization_schema).
+    pub fn accounting_schema(&mut self, schema: ArgumentSchema) -> &mut Self {
+        self.accounting_schema = Some(Arc::new(schema));
+        self
+    }
+
+    /// Registers a function that's called with the issues a non-[strict](ArgumentSchema::strict) schema finds in the
+    /// arguments of an outgoing request, which is still sent afterwards.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ArgumentSchema, ClientBuilder, ValueKind};
+    ///
+    /// let mut schema = ArgumentSchema::new();
+    /// schema.required("service", ValueKind::Text);
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .authorization_schema(schema)
+    ///     .schema_issue_handler(|operation, issues| {
+    ///         for issue in issues {
+    ///             eprintln!("warning: {operation} arguments: {issue}");
+    ///         }
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn schema_issue_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(OperationKind, &[SchemaIssue]) + Send + Sync + 'static,
+    {
+        self.schema_issue_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Sets how many times [`Client::authenticate()`] starts a new session with the same credentials when the server
+    /// replies with a RESTART status.
+    ///
+    /// By default this is zero, meaning restarts are reported as failures right away.
+    pub fn max_authentication_restarts(&mut self, limit: u8) -> &mut Self {
+        self.max_authentication_restarts = limit;
+        self
+    }
+
+    /// Registers a callback that receives the administrative messages (the `data` field) of the final replies of all
+    /// authentication, authorization and accounting sessions, so they can be logged centrally rather than inspected on
+    /// each response.
+    ///
+    /// The callback is invoked for every nonempty message, including those in replies that result in an error.
+    /// Authentication data isn't necessarily text, so any invalid UTF-8 in it is replaced.
+    /// It's called inline by the operation that received the message, so it should return quickly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::ClientBuilder;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .admin_message_sink(|message| {
+    ///         eprintln!(
+    ///             "[{} session {:08x}] {}",
+    ///             message.operation, message.session.session_id, message.message
+    ///         );
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn admin_message_sink<F>(&mut self, sink: F) -> &mut Self
+    where
+        F: Fn(&AdminMessage) + Send + Sync + 'static,
+    {
+        self.admin_message_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Registers a function that rewrites the user messages (the `server_msg` field) of all replies before they're
+    /// returned, e.g. to replace localization keys like `%AUTH_FAIL%` with text in the user's language.
+    ///
+    /// The processor is applied uniformly to the `user_message` of every response & error and to the message of every
+    /// [`Prompt`](super::Prompt) in interactive authentication, so rewriting can be done in one place. Prompts are
+    /// classified based on the original message. Messages are passed to the processor even if they're empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::ClientBuilder;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .user_message_processor(|_operation, message| match message.as_str() {
+    ///         "%AUTH_FAIL%" => String::from("Authentifizierung fehlgeschlagen"),
+    ///         _ => message,
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn user_message_processor<F>(&mut self, processor: F) -> &mut Self
+    where
+        F: Fn(OperationKind, String) -> String + Send + Sync + 'static,
+    {
+        self.user_message_processor = Some(Arc::new(processor));
+        self
+    }
+
+    /// Registers a callback for [`AccountingTask`](super::AccountingTask)s that are dropped without a stop record being
+    /// acknowledged by the server, which would otherwise go unnoticed.
+    ///
+    /// No stop record can be sent when a task is dropped, so the callback could e.g. log the task or queue a stop record
+    /// to be sent separately. Tasks that are explicitly [abandoned](super::AccountingTask::abandon) are not passed to it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::ClientBuilder;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .accounting_abandon_handler(|task| {
+    ///         eprintln!(
+    ///             "accounting task {} was dropped after {:?} without being stopped",
+    ///             task.task_id, task.elapsed
+    ///         );
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn accounting_abandon_handler<F>(&mut self, handler: F) -> &mut Self
+    where
+        F: Fn(&AbandonedTask) + Send + Sync + 'static,
+    {
+        self.abandon_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Switches accounting to local logging once the server has rejected `error_threshold` records in a row with an
+    /// [`Error`](tacacs_plus_protocol::accounting::Status::Error) status, e.g. for servers that support
+    /// authentication but not accounting.
+    ///
+    /// While accounting is degraded, records are passed to `log` instead of being sent to the server, and accounting
+    /// operations return a response with [`logged_locally`](super::AccountingResponse::logged_locally) set rather than
+    /// an error. This includes the record that crossed the threshold. A
+    /// [`SessionEvent::AccountingDegraded`](super::SessionEvent::AccountingDegraded) event is emitted when accounting
+    /// is degraded, and degraded mode lasts until [`Client::resume_accounting()`](super::Client::resume_accounting)
+    /// is called.
+    ///
+    /// Any record accepted by the server resets the count of errors; other errors (e.g. I/O errors) aren't counted.
+    /// A threshold of zero is treated as one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::ClientBuilder;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .accounting_fallback(3, |record| {
+    ///         let arguments: Vec<_> = record.arguments.iter().map(ToString::to_string).collect();
+    ///         eprintln!("accounting ({:?}) at {:?}: {}", record.flags, record.timestamp, arguments.join(" "));
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// assert!(!client.is_accounting_degraded());
+    /// ```
+    pub fn accounting_fallback<F>(&mut self, error_threshold: u32, log: F) -> &mut Self
+    where
+        F: Fn(&LocalAccountingRecord) + Send + Sync + 'static,
+    {
+        self.accounting_fallback = Some((error_threshold, Arc::new(log)));
+        self
+    }
+
+    /// Sends retries of an authorization request under the session id of the original attempt, if the server didn't
+    /// answer it within `window` of its first attempt.
+    ///
+    /// Requests are identified by their arguments along with everything in their [context](super::SessionContext) that's
+    /// sent to the server (i.e. all but the priority), after the user name is normalized. An attempt that fails before
+    /// the server answered it (e.g. since the connection was closed) might still have been processed by the server, so
+    /// reusing its session id lets the server recognize a retry rather than seeing two distinct sessions. This applies
+    /// to the automatic retry after the connection is closed, as well as to retries by a [middleware](Self::middleware)
+    /// or the caller.
+    ///
+    /// While an identical request is in flight, others wait for it to finish before being sent, and only reuse its
+    /// session id if it wasn't answered. Once the server has answered a request, the next identical request starts a
+    /// new session as usual.
+    pub fn deduplicate_authorization(&mut self, window: Duration) -> &mut Self {
+        self.authorization_deduplication = Some(window);
+        self
+    }
+
+    /// Encrypts the values of the arguments with the provided names in authorization requests via `cipher`, and
+    /// decrypts the values of the same arguments in replies.
+    ///
+    /// This protects sensitive values beyond the obfuscation of packet bodies, given the server implements the same
+    /// scheme. Values are encrypted after they're validated against the [authorization schema](Self::authorization_schema),
+    /// and the arguments in an [`AuthorizationResponse`](super::AuthorizationResponse) hold decrypted values. See
+    /// [`ArgumentCipher`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ArgumentCipher, CipherError, ClientBuilder};
+    ///
+    /// #[derive(Debug)]
+    /// struct Reversed;
+    ///
+    /// impl ArgumentCipher for Reversed {
+    ///     fn encrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+    ///         Ok(value.chars().rev().collect())
+    ///     }
+    ///
+    ///     fn decrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+    ///         Ok(value.chars().rev().collect())
+    ///     }
+    /// }
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .argument_encryption(Reversed, ["api-token", "otp"])
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn argument_encryption<C, I, N>(&mut self, cipher: C, names: I) -> &mut Self
+    where
+        C: ArgumentCipher + 'static,
+        I: IntoIterator<Item = N>,
+        N: Into<String>,
+    {
+        self.argument_encryption = Some(ArgumentEncryption::new(
+            Arc::new(cipher),
+            names.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Sets the timezone & locale information attached to the start and stop records of accounting tasks.
+    ///
+    /// No such information is sent by default; [`LocaleAttributes::from_system()`] can be used to detect it from the
+    /// settings of the system the client is running on.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ClientBuilder, LocaleAttributes};
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .accounting_locale(LocaleAttributes::from_system())
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    #[cfg(feature = "locale-attributes")]
+    pub fn accounting_locale(&mut self, attributes: LocaleAttributes) -> &mut Self {
+        self.locale_attributes = Some(attributes);
+        self
+    }
+
+    /// Sets the source of the timestamps & durations sent in accounting records, which is also used to measure the
+    /// [idle timeout](Self::idle_timeout) & [age](Self::max_connection_age) of connections.
+    ///
+    /// The time is taken from the operating system by default (i.e., [`SystemClock`]).
+    pub fn clock<C: Clock + 'static>(&mut self, clock: C) -> &mut Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Sets the builder used for the start packets of CHAP authentication sessions, e.g. to change the challenge length.
+    pub fn chap_start_data(&mut self, builder: ChapStartData) -> &mut Self {
+        self.chap_start_data = builder;
+        self
+    }
+
+    /// Registers a builder for the start packets of a custom authentication method, which is used when authenticating
+    /// with [`AuthenticationType::Custom`](super::AuthenticationType::Custom) and the same name.
+    ///
+    /// Registering another builder with the same name replaces the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::protocol::{AuthenticationType as ProtocolType, MinorVersion};
+    /// use tacacs_plus::{AuthenticationType, ClientBuilder, ClientError, SessionContext, StartData};
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .start_data_builder("uppercase-pap", |_: &SessionContext, password: &str| {
+    ///         Ok(StartData {
+    ///             authentication_type: ProtocolType::Pap,
+    ///             minor_version: MinorVersion::V1,
+    ///             data: password.to_uppercase().into_bytes(),
+    ///         })
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    ///
+    /// // the builder is then used via the custom authentication type
+    /// let authentication_type = AuthenticationType::Custom("uppercase-pap");
+    /// # let _ = (client, authentication_type);
+    /// ```
+    pub fn start_data_builder<B: StartDataBuilder + 'static>(
+        &mut self,
+        name: &'static str,
+        builder: B,
+    ) -> &mut Self {
+        self.start_data_builders.insert(name, Arc::new(builder));
+        self
+    }
+
+    /// Sets the random number generator used for session IDs & authentication challenges (e.g., for CHAP).
+    ///
+    /// The RNG is shared between clones of the resulting client. By default, the thread-local RNG from the
+    /// [`rand`] crate is used, which might not be available or appropriate in some environments (e.g., where only
+    /// FIPS-validated generators may be used). A seeded RNG can also be used to make packets deterministic in tests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    /// use rand::rngs::StdRng;
+    /// use rand::SeedableRng;
+    ///
+    /// use tacacs_plus::ClientBuilder;
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .rng(StdRng::seed_from_u64(42))
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn rng<R: RngCore + CryptoRng + Send + 'static>(&mut self, rng: R) -> &mut Self {
+        self.rng = Some(Arc::new(std::sync::Mutex::new(rng)));
+        self
+    }
+
+    /// Sets the normalizer applied to the username of every context before it's sent to a server, e.g. to strip realms.
+    ///
+    /// The normalized username is also the one recorded in [`AccountingTask`](super::AccountingTask)s and sent in response
+    /// to GETUSER prompts. By default, usernames are sent as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ClientBuilder, UserNameRules};
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .user_name_normalizer(UserNameRules {
+    ///         strip_realm: true,
+    ///         lowercase: true,
+    ///         ..Default::default()
+    ///     })
+    ///     .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })));
+    /// # let _ = client;
+    /// ```
+    pub fn user_name_normalizer<N: UserNameNormalizer + 'static>(
+        &mut self,
+        normalizer: N,
+    ) -> &mut Self {
+        self.user_name_normalizer = Some(Arc::new(normalizer));
+        self
+    }
+
+    /// Adds a middleware that every operation of the client passes through.
+    ///
+    /// Middlewares are layered in the order they're added, so the first one sees each operation first and its outcome
+    /// last. See [`Middleware`] for details.
+    pub fn middleware<M: Middleware + 'static>(&mut self, middleware: M) -> &mut Self {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Sets the secrets of individual servers, for clients built with [`build_with_peers()`](Self::build_with_peers).
+    ///
+    /// By default, the default secret is only used with the first server a client connects to; see [`PeerSecrets`]
+    /// for details.
+    pub fn peer_secrets(&mut self, secrets: PeerSecrets) -> &mut Self {
+        self.peer_secrets = secrets;
+        self
+    }
+
+    /// Builds a [`Client`] that uses the provided factory to open connections to a server.
+    pub fn build<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection_factory: ConnectionFactory<S>,
+    ) -> Client<S> {
+        self.build_with_inner(ClientInner::new(connection_factory))
+    }
+
+    /// Builds a [`Client`] whose connections can go to different servers, as reported by the provided factory.
+    ///
+    /// The secret for each connection is chosen by its peer, using the [`PeerSecrets`] set via
+    /// [`peer_secrets()`](Self::peer_secrets). Connections to a peer the client isn't configured for fail with
+    /// [`ClientError::UnexpectedPeer`](super::ClientError::UnexpectedPeer) before any packets are sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::net::SocketAddr;
+    ///
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ClientBuilder, PeerSecrets};
+    ///
+    /// let primary: SocketAddr = "192.0.2.1:49".parse().unwrap();
+    /// let backup: SocketAddr = "192.0.2.2:49".parse().unwrap();
+    ///
+    /// let mut secrets = PeerSecrets::new();
+    /// secrets.insert(primary, "primary secret").insert(backup, "backup secret");
+    ///
+    /// let client = ClientBuilder::new()
+    ///     .peer_secrets(secrets)
+    ///     .build_with_peers(Box::new(move || {
+    ///         // e.g. connect to the primary server, falling back to the backup
+    ///         Box::pin(async move { Ok((Cursor::new(Vec::<u8>::new()), primary)) })
+    ///     }));
+    /// # let _ = (client, backup);
+    /// ```
+    pub fn build_with_peers<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        connection_factory: PeerConnectionFactory<S>,
+    ) -> Client<S> {
+        self.build_with_inner(ClientInner::with_peers(
+            connection_factory,
+            self.peer_secrets.clone(),
+        ))
+    }
+
+    fn build_with_inner<S: AsyncRead + AsyncWrite + Unpin>(
+        &self,
+        mut inner: ClientInner<S>,
+    ) -> Client<S> {
+        let clock = self.clock.clone().unwrap_or_else(|| Arc::new(SystemClock));
+
+        inner.set_single_connection_negotiation(self.single_connection_negotiation);
+        inner.set_full_duplex(self.full_duplex);
+        if let Some(max_body_length) = self.max_body_length {
+            inner.set_max_body_length(max_body_length);
+        }
+        inner.set_connection_limits(self.idle_timeout, self.max_connection_age, clock.clone());
+
+        Client {
+            snapshot: inner.snapshot().clone(),
+            stats: inner.stats().clone(),
+            #[cfg(feature = "session-events")]
+            events: inner.events().clone(),
+            inner: Arc::new(Mutex::new(inner)),
+            queue: Arc::default(),
+            secret: self.secret.clone(),
+            compatibility_profile: self.compatibility_profile,
+            password_policy: self.password_policy.clone(),
+            authorization_schema: self.authorization_schema.clone(),
+            accounting_schema: self.accounting_schema.clone(),
+            schema_issue_handler: self.schema_issue_handler.clone(),
+            authorization_deduplicator: self
+                .authorization_deduplication
+                .map(|window| Arc::new(AuthorizationDeduplicator::new(window))),
+            argument_encryption: self.argument_encryption.clone(),
+            max_authentication_restarts: self.max_authentication_restarts,
+            admin_message_sink: self.admin_message_sink.clone(),
+            user_message_processor: self.user_message_processor.clone(),
+            abandon_handler: self.abandon_handler.clone(),
+            accounting_fallback: self
+                .accounting_fallback
+                .as_ref()
+                .map(|(threshold, log)| Arc::new(AccountingFallback::new(*threshold, log.clone()))),
+            clock,
+            chap_start_data: self.chap_start_data,
+            start_data_builders: self.start_data_builders.clone(),
+            rng: self.rng.clone(),
+            user_name_normalizer: self.user_name_normalizer.clone(),
+            middlewares: self.middlewares.iter().cloned().collect(),
+            #[cfg(feature = "locale-attributes")]
+            locale_attributes: self.locale_attributes.clone(),
+        }
+    }
+
+    /// Builds a [`DynClient`] that uses the provided factory to open connections, erasing their concrete type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{ClientBuilder, DynClient};
+    ///
+    /// let builder = ClientBuilder::new();
+    ///
+    /// // clients over different transports share the same type
+    /// let clients: Vec<DynClient> = vec![
+    ///     builder.build_dyn(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) }))),
+    ///     builder.build_dyn(Box::new(|| Box::pin(async { Ok(Cursor::new(Box::<[u8]>::from([0; 64]))) }))),
+    /// ];
+    /// # let _ = clients;
+    /// ```
+    pub fn build_dyn<S: Connection + 'static>(
+        &self,
+        connection_factory: ConnectionFactory<S>,
+    ) -> DynClient {
+        self.build(dynamic::erase_connection_factory(connection_factory))
+    }
+}
@@ -0,0 +1,55 @@
+//! Time sources for the timestamps & durations in accounting records.
+
+use std::fmt;
+use std::time::{Instant, SystemTime};
+
+/// A source of time for accounting records, as configured via [`ClientBuilder::clock()`](super::ClientBuilder::clock).
+///
+/// This can be used to supply time from a source other than the operating system (e.g., the RTC of an embedded device)
+/// or to make accounting timestamps deterministic in tests.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+///
+/// use tacacs_plus::Clock;
+///
+/// /// A clock that is stuck at a fixed point in time.
+/// #[derive(Debug)]
+/// struct FixedClock(SystemTime);
+///
+/// impl Clock for FixedClock {
+///     fn now(&self) -> SystemTime {
+///         self.0
+///     }
+/// }
+///
+/// let clock = FixedClock(UNIX_EPOCH + Duration::from_secs(1_000_000_000));
+/// assert_eq!(clock.now(), clock.now());
+/// ```
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Returns the current wall-clock time, which is used for the `start_time` accounting argument.
+    ///
+    /// Later timestamps in a task (e.g., `stop_time`) are derived from its start time and the [monotonic](Self::instant)
+    /// time elapsed since, which keeps them consistent if the wall clock is adjusted while the task is running.
+    fn now(&self) -> SystemTime;
+
+    /// Returns the current time of a monotonic clock, which is used to measure how long a task has been running
+    /// (e.g., for the `elapsed_time` accounting argument).
+    ///
+    /// Defaults to [`Instant::now()`].
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// The default [`Clock`], which gets the time from the operating system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
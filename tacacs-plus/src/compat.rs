@@ -0,0 +1,86 @@
+use tacacs_plus_protocol::accounting;
+
+#[cfg(test)]
+mod tests;
+
+/// The flavor of the TACACS+ protocol spoken by a server, which adjusts a few client behaviors for compatibility.
+///
+/// RFC8907 formalized the protocol long after it was deployed, and some daemons still in use today (e.g., old Cisco ACS
+/// releases or Shrubbery's `tac_plus` built against the draft) follow the older draft semantics instead.
+/// The differences between the supported profiles are as follows:
+///
+/// | Behavior                                   | [`Rfc8907`](Self::Rfc8907)              | `LegacyDraft`                                 |
+/// |--------------------------------------------|-----------------------------------------|-----------------------------------------------|
+/// | Flags of accounting update records         | `START` + `WATCHDOG`                    | `WATCHDOG` only                               |
+/// | Connection after a reply with ERROR status | Closed, even in single connection mode  | Kept open if single connection mode is in use |
+/// | Reply with FOLLOW status                   | Failure (error for accounting)          | Same as `Rfc8907`                             |
+///
+/// Draft-era daemons treat any record with the `START` flag set as the start of a new task, so sending updates with
+/// only the `WATCHDOG` flag avoids them being logged as duplicate starts. Those daemons also use the ERROR status for
+/// problems with an individual request rather than the connection as a whole, so tearing down the connection is unnecessary.
+///
+/// The draft gives FOLLOW more weight than RFC8907 does, asking clients to retry the request against the alternate
+/// servers listed in the reply data. Those servers may well be unreachable through the connection factory of a client
+/// though, and FOLLOW is deprecated for good reason, so it's treated the same as under RFC8907 rather than redirecting
+/// requests to hosts that weren't configured.
+///
+/// The `LegacyDraft` profile is only available with the `legacy-draft` feature enabled.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CompatibilityProfile {
+    /// Behavior as specified in [RFC8907](https://www.rfc-editor.org/rfc/rfc8907).
+    #[default]
+    Rfc8907,
+
+    /// Behavior matching daemons implementing the pre-RFC TACACS+ draft (`draft-grant-tacacs-02`).
+    #[cfg(feature = "legacy-draft")]
+    LegacyDraft,
+}
+
+impl CompatibilityProfile {
+    /// Adjusts the flags of an outgoing accounting record for this profile.
+    pub(super) fn accounting_flags(self, flags: accounting::Flags) -> accounting::Flags {
+        match (self, flags) {
+            // WatchdogNoUpdate is encoded as just the WATCHDOG flag on the wire
+            #[cfg(feature = "legacy-draft")]
+            (Self::LegacyDraft, accounting::Flags::WatchdogUpdate) => {
+                accounting::Flags::WatchdogNoUpdate
+            }
+            (_, flags) => flags,
+        }
+    }
+
+    /// Whether a reply with an ERROR status should cause the current connection to be closed.
+    pub(super) fn error_closes_connection(self) -> bool {
+        match self {
+            Self::Rfc8907 => true,
+            #[cfg(feature = "legacy-draft")]
+            Self::LegacyDraft => false,
+        }
+    }
+}
+
+/// Which replies a server can agree to single connection mode in, by setting the `SINGLE_CONNECTION` flag.
+///
+/// [RFC8907 section 4.3] only gives the flag meaning in the first reply of the first session on a connection, which is
+/// what clients check by default. Some devices and servers (notably around Cisco IOS) only set it on later replies of
+/// that session, though, which leaves connections that the server is willing to reuse closed after every session.
+///
+/// | Reply in the first session with the flag set | [`FirstReply`](Self::FirstReply) | [`FirstSession`](Self::FirstSession) |
+/// |----------------------------------------------|----------------------------------|--------------------------------------|
+/// | First reply (sequence number 2)              | Single connection mode           | Single connection mode               |
+/// | Any later reply (e.g., during ASCII login)   | Ignored                          | Single connection mode               |
+///
+/// In either case, the flag is ignored in sessions after the first one on a connection.
+///
+/// [RFC8907 section 4.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.3
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SingleConnectionNegotiation {
+    /// Only the flag in the first reply of the first session is honored, as specified in RFC8907.
+    #[default]
+    FirstReply,
+
+    /// The flag is honored in any reply of the first session on a connection.
+    FirstSession,
+}
@@ -0,0 +1,96 @@
+use tacacs_plus_protocol::accounting;
+
+use super::CompatibilityProfile;
+use crate::scripted::{ReplyScript, ScriptedServer};
+use crate::{AuthenticationType, Client, ClientBuilder, ClientError, ContextBuilder};
+use crate::{ResponseStatus, SessionContext};
+
+/// The profiles available with the enabled features.
+fn profiles() -> Vec<CompatibilityProfile> {
+    vec![
+        CompatibilityProfile::Rfc8907,
+        #[cfg(feature = "legacy-draft")]
+        CompatibilityProfile::LegacyDraft,
+    ]
+}
+
+/// Builds a client with the provided profile, whose server replies to every request with the provided body.
+fn client_replying(
+    profile: CompatibilityProfile,
+    body: &'static [u8],
+) -> Client<ScriptedServer<impl ReplyScript + Send + Unpin>> {
+    ClientBuilder::new()
+        .compatibility_profile(profile)
+        .build(Box::new(move || {
+            let server = ScriptedServer::new(move |_: &_| Some(body.to_vec()));
+            Box::pin(async move { Ok(server) })
+        }))
+}
+
+fn context() -> SessionContext {
+    ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn update_flags_adjusted_for_profile() {
+    let rfc_flags =
+        CompatibilityProfile::Rfc8907.accounting_flags(accounting::Flags::WatchdogUpdate);
+    assert_eq!(rfc_flags, accounting::Flags::WatchdogUpdate);
+
+    #[cfg(feature = "legacy-draft")]
+    {
+        let draft_flags =
+            CompatibilityProfile::LegacyDraft.accounting_flags(accounting::Flags::WatchdogUpdate);
+        assert_eq!(draft_flags, accounting::Flags::WatchdogNoUpdate);
+    }
+
+    // start & stop records are the same in every profile
+    for profile in profiles() {
+        for flags in [
+            accounting::Flags::StartRecord,
+            accounting::Flags::StopRecord,
+        ] {
+            assert_eq!(profile.accounting_flags(flags), flags);
+        }
+    }
+}
+
+#[tokio::test]
+#[allow(deprecated)]
+async fn follow_status_interpreted_same_in_every_profile() {
+    for profile in profiles() {
+        // FOLLOW status, no flags, empty server message & alternate server list in data
+        let client = client_replying(profile, b"\x21\x00\x00\x00\x00\x09192.0.2.1");
+        let response = client
+            .authenticate(context(), "password", AuthenticationType::Pap)
+            .await
+            .expect("authentication session should complete");
+        assert_eq!(response.status, ResponseStatus::Failure, "{profile:?}");
+
+        // FOLLOW status, no arguments, empty server message & alternate server list in data
+        let client = client_replying(profile, b"\x21\x00\x00\x00\x00\x09192.0.2.1");
+        let response = client
+            .authorize(context(), [])
+            .await
+            .expect("authorization session should complete");
+        assert_eq!(response.status, ResponseStatus::Failure, "{profile:?}");
+
+        // empty server message, alternate server list in data & FOLLOW status
+        let client = client_replying(profile, b"\x00\x00\x00\x09\x21192.0.2.1");
+        let Err(error) = client.account_begin(context(), []).await else {
+            panic!("{profile:?}: accounting FOLLOW status should be an error");
+        };
+        assert!(
+            matches!(
+                error,
+                ClientError::AccountingError {
+                    status: accounting::Status::Follow,
+                    ..
+                }
+            ),
+            "{profile:?}: {error:?}"
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Client configuration in standard formats (TOML & JSON), e.g. for applications migrating from tac_plus-style configs.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::ClientBuilder;
+
+#[cfg(test)]
+mod tests;
+
+/// The port TACACS+ servers listen on if none is configured, as assigned in [RFC8907 section 4.1].
+///
+/// [RFC8907 section 4.1]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.1-2
+pub const DEFAULT_PORT: u16 = 49;
+
+/// An error encountered when loading a [`ClientConfig`].
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    /// The configuration file couldn't be read.
+    #[error("failed to read configuration file")]
+    Io(#[from] io::Error),
+
+    /// The configuration wasn't valid TOML, or didn't match the expected structure.
+    #[error("invalid TOML configuration")]
+    Toml(#[from] toml::de::Error),
+
+    /// The configuration wasn't valid JSON, or didn't match the expected structure.
+    #[error("invalid JSON configuration")]
+    Json(#[from] serde_json::Error),
+
+    /// The format of a configuration file couldn't be determined from its extension.
+    #[error("unknown configuration file format (expected a .toml or .json extension)")]
+    UnknownFormat,
+
+    /// No servers were configured.
+    #[error("no servers configured")]
+    NoServers,
+
+    /// A server was configured with an empty host.
+    #[error("server {index} has an empty host")]
+    EmptyHost {
+        /// The index of the offending server in the configured list.
+        index: usize,
+    },
+}
+
+/// The configuration of a TACACS+ client, as parsed from TOML or JSON.
+///
+/// Secrets, timeouts and the source interface can be set at the top level as defaults for all servers,
+/// or per server to override them. All durations are in seconds:
+///
+/// ```toml
+/// secret = "default shared secret"
+/// timeout = 5
+/// idle_timeout = 300
+/// max_connection_age = 3600
+///
+/// [[servers]]
+/// host = "tacacs1.example.com"
+///
+/// [[servers]]
+/// host = "10.0.0.2"
+/// port = 4949
+/// secret = "secret for this server only"
+/// source_interface = "eth1"
+/// ```
+///
+/// Since connections are opened by the application (see [`ConnectionFactory`](super::ConnectionFactory)), the timeout
+/// and source interface of a server aren't applied by the client itself, but are available to a connection factory
+/// via [`ServerConfig`]. The secret & connection lifetime limits are applied to a [`ClientBuilder`] via
+/// [`ServerConfig::client_builder()`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tacacs_plus::ClientConfig;
+///
+/// let config = ClientConfig::from_json(
+///     r#"{ "secret": "shared", "timeout": 3, "servers": [{ "host": "tacacs.example.com" }] }"#,
+/// )
+/// .unwrap();
+///
+/// let server = &config.servers()[0];
+/// assert_eq!(server.address(), ("tacacs.example.com", 49));
+/// assert_eq!(server.secret(), Some("shared"));
+/// assert_eq!(server.timeout(), Some(Duration::from_secs(3)));
+///
+/// let builder = server.client_builder();
+/// # let _ = builder;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientConfig {
+    servers: Vec<ServerConfig>,
+}
+
+/// The configuration of a single TACACS+ server, with any defaults from the top level of a [`ClientConfig`] applied.
+///
+/// The [`Debug`](fmt::Debug) output of a server configuration only indicates whether it has a secret, so the secret
+/// isn't leaked into logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    host: String,
+    port: u16,
+    secret: Option<String>,
+    timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    max_connection_age: Option<Duration>,
+    source_interface: Option<String>,
+}
+
+impl fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the secret is omitted to avoid exposing it
+        f.debug_struct("ServerConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("has_secret", &self.secret.is_some())
+            .field("timeout", &self.timeout)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("max_connection_age", &self.max_connection_age)
+            .field("source_interface", &self.source_interface)
+            .finish()
+    }
+}
+
+/// The structure of a configuration file, before defaults are applied & values are validated.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    servers: Vec<RawServer>,
+    secret: Option<String>,
+    timeout: Option<u64>,
+    idle_timeout: Option<u64>,
+    max_connection_age: Option<u64>,
+    source_interface: Option<String>,
+}
+
+/// A server as specified in a configuration file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawServer {
+    host: String,
+    port: Option<u16>,
+    secret: Option<String>,
+    timeout: Option<u64>,
+    idle_timeout: Option<u64>,
+    max_connection_age: Option<u64>,
+    source_interface: Option<String>,
+}
+
+impl ClientConfig {
+    /// Parses a configuration in TOML format.
+    pub fn from_toml(config: &str) -> Result<Self, ConfigError> {
+        Self::from_raw(toml::from_str(config)?)
+    }
+
+    /// Parses a configuration in JSON format.
+    pub fn from_json(config: &str) -> Result<Self, ConfigError> {
+        Self::from_raw(serde_json::from_str(config)?)
+    }
+
+    /// Reads a configuration from a file, whose format is determined by its extension (`.toml` or `.json`).
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+
+        match extension {
+            Some("toml") => Self::from_toml(&fs::read_to_string(path)?),
+            Some("json") => Self::from_json(&fs::read_to_string(path)?),
+            _ => Err(ConfigError::UnknownFormat),
+        }
+    }
+
+    /// The configured servers, in the order they were specified.
+    pub fn servers(&self) -> &[ServerConfig] {
+        &self.servers
+    }
+
+    /// Validates a parsed configuration & applies top-level defaults to each server.
+    fn from_raw(raw: RawConfig) -> Result<Self, ConfigError> {
+        if raw.servers.is_empty() {
+            return Err(ConfigError::NoServers);
+        }
+
+        let servers = raw
+            .servers
+            .into_iter()
+            .enumerate()
+            .map(|(index, server)| {
+                if server.host.trim().is_empty() {
+                    return Err(ConfigError::EmptyHost { index });
+                }
+
+                Ok(ServerConfig {
+                    host: server.host,
+                    port: server.port.unwrap_or(DEFAULT_PORT),
+                    secret: server.secret.or_else(|| raw.secret.clone()),
+                    timeout: server.timeout.or(raw.timeout).map(Duration::from_secs),
+                    idle_timeout: server
+                        .idle_timeout
+                        .or(raw.idle_timeout)
+                        .map(Duration::from_secs),
+                    max_connection_age: server
+                        .max_connection_age
+                        .or(raw.max_connection_age)
+                        .map(Duration::from_secs),
+                    source_interface: server
+                        .source_interface
+                        .or_else(|| raw.source_interface.clone()),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { servers })
+    }
+}
+
+impl ServerConfig {
+    /// The host & port of the server, which can be passed directly to e.g. `TcpStream::connect()`.
+    pub fn address(&self) -> (&str, u16) {
+        (&self.host, self.port)
+    }
+
+    /// The shared secret used to obfuscate packets, if any.
+    pub fn secret(&self) -> Option<&str> {
+        self.secret.as_deref()
+    }
+
+    /// The timeout for connecting to the server, if any.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// How long a connection to the server can sit idle between sessions before it's replaced, if limited.
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    /// How long after being opened a connection to the server is replaced, if limited.
+    pub fn max_connection_age(&self) -> Option<Duration> {
+        self.max_connection_age
+    }
+
+    /// The interface (or local address) connections to the server should be opened from, if any.
+    pub fn source_interface(&self) -> Option<&str> {
+        self.source_interface.as_deref()
+    }
+
+    /// Creates a [`ClientBuilder`] with the settings of this server applied.
+    pub fn client_builder(&self) -> ClientBuilder {
+        let mut builder = ClientBuilder::new();
+
+        if let Some(secret) = &self.secret {
+            builder.secret(secret);
+        }
+
+        if let Some(timeout) = self.idle_timeout {
+            builder.idle_timeout(timeout);
+        }
+
+        if let Some(age) = self.max_connection_age {
+            builder.max_connection_age(age);
+        }
+
+        builder
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use super::{ClientConfig, ConfigError, DEFAULT_PORT};
+
+#[test]
+fn toml_defaults_applied_to_servers() {
+    let config = ClientConfig::from_toml(
+        r#"
+        secret = "shared"
+        timeout = 5
+        source_interface = "eth0"
+
+        [[servers]]
+        host = "tacacs1.example.com"
+
+        [[servers]]
+        host = "10.0.0.2"
+        port = 4949
+        secret = "override"
+        timeout = 1
+        source_interface = "eth1"
+        "#,
+    )
+    .expect("config should be valid");
+
+    let [first, second] = config.servers() else {
+        panic!("expected two servers, got {:?}", config.servers());
+    };
+
+    assert_eq!(first.address(), ("tacacs1.example.com", DEFAULT_PORT));
+    assert_eq!(first.secret(), Some("shared"));
+    assert_eq!(first.timeout(), Some(Duration::from_secs(5)));
+    assert_eq!(first.source_interface(), Some("eth0"));
+
+    assert_eq!(second.address(), ("10.0.0.2", 4949));
+    assert_eq!(second.secret(), Some("override"));
+    assert_eq!(second.timeout(), Some(Duration::from_secs(1)));
+    assert_eq!(second.source_interface(), Some("eth1"));
+}
+
+#[test]
+fn connection_limits_overridden_per_server() {
+    let config = ClientConfig::from_toml(
+        r#"
+        idle_timeout = 300
+        max_connection_age = 3600
+
+        [[servers]]
+        host = "tacacs1.example.com"
+
+        [[servers]]
+        host = "tacacs2.example.com"
+        idle_timeout = 30
+        "#,
+    )
+    .expect("config should be valid");
+
+    let [first, second] = config.servers() else {
+        panic!("expected two servers, got {:?}", config.servers());
+    };
+
+    assert_eq!(first.idle_timeout(), Some(Duration::from_secs(300)));
+    assert_eq!(first.max_connection_age(), Some(Duration::from_secs(3600)));
+
+    assert_eq!(second.idle_timeout(), Some(Duration::from_secs(30)));
+    assert_eq!(second.max_connection_age(), Some(Duration::from_secs(3600)));
+}
+
+#[test]
+fn json_and_toml_equivalent() {
+    let toml = ClientConfig::from_toml(
+        r#"
+        [[servers]]
+        host = "localhost"
+        port = 5555
+        "#,
+    )
+    .unwrap();
+    let json = ClientConfig::from_json(r#"{ "servers": [{ "host": "localhost", "port": 5555 }] }"#)
+        .unwrap();
+
+    assert_eq!(toml, json);
+    assert_eq!(toml.servers()[0].secret(), None);
+}
+
+#[test]
+fn invalid_configs_rejected() {
+    assert!(matches!(
+        ClientConfig::from_json(r#"{ "servers": [] }"#),
+        Err(ConfigError::NoServers)
+    ));
+    assert!(matches!(
+        ClientConfig::from_json(r#"{ "servers": [{ "host": "a" }, { "host": " " }] }"#),
+        Err(ConfigError::EmptyHost { index: 1 })
+    ));
+
+    // typos shouldn't silently fall back to defaults
+    assert!(matches!(
+        ClientConfig::from_toml("secrte = \"oops\"\n[[servers]]\nhost = \"a\""),
+        Err(ConfigError::Toml(_))
+    ));
+}
+
+#[test]
+fn unknown_file_extension_rejected() {
+    assert!(matches!(
+        ClientConfig::from_file("tacacs.conf"),
+        Err(ConfigError::UnknownFormat)
+    ));
+}
+
+#[test]
+fn secrets_left_out_of_debug_output() {
+    let config = ClientConfig::from_toml(
+        r#"
+        secret = "hunter2"
+
+        [[servers]]
+        host = "tacacs.example.com"
+        "#,
+    )
+    .expect("config should be valid");
+
+    let debug = format!("{config:?}");
+    assert!(!debug.contains("hunter2"), "secret leaked: {debug}");
+    assert!(debug.contains("has_secret: true"));
+}
@@ -1,39 +1,171 @@
-use tacacs_plus_protocol::{AuthenticationMethod, PrivilegeLevel, UserInformation};
+use std::fmt;
 
-use super::ClientError;
+use thiserror::Error;
 
-pub(super) struct InvalidContext(());
+use tacacs_plus_protocol::{
+    AuthenticationMethod, AuthenticationType, FieldText, PrivilegeLevel, UserInformation,
+};
 
-impl From<InvalidContext> for ClientError {
-    fn from(_value: InvalidContext) -> Self {
-        ClientError::InvalidContext
+use super::{OperationKind, SessionPriority};
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "process-metadata")]
+use super::ProcessMetadata;
+
+/// The port used if none is set or detected.
+const FALLBACK_PORT: &str = "rust_client";
+
+/// The remote address used if none is set or detected.
+const FALLBACK_REMOTE_ADDRESS: &str = "tacacs_plus_rs";
+
+/// A field of a [`SessionContext`] that's sent in packets.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContextField {
+    /// The name of the user.
+    User,
+
+    /// The port the user is connected to.
+    Port,
+
+    /// The address the user is connecting from.
+    RemoteAddress,
+}
+
+impl fmt::Display for ContextField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::User => "user name",
+                Self::Port => "port",
+                Self::RemoteAddress => "remote address",
+            }
+        )
+    }
+}
+
+/// Why the value of a context field can't be sent in a packet.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldIssue {
+    /// The value was longer than the 255 bytes that fit in a packet.
+    TooLong {
+        /// The length of the value in bytes.
+        length: usize,
+    },
+
+    /// The value wasn't printable ASCII.
+    NotPrintableAscii,
+}
+
+impl fmt::Display for FieldIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong { length } => write!(
+                f,
+                "{length} bytes long, but at most 255 bytes fit in a packet"
+            ),
+            Self::NotPrintableAscii => write!(f, "not printable ASCII"),
+        }
+    }
+}
+
+/// The reason a [`SessionContext`] couldn't be built from a [`ContextBuilder`], i.e. which field was invalid & why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[error("{field} is {reason}")]
+pub struct ContextFieldError {
+    /// The field that was invalid.
+    pub field: ContextField,
+
+    /// Why the value of the field was invalid.
+    pub reason: FieldIssue,
+}
+
+impl ContextFieldError {
+    fn new(field: ContextField, reason: FieldIssue) -> Self {
+        Self { field, reason }
     }
 }
 
 /// Some information associated with all sessions, regardless of the action.
+///
+/// The fields of a context are validated when it's [built](ContextBuilder::build), so using it in a session only
+/// borrows them.
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct SessionContext {
     pub(super) user: String,
-    pub(super) port: String,
-    pub(super) remote_address: String,
+    pub(super) port: FieldText<'static>,
+    pub(super) remote_address: FieldText<'static>,
     pub(super) privilege_level: PrivilegeLevel,
     authentication_method: Option<AuthenticationMethod>,
+    authentication_type: Option<AuthenticationType>,
+    priority: Option<SessionPriority>,
 }
 
 impl SessionContext {
-    pub(super) fn as_user_information(&self) -> Result<UserInformation<'_>, InvalidContext> {
+    /// Returns a copy of this context with a different privilege level, e.g. for authorizing a user again
+    /// after privilege escalation (`enable`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus::ContextBuilder;
+    /// use tacacs_plus::protocol::PrivilegeLevel;
+    ///
+    /// let context = ContextBuilder::new(String::from("admin")).build().unwrap();
+    ///
+    /// let enable_level = PrivilegeLevel::new(15).unwrap();
+    /// let escalated = context.with_privilege_level(enable_level);
+    /// assert_eq!(escalated.privilege_level(), enable_level);
+    ///
+    /// // the original context is left as is
+    /// assert_eq!(context.privilege_level(), PrivilegeLevel::default());
+    /// ```
+    pub fn with_privilege_level(&self, privilege_level: PrivilegeLevel) -> Self {
+        Self {
+            privilege_level,
+            ..self.clone()
+        }
+    }
+
+    /// Gets the privilege level of this context.
+    pub fn privilege_level(&self) -> PrivilegeLevel {
+        self.privilege_level
+    }
+
+    /// Gets the priority of sessions performed with this context, if it was set explicitly.
+    ///
+    /// Sessions without an explicit priority get the [default for their operation](SessionPriority::default_for).
+    pub fn priority(&self) -> Option<SessionPriority> {
+        self.priority
+    }
+
+    /// Gets the priority of a session for an operation performed with this context.
+    pub(super) fn priority_for(&self, operation: OperationKind) -> SessionPriority {
+        self.priority
+            .unwrap_or_else(|| SessionPriority::default_for(operation))
+    }
+
+    /// Borrows the user information of this context for a packet body.
+    ///
+    /// The port & remote address were validated when this context was built, so only the length of the user name is
+    /// checked again in case it was changed by a [`UserNameNormalizer`](super::UserNameNormalizer).
+    pub(super) fn as_user_information(&self) -> Result<UserInformation<'_>, ContextFieldError> {
         UserInformation::new(
-            self.user.as_str(),
-            self.port
-                .as_str()
-                .try_into()
-                .map_err(|_| InvalidContext(()))?,
-            self.remote_address
-                .as_str()
-                .try_into()
-                .map_err(|_| InvalidContext(()))?,
+            &self.user,
+            self.port.as_borrowed(),
+            self.remote_address.as_borrowed(),
         )
-        .ok_or(InvalidContext(()))
+        .ok_or(ContextFieldError::new(
+            ContextField::User,
+            FieldIssue::TooLong {
+                length: self.user.len(),
+            },
+        ))
     }
 
     /// Gets the authentication method for this context object, defaulting to [`NotSet`](tacacs_plus_protocol::AuthenticationMethod::NotSet).
@@ -43,16 +175,46 @@ impl SessionContext {
         self.authentication_method
             .unwrap_or(AuthenticationMethod::NotSet)
     }
+
+    /// Gets the type of the authentication the user went through, defaulting to [`NotSet`](tacacs_plus_protocol::AuthenticationType::NotSet).
+    ///
+    /// This should not be used within an authentication session.
+    pub(super) fn authentication_type(&self) -> AuthenticationType {
+        self.authentication_type
+            .unwrap_or(AuthenticationType::NotSet)
+    }
+
+    /// Records that the user was authenticated via TACACS+ with the provided authentication type, for later
+    /// authorization & accounting requests.
+    ///
+    /// An authentication method that was set explicitly is kept as is.
+    pub(super) fn set_authenticated(&mut self, authentication_type: AuthenticationType) {
+        self.authentication_method
+            .get_or_insert(AuthenticationMethod::TacacsPlus);
+        self.authentication_type = Some(authentication_type);
+    }
 }
 
 /// Builder for [`SessionContext`] objects.
+///
+/// The port & remote address of the resulting context are taken from the first of these that's available:
+///
+/// 1. the value set via [`port()`](Self::port) or [`remote_address()`](Self::remote_address)
+/// 2. the value detected from the running process, if `process_metadata()` was called (requires the `process-metadata` feature)
+/// 3. a fixed default of `rust_client` for the port or `tacacs_plus_rs` for the remote address
+///
+/// Explicit values are validated as soon as they're set, but an invalid value is only reported once the context is
+/// [built](Self::build), so setters can still be chained.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ContextBuilder {
     user: String,
-    port: String,
-    remote_address: String,
+    port: Option<Result<FieldText<'static>, FieldIssue>>,
+    remote_address: Option<Result<FieldText<'static>, FieldIssue>>,
     privilege_level: PrivilegeLevel,
     authentication_method: Option<AuthenticationMethod>,
+    priority: Option<SessionPriority>,
+    #[cfg(feature = "process-metadata")]
+    process_metadata: Option<ProcessMetadata>,
 }
 
 // TODO: don't consume builder at each step
@@ -61,22 +223,32 @@ impl ContextBuilder {
     pub fn new(user: String) -> Self {
         Self {
             user,
-            port: String::from("rust_client"),
-            remote_address: String::from("tacacs_plus_rs"),
+            port: None,
+            remote_address: None,
             privilege_level: Default::default(),
             authentication_method: None,
+            priority: None,
+            #[cfg(feature = "process-metadata")]
+            process_metadata: None,
         }
     }
 
     /// Sets the port of the resulting context.
-    pub fn port(&mut self, port: String) -> &mut Self {
-        self.port = port;
+    ///
+    /// This accepts anything that converts to a [`FieldText`], so e.g. string literals are stored without copying them.
+    pub fn port(&mut self, port: impl TryInto<FieldText<'static>>) -> &mut Self {
+        self.port = Some(validate_field(port));
         self
     }
 
     /// Sets the remote address of the resulting context.
-    pub fn remote_address(&mut self, remote_address: String) -> &mut Self {
-        self.remote_address = remote_address;
+    ///
+    /// Like with [`port()`](Self::port), anything that converts to a [`FieldText`] is accepted.
+    pub fn remote_address(
+        &mut self,
+        remote_address: impl TryInto<FieldText<'static>>,
+    ) -> &mut Self {
+        self.remote_address = Some(validate_field(remote_address));
         self
     }
 
@@ -94,14 +266,118 @@ impl ContextBuilder {
         self
     }
 
-    /// Consumes this builder and turns it into a [`SessionContext`].
-    pub fn build(&self) -> SessionContext {
-        SessionContext {
+    /// Sets the priority of sessions performed with the resulting context, overriding the default for each operation.
+    ///
+    /// This is a hint for which sessions go first when several are waiting for the connection of a client, e.g. to
+    /// perform command authorization that a user is waiting on ahead of other authorization requests.
+    pub fn priority(&mut self, priority: SessionPriority) -> &mut Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Fills in the port & remote address of the resulting context from metadata about the running process,
+    /// for any of them that isn't set explicitly.
+    ///
+    /// See [`ProcessMetadata`] for what is reported in each field.
+    #[cfg(feature = "process-metadata")]
+    pub fn process_metadata(&mut self, metadata: ProcessMetadata) -> &mut Self {
+        self.process_metadata = Some(metadata);
+        self
+    }
+
+    /// Builds a [`SessionContext`] from this builder's fields.
+    ///
+    /// An error is returned if any field can't be sent in a packet, i.e. if it's longer than 255 bytes or if the
+    /// port or remote address isn't printable ASCII. The error reports the first such field, in the order user name,
+    /// port & remote address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus::{ContextBuilder, ContextField, FieldIssue};
+    ///
+    /// let context = ContextBuilder::new(String::from("someuser"))
+    ///     .port("tty0")
+    ///     .build();
+    /// assert!(context.is_ok());
+    ///
+    /// let error = ContextBuilder::new(String::from("someuser"))
+    ///     .port("tty\u{1F980}")
+    ///     .build()
+    ///     .unwrap_err();
+    /// assert_eq!(error.field, ContextField::Port);
+    /// assert_eq!(error.reason, FieldIssue::NotPrintableAscii);
+    /// ```
+    pub fn build(&self) -> Result<SessionContext, ContextFieldError> {
+        #[cfg(feature = "process-metadata")]
+        let (detected_port, detected_remote_address) = match &self.process_metadata {
+            Some(metadata) => (metadata.port(), metadata.remote_address()),
+            None => (None, None),
+        };
+        #[cfg(not(feature = "process-metadata"))]
+        let (detected_port, detected_remote_address) = (None, None);
+
+        if u8::try_from(self.user.len()).is_err() {
+            return Err(ContextFieldError::new(
+                ContextField::User,
+                FieldIssue::TooLong {
+                    length: self.user.len(),
+                },
+            ));
+        }
+
+        let port = resolve_field(&self.port, detected_port, FALLBACK_PORT)
+            .map_err(|reason| ContextFieldError::new(ContextField::Port, reason))?;
+        let remote_address = resolve_field(
+            &self.remote_address,
+            detected_remote_address,
+            FALLBACK_REMOTE_ADDRESS,
+        )
+        .map_err(|reason| ContextFieldError::new(ContextField::RemoteAddress, reason))?;
+
+        let context = SessionContext {
             user: self.user.clone(),
-            port: self.port.clone(),
-            remote_address: self.remote_address.clone(),
+            port,
+            remote_address,
             privilege_level: self.privilege_level,
             authentication_method: self.authentication_method,
-        }
+            authentication_type: None,
+            priority: self.priority,
+        };
+
+        Ok(context)
+    }
+}
+
+/// Checks that the value of a context field can be sent in a packet.
+fn validate_field(
+    value: impl TryInto<FieldText<'static>>,
+) -> Result<FieldText<'static>, FieldIssue> {
+    let text = value
+        .try_into()
+        .map_err(|_| FieldIssue::NotPrintableAscii)?;
+
+    if u8::try_from(text.len()).is_err() {
+        return Err(FieldIssue::TooLong { length: text.len() });
+    }
+
+    Ok(text)
+}
+
+/// Picks the value of a context field, preferring an explicit value over a detected one over the fallback.
+///
+/// An error is returned if the chosen value isn't valid in a packet.
+fn resolve_field(
+    explicit: &Option<Result<FieldText<'static>, FieldIssue>>,
+    detected: Option<&str>,
+    fallback: &'static str,
+) -> Result<FieldText<'static>, FieldIssue> {
+    match explicit {
+        Some(validated) => validated.clone(),
+        None => match detected {
+            Some(detected) => validate_field(detected.to_owned()),
+            // the fallback is borrowed for as long as the context lives, so it doesn't have to be copied
+            None => validate_field(fallback),
+        },
     }
 }
@@ -0,0 +1,97 @@
+use super::{ContextBuilder, ContextField, ContextFieldError, FieldIssue};
+use crate::ClientError;
+use tacacs_plus_protocol::FieldText;
+
+#[test]
+fn valid_fields_cached() {
+    let context = ContextBuilder::new(String::from("someuser"))
+        .port("tty0")
+        .remote_address(String::from("192.0.2.1"))
+        .build()
+        .expect("context should be valid");
+
+    let information = context.as_user_information().unwrap();
+    assert_eq!(information.user(), "someuser");
+    assert_eq!(*information.port(), "tty0");
+    assert_eq!(*information.remote_address(), "192.0.2.1");
+}
+
+#[test]
+fn invalid_fields_rejected_when_built() {
+    assert_eq!(
+        ContextBuilder::new("a".repeat(256)).build(),
+        Err(ContextFieldError {
+            field: ContextField::User,
+            reason: FieldIssue::TooLong { length: 256 }
+        })
+    );
+
+    assert_eq!(
+        ContextBuilder::new(String::from("someuser"))
+            .port("p".repeat(256))
+            .build(),
+        Err(ContextFieldError {
+            field: ContextField::Port,
+            reason: FieldIssue::TooLong { length: 256 }
+        })
+    );
+
+    assert_eq!(
+        ContextBuilder::new(String::from("someuser"))
+            .remote_address(String::from("tab\taddress"))
+            .build(),
+        Err(ContextFieldError {
+            field: ContextField::RemoteAddress,
+            reason: FieldIssue::NotPrintableAscii
+        })
+    );
+}
+
+#[test]
+fn earlier_invalid_setter_value_kept_until_replaced() {
+    let mut builder = ContextBuilder::new(String::from("someuser"));
+    builder
+        .port(String::from("tty\u{1F980}"))
+        .remote_address(String::from("192.0.2.1"));
+
+    let error = builder.build().unwrap_err();
+    assert_eq!(error.field, ContextField::Port);
+    assert_eq!(error.reason, FieldIssue::NotPrintableAscii);
+
+    builder.port(String::from("tty0"));
+    assert!(builder.build().is_ok());
+}
+
+#[test]
+fn invalid_field_detail_kept_in_client_error() {
+    let error: ClientError = ContextBuilder::new(String::from("someuser"))
+        .remote_address("a".repeat(300))
+        .build()
+        .unwrap_err()
+        .into();
+
+    assert!(matches!(
+        error,
+        ClientError::InvalidContext(ContextFieldError {
+            field: ContextField::RemoteAddress,
+            reason: FieldIssue::TooLong { length: 300 }
+        })
+    ));
+    assert_eq!(
+        error.to_string(),
+        "session context had invalid field: remote address is 300 bytes long, but at most 255 bytes fit in a packet"
+    );
+}
+
+#[test]
+fn field_text_values_accepted() {
+    let context = ContextBuilder::new(String::from("someuser"))
+        .port(FieldText::try_from("vty2").unwrap())
+        .remote_address("198.51.100.7")
+        .build()
+        .unwrap();
+
+    let information = context.as_user_information().unwrap();
+    assert_eq!(*information.port(), "vty2");
+    assert_eq!(*information.remote_address(), "198.51.100.7");
+}
@@ -0,0 +1,97 @@
+//! Local logging of accounting records for servers that keep rejecting them.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use tacacs_plus_protocol::accounting::Flags;
+use tacacs_plus_protocol::Argument;
+
+use super::SessionContext;
+
+#[cfg(test)]
+mod tests;
+
+/// An accounting record that was logged locally rather than sent to the server, since accounting was degraded.
+///
+/// See [`ClientBuilder::accounting_fallback()`](super::ClientBuilder::accounting_fallback) for details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalAccountingRecord {
+    /// The kind of record, i.e. whether it's a start, update (watchdog) or stop record.
+    pub flags: Flags,
+
+    /// The context of the task the record is for.
+    pub context: SessionContext,
+
+    /// The arguments of the record, including those added by the client (e.g. `task_id` & `start_time`).
+    pub arguments: Vec<Argument<'static>>,
+
+    /// The wall clock time at which the record would have been sent.
+    pub timestamp: SystemTime,
+}
+
+/// A callback that receives accounting records while accounting is degraded.
+pub(super) type LocalAccountingLog = Arc<dyn Fn(&LocalAccountingRecord) + Send + Sync>;
+
+/// Tracks consecutive accounting errors from a server, switching accounting to local logging after too many.
+pub(super) struct AccountingFallback {
+    /// The number of consecutive errors after which accounting is degraded.
+    error_threshold: u32,
+
+    consecutive_errors: AtomicU32,
+    degraded: AtomicBool,
+    log: LocalAccountingLog,
+}
+
+impl fmt::Debug for AccountingFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountingFallback")
+            .field("error_threshold", &self.error_threshold)
+            .field("consecutive_errors", &self.consecutive_errors)
+            .field("degraded", &self.degraded)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AccountingFallback {
+    pub(super) fn new(error_threshold: u32, log: LocalAccountingLog) -> Self {
+        Self {
+            // a threshold of zero would degrade accounting before a server ever got a chance to reject a record
+            error_threshold: error_threshold.max(1),
+            consecutive_errors: AtomicU32::new(0),
+            degraded: AtomicBool::new(false),
+            log,
+        }
+    }
+
+    /// Whether records are currently logged locally instead of being sent.
+    pub(super) fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire)
+    }
+
+    /// Counts an error reply from the server, returning the number of consecutive errors if that degraded accounting.
+    pub(super) fn record_error(&self) -> Option<u32> {
+        let errors = self.consecutive_errors.fetch_add(1, Ordering::AcqRel) + 1;
+
+        // only the error that crossed the threshold reports it, even if others arrive concurrently
+        (errors >= self.error_threshold && !self.degraded.swap(true, Ordering::AcqRel))
+            .then_some(errors)
+    }
+
+    /// Resets the error count after a record was accepted by the server.
+    pub(super) fn record_success(&self) {
+        self.consecutive_errors.store(0, Ordering::Release);
+    }
+
+    /// Leaves degraded mode, returning whether accounting was degraded before.
+    pub(super) fn resume(&self) -> bool {
+        self.consecutive_errors.store(0, Ordering::Release);
+        self.degraded.swap(false, Ordering::AcqRel)
+    }
+
+    /// Passes a record to the local log.
+    pub(super) fn log(&self, record: &LocalAccountingRecord) {
+        (self.log)(record);
+    }
+}
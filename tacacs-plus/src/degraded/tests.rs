@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+
+use tacacs_plus_protocol::accounting::Flags;
+use tacacs_plus_protocol::PacketType;
+
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{Client, ClientBuilder, ClientError, ContextBuilder, LocalAccountingRecord};
+
+/// An accounting reply body with an empty server message & data and an ERROR status.
+const ERROR_REPLY: [u8; 5] = [0, 0, 0, 0, 0x02];
+
+fn degrading_client(
+    received: &ReceivedRequests,
+    logged: &Arc<Mutex<Vec<LocalAccountingRecord>>>,
+) -> Client<AcceptingServer> {
+    let received = received.clone();
+    let logged = logged.clone();
+
+    ClientBuilder::new()
+        .accounting_fallback(2, move |record| logged.lock().unwrap().push(record.clone()))
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(received.clone(), false)
+                .with_accounting_reply(ERROR_REPLY.into());
+            Box::pin(async move { Ok(server) })
+        }))
+}
+
+fn sent_records(received: &ReceivedRequests) -> usize {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(packet_type, _)| *packet_type == PacketType::Accounting)
+        .count()
+}
+
+#[tokio::test]
+async fn records_logged_locally_after_repeated_errors() {
+    let received = ReceivedRequests::default();
+    let logged = Arc::default();
+    let client = degrading_client(&received, &logged);
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    // the first error is reported as usual
+    let Err(error) = client.account_begin(context.clone(), []).await else {
+        panic!("first error shouldn't degrade accounting");
+    };
+    assert!(
+        matches!(error, ClientError::AccountingError { .. }),
+        "unexpected error: {error:?}"
+    );
+    assert!(!client.is_accounting_degraded());
+
+    // the error that crosses the threshold degrades accounting, and its record is logged instead
+    let (_, response) = client.account_begin(context.clone(), []).await.unwrap();
+    assert!(response.logged_locally);
+    assert!(client.is_accounting_degraded());
+
+    // later records aren't sent to the server at all
+    let (task, response) = client.account_begin(context.clone(), []).await.unwrap();
+    assert!(response.logged_locally);
+    task.stop([]).await.unwrap();
+    assert_eq!(sent_records(&received), 2);
+
+    let logged = logged.lock().unwrap();
+    let flags: Vec<_> = logged.iter().map(|record| record.flags).collect();
+    assert_eq!(
+        flags,
+        [Flags::StartRecord, Flags::StartRecord, Flags::StopRecord]
+    );
+    assert!(logged.iter().all(|record| record.context == context));
+    assert!(logged.iter().all(|record| record
+        .arguments
+        .iter()
+        .any(|argument| argument.to_string().starts_with("task_id="))));
+}
+
+#[tokio::test]
+async fn resumed_accounting_sends_records_again() {
+    let received = ReceivedRequests::default();
+    let logged = Arc::default();
+    let client = degrading_client(&received, &logged);
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let _ = client.account_begin(context.clone(), []).await;
+    }
+    assert!(client.is_accounting_degraded());
+
+    client.resume_accounting();
+    assert!(!client.is_accounting_degraded());
+
+    // the server is asked again, and its error is reported since the count of consecutive errors was reset
+    let Err(error) = client.account_begin(context, []).await else {
+        panic!("server should be asked again after resuming");
+    };
+    assert!(
+        matches!(error, ClientError::AccountingError { .. }),
+        "unexpected error: {error:?}"
+    );
+    assert_eq!(sent_records(&received), 3);
+    assert_eq!(logged.lock().unwrap().len(), 1);
+}
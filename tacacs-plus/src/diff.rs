@@ -0,0 +1,146 @@
+//! The changes a server made to the arguments of an authorization request.
+
+use std::collections::{HashMap, HashSet};
+
+use tacacs_plus_protocol::authorization::Status;
+use tacacs_plus_protocol::Argument;
+
+#[cfg(test)]
+mod tests;
+
+/// How the arguments of an authorization request were changed by the server's reply, as included in an
+/// [`AuthorizationResponse`](super::AuthorizationResponse).
+///
+/// The merged [`arguments`](super::AuthorizationResponse::arguments) of a response only show the end result; this records
+/// what each argument was before, e.g. so audit logs can show exactly what the server changed.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::protocol::authorization::Status;
+/// use tacacs_plus::{Argument, ArgumentDiff, FieldText};
+///
+/// let argument = |name: &'static str, value: &'static str| {
+///     Argument::new(FieldText::try_from(name).unwrap(), FieldText::try_from(value).unwrap(), true).unwrap()
+/// };
+///
+/// let sent = [argument("service", "shell"), argument("priv-lvl", "1")];
+/// let received = [argument("priv-lvl", "15"), argument("timeout", "60")];
+///
+/// let diff = ArgumentDiff::new(Status::PassReplace, &sent, &received);
+/// assert_eq!(diff.added, [argument("timeout", "60")]);
+/// assert_eq!(diff.replaced[0].sent, argument("priv-lvl", "1"));
+/// assert_eq!(diff.replaced[0].received, argument("priv-lvl", "15"));
+/// assert_eq!(diff.unchanged, [argument("service", "shell")]);
+/// assert!(diff.dropped.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ArgumentDiff<'args> {
+    /// Received arguments that didn't replace a sent one, in the order they were received.
+    pub added: Vec<Argument<'args>>,
+
+    /// Sent arguments whose value was replaced, in the order they were sent.
+    pub replaced: Vec<ReplacedArgument<'args>>,
+
+    /// Sent arguments that were kept as-is, either since the server didn't mention them or since it replied with the
+    /// same value.
+    pub unchanged: Vec<Argument<'args>>,
+
+    /// Received arguments that were superseded by a later argument with the same name in the same reply, and so don't
+    /// appear in the merged arguments at all.
+    pub dropped: Vec<Argument<'args>>,
+}
+
+/// A sent argument whose value was replaced by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReplacedArgument<'args> {
+    /// The argument as sent.
+    pub sent: Argument<'args>,
+
+    /// The argument it was replaced with, whose value is used in the merged arguments.
+    pub received: Argument<'args>,
+}
+
+impl<'args> ArgumentDiff<'args> {
+    /// Computes the difference between the sent & received arguments of an authorization session with the provided
+    /// reply status.
+    ///
+    /// This mirrors how a [`Client`](super::Client) merges arguments: the arguments of a
+    /// [`PassReplace`](Status::PassReplace) reply replace the values of the first sent argument with the same name (with
+    /// later duplicates in the reply taking precedence), while those of any other reply are added as-is.
+    pub fn new(status: Status, sent: &[Argument<'args>], received: &[Argument<'args>]) -> Self {
+        if status != Status::PassReplace {
+            return Self {
+                added: received.to_vec(),
+                unchanged: sent.to_vec(),
+                ..Default::default()
+            };
+        }
+
+        // the last received argument with a given name is the one whose value ends up being used
+        let mut last_received = HashMap::with_capacity(received.len());
+        for (index, argument) in received.iter().enumerate() {
+            last_received.insert(argument.name().as_ref(), index);
+        }
+
+        let mut diff = Self::default();
+        let mut sent_names = HashSet::with_capacity(sent.len());
+
+        for argument in sent {
+            // only the first sent argument with a given name is replaced
+            let replacement = if sent_names.insert(argument.name().as_ref()) {
+                last_received
+                    .get(argument.name().as_ref())
+                    .map(|&index| &received[index])
+            } else {
+                None
+            };
+
+            match replacement {
+                Some(replacement) if replacement.value() != argument.value() => {
+                    diff.replaced.push(ReplacedArgument {
+                        sent: argument.clone(),
+                        received: replacement.clone(),
+                    })
+                }
+                _ => diff.unchanged.push(argument.clone()),
+            }
+        }
+
+        for (index, argument) in received.iter().enumerate() {
+            let name = argument.name().as_ref();
+
+            if last_received.get(name) != Some(&index) {
+                diff.dropped.push(argument.clone());
+            } else if !sent_names.contains(name) {
+                diff.added.push(argument.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Converts this diff to one that owns all of its arguments.
+    pub fn into_owned(self) -> ArgumentDiff<'static> {
+        let owned = |arguments: Vec<Argument<'_>>| {
+            arguments
+                .into_iter()
+                .map(Argument::into_owned)
+                .collect::<Vec<_>>()
+        };
+
+        ArgumentDiff {
+            added: owned(self.added),
+            replaced: self
+                .replaced
+                .into_iter()
+                .map(|replaced| ReplacedArgument {
+                    sent: replaced.sent.into_owned(),
+                    received: replaced.received.into_owned(),
+                })
+                .collect(),
+            unchanged: owned(self.unchanged),
+            dropped: owned(self.dropped),
+        }
+    }
+}
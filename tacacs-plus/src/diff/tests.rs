@@ -0,0 +1,76 @@
+use tacacs_plus_protocol::FieldText;
+
+use super::*;
+
+fn argument(name: &str, value: &str) -> Argument<'static> {
+    Argument::new(
+        FieldText::try_from(name).unwrap().into_owned(),
+        FieldText::try_from(value).unwrap().into_owned(),
+        true,
+    )
+    .unwrap()
+}
+
+#[test]
+fn added_arguments_leave_sent_unchanged() {
+    let sent = [argument("service", "shell")];
+    let received = [argument("service", "exec"), argument("priv-lvl", "15")];
+
+    let diff = ArgumentDiff::new(Status::PassAdd, &sent, &received);
+    assert_eq!(diff.added, received);
+    assert_eq!(diff.unchanged, sent);
+    assert!(diff.replaced.is_empty());
+    assert!(diff.dropped.is_empty());
+}
+
+#[test]
+fn replaced_arguments_classified() {
+    let sent = [
+        argument("service", "shell"),
+        argument("cmd", "show"),
+        argument("cmd-arg", "version"),
+    ];
+    let received = [
+        argument("new", "added"),
+        argument("cmd-arg", "running-config"),
+        argument("service", "shell"),
+    ];
+
+    let diff = ArgumentDiff::new(Status::PassReplace, &sent, &received);
+    assert_eq!(diff.added, [argument("new", "added")]);
+    assert_eq!(
+        diff.replaced,
+        [ReplacedArgument {
+            sent: argument("cmd-arg", "version"),
+            received: argument("cmd-arg", "running-config"),
+        }]
+    );
+
+    // replying with the same value doesn't count as a change
+    assert_eq!(
+        diff.unchanged,
+        [argument("service", "shell"), argument("cmd", "show")]
+    );
+    assert!(diff.dropped.is_empty());
+}
+
+#[test]
+fn superseded_duplicates_dropped() {
+    let sent = [argument("service", "shell")];
+    let received = [
+        argument("priv-lvl", "1"),
+        argument("service", "exec"),
+        argument("priv-lvl", "15"),
+    ];
+
+    let diff = ArgumentDiff::new(Status::PassReplace, &sent, &received);
+    assert_eq!(diff.added, [argument("priv-lvl", "15")]);
+    assert_eq!(diff.dropped, [argument("priv-lvl", "1")]);
+    assert_eq!(
+        diff.replaced,
+        [ReplacedArgument {
+            sent: argument("service", "shell"),
+            received: argument("service", "exec"),
+        }]
+    );
+}
@@ -0,0 +1,44 @@
+//! Type-erased connections, for using a single client type with multiple transports.
+
+use futures::{AsyncRead, AsyncWrite};
+
+use super::inner::ConnectionFactory;
+use super::Client;
+
+#[cfg(test)]
+mod tests;
+
+/// A connection that can be used by a [`DynClient`].
+///
+/// This is automatically implemented for all types that implement its supertraits, and only exists since
+/// trait objects can't be made from several non-auto traits directly.
+pub trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Connection for S {}
+
+/// A boxed connection with its concrete type erased.
+pub type DynConnection = Box<dyn Connection>;
+
+/// A [`Client`] whose connection type is erased.
+///
+/// Since [`Client`] is generic over its connection type, its internals are duplicated for each transport it is used with.
+/// Applications that use several transports (or that are sensitive to binary size) can use this type for all of them instead,
+/// at the cost of dynamic dispatch for connection I/O.
+///
+/// A `DynClient` can be created from a factory returning any type of connection with [`ClientBuilder::build_dyn()`](super::ClientBuilder::build_dyn),
+/// and otherwise has the same API as any other [`Client`].
+pub type DynClient = Client<DynConnection>;
+
+/// Wraps a connection factory to box the connections it returns.
+pub(super) fn erase_connection_factory<S: Connection + 'static>(
+    factory: ConnectionFactory<S>,
+) -> ConnectionFactory<DynConnection> {
+    Box::new(move || {
+        let connection_future = factory();
+
+        Box::pin(async move {
+            let connection = connection_future.await?;
+            Ok(Box::new(connection) as DynConnection)
+        })
+    })
+}
@@ -0,0 +1,72 @@
+use tacacs_plus_protocol::PacketType;
+
+use crate::tests::{authorization_reply, AcceptingServer, ReceivedRequests};
+use crate::{AuthenticationType, ClientBuilder, ContextBuilder, DynClient, ResponseStatus};
+
+#[tokio::test]
+async fn sessions_run_over_erased_connections() {
+    let received = ReceivedRequests::default();
+
+    // the two clients have different connection types, but share a client type
+    let clients: [DynClient; 2] = [
+        ClientBuilder::new().build_dyn(Box::new({
+            let received = received.clone();
+            move || {
+                let server = AcceptingServer::recording(received.clone(), false);
+                Box::pin(async move { Ok(server) })
+            }
+        })),
+        ClientBuilder::new().build_dyn(Box::new({
+            let received = received.clone();
+            move || {
+                // boxing the server makes it a different type than the one above
+                let server = Box::new(
+                    AcceptingServer::recording(received.clone(), false)
+                        .with_authorization_reply(authorization_reply(0x10, &[])),
+                );
+                Box::pin(async move { Ok(server) })
+            }
+        })),
+    ];
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    for client in &clients {
+        let response = client
+            .authenticate(context.clone(), "password", AuthenticationType::Pap)
+            .await
+            .expect("authentication session should complete");
+        assert_eq!(response.status, ResponseStatus::Success);
+    }
+
+    let [accepting, rejecting] = &clients;
+    let accepted = accepting
+        .authorize(context.clone(), [])
+        .await
+        .expect("authorization session should complete");
+    assert_eq!(accepted.status, ResponseStatus::Success);
+
+    // FAIL status
+    let rejected = rejecting
+        .authorize(context, [])
+        .await
+        .expect("authorization session should complete");
+    assert_eq!(rejected.status, ResponseStatus::Failure);
+
+    let packet_types: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(packet_type, _)| *packet_type)
+        .collect();
+    assert_eq!(
+        packet_types,
+        [
+            PacketType::Authentication,
+            PacketType::Authentication,
+            PacketType::Authorization,
+            PacketType::Authorization
+        ]
+    );
+}
@@ -0,0 +1,175 @@
+//! Encryption of sensitive authorization argument values on top of body obfuscation.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use tacacs_plus_protocol::{Argument, FieldText, InvalidArgument};
+
+use super::ClientError;
+
+#[cfg(test)]
+mod tests;
+
+/// A site-specific transform protecting the values of sensitive authorization arguments, as configured via
+/// [`ClientBuilder::argument_encryption()`](super::ClientBuilder::argument_encryption).
+///
+/// Argument values are only protected in transit by the obfuscation of packet bodies, which some deployments don't
+/// consider strong enough for secrets. A cipher is applied to the values of designated arguments in every
+/// authorization request, and the values of the same arguments in replies are decrypted with it, so the server
+/// (or whatever it hands the values off to) has to implement the same scheme.
+///
+/// Encrypted values have to be printable ASCII & short enough to fit in an argument, so binary ciphertext should be
+/// encoded (e.g. as hex or base64) before it's returned.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{ArgumentCipher, CipherError};
+///
+/// /// Hex-encodes values, standing in for a real encryption scheme.
+/// #[derive(Debug)]
+/// struct Hex;
+///
+/// impl ArgumentCipher for Hex {
+///     fn encrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+///         Ok(value.bytes().map(|byte| format!("{byte:02x}")).collect())
+///     }
+///
+///     fn decrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+///         (0..value.len())
+///             .step_by(2)
+///             .map(|start| value.get(start..start + 2).and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+///             .map(|byte| byte.map(char::from))
+///             .collect::<Option<String>>()
+///             .ok_or_else(|| CipherError::new("invalid hex"))
+///     }
+/// }
+///
+/// assert_eq!(Hex.encrypt("token", "secret").unwrap(), "736563726574");
+/// assert_eq!(Hex.decrypt("token", "736563726574").unwrap(), "secret");
+/// ```
+pub trait ArgumentCipher: fmt::Debug + Send + Sync {
+    /// Encrypts the value of an argument before it's sent to a server.
+    fn encrypt(&self, name: &str, value: &str) -> Result<String, CipherError>;
+
+    /// Decrypts the value of an argument received from a server.
+    fn decrypt(&self, name: &str, value: &str) -> Result<String, CipherError>;
+}
+
+/// The reason an [`ArgumentCipher`] couldn't encrypt or decrypt a value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+#[error("{message}")]
+pub struct CipherError {
+    message: String,
+}
+
+impl CipherError {
+    /// Creates an error with a description of what went wrong.
+    pub fn new<M: Into<String>>(message: M) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// The description of what went wrong.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Which way an argument value was being transformed by an [`ArgumentCipher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CipherDirection {
+    /// The value was being encrypted before being sent.
+    Encrypt,
+
+    /// The value was being decrypted after being received.
+    Decrypt,
+}
+
+impl fmt::Display for CipherDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Encrypt => write!(f, "encrypt"),
+            Self::Decrypt => write!(f, "decrypt"),
+        }
+    }
+}
+
+/// A cipher along with the names of the arguments whose values it's applied to.
+#[derive(Debug, Clone)]
+pub(crate) struct ArgumentEncryption {
+    cipher: Arc<dyn ArgumentCipher>,
+    names: BTreeSet<String>,
+}
+
+impl ArgumentEncryption {
+    pub(crate) fn new(cipher: Arc<dyn ArgumentCipher>, names: BTreeSet<String>) -> Self {
+        Self { cipher, names }
+    }
+
+    /// Encrypts the values of the protected arguments in a request, borrowing the arguments if none are protected.
+    pub(crate) fn encrypt<'a, 'args>(
+        &self,
+        arguments: &'a [Argument<'args>],
+    ) -> Result<Cow<'a, [Argument<'args>]>, ClientError> {
+        if !arguments.iter().any(|argument| self.protects(argument)) {
+            return Ok(Cow::Borrowed(arguments));
+        }
+
+        arguments
+            .iter()
+            .map(|argument| self.transform(argument.clone(), CipherDirection::Encrypt))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Cow::Owned)
+    }
+
+    /// Decrypts the values of the protected arguments in a reply.
+    pub(crate) fn decrypt<'args>(
+        &self,
+        arguments: Vec<Argument<'args>>,
+    ) -> Result<Vec<Argument<'args>>, ClientError> {
+        arguments
+            .into_iter()
+            .map(|argument| self.transform(argument, CipherDirection::Decrypt))
+            .collect()
+    }
+
+    /// Whether an argument's value is protected, which unspecified values never are since they have no value to hide.
+    fn protects(&self, argument: &Argument<'_>) -> bool {
+        !argument.is_unspecified() && self.names.contains(argument.name().as_ref())
+    }
+
+    fn transform<'args>(
+        &self,
+        argument: Argument<'args>,
+        direction: CipherDirection,
+    ) -> Result<Argument<'args>, ClientError> {
+        if !self.protects(&argument) {
+            return Ok(argument);
+        }
+
+        let name = argument.name().as_ref();
+        let value = argument.value().as_ref();
+        let transformed = match direction {
+            CipherDirection::Encrypt => self.cipher.encrypt(name, value),
+            CipherDirection::Decrypt => self.cipher.decrypt(name, value),
+        }
+        .map_err(|source| ClientError::ArgumentCipherFailed {
+            argument: name.to_owned(),
+            direction,
+            source,
+        })?;
+
+        let value = FieldText::try_from(transformed).map_err(|_| InvalidArgument::BadText)?;
+        Ok(Argument::new(
+            argument.name().clone(),
+            value,
+            argument.mandatory(),
+        )?)
+    }
+}
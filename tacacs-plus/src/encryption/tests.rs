@@ -0,0 +1,135 @@
+use super::*;
+use crate::tests::{argument, authorization_reply, AcceptingServer, ReceivedRequests};
+use crate::{ClientBuilder, ContextBuilder, ResponseStatus};
+
+/// Swaps the case of every letter, failing on values that aren't entirely lowercase or uppercase.
+#[derive(Debug)]
+struct SwapCase;
+
+impl SwapCase {
+    fn swap(value: &str, from_lowercase: bool) -> Result<String, CipherError> {
+        let expected_case = if from_lowercase {
+            char::is_ascii_lowercase
+        } else {
+            char::is_ascii_uppercase
+        };
+
+        if value.chars().all(|c| expected_case(&c)) {
+            Ok(value.chars().map(|c| (c as u8 ^ 0x20) as char).collect())
+        } else {
+            Err(CipherError::new("mixed case"))
+        }
+    }
+}
+
+impl ArgumentCipher for SwapCase {
+    fn encrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+        Self::swap(value, true)
+    }
+
+    fn decrypt(&self, _name: &str, value: &str) -> Result<String, CipherError> {
+        Self::swap(value, false)
+    }
+}
+
+fn encryption() -> ArgumentEncryption {
+    ArgumentEncryption::new(Arc::new(SwapCase), BTreeSet::from([String::from("token")]))
+}
+
+#[test]
+fn only_protected_values_encrypted() {
+    let arguments = [
+        argument("service", "shell"),
+        argument("token", "secret"),
+        Argument::unspecified(FieldText::try_from("token").unwrap()).unwrap(),
+    ];
+
+    let encrypted = encryption().encrypt(&arguments).unwrap();
+    assert_eq!(
+        *encrypted,
+        [
+            argument("service", "shell"),
+            argument("token", "SECRET"),
+            // there's no value to protect in unspecified arguments
+            Argument::unspecified(FieldText::try_from("token").unwrap()).unwrap(),
+        ]
+    );
+
+    // arguments are only copied if any of them are actually encrypted
+    let unprotected = [argument("service", "shell")];
+    assert!(matches!(
+        encryption().encrypt(&unprotected).unwrap(),
+        Cow::Borrowed(_)
+    ));
+}
+
+#[tokio::test]
+async fn protected_values_encrypted_in_transit() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let reply = authorization_reply(0x01, &["token=ROTATED", "idletime=5"]);
+
+    let client = ClientBuilder::new()
+        .argument_encryption(SwapCase, ["token"])
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(server_received.clone(), false)
+                .with_authorization_reply(reply.clone());
+            Box::pin(async move { Ok(server) })
+        }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let response = client
+        .authorize(
+            context,
+            [argument("service", "shell"), argument("token", "secret")],
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    // only the ciphertext of the protected value is sent to the server
+    let (_, request) = received.lock().unwrap().pop().unwrap();
+    assert!(request.ends_with(b"service=shelltoken=SECRET"));
+
+    // the caller only sees plaintext values
+    assert_eq!(
+        response.arguments,
+        [
+            argument("service", "shell"),
+            argument("token", "secret"),
+            argument("token", "rotated"),
+            argument("idletime", "5"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn undecryptable_reply_value_rejected() {
+    let reply = authorization_reply(0x01, &["token=NotCiphertext"]);
+
+    let client = ClientBuilder::new()
+        .argument_encryption(SwapCase, ["token"])
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(Default::default(), false)
+                .with_authorization_reply(reply.clone());
+            Box::pin(async move { Ok(server) })
+        }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let error = client.authorize(context, []).await.unwrap_err();
+    assert!(
+        matches!(
+            &error,
+            ClientError::ArgumentCipherFailed {
+                argument,
+                direction: CipherDirection::Decrypt,
+                source,
+            } if argument == "token" && source.message() == "mixed case"
+        ),
+        "unexpected error: {error:?}"
+    );
+}
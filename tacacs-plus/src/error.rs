@@ -1,9 +1,22 @@
+use std::collections::TryReserveError;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::Duration;
+
 use futures::io;
 use thiserror::Error;
 
 use tacacs_plus_protocol as protocol;
+
+use super::{
+    CipherDirection, CipherError, ContextFieldError, PasswordPolicyViolation, SchemaIssue,
+    TimeoutKind,
+};
 use tacacs_plus_protocol::{accounting, authentication, authorization};
 
+#[cfg(test)]
+mod tests;
+
 /// An error during a TACACS+ exchange.
 #[non_exhaustive]
 #[derive(Debug, Error)]
@@ -12,6 +25,13 @@ pub enum ClientError {
     #[error(transparent)]
     IOError(#[from] io::Error),
 
+    /// The server closed the connection before a session was completed.
+    #[error("connection closed by server while {during}")]
+    ConnectionClosedByServer {
+        /// The phase of the session that was interrupted by the connection closing.
+        during: OperationPhase,
+    },
+
     /// TACACS+ protocol error, e.g. an authentication failure.
     #[error("error in TACACS+ protocol exchange")]
     ProtocolError {
@@ -23,7 +43,7 @@ pub enum ClientError {
     },
 
     /// TACACS+ protocol error, as reported from a server during authentication.
-    #[error("error when performing TACACS+ authentication")]
+    #[error("error when performing TACACS+ authentication (status: {status})")]
     AuthenticationError {
         /// The status returned from the server, which will not be `Pass` or `Fail`.
         status: authentication::Status,
@@ -35,9 +55,11 @@ pub enum ClientError {
         user_message: String,
     },
 
-    // TODO: more descriptive error message
     /// Error when performing authorization.
-    #[error("error when performing TACACS+ authorization")]
+    #[error(
+        "error when performing TACACS+ authorization (status: {status}){}",
+        ServerMessages::new(.user_message, .admin_message)
+    )]
     AuthorizationError {
         /// The status received from the server.
         status: authorization::Status,
@@ -50,7 +72,10 @@ pub enum ClientError {
     },
 
     /// Error when performing accounting.
-    #[error("error when performing TACACS+ accounting")]
+    #[error(
+        "error when performing TACACS+ accounting (status: {status}){}",
+        ServerMessages::new(.user_message, .admin_message)
+    )]
     AccountingError {
         /// The status returned by the server.
         status: accounting::Status,
@@ -74,9 +99,31 @@ pub enum ClientError {
     #[error("packet could not be constructed from provided data")]
     InvalidPacketData,
 
-    /// The provided authentication password's length exceeded the valid range (i.e., 0 to `u8::MAX`, less some other data stored in the same field).
-    #[error("authentication data field (including password) was longer than 255 bytes")]
-    PasswordTooLong,
+    /// The provided authentication password was too long for the packet field it's sent in.
+    ///
+    /// For PAP, the password itself is limited to 255 bytes; see
+    /// [`AuthenticationType::max_password_len()`](super::AuthenticationType::max_password_len) to check passwords
+    /// before starting a session.
+    #[error("authentication data (including password) was {length} bytes long, but at most {limit} bytes fit in a packet")]
+    PasswordTooLong {
+        /// The length of the data the password was encoded into, in bytes.
+        length: usize,
+
+        /// The maximum length of that data, in bytes.
+        limit: usize,
+    },
+
+    /// No [`StartDataBuilder`](super::StartDataBuilder) was registered for a custom authentication type.
+    #[error("no start data builder registered for custom authentication type \"{0}\"")]
+    UnregisteredAuthenticationType(&'static str),
+
+    /// A new password was rejected by the client's [`PasswordPolicy`](super::PasswordPolicy) before being sent to the server.
+    #[error("new password rejected by client-side policy: {0}")]
+    PasswordPolicyViolation(#[from] PasswordPolicyViolation),
+
+    /// Arguments didn't conform to an [`ArgumentSchema`](super::ArgumentSchema) configured on the client.
+    #[error("{} argument issue(s) found when validating against schema", .0.len())]
+    ArgumentSchemaViolation(Vec<SchemaIssue>),
 
     /// Too many arguments were provided to fit in a packet.
     #[error("only up to 255 (i.e., `u8::MAX`) arguments fit in a packet")]
@@ -86,9 +133,27 @@ pub enum ClientError {
     #[error(transparent)]
     InvalidArgument(#[from] protocol::InvalidArgument),
 
-    /// Context had an invalid field.
-    #[error("session context had invalid field(s)")]
-    InvalidContext,
+    /// The value of an argument protected by the client's [`ArgumentCipher`](super::ArgumentCipher) couldn't be
+    /// encrypted or decrypted.
+    #[error("failed to {direction} value of protected argument \"{argument}\"")]
+    ArgumentCipherFailed {
+        /// The name of the argument.
+        argument: String,
+
+        /// Whether the value was being encrypted or decrypted.
+        direction: CipherDirection,
+
+        /// The error returned by the cipher.
+        source: CipherError,
+    },
+
+    /// Context had an invalid field, with which field was invalid & why.
+    #[error("session context had invalid field: {0}")]
+    InvalidContext(#[from] ContextFieldError),
+
+    /// The server granted a privilege level that isn't an integer between 0 & 15.
+    #[error("server granted invalid privilege level {0:?}")]
+    InvalidPrivilegeLevel(String),
 
     /// Sequence number in reply did not match what was expected.
     #[error("sequence number mismatch: expected {expected}, got {actual}")]
@@ -107,17 +172,180 @@ pub enum ClientError {
     #[error("sequence numberflow overflowed maximum, so session was terminated")]
     SequenceNumberOverflow,
 
+    /// A buffer for a packet couldn't be allocated, e.g. due to a memory limit on the process or a bogus length
+    /// reported by the server.
+    #[error("failed to allocate {size} bytes for packet buffer")]
+    AllocationFailed {
+        /// The size of the buffer that couldn't be allocated.
+        size: usize,
+
+        /// The underlying allocation error.
+        #[source]
+        source: TryReserveError,
+    },
+
+    /// A connection was opened to a peer without a secret of its own, while the client's default secret was already bound
+    /// to a different peer.
+    ///
+    /// See [`PeerSecrets`](super::PeerSecrets) for how secrets are bound to peers.
+    #[error("refusing to use the secret bound to {bound} with unexpected peer {peer}")]
+    UnexpectedPeer {
+        /// The peer of the rejected connection.
+        peer: SocketAddr,
+
+        /// The peer the default secret was bound to.
+        bound: SocketAddr,
+    },
+
+    /// A step of an [interactive authentication](super::Client::authenticate_interactive) session took longer than
+    /// allowed by its [`PromptTimeouts`](super::PromptTimeouts).
+    #[error("timed out waiting for {kind} after {timeout:?}")]
+    PromptTimeout {
+        /// Which side of the session failed to respond in time.
+        kind: TimeoutKind,
+
+        /// The timeout that elapsed.
+        timeout: Duration,
+    },
+
+    /// A session failed, and cleaning up its connection afterwards failed as well.
+    ///
+    /// The underlying [`CleanupError`] holds both errors, so the cleanup error doesn't hide why the session failed.
+    #[error(transparent)]
+    CleanupFailed(Box<CleanupError>),
+
+    /// The duration of a task passed to [`Client::account_stop_once()`](super::Client::account_stop_once), or the time
+    /// elapsed since an [`AccountingTask`](super::AccountingTask) started, is too long to be added to its start time.
+    #[error("task duration {duration:?} overflows its start time")]
+    TaskDurationOverflow {
+        /// The duration of the task.
+        duration: Duration,
+    },
+
     /// The system time was set before the Unix epoch, which is problematic for generating
     /// timestamps during accounting.
     #[error("system time was set before Unix epoch")]
     SystemTimeBeforeEpoch(#[from] std::time::SystemTimeError),
 }
 
-// authentication data being too long is a direct result of the password being too long
-// hidden since this is an implementation detail that isn't important to library consumers
-#[doc(hidden)]
-impl From<authentication::DataTooLong> for ClientError {
-    fn from(_value: authentication::DataTooLong) -> Self {
-        Self::PasswordTooLong
+/// An error that caused a session to fail, along with an error from cleaning up its connection afterwards.
+///
+/// The message only describes the cleanup error, as the primary error is exposed as the [source](std::error::Error::source).
+#[derive(Debug, Error)]
+#[error("connection cleanup failed after session error: {cleanup}")]
+pub struct CleanupError {
+    #[source]
+    primary: ClientError,
+    cleanup: io::Error,
+}
+
+impl CleanupError {
+    /// The error that caused the session to fail.
+    pub fn primary(&self) -> &ClientError {
+        &self.primary
+    }
+
+    /// The error that occurred while cleaning up the connection, e.g. when closing it.
+    pub fn cleanup(&self) -> &io::Error {
+        &self.cleanup
+    }
+
+    /// Splits this error into the primary & cleanup errors.
+    pub fn into_parts(self) -> (ClientError, io::Error) {
+        (self.primary, self.cleanup)
+    }
+}
+
+/// Combines the result of a session with the result of cleaning up its connection afterwards.
+///
+/// A cleanup error is only returned on its own if the session itself succeeded.
+pub(super) fn with_cleanup_result<T>(
+    result: Result<T, ClientError>,
+    cleanup_result: io::Result<()>,
+) -> Result<T, ClientError> {
+    match (result, cleanup_result) {
+        (result, Ok(())) => result,
+        (Ok(_), Err(cleanup)) => Err(cleanup.into()),
+        (Err(primary), Err(cleanup)) => Err(ClientError::CleanupFailed(Box::new(CleanupError {
+            primary,
+            cleanup,
+        }))),
+    }
+}
+
+/// The messages sent by a server in an error reply, formatted for inclusion in an error message.
+///
+/// Empty messages are omitted, and each message is sanitized since they come straight from the server
+/// and are likely to end up in logs/terminals.
+struct ServerMessages<'msg> {
+    user: &'msg str,
+    admin: &'msg str,
+}
+
+impl<'msg> ServerMessages<'msg> {
+    /// The maximum number of characters of each message that are displayed.
+    const MAX_DISPLAYED_LENGTH: usize = 200;
+
+    fn new(user: &'msg str, admin: &'msg str) -> Self {
+        Self { user, admin }
+    }
+
+    fn write_sanitized(f: &mut fmt::Formatter<'_>, message: &str) -> fmt::Result {
+        for c in message.chars().take(Self::MAX_DISPLAYED_LENGTH) {
+            // escape anything that could mess with terminal output, e.g. newlines or ANSI escape sequences
+            if c == ' ' || c.is_ascii_graphic() {
+                write!(f, "{c}")?;
+            } else {
+                write!(f, "{}", c.escape_default())?;
+            }
+        }
+
+        if message.chars().nth(Self::MAX_DISPLAYED_LENGTH).is_some() {
+            write!(f, "...")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ServerMessages<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.user.is_empty() {
+            write!(f, "; user message: \"")?;
+            Self::write_sanitized(f, self.user)?;
+            write!(f, "\"")?;
+        }
+
+        if !self.admin.is_empty() {
+            write!(f, "; admin message: \"")?;
+            Self::write_sanitized(f, self.admin)?;
+            write!(f, "\"")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A phase of a TACACS+ session, from the perspective of a client.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperationPhase {
+    /// Writing a request packet to the server.
+    SendingRequest,
+
+    /// Waiting for a reply from the server after sending a request.
+    AwaitingReply,
+}
+
+impl fmt::Display for OperationPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::SendingRequest => "sending request",
+                Self::AwaitingReply => "awaiting reply",
+            }
+        )
     }
 }
@@ -0,0 +1,94 @@
+use tacacs_plus_protocol::{accounting, authorization};
+
+use std::error::Error;
+use std::io;
+
+use super::{with_cleanup_result, ClientError};
+use crate::{ContextField, ContextFieldError, FieldIssue};
+
+#[test]
+fn accounting_error_display_includes_messages() {
+    let error = ClientError::AccountingError {
+        status: accounting::Status::Error,
+        user_message: "accounting unavailable".to_owned(),
+        admin_message: "disk full".to_owned(),
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "error when performing TACACS+ accounting (status: error); user message: \"accounting unavailable\"; admin message: \"disk full\""
+    );
+}
+
+#[test]
+fn authorization_error_display_omits_empty_messages() {
+    let error = ClientError::AuthorizationError {
+        status: authorization::Status::Error,
+        user_message: String::new(),
+        admin_message: "backend timeout".to_owned(),
+    };
+
+    assert_eq!(
+        error.to_string(),
+        "error when performing TACACS+ authorization (status: server-side error); admin message: \"backend timeout\""
+    );
+}
+
+#[test]
+fn server_messages_are_sanitized() {
+    let error = ClientError::AccountingError {
+        status: accounting::Status::Error,
+        user_message: "line one\nline two\u{1b}[31m".to_owned(),
+        admin_message: "x".repeat(300),
+    };
+
+    let message = error.to_string();
+    assert!(message.contains(r#"user message: "line one\nline two\u{1b}[31m""#));
+    assert!(message.ends_with(&format!("admin message: \"{}...\"", "x".repeat(200))));
+}
+
+#[test]
+fn cleanup_error_does_not_mask_primary_error() {
+    let primary = ClientError::AuthorizationError {
+        status: authorization::Status::Error,
+        user_message: String::new(),
+        admin_message: String::new(),
+    };
+    let cleanup = io::Error::new(io::ErrorKind::BrokenPipe, "close failed");
+
+    let error = with_cleanup_result::<()>(Err(primary), Err(cleanup)).unwrap_err();
+    // the primary error is only reported as the source, so it isn't printed twice when walking the chain
+    assert_eq!(
+        error.to_string(),
+        "connection cleanup failed after session error: close failed"
+    );
+
+    let ClientError::CleanupFailed(error) = error else {
+        panic!("expected both errors to be kept, got {error:?}");
+    };
+    assert!(matches!(
+        error.source(),
+        Some(source) if source.is::<ClientError>()
+    ));
+
+    let (primary, cleanup) = error.into_parts();
+    assert!(matches!(primary, ClientError::AuthorizationError { .. }));
+    assert_eq!(cleanup.kind(), io::ErrorKind::BrokenPipe);
+}
+
+#[test]
+fn cleanup_error_reported_alone_after_success() {
+    let cleanup = io::Error::new(io::ErrorKind::BrokenPipe, "close failed");
+
+    let error = with_cleanup_result(Ok(()), Err(cleanup)).unwrap_err();
+    assert!(matches!(error, ClientError::IOError(_)));
+
+    let invalid_port = ContextFieldError {
+        field: ContextField::Port,
+        reason: FieldIssue::NotPrintableAscii,
+    };
+    assert!(matches!(
+        with_cleanup_result::<()>(Err(ClientError::InvalidContext(invalid_port)), Ok(())),
+        Err(ClientError::InvalidContext(error)) if error == invalid_port
+    ));
+}
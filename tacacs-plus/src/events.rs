@@ -0,0 +1,146 @@
+//! Live events emitted by a client, for rendering session progress in user interfaces.
+
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::Stream;
+
+use tacacs_plus_protocol::authentication::PromptKind;
+use tacacs_plus_protocol::PacketType;
+
+use super::{OperationKind, ResponseStatus, SessionInfo};
+
+/// The status of a client's connection to a server.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionStatus {
+    /// A new connection was opened.
+    Connected,
+
+    /// The server agreed to reuse the current connection for multiple sessions.
+    SingleConnection,
+
+    /// The current connection was closed or dropped.
+    Disconnected,
+}
+
+/// An event that occurred while a client was communicating with a server.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SessionEvent {
+    /// A packet was sent to the server.
+    PacketSent {
+        /// The ID of the session the packet belongs to.
+        session_id: u32,
+
+        /// The sequence number of the packet.
+        sequence_number: u8,
+
+        /// The type of the packet.
+        packet_type: PacketType,
+    },
+
+    /// A packet was received from the server.
+    PacketReceived {
+        /// The ID of the session the packet belongs to.
+        session_id: u32,
+
+        /// The sequence number of the packet.
+        sequence_number: u8,
+
+        /// The type of the packet.
+        packet_type: PacketType,
+    },
+
+    /// The server prompted for input during authentication, which the client answered.
+    PromptRequested {
+        /// The ID of the authentication session.
+        session_id: u32,
+
+        /// The kind of input requested.
+        kind: PromptKind,
+
+        /// The prompt message sent by the server.
+        message: String,
+    },
+
+    /// The server reached a decision for an operation.
+    Decision {
+        /// The operation that was decided on.
+        operation: OperationKind,
+
+        /// Information about the session in which the decision was made.
+        session: SessionInfo,
+
+        /// The decision itself.
+        status: ResponseStatus,
+    },
+
+    /// The client's connection status changed.
+    ConnectionStateChanged(ConnectionStatus),
+
+    /// An authorization request was sent under the session id of an identical earlier request that wasn't answered,
+    /// as configured via [`ClientBuilder::deduplicate_authorization()`](super::ClientBuilder::deduplicate_authorization).
+    AuthorizationRepeated {
+        /// The session id shared by the attempts at the request.
+        session_id: u32,
+    },
+
+    /// Accounting records are only logged locally from now on, since the server kept rejecting them.
+    ///
+    /// See [`ClientBuilder::accounting_fallback()`](super::ClientBuilder::accounting_fallback).
+    AccountingDegraded {
+        /// The number of records the server rejected in a row.
+        consecutive_errors: u32,
+    },
+
+    /// Accounting records are sent to the server again, via [`Client::resume_accounting()`](super::Client::resume_accounting).
+    AccountingResumed,
+}
+
+/// A stream of [`SessionEvent`]s from a client, as returned by [`Client::session_events()`](super::Client::session_events).
+///
+/// Events that occur before a stream is created are not included in it.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct SessionEvents(UnboundedReceiver<SessionEvent>);
+
+impl Stream for SessionEvents {
+    type Item = SessionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+/// Distributes events to all current subscribers of a client.
+#[derive(Debug, Default)]
+pub(crate) struct EventBus {
+    subscribers: Mutex<Vec<UnboundedSender<SessionEvent>>>,
+}
+
+impl EventBus {
+    pub(crate) fn subscribe(&self) -> SessionEvents {
+        let (sender, receiver) = mpsc::unbounded();
+
+        // a poisoned lock just means another thread panicked while emitting, which doesn't leave the list in a bad state
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(sender);
+
+        SessionEvents(receiver)
+    }
+
+    pub(crate) fn emit(&self, event: SessionEvent) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // subscribers whose streams were dropped are removed here as well
+        subscribers.retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+}
@@ -0,0 +1,469 @@
+//! Fault injection for transports, for testing how a client copes with unreliable connections.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+
+use futures::{AsyncRead, AsyncWrite};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[cfg(test)]
+mod tests;
+
+/// A future that completes after some (simulated) network latency.
+pub type DelayFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Creates the delays injected by a [`FaultyTransport`], e.g. via the sleep function of an async runtime.
+type DelayFactory = Arc<dyn Fn() -> DelayFuture + Send + Sync>;
+
+/// A kind of fault injected by a [`FaultyTransport`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultKind {
+    /// A read or write was delayed.
+    Latency,
+
+    /// Only part of a buffer was written.
+    PartialWrite,
+
+    /// A bit was flipped in data that was read.
+    Corruption,
+
+    /// The connection was closed abruptly.
+    Close,
+}
+
+impl FaultKind {
+    const ALL: [Self; 4] = [
+        Self::Latency,
+        Self::PartialWrite,
+        Self::Corruption,
+        Self::Close,
+    ];
+
+    fn index(self) -> usize {
+        match self {
+            Self::Latency => 0,
+            Self::PartialWrite => 1,
+            Self::Corruption => 2,
+            Self::Close => 3,
+        }
+    }
+}
+
+impl fmt::Display for FaultKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Latency => "latency",
+                Self::PartialWrite => "partial write",
+                Self::Corruption => "corruption",
+                Self::Close => "abrupt close",
+            }
+        )
+    }
+}
+
+/// The probabilities of each kind of fault being injected by a [`FaultyTransport`].
+///
+/// Each probability is rolled once per read or write, and all of them are zero by default.
+#[derive(Clone, Default)]
+pub struct FaultConfig {
+    latency_probability: f64,
+    delay: Option<DelayFactory>,
+    partial_write_probability: f64,
+    corruption_probability: f64,
+    close_probability: f64,
+}
+
+impl fmt::Debug for FaultConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FaultConfig")
+            .field("latency_probability", &self.latency_probability)
+            .field("partial_write_probability", &self.partial_write_probability)
+            .field("corruption_probability", &self.corruption_probability)
+            .field("close_probability", &self.close_probability)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Panics if a probability isn't within `[0, 1]`.
+fn check_probability(probability: f64) -> f64 {
+    assert!(
+        (0.0..=1.0).contains(&probability),
+        "fault probability must be between 0 and 1, got {probability}"
+    );
+    probability
+}
+
+impl FaultConfig {
+    /// Creates a configuration that doesn't inject any faults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays reads & writes with the provided probability, waiting on a future returned by `delay` each time.
+    ///
+    /// Since this crate doesn't depend on a specific async runtime, the delay has to be created by the caller,
+    /// e.g. with `|| Box::pin(tokio::time::sleep(Duration::from_millis(100)))`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between 0 and 1.
+    pub fn latency<F>(&mut self, probability: f64, delay: F) -> &mut Self
+    where
+        F: Fn() -> DelayFuture + Send + Sync + 'static,
+    {
+        self.latency_probability = check_probability(probability);
+        self.delay = Some(Arc::new(delay));
+        self
+    }
+
+    /// Writes only part of the provided buffer with the provided probability.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between 0 and 1.
+    pub fn partial_writes(&mut self, probability: f64) -> &mut Self {
+        self.partial_write_probability = check_probability(probability);
+        self
+    }
+
+    /// Flips a random bit in the data returned from a read with the provided probability.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between 0 and 1.
+    pub fn corruption(&mut self, probability: f64) -> &mut Self {
+        self.corruption_probability = check_probability(probability);
+        self
+    }
+
+    /// Closes the connection abruptly with the provided probability, after which reads return EOF and writes fail.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the probability is not between 0 and 1.
+    pub fn closes(&mut self, probability: f64) -> &mut Self {
+        self.close_probability = check_probability(probability);
+        self
+    }
+}
+
+/// A record of the faults injected by one or more [`FaultyTransport`]s, with assertions for use in tests.
+///
+/// Clones of a log share the same counts, so a log can be kept by a test while the transports it was passed to
+/// are moved into a client.
+#[derive(Debug, Clone, Default)]
+pub struct FaultLog {
+    counts: Arc<Mutex<[usize; FaultKind::ALL.len()]>>,
+}
+
+impl FaultLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn record(&self, kind: FaultKind) {
+        // a poisoned lock just means a test panicked while holding it, which doesn't leave the counts in a bad state
+        self.counts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())[kind.index()] += 1;
+    }
+
+    /// Returns the number of faults of a kind that were injected.
+    pub fn count(&self, kind: FaultKind) -> usize {
+        self.counts
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())[kind.index()]
+    }
+
+    /// Returns the total number of faults that were injected.
+    pub fn total(&self) -> usize {
+        FaultKind::ALL.iter().map(|&kind| self.count(kind)).sum()
+    }
+
+    /// Asserts that at least one fault of a kind was injected.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no such fault was injected.
+    #[track_caller]
+    pub fn assert_injected(&self, kind: FaultKind) {
+        assert!(self.count(kind) > 0, "expected {kind} fault to be injected");
+    }
+
+    /// Asserts that no faults were injected at all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any fault was injected.
+    #[track_caller]
+    pub fn assert_none_injected(&self) {
+        for kind in FaultKind::ALL {
+            let count = self.count(kind);
+            assert_eq!(
+                count, 0,
+                "expected no faults, but {count} {kind} fault(s) were injected"
+            );
+        }
+    }
+}
+
+/// The state of a pending read or write with respect to injected latency.
+#[derive(Default)]
+enum DelayState {
+    /// Whether to delay the operation hasn't been decided yet.
+    #[default]
+    Undecided,
+
+    /// The operation is being delayed.
+    Delaying(DelayFuture),
+
+    /// The operation can proceed.
+    Done,
+}
+
+/// The faults rolled for a pending write, which are kept until it completes so they're only rolled (& logged) once.
+#[derive(Debug, Clone, Copy)]
+enum WriteDecision {
+    /// The connection is closed instead of writing.
+    Close,
+
+    /// At most this many bytes are written, which is less than the full buffer for a partial write.
+    Write { length: usize },
+}
+
+/// A transport wrapper that injects faults into reads & writes at configurable probabilities.
+///
+/// This is meant for testing how a client (or an application using one) copes with unreliable connections,
+/// e.g. that it reconnects or reports errors properly. Faults are decided by a seeded RNG, so a failing test
+/// can be reproduced with the same seed. Injected faults are recorded in a [`FaultLog`].
+///
+/// # Examples
+///
+/// ```
+/// use futures::io::Cursor;
+/// use futures::AsyncWriteExt;
+///
+/// use tacacs_plus::{FaultConfig, FaultKind, FaultLog, FaultyTransport};
+///
+/// # futures::executor::block_on(async {
+/// let log = FaultLog::new();
+/// let mut transport = FaultyTransport::new(
+///     Cursor::new(Vec::new()),
+///     FaultConfig::new().partial_writes(1.0).clone(),
+///     log.clone(),
+///     42,
+/// );
+///
+/// // write_all() retries partial writes, so everything is still written eventually
+/// transport.write_all(b"some packet").await.unwrap();
+/// assert_eq!(transport.into_inner().into_inner(), b"some packet");
+///
+/// log.assert_injected(FaultKind::PartialWrite);
+/// # });
+/// ```
+pub struct FaultyTransport<S> {
+    inner: S,
+    config: FaultConfig,
+    log: FaultLog,
+    rng: StdRng,
+    read_delay: DelayState,
+    write_delay: DelayState,
+    read_close_rolled: bool,
+    write_decision: Option<WriteDecision>,
+    closed: bool,
+}
+
+impl<S> fmt::Debug for FaultyTransport<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FaultyTransport")
+            .field("config", &self.config)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> FaultyTransport<S> {
+    /// Wraps a transport, injecting faults as configured & recording them in the provided log.
+    ///
+    /// The seed determines which operations are faulted.
+    pub fn new(inner: S, config: FaultConfig, log: FaultLog, seed: u64) -> Self {
+        Self {
+            inner,
+            config,
+            log,
+            rng: StdRng::seed_from_u64(seed),
+            read_delay: DelayState::Undecided,
+            write_delay: DelayState::Undecided,
+            read_close_rolled: false,
+            write_decision: None,
+            closed: false,
+        }
+    }
+
+    /// Returns the wrapped transport.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Rolls whether to inject a fault of some kind, recording it if so.
+    fn roll(&mut self, kind: FaultKind, probability: f64) -> bool {
+        let injected = probability > 0.0 && self.rng.gen_bool(probability);
+        if injected {
+            self.log.record(kind);
+        }
+
+        injected
+    }
+
+    /// Rolls whether to close the connection, returning true if it is (or already was) closed.
+    fn roll_close(&mut self) -> bool {
+        if !self.closed {
+            self.closed = self.roll(FaultKind::Close, self.config.close_probability);
+        }
+
+        self.closed
+    }
+
+    /// Rolls the faults for a write of a buffer with the provided length.
+    fn decide_write(&mut self, buffer_length: usize) -> WriteDecision {
+        if self.roll_close() {
+            return WriteDecision::Close;
+        }
+
+        // a write of at least one byte is required to make progress, so a single byte can't be split up further
+        let partial_write_probability = self.config.partial_write_probability;
+        let length =
+            if buffer_length > 1 && self.roll(FaultKind::PartialWrite, partial_write_probability) {
+                self.rng.gen_range(1..buffer_length)
+            } else {
+                buffer_length
+            };
+
+        WriteDecision::Write { length }
+    }
+
+    /// Waits out an injected delay for a read (`reading = true`) or a write, if one is rolled.
+    fn poll_delay(&mut self, cx: &mut Context<'_>, reading: bool) -> Poll<()> {
+        let probability = self.config.latency_probability;
+        let delay = self.config.delay.clone();
+
+        let state = if reading {
+            &mut self.read_delay
+        } else {
+            &mut self.write_delay
+        };
+
+        if let DelayState::Undecided = state {
+            *state = DelayState::Done;
+
+            if let Some(delay) = delay {
+                if probability > 0.0 && self.rng.gen_bool(probability) {
+                    self.log.record(FaultKind::Latency);
+                    *state = DelayState::Delaying(delay());
+                }
+            }
+        }
+
+        if let DelayState::Delaying(future) = state {
+            ready!(future.as_mut().poll(cx));
+            *state = DelayState::Done;
+        }
+
+        Poll::Ready(())
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FaultyTransport<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        ready!(this.poll_delay(cx, true));
+
+        // closing is only rolled once per read, rather than every time the read is polled
+        let closed = if this.read_close_rolled {
+            this.closed
+        } else {
+            this.read_close_rolled = true;
+            this.roll_close()
+        };
+
+        if closed {
+            this.read_delay = DelayState::Undecided;
+            this.read_close_rolled = false;
+            return Poll::Ready(Ok(0));
+        }
+
+        let result = ready!(Pin::new(&mut this.inner).poll_read(cx, buf));
+        this.read_delay = DelayState::Undecided;
+        this.read_close_rolled = false;
+
+        if let Ok(read_length @ 1..) = result {
+            let corruption_probability = this.config.corruption_probability;
+            if this.roll(FaultKind::Corruption, corruption_probability) {
+                let index = this.rng.gen_range(0..read_length);
+                buf[index] ^= 1 << this.rng.gen_range(0..8);
+            }
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FaultyTransport<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+
+        ready!(this.poll_delay(cx, false));
+
+        let decision = match this.write_decision {
+            Some(decision) => decision,
+            None => {
+                let decision = this.decide_write(buf.len());
+                this.write_decision = Some(decision);
+                decision
+            }
+        };
+
+        let length = match decision {
+            WriteDecision::Close => {
+                this.write_delay = DelayState::Undecided;
+                this.write_decision = None;
+                return Poll::Ready(Err(io::ErrorKind::ConnectionReset.into()));
+            }
+            // the buffer shouldn't change between polls of a write, but that's not guaranteed
+            WriteDecision::Write { length } => length.min(buf.len()),
+        };
+
+        let result = ready!(Pin::new(&mut this.inner).poll_write(cx, &buf[..length]));
+        this.write_delay = DelayState::Undecided;
+        this.write_decision = None;
+
+        Poll::Ready(result)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
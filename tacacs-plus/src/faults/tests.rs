@@ -0,0 +1,213 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::io::Cursor;
+use futures::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{FaultConfig, FaultKind, FaultLog, FaultyTransport};
+use crate::tests::SilentServer;
+use crate::{AuthenticationType, ClientBuilder, ClientError, ContextBuilder};
+
+const DATA: &[u8] = b"the quick brown fox jumps over the lazy dog";
+
+/// A writer that isn't ready the first time each write is polled, recording the length of every completed write.
+#[derive(Default)]
+struct HesitantWriter {
+    hesitated: bool,
+    write_lengths: Vec<usize>,
+}
+
+impl AsyncWrite for HesitantWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.hesitated {
+            self.hesitated = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.hesitated = false;
+        self.write_lengths.push(buf.len());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[test]
+fn no_faults_by_default() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        Cursor::new(DATA.to_vec()),
+        FaultConfig::new(),
+        log.clone(),
+        0,
+    );
+
+    let mut read = Vec::new();
+    futures::executor::block_on(transport.read_to_end(&mut read)).unwrap();
+
+    assert_eq!(read, DATA);
+    log.assert_none_injected();
+}
+
+#[test]
+fn partial_writes_still_deliver_everything() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        Cursor::new(Vec::new()),
+        FaultConfig::new().partial_writes(1.0).clone(),
+        log.clone(),
+        1,
+    );
+
+    futures::executor::block_on(transport.write_all(DATA)).unwrap();
+
+    assert_eq!(transport.into_inner().into_inner(), DATA);
+    assert!(log.count(FaultKind::PartialWrite) > 1);
+}
+
+#[test]
+fn faults_rolled_once_per_write() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        HesitantWriter::default(),
+        FaultConfig::new().partial_writes(1.0).closes(0.0).clone(),
+        log.clone(),
+        6,
+    );
+
+    futures::executor::block_on(transport.write_all(DATA)).unwrap();
+
+    // every write but the last one (of a single byte) is partial, regardless of how many times each was polled
+    let write_lengths = transport.into_inner().write_lengths;
+    assert_eq!(write_lengths.iter().sum::<usize>(), DATA.len());
+    assert_eq!(write_lengths.last(), Some(&1));
+    assert_eq!(log.count(FaultKind::PartialWrite), write_lengths.len() - 1);
+}
+
+#[test]
+fn corruption_changes_read_bytes() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        Cursor::new(DATA.to_vec()),
+        FaultConfig::new().corruption(1.0).clone(),
+        log.clone(),
+        2,
+    );
+
+    let mut read = Vec::new();
+    futures::executor::block_on(transport.read_to_end(&mut read)).unwrap();
+
+    assert_eq!(read.len(), DATA.len());
+    assert_ne!(read, DATA);
+    log.assert_injected(FaultKind::Corruption);
+}
+
+#[test]
+fn closed_transport_stays_closed() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        Cursor::new(DATA.to_vec()),
+        FaultConfig::new().closes(1.0).clone(),
+        log.clone(),
+        3,
+    );
+
+    futures::executor::block_on(async {
+        let mut buffer = [0; 8];
+        assert_eq!(transport.read(&mut buffer).await.unwrap(), 0);
+
+        let write_error = transport.write(DATA).await.unwrap_err();
+        assert_eq!(write_error.kind(), io::ErrorKind::ConnectionReset);
+    });
+
+    // the connection can only be closed once
+    assert_eq!(log.count(FaultKind::Close), 1);
+}
+
+#[test]
+fn latency_waits_on_delay() {
+    let log = FaultLog::new();
+    let mut transport = FaultyTransport::new(
+        Cursor::new(DATA.to_vec()),
+        FaultConfig::new()
+            .latency(1.0, || Box::pin(futures::future::ready(())))
+            .clone(),
+        log.clone(),
+        4,
+    );
+
+    let mut read = Vec::new();
+    futures::executor::block_on(transport.read_to_end(&mut read)).unwrap();
+
+    assert_eq!(read, DATA);
+    log.assert_injected(FaultKind::Latency);
+}
+
+#[test]
+fn same_seed_injects_same_faults() {
+    let read_with_seed = |seed| {
+        let mut transport = FaultyTransport::new(
+            Cursor::new(DATA.to_vec()),
+            FaultConfig::new().corruption(0.5).clone(),
+            FaultLog::new(),
+            seed,
+        );
+
+        let mut read = Vec::new();
+        futures::executor::block_on(transport.read_to_end(&mut read)).unwrap();
+        read
+    };
+
+    assert_eq!(read_with_seed(5), read_with_seed(5));
+}
+
+#[test]
+#[should_panic(expected = "fault probability must be between 0 and 1")]
+fn invalid_probability_rejected() {
+    FaultConfig::new().closes(1.5);
+}
+
+#[tokio::test]
+async fn client_reports_closed_connection() {
+    let log = FaultLog::new();
+    let factory_log = log.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let log = factory_log.clone();
+        Box::pin(async move {
+            Ok(FaultyTransport::new(
+                SilentServer,
+                FaultConfig::new().closes(1.0).clone(),
+                log,
+                6,
+            ))
+        })
+    }));
+
+    let error = client
+        .authenticate(
+            ContextBuilder::new(String::from("user")).build().unwrap(),
+            "password",
+            AuthenticationType::Pap,
+        )
+        .await
+        .expect_err("authentication should fail over a closed connection");
+
+    assert!(
+        matches!(error, ClientError::ConnectionClosedByServer { .. }),
+        "unexpected error: {error:?}"
+    );
+    log.assert_injected(FaultKind::Close);
+}
@@ -0,0 +1,166 @@
+//! Deduplication of authorization requests, so retries of a request reach the server as the same session.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use futures::lock::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tacacs_plus_protocol::Argument;
+
+use super::SessionContext;
+
+#[cfg(test)]
+mod tests;
+
+/// An authorization request that was sent recently, but hasn't been answered by the server yet.
+#[derive(Debug)]
+struct PendingRequest {
+    /// The session id every attempt at the request is sent with.
+    session_id: u32,
+
+    /// When the request was first attempted, after which it can only be retried for the configured window.
+    first_attempt: Instant,
+
+    /// Held by the attempt currently in flight, so identical requests wait for it instead of being sent alongside it.
+    in_flight: Arc<AsyncMutex<()>>,
+}
+
+/// Tracks unanswered authorization requests by their context & arguments, shared between clones of a client.
+///
+/// A request that failed before the server answered it (e.g. since the connection was closed) keeps its session id
+/// for the configured window, so a retry is sent as the same session rather than a new one. Once the server has
+/// answered a request, the next identical request starts a new session as usual.
+#[derive(Debug)]
+pub(crate) struct AuthorizationDeduplicator {
+    window: Duration,
+    requests: Mutex<HashMap<u64, Arc<PendingRequest>>>,
+}
+
+impl AuthorizationDeduplicator {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            requests: Mutex::default(),
+        }
+    }
+
+    /// The key identical requests are tracked by, i.e. a hash of the (normalized) context & arguments sent to the server.
+    ///
+    /// A context's priority isn't included, since it only affects when a request is sent rather than what's sent.
+    pub(crate) fn key(context: &SessionContext, arguments: &[Argument<'_>]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        context.user.hash(&mut hasher);
+        context.port.hash(&mut hasher);
+        context.remote_address.hash(&mut hasher);
+        context.privilege_level.hash(&mut hasher);
+        context.authentication_method().hash(&mut hasher);
+        context.authentication_type().hash(&mut hasher);
+        arguments.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Starts an attempt at a request, waiting for any identical request that's currently in flight to finish first.
+    ///
+    /// The session id of an unanswered earlier attempt is reused if there is one, and otherwise a new session id is
+    /// generated via `new_session_id`.
+    pub(crate) async fn begin(
+        &self,
+        key: u64,
+        now: Instant,
+        new_session_id: impl FnOnce() -> u32,
+    ) -> Attempt<'_> {
+        let mut new_session_id = Some(new_session_id);
+
+        loop {
+            let (request, repeated) = {
+                let mut requests = self.requests();
+
+                // requests nobody is attempting anymore are forgotten once they're too old to be retried
+                requests.retain(|_, request| {
+                    Arc::strong_count(request) > 1
+                        || now.saturating_duration_since(request.first_attempt) < self.window
+                });
+
+                match requests.get(&key) {
+                    Some(request) => (request.clone(), true),
+                    None => {
+                        // an attempt is returned as soon as it inserted a new request, so this only happens once
+                        let session_id = match new_session_id.take() {
+                            Some(new_session_id) => new_session_id(),
+                            None => unreachable!("session id generated twice"),
+                        };
+
+                        let request = Arc::new(PendingRequest {
+                            session_id,
+                            first_attempt: now,
+                            in_flight: Arc::default(),
+                        });
+                        requests.insert(key, request.clone());
+                        (request, false)
+                    }
+                }
+            };
+
+            // the map has to be unlocked while waiting, since the attempt in flight removes its request from it
+            let in_flight = request.in_flight.clone().lock_owned().await;
+
+            // an identical request that was answered while this one was waiting can't be repeated under its session id
+            if repeated && !self.is_pending(key, &request) {
+                continue;
+            }
+
+            return Attempt {
+                deduplicator: self,
+                key,
+                request,
+                repeated,
+                _in_flight: in_flight,
+            };
+        }
+    }
+
+    fn requests(&self) -> MutexGuard<'_, HashMap<u64, Arc<PendingRequest>>> {
+        // the map is never left inconsistent while locked, so poisoning can be ignored
+        self.requests.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether a request is still waiting for an answer from the server.
+    fn is_pending(&self, key: u64, request: &Arc<PendingRequest>) -> bool {
+        self.requests()
+            .get(&key)
+            .is_some_and(|pending| Arc::ptr_eq(pending, request))
+    }
+}
+
+/// An attempt at sending an authorization request, which lets identical requests proceed once dropped.
+///
+/// If the attempt is dropped without being [answered](Self::answered), the request stays pending, so a retry reuses
+/// its session id.
+#[derive(Debug)]
+pub(crate) struct Attempt<'a> {
+    deduplicator: &'a AuthorizationDeduplicator,
+    key: u64,
+    request: Arc<PendingRequest>,
+    repeated: bool,
+    _in_flight: OwnedMutexGuard<()>,
+}
+
+impl Attempt<'_> {
+    /// The session id to send the request with.
+    pub(crate) fn session_id(&self) -> u32 {
+        self.request.session_id
+    }
+
+    /// Whether this attempt repeats an unanswered identical request, and so reuses its session id.
+    pub(crate) fn is_repeated(&self) -> bool {
+        self.repeated
+    }
+
+    /// Records that the server answered the request, so identical requests start a new session from now on.
+    pub(crate) fn answered(self) {
+        // requests being attempted are never pruned, so the request is still the one tracked under its key
+        self.deduplicator.requests().remove(&self.key);
+    }
+}
@@ -0,0 +1,207 @@
+use std::task::{Context, Poll};
+
+use futures::task::noop_waker_ref;
+use futures::{AsyncReadExt, AsyncWriteExt, FutureExt};
+use tacacs_plus_protocol::{AuthenticationMethod, FieldText, PrivilegeLevel};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use super::*;
+use crate::{ClientBuilder, ContextBuilder, ResponseStatus, SessionPriority};
+
+const WINDOW: Duration = Duration::from_secs(30);
+
+fn context(user: &str) -> SessionContext {
+    ContextBuilder::new(user.to_owned()).build().unwrap()
+}
+
+fn key() -> u64 {
+    let arguments = [Argument::new(
+        FieldText::try_from("service").unwrap(),
+        FieldText::try_from("shell").unwrap(),
+        true,
+    )
+    .unwrap()];
+
+    AuthorizationDeduplicator::key(&context("someuser"), &arguments)
+}
+
+#[test]
+fn unanswered_request_reuses_session_id() {
+    let deduplicator = AuthorizationDeduplicator::new(WINDOW);
+    let now = Instant::now();
+
+    let first = deduplicator.begin(key(), now, || 1).now_or_never().unwrap();
+    assert_eq!(first.session_id(), 1);
+    assert!(!first.is_repeated());
+
+    // the first attempt fails without an answer, so the retry is sent as the same session
+    drop(first);
+    let retry = deduplicator.begin(key(), now, || 2).now_or_never().unwrap();
+    assert_eq!(retry.session_id(), 1);
+    assert!(retry.is_repeated());
+
+    // once answered, the next identical request is a new session
+    retry.answered();
+    let next = deduplicator.begin(key(), now, || 3).now_or_never().unwrap();
+    assert_eq!(next.session_id(), 3);
+    assert!(!next.is_repeated());
+}
+
+#[test]
+fn different_requests_tracked_separately() {
+    let deduplicator = AuthorizationDeduplicator::new(WINDOW);
+    let now = Instant::now();
+
+    let first = deduplicator.begin(key(), now, || 1).now_or_never().unwrap();
+
+    // a different user doesn't have to wait for the first request
+    let other_key = AuthorizationDeduplicator::key(&context("otheruser"), &[]);
+    let other = deduplicator
+        .begin(other_key, now, || 2)
+        .now_or_never()
+        .unwrap();
+    assert_eq!(other.session_id(), 2);
+    assert!(!other.is_repeated());
+
+    drop(first);
+}
+
+#[test]
+fn whole_context_distinguishes_requests() {
+    let key = |context: &SessionContext| AuthorizationDeduplicator::key(context, &[]);
+    let base = context("someuser");
+
+    let other_contexts = [
+        ContextBuilder::new("someuser".to_owned())
+            .port("tty1")
+            .build()
+            .unwrap(),
+        ContextBuilder::new("someuser".to_owned())
+            .remote_address("192.0.2.1")
+            .build()
+            .unwrap(),
+        base.with_privilege_level(PrivilegeLevel::new(15).unwrap()),
+        ContextBuilder::new("someuser".to_owned())
+            .auth_method(AuthenticationMethod::Local)
+            .build()
+            .unwrap(),
+    ];
+
+    for other in &other_contexts {
+        assert_ne!(key(&base), key(other), "{other:?} should have its own key");
+    }
+
+    // the priority doesn't change what's sent to the server
+    let prioritized = ContextBuilder::new("someuser".to_owned())
+        .priority(SessionPriority::Background)
+        .build()
+        .unwrap();
+    assert_eq!(key(&base), key(&prioritized));
+}
+
+#[test]
+fn expired_request_not_reused() {
+    let deduplicator = AuthorizationDeduplicator::new(WINDOW);
+    let now = Instant::now();
+
+    drop(deduplicator.begin(key(), now, || 1).now_or_never().unwrap());
+
+    let later = now + WINDOW;
+    let retry = deduplicator
+        .begin(key(), later, || 2)
+        .now_or_never()
+        .unwrap();
+    assert_eq!(retry.session_id(), 2);
+    assert!(!retry.is_repeated());
+}
+
+#[test]
+fn duplicate_waits_for_request_in_flight() {
+    let deduplicator = AuthorizationDeduplicator::new(WINDOW);
+    let now = Instant::now();
+    let mut cx = Context::from_waker(noop_waker_ref());
+
+    let first = deduplicator.begin(key(), now, || 1).now_or_never().unwrap();
+
+    let mut duplicate = Box::pin(deduplicator.begin(key(), now, || 2));
+    assert!(duplicate.poll_unpin(&mut cx).is_pending());
+
+    // the duplicate isn't sent under the session id of a request the server already answered
+    first.answered();
+    let Poll::Ready(duplicate) = duplicate.poll_unpin(&mut cx) else {
+        panic!("duplicate should proceed once the first request is answered");
+    };
+    assert_eq!(duplicate.session_id(), 2);
+    assert!(!duplicate.is_repeated());
+
+    // but it is if the first request wasn't answered
+    let mut retry = Box::pin(deduplicator.begin(key(), now, || 3));
+    assert!(retry.poll_unpin(&mut cx).is_pending());
+
+    drop(duplicate);
+    let Poll::Ready(retry) = retry.poll_unpin(&mut cx) else {
+        panic!("retry should proceed once the duplicate is dropped");
+    };
+    assert_eq!(retry.session_id(), 2);
+    assert!(retry.is_repeated());
+}
+
+/// Reads an authorization request from a client, returning its session id.
+async fn read_session_id(stream: &mut Compat<TcpStream>) -> [u8; 4] {
+    let mut header = [0; 12];
+    stream.read_exact(&mut header).await.unwrap();
+
+    let body_length = u32::from_be_bytes(header[8..12].try_into().unwrap());
+    stream
+        .read_exact(&mut vec![0; body_length as usize])
+        .await
+        .unwrap();
+
+    header[4..8].try_into().unwrap()
+}
+
+#[tokio::test]
+async fn retry_after_closed_connection_sent_as_same_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+
+    let client = ClientBuilder::new()
+        .deduplicate_authorization(WINDOW)
+        .build(Box::new(move || {
+            Box::pin(async move { Ok(TcpStream::connect(address).await?.compat()) })
+        }));
+
+    let server = tokio::spawn(async move {
+        // the connection is closed after the request is received, so the client can't tell if it was processed
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let first = read_session_id(&mut stream).await;
+        drop(stream);
+
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let retry = read_session_id(&mut stream).await;
+
+        // unobfuscated PASS_ADD reply with no arguments & empty messages
+        let mut reply = vec![0xc0, 0x02, 2, 0x01];
+        reply.extend(retry);
+        reply.extend(6u32.to_be_bytes());
+        reply.extend([0x01, 0, 0, 0, 0, 0]);
+        stream.write_all(&reply).await.unwrap();
+
+        (first, retry)
+    });
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let response = client.authorize(context, []).await.unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let (first, retry) = server.await.unwrap();
+    assert_eq!(first, retry);
+
+    // the built-in retry is part of the same call, and the request isn't tracked anymore once it was answered
+    let deduplicator = client.authorization_deduplicator.as_ref().unwrap();
+    assert!(deduplicator.requests().is_empty());
+    assert_eq!(client.stats().repeated_authorizations, 0);
+}
@@ -1,18 +1,40 @@
 //! The non-thread-safe internals of a client.
 
+use std::collections::TryReserveError;
 use std::fmt;
 use std::future::Future;
 use std::io;
+use std::mem;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
 use byteorder::{ByteOrder, NetworkEndian};
-use futures::poll;
+use bytes::{Bytes, BytesMut};
+use futures::future::Either;
+use futures::lock::Mutex as AsyncMutex;
+use futures::{poll, FutureExt};
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tacacs_plus_protocol::{Deserialize, PacketBody, Serialize};
-use tacacs_plus_protocol::{HeaderInfo, Packet, PacketFlags};
+use tacacs_plus_protocol::{Deserialize, DeserializeError, PacketBody, Serialize};
+use tacacs_plus_protocol::{HeaderInfo, Packet, PacketStreamParser};
 
-use super::ClientError;
+use super::peer::{PeerBinding, PeerConnectionFactory, PeerSecrets};
+use super::queue::SessionGuard;
+use super::stats::StatsRecorder;
+use super::{ClientError, Clock, OperationPhase, SingleConnectionNegotiation, SystemClock};
+
+mod state;
+use state::ConnectionState;
+pub(super) use state::StateSnapshot;
+
+mod duplex;
+use duplex::SharedSession;
+
+use std::sync::Arc;
+
+#[cfg(feature = "session-events")]
+use crate::events::{ConnectionStatus, EventBus, SessionEvent};
 
 #[cfg(test)]
 mod tests;
@@ -64,102 +86,342 @@ pub type ConnectionFuture<S> = Pin<Box<dyn Future<Output = io::Result<S>> + Send
 /// ```
 pub type ConnectionFactory<S> = Box<dyn Fn() -> ConnectionFuture<S> + Send>;
 
+/// The factory a client opens connections with, which may also report the peer of each connection.
+enum Connector<S> {
+    Plain(ConnectionFactory<S>),
+    WithPeer(PeerConnectionFactory<S>),
+}
+
+impl<S> Connector<S> {
+    /// Opens a new connection, returning its peer if the factory reports it.
+    ///
+    /// The factory is called before returning so the returned future doesn't borrow it; factories aren't required to
+    /// be `Sync`, so holding a reference to one across an await point would make client futures `!Send`.
+    fn open(&self) -> impl Future<Output = io::Result<(S, Option<SocketAddr>)>> {
+        match self {
+            Self::Plain(factory) => {
+                Either::Left(factory().map(|result| result.map(|connection| (connection, None))))
+            }
+            Self::WithPeer(factory) => Either::Right(
+                factory().map(|result| result.map(|(connection, peer)| (connection, Some(peer)))),
+            ),
+        }
+    }
+}
+
 pub(super) struct ClientInner<S> {
-    /// The underlying (TCP per RFC8907) connection for this client, if present.
-    connection: Option<S>,
+    /// The underlying (TCP per RFC8907) connection for this client along with its single connection status.
+    state: ConnectionState<S>,
 
     /// A factory for opening new connections internally, so the library consumer doesn't have to.
     ///
     /// The factory is invoked whenever a new connection needs to be established, including when an ERROR status
     /// is reported by the server as well as for each new session if the server doesn't support single connection mode.
-    connection_factory: ConnectionFactory<S>,
+    connector: Connector<S>,
+
+    /// The secrets of individual peers, if the connection factory reports peers.
+    peer_binding: Option<PeerBinding>,
 
-    /// Whether a session has been completed on the contained connection.
-    first_session_completed: bool,
+    /// The secret specific to the peer of the current connection, which takes precedence over the client's default secret.
+    peer_secret: Option<Arc<[u8]>>,
 
-    /// Whether single connection mode has been established for this connection.
+    /// Whether a session was started without being cleaned up afterwards, e.g. since the future performing it was dropped.
     ///
-    /// The single connection flag is meant to be ignored after the first two packets
-    /// in a session according to [RFC8907 section 4.3], so we have to keep track of
-    /// that internally.
+    /// The server might still send packets for such a session, so its connection can't be reused for the next one.
+    session_in_progress: bool,
+
+    /// Which replies the server can agree to single connection mode in.
+    single_connection_negotiation: SingleConnectionNegotiation,
+
+    /// Whether connections in single connection mode are shared between concurrent sessions.
+    full_duplex: bool,
+
+    /// Limits on how long a connection is reused across sessions, along with the times they're checked against.
+    lifetime: ConnectionLifetime,
+
+    /// The source of the times a connection's lifetime is measured with.
+    clock: Arc<dyn Clock>,
+
+    /// The longest body a server's packet can announce, beyond which the packet is rejected without being read.
+    max_body_length: usize,
+
+    /// A buffer reused for serializing outgoing packets, so each one doesn't need a fresh allocation.
+    send_buffer: Vec<u8>,
+
+    /// A buffer reused for reading incoming packets, whose allocation is reclaimed once a packet is deserialized.
+    receive_buffer: BytesMut,
+
+    /// Metadata about the connection, published for the outer client whenever the state changes.
+    snapshot: Arc<StateSnapshot>,
+
+    /// Counters of the traffic on this client's connections, shared with the outer client.
+    stats: Arc<StatsRecorder>,
+
+    /// When the last packet was sent, if no reply to it has been received yet.
+    last_sent_at: Option<Instant>,
+
+    /// Data the server sent before a session started, which was read while checking that the connection is still open.
     ///
-    /// [RFC8907 section 4.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.3-5
-    single_connection_established: bool,
+    /// This is consumed before reading from the connection again so no bytes are lost, and is discarded along with the
+    /// connection it was read from.
+    early_data: Vec<u8>,
+
+    /// Subscribers to events emitted by the client.
+    #[cfg(feature = "session-events")]
+    events: Arc<EventBus>,
 }
 
 impl<S: fmt::Debug> fmt::Debug for ClientInner<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("ClientInner")
-            .field("connection", &self.connection)
-            .field("first_session_completed", &self.first_session_completed)
-            .field(
-                "single_connection_established",
-                &self.single_connection_established,
-            )
+            .field("state", &self.state)
+            .field("snapshot", &self.snapshot)
             .finish_non_exhaustive()
     }
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> ClientInner<S> {
     pub(super) fn new(factory: ConnectionFactory<S>) -> Self {
+        Self::with_connector(Connector::Plain(factory), None)
+    }
+
+    /// Creates a client whose factory reports the peer of each connection, so secrets can be chosen per peer.
+    pub(super) fn with_peers(factory: PeerConnectionFactory<S>, secrets: PeerSecrets) -> Self {
+        Self::with_connector(
+            Connector::WithPeer(factory),
+            Some(PeerBinding::new(secrets)),
+        )
+    }
+
+    fn with_connector(connector: Connector<S>, peer_binding: Option<PeerBinding>) -> Self {
         Self {
-            connection: None,
-            connection_factory: factory,
-            first_session_completed: false,
-            single_connection_established: false,
+            state: ConnectionState::Disconnected,
+            connector,
+            peer_binding,
+            peer_secret: None,
+            session_in_progress: false,
+            single_connection_negotiation: SingleConnectionNegotiation::default(),
+            full_duplex: false,
+            lifetime: ConnectionLifetime::default(),
+            clock: Arc::new(SystemClock),
+            max_body_length: PacketStreamParser::DEFAULT_MAX_BODY_LENGTH,
+            send_buffer: Vec::new(),
+            receive_buffer: BytesMut::new(),
+            snapshot: Arc::default(),
+            stats: Arc::default(),
+            last_sent_at: None,
+            early_data: Vec::new(),
+            #[cfg(feature = "session-events")]
+            events: Arc::default(),
         }
     }
 
+    /// Sets which replies the server can agree to single connection mode in.
+    pub(super) fn set_single_connection_negotiation(
+        &mut self,
+        negotiation: SingleConnectionNegotiation,
+    ) {
+        self.single_connection_negotiation = negotiation;
+    }
+
+    /// Sets whether connections in single connection mode are shared between concurrent sessions.
+    pub(super) fn set_full_duplex(&mut self, full_duplex: bool) {
+        self.full_duplex = full_duplex;
+    }
+
+    /// Sets the longest body a packet from the server can announce.
+    pub(super) fn set_max_body_length(&mut self, max_body_length: usize) {
+        self.max_body_length = max_body_length;
+    }
+
+    /// Sets how long a connection can sit idle between sessions & how long it can be reused overall, measured with the
+    /// provided clock.
+    pub(super) fn set_connection_limits(
+        &mut self,
+        idle_timeout: Option<Duration>,
+        max_age: Option<Duration>,
+        clock: Arc<dyn Clock>,
+    ) {
+        self.lifetime.idle_timeout = idle_timeout;
+        self.lifetime.max_age = max_age;
+        self.clock = clock;
+    }
+
+    /// Returns the connection state snapshot of this client, so it can be shared with the outer client.
+    pub(super) fn snapshot(&self) -> &Arc<StateSnapshot> {
+        &self.snapshot
+    }
+
+    /// Returns the counters of this client, so they can be shared with the outer client.
+    pub(super) fn stats(&self) -> &Arc<StatsRecorder> {
+        &self.stats
+    }
+
+    /// Publishes the kind of the current connection state to the snapshot.
+    fn publish_state(&self) {
+        self.snapshot.publish(self.state.kind());
+    }
+
+    /// Returns the event bus used by this client, so it can be shared with the outer client.
+    #[cfg(feature = "session-events")]
+    pub(super) fn events(&self) -> &Arc<EventBus> {
+        &self.events
+    }
+
     /// NOTE: This function will open a new connection with the stored factory as needed.
-    async fn connection(&mut self) -> io::Result<&mut S> {
+    async fn connection(&mut self, phase: OperationPhase) -> Result<&mut S, ClientError> {
         // obtain new connection from factory
-        if self.connection.is_none() {
-            let new_conn = (self.connection_factory)().await?;
-            self.connection = Some(new_conn);
+        if self.state.connection().is_none() {
+            let (new_conn, peer) = match self.connector.open().await {
+                Ok(opened) => opened,
+                Err(err) => return Err(self.handle_io_error(err, phase)),
+            };
+
+            // the connection is dropped without being used if its peer isn't allowed to use the default secret
+            self.peer_secret = match (&mut self.peer_binding, peer) {
+                (Some(binding), Some(peer)) => binding.secret_for(peer)?,
+                _ => None,
+            };
+
+            self.state.open(new_conn);
+            self.lifetime.opened(self.clock.instant());
+            self.snapshot.connection_opened();
+            self.stats.connection_opened();
+            self.publish_state();
+
+            emit_event!(
+                self.events,
+                SessionEvent::ConnectionStateChanged(ConnectionStatus::Connected)
+            );
         }
 
-        // SAFETY: self.connection is guaranteed to be non-None by the above check
-        let conn = self.connection.as_mut().unwrap();
+        // SAFETY: the state is guaranteed to hold a connection by the above check
+        let conn = self.state.connection().unwrap();
 
         Ok(conn)
     }
 
+    /// Hands out the current connection to a session without keeping the client locked, if it's shared between sessions
+    /// and can still be used.
+    ///
+    /// A shared connection that can't be used anymore (e.g. since it expired or the server closed it) is retired, so the
+    /// session opens a new connection as usual.
+    pub(super) async fn share_connection<'a>(
+        &mut self,
+        this: &'a AsyncMutex<Self>,
+    ) -> Option<SharedSession<'a, S>> {
+        let connection = self.state.duplex()?.clone();
+
+        let usable = connection.is_usable()
+            && !self.lifetime.is_expired(self.clock.instant())
+            && connection.check_if_idle().await;
+        if !usable {
+            self.retire_duplex();
+            return None;
+        }
+
+        Some(SharedSession::new(this, self, connection))
+    }
+
+    /// Stops handing out the current shared connection, letting the sessions still using it finish.
+    fn retire_duplex(&mut self) {
+        self.early_data.clear();
+        self.state.take_connection();
+        self.publish_state();
+
+        emit_event!(
+            self.events,
+            SessionEvent::ConnectionStateChanged(ConnectionStatus::Disconnected)
+        );
+    }
+
+    /// Opens a separate connection with the stored factory and closes it again, leaving the current connection untouched.
+    pub(super) async fn probe_connection(&mut self) -> io::Result<()> {
+        let (mut connection, _) = self.connector.open().await?;
+        connection.close().await
+    }
+
     /// Writes a packet to the underlying connection, reconnecting if necessary.
     pub(super) async fn send_packet<B: PacketBody + Serialize>(
         &mut self,
         packet: Packet<B>,
         secret_key: Option<&[u8]>,
     ) -> Result<(), ClientError> {
+        // a previous session that was cancelled partway through leaves the connection in an unknown state
+        if self.session_in_progress {
+            self.disconnect();
+        }
+
+        // a connection kept around for too long might have been dropped silently by a middlebox in the meantime
+        if self.lifetime.is_expired(self.clock.instant()) {
+            self.recycle_connection().await;
+        }
+
         // check if other end closed our connection, and reopen it accordingly
-        let connection = self.connection().await?;
-        if !is_connection_open(connection).await? {
-            self.post_session_cleanup(true).await?;
+        let connection = self.connection(OperationPhase::SendingRequest).await?;
+        match check_connection(connection).await? {
+            ConnectionCheck::Open => {}
+            ConnectionCheck::EarlyData(byte) => self.early_data.push(byte),
+            ConnectionCheck::Closed => self.post_session_cleanup(true).await?,
         }
 
         // send the packet after ensuring the connection is valid (or dropping
         // it if it's invalid)
-        self._send_packet(packet, secret_key).await
+        self.session_in_progress = true;
+        self.send_packet_unchecked(packet, secret_key).await
     }
 
-    /// Writes a packet to the underlying connection.
-    async fn _send_packet<B: PacketBody + Serialize>(
+    /// Writes a packet to the underlying connection without checking if it's still open.
+    ///
+    /// This should be used for packets after the first in a session, since the check could consume data sent by the server.
+    pub(super) async fn send_packet_unchecked<B: PacketBody + Serialize>(
         &mut self,
         packet: Packet<B>,
         secret_key: Option<&[u8]>,
     ) -> Result<(), ClientError> {
-        // allocate zero-filled buffer large enough to hold packet
-        let mut packet_buffer = vec![0; packet.wire_size()];
+        #[cfg(feature = "session-events")]
+        let header = *packet.header();
 
-        // obfuscate packet if we have a secret key
-        if let Some(key) = secret_key {
-            packet.serialize(key, &mut packet_buffer)?;
-        } else {
-            packet.serialize_unobfuscated(&mut packet_buffer)?;
-        }
+        let peer_secret = self.peer_secret.clone();
+        let secret_key = peer_secret.as_deref().or(secret_key);
+
+        // the buffer is taken out temporarily since obtaining the connection borrows the whole client
+        let mut packet_buffer = mem::take(&mut self.send_buffer);
+
+        let write_result = match serialize_packet(packet, secret_key, &mut packet_buffer) {
+            Ok(()) => self.write_packet_bytes(&packet_buffer).await,
+            Err(err) => Err(err),
+        };
+        self.send_buffer = packet_buffer;
+
+        write_result?;
+
+        emit_event!(
+            self.events,
+            SessionEvent::PacketSent {
+                session_id: header.session_id(),
+                sequence_number: header.sequence_number(),
+                packet_type: B::TYPE,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Writes & flushes the bytes of a serialized packet to the connection.
+    async fn write_packet_bytes(&mut self, packet_bytes: &[u8]) -> Result<(), ClientError> {
+        let connection = self.connection(OperationPhase::SendingRequest).await?;
+        let write_result = match connection.write_all(packet_bytes).await {
+            Ok(()) => connection.flush().await,
+            Err(err) => Err(err),
+        };
+
+        write_result.map_err(|err| self.handle_io_error(err, OperationPhase::SendingRequest))?;
 
-        let connection = self.connection().await?;
-        connection.write_all(&packet_buffer).await?;
-        connection.flush().await.map_err(Into::into)
+        self.stats.bytes_sent(packet_bytes.len());
+        self.last_sent_at = Some(self.clock.instant());
+
+        Ok(())
     }
 
     /// Receives a packet from the underlying connection.
@@ -171,79 +433,308 @@ impl<S: AsyncRead + AsyncWrite + Unpin> ClientInner<S> {
     where
         B: PacketBody + for<'a> Deserialize<'a>,
     {
-        let mut buffer = vec![0; HeaderInfo::HEADER_SIZE_BYTES];
-        let buffer = &mut buffer;
-
-        let connection = self.connection().await?;
-        connection.read_exact(buffer).await?;
-
-        // read rest of body based on length reported in header
-        let body_length = NetworkEndian::read_u32(&buffer[8..12]);
-        buffer.resize(HeaderInfo::HEADER_SIZE_BYTES + body_length as usize, 0);
-        connection
-            .read_exact(&mut buffer[HeaderInfo::HEADER_SIZE_BYTES..])
-            .await?;
-
-        // unobfuscate packet as necessary
-        let deserialize_result: Packet<B> = if let Some(key) = secret_key {
-            Packet::deserialize(key, buffer)?
-        } else {
-            Packet::deserialize_unobfuscated(buffer)?
+        let buffer = self.read_packet_bytes().await?;
+
+        self.stats.bytes_received(buffer.len());
+        if let Some(sent_at) = self.last_sent_at.take() {
+            let now = self.clock.instant();
+            self.stats
+                .round_trip(now.saturating_duration_since(sent_at));
+        }
+
+        let peer_secret = self.peer_secret.clone();
+        let secret_key = peer_secret.as_deref().or(secret_key);
+        let packet: Packet<B> = deserialize_reply(buffer, secret_key)?;
+
+        emit_event!(
+            self.events,
+            SessionEvent::PacketReceived {
+                session_id: packet.header().session_id(),
+                sequence_number: packet.header().sequence_number(),
+                packet_type: B::TYPE,
+            }
+        );
+
+        check_sequence_number(packet, expected_sequence_number)
+    }
+
+    /// Reads the raw bytes of a full packet from the underlying connection.
+    async fn read_packet_bytes(&mut self) -> Result<BytesMut, ClientError> {
+        // the buffers are taken out temporarily since getting the connection borrows the whole client
+        let mut early_data = mem::take(&mut self.early_data);
+        let mut buffer = mem::take(&mut self.receive_buffer);
+        let max_body_length = self.max_body_length;
+
+        let connection = match self.connection(OperationPhase::AwaitingReply).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                self.early_data = early_data;
+                self.receive_buffer = buffer;
+                return Err(err);
+            }
         };
 
-        let actual_sequence_number = deserialize_result.header().sequence_number();
-        if actual_sequence_number == expected_sequence_number {
-            Ok(deserialize_result)
+        // any early data from the server is the start of the packet
+        let mut reader = early_data.as_slice().chain(connection);
+        let read_result = read_framed_packet(&mut reader, &mut buffer, max_body_length).await;
+
+        let consumed = early_data.len() - reader.into_inner().0.len();
+        early_data.drain(..consumed);
+
+        self.early_data = early_data;
+        self.receive_buffer = buffer;
+
+        read_result.map_err(|err| match err {
+            FrameError::Io(err) => self.handle_io_error(err, OperationPhase::AwaitingReply),
+            FrameError::Unread(err) => {
+                // the rest of the packet can't be skipped without reading it, so the connection is out of sync
+                self.abandon_session();
+                err
+            }
+        })
+    }
+
+    /// Converts an IO error that occurred during a session into a [`ClientError`], dropping the
+    /// current connection if the error indicates that the server closed it.
+    fn handle_io_error(&mut self, error: io::Error, phase: OperationPhase) -> ClientError {
+        if connection_closed(&error) {
+            self.disconnect();
+
+            ClientError::ConnectionClosedByServer { during: phase }
         } else {
-            Err(ClientError::SequenceNumberMismatch {
-                expected: expected_sequence_number,
-                actual: actual_sequence_number,
-            })
+            error.into()
         }
     }
 
     /// NOTE: This function is separate from post_session_cleanup since it has to be done after the first reply/second packet
     /// in a session, but ASCII authentication can span more packets.
     pub(super) fn set_internal_single_connect_status(&mut self, header: &HeaderInfo) {
-        // only update single connection status if this is the first reply of the first session of this connection
-        if !self.first_session_completed
-            && header.sequence_number() == 2
-            && header.flags().contains(PacketFlags::SINGLE_CONNECTION)
-        {
-            self.single_connection_established = true;
+        #[cfg(feature = "session-events")]
+        let was_fresh = matches!(self.state, ConnectionState::FreshConnection(_));
+
+        self.state
+            .observe_reply(header, self.single_connection_negotiation);
+        self.publish_state();
+
+        #[cfg(feature = "session-events")]
+        if was_fresh && matches!(self.state, ConnectionState::SingleConnection(_)) {
+            self.events.emit(SessionEvent::ConnectionStateChanged(
+                ConnectionStatus::SingleConnection,
+            ));
+        }
+    }
+
+    /// Drops the current connection without closing it gracefully, for when a session ends up in an unknown state.
+    pub(super) fn abandon_session(&mut self) {
+        self.disconnect();
+    }
+
+    /// Drops the current connection, if there is one.
+    fn disconnect(&mut self) {
+        self.session_in_progress = false;
+        self.early_data.clear();
+
+        let connection = self.state.take_connection();
+        self.publish_state();
+
+        if connection.is_some() {
+            emit_event!(
+                self.events,
+                SessionEvent::ConnectionStateChanged(ConnectionStatus::Disconnected)
+            );
+        }
+    }
+
+    /// Closes the current connection gracefully between sessions, so the next one opens a fresh connection.
+    async fn recycle_connection(&mut self) {
+        self.early_data.clear();
+
+        if let Some(mut connection) = self.state.take_connection() {
+            self.publish_state();
+
+            emit_event!(
+                self.events,
+                SessionEvent::ConnectionStateChanged(ConnectionStatus::Disconnected)
+            );
+
+            // the connection is being replaced either way, so an error closing it doesn't matter
+            let _ = connection.close().await;
         }
     }
 
     pub(super) async fn post_session_cleanup(&mut self, status_is_error: bool) -> io::Result<()> {
         // close session if server doesn't agree to SINGLE_CONNECTION negotiation, or if an error occurred (since a mutex guarantees only one session is going at a time)
-        if !self.single_connection_established || status_is_error {
-            // SAFETY: connection() should be called before this function, and guarantees inner.connection is non-None
-            let mut connection = self.connection.take().unwrap();
-            connection.close().await?;
+        self.session_in_progress = false;
+        self.lifetime.used(self.clock.instant());
+
+        let connection = self.state.finish_session(status_is_error);
+        self.snapshot.session_completed();
+        self.publish_state();
+
+        if let Some(mut connection) = connection {
+            self.early_data.clear();
 
-            // reset connection status "flags", as a new one will be opened for the next session
-            self.single_connection_established = false;
-            self.first_session_completed = false;
-        } else if !self.first_session_completed {
-            // connection was not closed, so we indicate that a session was completed on this connection to ignore
-            // the single connection mode flag for future sessions on this connection, as required by RFC 8907.
-            // (see section 4.3: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.3-5)
-            self.first_session_completed = true;
+            emit_event!(
+                self.events,
+                SessionEvent::ConnectionStateChanged(ConnectionStatus::Disconnected)
+            );
+
+            connection.close().await?;
+        } else if self.full_duplex && self.early_data.is_empty() {
+            // the connection is kept open for later sessions, which can now share it
+            self.state.split(self.max_body_length);
         }
 
         Ok(())
     }
 }
 
+/// The connection a session is performed over, which is either locked for the session or shared with other sessions.
+pub(crate) enum SessionLink<'a, S> {
+    /// The client is locked for the whole session, so no other sessions can use the connection in the meantime.
+    Exclusive(SessionGuard<'a, ClientInner<S>>),
+
+    /// The connection is shared with other sessions in flight, with replies dispatched by session id.
+    Shared(SharedSession<'a, S>),
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> SessionLink<'_, S> {
+    /// Writes the first packet of a session, reconnecting if necessary.
+    pub(super) async fn send_packet<B: PacketBody + Serialize>(
+        &mut self,
+        packet: Packet<B>,
+        secret_key: Option<&[u8]>,
+    ) -> Result<(), ClientError> {
+        match self {
+            Self::Exclusive(inner) => inner.send_packet(packet, secret_key).await,
+            Self::Shared(session) => session.send_packet(packet, secret_key).await,
+        }
+    }
+
+    /// Writes a later packet of a session.
+    pub(super) async fn send_packet_unchecked<B: PacketBody + Serialize>(
+        &mut self,
+        packet: Packet<B>,
+        secret_key: Option<&[u8]>,
+    ) -> Result<(), ClientError> {
+        match self {
+            Self::Exclusive(inner) => inner.send_packet_unchecked(packet, secret_key).await,
+            Self::Shared(session) => session.send_packet(packet, secret_key).await,
+        }
+    }
+
+    /// Receives the next reply of a session.
+    pub(super) async fn receive_packet<B>(
+        &mut self,
+        secret_key: Option<&[u8]>,
+        expected_sequence_number: u8,
+    ) -> Result<Packet<B>, ClientError>
+    where
+        B: PacketBody + for<'a> Deserialize<'a>,
+    {
+        match self {
+            Self::Exclusive(inner) => {
+                inner
+                    .receive_packet(secret_key, expected_sequence_number)
+                    .await
+            }
+            Self::Shared(session) => {
+                session
+                    .receive_packet(secret_key, expected_sequence_number)
+                    .await
+            }
+        }
+    }
+
+    /// Updates the single connection status of the connection based on a reply.
+    ///
+    /// Shared connections are already in single connection mode, so this only affects exclusive ones.
+    pub(super) fn set_internal_single_connect_status(&mut self, header: &HeaderInfo) {
+        if let Self::Exclusive(inner) = self {
+            inner.set_internal_single_connect_status(header);
+        }
+    }
+
+    /// Completes a session, closing the connection if it can't be reused.
+    pub(super) async fn post_session_cleanup(&mut self, status_is_error: bool) -> io::Result<()> {
+        match self {
+            Self::Exclusive(inner) => inner.post_session_cleanup(status_is_error).await,
+            Self::Shared(session) => session.post_session_cleanup(status_is_error).await,
+        }
+    }
+
+    /// Ends a session that was left in an unknown state.
+    ///
+    /// An exclusive connection is dropped, while replies that are still sent for the session over a shared connection
+    /// are discarded.
+    pub(super) fn abandon_session(&mut self) {
+        match self {
+            Self::Exclusive(inner) => inner.abandon_session(),
+            Self::Shared(session) => session.abandon_session(),
+        }
+    }
+}
+
+/// Limits on how long a single connection is reused for, along with the times they're measured from.
+#[derive(Debug, Default)]
+struct ConnectionLifetime {
+    /// How long a connection can sit idle between sessions before it's replaced.
+    idle_timeout: Option<Duration>,
+
+    /// How long after being opened a connection is replaced, regardless of how often it's used.
+    max_age: Option<Duration>,
+
+    /// When the current connection was opened.
+    opened_at: Option<Instant>,
+
+    /// When the current connection last finished a session (or was opened, if it hasn't yet).
+    last_used_at: Option<Instant>,
+}
+
+impl ConnectionLifetime {
+    fn opened(&mut self, now: Instant) {
+        self.opened_at = Some(now);
+        self.last_used_at = Some(now);
+    }
+
+    fn used(&mut self, now: Instant) {
+        self.last_used_at = Some(now);
+    }
+
+    /// Whether the current connection has exceeded either limit by the provided time.
+    fn is_expired(&self, now: Instant) -> bool {
+        let exceeded = |since: Option<Instant>, limit: Option<Duration>| match (since, limit) {
+            (Some(since), Some(limit)) => now.saturating_duration_since(since) >= limit,
+            _ => false,
+        };
+
+        exceeded(self.last_used_at, self.idle_timeout) || exceeded(self.opened_at, self.max_age)
+    }
+}
+
+/// The outcome of checking whether a connection is still open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionCheck {
+    /// The connection is open, with nothing to read yet.
+    Open,
+
+    /// The connection is open and the server already sent data, whose first byte was read by the check.
+    EarlyData(u8),
+
+    /// The connection was closed by the other side.
+    Closed,
+}
+
 /// Checks if the provided connection is still open on both sides.
 ///
 /// This is accomplished by attempting to read a single byte from the connection
 /// and checking for an EOF condition or specific errors (broken pipe/connection reset).
+/// If a byte is actually read, it's returned so the caller can keep it for the next read.
 ///
 /// This might be overkill, but during testing I encountered a case where a write succeeded
 /// and a subsequent read hung due to the connection being closed on the other side, so
 /// avoiding that is preferable.
-async fn is_connection_open<C>(connection: &mut C) -> io::Result<bool>
+async fn check_connection<C>(connection: &mut C) -> io::Result<ConnectionCheck>
 where
     C: AsyncRead + Unpin,
 {
@@ -256,7 +747,7 @@ where
         // expecting any data to actually be ready
         Poll::Ready(ready) => match ready {
             // read of length 0 indicates an EOF, which happens when the other side closes a TCP connection
-            Ok(0) => Ok(false),
+            Ok(0) => Ok(ConnectionCheck::Closed),
 
             Err(e) => match e.kind() {
                 // these errors indicate that the connection is closed, which is the exact
@@ -264,7 +755,9 @@ where
                 //
                 // BrokenPipe seems to be Linux-specific (?), ConnectionReset is more general though
                 // (checked TCP & read(2) man pages for MacOS/FreeBSD/Linux)
-                io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset => Ok(false),
+                io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset => {
+                    Ok(ConnectionCheck::Closed)
+                }
 
                 // bubble up any other errors to the caller
                 _ => Err(e),
@@ -272,10 +765,155 @@ where
 
             // if there's data still available, the connection is still open, although
             // this shouldn't happen in the context of TACACS+
-            Ok(_) => Ok(true),
+            Ok(_) => Ok(ConnectionCheck::EarlyData(buffer[0])),
         },
 
         // nothing ready to read -> connection is still open
-        Poll::Pending => Ok(true),
+        Poll::Pending => Ok(ConnectionCheck::Open),
     }
 }
+
+/// Whether an IO error indicates that the server closed the connection.
+///
+/// An EOF mid-packet or a reset/broken pipe means the other end is gone, so the connection can't be reused and a new
+/// one has to be opened for the next session.
+fn connection_closed(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
+
+/// Deserializes a received packet, unobfuscating it if a secret key is provided.
+fn deserialize_reply<B>(
+    buffer: BytesMut,
+    secret_key: Option<&[u8]>,
+) -> Result<Packet<B>, ClientError>
+where
+    B: PacketBody + for<'a> Deserialize<'a>,
+{
+    let packet = if let Some(key) = secret_key {
+        Packet::from_bytes(key, buffer)?
+    } else {
+        Packet::from_bytes_unobfuscated(buffer)?
+    };
+
+    Ok(packet)
+}
+
+/// Checks that a reply has the sequence number expected at this point in its session.
+fn check_sequence_number<B: PacketBody>(
+    packet: Packet<B>,
+    expected_sequence_number: u8,
+) -> Result<Packet<B>, ClientError> {
+    let actual_sequence_number = packet.header().sequence_number();
+    if actual_sequence_number == expected_sequence_number {
+        Ok(packet)
+    } else {
+        Err(ClientError::SequenceNumberMismatch {
+            expected: expected_sequence_number,
+            actual: actual_sequence_number,
+        })
+    }
+}
+
+/// Serializes a packet into a reusable buffer, obfuscating it if a secret key is provided.
+///
+/// The packet is appended to the cleared buffer rather than written over zeroed space, so its bytes are only written once.
+fn serialize_packet<B: PacketBody + Serialize>(
+    packet: Packet<B>,
+    secret_key: Option<&[u8]>,
+    buffer: &mut Vec<u8>,
+) -> Result<(), ClientError> {
+    let size = packet.encoded_size();
+    buffer.clear();
+    buffer
+        .try_reserve_exact(size)
+        .map_err(|source| ClientError::AllocationFailed { size, source })?;
+
+    if let Some(key) = secret_key {
+        packet.serialize_into_vec(key, buffer)?;
+    } else {
+        packet.serialize_unobfuscated_into_vec(buffer)?;
+    }
+
+    Ok(())
+}
+
+/// Why a packet couldn't be read by [`read_framed_packet`].
+enum FrameError {
+    /// Reading from the connection failed.
+    Io(io::Error),
+
+    /// The packet was rejected after its header was read, so the connection is left partway through it.
+    Unread(ClientError),
+}
+
+/// Reads the raw bytes of a full packet, using the provided buffer for its allocation.
+///
+/// A packet whose header announces a body longer than `max_body_length` is rejected before any space is allocated for
+/// it. The packet is split off the buffer, so its allocation can be reclaimed by the next read once it's dropped.
+async fn read_framed_packet<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut BytesMut,
+    max_body_length: usize,
+) -> Result<BytesMut, FrameError> {
+    let mut header = [0; HeaderInfo::HEADER_SIZE_BYTES];
+    reader
+        .read_exact(&mut header)
+        .await
+        .map_err(FrameError::Io)?;
+
+    // the body is obfuscated at this point, so its length can only be taken from the header
+    // (KnownLength only applies once a body is deobfuscated)
+    let body_length = NetworkEndian::read_u32(&header[8..12]) as usize;
+    if body_length > max_body_length {
+        return Err(FrameError::Unread(
+            DeserializeError::BodyTooLong {
+                length: body_length,
+                max: max_body_length,
+            }
+            .into(),
+        ));
+    }
+
+    // size the buffer for the full packet upfront, so it never has to grow while reading
+    let packet_length = HeaderInfo::HEADER_SIZE_BYTES + body_length;
+    try_resize_zeroed(buffer, packet_length).map_err(|source| {
+        FrameError::Unread(ClientError::AllocationFailed {
+            size: packet_length,
+            source,
+        })
+    })?;
+
+    buffer[..HeaderInfo::HEADER_SIZE_BYTES].copy_from_slice(&header);
+    let read_result = reader
+        .read_exact(&mut buffer[HeaderInfo::HEADER_SIZE_BYTES..])
+        .await;
+
+    let packet = buffer.split();
+    read_result.map(|()| packet).map_err(FrameError::Io)
+}
+
+/// Clears a buffer and resizes it to hold `length` zeroed bytes, reporting allocation failure rather than aborting.
+///
+/// Body lengths from a server are capped before getting here, so this mainly keeps a tight memory limit on the process
+/// from taking down everything else along with the session.
+fn try_resize_zeroed(buffer: &mut BytesMut, length: usize) -> Result<(), TryReserveError> {
+    buffer.clear();
+
+    // reclaiming the existing allocation never allocates, so only a replacement needs to be reserved fallibly
+    if !buffer.try_reclaim(length) {
+        let mut replacement = Vec::new();
+        replacement.try_reserve_exact(length)?;
+
+        // a uniquely owned Bytes is converted back without copying, so this keeps the reserved allocation
+        *buffer = BytesMut::from(Bytes::from(replacement));
+    }
+
+    buffer.resize(length, 0);
+    Ok(())
+}
@@ -0,0 +1,497 @@
+//! Connections shared between concurrent sessions in single connection mode, with replies dispatched by session id.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::poll_fn;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Poll, Waker};
+use std::time::Instant;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use bytes::BytesMut;
+use futures::io::{ReadHalf, WriteHalf};
+use futures::lock::Mutex as AsyncMutex;
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tacacs_plus_protocol::{Deserialize, Packet, PacketBody, Serialize};
+
+use super::{check_connection, check_sequence_number, connection_closed, deserialize_reply};
+use super::{
+    read_framed_packet, serialize_packet, ClientInner, ConnectionCheck, FrameError, StateSnapshot,
+};
+use crate::stats::StatsRecorder;
+use crate::{ClientError, Clock, OperationPhase};
+
+#[cfg(feature = "session-events")]
+use crate::events::{EventBus, SessionEvent};
+
+#[cfg(test)]
+mod tests;
+
+/// A connection split into independent read & write halves, so several sessions can be in flight at once.
+///
+/// Requests are written whole while holding the write half, so packets of different sessions never interleave. Replies
+/// are read by whichever waiting session gets to the read half first, which hands each reply to the session it's
+/// addressed to; this avoids a separate dispatcher task, which would tie the client to a specific runtime.
+pub(crate) struct DuplexConnection<S> {
+    reader: AsyncMutex<ReadHalf<S>>,
+    writer: AsyncMutex<WriteHalf<S>>,
+    dispatch: Mutex<Dispatch>,
+
+    /// The longest body a server's packet can announce, beyond which the connection fails.
+    max_body_length: usize,
+
+    /// Whether the client has stopped handing this connection out to new sessions, e.g. since it expired.
+    retired: AtomicBool,
+}
+
+/// The sessions waiting for replies on a shared connection.
+#[derive(Debug, Default)]
+struct Dispatch {
+    /// Replies that were read for each session in flight, but haven't been picked up yet.
+    mailboxes: HashMap<u32, Mailbox>,
+
+    /// Whether a session is currently reading from the connection.
+    reading: bool,
+
+    /// The kind of error that made the connection unusable, if any.
+    failure: Option<io::ErrorKind>,
+}
+
+impl Dispatch {
+    /// Wakes all sessions waiting for a reply, e.g. so one of them takes over reading from the connection.
+    fn wake_all(&mut self) {
+        for waker in self
+            .mailboxes
+            .values_mut()
+            .filter_map(|mailbox| mailbox.waker.take())
+        {
+            waker.wake();
+        }
+    }
+
+    /// Marks the connection as unusable, waking all sessions so they can report the error.
+    fn fail(&mut self, kind: io::ErrorKind) {
+        self.failure.get_or_insert(kind);
+        self.reading = false;
+        self.wake_all();
+    }
+}
+
+#[derive(Debug, Default)]
+struct Mailbox {
+    replies: VecDeque<BytesMut>,
+    waker: Option<Waker>,
+}
+
+impl<S: AsyncRead + AsyncWrite> DuplexConnection<S> {
+    /// Splits a connection, which must not have any unread data left from previous sessions.
+    pub(super) fn new(connection: S, max_body_length: usize) -> Self {
+        let (reader, writer) = connection.split();
+
+        Self {
+            reader: AsyncMutex::new(reader),
+            writer: AsyncMutex::new(writer),
+            dispatch: Mutex::default(),
+            max_body_length,
+            retired: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<S> DuplexConnection<S> {
+    fn dispatch(&self) -> MutexGuard<'_, Dispatch> {
+        // the dispatch state is never left inconsistent while locked, so poisoning can be ignored
+        self.dispatch.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether this connection can be handed out to new sessions.
+    pub(super) fn is_usable(&self) -> bool {
+        !self.is_retired() && self.dispatch().failure.is_none()
+    }
+
+    pub(super) fn is_retired(&self) -> bool {
+        self.retired.load(Ordering::Relaxed)
+    }
+
+    /// Stops handing this connection out to new sessions, while letting sessions already in flight finish.
+    ///
+    /// The connection is closed once the last of those sessions is done with it.
+    pub(super) fn retire(&self) {
+        self.retired.store(true, Ordering::Relaxed);
+    }
+
+    /// Starts routing replies with the provided session id to a session.
+    fn register(&self, session_id: u32) -> Result<(), ClientError> {
+        let mut dispatch = self.dispatch();
+        if let Some(kind) = dispatch.failure {
+            return Err(failure_error(kind, OperationPhase::SendingRequest));
+        }
+
+        if dispatch.mailboxes.contains_key(&session_id) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "session id already in use on shared connection",
+            )
+            .into());
+        }
+
+        dispatch.mailboxes.insert(session_id, Mailbox::default());
+        Ok(())
+    }
+
+    /// Stops routing replies to a session, discarding any that it didn't pick up.
+    fn unregister(&self, session_id: u32) {
+        self.dispatch().mailboxes.remove(&session_id);
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> DuplexConnection<S> {
+    /// Checks that the connection is still open if no sessions are using it, returning whether it can be reused.
+    ///
+    /// Any data the server sent while the connection sat idle can't be attributed to a session, so the connection
+    /// isn't reused in that case either.
+    pub(super) async fn check_if_idle(&self) -> bool {
+        {
+            let mut dispatch = self.dispatch();
+            if !dispatch.mailboxes.is_empty() || dispatch.reading {
+                // sessions in flight notice if the connection was closed, so it's left to them
+                return true;
+            }
+
+            dispatch.reading = true;
+        }
+
+        let mut turn = ReadTurn::new(self);
+        let check = check_connection(&mut *self.reader.lock().await).await;
+        turn.finish();
+
+        matches!(check, Ok(ConnectionCheck::Open))
+    }
+
+    /// Writes & flushes the bytes of a serialized packet to the connection.
+    async fn write(&self, packet_bytes: &[u8]) -> Result<(), ClientError> {
+        if let Some(kind) = self.dispatch().failure {
+            return Err(failure_error(kind, OperationPhase::SendingRequest));
+        }
+
+        let mut writer = self.writer.lock().await;
+
+        // a partially written packet leaves the connection unusable, which is noticed if this write is cancelled
+        let mut failure_guard = FailureGuard(Some(self));
+        let write_result = match writer.write_all(packet_bytes).await {
+            Ok(()) => writer.flush().await,
+            Err(err) => Err(err),
+        };
+        failure_guard.0 = None;
+
+        write_result.map_err(|err| self.fail(err, OperationPhase::SendingRequest))
+    }
+
+    /// Waits for the next reply addressed to a session, reading from the connection in turn with other sessions.
+    async fn receive(&self, session_id: u32) -> Result<BytesMut, ClientError> {
+        loop {
+            // wait until either a reply was handed to this session, or no other session is reading
+            let delivered = poll_fn(|cx| {
+                let mut dispatch = self.dispatch();
+                if let Some(kind) = dispatch.failure {
+                    return Poll::Ready(Err(failure_error(kind, OperationPhase::AwaitingReply)));
+                }
+
+                let reading = dispatch.reading;
+                let Some(mailbox) = dispatch.mailboxes.get_mut(&session_id) else {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "session not registered on shared connection",
+                    )
+                    .into()));
+                };
+
+                if let Some(reply) = mailbox.replies.pop_front() {
+                    Poll::Ready(Ok(Some(reply)))
+                } else if !reading {
+                    dispatch.reading = true;
+                    Poll::Ready(Ok(None))
+                } else {
+                    mailbox.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            })
+            .await?;
+
+            if let Some(reply) = delivered {
+                return Ok(reply);
+            }
+
+            // a partially read packet leaves the connection unusable, which is noticed if the read is cancelled
+            let mut turn = ReadTurn::new(self);
+            let packet = self.read_packet().await?;
+            turn.finish();
+
+            let addressee = NetworkEndian::read_u32(&packet[4..8]);
+            if addressee == session_id {
+                return Ok(packet);
+            }
+
+            // replies for sessions that were abandoned are discarded
+            let mut dispatch = self.dispatch();
+            if let Some(mailbox) = dispatch.mailboxes.get_mut(&addressee) {
+                mailbox.replies.push_back(packet);
+            }
+
+            // the dispatch state has to be unlocked before the turn is passed on
+            drop(dispatch);
+        }
+    }
+
+    /// Reads the raw bytes of a full packet from the connection.
+    async fn read_packet(&self) -> Result<BytesMut, ClientError> {
+        let mut reader = self.reader.lock().await;
+
+        // packets are handed to other sessions, so each one gets its own allocation
+        read_framed_packet(&mut *reader, &mut BytesMut::new(), self.max_body_length)
+            .await
+            .map_err(|err| match err {
+                FrameError::Io(err) => self.fail(err, OperationPhase::AwaitingReply),
+                FrameError::Unread(err) => {
+                    // the rest of the packet can't be skipped without reading it, so the connection is out of sync
+                    let kind = match err {
+                        ClientError::AllocationFailed { .. } => io::ErrorKind::OutOfMemory,
+                        _ => io::ErrorKind::InvalidData,
+                    };
+                    self.dispatch().fail(kind);
+                    err
+                }
+            })
+    }
+
+    /// Marks the connection as unusable after an IO error, converting the error for the session that encountered it.
+    fn fail(&self, error: io::Error, phase: OperationPhase) -> ClientError {
+        self.dispatch().fail(error.kind());
+
+        if connection_closed(&error) {
+            ClientError::ConnectionClosedByServer { during: phase }
+        } else {
+            error.into()
+        }
+    }
+}
+
+impl<S> fmt::Debug for DuplexConnection<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dispatch = self.dispatch();
+        f.debug_struct("DuplexConnection")
+            .field("sessions", &dispatch.mailboxes.len())
+            .field("failure", &dispatch.failure)
+            .field("retired", &self.is_retired())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Converts the error a shared connection failed with into an error for another session using it.
+fn failure_error(kind: io::ErrorKind, phase: OperationPhase) -> ClientError {
+    let error = io::Error::from(kind);
+
+    if connection_closed(&error) {
+        ClientError::ConnectionClosedByServer { during: phase }
+    } else {
+        error.into()
+    }
+}
+
+/// A session's turn reading from a shared connection, which is passed on to another session once it's over.
+///
+/// If the turn is dropped before it's finished, the connection is marked as unusable, since it might have been left
+/// partway through a packet.
+struct ReadTurn<'connection, S> {
+    connection: &'connection DuplexConnection<S>,
+    finished: bool,
+}
+
+impl<'connection, S> ReadTurn<'connection, S> {
+    fn new(connection: &'connection DuplexConnection<S>) -> Self {
+        Self {
+            connection,
+            finished: false,
+        }
+    }
+
+    fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+
+impl<S> Drop for ReadTurn<'_, S> {
+    fn drop(&mut self) {
+        let mut dispatch = self.connection.dispatch();
+
+        if self.finished {
+            dispatch.reading = false;
+            dispatch.wake_all();
+        } else {
+            dispatch.fail(io::ErrorKind::Interrupted);
+        }
+    }
+}
+
+/// Marks a shared connection as unusable when dropped, unless it's defused by taking the connection out.
+struct FailureGuard<'connection, S>(Option<&'connection DuplexConnection<S>>);
+
+impl<S> Drop for FailureGuard<'_, S> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.0 {
+            connection.dispatch().fail(io::ErrorKind::Interrupted);
+        }
+    }
+}
+
+/// A session performed over a connection shared with other sessions, without holding the client locked.
+pub(crate) struct SharedSession<'a, S> {
+    inner: &'a AsyncMutex<ClientInner<S>>,
+    connection: Arc<DuplexConnection<S>>,
+
+    /// The id of the session, once its first packet was sent.
+    session_id: Option<u32>,
+
+    /// When the last packet of this session was sent, if no reply to it has been received yet.
+    last_sent_at: Option<Instant>,
+
+    peer_secret: Option<Arc<[u8]>>,
+    snapshot: Arc<StateSnapshot>,
+    stats: Arc<StatsRecorder>,
+    clock: Arc<dyn Clock>,
+
+    #[cfg(feature = "session-events")]
+    events: Arc<EventBus>,
+}
+
+impl<'a, S: AsyncRead + AsyncWrite + Unpin> SharedSession<'a, S> {
+    pub(super) fn new(
+        inner_mutex: &'a AsyncMutex<ClientInner<S>>,
+        inner: &ClientInner<S>,
+        connection: Arc<DuplexConnection<S>>,
+    ) -> Self {
+        Self {
+            inner: inner_mutex,
+            connection,
+            session_id: None,
+            last_sent_at: None,
+            peer_secret: inner.peer_secret.clone(),
+            snapshot: inner.snapshot.clone(),
+            stats: inner.stats.clone(),
+            clock: inner.clock.clone(),
+            #[cfg(feature = "session-events")]
+            events: inner.events.clone(),
+        }
+    }
+
+    /// Writes a packet to the shared connection, registering the session for replies with its first packet.
+    pub(super) async fn send_packet<B: PacketBody + Serialize>(
+        &mut self,
+        packet: Packet<B>,
+        secret_key: Option<&[u8]>,
+    ) -> Result<(), ClientError> {
+        let header = *packet.header();
+        if self.session_id.is_none() {
+            self.connection.register(header.session_id())?;
+            self.session_id = Some(header.session_id());
+        }
+
+        let secret_key = self.peer_secret.as_deref().or(secret_key);
+
+        let mut packet_bytes = Vec::new();
+        serialize_packet(packet, secret_key, &mut packet_bytes)?;
+        self.connection.write(&packet_bytes).await?;
+
+        self.stats.bytes_sent(packet_bytes.len());
+        self.last_sent_at = Some(self.clock.instant());
+
+        emit_event!(
+            self.events,
+            SessionEvent::PacketSent {
+                session_id: header.session_id(),
+                sequence_number: header.sequence_number(),
+                packet_type: B::TYPE,
+            }
+        );
+
+        Ok(())
+    }
+
+    /// Waits for the next reply to this session on the shared connection.
+    pub(super) async fn receive_packet<B>(
+        &mut self,
+        secret_key: Option<&[u8]>,
+        expected_sequence_number: u8,
+    ) -> Result<Packet<B>, ClientError>
+    where
+        B: PacketBody + for<'b> Deserialize<'b>,
+    {
+        // SAFETY: every session sends a packet before waiting for a reply
+        let session_id = self
+            .session_id
+            .expect("replies are only awaited after sending a packet");
+        let buffer = self.connection.receive(session_id).await?;
+
+        self.stats.bytes_received(buffer.len());
+        if let Some(sent_at) = self.last_sent_at.take() {
+            let now = self.clock.instant();
+            self.stats
+                .round_trip(now.saturating_duration_since(sent_at));
+        }
+
+        let secret_key = self.peer_secret.as_deref().or(secret_key);
+        let packet: Packet<B> = deserialize_reply(buffer, secret_key)?;
+
+        emit_event!(
+            self.events,
+            SessionEvent::PacketReceived {
+                session_id: packet.header().session_id(),
+                sequence_number: packet.header().sequence_number(),
+                packet_type: B::TYPE,
+            }
+        );
+
+        check_sequence_number(packet, expected_sequence_number)
+    }
+
+    /// Completes the session, retiring the shared connection if the session ended in an error.
+    pub(super) async fn post_session_cleanup(&mut self, status_is_error: bool) -> io::Result<()> {
+        self.unregister();
+        self.snapshot.session_completed();
+
+        // a retired connection was already replaced, so its state doesn't matter anymore
+        if !self.connection.is_retired() {
+            let mut inner = self.inner.lock().await;
+            if inner.state.is_duplex(&self.connection) {
+                inner.lifetime.used(self.clock.instant());
+
+                if status_is_error {
+                    inner.retire_duplex();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Leaves a session that ended in an unknown state, discarding any replies the server still sends for it.
+    pub(super) fn abandon_session(&mut self) {
+        self.unregister();
+    }
+
+    fn unregister(&mut self) {
+        if let Some(session_id) = self.session_id.take() {
+            self.connection.unregister(session_id);
+        }
+    }
+}
+
+impl<S> Drop for SharedSession<'_, S> {
+    fn drop(&mut self) {
+        if let Some(session_id) = self.session_id.take() {
+            self.connection.unregister(session_id);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use futures::{AsyncReadExt, AsyncWriteExt};
+use tacacs_plus_protocol::{DeserializeError, PacketStreamParser};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use crate::{Client, ClientBuilder, ClientError, ContextBuilder, OperationPhase, ResponseStatus};
+
+/// Reads a request from a client, returning its session id & the user it was sent for.
+async fn read_request(stream: &mut Compat<TcpStream>) -> ([u8; 4], String) {
+    let mut header = [0; 12];
+    stream.read_exact(&mut header).await.unwrap();
+
+    let body_length = u32::from_be_bytes(header[8..12].try_into().unwrap());
+    let mut body = vec![0; body_length as usize];
+    stream.read_exact(&mut body).await.unwrap();
+
+    // the user comes right after the fixed fields & argument lengths of an authorization request
+    let user_start = 8 + usize::from(body[7]);
+    let user = &body[user_start..user_start + usize::from(body[4])];
+
+    (
+        header[4..8].try_into().unwrap(),
+        String::from_utf8(user.to_vec()).unwrap(),
+    )
+}
+
+/// Writes an unobfuscated PASS_ADD authorization reply agreeing to single connection mode, with the user the request
+/// was sent for as its server message.
+async fn write_reply(stream: &mut Compat<TcpStream>, session_id: [u8; 4], user: &str) {
+    let mut body = vec![0x01, 0];
+    body.extend((user.len() as u16).to_be_bytes());
+    body.extend([0, 0]);
+    body.extend(user.as_bytes());
+
+    let mut reply = vec![0xc0, 0x02, 2, 0x05];
+    reply.extend(session_id);
+    reply.extend((body.len() as u32).to_be_bytes());
+    reply.extend(body);
+
+    stream.write_all(&reply).await.unwrap();
+}
+
+async fn full_duplex_client(listener: &TcpListener) -> Client<Compat<TcpStream>> {
+    let address = listener.local_addr().unwrap();
+
+    ClientBuilder::new()
+        .full_duplex(true)
+        .build(Box::new(move || {
+            Box::pin(async move { Ok(TcpStream::connect(address).await?.compat()) })
+        }))
+}
+
+async fn authorize(client: &Client<Compat<TcpStream>>, user: &str) -> Result<String, ClientError> {
+    let context = ContextBuilder::new(user.to_owned()).build().unwrap();
+    let response = client.authorize(context, []).await?;
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    Ok(response.user_message)
+}
+
+#[tokio::test]
+async fn replies_dispatched_to_sessions_out_of_order() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = full_duplex_client(&listener).await;
+
+    let server = tokio::spawn(async move {
+        let mut stream = listener.accept().await.unwrap().0.compat();
+
+        // the first session agrees to single connection mode
+        let (session_id, user) = read_request(&mut stream).await;
+        write_reply(&mut stream, session_id, &user).await;
+
+        // both requests arrive before either is answered, and are answered in reverse order
+        let first = read_request(&mut stream).await;
+        let second = read_request(&mut stream).await;
+        write_reply(&mut stream, second.0, &second.1).await;
+        write_reply(&mut stream, first.0, &first.1).await;
+
+        // keep the connection open until the client is done with it
+        let _ = stream.read(&mut [0]).await;
+    });
+
+    assert_eq!(authorize(&client, "initial").await.unwrap(), "initial");
+
+    let sessions = async { futures::join!(authorize(&client, "alice"), authorize(&client, "bob")) };
+    let (alice, bob) = tokio::time::timeout(Duration::from_secs(5), sessions)
+        .await
+        .expect("sessions should be in flight concurrently");
+    assert_eq!(alice.unwrap(), "alice");
+    assert_eq!(bob.unwrap(), "bob");
+
+    // all sessions were performed over the same connection
+    assert_eq!(client.stats().reconnects, 0);
+
+    drop(client);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn sessions_retried_after_shared_connection_closed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = full_duplex_client(&listener).await;
+
+    let server = tokio::spawn(async move {
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let (session_id, user) = read_request(&mut stream).await;
+        write_reply(&mut stream, session_id, &user).await;
+
+        // the connection is closed while two sessions are waiting for replies
+        read_request(&mut stream).await;
+        read_request(&mut stream).await;
+        drop(stream);
+
+        // both sessions are retried on a new connection
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        for _ in 0..2 {
+            let (session_id, user) = read_request(&mut stream).await;
+            write_reply(&mut stream, session_id, &user).await;
+        }
+    });
+
+    authorize(&client, "initial").await.unwrap();
+
+    let (alice, bob) = futures::join!(authorize(&client, "alice"), authorize(&client, "bob"));
+    assert_eq!(alice.unwrap(), "alice");
+    assert_eq!(bob.unwrap(), "bob");
+    assert_eq!(client.stats().reconnects, 1);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn closed_shared_connection_fails_sessions_in_flight() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = full_duplex_client(&listener).await;
+
+    let server = tokio::spawn(async move {
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let (session_id, user) = read_request(&mut stream).await;
+        write_reply(&mut stream, session_id, &user).await;
+
+        // the connection is closed while an accounting session is waiting for its reply
+        let _ = stream.read(&mut [0; 1024]).await;
+    });
+
+    authorize(&client, "initial").await.unwrap();
+
+    let context = ContextBuilder::new(String::from("alice")).build().unwrap();
+    let Err(error) = client.account_begin(context, []).await else {
+        panic!("accounting shouldn't be retried");
+    };
+    assert!(
+        matches!(
+            error,
+            ClientError::ConnectionClosedByServer {
+                during: OperationPhase::AwaitingReply
+            }
+        ),
+        "unexpected error: {error:?}"
+    );
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn oversized_reply_fails_shared_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = full_duplex_client(&listener).await;
+
+    let server = tokio::spawn(async move {
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let (session_id, user) = read_request(&mut stream).await;
+        write_reply(&mut stream, session_id, &user).await;
+
+        // the next reply announces a body far longer than the client accepts, but never sends it
+        let mut header = [0; 12];
+        stream.read_exact(&mut header).await.unwrap();
+        let mut body = vec![0; u32::from_be_bytes(header[8..12].try_into().unwrap()) as usize];
+        stream.read_exact(&mut body).await.unwrap();
+
+        let mut reply = vec![0xc0, 0x03, 2, 0x05];
+        reply.extend(&header[4..8]);
+        reply.extend([0xff; 4]);
+        stream.write_all(&reply).await.unwrap();
+
+        // the client gives up on the connection, since the rest of it can't be made sense of
+        let mut stream = listener.accept().await.unwrap().0.compat();
+        let (session_id, user) = read_request(&mut stream).await;
+        write_reply(&mut stream, session_id, &user).await;
+    });
+
+    authorize(&client, "initial").await.unwrap();
+
+    let context = ContextBuilder::new(String::from("alice")).build().unwrap();
+    let Err(error) = client.account_begin(context, []).await else {
+        panic!("reply with oversized body should be rejected");
+    };
+    assert!(
+        matches!(
+            error,
+            ClientError::InvalidPacketReceived(DeserializeError::BodyTooLong {
+                length: 0xffff_ffff,
+                max: PacketStreamParser::DEFAULT_MAX_BODY_LENGTH,
+            })
+        ),
+        "unexpected error: {error:?}"
+    );
+
+    assert_eq!(authorize(&client, "bob").await.unwrap(), "bob");
+    assert_eq!(client.stats().reconnects, 1);
+
+    server.await.unwrap();
+}
@@ -0,0 +1,224 @@
+//! Typed states for the connection held by a client.
+
+use std::fmt;
+use std::mem;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::{HeaderInfo, PacketFlags};
+
+use super::duplex::DuplexConnection;
+use crate::SingleConnectionNegotiation;
+
+/// The state of a client's connection with respect to single connection mode, as described in [RFC8907 section 4.3].
+///
+/// The single connection flag is only meaningful in the first reply of the first session on a connection, so
+/// the flag in any later packets is ignored once a connection has left the [`FreshConnection`](Self::FreshConnection) state.
+///
+/// [RFC8907 section 4.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.3
+#[derive(Debug)]
+pub(super) enum ConnectionState<S> {
+    /// No connection is currently open.
+    Disconnected,
+
+    /// A connection was opened, but the server hasn't replied during the first session yet.
+    FreshConnection(S),
+
+    /// The server agreed to single connection mode, so the connection can be reused across sessions.
+    SingleConnection(S),
+
+    /// The server didn't agree to single connection mode, so the connection is closed after the current session.
+    PerSession(S),
+
+    /// The server agreed to single connection mode and the connection was split, so sessions can share it concurrently.
+    Duplex(Arc<DuplexConnection<S>>),
+}
+
+impl<S> ConnectionState<S> {
+    /// Returns a mutable reference to the open connection, if there is one that isn't shared between sessions.
+    pub(super) fn connection(&mut self) -> Option<&mut S> {
+        match self {
+            Self::Disconnected | Self::Duplex(_) => None,
+            Self::FreshConnection(connection)
+            | Self::SingleConnection(connection)
+            | Self::PerSession(connection) => Some(connection),
+        }
+    }
+
+    /// Transitions to the state of a newly opened connection, replacing any existing one.
+    pub(super) fn open(&mut self, connection: S) {
+        self.take_connection();
+        *self = Self::FreshConnection(connection);
+    }
+
+    /// Returns the shared connection, if the connection was split.
+    pub(super) fn duplex(&self) -> Option<&Arc<DuplexConnection<S>>> {
+        match self {
+            Self::Duplex(connection) => Some(connection),
+            _ => None,
+        }
+    }
+
+    /// Whether the provided shared connection is the current connection.
+    pub(super) fn is_duplex(&self, connection: &Arc<DuplexConnection<S>>) -> bool {
+        self.duplex()
+            .is_some_and(|current| Arc::ptr_eq(current, connection))
+    }
+
+    /// Updates the single connection status of a fresh connection based on the header of a received reply.
+    ///
+    /// Only replies in the first session on a connection are considered, and of those only the first one
+    /// (i.e., sequence number 2) unless the negotiation mode allows otherwise.
+    pub(super) fn observe_reply(
+        &mut self,
+        header: &HeaderInfo,
+        negotiation: SingleConnectionNegotiation,
+    ) {
+        let single_connection = header.flags().contains(PacketFlags::SINGLE_CONNECTION);
+        let first_reply = header.sequence_number() == 2;
+
+        *self = match (mem::replace(self, Self::Disconnected), negotiation) {
+            (Self::FreshConnection(connection), _) if first_reply && single_connection => {
+                Self::SingleConnection(connection)
+            }
+            (Self::FreshConnection(connection), SingleConnectionNegotiation::FirstReply)
+                if first_reply =>
+            {
+                Self::PerSession(connection)
+            }
+
+            // the connection stays fresh until the end of the session, since a later reply might still agree
+            (Self::FreshConnection(connection), SingleConnectionNegotiation::FirstSession)
+                if single_connection =>
+            {
+                Self::SingleConnection(connection)
+            }
+
+            (other, _) => other,
+        };
+    }
+
+    /// Completes a session, returning the connection if it has to be closed rather than reused.
+    ///
+    /// Only connections in single connection mode are kept open, and even then only if the session didn't end in an error.
+    pub(super) fn finish_session(&mut self, status_is_error: bool) -> Option<S> {
+        match self {
+            Self::SingleConnection(_) if !status_is_error => None,
+            _ => self.take_connection(),
+        }
+    }
+
+    /// Returns the kind of this state, without the connection itself.
+    pub(super) fn kind(&self) -> StateKind {
+        match self {
+            Self::Disconnected => StateKind::Disconnected,
+            Self::FreshConnection(_) => StateKind::FreshConnection,
+            Self::SingleConnection(_) | Self::Duplex(_) => StateKind::SingleConnection,
+            Self::PerSession(_) => StateKind::PerSession,
+        }
+    }
+
+    /// Takes the connection out of this state (if any), leaving it [`Disconnected`](Self::Disconnected).
+    ///
+    /// A shared connection can't be taken out, so it's retired instead, to be closed once the sessions still using it
+    /// are done.
+    pub(super) fn take_connection(&mut self) -> Option<S> {
+        match mem::replace(self, Self::Disconnected) {
+            Self::Disconnected => None,
+            Self::Duplex(connection) => {
+                connection.retire();
+                None
+            }
+            Self::FreshConnection(connection)
+            | Self::SingleConnection(connection)
+            | Self::PerSession(connection) => Some(connection),
+        }
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite> ConnectionState<S> {
+    /// Splits a connection in single connection mode, so it can be shared between concurrent sessions.
+    pub(super) fn split(&mut self, max_body_length: usize) {
+        *self = match mem::replace(self, Self::Disconnected) {
+            Self::SingleConnection(connection) => {
+                Self::Duplex(Arc::new(DuplexConnection::new(connection, max_body_length)))
+            }
+            other => other,
+        };
+    }
+}
+
+/// The kind of a [`ConnectionState`], without any connection attached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum StateKind {
+    Disconnected,
+    FreshConnection,
+    SingleConnection,
+    PerSession,
+}
+
+impl StateKind {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::FreshConnection,
+            2 => Self::SingleConnection,
+            3 => Self::PerSession,
+            _ => Self::Disconnected,
+        }
+    }
+}
+
+/// Connection metadata published by the inner client whenever its state changes.
+///
+/// This is shared with the outer client so it can be inspected (e.g., in `Debug` output) without locking the inner client,
+/// which may be held for the whole duration of a session.
+#[derive(Default)]
+pub(crate) struct StateSnapshot {
+    kind: AtomicU8,
+    connections_opened: AtomicUsize,
+    sessions_completed: AtomicUsize,
+}
+
+impl StateSnapshot {
+    /// Records the current kind of connection state.
+    pub(super) fn publish(&self, kind: StateKind) {
+        self.kind.store(kind as u8, Ordering::Relaxed);
+    }
+
+    /// Records that a new connection was opened.
+    pub(super) fn connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a session was completed.
+    pub(super) fn session_completed(&self) {
+        self.sessions_completed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The last published kind of connection state.
+    pub(crate) fn kind(&self) -> StateKind {
+        StateKind::from_u8(self.kind.load(Ordering::Relaxed))
+    }
+
+    /// The number of connections opened so far.
+    pub(crate) fn connections_opened(&self) -> usize {
+        self.connections_opened.load(Ordering::Relaxed)
+    }
+
+    /// The number of sessions completed so far.
+    pub(crate) fn sessions_completed(&self) -> usize {
+        self.sessions_completed.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for StateSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateSnapshot")
+            .field("state", &self.kind())
+            .field("connections_opened", &self.connections_opened())
+            .field("sessions_completed", &self.sessions_completed())
+            .finish()
+    }
+}
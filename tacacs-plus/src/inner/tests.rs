@@ -1,12 +1,28 @@
+use std::io;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use futures::AsyncWriteExt;
+use bytes::BytesMut;
+use futures::io::Cursor;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tacacs_plus_protocol::authorization;
+use tacacs_plus_protocol::{
+    Arguments, AuthenticationContext, AuthenticationMethod, AuthenticationService,
+    AuthenticationType, FieldText, PrivilegeLevel, UserInformation,
+};
+use tacacs_plus_protocol::{DeserializeError, PacketStreamParser};
+use tacacs_plus_protocol::{HeaderInfo, MajorVersion, MinorVersion, Packet, PacketFlags, Version};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Notify;
 use tokio_util::compat::TokioAsyncReadCompatExt;
 
-use super::is_connection_open;
+use super::{
+    check_connection, serialize_packet, try_resize_zeroed, ClientInner, ConnectionCheck,
+    ConnectionLifetime, ConnectionState,
+};
+use crate::{ClientError, OperationPhase, SingleConnectionNegotiation};
 
 async fn bind_to_port(port: u16) -> TcpListener {
     TcpListener::bind(("localhost", port))
@@ -40,10 +56,10 @@ async fn connection_open_check() {
         .expect("couldn't connect to test listener");
     let mut client = client.compat();
 
-    let is_open = is_connection_open(&mut client)
+    let check = check_connection(&mut client)
         .await
         .expect("couldn't check if connection was open");
-    assert!(is_open);
+    assert_eq!(check, ConnectionCheck::Open);
 
     notify.notify_one();
 }
@@ -87,8 +103,389 @@ async fn connection_closed_check() {
     notify.notified().await;
 
     // ensure connection is detected as closed
-    let is_open = is_connection_open(&mut client)
+    let check = check_connection(&mut client)
         .await
         .expect("couldn't check if connection was open");
-    assert!(!is_open);
+    assert_eq!(check, ConnectionCheck::Closed);
+}
+
+#[tokio::test]
+async fn server_closing_mid_session_is_detected() {
+    // an empty cursor behaves like a connection the server closed without replying
+    let mut inner = ClientInner::new(Box::new(|| {
+        Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })
+    }));
+
+    let error = inner
+        .receive_packet::<authorization::ReplyOwned>(None, 2)
+        .await
+        .expect_err("receiving from a closed connection should fail");
+
+    assert!(matches!(
+        error,
+        ClientError::ConnectionClosedByServer {
+            during: OperationPhase::AwaitingReply
+        }
+    ));
+
+    // the dead connection shouldn't be reused
+    assert!(matches!(inner.state, ConnectionState::Disconnected));
+}
+
+#[tokio::test]
+async fn oversized_body_rejected_before_allocating() {
+    #[rustfmt::skip]
+    let header = vec![
+        // header: version 12.0, authorization, sequence number 2, unencrypted, session id 1234, body length 2^32 - 1
+        0xc0, 0x02, 0x02, 0x01,
+        0x00, 0x00, 0x04, 0xd2,
+        0xff, 0xff, 0xff, 0xff,
+    ];
+
+    let mut inner = ClientInner::new(Box::new(move || {
+        let header = header.clone();
+        Box::pin(async move { Ok(Cursor::new(header)) })
+    }));
+
+    let error = inner
+        .receive_packet::<authorization::ReplyOwned>(None, 2)
+        .await
+        .expect_err("announced body length should be rejected");
+
+    assert!(
+        matches!(
+            error,
+            ClientError::InvalidPacketReceived(DeserializeError::BodyTooLong {
+                length: 0xffff_ffff,
+                max: PacketStreamParser::DEFAULT_MAX_BODY_LENGTH,
+            })
+        ),
+        "unexpected error: {error:?}"
+    );
+
+    // nothing was allocated for the body, and the connection can't be reused since the body wasn't read
+    assert_eq!(inner.receive_buffer.capacity(), 0);
+    assert!(matches!(inner.state, ConnectionState::Disconnected));
+}
+
+#[cfg(feature = "session-events")]
+#[tokio::test]
+async fn connection_changes_are_emitted_as_events() {
+    use futures::StreamExt;
+
+    use crate::{ConnectionStatus, SessionEvent};
+
+    let mut inner = ClientInner::new(Box::new(|| {
+        Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })
+    }));
+    let events = inner.events().subscribe();
+
+    inner
+        .receive_packet::<authorization::ReplyOwned>(None, 2)
+        .await
+        .expect_err("receiving from a closed connection should fail");
+    drop(inner);
+
+    let events: Vec<_> = events.collect().await;
+    assert_eq!(
+        events,
+        [
+            SessionEvent::ConnectionStateChanged(ConnectionStatus::Connected),
+            SessionEvent::ConnectionStateChanged(ConnectionStatus::Disconnected),
+        ]
+    );
+}
+
+fn reply_header(sequence_number: u8, flags: PacketFlags) -> HeaderInfo {
+    HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        sequence_number,
+        flags,
+        1234,
+    )
+}
+
+#[test]
+fn single_connection_agreed_by_server() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+    assert!(matches!(state, ConnectionState::FreshConnection(_)));
+
+    state.observe_reply(
+        &reply_header(2, PacketFlags::SINGLE_CONNECTION),
+        SingleConnectionNegotiation::FirstReply,
+    );
+    assert!(matches!(state, ConnectionState::SingleConnection(_)));
+
+    // connection should be kept for the next session
+    assert_eq!(state.finish_session(false), None);
+    assert_eq!(state.connection(), Some(&mut "connection"));
+
+    // the flag is ignored after the first session, so its absence shouldn't change anything
+    state.observe_reply(
+        &reply_header(2, PacketFlags::empty()),
+        SingleConnectionNegotiation::FirstReply,
+    );
+    assert!(matches!(state, ConnectionState::SingleConnection(_)));
+}
+
+#[test]
+fn single_connection_refused_by_server() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+
+    state.observe_reply(
+        &reply_header(2, PacketFlags::empty()),
+        SingleConnectionNegotiation::FirstReply,
+    );
+    assert!(matches!(state, ConnectionState::PerSession(_)));
+
+    // connection should be closed after the session
+    assert_eq!(state.finish_session(false), Some("connection"));
+    assert!(matches!(state, ConnectionState::Disconnected));
+}
+
+#[test]
+fn single_connection_flag_ignored_outside_first_reply() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+
+    // only the first reply of a session (sequence number 2) negotiates single connection mode
+    state.observe_reply(
+        &reply_header(4, PacketFlags::SINGLE_CONNECTION),
+        SingleConnectionNegotiation::FirstReply,
+    );
+    assert!(matches!(state, ConnectionState::FreshConnection(_)));
+
+    // a session that never got a first reply shouldn't leave the connection open
+    assert_eq!(state.finish_session(false), Some("connection"));
+    assert!(matches!(state, ConnectionState::Disconnected));
+}
+
+#[test]
+fn single_connection_flag_accepted_later_in_first_session() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+
+    // without the flag in the first reply, the outcome isn't decided yet
+    state.observe_reply(
+        &reply_header(2, PacketFlags::empty()),
+        SingleConnectionNegotiation::FirstSession,
+    );
+    assert!(matches!(state, ConnectionState::FreshConnection(_)));
+
+    state.observe_reply(
+        &reply_header(4, PacketFlags::SINGLE_CONNECTION),
+        SingleConnectionNegotiation::FirstSession,
+    );
+    assert!(matches!(state, ConnectionState::SingleConnection(_)));
+
+    assert_eq!(state.finish_session(false), None);
+}
+
+#[test]
+fn single_connection_never_agreed_in_first_session() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+
+    state.observe_reply(
+        &reply_header(2, PacketFlags::empty()),
+        SingleConnectionNegotiation::FirstSession,
+    );
+    state.observe_reply(
+        &reply_header(4, PacketFlags::empty()),
+        SingleConnectionNegotiation::FirstSession,
+    );
+
+    // the server never agreed, so the connection is closed like it would be normally
+    assert_eq!(state.finish_session(false), Some("connection"));
+    assert!(matches!(state, ConnectionState::Disconnected));
+}
+
+#[test]
+fn error_closes_single_connection() {
+    let mut state = ConnectionState::Disconnected;
+    state.open("connection");
+    state.observe_reply(
+        &reply_header(2, PacketFlags::SINGLE_CONNECTION),
+        SingleConnectionNegotiation::FirstReply,
+    );
+
+    assert_eq!(state.finish_session(true), Some("connection"));
+    assert!(matches!(state, ConnectionState::Disconnected));
+}
+
+#[test]
+fn taking_connection_disconnects() {
+    let mut state: ConnectionState<&str> = ConnectionState::Disconnected;
+    assert_eq!(state.take_connection(), None);
+
+    state.open("connection");
+    assert_eq!(state.take_connection(), Some("connection"));
+    assert!(matches!(state, ConnectionState::Disconnected));
+    assert_eq!(state.connection(), None);
+}
+
+#[test]
+fn buffer_resize_reports_allocation_failure() {
+    let mut buffer = BytesMut::new();
+    assert!(try_resize_zeroed(&mut buffer, usize::MAX).is_err());
+
+    // the buffer is still usable afterwards, and keeps its allocation for smaller packets
+    try_resize_zeroed(&mut buffer, 64).expect("small allocation should succeed");
+    assert_eq!(&buffer[..], &[0; 64]);
+
+    let allocation = buffer.as_ptr();
+    buffer.copy_from_slice(&[1; 64]);
+    try_resize_zeroed(&mut buffer, 32).expect("buffer should be reused");
+    assert_eq!(buffer.as_ptr(), allocation);
+    assert_eq!(&buffer[..], &[0; 32]);
+}
+
+#[test]
+fn packets_serialized_by_appending() {
+    for (flags, key) in [
+        (PacketFlags::UNENCRYPTED, None),
+        (PacketFlags::empty(), Some(&b"secret"[..])),
+    ] {
+        let size = authorization_request(flags).encoded_size();
+
+        // a buffer with exactly enough space is filled in place, without being resized or zero-filled first
+        let mut buffer = Vec::with_capacity(size);
+        let allocation = buffer.as_ptr();
+        serialize_packet(authorization_request(flags), key, &mut buffer)
+            .expect("packet should be serialized");
+
+        assert_eq!(buffer.len(), size);
+        assert_eq!(buffer.capacity(), size);
+        assert_eq!(buffer.as_ptr(), allocation);
+
+        let mut expected = vec![0; size];
+        match key {
+            Some(key) => authorization_request(flags).serialize(key, &mut expected),
+            None => authorization_request(flags).serialize_unobfuscated(&mut expected),
+        }
+        .unwrap();
+        assert_eq!(buffer, expected);
+
+        // reusing the buffer replaces its contents rather than appending to them
+        serialize_packet(authorization_request(flags), key, &mut buffer).unwrap();
+        assert_eq!(buffer, expected);
+        assert_eq!(buffer.as_ptr(), allocation);
+    }
+}
+
+#[test]
+fn connection_lifetime_limits() {
+    let opened_at = Instant::now();
+    let mut lifetime = ConnectionLifetime {
+        idle_timeout: Some(Duration::from_secs(60)),
+        max_age: Some(Duration::from_secs(300)),
+        ..Default::default()
+    };
+    lifetime.opened(opened_at);
+
+    assert!(!lifetime.is_expired(opened_at + Duration::from_secs(59)));
+    assert!(lifetime.is_expired(opened_at + Duration::from_secs(60)));
+
+    // using the connection resets the idle timeout, but not its age
+    lifetime.used(opened_at + Duration::from_secs(250));
+    assert!(!lifetime.is_expired(opened_at + Duration::from_secs(299)));
+    assert!(lifetime.is_expired(opened_at + Duration::from_secs(300)));
+}
+
+#[test]
+fn connection_lifetime_unlimited_by_default() {
+    let opened_at = Instant::now();
+    let mut lifetime = ConnectionLifetime::default();
+    lifetime.opened(opened_at);
+
+    assert!(!lifetime.is_expired(opened_at + Duration::from_secs(86400 * 365)));
+}
+
+/// A connection where the server's reply is already available before the request is written.
+struct EagerServer {
+    reply: Cursor<Vec<u8>>,
+}
+
+impl AsyncRead for EagerServer {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.reply).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for EagerServer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn authorization_request(flags: PacketFlags) -> Packet<authorization::Request<'static>> {
+    Packet::new(
+        reply_header(1, flags),
+        authorization::Request::new(
+            AuthenticationMethod::NotSet,
+            AuthenticationContext {
+                privilege_level: PrivilegeLevel::default(),
+                authentication_type: AuthenticationType::NotSet,
+                service: AuthenticationService::Login,
+            },
+            UserInformation::new(
+                "user",
+                FieldText::try_from("tty0").unwrap(),
+                FieldText::try_from("127.0.0.1").unwrap(),
+            )
+            .unwrap(),
+            Arguments::new(&[]).unwrap(),
+        ),
+    )
+}
+
+#[tokio::test]
+async fn early_data_kept_by_connection_check() {
+    #[rustfmt::skip]
+    let reply = vec![
+        // header: version 12.0, authorization, sequence number 2, unencrypted, session id 1234, body length 6
+        0xc0, 0x02, 0x02, 0x01,
+        0x00, 0x00, 0x04, 0xd2,
+        0x00, 0x00, 0x00, 0x06,
+        // body: PASS_ADD status, no arguments, empty server message & data
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+
+    let mut inner = ClientInner::new(Box::new(move || {
+        let reply = Cursor::new(reply.clone());
+        Box::pin(async move { Ok(EagerServer { reply }) })
+    }));
+
+    let request = authorization_request(PacketFlags::UNENCRYPTED);
+
+    // the check reads the first byte of the reply, which shouldn't corrupt the packet that's read afterwards
+    inner
+        .send_packet(request, None)
+        .await
+        .expect("request should be sent");
+    let reply = inner
+        .receive_packet::<authorization::ReplyOwned>(None, 2)
+        .await
+        .expect("reply should be read intact");
+
+    assert_eq!(reply.header().session_id(), 1234);
+    assert_eq!(reply.body().status, authorization::Status::PassAdd);
 }
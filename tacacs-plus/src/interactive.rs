@@ -0,0 +1,150 @@
+//! Interactive ASCII authentication, where the user is prompted for each piece of information requested by the server.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::{pin, Pin};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use tacacs_plus_protocol::authentication::{PromptKind, ReplyFlags, ReplyOwned};
+
+#[cfg(test)]
+mod tests;
+
+/// A (pinned, boxed) future that completes once a duration has elapsed, as returned from the sleep function of a
+/// [`PromptTimeouts`].
+pub type SleepFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A (pinned, boxed) future that resolves to the user's answer to a [`Prompt`].
+pub type PromptResponseFuture = Pin<Box<dyn Future<Output = String> + Send>>;
+
+/// Creates a future that completes after the provided duration, e.g. via the sleep function of an async runtime.
+type SleepFactory = Arc<dyn Fn(Duration) -> SleepFuture + Send + Sync>;
+
+/// A request for input from the server during [interactive authentication](super::Client::authenticate_interactive).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prompt {
+    /// What kind of information is being requested, as classified from the reply status & message.
+    pub kind: PromptKind,
+
+    /// The message sent by the server, to be displayed to the user.
+    pub message: String,
+
+    /// Whether the server asked for the user's input not to be echoed, e.g. for a password.
+    pub no_echo: bool,
+}
+
+impl Prompt {
+    /// Extracts a prompt from an authentication reply, or returns `None` if the reply doesn't request any input.
+    pub(super) fn from_reply(reply: &ReplyOwned) -> Option<Self> {
+        PromptKind::classify(reply.status, &reply.server_message).map(|kind| Self {
+            kind,
+            message: reply.server_message.clone(),
+            no_echo: reply.flags.contains(ReplyFlags::NO_ECHO),
+        })
+    }
+}
+
+/// Which side of an interactive authentication session failed to respond in time.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeoutKind {
+    /// The server didn't reply to a packet from the client.
+    ServerResponse,
+
+    /// The user didn't answer a prompt from the server.
+    UserInput,
+}
+
+impl fmt::Display for TimeoutKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ServerResponse => write!(f, "server response"),
+            Self::UserInput => write!(f, "user input"),
+        }
+    }
+}
+
+/// Time limits for each step of an [interactive authentication](super::Client::authenticate_interactive) session.
+///
+/// Since the client doesn't depend on a particular async runtime, timeouts are measured by futures from the provided
+/// sleep function. Both limits are unset by default, in which case the corresponding step can take arbitrarily long.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tacacs_plus::PromptTimeouts;
+///
+/// let mut timeouts = PromptTimeouts::new(|duration| Box::pin(tokio::time::sleep(duration)));
+/// timeouts
+///     .server_response(Duration::from_secs(10))
+///     .user_input(Duration::from_secs(60));
+/// # let _ = timeouts;
+/// ```
+#[derive(Clone)]
+pub struct PromptTimeouts {
+    sleep: SleepFactory,
+    server_response: Option<Duration>,
+    user_input: Option<Duration>,
+}
+
+impl fmt::Debug for PromptTimeouts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PromptTimeouts")
+            .field("server_response", &self.server_response)
+            .field("user_input", &self.user_input)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PromptTimeouts {
+    /// Creates a set of timeouts measured by futures from `sleep`, e.g. `|duration| Box::pin(tokio::time::sleep(duration))`.
+    pub fn new<F>(sleep: F) -> Self
+    where
+        F: Fn(Duration) -> SleepFuture + Send + Sync + 'static,
+    {
+        Self {
+            sleep: Arc::new(sleep),
+            server_response: None,
+            user_input: None,
+        }
+    }
+
+    /// Sets how long to wait for each reply from the server.
+    pub fn server_response(&mut self, timeout: Duration) -> &mut Self {
+        self.server_response = Some(timeout);
+        self
+    }
+
+    /// Sets how long to wait for the user to answer each prompt.
+    pub fn user_input(&mut self, timeout: Duration) -> &mut Self {
+        self.user_input = Some(timeout);
+        self
+    }
+
+    /// Runs a future to completion, or until the timeout of the provided kind elapses if one is set.
+    ///
+    /// If the timeout elapses first, the future is dropped and its duration is returned as an error.
+    pub(super) async fn time_box<F: Future>(
+        &self,
+        kind: TimeoutKind,
+        future: F,
+    ) -> Result<F::Output, Duration> {
+        let timeout = match kind {
+            TimeoutKind::ServerResponse => self.server_response,
+            TimeoutKind::UserInput => self.user_input,
+        };
+
+        let Some(timeout) = timeout else {
+            return Ok(future.await);
+        };
+
+        match future::select(pin!(future), (self.sleep)(timeout)).await {
+            Either::Left((output, _)) => Ok(output),
+            Either::Right(((), _)) => Err(timeout),
+        }
+    }
+}
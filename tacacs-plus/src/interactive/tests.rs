@@ -0,0 +1,138 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future;
+
+use super::*;
+use crate::tests::{AcceptingServer, EndlessPromptServer, ReceivedPackets, SilentServer};
+use crate::{ClientBuilder, ClientError, ContextBuilder, ResponseStatus};
+
+fn timeouts() -> PromptTimeouts {
+    let mut timeouts = PromptTimeouts::new(|duration| Box::pin(tokio::time::sleep(duration)));
+    timeouts
+        .server_response(Duration::from_millis(50))
+        .user_input(Duration::from_millis(50));
+    timeouts
+}
+
+#[test]
+fn prompt_extracted_from_reply() {
+    let reply = ReplyOwned {
+        status: tacacs_plus_protocol::authentication::Status::GetPassword,
+        flags: ReplyFlags::NO_ECHO,
+        raw_flags: ReplyFlags::NO_ECHO.bits(),
+        server_message: String::from("Password: "),
+        data: Vec::new(),
+    };
+
+    assert_eq!(
+        Prompt::from_reply(&reply),
+        Some(Prompt {
+            kind: PromptKind::Password,
+            message: String::from("Password: "),
+            no_echo: true,
+        })
+    );
+}
+
+#[tokio::test]
+async fn decision_returned_without_prompts() {
+    let client = ClientBuilder::new().build(Box::new(|| {
+        Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+    }));
+
+    let response = client
+        .authenticate_interactive(
+            ContextBuilder::new(String::from("someuser"))
+                .build()
+                .unwrap(),
+            &timeouts(),
+            |prompt| panic!("unexpected prompt: {prompt:?}"),
+        )
+        .await
+        .expect("authentication should succeed");
+    assert_eq!(response.status, ResponseStatus::Success);
+}
+
+#[tokio::test]
+async fn user_input_timeout_aborts_session() {
+    let received = ReceivedPackets::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = EndlessPromptServer::recording(server_received.clone());
+        Box::pin(async move { Ok(server) })
+    }));
+
+    // the first prompt is answered right away, but the user walks away before the second one
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let seen_prompts = prompts.clone();
+    let error = client
+        .authenticate_interactive(
+            ContextBuilder::new(String::from("someuser"))
+                .build()
+                .unwrap(),
+            &timeouts(),
+            move |prompt| {
+                let mut prompts = seen_prompts.lock().unwrap();
+                prompts.push(prompt);
+
+                if prompts.len() == 1 {
+                    Box::pin(future::ready(String::from("first answer")))
+                } else {
+                    Box::pin(future::pending())
+                }
+            },
+        )
+        .await
+        .expect_err("authentication should time out");
+    assert!(
+        matches!(
+            error,
+            ClientError::PromptTimeout {
+                kind: TimeoutKind::UserInput,
+                ..
+            }
+        ),
+        "unexpected error: {error:?}"
+    );
+    assert_eq!(prompts.lock().unwrap().len(), 2);
+
+    let received = received.lock().unwrap();
+    let sequence_numbers: Vec<u8> = received.iter().map(|(number, _)| *number).collect();
+    assert_eq!(sequence_numbers, [1, 3, 5]);
+
+    let (_, answer_body) = &received[1];
+    assert!(answer_body.ends_with(b"first answer"));
+
+    // the unanswered prompt is followed by an abort
+    let (_, abort_body) = &received[2];
+    assert_eq!(abort_body[4], 0x01, "abort flag should be set");
+    assert!(abort_body.ends_with(crate::USER_INPUT_TIMEOUT_ABORT_MESSAGE));
+}
+
+#[tokio::test]
+async fn server_response_timeout_reported() {
+    let client = ClientBuilder::new().build(Box::new(|| Box::pin(async { Ok(SilentServer) })));
+
+    let error = client
+        .authenticate_interactive(
+            ContextBuilder::new(String::from("someuser"))
+                .build()
+                .unwrap(),
+            &timeouts(),
+            |prompt| panic!("unexpected prompt: {prompt:?}"),
+        )
+        .await
+        .expect_err("authentication should time out");
+    assert!(
+        matches!(
+            error,
+            ClientError::PromptTimeout {
+                kind: TimeoutKind::ServerResponse,
+                timeout,
+            } if timeout == Duration::from_millis(50)
+        ),
+        "unexpected error: {error:?}"
+    );
+}
@@ -4,54 +4,265 @@
 
 #![warn(missing_docs)]
 
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
 
 use futures::lock::Mutex;
 use futures::{AsyncRead, AsyncWrite};
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 use tacacs_plus_protocol::Arguments;
 use tacacs_plus_protocol::{authentication, authorization};
 use tacacs_plus_protocol::{AuthenticationContext, AuthenticationService};
 use tacacs_plus_protocol::{HeaderInfo, MajorVersion, MinorVersion, Version};
-use tacacs_plus_protocol::{Packet, PacketFlags};
+use tacacs_plus_protocol::{Packet, PacketFlags, PrivilegeLevel};
+
+#[cfg(feature = "raw-packets")]
+use tacacs_plus_protocol::{Deserialize, PacketBody, Serialize};
+
+/// Emits a [`SessionEvent`] to an event bus, compiling to nothing if the `session-events` feature is disabled.
+macro_rules! emit_event {
+    ($bus:expr, $event:expr) => {
+        #[cfg(feature = "session-events")]
+        $bus.emit($event);
+    };
+}
 
 mod inner;
+use inner::{ClientInner, SessionLink};
 pub use inner::{ConnectionFactory, ConnectionFuture};
 
 mod response;
 pub use response::{
-    AccountingResponse, AuthenticationResponse, AuthorizationResponse, ResponseStatus,
+    AccountingResponse, AuthenticationResponse, AuthorizationResponse, ResponseStatus, SessionInfo,
 };
 
 mod context;
-pub use context::{ContextBuilder, SessionContext};
+pub use context::{ContextBuilder, ContextField, ContextFieldError, FieldIssue, SessionContext};
+
+mod builder;
+pub use builder::ClientBuilder;
+
+mod dynamic;
+pub use dynamic::{Connection, DynClient, DynConnection};
+
+mod peer;
+pub use peer::{PeerConnectionFactory, PeerConnectionFuture, PeerSecrets};
+
+mod compat;
+pub use compat::{CompatibilityProfile, SingleConnectionNegotiation};
+
+mod password;
+pub use password::{PasswordPolicy, PasswordPolicyViolation};
+
+mod schema;
+pub use schema::{ArgumentSchema, SchemaIssue, ValueKind};
+
+mod admin;
+pub use admin::{AdminMessage, OperationKind};
+
+#[cfg(feature = "session-events")]
+mod events;
+#[cfg(feature = "session-events")]
+pub use events::{ConnectionStatus, SessionEvent, SessionEvents};
 
 mod error;
-pub use error::ClientError;
+pub use error::{CleanupError, ClientError, OperationPhase};
 
 mod task;
-pub use task::AccountingTask;
+pub use task::{AbandonedTask, AccountingTask};
+
+mod degraded;
+pub use degraded::LocalAccountingRecord;
+
+mod idempotency;
+
+mod encryption;
+pub use encryption::{ArgumentCipher, CipherDirection, CipherError};
+
+mod session;
+pub use session::Session;
+
+mod interactive;
+pub use interactive::{Prompt, PromptResponseFuture, PromptTimeouts, SleepFuture, TimeoutKind};
+
+mod probe;
+pub use probe::{ProbeMethod, ProbeReport};
+
+mod sink;
+pub use sink::{AccountingDriver, AccountingRecord, AccountingSink, OverflowPolicy, SinkError};
+
+mod clock;
+pub use clock::{Clock, SystemClock};
+
+mod stats;
+pub use stats::{ClientStats, QueueDepth, SessionCounts};
+
+mod diff;
+pub use diff::{ArgumentDiff, ReplacedArgument};
+
+mod middleware;
+pub use middleware::{Middleware, Next, Outcome, TacacsOperation};
+
+mod queue;
+use queue::SessionGuard;
+pub use queue::SessionPriority;
+
+mod username;
+pub use username::{UserNameNormalizer, UserNameRules};
+
+pub mod presets;
+
+mod start_data;
+pub use start_data::{ChapStartData, MsChapStartData, PapStartData, StartData, StartDataBuilder};
+
+#[cfg(feature = "locale-attributes")]
+mod locale;
+#[cfg(feature = "locale-attributes")]
+pub use locale::LocaleAttributes;
+
+#[cfg(feature = "process-metadata")]
+mod process;
+#[cfg(feature = "process-metadata")]
+pub use process::ProcessMetadata;
+
+#[cfg(feature = "test-utils")]
+mod faults;
+#[cfg(feature = "test-utils")]
+pub use faults::{DelayFuture, FaultConfig, FaultKind, FaultLog, FaultyTransport};
+
+#[cfg(any(test, feature = "test-utils"))]
+mod scripted;
+#[cfg(feature = "test-utils")]
+pub use scripted::{ReplyScript, ScriptedServer};
+
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "config")]
+pub use config::{ClientConfig, ConfigError, ServerConfig, DEFAULT_PORT};
+
+#[cfg(feature = "trace-export")]
+mod trace;
+#[cfg(feature = "trace-export")]
+pub use trace::{ProtocolTrace, TracingTransport, REDACTED, TRACE_SCHEMA_VERSION};
+
+#[cfg(feature = "tower")]
+mod service;
+#[cfg(feature = "tower")]
+pub use service::AuthorizationRequest;
 
 // reexported for ease of access
 pub use tacacs_plus_protocol as protocol;
 pub use tacacs_plus_protocol::{Argument, AuthenticationMethod, FieldText};
 
+#[cfg(test)]
+mod tests;
+
+/// The name of the argument a server grants a privilege level with.
+const PRIVILEGE_LEVEL_ARGUMENT: &str = "priv-lvl";
+
+/// The administrative message sent when aborting an authentication session whose sequence numbers ran out.
+const SEQUENCE_OVERFLOW_ABORT_MESSAGE: &[u8] = b"sequence number overflow";
+
+/// The administrative message sent when aborting an interactive authentication session whose user didn't answer a prompt in time.
+const USER_INPUT_TIMEOUT_ABORT_MESSAGE: &[u8] = b"timed out waiting for user input";
+
+/// A random number generator shared between clones of a client, as configured via [`ClientBuilder::rng()`].
+type SharedRng = Arc<std::sync::Mutex<dyn RngCore + Send>>;
+
 /// A TACACS+ client.
-#[derive(Clone)]
+///
+/// Clones of a client share the same connection & counters, so a client can be cloned into multiple tasks that perform
+/// sessions concurrently; sessions are then performed one at a time over the shared connection, unless the client is
+/// [full duplex](ClientBuilder::full_duplex).
 pub struct Client<S> {
     /// The underlying TCP connection of the client.
     inner: Arc<Mutex<inner::ClientInner<S>>>,
 
+    /// The sessions waiting for their turn to use the connection, shared between clones of the client.
+    queue: Arc<queue::SessionQueue>,
+
+    /// Metadata about the underlying connection, readable without locking the inner client.
+    snapshot: Arc<inner::StateSnapshot>,
+
+    /// Counters of sessions & traffic, shared with the inner client.
+    stats: Arc<stats::StatsRecorder>,
+
     /// The shared secret used for packet obfuscation, if provided.
     secret: Option<Vec<u8>>,
+
+    /// The protocol flavor spoken by the server.
+    compatibility_profile: CompatibilityProfile,
+
+    /// The policy new passwords are checked against before changing them.
+    password_policy: PasswordPolicy,
+
+    /// The schema outgoing authorization arguments are validated against, if any.
+    authorization_schema: Option<Arc<ArgumentSchema>>,
+
+    /// The schema outgoing accounting arguments are validated against, if any.
+    accounting_schema: Option<Arc<ArgumentSchema>>,
+
+    /// A callback for the issues found by non-strict schemas, if registered.
+    schema_issue_handler: Option<schema::SchemaIssueHandler>,
+
+    /// The tracker of unanswered authorization requests whose retries reuse their session id, if enabled.
+    authorization_deduplicator: Option<Arc<idempotency::AuthorizationDeduplicator>>,
+
+    /// The cipher applied to the values of protected authorization arguments, if configured.
+    argument_encryption: Option<encryption::ArgumentEncryption>,
+
+    /// The number of times an authentication session is restarted at the server's request.
+    max_authentication_restarts: u8,
+
+    /// A callback for administrative messages from the server, if registered.
+    admin_message_sink: Option<admin::AdminMessageSink>,
+
+    /// A rewrite applied to user messages from the server, if registered.
+    user_message_processor: Option<admin::UserMessageProcessor>,
+
+    /// A callback for accounting tasks that are dropped without being stopped, if registered.
+    abandon_handler: Option<task::AbandonHandler>,
+
+    /// The local fallback for accounting records if the server keeps rejecting them, if configured.
+    accounting_fallback: Option<Arc<degraded::AccountingFallback>>,
+
+    /// The source of timestamps & durations for accounting records.
+    clock: Arc<dyn Clock>,
+
+    /// The builder for CHAP start packets.
+    chap_start_data: ChapStartData,
+
+    /// Builders for the start packets of custom authentication types, keyed by name.
+    start_data_builders: BTreeMap<&'static str, Arc<dyn StartDataBuilder>>,
+
+    /// The source of session IDs & authentication challenges, if one other than the thread-local RNG is configured.
+    rng: Option<SharedRng>,
+
+    /// The transformation applied to usernames before they're sent, if configured.
+    user_name_normalizer: Option<Arc<dyn UserNameNormalizer>>,
+
+    /// The middlewares operations pass through, outermost first.
+    middlewares: Arc<[Arc<dyn Middleware>]>,
+
+    /// Timezone & locale information attached to accounting records, if configured.
+    #[cfg(feature = "locale-attributes")]
+    locale_attributes: Option<LocaleAttributes>,
+
+    /// Subscribers to events emitted by this client, shared with the inner client.
+    #[cfg(feature = "session-events")]
+    events: Arc<events::EventBus>,
 }
 
 /// The type of authentication used for a given session.
 ///
 /// More of these might be added in the future, but the variants here are
-/// the only currently supported authentication types with a [`Client`].
+/// the only currently supported authentication types with a [`Client`]. Other methods can be supported
+/// via [`Custom`](Self::Custom) authentication types.
 #[non_exhaustive]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AuthenticationType {
@@ -59,6 +270,38 @@ pub enum AuthenticationType {
     Pap,
     /// Authentication via the Challenge-Authentication Protocol (CHAP).
     Chap,
+    /// Authentication via Microsoft's CHAP extensions, version 1 (MS-CHAP).
+    MsChap,
+    /// Authentication via a custom method, whose start packet is produced by the [`StartDataBuilder`] registered
+    /// under this name with [`ClientBuilder::start_data_builder()`].
+    Custom(&'static str),
+}
+
+impl AuthenticationType {
+    /// Returns the maximum length of a password (in bytes) that can be used with this authentication type, or `None` if
+    /// the limit isn't known up front.
+    ///
+    /// This can be used to reject passwords when they're entered rather than once a session is started, which fails
+    /// with [`ClientError::PasswordTooLong`] for passwords that are too long.
+    ///
+    /// Only PAP sends the password itself, which has to fit in the 255-byte data field of a start packet. CHAP &
+    /// MS-CHAP send fixed-length responses computed from the password instead, so any password fits; for custom types,
+    /// the limit depends on their [`StartDataBuilder`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tacacs_plus::AuthenticationType;
+    ///
+    /// assert_eq!(AuthenticationType::Pap.max_password_len(), Some(255));
+    /// assert_eq!(AuthenticationType::Chap.max_password_len(), None);
+    /// ```
+    pub fn max_password_len(&self) -> Option<usize> {
+        match self {
+            Self::Pap => Some(start_data::MAX_START_DATA_LENGTH),
+            Self::Chap | Self::MsChap | Self::Custom(_) => None,
+        }
+    }
 }
 
 impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
@@ -83,236 +326,1040 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// packet transfer MUST NOT be used in production, so prefer to provide a secret (of a secure length)
     /// where possible.
     ///
+    /// To configure other client behavior, use a [`ClientBuilder`] instead.
+    ///
     /// [RFC8907 section 4.5]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.5-16
     pub fn new<K: AsRef<[u8]>>(
         connection_factory: ConnectionFactory<S>,
         secret: Option<K>,
     ) -> Self {
-        let inner = inner::ClientInner::new(connection_factory);
+        let mut builder = ClientBuilder::new();
+        if let Some(secret) = secret {
+            builder.secret(secret);
+        }
 
-        Self {
-            inner: Arc::new(Mutex::new(inner)),
-            secret: secret.map(|s| s.as_ref().to_owned()),
+        builder.build(connection_factory)
+    }
+
+    /// Subscribes to a stream of live events from this client, e.g. for rendering session progress in a user interface.
+    ///
+    /// Each call returns an independent stream that receives all events emitted after it was created, including those from
+    /// clones of this client. Events are buffered until the stream is polled, so streams that are no longer needed should be dropped.
+    #[cfg(feature = "session-events")]
+    pub fn session_events(&self) -> SessionEvents {
+        self.events.subscribe()
+    }
+
+    /// Returns a snapshot of the counters of this client & its clones, e.g. the number of sessions with each outcome.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{Client, OperationKind};
+    ///
+    /// let client = Client::new(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })), None::<&[u8]>);
+    ///
+    /// let stats = client.stats();
+    /// assert_eq!(stats.sessions(OperationKind::Authorization).succeeded, 0);
+    /// assert_eq!(stats.average_rtt, None);
+    /// ```
+    pub fn stats(&self) -> ClientStats {
+        ClientStats {
+            queued_sessions: self.queue.depth(),
+            ..self.stats.snapshot()
+        }
+    }
+
+    /// Whether accounting records are currently only logged locally, since the server kept rejecting them.
+    ///
+    /// This is always false unless a fallback was configured via
+    /// [`ClientBuilder::accounting_fallback()`](ClientBuilder::accounting_fallback).
+    pub fn is_accounting_degraded(&self) -> bool {
+        self.accounting_fallback
+            .as_ref()
+            .is_some_and(|fallback| fallback.is_degraded())
+    }
+
+    /// Sends accounting records to the server again after they were only logged locally, e.g. once the server's
+    /// configuration was fixed.
+    ///
+    /// Accounting is degraded again if the server keeps rejecting records.
+    pub fn resume_accounting(&self) {
+        if let Some(fallback) = &self.accounting_fallback {
+            if fallback.resume() {
+                emit_event!(self.events, SessionEvent::AccountingResumed);
+            }
+        }
+    }
+
+    /// Waits for a session's turn to use the connection, and then locks it for the session.
+    async fn lock_inner(&self, priority: SessionPriority) -> SessionGuard<'_, ClientInner<S>> {
+        self.queue.lock(&self.inner, priority).await
+    }
+
+    /// Waits for a session's turn to use the connection, and then either shares the connection with the session if
+    /// it's [full duplex](ClientBuilder::full_duplex), or locks it for the session otherwise.
+    async fn begin_session(&self, priority: SessionPriority) -> SessionLink<'_, S> {
+        let mut inner = self.lock_inner(priority).await;
+
+        match inner.share_connection(&self.inner).await {
+            Some(shared) => SessionLink::Shared(shared),
+            None => SessionLink::Exclusive(inner),
         }
     }
 
     fn make_header(&self, sequence_number: u8, minor_version: MinorVersion) -> HeaderInfo {
-        // generate random id for this session
-        // rand::ThreadRng implements CryptoRng, so it should be suitable for use as a CSPRNG
-        let session_id: u32 = rand::thread_rng().gen();
+        self.make_session_header(sequence_number, minor_version, self.new_session_id())
+    }
 
-        // set single connection/unencrypted flags accordingly
-        let flags = if self.secret.is_some() {
-            PacketFlags::SINGLE_CONNECTION
-        } else {
-            PacketFlags::SINGLE_CONNECTION | PacketFlags::UNENCRYPTED
-        };
+    /// Generates a random id for a new session.
+    fn new_session_id(&self) -> u32 {
+        // the RNG is required to implement CryptoRng, so it should be suitable for use as a CSPRNG
+        self.with_rng(|rng| rng.gen())
+    }
 
+    /// Makes the header for the first client packet of a session with a known id.
+    fn make_session_header(
+        &self,
+        sequence_number: u8,
+        minor_version: MinorVersion,
+        session_id: u32,
+    ) -> HeaderInfo {
         HeaderInfo::new(
             Version::new(MajorVersion::RFC8907, minor_version),
             sequence_number,
-            flags,
+            self.header_flags(),
             session_id,
         )
     }
 
-    fn pap_login_start_packet<'packet>(
+    /// Runs a function with the configured RNG, falling back to the thread-local one if none was configured.
+    fn with_rng<T>(&self, f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+        match &self.rng {
+            // an RNG can't be left in an invalid state by a panic, so poisoning can be ignored
+            Some(rng) => f(&mut *rng.lock().unwrap_or_else(PoisonError::into_inner)),
+            None => f(&mut rand::thread_rng()),
+        }
+    }
+
+    /// Makes the header for the next client packet in an ongoing session, based on the header of the last server reply.
+    fn make_continue_header(&self, reply_header: &HeaderInfo) -> Result<HeaderInfo, ClientError> {
+        let sequence_number = reply_header
+            .sequence_number()
+            .checked_add(1)
+            .ok_or(ClientError::SequenceNumberOverflow)?;
+
+        Ok(HeaderInfo::new(
+            reply_header.version(),
+            sequence_number,
+            self.header_flags(),
+            reply_header.session_id(),
+        ))
+    }
+
+    /// Sends the next client packet in an ongoing authentication session in reply to a server packet, returning the
+    /// sequence number expected for the server's next reply.
+    ///
+    /// Sequence numbers can't wrap around within a session ([RFC8907 section 4.1]), so if the server wouldn't be able
+    /// to reply to the continue packet, the session is aborted instead and [`ClientError::SequenceNumberOverflow`] is returned.
+    ///
+    /// [RFC8907 section 4.1]: https://www.rfc-editor.org/rfc/rfc8907.html#section-4.1-13.2.1
+    async fn continue_authentication(
+        &self,
+        inner: &mut SessionLink<'_, S>,
+        reply_header: &HeaderInfo,
+        user_message: &[u8],
+    ) -> Result<u8, ClientError> {
+        use protocol::authentication::{Continue, ContinueFlags};
+
+        let secret_key = self.secret.as_deref();
+        let continue_header = self.make_continue_header(reply_header)?;
+
+        let Some(expected_sequence_number) = continue_header.sequence_number().checked_add(1)
+        else {
+            self.abort_authentication(inner, reply_header, SEQUENCE_OVERFLOW_ABORT_MESSAGE)
+                .await;
+            return Err(ClientError::SequenceNumberOverflow);
+        };
+
+        let continue_packet = Packet::new(
+            continue_header,
+            Continue::new(Some(user_message), None, ContinueFlags::empty()).ok_or(
+                ClientError::PasswordTooLong {
+                    length: user_message.len(),
+                    limit: usize::from(u16::MAX),
+                },
+            )?,
+        );
+        inner
+            .send_packet_unchecked(continue_packet, secret_key)
+            .await?;
+
+        Ok(expected_sequence_number)
+    }
+
+    /// Applies the configured [`UserNameNormalizer`] to the username of a context, only copying the context if the username changes.
+    fn normalize_context<'c>(&self, context: Cow<'c, SessionContext>) -> Cow<'c, SessionContext> {
+        let Some(normalizer) = &self.user_name_normalizer else {
+            return context;
+        };
+
+        let user = match normalizer.normalize(&context.user) {
+            normalized if normalized == context.user.as_str() => return context,
+            normalized => normalized.into_owned(),
+        };
+
+        let mut context = context.into_owned();
+        context.user = user;
+        Cow::Owned(context)
+    }
+
+    /// Validates caller-provided accounting arguments against the configured schema, if any.
+    fn check_accounting_arguments(&self, arguments: &[Argument<'_>]) -> Result<(), ClientError> {
+        match &self.accounting_schema {
+            Some(schema) => schema.enforce(
+                OperationKind::Accounting,
+                arguments,
+                self.schema_issue_handler.as_ref(),
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Aborts an ongoing authentication session in reply to a server packet, with an administrative message saying why.
+    async fn abort_authentication(
+        &self,
+        inner: &mut SessionLink<'_, S>,
+        reply_header: &HeaderInfo,
+        message: &[u8],
+    ) {
+        use protocol::authentication::{Continue, ContinueFlags};
+
+        let Ok(abort_header) = self.make_continue_header(reply_header) else {
+            // the server couldn't have sent a reply with the maximum sequence number, so there's nothing to abort
+            return;
+        };
+
+        // SAFETY: the abort messages are well under the maximum length of the data field
+        let abort = Continue::new(None, Some(message), ContinueFlags::ABORT).unwrap();
+
+        // the session is terminated regardless of whether the server receives the abort, so errors are ignored here
+        let _ = inner
+            .send_packet_unchecked(Packet::new(abort_header, abort), self.secret.as_deref())
+            .await;
+    }
+
+    /// Converts the final reply of an authentication session into a response, or an error if its status isn't a final one.
+    fn authentication_response(
+        &self,
+        reply: &Packet<authentication::ReplyOwned>,
+        record: stats::SessionRecord<'_>,
+    ) -> Result<AuthenticationResponse, ClientError> {
+        let reply_status = ResponseStatus::try_from(reply.body().status);
+        let user_message = self.process_user_message(
+            OperationKind::Authentication,
+            reply.body().server_message.clone(),
+        );
+        let data = reply.body().data.clone();
+
+        // authentication data isn't necessarily text, unlike that of the other operations
+        self.report_admin_message(
+            OperationKind::Authentication,
+            &String::from_utf8_lossy(&data),
+            reply.header(),
+        );
+
+        match reply_status {
+            Ok(status) => {
+                emit_event!(
+                    self.events,
+                    SessionEvent::Decision {
+                        operation: OperationKind::Authentication,
+                        session: SessionInfo::from_header(reply.header()),
+                        status,
+                    }
+                );
+                record.finish(status);
+
+                Ok(AuthenticationResponse {
+                    status,
+                    raw_status: reply.body().status,
+                    user_message,
+                    data,
+                    restart_requested: reply.body().status == authentication::Status::Restart,
+                    session: SessionInfo::from_header(reply.header()),
+                })
+            }
+            Err(response::BadAuthenticationStatus(status)) => {
+                Err(ClientError::AuthenticationError {
+                    status,
+                    data,
+                    user_message,
+                })
+            }
+        }
+    }
+
+    /// Emits a [`SessionEvent::PromptRequested`] event if an authentication reply is prompting for input.
+    #[cfg(feature = "session-events")]
+    fn report_prompt(&self, reply: &Packet<authentication::ReplyOwned>) {
+        let body = reply.body();
+
+        if let Some(kind) = authentication::PromptKind::classify(body.status, &body.server_message)
+        {
+            self.events.emit(SessionEvent::PromptRequested {
+                session_id: reply.header().session_id(),
+                kind,
+                message: body.server_message.clone(),
+            });
+        }
+    }
+
+    /// Passes an administrative message from a server to the registered sink, if any.
+    fn report_admin_message(&self, operation: OperationKind, message: &str, header: &HeaderInfo) {
+        if let Some(sink) = &self.admin_message_sink {
+            if !message.is_empty() {
+                sink(&AdminMessage {
+                    operation,
+                    message: message.to_owned(),
+                    session: SessionInfo::from_header(header),
+                });
+            }
+        }
+    }
+
+    /// Passes a user message from a server through the registered processor, if any.
+    fn process_user_message(&self, operation: OperationKind, message: String) -> String {
+        match &self.user_message_processor {
+            Some(processor) => processor(operation, message),
+            None => message,
+        }
+    }
+
+    fn header_flags(&self) -> PacketFlags {
+        // set single connection/unencrypted flags accordingly
+        if self.secret.is_some() {
+            PacketFlags::SINGLE_CONNECTION
+        } else {
+            PacketFlags::SINGLE_CONNECTION | PacketFlags::UNENCRYPTED
+        }
+    }
+
+    /// Produces the start packet contents for an authentication method, using a custom builder if one is registered.
+    fn login_start_data(
+        &self,
+        context: &SessionContext,
+        password: &str,
+        authentication_type: AuthenticationType,
+    ) -> Result<StartData, ClientError> {
+        let builder: &dyn StartDataBuilder = match authentication_type {
+            AuthenticationType::Pap => &PapStartData,
+            AuthenticationType::Chap => &self.chap_start_data,
+            AuthenticationType::MsChap => &MsChapStartData,
+            AuthenticationType::Custom(name) => self
+                .start_data_builders
+                .get(name)
+                .ok_or(ClientError::UnregisteredAuthenticationType(name))?
+                .as_ref(),
+        };
+
+        self.with_rng(|rng| builder.build_with_rng(context, password, rng))
+    }
+
+    fn login_start_packet<'packet>(
         &self,
         context: &'packet SessionContext,
-        password: &'packet str,
+        start_data: &'packet StartData,
     ) -> Result<Packet<authentication::Start<'packet>>, ClientError> {
-        use protocol::authentication::BadStart;
-
         Ok(Packet::new(
             // sequence number = 1 (first packet in session)
-            // also set minor version accordingly
-            self.make_header(1, MinorVersion::V1),
+            self.make_header(1, start_data.minor_version),
             authentication::Start::new(
                 authentication::Action::Login,
                 AuthenticationContext {
                     privilege_level: context.privilege_level,
-                    authentication_type: protocol::AuthenticationType::Pap,
+                    authentication_type: start_data.authentication_type,
                     service: AuthenticationService::Login,
                 },
                 context.as_user_information()?,
-                Some(password.as_bytes().try_into()?),
+                Some(start_data.data.as_slice().try_into().map_err(|_| {
+                    ClientError::PasswordTooLong {
+                        length: start_data.data.len(),
+                        limit: start_data::MAX_START_DATA_LENGTH,
+                    }
+                })?),
             )
-            .map_err(|err| match err {
-                // SAFETY: the version, authentication type & saction fields are hard-coded to valid values so neither of these errors can occur
-                BadStart::AuthTypeNotSet | BadStart::IncompatibleActionAndType => unreachable!(),
-                // we have to have a catch-all case since BadStart is marked #[non_exhaustive]
-                _ => ClientError::InvalidPacketData,
-            })?,
+            // the built-in builders always use valid authentication types, but custom ones might not
+            .map_err(|_| ClientError::InvalidPacketData)?,
         ))
     }
 
-    fn chap_login_start_packet<'packet>(
+    /// Authenticates against a TACACS+ server with a username and password using the specified protocol.
+    ///
+    /// If the server requests a restart, the authentication is reattempted in a new session up to the limit set by
+    /// [`ClientBuilder::max_authentication_restarts()`] (zero by default). Once that limit is exhausted, the restart is
+    /// reported as a failure with [`restart_requested`](AuthenticationResponse::restart_requested) set.
+    ///
+    /// For PAP, the password is sent in the start packet as specified by RFC8907. If the server prompts for the password
+    /// anyways (as some lenient servers do), it is sent again in response to that prompt.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped after the start packet was sent, the server may or may not have counted the
+    /// attempt (e.g., towards a lockout policy), but its result is lost either way. Retrying starts a new session.
+    pub async fn authenticate(
         &self,
-        context: &'packet SessionContext,
-        password: &'packet str,
-    ) -> Result<Packet<authentication::Start<'packet>>, ClientError> {
-        use md5::{Digest, Md5};
-        use protocol::authentication::BadStart;
-
-        // generate random PPP ID/challenge
-        let ppp_id: u8 = rand::thread_rng().gen();
-        let challenge = uuid::Uuid::new_v4();
-
-        // "The Response Value is the one-way hash calculated over a stream of octets consisting of the Identifier,
-        // followed by (concatenated with) the "secret", followed by (concatenated with) the Challenge Value."
-        // RFC1334 section 3.2.1 ("Value" subheading): https://www.rfc-editor.org/rfc/rfc1334.html#section-3.2.1
-        //
-        // "The MD5 algorithm option is always used." (RFC8907 section 5.4.2.3)
-        // https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3-4
-        let mut hasher = Md5::new();
-        hasher.update([ppp_id]);
-        hasher.update(password.as_bytes()); // the secret is the password in this case
-        hasher.update(challenge);
-        let response = hasher.finalize();
-
-        // "the data field is a concatenation of the PPP id, the challenge, and the response"
-        // RFC8907 section 5.4.2.3: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3-2
-        let mut data = vec![ppp_id];
-        data.extend(challenge.as_bytes());
-        data.extend(response);
+        context: SessionContext,
+        password: &str,
+        authentication_type: AuthenticationType,
+    ) -> Result<AuthenticationResponse, ClientError> {
+        self.authenticate_with_type(&context, password, authentication_type)
+            .await
+            .map(|(response, _)| response)
+    }
 
-        Ok(Packet::new(
-            self.make_header(1, MinorVersion::V1),
+    /// Authenticates against a TACACS+ server, also returning the authentication type used on the wire.
+    async fn authenticate_with_type(
+        &self,
+        context: &SessionContext,
+        password: &str,
+        authentication_type: AuthenticationType,
+    ) -> Result<(AuthenticationResponse, protocol::AuthenticationType), ClientError> {
+        let operation = middleware::Authentication {
+            context,
+            authentication_type,
+        };
+
+        middleware::run(
+            &self.middlewares,
+            &operation,
+            |(response, _): &(AuthenticationResponse, _)| response.status,
+            || self.perform_authentication(context, password, authentication_type),
+        )
+        .await
+    }
+
+    /// Performs an authentication session, without passing it through the configured middlewares.
+    async fn perform_authentication(
+        &self,
+        context: &SessionContext,
+        password: &str,
+        authentication_type: AuthenticationType,
+    ) -> Result<(AuthenticationResponse, protocol::AuthenticationType), ClientError> {
+        use protocol::authentication::ReplyOwned;
+
+        let context = &*self.normalize_context(Cow::Borrowed(context));
+        let record = self.stats.start_session(OperationKind::Authentication);
+        let mut restarts = 0;
+
+        let (reply, wire_authentication_type, cleanup_result) = loop {
+            // the start packet is regenerated for each attempt, since a restart requires a new session
+            // (and CHAP needs a fresh challenge anyways)
+            let start_data = self.login_start_data(context, password, authentication_type)?;
+            let start_packet = self.login_start_packet(context, &start_data)?;
+
+            // block expression is used here to ensure that the connection mutex is only locked during communication
+            let reply = {
+                let secret_key = self.secret.as_deref();
+
+                let mut inner = self
+                    .begin_session(context.priority_for(OperationKind::Authentication))
+                    .await;
+
+                // the exchange is wrapped so that the connection can be cleaned up properly if an error occurs partway through
+                let exchange_result = async {
+                    inner.send_packet(start_packet, secret_key).await?;
+
+                    // response: whether authentication succeeded
+                    let reply = inner.receive_packet::<ReplyOwned>(secret_key, 2).await?;
+                    inner.set_internal_single_connect_status(reply.header());
+
+                    // some lenient servers ignore the password in a PAP start packet and prompt for it instead,
+                    // so we answer the prompt with the password as if this were ASCII authentication
+                    if authentication_type == AuthenticationType::Pap
+                        && reply.body().status == authentication::Status::GetPassword
+                    {
+                        #[cfg(feature = "session-events")]
+                        self.report_prompt(&reply);
+
+                        let expected_sequence_number = self
+                            .continue_authentication(
+                                &mut inner,
+                                reply.header(),
+                                password.as_bytes(),
+                            )
+                            .await?;
+                        inner
+                            .receive_packet::<ReplyOwned>(secret_key, expected_sequence_number)
+                            .await
+                    } else {
+                        Ok(reply)
+                    }
+                }
+                .await;
+
+                match exchange_result {
+                    Ok(reply) => {
+                        let cleanup_result = inner
+                            .post_session_cleanup(
+                                reply.body().status == authentication::Status::Error
+                                    && self.compatibility_profile.error_closes_connection(),
+                            )
+                            .await;
+                        (reply, cleanup_result)
+                    }
+                    Err(err) => {
+                        // the session was left in an unknown state, so the connection can't be reused
+                        inner.abandon_session();
+                        return Err(err);
+                    }
+                }
+            };
+
+            let (reply, cleanup_result) = reply;
+
+            // a failed cleanup is reported rather than restarting
+            if reply.body().status == authentication::Status::Restart
+                && restarts < self.max_authentication_restarts
+                && cleanup_result.is_ok()
+            {
+                restarts += 1;
+            } else {
+                break (reply, start_data.authentication_type, cleanup_result);
+            }
+        };
+
+        let result = self
+            .authentication_response(&reply, record)
+            .map(|response| (response, wire_authentication_type));
+
+        error::with_cleanup_result(result, cleanup_result)
+    }
+
+    /// Changes a user's password via an ASCII authentication session, as described in [RFC8907 section 5.4.2.7].
+    ///
+    /// The server's prompts are answered in order: GETUSER with the username from the context, the first GETPASS/GETDATA
+    /// prompt with the old password, and any subsequent ones (e.g., for confirmation) with the new password.
+    ///
+    /// The new password is checked against the client's [`PasswordPolicy`] before anything is sent to the server, returning a
+    /// [`ClientError::PasswordPolicyViolation`] if it is rejected.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped after the new password was sent, it may or may not have been changed on the
+    /// server. Authenticating with either password afterwards tells which one is in effect.
+    ///
+    /// [RFC8907 section 5.4.2.7]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.7
+    pub async fn change_password(
+        &self,
+        context: SessionContext,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<AuthenticationResponse, ClientError> {
+        use protocol::authentication::{BadStart, ReplyOwned, Status};
+
+        self.password_policy.check(old_password, new_password)?;
+
+        let context = self.normalize_context(Cow::Owned(context));
+
+        let start_packet = Packet::new(
+            // ASCII authentication uses the default minor version
+            self.make_header(1, MinorVersion::Default),
             authentication::Start::new(
-                authentication::Action::Login,
+                authentication::Action::ChangePassword,
                 AuthenticationContext {
                     privilege_level: context.privilege_level,
-                    authentication_type: protocol::AuthenticationType::Chap,
+                    authentication_type: protocol::AuthenticationType::Ascii,
                     service: AuthenticationService::Login,
                 },
                 context.as_user_information()?,
-                Some(data.try_into()?),
+                None,
             )
             .map_err(|err| match err {
-                // SAFETY: the version, authentication type & action fields are hard-coded to valid values so the start constructor will not fail
+                // SAFETY: the authentication type & action fields are hard-coded to valid values so neither of these errors can occur
                 BadStart::AuthTypeNotSet | BadStart::IncompatibleActionAndType => unreachable!(),
                 _ => ClientError::InvalidPacketData,
             })?,
-        ))
+        );
+
+        let record = self.stats.start_session(OperationKind::Authentication);
+        let secret_key = self.secret.as_deref();
+        let mut inner = self
+            .begin_session(context.priority_for(OperationKind::Authentication))
+            .await;
+
+        // the exchange is wrapped so that the connection can be cleaned up properly if an error occurs partway through
+        let exchange_result = async {
+            inner.send_packet(start_packet, secret_key).await?;
+
+            let mut expected_sequence_number = 2;
+            let mut old_password_sent = false;
+
+            loop {
+                let reply: Packet<ReplyOwned> = inner
+                    .receive_packet(secret_key, expected_sequence_number)
+                    .await?;
+                inner.set_internal_single_connect_status(reply.header());
+
+                #[cfg(feature = "session-events")]
+                self.report_prompt(&reply);
+
+                let user_message = match reply.body().status {
+                    Status::GetUser => context.user.as_bytes(),
+                    Status::GetPassword | Status::GetData if !old_password_sent => {
+                        old_password_sent = true;
+                        old_password.as_bytes()
+                    }
+                    Status::GetPassword | Status::GetData => new_password.as_bytes(),
+                    _ => break Ok(reply),
+                };
+
+                expected_sequence_number = self
+                    .continue_authentication(&mut inner, reply.header(), user_message)
+                    .await?;
+            }
+        }
+        .await;
+
+        let (reply, cleanup_result) = match exchange_result {
+            Ok(reply) => {
+                let cleanup_result = inner
+                    .post_session_cleanup(
+                        reply.body().status == Status::Error
+                            && self.compatibility_profile.error_closes_connection(),
+                    )
+                    .await;
+                (reply, cleanup_result)
+            }
+            Err(err) => {
+                // the session was left in an unknown state, so the connection can't be reused
+                inner.abandon_session();
+                return Err(err);
+            }
+        };
+        drop(inner);
+
+        let result = self.authentication_response(&reply, record);
+        error::with_cleanup_result(result, cleanup_result)
     }
 
-    /// Authenticates against a TACACS+ server with a username and password using the specified protocol.
-    pub async fn authenticate(
+    /// Authenticates a user interactively via an ASCII authentication session, as described in [RFC8907 section 5.4.2.1].
+    ///
+    /// Each prompt from the server (e.g., for a username or password) is passed to `responder`, and the user's answer is
+    /// sent back once the returned future resolves. If the context has a nonempty username it's included in the start
+    /// packet, in which case servers generally only prompt for the password.
+    ///
+    /// Every reply from the server and every answer from the user is time-boxed according to `timeouts`, returning a
+    /// [`ClientError::PromptTimeout`] with the [`TimeoutKind`] of whichever side stalled. If the user doesn't answer in
+    /// time, the session is aborted with a continue packet as described in [RFC8907 section 5.3]; if the server doesn't
+    /// reply in time, there is no reply to continue from, so the connection is closed instead.
+    ///
+    /// The client's connection is held for the whole session, including while waiting for the user, so other operations on
+    /// this client wait until it finishes.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped partway through, the session is left unfinished on the server until it times out
+    /// there, and the connection isn't reused for the next session.
+    ///
+    /// [RFC8907 section 5.4.2.1]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.1
+    /// [RFC8907 section 5.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.3
+    pub async fn authenticate_interactive<F>(
         &self,
         context: SessionContext,
-        password: &str,
-        authentication_type: AuthenticationType,
-    ) -> Result<AuthenticationResponse, ClientError> {
-        use protocol::authentication::ReplyOwned;
+        timeouts: &PromptTimeouts,
+        mut responder: F,
+    ) -> Result<AuthenticationResponse, ClientError>
+    where
+        F: FnMut(Prompt) -> PromptResponseFuture,
+    {
+        use protocol::authentication::{BadStart, ReplyOwned};
 
-        let start_packet = match authentication_type {
-            AuthenticationType::Pap => self.pap_login_start_packet(&context, password),
-            AuthenticationType::Chap => self.chap_login_start_packet(&context, password),
-        }?;
+        let context = self.normalize_context(Cow::Owned(context));
 
-        // block expression is used here to ensure that the connection mutex is only locked during communication
-        let reply = {
-            let secret_key = self.secret.as_deref();
+        let start_packet = Packet::new(
+            // ASCII authentication uses the default minor version
+            self.make_header(1, MinorVersion::Default),
+            authentication::Start::new(
+                authentication::Action::Login,
+                AuthenticationContext {
+                    privilege_level: context.privilege_level,
+                    authentication_type: protocol::AuthenticationType::Ascii,
+                    service: AuthenticationService::Login,
+                },
+                context.as_user_information()?,
+                None,
+            )
+            .map_err(|err| match err {
+                // SAFETY: the authentication type & action fields are hard-coded to valid values so neither of these errors can occur
+                BadStart::AuthTypeNotSet | BadStart::IncompatibleActionAndType => unreachable!(),
+                _ => ClientError::InvalidPacketData,
+            })?,
+        );
 
-            let mut inner = self.inner.lock().await;
+        let record = self.stats.start_session(OperationKind::Authentication);
+        let secret_key = self.secret.as_deref();
+        let mut inner = self
+            .begin_session(context.priority_for(OperationKind::Authentication))
+            .await;
+
+        // the exchange is wrapped so that the connection can be cleaned up properly if an error occurs partway through
+        let exchange_result = async {
             inner.send_packet(start_packet, secret_key).await?;
 
-            // response: whether authentication succeeded
-            let reply = inner.receive_packet::<ReplyOwned>(secret_key, 2).await?;
+            let mut expected_sequence_number = 2;
 
-            inner.set_internal_single_connect_status(reply.header());
-            inner
-                .post_session_cleanup(reply.body().status == authentication::Status::Error)
-                .await?;
+            loop {
+                let reply: Packet<ReplyOwned> = timeouts
+                    .time_box(
+                        TimeoutKind::ServerResponse,
+                        inner.receive_packet(secret_key, expected_sequence_number),
+                    )
+                    .await
+                    .map_err(|timeout| ClientError::PromptTimeout {
+                        kind: TimeoutKind::ServerResponse,
+                        timeout,
+                    })??;
+                inner.set_internal_single_connect_status(reply.header());
 
-            reply
-        };
+                let Some(mut prompt) = Prompt::from_reply(reply.body()) else {
+                    break Ok(reply);
+                };
+                prompt.message =
+                    self.process_user_message(OperationKind::Authentication, prompt.message);
 
-        let reply_status = ResponseStatus::try_from(reply.body().status);
-        let user_message = reply.body().server_message.clone();
-        let data = reply.body().data.clone();
+                #[cfg(feature = "session-events")]
+                self.report_prompt(&reply);
 
-        match reply_status {
-            Ok(status) => Ok(AuthenticationResponse {
-                status,
-                user_message,
-                data,
-            }),
-            Err(response::BadAuthenticationStatus(status)) => {
-                Err(ClientError::AuthenticationError {
-                    status,
-                    data,
-                    user_message,
-                })
+                let answer = match timeouts
+                    .time_box(TimeoutKind::UserInput, responder(prompt))
+                    .await
+                {
+                    Ok(answer) => answer,
+                    Err(timeout) => {
+                        self.abort_authentication(
+                            &mut inner,
+                            reply.header(),
+                            USER_INPUT_TIMEOUT_ABORT_MESSAGE,
+                        )
+                        .await;
+
+                        break Err(ClientError::PromptTimeout {
+                            kind: TimeoutKind::UserInput,
+                            timeout,
+                        });
+                    }
+                };
+
+                expected_sequence_number = self
+                    .continue_authentication(&mut inner, reply.header(), answer.as_bytes())
+                    .await?;
             }
         }
+        .await;
+
+        let (reply, cleanup_result) = match exchange_result {
+            Ok(reply) => {
+                let cleanup_result = inner
+                    .post_session_cleanup(
+                        reply.body().status == authentication::Status::Error
+                            && self.compatibility_profile.error_closes_connection(),
+                    )
+                    .await;
+                (reply, cleanup_result)
+            }
+            Err(err) => {
+                // the session was left in an unknown state, so the connection can't be reused
+                inner.abandon_session();
+                return Err(err);
+            }
+        };
+        drop(inner);
+
+        let result = self.authentication_response(&reply, record);
+        error::with_cleanup_result(result, cleanup_result)
     }
 
     /// Performs TACACS+ authorization against the server with the provided arguments.
     ///
     /// A merged `Vec` of all of the sent and received arguments is returned, with values replaced from
     /// the server as necessary. No guarantees are made for the replacement of several arguments with
-    /// the same name, however, since even RFC8907 doesn't specify how to handle that case.
-    pub async fn authorize(
+    /// the same name, however, since even RFC8907 doesn't specify how to handle that case. If the server
+    /// denies the request, only the arguments it returned are included, e.g. so the reason for the denial
+    /// can be displayed.
+    ///
+    /// The arguments can be passed as anything that can be borrowed as a slice (e.g., an array or a `Vec`), and are
+    /// only copied into the returned response if the server authorizes the request.
+    ///
+    /// Since authorization is idempotent, the request is retried once in a new session if the server
+    /// closes the connection before replying.
+    ///
+    /// # Cancellation
+    ///
+    /// Authorization has no lasting effect on the server beyond logging, so a cancelled request can simply be retried.
+    pub async fn authorize<'args, A: AsRef<[Argument<'args>]>>(
+        &self,
+        context: SessionContext,
+        arguments: A,
+    ) -> Result<AuthorizationResponse<'args>, ClientError> {
+        let arguments = arguments.as_ref();
+
+        // arguments are validated up front, since retrying a request with invalid arguments wouldn't help anyways
+        if let Some(schema) = &self.authorization_schema {
+            schema.enforce(
+                OperationKind::Authorization,
+                arguments,
+                self.schema_issue_handler.as_ref(),
+            )?;
+        }
+
+        let operation = middleware::WithArguments {
+            kind: OperationKind::Authorization,
+            context: &context,
+            arguments,
+        };
+
+        middleware::run(
+            &self.middlewares,
+            &operation,
+            |response: &AuthorizationResponse<'_>| response.status,
+            || self.perform_authorization(&context, arguments),
+        )
+        .await
+    }
+
+    /// Queries the privilege level a user is granted in privileged (enable) mode, via the conventional authorization
+    /// request with a `service=enable` argument.
+    ///
+    /// The privilege level of the context is sent as the requested level. If the server authorizes the request, the
+    /// level from the `priv-lvl` argument it returned is granted, or the requested level if it didn't return one;
+    /// `None` is returned if the server denies the request. If the server returns a `priv-lvl` that isn't a valid
+    /// privilege level, [`ClientError::InvalidPrivilegeLevel`] is returned.
+    ///
+    /// The request passes through the configured middlewares & authorization schema like any other authorization.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tacacs_plus::protocol::PrivilegeLevel;
+    /// use tacacs_plus::{Client, ContextBuilder};
+    /// # use futures::io::Cursor;
+    ///
+    /// # async fn enable(client: Client<Cursor<Vec<u8>>>) -> Result<(), tacacs_plus::ClientError> {
+    /// let context = ContextBuilder::new(String::from("someuser"))
+    ///     .privilege_level(PrivilegeLevel::new(15).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// match client.effective_privilege(context).await? {
+    ///     Some(level) => println!("enable granted at privilege level {level}"),
+    ///     None => println!("enable denied"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn effective_privilege(
         &self,
         context: SessionContext,
-        arguments: Vec<Argument<'_>>,
-    ) -> Result<AuthorizationResponse, ClientError> {
+    ) -> Result<Option<PrivilegeLevel>, ClientError> {
+        let requested = context.privilege_level();
+
+        // SAFETY: the argument is hardcoded & known to be valid ASCII
+        let service = Argument::new(
+            FieldText::try_from("service").unwrap(),
+            FieldText::try_from("enable").unwrap(),
+            true,
+        )
+        .unwrap();
+
+        let response = self.authorize(context, [service]).await?;
+        if response.status == ResponseStatus::Failure {
+            return Ok(None);
+        }
+
+        let granted = response
+            .arguments
+            .iter()
+            .find(|argument| argument.name().as_ref() == PRIVILEGE_LEVEL_ARGUMENT);
+
+        match granted {
+            Some(argument) => {
+                let value = argument.value().as_ref();
+                value
+                    .parse()
+                    .ok()
+                    .and_then(PrivilegeLevel::new)
+                    .map(Some)
+                    .ok_or_else(|| ClientError::InvalidPrivilegeLevel(value.to_owned()))
+            }
+            None => Ok(Some(requested)),
+        }
+    }
+
+    /// Performs an authorization session, without passing it through the configured middlewares.
+    async fn perform_authorization<'args>(
+        &self,
+        context: &SessionContext,
+        arguments: &[Argument<'args>],
+    ) -> Result<AuthorizationResponse<'args>, ClientError> {
         use authorization::ReplyOwned;
 
-        let request_packet = Packet::new(
+        let context = self.normalize_context(Cow::Borrowed(context));
+
+        // a retry of an unanswered identical request is sent under the same session id
+        let attempt = match &self.authorization_deduplicator {
+            Some(deduplicator) => {
+                let key = idempotency::AuthorizationDeduplicator::key(&context, arguments);
+                let now = self.clock.instant();
+                Some(deduplicator.begin(key, now, || self.new_session_id()).await)
+            }
+            None => None,
+        };
+
+        if attempt
+            .as_ref()
+            .is_some_and(|attempt| attempt.is_repeated())
+        {
+            self.stats.authorization_repeated();
+        }
+
+        #[cfg(feature = "session-events")]
+        if let Some(attempt) = attempt.as_ref().filter(|attempt| attempt.is_repeated()) {
+            self.events.emit(SessionEvent::AuthorizationRepeated {
+                session_id: attempt.session_id(),
+            });
+        }
+
+        // protected values are encrypted once, so a retried request is sent with the same values
+        let sent_arguments = match &self.argument_encryption {
+            Some(encryption) => encryption.encrypt(arguments)?,
+            None => Cow::Borrowed(arguments),
+        };
+
+        let make_request_packet = || {
             // use default minor version, since there's no reason to use v1 outside of authentication
-            self.make_header(1, MinorVersion::Default),
-            authorization::Request::new(
-                context.authentication_method(),
-                AuthenticationContext {
-                    privilege_level: context.privilege_level,
-                    authentication_type: protocol::AuthenticationType::NotSet,
-                    // TODO: allow this to be specified as well? for guest it should probably be none
-                    service: AuthenticationService::Login,
-                },
-                context.as_user_information()?,
-                Arguments::new(&arguments).ok_or(ClientError::TooManyArguments)?,
-            ),
-        );
+            let header = match &attempt {
+                Some(attempt) => {
+                    self.make_session_header(1, MinorVersion::Default, attempt.session_id())
+                }
+                None => self.make_header(1, MinorVersion::Default),
+            };
+
+            Ok::<_, ClientError>(Packet::new(
+                header,
+                authorization::Request::new(
+                    context.authentication_method(),
+                    AuthenticationContext {
+                        privilege_level: context.privilege_level,
+                        authentication_type: context.authentication_type(),
+                        // TODO: allow this to be specified as well? for guest it should probably be none
+                        service: AuthenticationService::Login,
+                    },
+                    context.as_user_information()?,
+                    Arguments::new(&sent_arguments).ok_or(ClientError::TooManyArguments)?,
+                ),
+            ))
+        };
+
+        let record = self.stats.start_session(OperationKind::Authorization);
 
         // the inner mutex is locked within a block to ensure it's only locked as long as necessary
-        let reply = {
+        let (reply, cleanup_result) = {
             let secret_key = self.secret.as_deref();
 
-            let mut inner = self.inner.lock().await;
-            inner.send_packet(request_packet, secret_key).await?;
+            let priority = context.priority_for(OperationKind::Authorization);
+            let mut inner = self.begin_session(priority).await;
+
+            let exchange_result = match inner.send_packet(make_request_packet()?, secret_key).await
+            {
+                Ok(()) => inner.receive_packet(secret_key, 2).await,
+                Err(err) => Err(err),
+            };
 
-            let reply: Packet<ReplyOwned> = inner.receive_packet(secret_key, 2).await?;
+            let reply: Packet<ReplyOwned> = match exchange_result {
+                // the connection has already been dropped at this point, so the retry will happen on a fresh one
+                Err(ClientError::ConnectionClosedByServer { .. }) => {
+                    // a shared connection can't be reopened by a single session, so the retry begins a new one
+                    if let SessionLink::Shared(_) = inner {
+                        inner = self.begin_session(priority).await;
+                    }
+
+                    inner
+                        .send_packet(make_request_packet()?, secret_key)
+                        .await?;
+                    inner.receive_packet(secret_key, 2).await?
+                }
+                result => result?,
+            };
 
             // update inner state based on response
             inner.set_internal_single_connect_status(reply.header());
-            inner
-                .post_session_cleanup(reply.body().status == authorization::Status::Error)
-                .await?;
+            let cleanup_result = inner
+                .post_session_cleanup(
+                    reply.body().status == authorization::Status::Error
+                        && self.compatibility_profile.error_closes_connection(),
+                )
+                .await;
+
+            (reply, cleanup_result)
+        };
+
+        if let Some(attempt) = attempt {
+            attempt.answered();
+        }
 
-            reply
+        let received_arguments = match &self.argument_encryption {
+            Some(encryption) => match encryption.decrypt(reply.body().arguments.clone()) {
+                Ok(arguments) => arguments,
+                Err(err) => return error::with_cleanup_result(Err(err), cleanup_result),
+            },
+            None => reply.body().arguments.clone(),
         };
 
         let packet_status = reply.body().status;
-        let user_message = reply.body().server_message.clone();
+        let user_message = self.process_user_message(
+            OperationKind::Authorization,
+            reply.body().server_message.clone(),
+        );
         let admin_message = reply.body().data.clone();
 
-        match ResponseStatus::try_from(packet_status) {
-            Ok(status) => {
-                let owned_arguments = arguments.into_iter().map(Argument::into_owned).collect();
+        self.report_admin_message(OperationKind::Authorization, &admin_message, reply.header());
 
-                let merged_arguments = merge_authorization_arguments(
-                    packet_status == authorization::Status::PassReplace,
-                    owned_arguments,
-                    reply.body().arguments.clone(),
+        let result = match ResponseStatus::try_from(packet_status) {
+            Ok(status) => {
+                emit_event!(
+                    self.events,
+                    SessionEvent::Decision {
+                        operation: OperationKind::Authorization,
+                        session: SessionInfo::from_header(reply.header()),
+                        status,
+                    }
                 );
+                record.finish(status);
+
+                let changes = ArgumentDiff::new(packet_status, arguments, &received_arguments);
+
+                // none of the sent arguments were authorized if the request was denied, so only the server's
+                // arguments (e.g. the reason for the denial) are returned in that case
+                let response_arguments = match status {
+                    ResponseStatus::Success => merge_authorization_arguments(
+                        packet_status == authorization::Status::PassReplace,
+                        arguments,
+                        received_arguments,
+                    ),
+                    ResponseStatus::Failure => received_arguments,
+                };
 
                 Ok(AuthorizationResponse {
                     status,
-                    arguments: merged_arguments,
+                    raw_status: packet_status,
+                    arguments: response_arguments,
+                    changes,
                     user_message,
                     admin_message,
+                    session: SessionInfo::from_header(reply.header()),
                 })
             }
             Err(response::BadAuthorizationStatus(status)) => Err(ClientError::AuthorizationError {
@@ -320,7 +1367,9 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
                 user_message,
                 admin_message,
             }),
-        }
+        };
+
+        error::with_cleanup_result(result, cleanup_result)
     }
 
     /// Starts tracking a task via the TACACS+ accounting mechanism.
@@ -332,6 +1381,13 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     /// [`stop()`](AccountingTask::stop) methods on the returned [`AccountingTask`] should be used for sending
     /// additional accounting records.
     ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped after the start record was sent, the server may have recorded the task as
+    /// started, but no [`AccountingTask`] is returned to stop it with. Such a task never appears to finish on the
+    /// server, unless it's stopped separately via [`account_stop_once()`](Self::account_stop_once) (under a new task
+    /// ID).
+    ///
     /// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
     pub async fn account_begin<'args, A: AsRef<[Argument<'args>]>>(
         &self,
@@ -340,45 +1396,233 @@ impl<S: AsyncRead + AsyncWrite + Unpin> Client<S> {
     ) -> Result<(AccountingTask<&Self>, AccountingResponse), ClientError> {
         AccountingTask::start(self, context, arguments).await
     }
-}
 
-impl<S: fmt::Debug> fmt::Debug for Client<S> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        // adapted from std mutex impl
-        let inner_debug = match self.inner.try_lock() {
-            Some(inner) => format!("{inner:?}"),
-            None => String::from("(locked)"),
+    /// Sends a single stop record for a task that has already completed, without a prior start record.
+    ///
+    /// This is meant for integrations that only learn about a task after the fact. The `task_id`, `start_time`,
+    /// `stop_time` and `elapsed_time` arguments specified in [RFC8907 section 8.3] are set internally based on when
+    /// the task started and how long it took, in addition to the provided arguments. As with
+    /// [`account_begin()`](Self::account_begin), the start time has to be after the Unix epoch, and the end of the task
+    /// has to be representable as a [`SystemTime`]; otherwise, an error is returned.
+    ///
+    /// # Cancellation
+    ///
+    /// A stop record whose future is dropped after it was sent may or may not have been recorded by the server, so
+    /// retrying it can result in a duplicate record.
+    ///
+    /// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
+    pub async fn account_stop_once<'args, A: AsRef<[Argument<'args>]>>(
+        &self,
+        context: SessionContext,
+        arguments: A,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> Result<AccountingResponse, ClientError> {
+        AccountingTask::stop_once(self, context, arguments, started_at, duration).await
+    }
+
+    /// Starts a sequence of related operations for a user, all of which share the provided context.
+    ///
+    /// See [`Session`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use futures::io::Cursor;
+    ///
+    /// use tacacs_plus::{Client, ContextBuilder};
+    ///
+    /// let client = Client::new(
+    ///     Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })),
+    ///     Some("secret"),
+    /// );
+    /// let session = client.session(ContextBuilder::new(String::from("admin")).build().unwrap());
+    /// assert_eq!(session.context().privilege_level(), Default::default());
+    /// ```
+    pub fn session(&self, context: SessionContext) -> Session<'_, S> {
+        Session::new(self, context)
+    }
+
+    /// Checks that the server is reachable, e.g. as part of a monitoring loop, and measures how long that took.
+    ///
+    /// See [`ProbeMethod`] for what each kind of probe checks. Errors are returned as they would be from the
+    /// corresponding operation, so e.g. an unreachable server results in a [`ClientError::IOError`].
+    ///
+    /// The latency includes waiting for other sessions on this client to finish, since they share a connection.
+    ///
+    /// # Cancellation
+    ///
+    /// A cancelled [`Connect`](ProbeMethod::Connect) probe drops its separate connection without closing it, and a
+    /// cancelled [`Authorization`](ProbeMethod::Authorization) probe behaves like a cancelled
+    /// [`authorize()`](Self::authorize) call.
+    pub async fn probe(&self, method: ProbeMethod) -> Result<ProbeReport, ClientError> {
+        let started = Instant::now();
+
+        let status = match method {
+            ProbeMethod::Connect => {
+                self.lock_inner(SessionPriority::Standard)
+                    .await
+                    .probe_connection()
+                    .await?;
+                None
+            }
+            ProbeMethod::Authorization(context) => {
+                let response = self.authorize(context, [ProbeMethod::argument()]).await?;
+                Some(response.status)
+            }
+        };
+
+        Ok(ProbeReport {
+            latency: started.elapsed(),
+            status,
+        })
+    }
+
+    /// Sends a prebuilt packet to the server and returns the server's reply, bypassing the higher-level session helpers.
+    ///
+    /// **This is an advanced API**, mainly intended for conformance testing against servers with intentionally
+    /// unusual packets. No validation is performed on the provided packet beyond what is required to serialize it,
+    /// but it is still obfuscated with the configured secret (if any) and sent over the client's shared connection.
+    ///
+    /// The reply is expected to have a sequence number one greater than that of the sent packet,
+    /// and each call is treated as a complete session with respect to connection reuse; that is, the connection is
+    /// closed afterwards unless single connection mode has been negotiated with the server.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped after the packet was sent, whether the server processed it is unknown. As with
+    /// other operations, the connection isn't reused for the next session in that case.
+    #[cfg(feature = "raw-packets")]
+    pub async fn send_raw<B, R>(&self, packet: Packet<B>) -> Result<Packet<R>, ClientError>
+    where
+        B: PacketBody + Serialize,
+        R: PacketBody + for<'a> Deserialize<'a>,
+    {
+        let expected_sequence_number = packet
+            .header()
+            .sequence_number()
+            .checked_add(1)
+            .ok_or(ClientError::SequenceNumberOverflow)?;
+
+        let secret_key = self.secret.as_deref();
+
+        let mut inner = self.begin_session(SessionPriority::Standard).await;
+
+        let exchange_result = async {
+            inner.send_packet(packet, secret_key).await?;
+            inner
+                .receive_packet(secret_key, expected_sequence_number)
+                .await
+        }
+        .await;
+
+        let reply = match exchange_result {
+            Ok(reply) => reply,
+            Err(err) => {
+                // the session was left in an unknown state, so the connection can't be reused
+                inner.abandon_session();
+                return Err(err);
+            }
         };
 
-        // we explicitly omit the secret here to avoid exposing it
+        inner.set_internal_single_connect_status(reply.header());
+        inner.post_session_cleanup(false).await?;
+
+        Ok(reply)
+    }
+}
+
+impl<S> fmt::Debug for Client<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the inner client is locked for the duration of a session, so the connection metadata is read from
+        // the published snapshot instead; the secret is explicitly omitted to avoid exposing it
         f.debug_struct("Client")
-            .field("inner", &inner_debug)
+            .field("state", &self.snapshot.kind())
+            .field("connections_opened", &self.snapshot.connections_opened())
+            .field("sessions_completed", &self.snapshot.sessions_completed())
             .finish_non_exhaustive()
     }
 }
 
+// not derived, since that would require the connection type to be Clone as well
+impl<S> Clone for Client<S> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            queue: self.queue.clone(),
+            snapshot: self.snapshot.clone(),
+            stats: self.stats.clone(),
+            secret: self.secret.clone(),
+            compatibility_profile: self.compatibility_profile,
+            password_policy: self.password_policy.clone(),
+            authorization_schema: self.authorization_schema.clone(),
+            accounting_schema: self.accounting_schema.clone(),
+            schema_issue_handler: self.schema_issue_handler.clone(),
+            authorization_deduplicator: self.authorization_deduplicator.clone(),
+            argument_encryption: self.argument_encryption.clone(),
+            max_authentication_restarts: self.max_authentication_restarts,
+            admin_message_sink: self.admin_message_sink.clone(),
+            user_message_processor: self.user_message_processor.clone(),
+            abandon_handler: self.abandon_handler.clone(),
+            accounting_fallback: self.accounting_fallback.clone(),
+            clock: self.clock.clone(),
+            chap_start_data: self.chap_start_data,
+            start_data_builders: self.start_data_builders.clone(),
+            rng: self.rng.clone(),
+            user_name_normalizer: self.user_name_normalizer.clone(),
+            middlewares: self.middlewares.clone(),
+            #[cfg(feature = "locale-attributes")]
+            locale_attributes: self.locale_attributes.clone(),
+            #[cfg(feature = "session-events")]
+            events: self.events.clone(),
+        }
+    }
+}
+
 /// Merges the sent & received arguments within a successful authorization session.
 ///
 /// Note that this assumes there are no duplicate arguments, as even RFC8907 is unclear
-/// on how to handle that case.
-fn merge_authorization_arguments(
+/// on how to handle that case. If there are duplicates, received values are applied to the first
+/// argument with the same name.
+fn merge_authorization_arguments<'args>(
     replacing: bool,
-    mut sent_arguments: Vec<Argument<'static>>,
-    mut received_arguments: Vec<Argument<'static>>,
-) -> Vec<Argument<'static>> {
+    sent_arguments: &[Argument<'args>],
+    mut received_arguments: Vec<Argument<'args>>,
+) -> Vec<Argument<'args>> {
+    let mut merged_arguments = Vec::with_capacity(sent_arguments.len() + received_arguments.len());
+    merged_arguments.extend_from_slice(sent_arguments);
+
     if replacing {
-        for received in received_arguments.into_iter() {
-            if let Some(sent) = sent_arguments
-                .iter_mut()
-                .find(|arg| arg.name() == received.name())
-            {
-                sent.set_value(received.value().clone());
-            } else {
-                sent_arguments.push(received);
+        // look up the position of each received argument in the merged list by name up front, so merging
+        // stays linear for replies with many arguments
+        let mut positions = HashMap::with_capacity(merged_arguments.capacity());
+        for (index, sent) in sent_arguments.iter().enumerate() {
+            positions.entry(sent.name().as_ref()).or_insert(index);
+        }
+
+        let mut next_position = sent_arguments.len();
+        let targets: Vec<Option<usize>> = received_arguments
+            .iter()
+            .map(|received| match positions.entry(received.name().as_ref()) {
+                Entry::Occupied(position) => Some(*position.get()),
+                Entry::Vacant(position) => {
+                    position.insert(next_position);
+                    next_position += 1;
+                    None
+                }
+            })
+            .collect();
+
+        for (received, target) in received_arguments.into_iter().zip(targets) {
+            match target {
+                Some(position) => {
+                    merged_arguments[position].set_value(received.value().clone());
+                }
+                None => merged_arguments.push(received),
             }
         }
     } else {
-        sent_arguments.append(&mut received_arguments);
+        merged_arguments.append(&mut received_arguments);
     }
-    sent_arguments
+    merged_arguments
 }
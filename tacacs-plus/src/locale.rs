@@ -0,0 +1,134 @@
+//! Timezone & locale accounting arguments, which allow servers to interpret the timestamps in accounting records.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use tacacs_plus_protocol::{Argument, FieldText};
+
+use super::ClientError;
+
+#[cfg(test)]
+mod tests;
+
+/// The name of the timezone argument, as specified in [RFC8907 section 8.3](https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments).
+const TIMEZONE: &str = "timezone";
+
+/// The name of the (nonstandard) locale argument.
+const LOCALE: &str = "locale";
+
+/// The file containing the system timezone name on Debian-based systems.
+const TIMEZONE_FILE: &str = "/etc/timezone";
+
+/// The symbolic link to the system timezone database entry on most other Unix-like systems.
+const LOCALTIME_LINK: &str = "/etc/localtime";
+
+/// Timezone & locale information attached to the start and stop records of accounting tasks,
+/// as configured via [`ClientBuilder::accounting_locale()`](super::ClientBuilder::accounting_locale).
+///
+/// The timezone is sent as the `timezone` argument from [RFC8907 section 8.3], while the locale is sent
+/// as a nonstandard `locale` argument, since the RFC doesn't specify one. Fields set to `None` are omitted.
+///
+/// Values detected via [`from_system()`](Self::from_system) can be overridden by setting the fields directly.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{FieldText, LocaleAttributes};
+///
+/// let mut attributes = LocaleAttributes::from_system();
+///
+/// // the detected timezone can be replaced, e.g. if a server expects abbreviations instead of IANA names
+/// attributes.timezone = Some(FieldText::try_from("UTC").unwrap());
+///
+/// // and the locale omitted entirely
+/// attributes.locale = None;
+/// ```
+///
+/// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LocaleAttributes {
+    /// The timezone all timestamps in a record are in.
+    pub timezone: Option<FieldText<'static>>,
+
+    /// The locale of the system the client is running on (e.g., `en_US.UTF-8`).
+    pub locale: Option<FieldText<'static>>,
+}
+
+impl LocaleAttributes {
+    /// Detects the timezone & locale from the settings of the system the client is running on.
+    ///
+    /// The timezone is taken from the `TZ` environment variable if set, and otherwise from the system timezone
+    /// configuration (`/etc/timezone` or the target of the `/etc/localtime` link). The locale is taken from the first
+    /// of the `LC_ALL`, `LC_TIME` and `LANG` environment variables that is set, following POSIX precedence.
+    ///
+    /// Values that can't be determined or aren't printable ASCII are left as `None`.
+    pub fn from_system() -> Self {
+        Self {
+            timezone: system_timezone().and_then(into_field_text),
+            locale: system_locale().and_then(into_field_text),
+        }
+    }
+
+    /// Appends the configured attributes to the arguments of an accounting record.
+    pub(super) fn append_arguments<'a>(
+        &'a self,
+        arguments: &mut Vec<Argument<'a>>,
+    ) -> Result<(), ClientError> {
+        for (name, value) in [(TIMEZONE, &self.timezone), (LOCALE, &self.locale)] {
+            if let Some(value) = value {
+                arguments.push(Argument::new(
+                    // SAFETY: argument names are hardcoded & known to be valid ASCII
+                    FieldText::try_from(name).unwrap(),
+                    value.clone(),
+                    false,
+                )?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the name of the system timezone, if it can be determined.
+fn system_timezone() -> Option<String> {
+    // POSIX allows a leading colon to indicate an implementation-defined format, which is usually just a zoneinfo name
+    if let Some(timezone) = non_empty_env_var("TZ") {
+        return Some(timezone.trim_start_matches(':').to_owned());
+    }
+
+    if let Ok(contents) = fs::read_to_string(TIMEZONE_FILE) {
+        let timezone = contents.trim();
+        if !timezone.is_empty() {
+            return Some(timezone.to_owned());
+        }
+    }
+
+    // e.g. /usr/share/zoneinfo/America/New_York -> America/New_York
+    let target = fs::read_link(LOCALTIME_LINK).ok()?;
+    zoneinfo_name(&target)
+}
+
+/// Extracts the timezone name from a path into the timezone database.
+fn zoneinfo_name(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    let (_, name) = path.split_once("zoneinfo/")?;
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+/// Gets the locale of the system, if set.
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_TIME", "LANG"]
+        .into_iter()
+        .find_map(non_empty_env_var)
+}
+
+/// Gets the value of an environment variable, treating an empty value the same as an unset one.
+fn non_empty_env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Converts a detected value into a [`FieldText`], discarding it if it isn't printable ASCII.
+fn into_field_text(value: String) -> Option<FieldText<'static>> {
+    FieldText::try_from(value).ok()
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use tacacs_plus_protocol::{Argument, FieldText};
+
+use super::{zoneinfo_name, LocaleAttributes};
+
+#[test]
+fn zoneinfo_name_extracted_from_link_target() {
+    assert_eq!(
+        zoneinfo_name(Path::new("/usr/share/zoneinfo/America/New_York")),
+        Some(String::from("America/New_York"))
+    );
+    assert_eq!(
+        zoneinfo_name(Path::new("../usr/share/zoneinfo/UTC")),
+        Some(String::from("UTC"))
+    );
+    assert_eq!(zoneinfo_name(Path::new("/usr/share/zoneinfo/")), None);
+    assert_eq!(zoneinfo_name(Path::new("/etc/some-other-file")), None);
+}
+
+#[test]
+fn unset_attributes_omitted() {
+    let attributes = LocaleAttributes {
+        timezone: Some(FieldText::try_from("UTC").unwrap()),
+        locale: None,
+    };
+
+    let mut arguments = Vec::new();
+    attributes
+        .append_arguments(&mut arguments)
+        .expect("timezone argument should be valid");
+
+    assert_eq!(
+        arguments,
+        [Argument::new(
+            FieldText::try_from("timezone").unwrap(),
+            FieldText::try_from("UTC").unwrap(),
+            false
+        )
+        .unwrap()]
+    );
+}
@@ -0,0 +1,378 @@
+//! Middlewares that the operations of a client pass through, for behavior shared between all operations.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::pin::{pin, Pin};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use tacacs_plus_protocol::Argument;
+
+use super::SessionPriority;
+use super::{AuthenticationType, ClientError, OperationKind, ResponseStatus, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An operation performed by a [`Client`](super::Client), as seen by a [`Middleware`].
+///
+/// Each operation sends a request packet in a new session and builds a typed response (e.g. an
+/// [`AuthorizationResponse`](super::AuthorizationResponse)) from the server's reply. Middlewares only see what all
+/// operations have in common, so they can be written once rather than for each operation.
+///
+/// Operations are created by the client for each call to [`authenticate()`](super::Client::authenticate),
+/// [`authorize()`](super::Client::authorize) and each accounting record; this trait can't be implemented outside of this
+/// crate.
+pub trait TacacsOperation: fmt::Debug + Send + Sync + sealed::Sealed {
+    /// The kind of the operation.
+    fn kind(&self) -> OperationKind;
+
+    /// The context of the session the operation is performed in.
+    fn context(&self) -> &SessionContext;
+
+    /// The type of authentication used, or `None` for operations other than authentication.
+    fn authentication_type(&self) -> Option<AuthenticationType>;
+
+    /// The arguments sent in the request, including those added by the client (e.g. `task_id` for accounting).
+    ///
+    /// Authentication requests don't have arguments, so this is always empty for them.
+    fn arguments(&self) -> &[Argument<'_>];
+
+    /// The priority of the operation's session when waiting for the connection of the client.
+    ///
+    /// This is the priority [set on its context](super::ContextBuilder::priority) if any, or otherwise the
+    /// [default for its kind](SessionPriority::default_for).
+    fn priority(&self) -> SessionPriority {
+        self.context().priority_for(self.kind())
+    }
+}
+
+/// Cross-cutting behavior wrapped around every operation of a client, e.g. retries, metrics or tracing.
+///
+/// Middlewares are added via [`ClientBuilder::middleware()`](super::ClientBuilder::middleware) and layered in the order
+/// they're added, so the first middleware sees each operation first and its outcome last. The operation itself is
+/// performed by running the rest of the chain via [`Next::run()`], which can be done several times (e.g. to retry)
+/// or not at all (e.g. to reject an operation by returning an error).
+///
+/// Only the [`Outcome`] of an operation passes through middlewares, while the typed response corresponding to the
+/// returned outcome is handed back to the caller.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Instant;
+///
+/// use futures::future::BoxFuture;
+/// use tacacs_plus::{ClientError, Middleware, Next, Outcome, TacacsOperation};
+///
+/// /// Logs how long each operation took, along with its outcome.
+/// struct Timing;
+///
+/// impl Middleware for Timing {
+///     fn handle<'a>(
+///         &'a self,
+///         operation: &'a dyn TacacsOperation,
+///         next: Next<'a>,
+///     ) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+///         Box::pin(async move {
+///             let started = Instant::now();
+///             let result = next.run().await;
+///
+///             let status = result.as_ref().map(Outcome::status);
+///             println!("{:?} took {:?}: {status:?}", operation.kind(), started.elapsed());
+///             result
+///         })
+///     }
+/// }
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Handles an operation, usually by running the rest of the chain via [`next`](Next::run) and inspecting its
+    /// outcome.
+    ///
+    /// Since an [`Outcome`] can only be obtained from [`Next::run()`], a middleware can only end an operation early
+    /// by returning an error.
+    fn handle<'a>(
+        &'a self,
+        operation: &'a dyn TacacsOperation,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Outcome, ClientError>>;
+}
+
+/// The rest of a middleware chain, which ends with the operation itself being performed.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    operation: &'a dyn TacacsOperation,
+    middlewares: &'a [Arc<dyn Middleware>],
+    runs: &'a Runs,
+}
+
+impl<'a> Next<'a> {
+    /// Runs the rest of the chain.
+    ///
+    /// This can be called several times, in which case the operation is performed again in a new session each time,
+    /// e.g. to retry an operation that failed with an error. Runs that are in progress at the same time are performed
+    /// one after another, and dropping the future of a run stops it from being performed.
+    pub fn run(self) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => middleware.handle(
+                self.operation,
+                Next {
+                    middlewares: rest,
+                    ..self
+                },
+            ),
+            None => Box::pin(Run {
+                runs: self.runs,
+                id: None,
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("operation", &self.operation)
+            .field("remaining_middlewares", &self.middlewares.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The outcome of an operation that completed without an error, as passed back through a middleware chain.
+#[derive(Debug)]
+pub struct Outcome {
+    status: ResponseStatus,
+
+    /// The index of the response this outcome corresponds to, among all runs of the operation.
+    index: usize,
+}
+
+impl Outcome {
+    /// Whether the server granted the request.
+    ///
+    /// Accounting records are always successful, since any other reply status is reported as an error.
+    pub fn status(&self) -> ResponseStatus {
+        self.status
+    }
+}
+
+/// The runs of an operation requested by the end of a middleware chain.
+///
+/// Operations aren't performed by the chain itself but by the future driving it (see [`run()`]), so the boxed futures
+/// of middlewares don't hold on to the connection of a client, which would otherwise have to be `Send`.
+#[derive(Default)]
+struct Runs(Mutex<RunsState>);
+
+#[derive(Default)]
+struct RunsState {
+    next_id: usize,
+
+    /// The state of each run whose future is still around, by the order they were requested in.
+    states: BTreeMap<usize, RunState>,
+}
+
+enum RunState {
+    Requested,
+    Performing,
+    Finished(Result<Outcome, ClientError>),
+}
+
+impl Runs {
+    fn lock(&self) -> MutexGuard<'_, RunsState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A run of an operation at the end of a middleware chain, which is requested when it's first polled.
+///
+/// Runs don't register wakers, since the chain is polled again whenever a run is finished.
+struct Run<'a> {
+    runs: &'a Runs,
+    id: Option<usize>,
+}
+
+impl Future for Run<'_> {
+    type Output = Result<Outcome, ClientError>;
+
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut runs = self.runs.lock();
+
+        let Some(id) = self.id else {
+            let id = runs.next_id;
+            runs.next_id += 1;
+            runs.states.insert(id, RunState::Requested);
+
+            drop(runs);
+            self.id = Some(id);
+            return Poll::Pending;
+        };
+
+        match runs.states.remove(&id) {
+            Some(RunState::Finished(result)) => {
+                drop(runs);
+                self.id = None;
+                Poll::Ready(result)
+            }
+            Some(state) => {
+                runs.states.insert(id, state);
+                Poll::Pending
+            }
+            None => unreachable!("runs are only removed by their own future"),
+        }
+    }
+}
+
+impl Drop for Run<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            self.runs.lock().states.remove(&id);
+        }
+    }
+}
+
+/// Performs an operation through a chain of middlewares, returning the response whose outcome the chain returned.
+pub(crate) async fn run<R, F, Fut>(
+    middlewares: &[Arc<dyn Middleware>],
+    operation: &dyn TacacsOperation,
+    status: fn(&R) -> ResponseStatus,
+    mut perform: F,
+) -> Result<R, ClientError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<R, ClientError>>,
+{
+    if middlewares.is_empty() {
+        return perform().await;
+    }
+
+    let runs = Runs::default();
+    let mut chain = Next {
+        operation,
+        middlewares,
+        runs: &runs,
+    }
+    .run();
+
+    // responses are stored aside, since only their outcomes pass through the middlewares
+    let mut responses = Vec::new();
+    let mut performing = None;
+    let mut current = pin!(None::<Fut>);
+
+    let outcome = poll_fn(|cx| loop {
+        if let Poll::Ready(outcome) = chain.as_mut().poll(cx) {
+            return Poll::Ready(outcome);
+        }
+
+        let mut state = runs.lock();
+
+        // a run is abandoned if the chain drops its future, e.g. when a middleware enforces a timeout
+        if performing.is_some_and(|id| !state.states.contains_key(&id)) {
+            performing = None;
+            current.set(None);
+        }
+
+        if performing.is_none() {
+            let Some((&id, run)) = state
+                .states
+                .iter_mut()
+                .find(|(_, run)| matches!(run, RunState::Requested))
+            else {
+                return Poll::Pending;
+            };
+
+            *run = RunState::Performing;
+            performing = Some(id);
+            current.set(Some(perform()));
+        }
+        drop(state);
+
+        let Some(future) = current.as_mut().as_pin_mut() else {
+            unreachable!("operation is always being performed at this point");
+        };
+        let Poll::Ready(result) = future.poll(cx) else {
+            return Poll::Pending;
+        };
+        current.set(None);
+
+        let result = result.map(|response| {
+            let status = status(&response);
+            responses.push(Some(response));
+            Outcome {
+                status,
+                index: responses.len() - 1,
+            }
+        });
+
+        if let Some(id) = performing.take() {
+            if let Some(run) = runs.lock().states.get_mut(&id) {
+                *run = RunState::Finished(result);
+            }
+        }
+    })
+    .await?;
+
+    drop(chain);
+    let response = responses.get_mut(outcome.index).and_then(Option::take);
+    Ok(response.expect("middleware returned an outcome from a different operation"))
+}
+
+/// An authentication operation with a password.
+#[derive(Debug)]
+pub(crate) struct Authentication<'a> {
+    pub(crate) context: &'a SessionContext,
+    pub(crate) authentication_type: AuthenticationType,
+}
+
+impl sealed::Sealed for Authentication<'_> {}
+
+impl TacacsOperation for Authentication<'_> {
+    fn kind(&self) -> OperationKind {
+        OperationKind::Authentication
+    }
+
+    fn context(&self) -> &SessionContext {
+        self.context
+    }
+
+    fn authentication_type(&self) -> Option<AuthenticationType> {
+        Some(self.authentication_type)
+    }
+
+    fn arguments(&self) -> &[Argument<'_>] {
+        &[]
+    }
+}
+
+/// An authorization or accounting operation, which both send a set of arguments.
+#[derive(Debug)]
+pub(crate) struct WithArguments<'a, 'args> {
+    pub(crate) kind: OperationKind,
+    pub(crate) context: &'a SessionContext,
+    pub(crate) arguments: &'a [Argument<'args>],
+}
+
+impl sealed::Sealed for WithArguments<'_, '_> {}
+
+impl TacacsOperation for WithArguments<'_, '_> {
+    fn kind(&self) -> OperationKind {
+        self.kind
+    }
+
+    fn context(&self) -> &SessionContext {
+        self.context
+    }
+
+    fn authentication_type(&self) -> Option<AuthenticationType> {
+        None
+    }
+
+    fn arguments(&self) -> &[Argument<'_>] {
+        self.arguments
+    }
+}
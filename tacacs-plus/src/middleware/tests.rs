@@ -0,0 +1,312 @@
+use std::io;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{AuthenticationType, ClientBuilder, ContextBuilder};
+
+/// Records the operations passing through it, along with their outcomes.
+struct Recorder {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Recorder {
+    fn handle<'a>(
+        &'a self,
+        operation: &'a dyn TacacsOperation,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+        Box::pin(async move {
+            let arguments: Vec<String> = operation
+                .arguments()
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            self.log.lock().unwrap().push(format!(
+                "{} before {:?} {}",
+                self.name,
+                operation.kind(),
+                arguments.join(" ")
+            ));
+
+            let outcome = next.run().await;
+
+            let status = outcome.as_ref().map(Outcome::status);
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} after {status:?}", self.name));
+            outcome
+        })
+    }
+}
+
+/// Retries operations that fail with an error, up to a limit.
+struct Retry(usize);
+
+impl Middleware for Retry {
+    fn handle<'a>(
+        &'a self,
+        _operation: &'a dyn TacacsOperation,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+        Box::pin(async move {
+            let mut attempts = 1;
+            loop {
+                match next.run().await {
+                    Err(_) if attempts < self.0 => attempts += 1,
+                    result => return result,
+                }
+            }
+        })
+    }
+}
+
+/// Rejects every operation without performing it.
+struct Reject;
+
+impl Middleware for Reject {
+    fn handle<'a>(
+        &'a self,
+        _operation: &'a dyn TacacsOperation,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+        Box::pin(async { Err(io::Error::other("rejected by middleware").into()) })
+    }
+}
+
+/// Requests a run of the operation but drops it before it's performed, and then runs the operation for real.
+struct AbandonFirstRun;
+
+impl Middleware for AbandonFirstRun {
+    fn handle<'a>(
+        &'a self,
+        _operation: &'a dyn TacacsOperation,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Outcome, ClientError>> {
+        Box::pin(async move {
+            let mut abandoned = next.run();
+            assert!(futures::poll!(&mut abandoned).is_pending());
+            drop(abandoned);
+
+            next.run().await
+        })
+    }
+}
+
+/// A connection that can't be sent between threads.
+struct NotSend<S>(S, PhantomData<Rc<()>>);
+
+impl<S: AsyncRead + Unpin> AsyncRead for NotSend<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for NotSend<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_close(cx)
+    }
+}
+
+#[tokio::test]
+async fn middlewares_layered_in_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let client = ClientBuilder::new()
+        .middleware(Recorder {
+            name: "outer",
+            log: log.clone(),
+        })
+        .middleware(Recorder {
+            name: "inner",
+            log: log.clone(),
+        })
+        .build(Box::new(|| {
+            Box::pin(async { Ok(AcceptingServer::recording(Default::default(), true)) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let sent = [Argument::new(
+        "service".try_into().unwrap(),
+        "shell".try_into().unwrap(),
+        true,
+    )
+    .unwrap()];
+    let response = client.authorize(context.clone(), &sent).await.unwrap();
+    assert_eq!(response.arguments, sent);
+
+    let response = client
+        .authenticate(context, "password", AuthenticationType::Pap)
+        .await
+        .unwrap();
+    assert_eq!(response.status, ResponseStatus::Failure);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "outer before Authorization service=shell",
+            "inner before Authorization service=shell",
+            "inner after Ok(Success)",
+            "outer after Ok(Success)",
+            "outer before Authentication ",
+            "inner before Authentication ",
+            "inner after Ok(Failure)",
+            "outer after Ok(Failure)",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn accounting_records_pass_through_middlewares() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let client = ClientBuilder::new()
+        .middleware(Recorder {
+            name: "recorder",
+            log: log.clone(),
+        })
+        .build(Box::new(|| {
+            Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let (task, _) = client.account_begin(context, []).await.unwrap();
+    task.stop([]).await.unwrap();
+
+    // the arguments added by the client are visible to middlewares
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 4);
+    assert!(log[0].starts_with("recorder before Accounting task_id="));
+    assert!(log[0].contains("start_time="));
+    assert!(log[2].contains("stop_time="));
+}
+
+#[tokio::test]
+async fn retrying_middleware_performs_operation_again() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let connection_attempts = Arc::new(AtomicUsize::new(0));
+    let factory_attempts = connection_attempts.clone();
+
+    // the first connection attempt fails, but the retry goes through
+    let client = ClientBuilder::new()
+        .middleware(Retry(2))
+        .build(Box::new(move || {
+            let attempt = factory_attempts.fetch_add(1, Ordering::SeqCst);
+            let server = AcceptingServer::recording(server_received.clone(), false);
+            Box::pin(async move {
+                if attempt == 0 {
+                    Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+                } else {
+                    Ok(server)
+                }
+            })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let response = client
+        .authorize(context, [])
+        .await
+        .expect("retry should succeed");
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(connection_attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn rejecting_middleware_skips_operation() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new()
+        .middleware(Reject)
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(server_received.clone(), false);
+            Box::pin(async move { Ok(server) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let error = client
+        .authorize(context, [])
+        .await
+        .expect_err("middleware should reject operation");
+    assert!(
+        matches!(error, ClientError::IOError(ref error) if error.to_string() == "rejected by middleware"),
+        "unexpected error: {error:?}"
+    );
+    assert!(received.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn abandoned_run_not_performed() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new()
+        .middleware(AbandonFirstRun)
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(server_received.clone(), false);
+            Box::pin(async move { Ok(server) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let response = client.authorize(context, []).await.unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn middlewares_work_with_connections_that_are_not_send() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let client = ClientBuilder::new()
+        .middleware(Recorder {
+            name: "recorder",
+            log: log.clone(),
+        })
+        .build(Box::new(|| {
+            let server = AcceptingServer::recording(Default::default(), false);
+            Box::pin(async move { Ok(NotSend(server, PhantomData)) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let response = client.authorize(context, []).await.unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(log.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,81 @@
+use thiserror::Error;
+
+#[cfg(test)]
+mod tests;
+
+/// Client-side checks applied to a new password before attempting to change it on a server.
+///
+/// These allow obviously invalid passwords to be rejected without a round trip to the server, although
+/// the server may of course still enforce its own (stricter) rules. The default policy performs no checks.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{PasswordPolicy, PasswordPolicyViolation};
+///
+/// let policy = PasswordPolicy {
+///     min_length: 8,
+///     require_printable_ascii: true,
+///     reject_unchanged: true,
+/// };
+///
+/// assert_eq!(policy.check("hunter2", "hunter2"), Err(PasswordPolicyViolation::TooShort { min_length: 8 }));
+/// assert_eq!(policy.check("hunter22", "hunter22"), Err(PasswordPolicyViolation::Unchanged));
+/// assert_eq!(policy.check("hunter2", "correct horse battery staple"), Ok(()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PasswordPolicy {
+    /// The minimum length of a new password, in bytes.
+    pub min_length: usize,
+
+    /// Whether a new password must consist only of printable ASCII characters.
+    pub require_printable_ascii: bool,
+
+    /// Whether a new password must differ from the old one.
+    pub reject_unchanged: bool,
+}
+
+/// The reason a new password was rejected by a [`PasswordPolicy`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum PasswordPolicyViolation {
+    /// The new password was shorter than the policy's minimum length.
+    #[error("new password must be at least {min_length} bytes long")]
+    TooShort {
+        /// The minimum length required by the policy.
+        min_length: usize,
+    },
+
+    /// The new password contained characters that aren't printable ASCII.
+    #[error("new password must only contain printable ASCII characters")]
+    NotPrintableAscii,
+
+    /// The new password was the same as the old one.
+    #[error("new password must differ from the old password")]
+    Unchanged,
+}
+
+impl PasswordPolicy {
+    /// Checks a new password against this policy.
+    pub fn check(
+        &self,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), PasswordPolicyViolation> {
+        if new_password.len() < self.min_length {
+            Err(PasswordPolicyViolation::TooShort {
+                min_length: self.min_length,
+            })
+        } else if self.require_printable_ascii
+            && !new_password
+                .chars()
+                .all(|c| c.is_ascii() && !c.is_ascii_control())
+        {
+            Err(PasswordPolicyViolation::NotPrintableAscii)
+        } else if self.reject_unchanged && old_password == new_password {
+            Err(PasswordPolicyViolation::Unchanged)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::{PasswordPolicy, PasswordPolicyViolation};
+
+#[test]
+fn default_policy_accepts_anything() {
+    let policy = PasswordPolicy::default();
+
+    assert_eq!(policy.check("old", "old"), Ok(()));
+    assert_eq!(policy.check("old", ""), Ok(()));
+    assert_eq!(policy.check("old", "pässwörd\n"), Ok(()));
+}
+
+#[test]
+fn non_printable_passwords_rejected() {
+    let policy = PasswordPolicy {
+        require_printable_ascii: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        policy.check("old", "pässwörd"),
+        Err(PasswordPolicyViolation::NotPrintableAscii)
+    );
+    assert_eq!(
+        policy.check("old", "tab\tseparated"),
+        Err(PasswordPolicyViolation::NotPrintableAscii)
+    );
+    assert_eq!(policy.check("old", "spaces are fine"), Ok(()));
+}
+
+#[test]
+fn length_checked_before_other_rules() {
+    let policy = PasswordPolicy {
+        min_length: 10,
+        require_printable_ascii: true,
+        reject_unchanged: true,
+    };
+
+    assert_eq!(
+        policy.check("short", "short"),
+        Err(PasswordPolicyViolation::TooShort { min_length: 10 })
+    );
+}
@@ -0,0 +1,141 @@
+//! Binding of secrets to the servers (peers) they're shared with.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use super::ClientError;
+
+#[cfg(test)]
+mod tests;
+
+/// A (pinned, boxed) future that returns a client connection along with the address of its peer, as returned from a
+/// [`PeerConnectionFactory`].
+pub type PeerConnectionFuture<S> =
+    Pin<Box<dyn Future<Output = io::Result<(S, SocketAddr)>> + Send>>;
+
+/// An async factory that returns connections along with the addresses of the servers they're connected to.
+///
+/// This is used in place of a [`ConnectionFactory`](super::ConnectionFactory) with
+/// [`ClientBuilder::build_with_peers()`](super::ClientBuilder::build_with_peers), so the client can pick the secret for
+/// each server & refuse to use a secret with a server it isn't meant for, e.g. when the factory fails over to a backup server.
+///
+/// # Examples
+///
+/// ```
+/// use std::net::SocketAddr;
+///
+/// use futures::io::Cursor;
+///
+/// use tacacs_plus::PeerConnectionFactory;
+///
+/// let factory: PeerConnectionFactory<_> = Box::new(|| {
+///     Box::pin(async {
+///         let peer: SocketAddr = "192.0.2.1:49".parse().unwrap();
+///         Ok((Cursor::new(Vec::<u8>::new()), peer))
+///     })
+/// });
+/// # let _ = factory;
+/// ```
+pub type PeerConnectionFactory<S> = Box<dyn Fn() -> PeerConnectionFuture<S> + Send>;
+
+/// The secrets of individual servers, for clients whose connections can go to more than one server.
+///
+/// Secrets are looked up by the peer address reported by a [`PeerConnectionFactory`]. Connections to peers without a
+/// secret of their own use the client's default secret (as set via [`ClientBuilder::secret()`](super::ClientBuilder::secret)),
+/// which is bound to the first such peer: connecting to a different one afterwards fails with
+/// [`ClientError::UnexpectedPeer`] rather than sending packets obfuscated with a secret that's likely wrong, unless reuse of
+/// the default secret is [allowed](Self::allow_default_secret_reuse).
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::PeerSecrets;
+///
+/// let mut secrets = PeerSecrets::new();
+/// secrets
+///     .insert("192.0.2.1:49".parse().unwrap(), "primary server secret")
+///     .insert("192.0.2.2:49".parse().unwrap(), "backup server secret");
+/// # let _ = secrets;
+/// ```
+#[derive(Clone, Default)]
+pub struct PeerSecrets {
+    secrets: HashMap<SocketAddr, Arc<[u8]>>,
+    allow_default_secret_reuse: bool,
+}
+
+// the secrets themselves are omitted so they don't end up in logs
+impl fmt::Debug for PeerSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerSecrets")
+            .field("peers", &self.secrets.keys().collect::<Vec<_>>())
+            .field(
+                "allow_default_secret_reuse",
+                &self.allow_default_secret_reuse,
+            )
+            .finish()
+    }
+}
+
+impl PeerSecrets {
+    /// Creates an empty set of secrets, under which the default secret is only used with a single peer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the secret used for connections to a peer, replacing any previous one.
+    pub fn insert<K: AsRef<[u8]>>(&mut self, peer: SocketAddr, secret: K) -> &mut Self {
+        self.secrets.insert(peer, Arc::from(secret.as_ref()));
+        self
+    }
+
+    /// Sets whether the default secret can be used with any peer that doesn't have a secret of its own, rather than
+    /// only the first one.
+    ///
+    /// This is disabled by default, and should only be enabled if all such servers are known to share the same secret.
+    pub fn allow_default_secret_reuse(&mut self, allow: bool) -> &mut Self {
+        self.allow_default_secret_reuse = allow;
+        self
+    }
+}
+
+/// The peer secrets of a client, along with the peer its default secret was bound to.
+#[derive(Debug)]
+pub(super) struct PeerBinding {
+    secrets: PeerSecrets,
+    default_secret_peer: Option<SocketAddr>,
+}
+
+impl PeerBinding {
+    pub(super) fn new(secrets: PeerSecrets) -> Self {
+        Self {
+            secrets,
+            default_secret_peer: None,
+        }
+    }
+
+    /// Returns the secret to use with a newly connected peer, or `None` if the default secret should be used.
+    pub(super) fn secret_for(
+        &mut self,
+        peer: SocketAddr,
+    ) -> Result<Option<Arc<[u8]>>, ClientError> {
+        if let Some(secret) = self.secrets.secrets.get(&peer) {
+            return Ok(Some(secret.clone()));
+        }
+
+        match self.default_secret_peer {
+            Some(bound) if bound != peer && !self.secrets.allow_default_secret_reuse => {
+                Err(ClientError::UnexpectedPeer { peer, bound })
+            }
+            Some(_) => Ok(None),
+            None => {
+                self.default_secret_peer = Some(peer);
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{AsyncRead, AsyncWrite};
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{ClientBuilder, ContextBuilder};
+
+fn primary() -> SocketAddr {
+    "192.0.2.1:49".parse().unwrap()
+}
+
+fn backup() -> SocketAddr {
+    "192.0.2.2:49".parse().unwrap()
+}
+
+#[test]
+fn peer_specific_secret_used() {
+    let mut secrets = PeerSecrets::new();
+    secrets.insert(primary(), "primary secret");
+
+    let mut binding = PeerBinding::new(secrets);
+    let secret = binding
+        .secret_for(primary())
+        .expect("configured peer should be accepted");
+    assert_eq!(secret.as_deref(), Some(b"primary secret".as_slice()));
+
+    // peers with their own secret don't bind the default one
+    assert_eq!(binding.default_secret_peer, None);
+}
+
+#[test]
+fn default_secret_bound_to_first_peer() {
+    let mut binding = PeerBinding::new(PeerSecrets::new());
+
+    assert!(matches!(binding.secret_for(primary()), Ok(None)));
+    assert!(matches!(binding.secret_for(primary()), Ok(None)));
+
+    match binding.secret_for(backup()) {
+        Err(ClientError::UnexpectedPeer { peer, bound }) => {
+            assert_eq!(peer, backup());
+            assert_eq!(bound, primary());
+        }
+        other => panic!("expected unexpected peer error, got {other:?}"),
+    }
+}
+
+#[test]
+fn default_secret_reuse_allowed() {
+    let mut secrets = PeerSecrets::new();
+    secrets.allow_default_secret_reuse(true);
+
+    let mut binding = PeerBinding::new(secrets);
+    assert!(matches!(binding.secret_for(primary()), Ok(None)));
+    assert!(matches!(binding.secret_for(backup()), Ok(None)));
+}
+
+#[test]
+fn debug_output_omits_secrets() {
+    let mut secrets = PeerSecrets::new();
+    secrets.insert(primary(), "hunter2");
+
+    let output = format!("{secrets:?}");
+    assert!(output.contains("192.0.2.1:49"));
+    assert!(!output.contains("hunter2"));
+}
+
+/// A connection that reports EOF once its replies have been read, so the client reconnects for the next session.
+struct ClosingConnection<S> {
+    inner: S,
+    replied: bool,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for ClosingConnection<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match Pin::new(&mut self.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(length)) => {
+                self.replied = true;
+                Poll::Ready(Ok(length))
+            }
+            Poll::Pending if self.replied => Poll::Ready(Ok(0)),
+            other => other,
+        }
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for ClosingConnection<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[tokio::test]
+async fn unexpected_peer_rejected_before_sending() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    // the first connection goes to the primary server, and every other one fails over to the backup
+    let client = ClientBuilder::new().build_with_peers(Box::new(move || {
+        let peer = match connections.fetch_add(1, Ordering::Relaxed) {
+            0 => primary(),
+            _ => backup(),
+        };
+        let connection = ClosingConnection {
+            inner: AcceptingServer::recording(server_received.clone(), false),
+            replied: false,
+        };
+
+        Box::pin(async move { Ok((connection, peer)) })
+    }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let _ = client
+        .authorize(context.clone(), [])
+        .await
+        .expect("authorization with the primary server should succeed");
+
+    let error = client
+        .authorize(context, [])
+        .await
+        .expect_err("authorization with the backup server should be refused");
+    assert!(matches!(
+        error,
+        ClientError::UnexpectedPeer { peer, bound } if peer == backup() && bound == primary()
+    ));
+
+    // nothing was sent to the backup server
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,160 @@
+//! Argument bundles for common kinds of accounting records, with the argument names & value formats from
+//! [RFC8907 section 8](https://www.rfc-editor.org/rfc/rfc8907.html#name-authorization-and-accountin).
+//!
+//! Each preset only covers the arguments specific to its kind of record; the task id & timestamps are added by
+//! [`AccountingTask`](super::AccountingTask) as usual, and the user, port & remote address are taken from the
+//! [`SessionContext`](super::SessionContext). More arguments can be pushed onto the returned vectors as needed.
+//!
+//! # Examples
+//!
+//! ```
+//! use tacacs_plus::presets;
+//!
+//! let arguments = presets::shell_command("show", &["running-config", "interface"]).unwrap();
+//! let encoded: Vec<_> = arguments.iter().map(ToString::to_string).collect();
+//!
+//! assert_eq!(
+//!     encoded,
+//!     ["service=shell", "cmd=show", "cmd-arg=running-config", "cmd-arg=interface"]
+//! );
+//! ```
+
+use std::time::Duration;
+
+use tacacs_plus_protocol::{Argument, FieldText, InvalidArgument};
+
+#[cfg(test)]
+mod tests;
+
+/// The protocol value used for 802.1X port authentication sessions.
+const DOT1X_PROTOCOL: &str = "dot1x";
+
+/// Counters of the traffic of a network session, reported in the stop (or watchdog) records of a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SessionUsage {
+    /// The number of bytes received from the user (`bytes_in`).
+    pub bytes_in: u64,
+
+    /// The number of bytes sent to the user (`bytes_out`).
+    pub bytes_out: u64,
+
+    /// The number of packets received from the user (`paks_in`).
+    pub packets_in: u64,
+
+    /// The number of packets sent to the user (`paks_out`).
+    pub packets_out: u64,
+
+    /// How long the session has lasted so far (`elapsed_time`), sent in whole seconds.
+    pub elapsed: Duration,
+}
+
+/// Arguments for accounting a shell command: `service=shell`, the command as `cmd` and each of its arguments as a
+/// separate `cmd-arg`, in order.
+///
+/// An error is returned if the command or one of its arguments isn't printable ASCII or is too long to encode.
+pub fn shell_command(
+    command: &str,
+    arguments: &[&str],
+) -> Result<Vec<Argument<'static>>, InvalidArgument> {
+    let mut bundle = Vec::with_capacity(2 + arguments.len());
+    bundle.push(argument("service", "shell")?);
+    bundle.push(argument("cmd", command)?);
+
+    for value in arguments {
+        bundle.push(argument("cmd-arg", value)?);
+    }
+
+    Ok(bundle)
+}
+
+/// Arguments for accounting an 802.1X port authentication session: `service=network` & `protocol=dot1x`, followed
+/// by the traffic counters of the session if provided.
+///
+/// The port & MAC address of the supplicant are sent in the packet header rather than as arguments, so they should be
+/// set via [`ContextBuilder::port()`](super::ContextBuilder::port) and
+/// [`ContextBuilder::remote_address()`](super::ContextBuilder::remote_address) instead.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use tacacs_plus::presets::{self, SessionUsage};
+///
+/// let usage = SessionUsage {
+///     bytes_in: 2048,
+///     bytes_out: 1024,
+///     packets_in: 20,
+///     packets_out: 10,
+///     elapsed: Duration::from_secs(90),
+/// };
+///
+/// // start records don't have any counters yet
+/// assert_eq!(presets::dot1x_session(None).len(), 2);
+///
+/// let encoded: Vec<_> = presets::dot1x_session(Some(&usage)).iter().map(ToString::to_string).collect();
+/// assert_eq!(
+///     encoded,
+///     [
+///         "service=network",
+///         "protocol=dot1x",
+///         "bytes_in=2048",
+///         "bytes_out=1024",
+///         "paks_in=20",
+///         "paks_out=10",
+///         "elapsed_time=90",
+///     ]
+/// );
+/// ```
+pub fn dot1x_session(usage: Option<&SessionUsage>) -> Vec<Argument<'static>> {
+    let mut bundle = vec![
+        // SAFETY: the names & values are hardcoded, and known to be valid ASCII short enough to encode
+        argument("service", "network").unwrap(),
+        argument("protocol", DOT1X_PROTOCOL).unwrap(),
+    ];
+
+    if let Some(usage) = usage {
+        let counters = [
+            ("bytes_in", usage.bytes_in),
+            ("bytes_out", usage.bytes_out),
+            ("paks_in", usage.packets_in),
+            ("paks_out", usage.packets_out),
+            ("elapsed_time", usage.elapsed.as_secs()),
+        ];
+
+        // SAFETY: a decimal u64 is at most 20 characters, so these always fit in an argument
+        bundle.extend(
+            counters
+                .into_iter()
+                .map(|(name, value)| argument(name, &value.to_string()).unwrap()),
+        );
+    }
+
+    bundle
+}
+
+/// Arguments for auditing a call to a management API: `service=api`, the request method as `cmd`, the request path
+/// as `cmd-arg` and the response status code as `status`.
+///
+/// An error is returned if the method or path isn't printable ASCII or is too long to encode; query strings with
+/// percent-encoded values are fine as is.
+pub fn api_call(
+    method: &str,
+    path: &str,
+    status: u16,
+) -> Result<Vec<Argument<'static>>, InvalidArgument> {
+    Ok(vec![
+        argument("service", "api")?,
+        argument("cmd", method)?,
+        argument("cmd-arg", path)?,
+        argument("status", &status.to_string())?,
+    ])
+}
+
+/// Builds a mandatory argument with an owned value.
+fn argument(name: &'static str, value: &str) -> Result<Argument<'static>, InvalidArgument> {
+    let value = FieldText::try_from(value.to_owned()).map_err(|_| InvalidArgument::BadText)?;
+
+    // SAFETY: argument names are hardcoded & known to be valid ASCII
+    Argument::new(FieldText::try_from(name).unwrap(), value, true)
+}
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use tacacs_plus_protocol::{Argument, InvalidArgument};
+
+use super::{api_call, dot1x_session, shell_command, SessionUsage};
+
+fn encoded(arguments: &[Argument<'_>]) -> Vec<String> {
+    arguments.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn shell_command_without_arguments() {
+    let arguments = shell_command("reload", &[]).unwrap();
+    assert_eq!(encoded(&arguments), ["service=shell", "cmd=reload"]);
+}
+
+#[test]
+fn invalid_command_rejected() {
+    assert_eq!(
+        shell_command("show", &["caf\u{e9}"]),
+        Err(InvalidArgument::BadText)
+    );
+    assert_eq!(
+        shell_command(&"x".repeat(256), &[]),
+        Err(InvalidArgument::TooLong)
+    );
+}
+
+#[test]
+fn dot1x_elapsed_time_truncated_to_seconds() {
+    let usage = SessionUsage {
+        elapsed: Duration::from_millis(61_999),
+        ..SessionUsage::default()
+    };
+
+    let arguments = dot1x_session(Some(&usage));
+    assert_eq!(
+        encoded(&arguments).last().map(String::as_str),
+        Some("elapsed_time=61")
+    );
+}
+
+#[test]
+fn api_call_arguments() {
+    let arguments = api_call("DELETE", "/v1/users/alice?force=true", 204).unwrap();
+    assert_eq!(
+        encoded(&arguments),
+        [
+            "service=api",
+            "cmd=DELETE",
+            "cmd-arg=/v1/users/alice?force=true",
+            "status=204"
+        ]
+    );
+}
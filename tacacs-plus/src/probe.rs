@@ -0,0 +1,51 @@
+//! Lightweight health checks against a server.
+
+use std::time::Duration;
+
+use tacacs_plus_protocol::{Argument, FieldText};
+
+use super::{ResponseStatus, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+/// How [`Client::probe()`](super::Client::probe) checks on a server.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub enum ProbeMethod {
+    /// Opens a separate connection and closes it again without starting a session.
+    ///
+    /// This only shows that the server accepts connections, not that it shares the client's secret key.
+    Connect,
+
+    /// Sends an authorization request for the provided context, with a single `service=probe` argument.
+    ///
+    /// Any decision from the server shows that it's reachable and shares the client's secret key, even if the request
+    /// is denied; a mismatched key generally results in a [`ClientError::InvalidPacketReceived`](super::ClientError::InvalidPacketReceived)
+    /// instead, since the reply can't be deobfuscated properly.
+    Authorization(SessionContext),
+}
+
+impl ProbeMethod {
+    /// The argument sent in authorization probes.
+    pub(super) fn argument() -> Argument<'static> {
+        // SAFETY: the argument is hardcoded & known to be valid
+        Argument::new(
+            FieldText::try_from("service").unwrap(),
+            FieldText::try_from("probe").unwrap(),
+            true,
+        )
+        .unwrap()
+    }
+}
+
+/// The outcome of a successful [`Client::probe()`](super::Client::probe).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProbeReport {
+    /// How long the probe took, from starting to connect or sending a request to closing the connection or receiving a reply.
+    pub latency: Duration,
+
+    /// The decision made by the server for an authorization probe, or `None` for a connection probe.
+    pub status: Option<ResponseStatus>,
+}
@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tacacs_plus_protocol::PacketType;
+
+use super::ProbeMethod;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{Client, ClientBuilder, ContextBuilder, ResponseStatus};
+
+fn client() -> (Client<AcceptingServer>, ReceivedRequests, Arc<AtomicUsize>) {
+    let received = ReceivedRequests::default();
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let server_received = received.clone();
+    let server_connections = connections.clone();
+    let client = ClientBuilder::new().build(Box::new(move || {
+        server_connections.fetch_add(1, Ordering::SeqCst);
+        let server = AcceptingServer::recording(server_received.clone(), false);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    (client, received, connections)
+}
+
+#[tokio::test]
+async fn connection_probe_opens_separate_connection() {
+    let (client, received, connections) = client();
+
+    let report = client
+        .probe(ProbeMethod::Connect)
+        .await
+        .expect("probe should succeed");
+    assert_eq!(report.status, None);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    // no session should have been started over the probed connection
+    assert!(received.lock().unwrap().is_empty());
+    assert_eq!(client.snapshot.connections_opened(), 0);
+}
+
+#[tokio::test]
+async fn authorization_probe_reports_decision() {
+    let (client, received, _) = client();
+
+    let context = ContextBuilder::new(String::from("monitor"))
+        .build()
+        .unwrap();
+    let report = client
+        .probe(ProbeMethod::Authorization(context))
+        .await
+        .expect("probe should succeed");
+    assert_eq!(report.status, Some(ResponseStatus::Success));
+
+    let received = received.lock().unwrap();
+    let [(PacketType::Authorization, body)] = &received[..] else {
+        panic!("expected a single authorization request, got {received:?}");
+    };
+    assert!(body.ends_with(b"service=probe"));
+}
@@ -0,0 +1,150 @@
+//! Line identifiers for session contexts, detected from the metadata of the running process.
+
+use std::env;
+use std::path::Path;
+
+use tacacs_plus_protocol::FieldText;
+
+#[cfg(test)]
+mod tests;
+
+/// Metadata about the running process, used to fill in the port & remote address of a [`SessionContext`](super::SessionContext)
+/// via [`ContextBuilder::process_metadata()`](super::ContextBuilder::process_metadata).
+///
+/// Network devices typically identify a session by the line a user is connected on (e.g. `tty0` or `vty1`), which servers
+/// may use in their policies or logs. For a process on a general-purpose host, the closest equivalents are:
+///
+/// - port: the name of the terminal the process is attached to (e.g. `pts/3`), or the name of the process if it isn't
+///   attached to one
+/// - remote address: the hostname of the system
+///
+/// Values detected via [`detect()`](Self::detect) can be overridden by setting the fields directly.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{ContextBuilder, ProcessMetadata};
+///
+/// let mut metadata = ProcessMetadata::detect();
+///
+/// // report the service name instead of the executable name when not attached to a terminal
+/// metadata.process_name = Some(String::from("backup-agent"));
+///
+/// let context = ContextBuilder::new(String::from("backup"))
+///     .process_metadata(metadata)
+///     .build().unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ProcessMetadata {
+    /// The hostname of the system the process is running on.
+    pub hostname: Option<String>,
+
+    /// The name of the terminal the process is attached to, relative to `/dev` (e.g. `pts/3` or `ttyS0`).
+    pub tty: Option<String>,
+
+    /// The name of the running process, i.e. the file name of its executable.
+    pub process_name: Option<String>,
+}
+
+impl ProcessMetadata {
+    /// Detects the hostname, terminal name & process name of the running process.
+    ///
+    /// The terminal is the first of standard input, output and error that is attached to one, and is only detected on
+    /// Unix-like systems. Values that can't be determined or wouldn't be valid in a packet are left as `None`.
+    pub fn detect() -> Self {
+        Self {
+            hostname: hostname().filter(|name| is_valid_field(name)),
+            tty: terminal_name().filter(|name| is_valid_field(name)),
+            process_name: process_name().filter(|name| is_valid_field(name)),
+        }
+    }
+
+    /// The port to report for the process, if known.
+    pub(super) fn port(&self) -> Option<&str> {
+        self.tty.as_deref().or(self.process_name.as_deref())
+    }
+
+    /// The remote address to report for the process, if known.
+    pub(super) fn remote_address(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+}
+
+/// Checks whether a detected value can be sent as the port or remote address of a packet.
+fn is_valid_field(value: &str) -> bool {
+    !value.is_empty() && value.len() <= u8::MAX as usize && FieldText::try_from(value).is_ok()
+}
+
+/// Converts a path to a terminal device into a line name, e.g. `/dev/pts/3` -> `pts/3`.
+fn line_name(path: &str) -> &str {
+    path.strip_prefix("/dev/").unwrap_or(path)
+}
+
+/// Gets the name of the terminal the process is attached to, if any.
+#[cfg(unix)]
+fn terminal_name() -> Option<String> {
+    use std::ffi::CStr;
+
+    [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO]
+        .into_iter()
+        .find_map(|descriptor| {
+            // SAFETY: isatty only inspects the file descriptor, and reports an error if it isn't open
+            if unsafe { libc::isatty(descriptor) } != 1 {
+                return None;
+            }
+
+            let mut buffer = [0u8; 256];
+
+            // SAFETY: the buffer is valid for writes of the length passed along with it, and ttyname_r
+            // reports an error instead of writing a truncated name
+            let result =
+                unsafe { libc::ttyname_r(descriptor, buffer.as_mut_ptr().cast(), buffer.len()) };
+            if result != 0 {
+                return None;
+            }
+
+            let path = CStr::from_bytes_until_nul(&buffer).ok()?.to_str().ok()?;
+            Some(line_name(path).to_owned())
+        })
+}
+
+#[cfg(not(unix))]
+fn terminal_name() -> Option<String> {
+    None
+}
+
+/// Gets the hostname of the system.
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buffer = [0u8; 256];
+
+    // SAFETY: the buffer is valid for writes of the length passed along with it
+    if unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) } != 0 {
+        return None;
+    }
+
+    // a truncated hostname isn't guaranteed to be null-terminated, in which case it's discarded
+    let name = CStr::from_bytes_until_nul(&buffer).ok()?.to_str().ok()?;
+    Some(name.to_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+/// Gets the name of the running process from its executable, falling back to the first command line argument.
+fn process_name() -> Option<String> {
+    let executable = env::current_exe()
+        .ok()
+        .or_else(|| env::args_os().next().map(Into::into))?;
+
+    executable_name(&executable)
+}
+
+/// Extracts the name of a process from the path to its executable, without any extension (e.g. `.exe`).
+fn executable_name(path: &Path) -> Option<String> {
+    path.file_stem()?.to_str().map(str::to_owned)
+}
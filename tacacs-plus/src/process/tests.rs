@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use super::{executable_name, is_valid_field, line_name, ProcessMetadata};
+use crate::ContextBuilder;
+
+fn metadata(tty: Option<&str>) -> ProcessMetadata {
+    ProcessMetadata {
+        hostname: Some(String::from("router1")),
+        tty: tty.map(str::to_owned),
+        process_name: Some(String::from("tacacs-cli")),
+    }
+}
+
+#[test]
+fn terminal_preferred_over_process_name() {
+    let context = ContextBuilder::new(String::from("user"))
+        .process_metadata(metadata(Some("pts/3")))
+        .build()
+        .unwrap();
+    assert_eq!(context.port, "pts/3");
+    assert_eq!(context.remote_address, "router1");
+
+    let context = ContextBuilder::new(String::from("user"))
+        .process_metadata(metadata(None))
+        .build()
+        .unwrap();
+    assert_eq!(context.port, "tacacs-cli");
+}
+
+#[test]
+fn explicit_values_override_detected_ones() {
+    // the order of the builder calls doesn't matter
+    let context = ContextBuilder::new(String::from("user"))
+        .port(String::from("vty1"))
+        .process_metadata(metadata(Some("pts/3")))
+        .remote_address(String::from("192.0.2.1"))
+        .build()
+        .unwrap();
+
+    assert_eq!(context.port, "vty1");
+    assert_eq!(context.remote_address, "192.0.2.1");
+}
+
+#[test]
+fn fallbacks_used_for_undetected_values() {
+    let context = ContextBuilder::new(String::from("user"))
+        .process_metadata(ProcessMetadata::default())
+        .build()
+        .unwrap();
+
+    assert_eq!(context.port, "rust_client");
+    assert_eq!(context.remote_address, "tacacs_plus_rs");
+}
+
+#[test]
+fn names_extracted_from_paths() {
+    assert_eq!(line_name("/dev/pts/3"), "pts/3");
+    assert_eq!(line_name("/dev/ttyS0"), "ttyS0");
+    assert_eq!(line_name("console"), "console");
+
+    assert_eq!(
+        executable_name(Path::new("/usr/local/bin/tacacs-cli")),
+        Some(String::from("tacacs-cli"))
+    );
+    assert_eq!(
+        executable_name(Path::new("tacacs-cli.exe")),
+        Some(String::from("tacacs-cli"))
+    );
+}
+
+#[test]
+fn detected_values_are_valid_fields() {
+    let metadata = ProcessMetadata::detect();
+
+    for value in [&metadata.hostname, &metadata.tty, &metadata.process_name]
+        .into_iter()
+        .flatten()
+    {
+        assert!(is_valid_field(value), "{value:?} is not a valid field");
+    }
+
+    assert!(!is_valid_field(""));
+    assert!(!is_valid_field("bad\nhost"));
+    assert!(!is_valid_field(&"a".repeat(256)));
+}
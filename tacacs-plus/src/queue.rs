@@ -0,0 +1,281 @@
+//! Scheduling of the sessions waiting to use the connection of a client.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use futures::lock::{Mutex as AsyncMutex, MutexGuard};
+
+use super::stats::QueueDepth;
+use super::OperationKind;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of sessions in a row that may be let through ahead of an older session with a lower priority, after
+/// which the oldest waiting session goes next regardless of its priority.
+const MAX_BYPASSES: u32 = 8;
+
+/// How urgently a session should get the connection when several sessions are waiting for it.
+///
+/// Sessions are performed one at a time over the connection of a client (or at least start one at a time, for a
+/// [full duplex](super::ClientBuilder::full_duplex) client), so when several are waiting, those with a
+/// higher priority go first, and those with the same priority go in the order they started waiting. To keep a steady
+/// stream of higher-priority sessions from starving lower-priority ones, the oldest waiting session goes next after a
+/// few sessions in a row have skipped ahead of it.
+///
+/// Each operation has a default priority based on its kind (see [`default_for()`](Self::default_for)), which can
+/// be overridden for a context via [`ContextBuilder::priority()`](super::ContextBuilder::priority), e.g. for command
+/// authorization that a user is waiting on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SessionPriority {
+    /// A user is waiting on the result, e.g. at a login prompt.
+    Interactive,
+
+    /// The result is waited on, but not necessarily by a user.
+    Standard,
+
+    /// Nothing is waiting on the result, e.g. for records that are only logged.
+    Background,
+}
+
+impl SessionPriority {
+    /// The default priority of an operation: [`Interactive`](Self::Interactive) for authentication,
+    /// [`Standard`](Self::Standard) for authorization and [`Background`](Self::Background) for accounting.
+    pub fn default_for(operation: OperationKind) -> Self {
+        match operation {
+            OperationKind::Authentication => Self::Interactive,
+            OperationKind::Authorization => Self::Standard,
+            OperationKind::Accounting => Self::Background,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Interactive => 0,
+            Self::Standard => 1,
+            Self::Background => 2,
+        }
+    }
+}
+
+/// A session waiting for its turn.
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    waker: Waker,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+    /// Whether the connection is currently in use by (or has been handed over to) a session.
+    busy: bool,
+
+    /// The ticket of a waiting session the connection was handed over to, but which hasn't been polled since.
+    granted: Option<u64>,
+
+    /// The ticket handed out to the next session that has to wait, which orders sessions by when they started waiting.
+    next_ticket: u64,
+
+    /// The waiting sessions for each priority, from highest to lowest, in the order they started waiting.
+    waiting: [VecDeque<Waiter>; 3],
+
+    /// The number of sessions in a row that were let through ahead of an older, lower-priority session.
+    bypasses: u32,
+}
+
+impl QueueState {
+    /// Hands the connection over to the next waiting session, or marks it as free if there are none.
+    fn hand_over(&mut self) {
+        let Some(highest) = self.waiting.iter().position(|queue| !queue.is_empty()) else {
+            self.busy = false;
+            self.bypasses = 0;
+            return;
+        };
+
+        // SAFETY: at least one queue is nonempty, as checked above
+        let (_, oldest) = self
+            .waiting
+            .iter()
+            .enumerate()
+            .filter_map(|(index, queue)| queue.front().map(|waiter| (waiter.ticket, index)))
+            .min()
+            .unwrap();
+
+        let next = if oldest == highest {
+            self.bypasses = 0;
+            highest
+        } else if self.bypasses >= MAX_BYPASSES {
+            self.bypasses = 0;
+            oldest
+        } else {
+            self.bypasses += 1;
+            highest
+        };
+
+        // SAFETY: the chosen queue is one of the nonempty ones found above
+        let waiter = self.waiting[next].pop_front().unwrap();
+        self.granted = Some(waiter.ticket);
+        waiter.waker.wake();
+    }
+}
+
+/// The queue of sessions waiting to use the connection of a client, shared between its clones.
+#[derive(Debug, Default)]
+pub(crate) struct SessionQueue {
+    state: Mutex<QueueState>,
+}
+
+impl SessionQueue {
+    fn state(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        // the state is never left inconsistent while locked, so poisoning can be ignored
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for a session's turn to use the connection.
+    pub(crate) fn acquire(&self, priority: SessionPriority) -> Acquire<'_> {
+        Acquire {
+            queue: self,
+            priority,
+            ticket: None,
+            acquired: false,
+        }
+    }
+
+    /// Waits for a session's turn, and then locks the connection for it.
+    pub(crate) async fn lock<'a, T>(
+        &'a self,
+        mutex: &'a AsyncMutex<T>,
+        priority: SessionPriority,
+    ) -> SessionGuard<'a, T> {
+        let permit = self.acquire(priority).await;
+
+        // only sessions holding a permit lock the connection, so this doesn't actually wait
+        SessionGuard {
+            guard: mutex.lock().await,
+            _permit: permit,
+        }
+    }
+
+    /// The number of sessions currently waiting for each priority.
+    pub(crate) fn depth(&self) -> QueueDepth {
+        let state = self.state();
+        let depth = |priority: SessionPriority| state.waiting[priority.index()].len() as u64;
+
+        QueueDepth {
+            interactive: depth(SessionPriority::Interactive),
+            standard: depth(SessionPriority::Standard),
+            background: depth(SessionPriority::Background),
+        }
+    }
+}
+
+/// A future that resolves once it's a session's turn to use the connection.
+#[derive(Debug)]
+pub(crate) struct Acquire<'queue> {
+    queue: &'queue SessionQueue,
+    priority: SessionPriority,
+    ticket: Option<u64>,
+    acquired: bool,
+}
+
+impl<'queue> Future for Acquire<'queue> {
+    type Output = SessionPermit<'queue>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let mut state = this.queue.state();
+
+        let ready = match this.ticket {
+            None if !state.busy => {
+                state.busy = true;
+                true
+            }
+            None => {
+                let ticket = state.next_ticket;
+                state.next_ticket += 1;
+                state.waiting[this.priority.index()].push_back(Waiter {
+                    ticket,
+                    waker: cx.waker().clone(),
+                });
+
+                this.ticket = Some(ticket);
+                false
+            }
+            Some(ticket) if state.granted == Some(ticket) => {
+                state.granted = None;
+                true
+            }
+            Some(ticket) => {
+                // the task polling this future may have changed since it started waiting
+                if let Some(waiter) = state.waiting[this.priority.index()]
+                    .iter_mut()
+                    .find(|waiter| waiter.ticket == ticket)
+                {
+                    waiter.waker.clone_from(cx.waker());
+                }
+                false
+            }
+        };
+
+        if ready {
+            this.acquired = true;
+            Poll::Ready(SessionPermit { queue: this.queue })
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(ticket) = self.ticket.filter(|_| !self.acquired) else {
+            return;
+        };
+
+        let mut state = self.queue.state();
+        if state.granted == Some(ticket) {
+            // the connection was handed over to this session, but it was cancelled before it noticed
+            state.granted = None;
+            state.hand_over();
+        } else {
+            state.waiting[self.priority.index()].retain(|waiter| waiter.ticket != ticket);
+        }
+    }
+}
+
+/// A session's turn to use the connection, which is handed over to the next waiting session when dropped.
+#[derive(Debug)]
+pub(crate) struct SessionPermit<'queue> {
+    queue: &'queue SessionQueue,
+}
+
+impl Drop for SessionPermit<'_> {
+    fn drop(&mut self) {
+        self.queue.state().hand_over();
+    }
+}
+
+/// The connection of a client, locked for a session whose turn it is.
+pub(crate) struct SessionGuard<'a, T> {
+    // the connection is unlocked before the permit is dropped, since fields are dropped in declaration order
+    guard: MutexGuard<'a, T>,
+    _permit: SessionPermit<'a>,
+}
+
+impl<T> Deref for SessionGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for SessionGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
@@ -0,0 +1,193 @@
+use std::task::Context;
+
+use futures::task::noop_waker_ref;
+use futures::FutureExt;
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{ClientBuilder, ContextBuilder};
+use tacacs_plus_protocol::PacketType;
+
+/// Polls a waiting session once, returning its permit if it's that session's turn.
+fn poll<'queue>(acquire: &mut Acquire<'queue>) -> Option<SessionPermit<'queue>> {
+    match acquire.poll_unpin(&mut Context::from_waker(noop_waker_ref())) {
+        Poll::Ready(permit) => Some(permit),
+        Poll::Pending => None,
+    }
+}
+
+#[test]
+fn higher_priority_sessions_go_first() {
+    let queue = SessionQueue::default();
+    let mut first = queue.acquire(SessionPriority::Background);
+    let permit = poll(&mut first).expect("free queue should be acquired immediately");
+
+    let mut background = queue.acquire(SessionPriority::Background);
+    let mut standard = queue.acquire(SessionPriority::Standard);
+    let mut interactive = queue.acquire(SessionPriority::Interactive);
+    assert!(poll(&mut background).is_none());
+    assert!(poll(&mut standard).is_none());
+    assert!(poll(&mut interactive).is_none());
+
+    drop(permit);
+    assert!(poll(&mut background).is_none());
+    assert!(poll(&mut standard).is_none());
+    let permit = poll(&mut interactive).expect("interactive session should go first");
+
+    drop(permit);
+    assert!(poll(&mut background).is_none());
+    let permit = poll(&mut standard).expect("standard session should go next");
+
+    drop(permit);
+    drop(poll(&mut background).expect("background session should go last"));
+
+    // the connection is free again
+    assert!(poll(&mut queue.acquire(SessionPriority::Background)).is_some());
+}
+
+#[test]
+fn sessions_with_same_priority_go_in_order() {
+    let queue = SessionQueue::default();
+    let permit = poll(&mut queue.acquire(SessionPriority::Standard)).unwrap();
+
+    let mut waiting: Vec<_> = (0..3)
+        .map(|_| queue.acquire(SessionPriority::Standard))
+        .collect();
+    for acquire in &mut waiting {
+        assert!(poll(acquire).is_none());
+    }
+
+    drop(permit);
+    for index in 0..waiting.len() {
+        for later in &mut waiting[index + 1..] {
+            assert!(poll(later).is_none());
+        }
+
+        drop(poll(&mut waiting[index]).expect("oldest session should go next"));
+    }
+}
+
+#[test]
+fn lower_priority_sessions_not_starved() {
+    let queue = SessionQueue::default();
+    let mut permit = poll(&mut queue.acquire(SessionPriority::Interactive)).unwrap();
+
+    let mut background = queue.acquire(SessionPriority::Background);
+    assert!(poll(&mut background).is_none());
+
+    // a steady stream of interactive sessions only skips ahead of the background session a limited number of times
+    for _ in 0..MAX_BYPASSES {
+        let mut interactive = queue.acquire(SessionPriority::Interactive);
+        assert!(poll(&mut interactive).is_none());
+
+        drop(permit);
+        assert!(poll(&mut background).is_none());
+        permit = poll(&mut interactive).expect("interactive session should skip ahead");
+    }
+
+    let mut interactive = queue.acquire(SessionPriority::Interactive);
+    assert!(poll(&mut interactive).is_none());
+
+    drop(permit);
+    assert!(poll(&mut interactive).is_none());
+    let permit = poll(&mut background).expect("background session should get its turn eventually");
+
+    drop(permit);
+    assert!(poll(&mut interactive).is_some());
+}
+
+#[test]
+fn cancelled_sessions_leave_queue() {
+    let queue = SessionQueue::default();
+    let permit = poll(&mut queue.acquire(SessionPriority::Standard)).unwrap();
+
+    let mut cancelled = queue.acquire(SessionPriority::Interactive);
+    let mut granted_then_cancelled = queue.acquire(SessionPriority::Interactive);
+    let mut waiting = queue.acquire(SessionPriority::Background);
+    assert!(poll(&mut cancelled).is_none());
+    assert!(poll(&mut granted_then_cancelled).is_none());
+    assert!(poll(&mut waiting).is_none());
+
+    assert_eq!(
+        queue.depth(),
+        QueueDepth {
+            interactive: 2,
+            standard: 0,
+            background: 1
+        }
+    );
+
+    drop(cancelled);
+    assert_eq!(queue.depth().interactive, 1);
+
+    // the connection is handed over to a session that's cancelled before noticing, so it's passed on to the next one
+    drop(permit);
+    drop(granted_then_cancelled);
+    assert!(poll(&mut waiting).is_some());
+    assert_eq!(queue.depth().total(), 0);
+}
+
+#[tokio::test]
+async fn queued_sessions_reported_in_stats() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), false);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    // hold the connection as if a session were in progress, so new sessions have to wait
+    let permit = client.queue.acquire(SessionPriority::Standard).await;
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let interactive_context = ContextBuilder::new(String::from("someuser"))
+        .priority(SessionPriority::Interactive)
+        .build()
+        .unwrap();
+
+    let accounting = tokio::spawn({
+        let client = client.clone();
+        async move { client.account_begin(context, []).await.map(|_| ()) }
+    });
+    let authorization = tokio::spawn({
+        let client = client.clone();
+        async move {
+            client
+                .authorize(interactive_context, [])
+                .await
+                .map(|response| response.status)
+        }
+    });
+
+    while client.stats().queued_sessions.total() < 2 {
+        tokio::task::yield_now().await;
+    }
+    assert_eq!(
+        client.stats().queued_sessions,
+        QueueDepth {
+            interactive: 1,
+            standard: 0,
+            background: 1
+        }
+    );
+
+    drop(permit);
+    authorization.await.unwrap().unwrap();
+    accounting.await.unwrap().unwrap();
+    assert_eq!(client.stats().queued_sessions.total(), 0);
+
+    // the authorization request was sent first, since its context overrides its priority
+    let received: Vec<_> = received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(packet_type, _)| *packet_type)
+        .collect();
+    assert_eq!(
+        received,
+        [PacketType::Authorization, PacketType::Accounting]
+    );
+}
@@ -1,7 +1,25 @@
+use std::time::SystemTime;
+
 use tacacs_plus_protocol::Argument;
-use tacacs_plus_protocol::{authentication, authorization};
+use tacacs_plus_protocol::{accounting, authentication, authorization};
+use tacacs_plus_protocol::{HeaderInfo, PacketFlags};
+
+use super::ArgumentDiff;
 
 /// The final status returned by a server during a TACACS+ session.
+///
+/// This is a simplified view of the status in the server's final reply, which is also available as the `raw_status` field
+/// of each response. The reply statuses are mapped as follows:
+///
+/// | Operation      | [`Success`](Self::Success) | [`Failure`](Self::Failure)  | Error                                           |
+/// |----------------|----------------------------|-----------------------------|-------------------------------------------------|
+/// | Authentication | `Pass`                     | `Fail`, `Restart`, `Follow` | `Error`, `GetData`, `GetUser`, `GetPassword`, `Unknown` |
+/// | Authorization  | `PassAdd`, `PassReplace`   | `Fail`, `Follow`            | `Error`, `Unknown`                              |
+/// | Accounting     | `Success`                  |                             | `Error`, `Follow`, `Unknown`                    |
+///
+/// Statuses in the last column are reported as a [`ClientError`](super::ClientError) instead of a response. The same
+/// mapping is available via [`from_authentication()`](Self::from_authentication),
+/// [`from_authorization()`](Self::from_authorization) & [`from_accounting()`](Self::from_accounting).
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum ResponseStatus {
     /// The operation succeeded.
@@ -10,6 +28,52 @@ pub enum ResponseStatus {
     Failure,
 }
 
+impl ResponseStatus {
+    /// Simplifies an authentication reply status, returning `None` if the client treats it as an error.
+    pub fn from_authentication(status: authentication::Status) -> Option<Self> {
+        Self::try_from(status).ok()
+    }
+
+    /// Simplifies an authorization reply status, returning `None` if the client treats it as an error.
+    pub fn from_authorization(status: authorization::Status) -> Option<Self> {
+        Self::try_from(status).ok()
+    }
+
+    /// Simplifies an accounting reply status, returning `None` if the client treats it as an error.
+    pub fn from_accounting(status: accounting::Status) -> Option<Self> {
+        match status {
+            accounting::Status::Success => Some(Self::Success),
+            _ => None,
+        }
+    }
+}
+
+/// Information about the TACACS+ session in which a response was received.
+///
+/// This can be used to correlate client-side logs with server-side logs or packet captures.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct SessionInfo {
+    /// The randomly generated ID of the session.
+    pub session_id: u32,
+
+    /// The sequence number of the final packet in the session, i.e. the one sent by the server.
+    pub final_sequence_number: u8,
+
+    /// The header flags set by the server in its final reply.
+    pub flags: PacketFlags,
+}
+
+impl SessionInfo {
+    /// Extracts session information from the header of the final packet in a session.
+    pub(super) fn from_header(header: &HeaderInfo) -> Self {
+        Self {
+            session_id: header.session_id(),
+            final_sequence_number: header.sequence_number(),
+            flags: header.flags(),
+        }
+    }
+}
+
 /// A server response from an authentication session.
 #[must_use = "Authentication failure is not reported as an error, so the status field must be checked."]
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
@@ -17,38 +81,113 @@ pub struct AuthenticationResponse {
     /// Whether the authentication attempt passed or failed.
     pub status: ResponseStatus,
 
+    /// The status from the server's final reply, which [`status`](Self::status) is derived from.
+    ///
+    /// See [`ResponseStatus`] for how the two relate.
+    pub raw_status: authentication::Status,
+
     /// The message returned by the server, intended to be displayed to the user.
     pub user_message: String,
 
     /// Extra data returned by the server.
     pub data: Vec<u8>,
+
+    /// Whether the server asked for authentication to be restarted, which is reported as a failure.
+    ///
+    /// This is only set if the restart wasn't retried automatically; see [`Client::authenticate()`](super::Client::authenticate).
+    pub restart_requested: bool,
+
+    /// Information about the session this response was received in.
+    pub session: SessionInfo,
 }
 
 /// A TACACS+ server response from an authorization session.
+///
+/// The arguments of a response borrow from the arguments sent in the request (with the lifetime `'args`) rather than
+/// being copied; [`into_owned()`](Self::into_owned) can be used to keep a response around longer than those.
 #[must_use = "The status of the response should be checked, since a failure is not reported as an error."]
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
-pub struct AuthorizationResponse {
+pub struct AuthorizationResponse<'args> {
     /// Whether the authorization attempt succeeded.
     pub status: ResponseStatus,
 
-    /// The arguments returned from the server, if any.
-    pub arguments: Vec<Argument<'static>>,
+    /// The status from the server's final reply, which [`status`](Self::status) is derived from.
+    ///
+    /// This distinguishes whether the server added to ([`PassAdd`](authorization::Status::PassAdd)) or replaced
+    /// ([`PassReplace`](authorization::Status::PassReplace)) the requested arguments, for example.
+    pub raw_status: authorization::Status,
+
+    /// The authorized arguments, i.e. the sent arguments merged with those returned from the server.
+    ///
+    /// If the request was denied, this only holds the arguments returned from the server instead, since none of the
+    /// sent arguments were authorized. Some servers include the reason for a denial here, e.g. as
+    /// `reason=command-not-permitted`.
+    pub arguments: Vec<Argument<'args>>,
+
+    /// How the server changed the sent arguments, which can't be told from the merged [`arguments`](Self::arguments) alone.
+    pub changes: ArgumentDiff<'args>,
 
     /// A message that may be presented to a user connected to this client. (`server_msg` from RFC8907)
     pub user_message: String,
 
     /// Administrative console message from the server. (`data` from RFC8907)
     pub admin_message: String,
+
+    /// Information about the session this response was received in.
+    pub session: SessionInfo,
+}
+
+impl AuthorizationResponse<'_> {
+    /// Converts this response to one that owns all of its arguments, e.g. to store it after the sent arguments are dropped.
+    pub fn into_owned(self) -> AuthorizationResponse<'static> {
+        AuthorizationResponse {
+            status: self.status,
+            raw_status: self.raw_status,
+            arguments: self
+                .arguments
+                .into_iter()
+                .map(Argument::into_owned)
+                .collect(),
+            changes: self.changes.into_owned(),
+            user_message: self.user_message,
+            admin_message: self.admin_message,
+            session: self.session,
+        }
+    }
 }
 
 /// The response from a successful TACACS+ accounting operation.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct AccountingResponse {
+    /// The status from the server's reply.
+    ///
+    /// Only [`Success`](accounting::Status::Success) results in a response, but this is included for consistency with the
+    /// other response types.
+    pub raw_status: accounting::Status,
+
     /// The message that can be displayed to the user, if any.
     pub user_message: String,
 
     /// An administrative log message.
     pub admin_message: String,
+
+    /// The flags of the accounting record this response is for, i.e. what kind of record it was.
+    pub flags: accounting::Flags,
+
+    /// The wall clock time at which the client sent the accounting record.
+    ///
+    /// For start and stop records, this is the time reported to the server in the
+    /// `start_time` or `stop_time` argument, respectively.
+    pub timestamp: SystemTime,
+
+    /// Information about the session this response was received in.
+    pub session: SessionInfo,
+
+    /// Whether the record was only logged locally because accounting was degraded, in which case no session was
+    /// performed, so the messages are empty and the session information is all zeros.
+    ///
+    /// See [`ClientBuilder::accounting_fallback()`](super::ClientBuilder::accounting_fallback).
+    pub logged_locally: bool,
 }
 
 #[doc(hidden)]
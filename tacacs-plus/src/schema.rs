@@ -0,0 +1,238 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use tacacs_plus_protocol::Argument;
+
+use super::{ClientError, OperationKind};
+
+#[cfg(test)]
+mod tests;
+
+/// The expected type of an argument value in an [`ArgumentSchema`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    /// Any text value.
+    Text,
+
+    /// A (signed) decimal integer.
+    Integer,
+
+    /// A boolean, i.e. `true` or `false`.
+    Boolean,
+
+    /// An IPv4 or IPv6 address.
+    IpAddress,
+}
+
+impl ValueKind {
+    fn matches(self, value: &str) -> bool {
+        match self {
+            Self::Text => true,
+            Self::Integer => value.parse::<i64>().is_ok(),
+            Self::Boolean => matches!(value, "true" | "false"),
+            Self::IpAddress => value.parse::<IpAddr>().is_ok(),
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Text => "text",
+                Self::Integer => "an integer",
+                Self::Boolean => "a boolean",
+                Self::IpAddress => "an IP address",
+            }
+        )
+    }
+}
+
+/// A problem found when validating a set of arguments against an [`ArgumentSchema`].
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum SchemaIssue {
+    /// An argument wasn't listed in the schema.
+    #[error("unknown argument `{name}`")]
+    UnknownArgument {
+        /// The name of the argument.
+        name: String,
+    },
+
+    /// An argument's value didn't match the type listed in the schema.
+    #[error("argument `{name}` should be {expected}, but had value `{value}`")]
+    WrongType {
+        /// The name of the argument.
+        name: String,
+
+        /// The type the value was expected to have.
+        expected: ValueKind,
+
+        /// The actual value of the argument.
+        value: String,
+    },
+
+    /// An argument marked as required in the schema was not present.
+    #[error("required argument `{name}` is missing")]
+    MissingArgument {
+        /// The name of the missing argument.
+        name: String,
+    },
+}
+
+/// A callback for the issues found in outgoing arguments by a non-strict schema.
+pub(super) type SchemaIssueHandler = Arc<dyn Fn(OperationKind, &[SchemaIssue]) + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct AttributeSpec {
+    kind: ValueKind,
+    required: bool,
+}
+
+/// A description of the arguments a set of tools is expected to send, used to keep attribute vocabularies consistent.
+///
+/// A schema can be used standalone via [`validate()`](Self::validate) to lint argument sets, or be configured on a client
+/// via [`ClientBuilder`](super::ClientBuilder) to check outgoing requests. By default, the issues found in a request
+/// are only reported to the [issue handler](super::ClientBuilder::schema_issue_handler) and the request is still sent;
+/// [strict](Self::strict) schemas reject nonconforming requests before they are sent instead.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{Argument, FieldText};
+/// use tacacs_plus::{ArgumentSchema, SchemaIssue, ValueKind};
+///
+/// let mut schema = ArgumentSchema::new();
+/// schema
+///     .required("service", ValueKind::Text)
+///     .optional("priv-lvl", ValueKind::Integer);
+///
+/// let argument = |name, value| {
+///     Argument::new(
+///         FieldText::try_from(name).unwrap(),
+///         FieldText::try_from(value).unwrap(),
+///         true,
+///     )
+///     .unwrap()
+/// };
+///
+/// let arguments = [argument("service", "shell"), argument("priv-lvl", "high")];
+///
+/// assert_eq!(
+///     schema.validate(&arguments),
+///     [SchemaIssue::WrongType {
+///         name: "priv-lvl".to_owned(),
+///         expected: ValueKind::Integer,
+///         value: "high".to_owned(),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ArgumentSchema {
+    attributes: BTreeMap<String, AttributeSpec>,
+    strict: bool,
+}
+
+impl ArgumentSchema {
+    /// Creates an empty schema, which treats every argument as unknown.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an argument that must be present in every validated argument set.
+    pub fn required<N: Into<String>>(&mut self, name: N, kind: ValueKind) -> &mut Self {
+        self.add(name.into(), kind, true)
+    }
+
+    /// Adds an argument that may be present in a validated argument set.
+    pub fn optional<N: Into<String>>(&mut self, name: N, kind: ValueKind) -> &mut Self {
+        self.add(name.into(), kind, false)
+    }
+
+    /// Makes a client reject requests whose arguments don't conform to this schema, rather than only reporting the issues.
+    ///
+    /// Rejected requests fail with [`ClientError::ArgumentSchemaViolation`] without anything being sent to the server.
+    pub fn strict(&mut self) -> &mut Self {
+        self.strict = true;
+        self
+    }
+
+    fn add(&mut self, name: String, kind: ValueKind, required: bool) -> &mut Self {
+        self.attributes
+            .insert(name, AttributeSpec { kind, required });
+        self
+    }
+
+    /// Validates a set of arguments against this schema, returning all of the issues found.
+    ///
+    /// Issues for individual arguments are returned in the order the arguments appear, followed by any
+    /// missing required arguments in lexicographic order.
+    ///
+    /// [Unspecified](Argument::is_unspecified) arguments (i.e., optional ones with empty values) aren't type checked,
+    /// but also don't count towards required arguments being present. Empty values of mandatory arguments are checked
+    /// like any other value.
+    pub fn validate(&self, arguments: &[Argument<'_>]) -> Vec<SchemaIssue> {
+        let mut issues = Vec::new();
+
+        for argument in arguments {
+            let name = argument.name().as_ref();
+            let value = argument.value().as_ref();
+
+            match self.attributes.get(name) {
+                None => issues.push(SchemaIssue::UnknownArgument {
+                    name: name.to_owned(),
+                }),
+                // an unspecified value has no type to check, unlike an empty string
+                Some(_) if argument.is_unspecified() => {}
+                Some(spec) if !spec.kind.matches(value) => issues.push(SchemaIssue::WrongType {
+                    name: name.to_owned(),
+                    expected: spec.kind,
+                    value: value.to_owned(),
+                }),
+                Some(_) => {}
+            }
+        }
+
+        for (name, _) in self.attributes.iter().filter(|(_, spec)| spec.required) {
+            // an argument with an unspecified value doesn't provide a value for a required one
+            if !arguments
+                .iter()
+                .any(|argument| argument.name().as_ref() == name && !argument.is_unspecified())
+            {
+                issues.push(SchemaIssue::MissingArgument { name: name.clone() });
+            }
+        }
+
+        issues
+    }
+
+    /// Validates a set of outgoing arguments, rejecting them with a [`ClientError`] if the schema is strict & reporting
+    /// any issues to the handler otherwise.
+    pub(super) fn enforce(
+        &self,
+        operation: OperationKind,
+        arguments: &[Argument<'_>],
+        handler: Option<&SchemaIssueHandler>,
+    ) -> Result<(), ClientError> {
+        let issues = self.validate(arguments);
+
+        if issues.is_empty() {
+            Ok(())
+        } else if self.strict {
+            Err(ClientError::ArgumentSchemaViolation(issues))
+        } else {
+            if let Some(handler) = handler {
+                handler(operation, &issues);
+            }
+
+            Ok(())
+        }
+    }
+}
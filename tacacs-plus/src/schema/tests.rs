@@ -0,0 +1,179 @@
+use std::sync::{Arc, Mutex};
+
+use tacacs_plus_protocol::{Argument, FieldText};
+
+use super::{ArgumentSchema, SchemaIssue, ValueKind};
+use crate::tests::{authorization_reply, AcceptingServer, ReceivedRequests};
+use crate::{Client, ClientBuilder, ClientError, ContextBuilder, OperationKind, ResponseStatus};
+
+fn argument(name: &'static str, value: &'static str) -> Argument<'static> {
+    Argument::new(
+        FieldText::try_from(name).unwrap(),
+        FieldText::try_from(value).unwrap(),
+        true,
+    )
+    .unwrap()
+}
+
+#[test]
+fn conforming_arguments_have_no_issues() {
+    let mut schema = ArgumentSchema::new();
+    schema
+        .required("service", ValueKind::Text)
+        .optional("priv-lvl", ValueKind::Integer)
+        .optional("autocmd-enabled", ValueKind::Boolean)
+        .optional("addr", ValueKind::IpAddress);
+
+    let arguments = [
+        argument("service", "shell"),
+        argument("priv-lvl", "15"),
+        argument("autocmd-enabled", "false"),
+        argument("addr", "2001:db8::1"),
+    ];
+
+    assert!(schema.validate(&arguments).is_empty());
+}
+
+#[test]
+fn all_issues_reported() {
+    let mut schema = ArgumentSchema::new();
+    schema
+        .required("service", ValueKind::Text)
+        .required("cmd", ValueKind::Text)
+        .optional("addr", ValueKind::IpAddress);
+
+    let arguments = [
+        argument("addr", "not an address"),
+        argument("protocol", "ip"),
+        argument("service", "ppp"),
+    ];
+
+    assert_eq!(
+        schema.validate(&arguments),
+        [
+            SchemaIssue::WrongType {
+                name: "addr".to_owned(),
+                expected: ValueKind::IpAddress,
+                value: "not an address".to_owned()
+            },
+            SchemaIssue::UnknownArgument {
+                name: "protocol".to_owned()
+            },
+            SchemaIssue::MissingArgument {
+                name: "cmd".to_owned()
+            },
+        ]
+    );
+}
+
+#[test]
+fn unspecified_values_not_type_checked() {
+    let mut schema = ArgumentSchema::new();
+    schema
+        .required("service", ValueKind::Text)
+        .optional("priv-lvl", ValueKind::Integer)
+        .optional("timeout", ValueKind::Integer);
+
+    let arguments = [
+        Argument::unspecified(FieldText::try_from("service").unwrap()).unwrap(),
+        Argument::unspecified(FieldText::try_from("priv-lvl").unwrap()).unwrap(),
+        argument("timeout", ""),
+    ];
+
+    // an empty mandatory value is still an (invalid) integer, while an unspecified one doesn't satisfy a required argument
+    assert_eq!(
+        schema.validate(&arguments),
+        [
+            SchemaIssue::WrongType {
+                name: "timeout".to_owned(),
+                expected: ValueKind::Integer,
+                value: String::new()
+            },
+            SchemaIssue::MissingArgument {
+                name: "service".to_owned()
+            },
+        ]
+    );
+}
+
+/// The issues reported to a client's schema issue handler, along with the operation they were found in.
+type ReportedIssues = Arc<Mutex<Vec<(OperationKind, Vec<SchemaIssue>)>>>;
+
+fn client_with_schema(
+    schema: ArgumentSchema,
+    received: ReceivedRequests,
+    reported: ReportedIssues,
+) -> Client<AcceptingServer> {
+    ClientBuilder::new()
+        .authorization_schema(schema)
+        .schema_issue_handler(move |operation, issues| {
+            reported.lock().unwrap().push((operation, issues.to_vec()))
+        })
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(received.clone(), false)
+                .with_authorization_reply(authorization_reply(0x01, &[]));
+            Box::pin(async move { Ok(server) })
+        }))
+}
+
+#[tokio::test]
+async fn issues_reported_and_request_still_sent() {
+    let mut schema = ArgumentSchema::new();
+    schema.required("service", ValueKind::Text);
+
+    let received = ReceivedRequests::default();
+    let reported = ReportedIssues::default();
+    let client = client_with_schema(schema, received.clone(), Arc::clone(&reported));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let response = client
+        .authorize(
+            context,
+            [argument("service", "shell"), argument("protocol", "ip")],
+        )
+        .await
+        .expect("unknown argument shouldn't fail request");
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    assert_eq!(received.lock().unwrap().len(), 1);
+    assert_eq!(
+        *reported.lock().unwrap(),
+        [(
+            OperationKind::Authorization,
+            vec![SchemaIssue::UnknownArgument {
+                name: "protocol".to_owned()
+            }]
+        )]
+    );
+}
+
+#[tokio::test]
+async fn strict_schema_rejects_request() {
+    let mut schema = ArgumentSchema::new();
+    schema.required("service", ValueKind::Text).strict();
+
+    let received = ReceivedRequests::default();
+    let reported = ReportedIssues::default();
+    let client = client_with_schema(schema, received.clone(), Arc::clone(&reported));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let error = client
+        .authorize(
+            context,
+            [argument("service", "shell"), argument("protocol", "ip")],
+        )
+        .await
+        .expect_err("strict schema should reject unknown argument");
+    assert!(
+        matches!(&error, ClientError::ArgumentSchemaViolation(issues) if issues.len() == 1),
+        "unexpected error: {error:?}"
+    );
+
+    // nothing is sent or reported for a rejected request
+    assert!(received.lock().unwrap().is_empty());
+    assert!(reported.lock().unwrap().is_empty());
+}
@@ -0,0 +1,146 @@
+//! An in-memory server that answers packets with scripted replies, for testing clients without a real server.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::{AnyBody, Packet, PacketStreamParser};
+
+/// Decides how a [`ScriptedServer`] answers each packet it receives.
+///
+/// This is implemented for closures taking a packet, so simple scripts don't need a type of their own.
+pub trait ReplyScript {
+    /// Returns the body of the reply to a packet from the client, or `None` to leave it unanswered.
+    fn reply(&mut self, packet: &Packet<AnyBody>) -> Option<Vec<u8>>;
+}
+
+impl<F: FnMut(&Packet<AnyBody>) -> Option<Vec<u8>>> ReplyScript for F {
+    fn reply(&mut self, packet: &Packet<AnyBody>) -> Option<Vec<u8>> {
+        self(packet)
+    }
+}
+
+/// A connection to an in-memory server, which answers each packet a client writes as directed by a [`ReplyScript`].
+///
+/// Replies have the same version, type & session id as the packet they answer, the next sequence number and both the
+/// unencrypted & single connection flags set. Packets are exchanged unobfuscated, so clients connecting to a scripted
+/// server shouldn't be configured with a secret key.
+///
+/// Replies are available as soon as the packet they answer is written, so reads only ever wait if a script leaves a
+/// packet unanswered, in which case they wait forever as with a server that has hung.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{Client, ContextBuilder, ResponseStatus, ScriptedServer};
+///
+/// # futures::executor::block_on(async {
+/// let client = Client::new(
+///     Box::new(|| {
+///         // PASS_ADD status, no arguments, empty server message & data
+///         let server = ScriptedServer::new(|_: &_| Some(vec![0x01, 0, 0, 0, 0, 0]));
+///         Box::pin(async move { Ok(server) })
+///     }),
+///     None::<&[u8]>,
+/// );
+///
+/// let context = ContextBuilder::new(String::from("someuser")).build().unwrap();
+/// let response = client.authorize(context, []).await.unwrap();
+/// assert_eq!(response.status, ResponseStatus::Success);
+/// # });
+/// ```
+pub struct ScriptedServer<S> {
+    script: S,
+    parser: PacketStreamParser,
+    replies: Vec<u8>,
+}
+
+impl<S: ReplyScript> ScriptedServer<S> {
+    /// Creates a server following the provided script.
+    pub fn new(script: S) -> Self {
+        Self {
+            script,
+            parser: PacketStreamParser::new(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Returns a mutable reference to the script the server follows, e.g. to change how later packets are answered.
+    pub fn script_mut(&mut self) -> &mut S {
+        &mut self.script
+    }
+
+    /// Builds an unobfuscated reply to a packet with the provided body.
+    fn queue_reply(&mut self, packet: &Packet<AnyBody>, body: &[u8]) {
+        let header = packet.header();
+
+        self.replies.extend([
+            u8::from(header.version()),
+            packet.body().packet_type() as u8,
+            header.sequence_number().wrapping_add(1),
+            // unencrypted & single connection flags
+            0x05,
+        ]);
+        self.replies.extend(header.session_id().to_be_bytes());
+        self.replies.extend(
+            u32::try_from(body.len())
+                .expect("reply body should fit in a packet")
+                .to_be_bytes(),
+        );
+        self.replies.extend(body);
+    }
+}
+
+impl<S: ReplyScript + Unpin> AsyncRead for ScriptedServer<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // replies are queued as soon as a packet is written, so there's nothing to wake up for otherwise
+        if self.replies.is_empty() {
+            return Poll::Pending;
+        }
+
+        let length = buf.len().min(self.replies.len());
+        buf[..length].copy_from_slice(&self.replies[..length]);
+        self.replies.drain(..length);
+
+        Poll::Ready(Ok(length))
+    }
+}
+
+impl<S: ReplyScript + Unpin> AsyncWrite for ScriptedServer<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.parser.feed(buf);
+
+        loop {
+            let packet = match self.parser.next_packet() {
+                Ok(Some(packet)) => packet,
+                Ok(None) => break,
+                Err(err) => {
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, err)))
+                }
+            };
+
+            if let Some(body) = self.script.reply(&packet) {
+                self.queue_reply(&packet, &body);
+            }
+        }
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
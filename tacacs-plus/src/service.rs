@@ -0,0 +1,83 @@
+//! A [`tower_service::Service`] implementation for authorization checks.
+
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::Argument;
+use tower_service::Service;
+
+use super::{AuthorizationResponse, Client, ClientError, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+/// An authorization check, as handled by the [`Service`] implementation of a [`Client`].
+///
+/// This mirrors the parameters of [`Client::authorize()`], but owns its arguments so it can be passed through
+/// middleware stacks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AuthorizationRequest {
+    /// The context of the user being authorized.
+    pub context: SessionContext,
+
+    /// The arguments to send to the server, e.g. `service` & `cmd` for command authorization.
+    pub arguments: Vec<Argument<'static>>,
+}
+
+/// Performs authorization checks, e.g. for web backends or policy gateways with existing middleware stacks.
+///
+/// A client is always ready to accept requests, since sessions are queued for the shared connection internally;
+/// timeouts, load shedding & retries can be layered on top as with any other service. Requests are performed as
+/// with [`Client::authorize()`], so a denial is reported as a response with a
+/// [`Failure`](super::ResponseStatus::Failure) status rather than an error.
+///
+/// # Examples
+///
+/// ```
+/// use futures::future;
+/// use futures::io::Cursor;
+/// use tower_service::Service;
+///
+/// use tacacs_plus::{Argument, AuthorizationRequest, Client, ContextBuilder, FieldText};
+///
+/// # async fn example() -> Result<(), tacacs_plus::ClientError> {
+/// let mut client = Client::new(
+///     Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::<u8>::new())) })),
+///     Some("secret"),
+/// );
+///
+/// let request = AuthorizationRequest {
+///     context: ContextBuilder::new(String::from("someuser")).build()?,
+///     arguments: vec![Argument::new(
+///         FieldText::try_from("service").unwrap(),
+///         FieldText::try_from("shell").unwrap(),
+///         true,
+///     )?],
+/// };
+///
+/// future::poll_fn(|cx| client.poll_ready(cx)).await?;
+/// let response = client.call(request).await?;
+/// # let _ = response;
+/// # Ok(())
+/// # }
+/// ```
+impl<S> Service<AuthorizationRequest> for Client<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    type Response = AuthorizationResponse<'static>;
+    type Error = ClientError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: AuthorizationRequest) -> Self::Future {
+        // clones share the connection, so the returned future doesn't need to borrow this client
+        let client = self.clone();
+
+        Box::pin(async move { client.authorize(request.context, &request.arguments).await })
+    }
+}
@@ -0,0 +1,47 @@
+use futures::future;
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{ClientBuilder, ContextBuilder, ResponseStatus};
+use tacacs_plus_protocol::{FieldText, PacketType};
+
+#[tokio::test]
+async fn authorization_performed_as_service() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let mut client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), false);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    let service_argument = Argument::new(
+        FieldText::try_from("service").unwrap(),
+        FieldText::try_from("shell").unwrap(),
+        true,
+    )
+    .unwrap();
+    let request = AuthorizationRequest {
+        context: ContextBuilder::new(String::from("someuser"))
+            .build()
+            .unwrap(),
+        arguments: vec![service_argument.clone()],
+    };
+
+    future::poll_fn(|cx| client.poll_ready(cx))
+        .await
+        .expect("client should always be ready");
+
+    // the future doesn't borrow the client, so it can be spawned onto another task
+    let response = tokio::spawn(client.call(request))
+        .await
+        .unwrap()
+        .expect("authorization should succeed");
+
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(response.arguments, [service_argument]);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, PacketType::Authorization);
+}
@@ -0,0 +1,109 @@
+//! Related operations performed for the same user session on a device.
+
+use std::time::{Duration, SystemTime};
+
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::Argument;
+
+use super::{AccountingResponse, AccountingTask, AuthenticationResponse, AuthorizationResponse};
+use super::{AuthenticationType, Client, ClientError, ResponseStatus, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+/// A sequence of operations for the same user session, e.g. authentication followed by authorization & accounting.
+///
+/// RFC8907 treats each TACACS+ session independently, but devices commonly authenticate a user and then authorize & account
+/// for their commands, in which case the fields shared between those sessions should line up. A `Session` keeps them consistent
+/// by using the same [`SessionContext`] for every operation. Once authentication succeeds, its type is also sent in later
+/// authorization & accounting requests, along with an authentication method of
+/// [`TacacsPlus`](crate::AuthenticationMethod::TacacsPlus) unless the context specifies one explicitly.
+///
+/// All operations go through the same client, and so share its connection if the server agrees to single connection mode.
+///
+/// A `Session` is created with [`Client::session()`].
+#[derive(Debug)]
+pub struct Session<'client, S> {
+    client: &'client Client<S>,
+    context: SessionContext,
+}
+
+impl<'client, S> Session<'client, S> {
+    pub(super) fn new(client: &'client Client<S>, context: SessionContext) -> Self {
+        Self { client, context }
+    }
+
+    /// The context shared by the operations in this session, including any authentication information recorded so far.
+    pub fn context(&self) -> &SessionContext {
+        &self.context
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<'_, S> {
+    /// Authenticates the user of this session, as in [`Client::authenticate()`].
+    ///
+    /// If authentication succeeds, its type is recorded for later authorization & accounting requests.
+    ///
+    /// # Cancellation
+    ///
+    /// Cancellation behaves as for [`Client::authenticate()`]; if the future is dropped, no authentication type is
+    /// recorded even if the server accepted the credentials.
+    pub async fn authenticate(
+        &mut self,
+        password: &str,
+        authentication_type: AuthenticationType,
+    ) -> Result<AuthenticationResponse, ClientError> {
+        let (response, wire_authentication_type) = self
+            .client
+            .authenticate_with_type(&self.context, password, authentication_type)
+            .await?;
+
+        if response.status == ResponseStatus::Success {
+            self.context.set_authenticated(wire_authentication_type);
+        }
+
+        Ok(response)
+    }
+
+    /// Performs authorization for the user of this session, as in [`Client::authorize()`].
+    ///
+    /// # Cancellation
+    ///
+    /// Cancellation behaves as for [`Client::authorize()`].
+    pub async fn authorize<'args, A: AsRef<[Argument<'args>]>>(
+        &self,
+        arguments: A,
+    ) -> Result<AuthorizationResponse<'args>, ClientError> {
+        self.client.authorize(self.context.clone(), arguments).await
+    }
+
+    /// Starts tracking a task for the user of this session, as in [`Client::account_begin()`].
+    ///
+    /// # Cancellation
+    ///
+    /// Cancellation behaves as for [`Client::account_begin()`].
+    pub async fn account_begin<'args, A: AsRef<[Argument<'args>]>>(
+        &self,
+        arguments: A,
+    ) -> Result<(AccountingTask<&Client<S>>, AccountingResponse), ClientError> {
+        self.client
+            .account_begin(self.context.clone(), arguments)
+            .await
+    }
+
+    /// Sends a single stop record for a completed task of the user of this session, as in [`Client::account_stop_once()`].
+    ///
+    /// # Cancellation
+    ///
+    /// Cancellation behaves as for [`Client::account_stop_once()`].
+    pub async fn account_stop_once<'args, A: AsRef<[Argument<'args>]>>(
+        &self,
+        arguments: A,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> Result<AccountingResponse, ClientError> {
+        self.client
+            .account_stop_once(self.context.clone(), arguments, started_at, duration)
+            .await
+    }
+}
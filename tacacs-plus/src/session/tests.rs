@@ -0,0 +1,127 @@
+use tacacs_plus_protocol::{authentication, authorization, AuthenticationMethod, PacketType};
+
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{AuthenticationType, ClientBuilder, ContextBuilder, ResponseStatus};
+
+fn client(reject_authentication: bool) -> (crate::Client<AcceptingServer>, ReceivedRequests) {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), reject_authentication);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    (client, received)
+}
+
+/// Extracts the authentication method & type from an authorization or accounting request body.
+fn authentication_fields(packet_type: PacketType, body: &[u8]) -> (u8, u8) {
+    match packet_type {
+        PacketType::Authorization => (body[0], body[2]),
+        // accounting requests start with a flags byte
+        PacketType::Accounting => (body[1], body[3]),
+        PacketType::Authentication => {
+            panic!("authentication requests don't have an authentication method")
+        }
+    }
+}
+
+#[tokio::test]
+async fn authentication_is_reflected_in_later_requests() {
+    let (client, received) = client(false);
+
+    let mut session = client.session(ContextBuilder::new(String::from("user")).build().unwrap());
+    let response = session
+        .authenticate("password", AuthenticationType::Pap)
+        .await
+        .expect("authentication should complete");
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(response.raw_status, authentication::Status::Pass);
+
+    let response = session
+        .authorize([])
+        .await
+        .expect("authorization should complete");
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert_eq!(response.raw_status, authorization::Status::PassAdd);
+    let (task, _) = session
+        .account_begin([])
+        .await
+        .expect("accounting should complete");
+    task.stop([]).await.expect("accounting should complete");
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 4);
+
+    for (packet_type, body) in &received[1..] {
+        assert_eq!(
+            authentication_fields(*packet_type, body),
+            (
+                AuthenticationMethod::TacacsPlus as u8,
+                tacacs_plus_protocol::AuthenticationType::Pap as u8
+            ),
+            "{packet_type:?} request has inconsistent authentication fields"
+        );
+    }
+}
+
+#[tokio::test]
+async fn failed_authentication_is_not_recorded() {
+    let (client, received) = client(true);
+
+    let mut session = client.session(ContextBuilder::new(String::from("user")).build().unwrap());
+    let response = session
+        .authenticate("password", AuthenticationType::Pap)
+        .await
+        .expect("authentication should complete");
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.raw_status, authentication::Status::Fail);
+
+    let response = session
+        .authorize([])
+        .await
+        .expect("authorization should complete");
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let received = received.lock().unwrap();
+    let (packet_type, body) = &received[1];
+    assert_eq!(
+        authentication_fields(*packet_type, body),
+        (
+            AuthenticationMethod::NotSet as u8,
+            tacacs_plus_protocol::AuthenticationType::NotSet as u8
+        )
+    );
+}
+
+#[tokio::test]
+async fn explicit_authentication_method_is_kept() {
+    let (client, received) = client(false);
+
+    let context = ContextBuilder::new(String::from("user"))
+        .auth_method(AuthenticationMethod::Local)
+        .build()
+        .unwrap();
+    let mut session = client.session(context);
+    let _ = session
+        .authenticate("password", AuthenticationType::Chap)
+        .await
+        .expect("authentication should complete");
+
+    let response = session
+        .authorize([])
+        .await
+        .expect("authorization should complete");
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let received = received.lock().unwrap();
+    let (packet_type, body) = &received[1];
+    assert_eq!(
+        authentication_fields(*packet_type, body),
+        (
+            AuthenticationMethod::Local as u8,
+            tacacs_plus_protocol::AuthenticationType::Chap as u8
+        )
+    );
+}
@@ -0,0 +1,390 @@
+//! A bounded queue of accounting records, sent to a server in the background.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::poll_fn;
+use std::mem;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+use std::time::{Duration, SystemTime};
+
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::Argument;
+use thiserror::Error;
+
+use super::{Client, ClientError, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+/// What to do when a record is sent to an [`AccountingSink`] whose queue is full.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverflowPolicy {
+    /// Wait until the driver makes room in the queue.
+    ///
+    /// [`AccountingSink::try_send()`] can't wait, and so treats this the same as [`Error`](Self::Error).
+    #[default]
+    Block,
+
+    /// Discard the oldest queued record to make room for the new one.
+    DropOldest,
+
+    /// Return the new record to the caller in a [`SinkError::Full`].
+    Error,
+}
+
+/// A record of a completed task, to be sent as an accounting stop record via [`Client::account_stop_once()`].
+#[derive(Debug, Clone)]
+pub struct AccountingRecord {
+    /// Information about the user & device the task was performed for.
+    pub context: SessionContext,
+
+    /// The arguments describing the task.
+    pub arguments: Vec<Argument<'static>>,
+
+    /// When the task was started.
+    pub started_at: SystemTime,
+
+    /// How long the task took.
+    pub duration: Duration,
+}
+
+impl AccountingRecord {
+    /// Creates a record for a task that started at `started_at` and took `duration` to complete.
+    pub fn new(
+        context: SessionContext,
+        arguments: Vec<Argument<'static>>,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            context,
+            arguments,
+            started_at,
+            duration,
+        }
+    }
+}
+
+/// An error when sending a record to an [`AccountingSink`], which contains the record that couldn't be queued.
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SinkError {
+    /// The queue was full and the sink's [`OverflowPolicy`] didn't allow waiting or dropping older records.
+    #[error("accounting queue is full")]
+    Full(Box<AccountingRecord>),
+
+    /// The driver for the sink was dropped, so no more records will be sent.
+    #[error("accounting driver is no longer running")]
+    Closed(Box<AccountingRecord>),
+}
+
+impl SinkError {
+    /// Returns the record that couldn't be queued.
+    pub fn into_record(self) -> AccountingRecord {
+        match self {
+            Self::Full(record) | Self::Closed(record) => *record,
+        }
+    }
+}
+
+/// The state shared between the sinks & driver of a queue.
+#[derive(Debug)]
+struct Queue {
+    records: VecDeque<AccountingRecord>,
+    capacity: usize,
+    policy: OverflowPolicy,
+
+    /// The number of live sinks, which once zero means no more records can arrive.
+    senders: usize,
+
+    /// Whether the driver was dropped.
+    closed: bool,
+
+    /// The number of records discarded due to [`OverflowPolicy::DropOldest`].
+    dropped: u64,
+
+    driver_waker: Option<Waker>,
+    blocked_senders: Vec<Waker>,
+}
+
+#[derive(Debug)]
+struct Shared(Mutex<Queue>);
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        // the queue is never left in an inconsistent state, so a panic elsewhere while it was locked is fine to ignore
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A cloneable handle for queueing accounting records, which are sent to a server by an [`AccountingDriver`].
+///
+/// The queue holds a bounded number of records, and an [`OverflowPolicy`] decides what happens when it's full. Records are sent
+/// in the order they were queued; once every sink has been dropped, the driver finishes sending the remaining records and stops.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+///
+/// use futures::{AsyncRead, AsyncWrite};
+/// use tacacs_plus::{AccountingRecord, AccountingSink, Client, ContextBuilder, OverflowPolicy};
+///
+/// async fn log_commands<S: AsyncRead + AsyncWrite + Unpin>(client: &Client<S>) {
+///     let (sink, driver) = AccountingSink::new(client, 64, OverflowPolicy::DropOldest);
+///
+///     let producer = async move {
+///         for user in ["alice", "bob"] {
+///             let context = ContextBuilder::new(user.to_owned()).build().unwrap();
+///             let record = AccountingRecord::new(context, Vec::new(), SystemTime::now(), Duration::from_secs(1));
+///             sink.send(record).await.expect("driver should still be running");
+///         }
+///     };
+///
+///     futures::join!(producer, driver.run());
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AccountingSink {
+    shared: Arc<Shared>,
+}
+
+impl AccountingSink {
+    /// Creates a sink with room for `capacity` queued records, along with the driver that sends them through `client`.
+    ///
+    /// The driver must be [run](AccountingDriver::run) for records to be sent, e.g. by spawning it on an async runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn new<C>(
+        client: C,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> (Self, AccountingDriver<C>) {
+        assert!(capacity > 0, "accounting queue capacity must be nonzero");
+
+        let shared = Arc::new(Shared(Mutex::new(Queue {
+            records: VecDeque::with_capacity(capacity),
+            capacity,
+            policy,
+            senders: 1,
+            closed: false,
+            dropped: 0,
+            driver_waker: None,
+            blocked_senders: Vec::new(),
+        })));
+
+        let driver = AccountingDriver {
+            client,
+            shared: shared.clone(),
+            error_handler: None,
+        };
+
+        (Self { shared }, driver)
+    }
+
+    /// Queues a record to be sent, waiting for room if the queue is full and the policy is [`OverflowPolicy::Block`].
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped while waiting for room in the queue, the record is dropped without being
+    /// queued.
+    pub async fn send(&self, record: AccountingRecord) -> Result<(), SinkError> {
+        let mut record = Some(record);
+        poll_fn(|cx| self.push(&mut record, Some(cx.waker()))).await
+    }
+
+    /// Queues a record to be sent without waiting, failing with [`SinkError::Full`] if there's no room for it.
+    ///
+    /// Records may still be dropped to make room if the policy is [`OverflowPolicy::DropOldest`].
+    pub fn try_send(&self, record: AccountingRecord) -> Result<(), SinkError> {
+        match self.push(&mut Some(record), None) {
+            Poll::Ready(result) => result,
+            Poll::Pending => unreachable!("records are only left unqueued when there's a waker"),
+        }
+    }
+
+    /// The number of records that were discarded to make room for newer ones under [`OverflowPolicy::DropOldest`].
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Attempts to queue a record, registering `waker` to be woken once there's room if the sink should block.
+    ///
+    /// `record` is left in place if the sink is blocked, and is otherwise taken.
+    fn push(
+        &self,
+        record: &mut Option<AccountingRecord>,
+        waker: Option<&Waker>,
+    ) -> Poll<Result<(), SinkError>> {
+        let mut queue = self.shared.lock();
+        let new_record = record.take().expect("record should not already be queued");
+
+        if queue.closed {
+            return Poll::Ready(Err(SinkError::Closed(Box::new(new_record))));
+        }
+
+        if queue.records.len() >= queue.capacity {
+            match (queue.policy, waker) {
+                (OverflowPolicy::Block, Some(waker)) => {
+                    if !queue.blocked_senders.iter().any(|w| w.will_wake(waker)) {
+                        queue.blocked_senders.push(waker.clone());
+                    }
+
+                    *record = Some(new_record);
+                    return Poll::Pending;
+                }
+                (OverflowPolicy::DropOldest, _) => {
+                    queue.records.pop_front();
+                    queue.dropped += 1;
+                }
+                _ => return Poll::Ready(Err(SinkError::Full(Box::new(new_record)))),
+            }
+        }
+
+        queue.records.push_back(new_record);
+        let driver_waker = queue.driver_waker.take();
+        drop(queue);
+
+        if let Some(waker) = driver_waker {
+            waker.wake();
+        }
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Clone for AccountingSink {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl Drop for AccountingSink {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.senders -= 1;
+
+        // the driver has to notice the last sink going away so it can stop once the queue is drained
+        let driver_waker = if queue.senders == 0 {
+            queue.driver_waker.take()
+        } else {
+            None
+        };
+        drop(queue);
+
+        if let Some(waker) = driver_waker {
+            waker.wake();
+        }
+    }
+}
+
+/// A handler for records that couldn't be sent, as registered via [`AccountingDriver::on_error()`].
+type ErrorHandler = Box<dyn FnMut(&ClientError, &AccountingRecord) + Send>;
+
+/// Sends the records queued in an [`AccountingSink`] to a server, as returned from [`AccountingSink::new()`].
+///
+/// `C` is a handle to a [`Client`], e.g. a reference or an [`Arc`].
+#[must_use = "records are only sent while the driver is run"]
+pub struct AccountingDriver<C> {
+    client: C,
+    shared: Arc<Shared>,
+    error_handler: Option<ErrorHandler>,
+}
+
+impl<C> fmt::Debug for AccountingDriver<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccountingDriver")
+            .field("queue", &self.shared)
+            .field("has_error_handler", &self.error_handler.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C> AccountingDriver<C> {
+    /// Registers a handler for records that the server rejected or that couldn't be sent due to an error.
+    ///
+    /// Failed records are otherwise discarded, since accounting records are typically not worth retrying indefinitely.
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: FnMut(&ClientError, &AccountingRecord) + Send + 'static,
+    {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Waits for the next queued record, or returns `None` if every sink was dropped and the queue is empty.
+    async fn next_record(&self) -> Option<AccountingRecord> {
+        poll_fn(|cx| {
+            let mut queue = self.shared.lock();
+
+            if let Some(record) = queue.records.pop_front() {
+                let blocked_senders = mem::take(&mut queue.blocked_senders);
+                drop(queue);
+
+                blocked_senders.into_iter().for_each(Waker::wake);
+                Poll::Ready(Some(record))
+            } else if queue.senders == 0 {
+                Poll::Ready(None)
+            } else {
+                queue.driver_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<C, S> AccountingDriver<C>
+where
+    C: Deref<Target = Client<S>>,
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Sends queued records until every [`AccountingSink`] is dropped and the queue is drained.
+    ///
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped, records still in the queue aren't sent, and the record being sent at the time
+    /// (if any) is cancelled as described for [`Client::account_stop_once()`]. A new driver can't be created for the
+    /// same sinks, so the driver should generally be run to completion, e.g. in a spawned task.
+    pub async fn run(mut self) {
+        while let Some(record) = self.next_record().await {
+            let result = self
+                .client
+                .account_stop_once(
+                    record.context.clone(),
+                    &record.arguments,
+                    record.started_at,
+                    record.duration,
+                )
+                .await;
+
+            if let (Err(error), Some(handler)) = (result, self.error_handler.as_mut()) {
+                handler(&error, &record);
+            }
+        }
+    }
+}
+
+impl<C> Drop for AccountingDriver<C> {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.closed = true;
+
+        let blocked_senders = mem::take(&mut queue.blocked_senders);
+        drop(queue);
+
+        // blocked senders are woken so they can find out that their records won't be sent
+        blocked_senders.into_iter().for_each(Waker::wake);
+    }
+}
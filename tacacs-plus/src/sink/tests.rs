@@ -0,0 +1,121 @@
+use std::time::{Duration, SystemTime};
+
+use tacacs_plus_protocol::PacketType;
+
+use super::{AccountingRecord, AccountingSink, OverflowPolicy, SinkError};
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{Client, ClientBuilder, ContextBuilder};
+
+fn client() -> (Client<AcceptingServer>, ReceivedRequests) {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), false);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    (client, received)
+}
+
+fn record(user: &str) -> AccountingRecord {
+    AccountingRecord::new(
+        ContextBuilder::new(user.to_owned()).build().unwrap(),
+        Vec::new(),
+        SystemTime::UNIX_EPOCH,
+        Duration::from_secs(1),
+    )
+}
+
+/// Extracts the users from the accounting requests a server received, in order.
+fn accounted_users(received: &ReceivedRequests) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(packet_type, body)| {
+            assert_eq!(*packet_type, PacketType::Accounting);
+
+            // the user comes right after the fixed fields & argument lengths
+            let user_length = usize::from(body[5]);
+            let user_start = 9 + usize::from(body[8]);
+            String::from_utf8(body[user_start..user_start + user_length].to_vec()).unwrap()
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn blocked_sender_waits_for_room() {
+    let (client, received) = client();
+    let (sink, driver) = AccountingSink::new(&client, 1, OverflowPolicy::Block);
+
+    let producer = async move {
+        for user in ["first", "second", "third"] {
+            sink.send(record(user))
+                .await
+                .expect("driver should be running");
+        }
+    };
+
+    tokio::join!(producer, driver.run());
+
+    assert_eq!(accounted_users(&received), ["first", "second", "third"]);
+}
+
+#[tokio::test]
+async fn oldest_records_dropped_when_full() {
+    let (client, received) = client();
+    let (sink, driver) = AccountingSink::new(&client, 2, OverflowPolicy::DropOldest);
+
+    for user in ["first", "second", "third", "fourth"] {
+        sink.try_send(record(user))
+            .expect("records should be dropped instead of failing");
+    }
+    assert_eq!(sink.dropped(), 2);
+
+    drop(sink);
+    driver.run().await;
+
+    assert_eq!(accounted_users(&received), ["third", "fourth"]);
+}
+
+#[test]
+fn full_queue_returns_record() {
+    let (client, _) = client();
+    let (sink, _driver) = AccountingSink::new(&client, 1, OverflowPolicy::Error);
+
+    sink.try_send(record("first"))
+        .expect("queue should have room");
+
+    let error =
+        futures::executor::block_on(sink.send(record("second"))).expect_err("queue should be full");
+    assert!(matches!(error, SinkError::Full(_)));
+    assert_eq!(error.into_record().context.user, "second");
+
+    // blocking sinks can't wait in try_send either
+    let (sink, _driver) = AccountingSink::new(&client, 1, OverflowPolicy::Block);
+    sink.try_send(record("first"))
+        .expect("queue should have room");
+    assert!(matches!(
+        sink.try_send(record("second")),
+        Err(SinkError::Full(_))
+    ));
+}
+
+#[test]
+fn send_fails_after_driver_dropped() {
+    let (client, _) = client();
+    let (sink, driver) = AccountingSink::new(&client, 4, OverflowPolicy::Block);
+    let cloned_sink = sink.clone();
+
+    drop(driver);
+
+    assert!(matches!(
+        sink.try_send(record("user")),
+        Err(SinkError::Closed(_))
+    ));
+    assert!(matches!(
+        futures::executor::block_on(cloned_sink.send(record("user"))),
+        Err(SinkError::Closed(_))
+    ));
+}
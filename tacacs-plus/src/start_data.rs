@@ -0,0 +1,331 @@
+//! Construction of the method-specific contents of authentication start packets.
+
+use des::cipher::{BlockEncrypt, KeyInit};
+use des::Des;
+use md4::{Digest, Md4};
+use rand::{Rng, RngCore};
+
+use tacacs_plus_protocol::authentication::{chap, pap};
+use tacacs_plus_protocol::{AuthenticationType, MinorVersion};
+
+use super::{ClientError, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum length of the data field of a start packet, whose length is encoded in a single byte.
+pub(super) const MAX_START_DATA_LENGTH: usize = u8::MAX as usize;
+
+/// The parts of an authentication start packet that depend on the authentication method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StartData {
+    /// The authentication type sent in the start packet.
+    pub authentication_type: AuthenticationType,
+
+    /// The protocol minor version of the session.
+    ///
+    /// Note that this is overridden if the authentication type requires a specific minor version.
+    pub minor_version: MinorVersion,
+
+    /// The contents of the data field of the start packet.
+    pub data: Vec<u8>,
+}
+
+/// Produces the [`StartData`] for an authentication method from a session context & credential.
+///
+/// This is implemented for each authentication method the client supports out of the box, but custom implementations
+/// can be registered via [`ClientBuilder::start_data_builder()`](super::ClientBuilder::start_data_builder) to support
+/// other methods (e.g., proprietary OTP data formats) without changes to the client.
+///
+/// Builders are invoked again for each attempt of an authentication session, so any per-session values
+/// (e.g., challenges) should be generated in [`build()`](Self::build). Builders that need randomness should implement
+/// [`build_with_rng()`](Self::build_with_rng) as well, so that they use the RNG configured for the client.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::protocol::{AuthenticationType, MinorVersion};
+/// use tacacs_plus::{ClientError, SessionContext, StartData, StartDataBuilder};
+///
+/// /// Sends a one-time password prefixed with a token serial number.
+/// struct SerialOtp {
+///     serial: String,
+/// }
+///
+/// impl StartDataBuilder for SerialOtp {
+///     fn build(&self, _context: &SessionContext, credential: &str) -> Result<StartData, ClientError> {
+///         Ok(StartData {
+///             authentication_type: AuthenticationType::Pap,
+///             minor_version: MinorVersion::V1,
+///             data: format!("{}:{credential}", self.serial).into_bytes(),
+///         })
+///     }
+/// }
+/// ```
+pub trait StartDataBuilder: Send + Sync {
+    /// Builds the start packet contents for a session with the provided context & credential (e.g., a password).
+    fn build(&self, context: &SessionContext, credential: &str) -> Result<StartData, ClientError>;
+
+    /// Builds the start packet contents like [`build()`](Self::build), drawing any random values from the provided RNG.
+    ///
+    /// This is what the client calls, passing the RNG configured via [`ClientBuilder::rng()`](super::ClientBuilder::rng).
+    /// The default implementation ignores the RNG and calls [`build()`](Self::build).
+    fn build_with_rng(
+        &self,
+        context: &SessionContext,
+        credential: &str,
+        rng: &mut dyn RngCore,
+    ) -> Result<StartData, ClientError> {
+        let _ = rng;
+        self.build(context, credential)
+    }
+}
+
+// closures can be used as builders directly, e.g. for simple custom methods
+impl<F> StartDataBuilder for F
+where
+    F: Fn(&SessionContext, &str) -> Result<StartData, ClientError> + Send + Sync,
+{
+    fn build(&self, context: &SessionContext, credential: &str) -> Result<StartData, ClientError> {
+        self(context, credential)
+    }
+}
+
+/// [`StartDataBuilder`] for the Password Authentication Protocol (PAP), where the data field is just the password.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct PapStartData;
+
+impl StartDataBuilder for PapStartData {
+    fn build(&self, _context: &SessionContext, credential: &str) -> Result<StartData, ClientError> {
+        let mut data = vec![0; credential.len()];
+        pap::encode_login_data(credential.as_bytes(), &mut data)
+            // the buffer is sized to fit, so the only possible error is the data being too long for a packet
+            .map_err(|_| ClientError::PasswordTooLong {
+                length: credential.len(),
+                limit: MAX_START_DATA_LENGTH,
+            })?;
+
+        Ok(StartData {
+            authentication_type: AuthenticationType::Pap,
+            minor_version: MinorVersion::V1,
+            data,
+        })
+    }
+}
+
+/// [`StartDataBuilder`] for the Challenge-Handshake Authentication Protocol (CHAP), as described in [RFC8907 section 5.4.2.3].
+///
+/// The PPP ID & challenge are drawn from the client's RNG (see [`ClientBuilder::rng()`](super::ClientBuilder::rng)),
+/// with a 16-byte challenge by default. Both can be adjusted for servers with specific expectations, and a customized
+/// builder can be set via [`ClientBuilder::chap_start_data()`](super::ClientBuilder::chap_start_data).
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::ChapStartData;
+///
+/// let builder = ChapStartData::new()
+///     .with_challenge_length(32)
+///     .expect("challenge length should be valid")
+///     .with_ppp_id(7);
+///
+/// assert_eq!(builder.challenge_length(), 32);
+/// assert_eq!(builder.ppp_id(), Some(7));
+/// ```
+///
+/// [RFC8907 section 5.4.2.3]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChapStartData {
+    challenge_length: u8,
+    ppp_id: Option<u8>,
+}
+
+impl ChapStartData {
+    /// The length of challenges if not configured otherwise.
+    pub const DEFAULT_CHALLENGE_LENGTH: u8 = 16;
+
+    /// The maximum length of a challenge, such that the PPP ID, challenge & 16-byte MD5 response fit in the data field.
+    pub const MAX_CHALLENGE_LENGTH: u8 = u8::MAX - 1 - 16;
+
+    /// Creates a builder with the default challenge length & a random PPP ID for each session.
+    pub fn new() -> Self {
+        Self {
+            challenge_length: Self::DEFAULT_CHALLENGE_LENGTH,
+            ppp_id: None,
+        }
+    }
+
+    /// Sets the length of generated challenges.
+    ///
+    /// Returns `None` if the length is 0 (which [RFC1994 section 4.1] forbids) or longer than
+    /// [`MAX_CHALLENGE_LENGTH`](Self::MAX_CHALLENGE_LENGTH).
+    ///
+    /// [RFC1994 section 4.1]: https://www.rfc-editor.org/rfc/rfc1994.html#section-4.1
+    pub fn with_challenge_length(self, length: u8) -> Option<Self> {
+        (1..=Self::MAX_CHALLENGE_LENGTH)
+            .contains(&length)
+            .then_some(Self {
+                challenge_length: length,
+                ..self
+            })
+    }
+
+    /// Uses a fixed PPP ID instead of a random one for each session.
+    pub fn with_ppp_id(self, ppp_id: u8) -> Self {
+        Self {
+            ppp_id: Some(ppp_id),
+            ..self
+        }
+    }
+
+    /// The length of generated challenges.
+    pub fn challenge_length(&self) -> u8 {
+        self.challenge_length
+    }
+
+    /// The fixed PPP ID used for each session, or `None` if a random one is generated instead.
+    pub fn ppp_id(&self) -> Option<u8> {
+        self.ppp_id
+    }
+}
+
+impl Default for ChapStartData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StartDataBuilder for ChapStartData {
+    fn build(&self, context: &SessionContext, credential: &str) -> Result<StartData, ClientError> {
+        self.build_with_rng(context, credential, &mut rand::thread_rng())
+    }
+
+    fn build_with_rng(
+        &self,
+        _context: &SessionContext,
+        credential: &str,
+        rng: &mut dyn RngCore,
+    ) -> Result<StartData, ClientError> {
+        // generate random PPP ID (unless fixed) & challenge
+        let ppp_id = self.ppp_id.unwrap_or_else(|| rng.gen());
+        let mut challenge = vec![0; usize::from(self.challenge_length)];
+        rng.fill_bytes(&mut challenge);
+
+        // the secret is the password in this case
+        let mut data = vec![0; chap::login_data_length(challenge.len())];
+        let length = data.len();
+        chap::encode_login_data(ppp_id, &challenge, credential.as_bytes(), &mut data)
+            // the buffer is sized to fit, so the only possible error is the data being too long for a packet
+            .map_err(|_| ClientError::PasswordTooLong {
+                length,
+                limit: MAX_START_DATA_LENGTH,
+            })?;
+
+        Ok(StartData {
+            authentication_type: AuthenticationType::Chap,
+            minor_version: MinorVersion::V1,
+            data,
+        })
+    }
+}
+
+/// [`StartDataBuilder`] for Microsoft's CHAP extensions, version 1 (MS-CHAP), as described in [RFC8907 section 5.4.2.4].
+///
+/// The response is computed as in [RFC2433], using only the Windows NT-compatible response.
+///
+/// [RFC8907 section 5.4.2.4]: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.4
+/// [RFC2433]: https://www.rfc-editor.org/rfc/rfc2433.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct MsChapStartData;
+
+impl MsChapStartData {
+    /// The length of an MS-CHAP challenge.
+    const CHALLENGE_LENGTH: usize = 8;
+
+    /// The length of each of the LAN Manager & NT responses.
+    const RESPONSE_LENGTH: usize = 24;
+
+    /// The flag at the end of the response that indicates the NT response should be used ([RFC2433 section 5]).
+    ///
+    /// [RFC2433 section 5]: https://www.rfc-editor.org/rfc/rfc2433.html#section-5
+    const USE_NT_RESPONSE: u8 = 1;
+
+    /// Assembles the data field from the PPP ID, the challenge & the password.
+    fn data(ppp_id: u8, challenge: [u8; Self::CHALLENGE_LENGTH], password: &str) -> Vec<u8> {
+        // "the data field is a concatenation of the PPP id, the MS-CHAP challenge and the MS-CHAP response"
+        // RFC8907 section 5.4.2.4: https://www.rfc-editor.org/rfc/rfc8907.html#section-5.4.2.4-2
+        let mut data = vec![ppp_id];
+        data.extend(challenge);
+
+        // the LAN Manager response is deprecated & zeroed out, since the NT response is always used
+        data.extend([0; Self::RESPONSE_LENGTH]);
+        data.extend(Self::nt_response(challenge, password));
+        data.push(Self::USE_NT_RESPONSE);
+
+        data
+    }
+
+    /// Computes the NT response to a challenge (`NtChallengeResponse()` in RFC2433 appendix A).
+    fn nt_response(
+        challenge: [u8; Self::CHALLENGE_LENGTH],
+        password: &str,
+    ) -> [u8; Self::RESPONSE_LENGTH] {
+        // the password hash is the MD4 hash of the password in UTF-16LE (NtPasswordHash())
+        let mut hasher = Md4::new();
+        for unit in password.encode_utf16() {
+            hasher.update(unit.to_le_bytes());
+        }
+
+        // the hash is zero-padded to 21 bytes & split into three 7-byte DES keys (ChallengeResponse())
+        let mut padded_hash = [0; 21];
+        padded_hash[..16].copy_from_slice(&hasher.finalize());
+
+        let mut response = [0; Self::RESPONSE_LENGTH];
+        for (key, block) in padded_hash.chunks(7).zip(response.chunks_mut(8)) {
+            block.copy_from_slice(&challenge);
+
+            // SAFETY: the chunks of the padded hash are all exactly 7 bytes long
+            let cipher = Des::new(&expand_des_key(key.try_into().unwrap()).into());
+            cipher.encrypt_block(block.into());
+        }
+
+        response
+    }
+}
+
+impl StartDataBuilder for MsChapStartData {
+    fn build(&self, context: &SessionContext, credential: &str) -> Result<StartData, ClientError> {
+        self.build_with_rng(context, credential, &mut rand::thread_rng())
+    }
+
+    fn build_with_rng(
+        &self,
+        _context: &SessionContext,
+        credential: &str,
+        rng: &mut dyn RngCore,
+    ) -> Result<StartData, ClientError> {
+        Ok(StartData {
+            authentication_type: AuthenticationType::MsChap,
+            minor_version: MinorVersion::V1,
+            data: Self::data(rng.gen(), rng.gen(), credential),
+        })
+    }
+}
+
+/// Spreads a 56-bit key over 8 bytes as DES expects, leaving the (ignored) parity bit of each byte unset.
+fn expand_des_key(key: [u8; 7]) -> [u8; 8] {
+    let bits = key
+        .iter()
+        .fold(0u64, |bits, &byte| (bits << 8) | u64::from(byte));
+
+    let mut expanded = [0; 8];
+    for (index, byte) in expanded.iter_mut().enumerate() {
+        // each byte takes the next 7 bits of the key, starting from the most significant ones
+        let shift = 49 - 7 * index;
+
+        // NOTE: the mask ensures the truncation to u8 is lossless
+        *byte = (((bits >> shift) & 0x7f) as u8) << 1;
+    }
+
+    expanded
+}
@@ -0,0 +1,116 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tacacs_plus_protocol::AuthenticationType;
+
+use super::{ChapStartData, MsChapStartData, PapStartData, StartDataBuilder};
+use crate::{ClientError, ContextBuilder};
+
+#[test]
+fn pap_data_is_password() {
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let start_data = PapStartData.build(&context, "hunter2").unwrap();
+
+    assert_eq!(start_data.authentication_type, AuthenticationType::Pap);
+    assert_eq!(start_data.data, b"hunter2");
+}
+
+#[test]
+fn pap_password_length_limit_reported() {
+    let limit = crate::AuthenticationType::Pap.max_password_len().unwrap();
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+
+    let longest = "a".repeat(limit);
+    assert!(PapStartData.build(&context, &longest).is_ok());
+
+    let too_long = "a".repeat(limit + 1);
+    let error = PapStartData.build(&context, &too_long).unwrap_err();
+    assert!(
+        matches!(error, ClientError::PasswordTooLong { length, limit: 255 } if length == limit + 1),
+        "unexpected error: {error:?}"
+    );
+
+    // CHAP only sends a response computed from the password, so its length doesn't matter
+    assert_eq!(crate::AuthenticationType::Chap.max_password_len(), None);
+    assert!(ChapStartData::new().build(&context, &too_long).is_ok());
+}
+
+#[test]
+fn chap_data_layout() {
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let start_data = ChapStartData::new().build(&context, "hunter2").unwrap();
+
+    assert_eq!(start_data.authentication_type, AuthenticationType::Chap);
+
+    // PPP ID + 16-byte challenge + 16-byte MD5 response
+    assert_eq!(start_data.data.len(), 1 + 16 + 16);
+}
+
+#[test]
+fn chap_challenge_length_and_ppp_id_configurable() {
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let builder = ChapStartData::new()
+        .with_challenge_length(ChapStartData::MAX_CHALLENGE_LENGTH)
+        .unwrap()
+        .with_ppp_id(42);
+
+    let start_data = builder.build(&context, "hunter2").unwrap();
+    assert_eq!(start_data.data[0], 42);
+    assert_eq!(start_data.data.len(), usize::from(u8::MAX));
+
+    assert_eq!(ChapStartData::new().with_challenge_length(0), None);
+    assert_eq!(
+        ChapStartData::new().with_challenge_length(ChapStartData::MAX_CHALLENGE_LENGTH + 1),
+        None
+    );
+}
+
+#[test]
+fn challenges_drawn_from_provided_rng() {
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let builders: [&dyn StartDataBuilder; 2] = [&ChapStartData::new(), &MsChapStartData];
+
+    for builder in builders {
+        let build = |seed| {
+            builder
+                .build_with_rng(&context, "hunter2", &mut StdRng::seed_from_u64(seed))
+                .unwrap()
+        };
+
+        assert_eq!(build(1), build(1));
+        assert_ne!(build(1), build(2));
+    }
+}
+
+#[test]
+fn mschap_nt_response_matches_rfc_example() {
+    // example values from RFC2433 appendix B.1
+    let challenge = [0x10, 0x2d, 0xb5, 0xdf, 0x08, 0x5d, 0x30, 0x41];
+    let expected_response = [
+        0x4e, 0x9d, 0x3c, 0x8f, 0x9c, 0xfd, 0x38, 0x5d, 0x5b, 0xf4, 0xd3, 0x24, 0x67, 0x91, 0x95,
+        0x6c, 0xa4, 0xc3, 0x51, 0xab, 0x40, 0x9a, 0x3d, 0x61,
+    ];
+
+    assert_eq!(
+        MsChapStartData::nt_response(challenge, "MyPw"),
+        expected_response
+    );
+}
+
+#[test]
+fn mschap_data_layout() {
+    let challenge = [1, 2, 3, 4, 5, 6, 7, 8];
+    let data = MsChapStartData::data(42, challenge, "MyPw");
+
+    // PPP ID + 8-byte challenge + 49-byte response
+    assert_eq!(data.len(), 1 + 8 + 49);
+    assert_eq!(data[0], 42);
+    assert_eq!(data[1..9], challenge);
+
+    // LAN Manager response is unused, and the NT response flag is set
+    assert!(data[9..33].iter().all(|&byte| byte == 0));
+    assert_eq!(
+        data[33..57],
+        MsChapStartData::nt_response(challenge, "MyPw")
+    );
+    assert_eq!(data[57], 1);
+}
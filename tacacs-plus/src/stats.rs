@@ -0,0 +1,220 @@
+//! Counters describing the activity of a client, for operators that don't have a full metrics backend.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use super::{OperationKind, ResponseStatus};
+
+#[cfg(test)]
+mod tests;
+
+/// A snapshot of the counters of a client, as returned from [`Client::stats()`](super::Client::stats).
+///
+/// Counters are shared between clones of a client and only ever increase, so rates can be computed by comparing
+/// successive snapshots. The only exception is [`queued_sessions`](Self::queued_sessions), which is the current depth
+/// of the session queue at the time of the snapshot.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ClientStats {
+    /// The outcomes of authentication sessions.
+    pub authentication: SessionCounts,
+
+    /// The outcomes of authorization sessions.
+    pub authorization: SessionCounts,
+
+    /// The outcomes of accounting sessions.
+    pub accounting: SessionCounts,
+
+    /// The number of bytes written to connections, including packet headers.
+    pub bytes_sent: u64,
+
+    /// The number of bytes read from connections, including packet headers.
+    pub bytes_received: u64,
+
+    /// The number of connections opened after the first one, e.g. since the server doesn't support single connection
+    /// mode or closed the previous connection.
+    pub reconnects: u64,
+
+    /// The number of authorization requests sent under the session id of an identical request that wasn't answered,
+    /// if [deduplication](super::ClientBuilder::deduplicate_authorization) is enabled.
+    pub repeated_authorizations: u64,
+
+    /// The average time between sending a packet and receiving the server's reply, or `None` if no replies have been
+    /// received yet.
+    pub average_rtt: Option<Duration>,
+
+    /// The number of sessions currently waiting for the connection, by priority.
+    pub queued_sessions: QueueDepth,
+}
+
+impl ClientStats {
+    /// The outcomes of the sessions for an operation.
+    pub fn sessions(&self, operation: OperationKind) -> SessionCounts {
+        match operation {
+            OperationKind::Authentication => self.authentication,
+            OperationKind::Authorization => self.authorization,
+            OperationKind::Accounting => self.accounting,
+        }
+    }
+}
+
+/// The number of sessions for an operation that ended with each outcome.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SessionCounts {
+    /// Sessions where the server granted the request.
+    pub succeeded: u64,
+
+    /// Sessions where the server denied the request.
+    pub failed: u64,
+
+    /// Sessions that ended without a decision, i.e. with a [`ClientError`](super::ClientError) (including errors
+    /// reported by the server) or by being cancelled.
+    pub errored: u64,
+}
+
+/// The number of sessions waiting for the connection of a client for each [`SessionPriority`](super::SessionPriority).
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct QueueDepth {
+    /// Sessions waiting with [`Interactive`](super::SessionPriority::Interactive) priority.
+    pub interactive: u64,
+
+    /// Sessions waiting with [`Standard`](super::SessionPriority::Standard) priority.
+    pub standard: u64,
+
+    /// Sessions waiting with [`Background`](super::SessionPriority::Background) priority.
+    pub background: u64,
+}
+
+impl QueueDepth {
+    /// The total number of waiting sessions, regardless of their priority.
+    pub fn total(&self) -> u64 {
+        self.interactive + self.standard + self.background
+    }
+}
+
+/// The atomic counters behind a [`SessionCounts`].
+#[derive(Debug, Default)]
+struct OutcomeCounters {
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    errored: AtomicU64,
+}
+
+impl OutcomeCounters {
+    fn load(&self) -> SessionCounts {
+        SessionCounts {
+            succeeded: self.succeeded.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            errored: self.errored.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The counters of a client, updated by both the outer & inner client without locking.
+#[derive(Debug, Default)]
+pub(crate) struct StatsRecorder {
+    authentication: OutcomeCounters,
+    authorization: OutcomeCounters,
+    accounting: OutcomeCounters,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    connections_opened: AtomicU64,
+    repeated_authorizations: AtomicU64,
+    rtt_total_micros: AtomicU64,
+    rtt_samples: AtomicU64,
+}
+
+impl StatsRecorder {
+    /// Starts recording the outcome of a session, which counts as an error unless it's [finished](SessionRecord::finish)
+    /// with a decision from the server.
+    pub(crate) fn start_session(&self, operation: OperationKind) -> SessionRecord<'_> {
+        let counters = match operation {
+            OperationKind::Authentication => &self.authentication,
+            OperationKind::Authorization => &self.authorization,
+            OperationKind::Accounting => &self.accounting,
+        };
+
+        SessionRecord {
+            counters,
+            finished: false,
+        }
+    }
+
+    pub(crate) fn bytes_sent(&self, count: usize) {
+        self.bytes_sent.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bytes_received(&self, count: usize) {
+        self.bytes_received
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn authorization_repeated(&self) {
+        self.repeated_authorizations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn round_trip(&self, rtt: Duration) {
+        let micros = u64::try_from(rtt.as_micros()).unwrap_or(u64::MAX);
+        self.rtt_total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.rtt_samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the current counter values.
+    ///
+    /// The session queue isn't tracked here, so the depth of the queue is left for the caller to fill in.
+    pub(crate) fn snapshot(&self) -> ClientStats {
+        let rtt_samples = self.rtt_samples.load(Ordering::Relaxed);
+        let average_rtt = (rtt_samples > 0).then(|| {
+            Duration::from_micros(self.rtt_total_micros.load(Ordering::Relaxed) / rtt_samples)
+        });
+
+        ClientStats {
+            authentication: self.authentication.load(),
+            authorization: self.authorization.load(),
+            accounting: self.accounting.load(),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            reconnects: self
+                .connections_opened
+                .load(Ordering::Relaxed)
+                .saturating_sub(1),
+            repeated_authorizations: self.repeated_authorizations.load(Ordering::Relaxed),
+            average_rtt,
+            queued_sessions: QueueDepth::default(),
+        }
+    }
+}
+
+/// The outcome of a session in progress, which is counted as an error if it's dropped without being finished (e.g.,
+/// since an error was returned early or the session was cancelled).
+pub(crate) struct SessionRecord<'recorder> {
+    counters: &'recorder OutcomeCounters,
+    finished: bool,
+}
+
+impl SessionRecord<'_> {
+    /// Records the decision the server made in the session.
+    pub(crate) fn finish(mut self, status: ResponseStatus) {
+        let counter = match status {
+            ResponseStatus::Success => &self.counters.succeeded,
+            ResponseStatus::Failure => &self.counters.failed,
+        };
+
+        counter.fetch_add(1, Ordering::Relaxed);
+        self.finished = true;
+    }
+}
+
+impl Drop for SessionRecord<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.counters.errored.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{AuthenticationType, ClientBuilder, ContextBuilder};
+
+#[test]
+fn unfinished_sessions_counted_as_errors() {
+    let recorder = StatsRecorder::default();
+
+    recorder
+        .start_session(OperationKind::Authorization)
+        .finish(ResponseStatus::Success);
+    recorder
+        .start_session(OperationKind::Authorization)
+        .finish(ResponseStatus::Failure);
+    drop(recorder.start_session(OperationKind::Authorization));
+
+    let stats = recorder.snapshot();
+    assert_eq!(
+        stats.sessions(OperationKind::Authorization),
+        SessionCounts {
+            succeeded: 1,
+            failed: 1,
+            errored: 1,
+        }
+    );
+    assert_eq!(stats.authentication, SessionCounts::default());
+    assert_eq!(stats.accounting, SessionCounts::default());
+}
+
+#[test]
+fn round_trip_times_averaged() {
+    let recorder = StatsRecorder::default();
+    assert_eq!(recorder.snapshot().average_rtt, None);
+
+    recorder.round_trip(Duration::from_millis(10));
+    recorder.round_trip(Duration::from_millis(30));
+    assert_eq!(
+        recorder.snapshot().average_rtt,
+        Some(Duration::from_millis(20))
+    );
+}
+
+#[test]
+fn first_connection_not_a_reconnect() {
+    let recorder = StatsRecorder::default();
+    assert_eq!(recorder.snapshot().reconnects, 0);
+
+    recorder.connection_opened();
+    assert_eq!(recorder.snapshot().reconnects, 0);
+
+    recorder.connection_opened();
+    assert_eq!(recorder.snapshot().reconnects, 1);
+}
+
+#[tokio::test]
+async fn client_sessions_recorded() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), true);
+        Box::pin(async move { Ok(server) })
+    }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let _ = client.authorize(context.clone(), []).await.unwrap();
+    let _ = client
+        .authenticate(context, "password", AuthenticationType::Pap)
+        .await
+        .unwrap();
+
+    let stats = client.stats();
+    assert_eq!(stats.authorization.succeeded, 1);
+    assert_eq!(stats.authentication.failed, 1);
+
+    // both sessions went over the same connection, with each request answered by a 6-byte body
+    assert_eq!(stats.reconnects, 0);
+    assert_eq!(stats.bytes_received, 2 * (12 + 6));
+
+    let sent: usize = received
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, body)| 12 + body.len())
+        .sum();
+    assert_eq!(stats.bytes_sent, sent as u64);
+    assert!(stats.average_rtt.is_some());
+}
@@ -1,16 +1,28 @@
+use std::borrow::Cow;
 use std::marker::Unpin;
-use std::time::{Instant, SystemTime, SystemTimeError, UNIX_EPOCH};
+use std::mem;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, SystemTimeError, UNIX_EPOCH};
 
 use futures::{AsyncRead, AsyncWrite};
 use tacacs_plus_protocol::accounting::{Flags, ReplyOwned, Request, Status};
 use tacacs_plus_protocol::Packet;
 use tacacs_plus_protocol::{Argument, Arguments, FieldText};
 use tacacs_plus_protocol::{
-    AuthenticationContext, AuthenticationService, AuthenticationType, MinorVersion,
+    AuthenticationContext, AuthenticationService, MinorVersion, PacketFlags, PrivilegeLevel,
 };
 
-use super::response::AccountingResponse;
-use super::{Client, ClientError, SessionContext};
+use super::degraded::{AccountingFallback, LocalAccountingRecord};
+use super::error::with_cleanup_result;
+use super::middleware;
+use super::response::{AccountingResponse, SessionInfo};
+use super::{Client, ClientError, Clock, OperationKind, ResponseStatus, SessionContext};
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "session-events")]
+use super::SessionEvent;
 
 // Arguments specified in RFC8907 section 8.3.
 /// Task ID, used for grouping together records from the same task.
@@ -25,7 +37,18 @@ const STOP_TIME: &str = "stop_time";
 /// The time this task has taken so far, in seconds.
 const ELAPSED_TIME: &str = "elapsed_time";
 
+/// The privilege level of the user, as specified for authorization in RFC8907 section 6.1.
+const PRIV_LVL: &str = "priv-lvl";
+
+/// The privilege level of the user before a change, which isn't specified by the RFC and so is sent as optional.
+const OLD_PRIV_LVL: &str = "old-priv-lvl";
+
 /// An ongoing task whose status is tracked via TACACS+ accounting.
+///
+/// A task should always be finished with [`stop()`](Self::stop), or explicitly [`abandon()`](Self::abandon)ed.
+/// Since stopping a task requires communicating with the server, which can't be done when a task is dropped, a task
+/// that is dropped otherwise is passed to the handler registered with
+/// [`ClientBuilder::accounting_abandon_handler()`](super::ClientBuilder::accounting_abandon_handler) instead, if any.
 #[must_use = "A task should eventually be marked as finished by calling the `stop()` method."]
 pub struct AccountingTask<C> {
     /// The client associated with this task.
@@ -38,23 +61,91 @@ pub struct AccountingTask<C> {
     /// The context associated with this task.
     context: SessionContext,
 
-    /// When this task was created/started.
-    start_time: Instant,
+    /// When this task was started according to the monotonic time of the client's clock, which all durations are measured from.
+    start_instant: Instant,
+
+    /// When this task was started according to the wall-clock time of the client's clock.
+    ///
+    /// Later timestamps are derived from this and the monotonic time elapsed since, so adjustments to the wall clock
+    /// during a task can't make its records inconsistent (e.g., a `stop_time` before `start_time` + `elapsed_time`).
+    start_timestamp: SystemTime,
+
+    /// The clock of the associated client, which is kept separately so it's available when the task is dropped.
+    clock: Arc<dyn Clock>,
+
+    /// The handler to call if this task is dropped before being stopped, which is cleared once that can no longer happen.
+    abandon_handler: Option<AbandonHandler>,
+}
+
+/// Information about an [`AccountingTask`] that was dropped without a stop record being acknowledged by the server,
+/// as passed to the handler registered via [`ClientBuilder::accounting_abandon_handler()`](super::ClientBuilder::accounting_abandon_handler).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AbandonedTask {
+    /// The ID of the task, as sent in the `task_id` argument of its records.
+    pub task_id: String,
+
+    /// The context the task was started with.
+    pub context: SessionContext,
+
+    /// How long the task ran before being dropped.
+    pub elapsed: Duration,
+}
+
+/// A callback for tasks that were dropped without being stopped.
+pub(super) type AbandonHandler = Arc<dyn Fn(&AbandonedTask) + Send + Sync>;
+
+impl<C> AccountingTask<C> {
+    /// Returns how long this task has been running according to the client's clock.
+    fn elapsed(&self) -> Duration {
+        // saturating, in case a custom clock isn't actually monotonic
+        self.clock
+            .instant()
+            .saturating_duration_since(self.start_instant)
+    }
+
+    /// Returns the wall-clock time for this task once some time has elapsed since its start.
+    ///
+    /// This fails if the start time was so far in the future (e.g., due to a skewed clock) that it can't be advanced.
+    fn timestamp_after(&self, elapsed: Duration) -> Result<SystemTime, ClientError> {
+        self.start_timestamp
+            .checked_add(elapsed)
+            .ok_or(ClientError::TaskDurationOverflow { duration: elapsed })
+    }
+
+    /// Drops this task without sending a stop record and without notifying the client's abandon handler.
+    ///
+    /// This is meant for tasks that are intentionally left unfinished, e.g. because the server is known to be unreachable
+    /// or the stop record is sent by a different process.
+    pub fn abandon(mut self) {
+        self.abandon_handler = None;
+    }
+}
+
+impl<C> Drop for AccountingTask<C> {
+    fn drop(&mut self) {
+        if let Some(handler) = self.abandon_handler.take() {
+            handler(&AbandonedTask {
+                task_id: mem::take(&mut self.id),
+                context: self.context.clone(),
+                elapsed: self.elapsed(),
+            });
+        }
+    }
 }
 
-/// Gets the Unix timestamp (in seconds) as a string, returning an error if
-/// the system clock is set before the Unix epoch.
-fn get_unix_timestamp_string() -> Result<String, SystemTimeError> {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
+/// Gets the Unix timestamp (in seconds) of a point in time as a string, returning an error if
+/// it is before the Unix epoch.
+fn get_unix_timestamp_string(time: SystemTime) -> Result<String, SystemTimeError> {
+    time.duration_since(UNIX_EPOCH)
         .map(|duration| duration.as_secs().to_string())
 }
 
 impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
     /// Sends a start accounting record to the TACACS+ server, returning the resulting associated [`Task`].
     ///
-    /// The `task_id` and `start_time` arguments from [RFC8907 section 8.3] are added internally.
-    /// Note that setting `start_time` requires the system clock to be set after the Unix epoch; otherwise,
+    /// The `task_id` and `start_time` arguments from [RFC8907 section 8.3] are added internally, along with the
+    /// client's [`LocaleAttributes`](super::LocaleAttributes) if the `locale-attributes` feature is enabled.
+    /// Note that setting `start_time` requires the client's [`Clock`] to be set after the Unix epoch; otherwise,
     /// an error is returned.
     ///
     /// This method should only be called once per task.
@@ -63,11 +154,20 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
         context: SessionContext,
         arguments: A,
     ) -> Result<(Self, AccountingResponse), ClientError> {
-        let task = Self {
+        client.check_accounting_arguments(arguments.as_ref())?;
+
+        let timestamp = client.clock.now();
+
+        // the abandon handler is only attached once the start record went through, since there's nothing to stop
+        // before that
+        let mut task = Self {
             client,
             id: uuid::Uuid::new_v4().to_string(),
-            context,
-            start_time: Instant::now(),
+            context: client.normalize_context(Cow::Owned(context)).into_owned(),
+            start_instant: client.clock.instant(),
+            start_timestamp: timestamp,
+            clock: client.clock.clone(),
+            abandon_handler: None,
         };
 
         // prepend a couple of informational arguments specified in RFC 8907 section 8.3
@@ -81,17 +181,23 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
             Argument::new(
                 // SAFETY: both fields are known to always be valid ASCII (hardcoded/purely numeric)
                 FieldText::try_from(START_TIME).unwrap(),
-                FieldText::try_from(get_unix_timestamp_string()?).unwrap(),
+                FieldText::try_from(get_unix_timestamp_string(timestamp)?).unwrap(),
                 true,
             )?,
         ];
         full_arguments.extend_from_slice(arguments.as_ref());
 
+        #[cfg(feature = "locale-attributes")]
+        if let Some(attributes) = &client.locale_attributes {
+            attributes.append_arguments(&mut full_arguments)?;
+        }
+
         // perform accounting request with task info/arguments
         let response = task
-            .make_request(Flags::StartRecord, full_arguments)
+            .make_request(Flags::StartRecord, timestamp, full_arguments)
             .await?;
 
+        task.abandon_handler = client.abandon_handler.clone();
         Ok((task, response))
     }
 
@@ -99,12 +205,20 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
     ///
     /// The `task_id` and `elapsed_time` arguments from [RFC8907 section 8.3] are added internally.
     ///
+    /// # Cancellation
+    ///
+    /// An update whose future is dropped after it was sent may or may not have been recorded by the server. The task
+    /// itself can still be updated or stopped afterwards.
+    ///
     /// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
     pub async fn update<'args, A: AsRef<[Argument<'args>]>>(
         &self,
         arguments: A,
     ) -> Result<AccountingResponse, ClientError> {
-        let elapsed_secs = Instant::now().duration_since(self.start_time).as_secs();
+        self.client.check_accounting_arguments(arguments.as_ref())?;
+
+        // the same elapsed time is used for the record's timestamp to keep the two consistent
+        let elapsed = self.elapsed();
         let mut full_arguments = vec![
             Argument::new(
                 // SAFETY: both fields are known to always be valid ASCII (hardcoded/UUID)
@@ -115,27 +229,76 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
             Argument::new(
                 // SAFETY: both fields are known to always be valid ASCII (hardcoded/purely numeric)
                 FieldText::try_from(ELAPSED_TIME).unwrap(),
-                FieldText::try_from(elapsed_secs.to_string()).unwrap(),
+                FieldText::try_from(elapsed.as_secs().to_string()).unwrap(),
                 true,
             )?,
         ];
         full_arguments.extend_from_slice(arguments.as_ref());
 
-        self.make_request(Flags::WatchdogUpdate, full_arguments)
-            .await
+        self.make_request(
+            Flags::WatchdogUpdate,
+            self.timestamp_after(elapsed)?,
+            full_arguments,
+        )
+        .await
+    }
+
+    /// Records a change of the user's privilege level during this task, e.g. after escalating via `enable`.
+    ///
+    /// This sends an update with the new level as a mandatory `priv-lvl` argument and the old one as an optional
+    /// `old-priv-lvl` argument, along with the arguments added by [`update()`](Self::update). The privilege level of the
+    /// task's context is changed to `new` beforehand, so this and all later records carry the new level in their body.
+    ///
+    /// # Cancellation
+    ///
+    /// As with [`update()`](Self::update), a record whose future is dropped may or may not have been recorded by the
+    /// server; the task's privilege level is changed regardless.
+    pub async fn record_priv_change(
+        &mut self,
+        old: PrivilegeLevel,
+        new: PrivilegeLevel,
+    ) -> Result<AccountingResponse, ClientError> {
+        self.context.privilege_level = new;
+
+        let arguments = [
+            // SAFETY: the names are hardcoded and privilege levels are formatted as integers, so both are valid ASCII
+            Argument::new(
+                FieldText::try_from(PRIV_LVL).unwrap(),
+                FieldText::try_from(new.to_string()).unwrap(),
+                true,
+            )?,
+            Argument::new(
+                FieldText::try_from(OLD_PRIV_LVL).unwrap(),
+                FieldText::try_from(old.to_string()).unwrap(),
+                false,
+            )?,
+        ];
+
+        self.update(arguments).await
     }
 
     /// Signals to the TACACS+ server that this task has completed.
     ///
-    /// Since this should only be done once, this consumes the task.
+    /// Since this should only be done once, this consumes the task. If the stop record isn't acknowledged by the server
+    /// (i.e., an error is returned), the task is still treated as abandoned and passed to the client's abandon handler, if any.
+    ///
+    /// The `stop_time` and `task_id` arguments from [RFC8907 section 8.3] are also added internally, where `stop_time`
+    /// is derived from the task's start time and the (monotonic) time elapsed since rather than read from the wall clock.
     ///
-    /// The `stop_time` and `task_id` arguments from [RFC8907 section 8.3] are also added internally.
+    /// # Cancellation
+    ///
+    /// If the returned future is dropped, the task is dropped along with it without the stop record being acknowledged,
+    /// so it's passed to the abandon handler as described above; the server may still have recorded the stop.
     ///
     /// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
     pub async fn stop<'args, A: AsRef<[Argument<'args>]>>(
-        self,
+        mut self,
         arguments: A,
     ) -> Result<AccountingResponse, ClientError> {
+        self.client.check_accounting_arguments(arguments.as_ref())?;
+
+        let timestamp = self.timestamp_after(self.elapsed())?;
+
         let mut full_arguments = vec![
             // NOTE: TASK_ID + a random uuid should always constitute a valid argument
             // (name is nonempty/doesn't contain delimiter, length shouldn't overflow)
@@ -149,29 +312,136 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
             Argument::new(
                 // SAFETY: both fields are known to be valid ASCII
                 FieldText::try_from(STOP_TIME).unwrap(),
-                FieldText::try_from(get_unix_timestamp_string()?).unwrap(),
+                FieldText::try_from(get_unix_timestamp_string(timestamp)?).unwrap(),
                 true,
             )?,
         ];
         full_arguments.extend_from_slice(arguments.as_ref());
 
-        self.make_request(Flags::StopRecord, full_arguments).await
+        #[cfg(feature = "locale-attributes")]
+        if let Some(attributes) = &self.client.locale_attributes {
+            attributes.append_arguments(&mut full_arguments)?;
+        }
+
+        let response = self
+            .make_request(Flags::StopRecord, timestamp, full_arguments)
+            .await?;
+
+        // the task is only considered finished once the server acknowledged it as such
+        self.abandon_handler = None;
+
+        Ok(response)
+    }
+
+    /// Sends a standalone stop record for a task that has already completed, without a prior start record.
+    ///
+    /// The `task_id`, `start_time`, `stop_time` and `elapsed_time` arguments from [RFC8907 section 8.3] are
+    /// synthesized from the provided start time and duration, with a fresh task ID.
+    ///
+    /// [RFC8907 section 8.3]: https://www.rfc-editor.org/rfc/rfc8907.html#name-accounting-arguments
+    pub(super) async fn stop_once<'args, A: AsRef<[Argument<'args>]>>(
+        client: &'a Client<S>,
+        context: SessionContext,
+        arguments: A,
+        started_at: SystemTime,
+        duration: Duration,
+    ) -> Result<AccountingResponse, ClientError> {
+        client.check_accounting_arguments(arguments.as_ref())?;
+
+        // the task only exists to send the record, so there's nothing to report if it's dropped
+        let task = Self {
+            client,
+            id: uuid::Uuid::new_v4().to_string(),
+            context: client.normalize_context(Cow::Owned(context)).into_owned(),
+            start_instant: client.clock.instant(),
+            start_timestamp: started_at,
+            clock: client.clock.clone(),
+            abandon_handler: None,
+        };
+
+        let stop_timestamp = started_at
+            .checked_add(duration)
+            .ok_or(ClientError::TaskDurationOverflow { duration })?;
+
+        let mut full_arguments = vec![
+            Argument::new(
+                // SAFETY: both fields are known to always be valid ASCII (hardcoded/UUID)
+                FieldText::try_from(TASK_ID).unwrap(),
+                FieldText::try_from(&*task.id).unwrap(),
+                true,
+            )?,
+            // SAFETY: the names of the timing arguments are hardcoded & their values are purely numeric,
+            // so they are known to always be valid ASCII
+            Argument::new(
+                FieldText::try_from(START_TIME).unwrap(),
+                FieldText::try_from(get_unix_timestamp_string(started_at)?).unwrap(),
+                true,
+            )?,
+            Argument::new(
+                FieldText::try_from(STOP_TIME).unwrap(),
+                FieldText::try_from(get_unix_timestamp_string(stop_timestamp)?).unwrap(),
+                true,
+            )?,
+            Argument::new(
+                FieldText::try_from(ELAPSED_TIME).unwrap(),
+                FieldText::try_from(duration.as_secs().to_string()).unwrap(),
+                true,
+            )?,
+        ];
+        full_arguments.extend_from_slice(arguments.as_ref());
+
+        #[cfg(feature = "locale-attributes")]
+        if let Some(attributes) = &client.locale_attributes {
+            attributes.append_arguments(&mut full_arguments)?;
+        }
+
+        task.make_request(Flags::StopRecord, stop_timestamp, full_arguments)
+            .await
     }
 
     async fn make_request(
         &self,
         flags: Flags,
+        timestamp: SystemTime,
         arguments: Vec<Argument<'_>>,
     ) -> Result<AccountingResponse, ClientError> {
+        let operation = middleware::WithArguments {
+            kind: OperationKind::Accounting,
+            context: &self.context,
+            arguments: &arguments,
+        };
+
+        middleware::run(
+            &self.client.middlewares,
+            &operation,
+            // any status other than success is reported as an error
+            |_| ResponseStatus::Success,
+            || self.perform_request(flags, timestamp, &arguments),
+        )
+        .await
+    }
+
+    /// Sends an accounting record in a new session, without passing it through the client's middlewares.
+    async fn perform_request(
+        &self,
+        flags: Flags,
+        timestamp: SystemTime,
+        arguments: &[Argument<'_>],
+    ) -> Result<AccountingResponse, ClientError> {
+        let fallback = self.client.accounting_fallback.as_deref();
+        if let Some(fallback) = fallback.filter(|fallback| fallback.is_degraded()) {
+            return Ok(self.log_locally(fallback, flags, timestamp, arguments));
+        }
+
         // send accounting request & ensure reply ok
         let request_packet = Packet::new(
             self.client.make_header(1, MinorVersion::Default),
             Request::new(
-                flags,
+                self.client.compatibility_profile.accounting_flags(flags),
                 self.context.authentication_method(),
                 AuthenticationContext {
                     privilege_level: self.context.privilege_level,
-                    authentication_type: AuthenticationType::NotSet,
+                    authentication_type: self.context.authentication_type(),
                     // TODO: should we allow externally setting this?
                     service: AuthenticationService::Login,
                 },
@@ -180,35 +450,139 @@ impl<'a, S: AsyncRead + AsyncWrite + Unpin> AccountingTask<&'a Client<S>> {
             ),
         );
 
-        let reply = {
+        let record = self.client.stats.start_session(OperationKind::Accounting);
+
+        let (reply, cleanup_result) = {
             let secret_key = self.client.secret.as_deref();
 
-            let mut inner = self.client.inner.lock().await;
+            let mut inner = self
+                .client
+                .begin_session(self.context.priority_for(OperationKind::Accounting))
+                .await;
             inner.send_packet(request_packet, secret_key).await?;
 
             let reply: Packet<ReplyOwned> = inner.receive_packet(secret_key, 2).await?;
 
             // update inner state based on response
             inner.set_internal_single_connect_status(reply.header());
-            inner
-                .post_session_cleanup(reply.body().status == Status::Error)
-                .await?;
+            let cleanup_result = inner
+                .post_session_cleanup(
+                    reply.body().status == Status::Error
+                        && self.client.compatibility_profile.error_closes_connection(),
+                )
+                .await;
 
-            reply
+            (reply, cleanup_result)
         };
 
-        match reply.body().status {
+        self.client.report_admin_message(
+            OperationKind::Accounting,
+            &reply.body().data,
+            reply.header(),
+        );
+        let user_message = self.client.process_user_message(
+            OperationKind::Accounting,
+            reply.body().server_message.clone(),
+        );
+
+        if reply.body().status == Status::Success {
+            emit_event!(
+                self.client.events,
+                SessionEvent::Decision {
+                    operation: OperationKind::Accounting,
+                    session: SessionInfo::from_header(reply.header()),
+                    status: ResponseStatus::Success,
+                }
+            );
+            record.finish(ResponseStatus::Success);
+        }
+
+        let result = match reply.body().status {
             Status::Success => Ok(AccountingResponse {
-                user_message: reply.body().server_message.clone(),
+                raw_status: Status::Success,
+                user_message,
                 admin_message: reply.body().data.clone(),
+                flags,
+                timestamp,
+                session: SessionInfo::from_header(reply.header()),
+                logged_locally: false,
             }),
             // NOTE: this also treats FOLLOW status as an error, which isn't directly specified by the RFC
             // but sort of mirrors the prescribed behavior for a FOLLOW in authentication
             bad_status => Err(ClientError::AccountingError {
                 status: bad_status,
-                user_message: reply.body().server_message.clone(),
+                user_message,
                 admin_message: reply.body().data.clone(),
             }),
+        };
+
+        let result = match (fallback, result) {
+            (Some(fallback), Ok(response)) => {
+                fallback.record_success();
+                Ok(response)
+            }
+            (
+                Some(fallback),
+                Err(
+                    error @ ClientError::AccountingError {
+                        status: Status::Error,
+                        ..
+                    },
+                ),
+            ) => {
+                let newly_degraded = fallback.record_error();
+
+                #[cfg(feature = "session-events")]
+                if let Some(consecutive_errors) = newly_degraded {
+                    self.client
+                        .events
+                        .emit(SessionEvent::AccountingDegraded { consecutive_errors });
+                }
+
+                // the record that degraded accounting is logged as well, as are any that were in flight at the time
+                if newly_degraded.is_some() || fallback.is_degraded() {
+                    Ok(self.log_locally(fallback, flags, timestamp, arguments))
+                } else {
+                    Err(error)
+                }
+            }
+            (_, result) => result,
+        };
+
+        with_cleanup_result(result, cleanup_result)
+    }
+
+    /// Passes a record to the local log instead of sending it to the server, since accounting is degraded.
+    fn log_locally(
+        &self,
+        fallback: &AccountingFallback,
+        flags: Flags,
+        timestamp: SystemTime,
+        arguments: &[Argument<'_>],
+    ) -> AccountingResponse {
+        fallback.log(&LocalAccountingRecord {
+            flags,
+            context: self.context.clone(),
+            arguments: arguments
+                .iter()
+                .cloned()
+                .map(Argument::into_owned)
+                .collect(),
+            timestamp,
+        });
+
+        AccountingResponse {
+            raw_status: Status::Success,
+            user_message: String::new(),
+            admin_message: String::new(),
+            flags,
+            timestamp,
+            session: SessionInfo {
+                session_id: 0,
+                final_sequence_number: 0,
+                flags: PacketFlags::empty(),
+            },
+            logged_locally: true,
         }
     }
 }
@@ -0,0 +1,400 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use futures::io::Cursor;
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::accounting::Flags;
+use tacacs_plus_protocol::PrivilegeLevel;
+
+use super::AccountingTask;
+use crate::{Client, ClientBuilder, ClientError, Clock, ContextBuilder};
+
+fn client_counting_abandoned_tasks(count: Arc<AtomicUsize>) -> Client<Cursor<Vec<u8>>> {
+    ClientBuilder::new()
+        .accounting_abandon_handler(move |task| {
+            assert_eq!(task.task_id, "task");
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+        .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::new())) })))
+}
+
+fn task(client: &Client<Cursor<Vec<u8>>>) -> AccountingTask<&Client<Cursor<Vec<u8>>>> {
+    AccountingTask {
+        client,
+        id: String::from("task"),
+        context: ContextBuilder::new(String::from("user")).build().unwrap(),
+        start_instant: client.clock.instant(),
+        start_timestamp: client.clock.now(),
+        clock: client.clock.clone(),
+        abandon_handler: client.abandon_handler.clone(),
+    }
+}
+
+#[test]
+fn dropped_task_passed_to_handler() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let client = client_counting_abandoned_tasks(count.clone());
+
+    drop(task(&client));
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn explicitly_abandoned_task_not_passed_to_handler() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let client = client_counting_abandoned_tasks(count.clone());
+
+    task(&client).abandon();
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn unacknowledged_stop_passed_to_handler() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let client = client_counting_abandoned_tasks(count.clone());
+
+    // the server "closes" the connection without replying, so the stop record is never acknowledged
+    task(&client)
+        .stop([])
+        .await
+        .expect_err("stop should fail without a reply");
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn failed_start_not_passed_to_handler() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handler_count = count.clone();
+    let client = ClientBuilder::new()
+        .accounting_abandon_handler(move |_| {
+            handler_count.fetch_add(1, Ordering::SeqCst);
+        })
+        .build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::new())) })));
+
+    // the start record is never acknowledged, so there's no task that would need a stop record
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let result = client.account_begin(context, []).await;
+    assert!(result.is_err(), "start should fail without a reply");
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+/// A clock that only advances when told to, whose wall-clock time can also be adjusted independently.
+#[derive(Debug, Clone)]
+struct ManualClock {
+    base: Instant,
+    wall_offset_secs: Arc<AtomicI64>,
+    monotonic_offset_secs: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// The wall-clock time (in seconds since the Unix epoch) before any adjustments.
+    const START_SECS: i64 = 1_000_000_000;
+
+    fn new() -> Self {
+        Self {
+            base: Instant::now(),
+            wall_offset_secs: Arc::new(AtomicI64::new(0)),
+            monotonic_offset_secs: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Lets time pass, advancing both the wall clock and the monotonic clock.
+    fn advance(&self, secs: u64) {
+        self.monotonic_offset_secs.fetch_add(secs, Ordering::SeqCst);
+        self.wall_offset_secs
+            .fetch_add(secs.try_into().unwrap(), Ordering::SeqCst);
+    }
+
+    /// Lets time pass on the monotonic clock only, leaving the wall clock where it is.
+    fn advance_monotonic(&self, secs: u64) {
+        self.monotonic_offset_secs.fetch_add(secs, Ordering::SeqCst);
+    }
+
+    /// Adjusts only the wall clock, as e.g. NTP or an administrator would.
+    fn adjust_wall_clock(&self, secs: i64) {
+        self.wall_offset_secs.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        let secs = Self::START_SECS + self.wall_offset_secs.load(Ordering::SeqCst);
+        UNIX_EPOCH + Duration::from_secs(secs.try_into().unwrap())
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + Duration::from_secs(self.monotonic_offset_secs.load(Ordering::SeqCst))
+    }
+}
+
+/// A connection that answers a request with a successful accounting reply, recording what was written to it.
+struct AccountingServer {
+    reply: Cursor<Vec<u8>>,
+    written: Arc<Mutex<Vec<u8>>>,
+    request_received: bool,
+}
+
+impl AccountingServer {
+    fn new(written: Arc<Mutex<Vec<u8>>>) -> Self {
+        #[rustfmt::skip]
+        let reply = vec![
+            // header: version 12.0, accounting, sequence number 2, unencrypted, session id 0, body length 5
+            0xc0, 0x03, 0x02, 0x01,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x05,
+            // body: empty server message & data, success status
+            0x00, 0x00, 0x00, 0x00, 0x01,
+        ];
+
+        Self {
+            reply: Cursor::new(reply),
+            written,
+            request_received: false,
+        }
+    }
+}
+
+impl AsyncRead for AccountingServer {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        // nothing is available before the request is sent, as with a real server; this is only polled once in that case
+        if self.request_received {
+            Pin::new(&mut self.reply).poll_read(cx, buf)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl AsyncWrite for AccountingServer {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.request_received = true;
+        self.written.lock().unwrap().extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Checks whether a byte string contains another, since the sent packets aren't deserialized by the client.
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+/// Creates a client using the given clock, along with a buffer of everything the client sends.
+fn accounting_client(clock: ManualClock) -> (Client<AccountingServer>, Arc<Mutex<Vec<u8>>>) {
+    let written = Arc::new(Mutex::new(Vec::new()));
+
+    let factory_written = written.clone();
+    let client = ClientBuilder::new().clock(clock).build(Box::new(move || {
+        let connection = AccountingServer::new(factory_written.clone());
+        Box::pin(async move { Ok(connection) })
+    }));
+
+    (client, written)
+}
+
+#[tokio::test]
+async fn timestamps_taken_from_client_clock() {
+    let clock = ManualClock::new();
+    let (client, written) = accounting_client(clock.clone());
+
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let (task, response) = client
+        .account_begin(context, [])
+        .await
+        .expect("start record should be acknowledged");
+    assert_eq!(
+        response.timestamp,
+        UNIX_EPOCH + Duration::from_secs(1_000_000_000)
+    );
+
+    clock.advance(5);
+    task.update([])
+        .await
+        .expect("update should be acknowledged");
+
+    clock.advance(5);
+    let response = task.stop([]).await.expect("stop should be acknowledged");
+    assert_eq!(
+        response.timestamp,
+        UNIX_EPOCH + Duration::from_secs(1_000_000_010)
+    );
+
+    let written = written.lock().unwrap();
+    assert!(contains(&written, "start_time=1000000000"));
+    assert!(contains(&written, "elapsed_time=5"));
+    assert!(contains(&written, "stop_time=1000000010"));
+}
+
+#[tokio::test]
+async fn wall_clock_adjustments_keep_records_consistent() {
+    for adjustment in [-3600, 3600] {
+        let clock = ManualClock::new();
+        let (client, written) = accounting_client(clock.clone());
+
+        let context = ContextBuilder::new(String::from("user")).build().unwrap();
+        let (task, _) = client
+            .account_begin(context, [])
+            .await
+            .expect("start record should be acknowledged");
+
+        // the wall clock jumps while the task is running, but only 10 seconds actually pass
+        clock.advance(5);
+        clock.adjust_wall_clock(adjustment);
+        task.update([])
+            .await
+            .expect("update should be acknowledged");
+
+        clock.advance(5);
+        let response = task.stop([]).await.expect("stop should be acknowledged");
+
+        // stop_time should still be exactly start_time + elapsed time
+        assert_eq!(
+            response.timestamp,
+            UNIX_EPOCH + Duration::from_secs(1_000_000_010),
+            "inconsistent stop timestamp after adjusting wall clock by {adjustment}s"
+        );
+
+        let written = written.lock().unwrap();
+        assert!(contains(&written, "start_time=1000000000"));
+        assert!(contains(&written, "elapsed_time=5"));
+        assert!(contains(&written, "stop_time=1000000010"));
+    }
+}
+
+#[tokio::test]
+async fn stop_only_record_synthesizes_timing_arguments() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let handler_count = count.clone();
+
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let factory_written = written.clone();
+    let client = ClientBuilder::new()
+        .accounting_abandon_handler(move |_| {
+            handler_count.fetch_add(1, Ordering::SeqCst);
+        })
+        .build(Box::new(move || {
+            let connection = AccountingServer::new(factory_written.clone());
+            Box::pin(async move { Ok(connection) })
+        }));
+
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let response = client
+        .account_stop_once(
+            context,
+            [],
+            UNIX_EPOCH + Duration::from_secs(1_000_000_000),
+            Duration::from_secs(90),
+        )
+        .await
+        .expect("stop record should be acknowledged");
+
+    assert_eq!(response.flags, Flags::StopRecord);
+    assert_eq!(
+        response.timestamp,
+        UNIX_EPOCH + Duration::from_secs(1_000_000_090)
+    );
+
+    let written = written.lock().unwrap();
+    assert!(contains(&written, "task_id="));
+    assert!(contains(&written, "start_time=1000000000"));
+    assert!(contains(&written, "stop_time=1000000090"));
+    assert!(contains(&written, "elapsed_time=90"));
+
+    // the record is complete on its own, so there's no task to abandon
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn stop_only_record_with_overflowing_duration_rejected() {
+    let client =
+        ClientBuilder::new().build(Box::new(|| Box::pin(async { Ok(Cursor::new(Vec::new())) })));
+
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let error = client
+        .account_stop_once(context, [], UNIX_EPOCH, Duration::MAX)
+        .await
+        .expect_err("stop time shouldn't be representable");
+    assert!(
+        matches!(error, ClientError::TaskDurationOverflow { duration } if duration == Duration::MAX),
+        "unexpected error: {error:?}"
+    );
+}
+
+#[tokio::test]
+async fn records_after_skewed_start_time_rejected() {
+    // the wall clock is as far ahead as it can be when the task starts, so no time can be added to it afterwards
+    let clock = ManualClock::new();
+    clock.adjust_wall_clock(i64::MAX - ManualClock::START_SECS);
+    let (client, _) = accounting_client(clock.clone());
+
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let (task, _) = client
+        .account_begin(context, [])
+        .await
+        .expect("start record should be acknowledged");
+
+    clock.advance_monotonic(5);
+    let error = task
+        .update([])
+        .await
+        .expect_err("update timestamp shouldn't be representable");
+    assert!(
+        matches!(error, ClientError::TaskDurationOverflow { duration } if duration == Duration::from_secs(5)),
+        "unexpected error: {error:?}"
+    );
+
+    let error = task
+        .stop([])
+        .await
+        .expect_err("stop timestamp shouldn't be representable");
+    assert!(
+        matches!(error, ClientError::TaskDurationOverflow { duration } if duration == Duration::from_secs(5)),
+        "unexpected error: {error:?}"
+    );
+}
+
+#[tokio::test]
+async fn privilege_change_recorded() {
+    let (client, written) = accounting_client(ManualClock::new());
+
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+    let (mut task, _) = client
+        .account_begin(context, [])
+        .await
+        .expect("start record should be acknowledged");
+
+    let enable_level = PrivilegeLevel::new(15).unwrap();
+    task.record_priv_change(PrivilegeLevel::default(), enable_level)
+        .await
+        .expect("privilege change should be acknowledged");
+    assert_eq!(task.context.privilege_level(), enable_level);
+
+    let written = written.lock().unwrap();
+    assert!(contains(&written, "priv-lvl=15"));
+    assert!(contains(&written, "old-priv-lvl*0"));
+    assert!(contains(&written, "elapsed_time=0"));
+
+    drop(written);
+    task.abandon();
+}
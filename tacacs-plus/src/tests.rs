@@ -0,0 +1,951 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::{AsyncRead, AsyncWrite};
+use tacacs_plus_protocol::PrivilegeLevel;
+use tacacs_plus_protocol::{authentication, AnyBody, Argument, FieldText, Packet, PacketType};
+
+use crate::scripted::{ReplyScript, ScriptedServer};
+use crate::{merge_authorization_arguments, ClientBuilder, ClientError, ContextBuilder};
+use crate::{AdminMessage, AuthenticationResponse, AuthenticationType, Client, Clock};
+use crate::{DynConnection, OperationKind, ResponseStatus};
+
+/// The packets a scripted server received, as (sequence number, body) pairs.
+pub(crate) type ReceivedPackets = Arc<Mutex<Vec<(u8, Vec<u8>)>>>;
+
+/// A server that replies to every authentication packet with a GETDATA prompt, so a session never ends on its own.
+pub(crate) type EndlessPromptServer = ScriptedServer<EndlessPrompt>;
+
+/// The script of an [`EndlessPromptServer`].
+pub(crate) struct EndlessPrompt {
+    received: ReceivedPackets,
+}
+
+impl EndlessPromptServer {
+    /// Creates a server recording the packets it receives.
+    pub(crate) fn recording(received: ReceivedPackets) -> Self {
+        Self::new(EndlessPrompt { received })
+    }
+}
+
+impl ReplyScript for EndlessPrompt {
+    fn reply(&mut self, packet: &Packet<AnyBody>) -> Option<Vec<u8>> {
+        let sequence_number = packet.header().sequence_number();
+        self.received
+            .lock()
+            .unwrap()
+            .push((sequence_number, packet.body().data().to_vec()));
+
+        // a reply to the last possible client packet would overflow the sequence number
+        // (otherwise, GETDATA status, no flags, empty server message & data)
+        (sequence_number < u8::MAX).then(|| vec![0x03, 0x00, 0x00, 0x00, 0x00, 0x00])
+    }
+}
+
+/// The requests an [`AcceptingServer`] received, as (packet type, body) pairs.
+pub(crate) type ReceivedRequests = Arc<Mutex<Vec<(PacketType, Vec<u8>)>>>;
+
+/// A server that accepts every request, or rejects authentication if `reject_authentication` is set.
+pub(crate) type AcceptingServer = ScriptedServer<Accepting>;
+
+/// The script of an [`AcceptingServer`].
+pub(crate) struct Accepting {
+    received: ReceivedRequests,
+    reject_authentication: bool,
+    authorization_reply: Option<Vec<u8>>,
+    accounting_reply: Option<Vec<u8>>,
+}
+
+impl AcceptingServer {
+    /// Creates a server recording the requests it receives.
+    pub(crate) fn recording(received: ReceivedRequests, reject_authentication: bool) -> Self {
+        Self::new(Accepting {
+            received,
+            reject_authentication,
+            authorization_reply: None,
+            accounting_reply: None,
+        })
+    }
+
+    /// Replies to authorization requests with the provided body instead of accepting them.
+    pub(crate) fn with_authorization_reply(mut self, body: Vec<u8>) -> Self {
+        self.script_mut().authorization_reply = Some(body);
+        self
+    }
+
+    /// Replies to accounting requests with the provided body instead of accepting them.
+    pub(crate) fn with_accounting_reply(mut self, body: Vec<u8>) -> Self {
+        self.script_mut().accounting_reply = Some(body);
+        self
+    }
+}
+
+impl ReplyScript for Accepting {
+    fn reply(&mut self, packet: &Packet<AnyBody>) -> Option<Vec<u8>> {
+        let packet_type = packet.body().packet_type();
+        self.received
+            .lock()
+            .unwrap()
+            .push((packet_type, packet.body().data().to_vec()));
+
+        let body: &[u8] = match packet_type {
+            PacketType::Authorization if self.authorization_reply.is_some() => {
+                self.authorization_reply.as_deref().unwrap()
+            }
+            PacketType::Accounting if self.accounting_reply.is_some() => {
+                self.accounting_reply.as_deref().unwrap()
+            }
+            // PASS/FAIL status, no flags, empty server message & data
+            PacketType::Authentication if self.reject_authentication => &[0x02, 0, 0, 0, 0, 0],
+            PacketType::Authentication => &[0x01, 0, 0, 0, 0, 0],
+            // PASS_ADD status, no arguments, empty server message & data
+            PacketType::Authorization => &[0x01, 0, 0, 0, 0, 0],
+            // empty server message & data, success status
+            PacketType::Accounting => &[0, 0, 0, 0, 0x01],
+        };
+
+        Some(body.to_vec())
+    }
+}
+
+#[tokio::test]
+async fn session_aborted_before_sequence_number_overflows() {
+    let received = ReceivedPackets::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = EndlessPromptServer::recording(server_received.clone());
+        Box::pin(async move { Ok(server) })
+    }));
+
+    let error = client
+        .change_password(
+            ContextBuilder::new(String::from("user")).build().unwrap(),
+            "old password",
+            "new password",
+        )
+        .await
+        .expect_err("session should be terminated once sequence numbers run out");
+    assert!(
+        matches!(error, ClientError::SequenceNumberOverflow),
+        "unexpected error: {error:?}"
+    );
+
+    let received = received.lock().unwrap();
+
+    // every odd sequence number should have been used by the client exactly once, in order
+    let sequence_numbers: Vec<u8> = received.iter().map(|(number, _)| *number).collect();
+    assert_eq!(
+        sequence_numbers,
+        (1..=u8::MAX).step_by(2).collect::<Vec<_>>()
+    );
+
+    // the last packet should abort the session
+    let (_, abort_body) = received.last().unwrap();
+    let abort_flag = abort_body[4];
+    assert_eq!(
+        abort_flag, 0x01,
+        "last packet should have the abort flag set"
+    );
+    assert!(abort_body.ends_with(super::SEQUENCE_OVERFLOW_ABORT_MESSAGE));
+}
+
+#[tokio::test]
+async fn debug_output_does_not_lock_inner_client() {
+    let client = ClientBuilder::new().build(Box::new(|| {
+        Box::pin(async { Ok(futures::io::Cursor::new(Vec::<u8>::new())) })
+    }));
+
+    // hold the lock as an in-progress session would
+    let _guard = client.inner.lock().await;
+
+    let debug = format!("{client:?}");
+    assert_eq!(
+        debug,
+        "Client { state: Disconnected, connections_opened: 0, sessions_completed: 0, .. }"
+    );
+}
+
+/// A server that accepts requests but never replies, e.g. one that has hung.
+pub(crate) struct SilentServer;
+
+impl AsyncRead for SilentServer {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        _buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Pending
+    }
+}
+
+impl AsyncWrite for SilentServer {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn cancelled_session_forces_reconnect() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    // the first server hangs, and any later connection goes to one that replies
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let connection: DynConnection = match connections.fetch_add(1, Ordering::Relaxed) {
+            0 => Box::new(SilentServer),
+            _ => Box::new(AcceptingServer::recording(server_received.clone(), false)),
+        };
+        Box::pin(async move { Ok(connection) })
+    }));
+    let context = ContextBuilder::new(String::from("user")).build().unwrap();
+
+    // dropping the future leaves the session waiting for a reply
+    let timed_out = tokio::time::timeout(
+        Duration::from_millis(50),
+        client.authorize(context.clone(), []),
+    )
+    .await;
+    assert!(timed_out.is_err(), "silent server shouldn't have replied");
+
+    let response = tokio::time::timeout(Duration::from_secs(5), client.authorize(context, []))
+        .await
+        .expect("session shouldn't reuse the connection of the cancelled one")
+        .expect("authorization should succeed on a new connection");
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    assert_eq!(client.snapshot.connections_opened(), 2);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[cfg(feature = "raw-packets")]
+#[tokio::test]
+async fn failed_raw_exchange_abandons_connection() {
+    use tacacs_plus_protocol::authorization::Request;
+    use tacacs_plus_protocol::AuthenticationType;
+    use tacacs_plus_protocol::{
+        accounting, Arguments, AuthenticationContext, AuthenticationMethod,
+    };
+    use tacacs_plus_protocol::{AuthenticationService, HeaderInfo, MajorVersion, MinorVersion};
+    use tacacs_plus_protocol::{PacketFlags, UserInformation, Version};
+
+    let client = ClientBuilder::new().build(Box::new(|| {
+        Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+    }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    // the server agrees to single connection mode, so the connection would otherwise be reused
+    let _ = client.authorize(context.clone(), []).await.unwrap();
+    assert_eq!(client.snapshot.connections_opened(), 1);
+
+    let packet = Packet::new(
+        HeaderInfo::new(
+            Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+            1,
+            PacketFlags::SINGLE_CONNECTION,
+            0x1234_5678,
+        ),
+        Request::new(
+            AuthenticationMethod::TacacsPlus,
+            AuthenticationContext {
+                privilege_level: PrivilegeLevel::new(1).unwrap(),
+                authentication_type: AuthenticationType::NotSet,
+                service: AuthenticationService::Login,
+            },
+            UserInformation::new(
+                "someuser",
+                FieldText::try_from("raw").unwrap(),
+                FieldText::try_from("tacacs_plus_rs").unwrap(),
+            )
+            .unwrap(),
+            Arguments::new(&[]).unwrap(),
+        ),
+    );
+
+    // an authorization reply can't be parsed as an accounting one, which leaves the session in an unknown state
+    let result = client.send_raw::<_, accounting::ReplyOwned>(packet).await;
+    assert!(result.is_err(), "reply shouldn't have been accepted");
+
+    // the connection is dropped right away, rather than being left for the next session to notice
+    assert!(
+        format!("{client:?}").contains("state: Disconnected"),
+        "connection should have been abandoned: {client:?}"
+    );
+
+    let _ = client.authorize(context, []).await.unwrap();
+    assert_eq!(client.snapshot.connections_opened(), 2);
+}
+
+/// A monotonic clock that only advances when told to.
+#[derive(Debug, Clone)]
+struct SteppingClock {
+    base: Instant,
+    offset_secs: Arc<AtomicU64>,
+}
+
+impl SteppingClock {
+    fn advance(&self, secs: u64) {
+        self.offset_secs.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for SteppingClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn instant(&self) -> Instant {
+        self.base + Duration::from_secs(self.offset_secs.load(Ordering::SeqCst))
+    }
+}
+
+#[tokio::test]
+async fn idle_connection_recycled_between_sessions() {
+    let clock = SteppingClock {
+        base: Instant::now(),
+        offset_secs: Arc::default(),
+    };
+
+    let client = ClientBuilder::new()
+        .idle_timeout(Duration::from_secs(60))
+        .clock(clock.clone())
+        .build(Box::new(|| {
+            Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    // the server agrees to single connection mode, so the connection is reused while it's in use
+    for _ in 0..3 {
+        clock.advance(30);
+        let _ = client.authorize(context.clone(), []).await.unwrap();
+    }
+    assert_eq!(client.snapshot.connections_opened(), 1);
+
+    // but replaced once it's been idle for too long
+    clock.advance(60);
+    let _ = client.authorize(context, []).await.unwrap();
+    assert_eq!(client.snapshot.connections_opened(), 2);
+}
+
+pub(crate) fn argument(name: &str, value: &str) -> Argument<'static> {
+    Argument::new(
+        name.to_owned().try_into().unwrap(),
+        value.to_owned().try_into().unwrap(),
+        true,
+    )
+    .unwrap()
+}
+
+#[test]
+fn merge_replaced_arguments_preserves_order() {
+    let sent = [
+        argument("service", "shell"),
+        argument("cmd", "show"),
+        argument("cmd-arg", "version"),
+    ];
+    let received = vec![
+        argument("new", "added"),
+        argument("cmd-arg", "running-config"),
+        argument("service", "exec"),
+    ];
+
+    let merged = merge_authorization_arguments(true, &sent, received);
+    assert_eq!(
+        merged,
+        [
+            argument("service", "exec"),
+            argument("cmd", "show"),
+            argument("cmd-arg", "running-config"),
+            argument("new", "added"),
+        ]
+    );
+}
+
+#[test]
+fn merge_duplicate_received_arguments_updates_first_occurrence() {
+    let sent = [argument("service", "shell")];
+    let received = vec![
+        argument("priv-lvl", "1"),
+        argument("service", "exec"),
+        argument("priv-lvl", "15"),
+    ];
+
+    let merged = merge_authorization_arguments(true, &sent, received);
+    assert_eq!(
+        merged,
+        [argument("service", "exec"), argument("priv-lvl", "15")]
+    );
+}
+
+#[test]
+fn merge_added_arguments_appends_all() {
+    let sent = [argument("service", "shell")];
+    let received = vec![argument("service", "exec"), argument("priv-lvl", "15")];
+
+    let merged = merge_authorization_arguments(false, &sent, received);
+    assert_eq!(
+        merged,
+        [
+            argument("service", "shell"),
+            argument("service", "exec"),
+            argument("priv-lvl", "15"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn authorization_response_borrows_sent_arguments() {
+    let client = ClientBuilder::new().build(Box::new(|| {
+        Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+    }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let value = String::from("shell");
+    let sent = [Argument::new(
+        FieldText::try_from("service").unwrap(),
+        FieldText::try_from(value.as_str()).unwrap(),
+        true,
+    )
+    .unwrap()];
+
+    let response = client
+        .authorize(context, &sent)
+        .await
+        .expect("authorization should succeed");
+
+    // the sent argument wasn't copied into the response
+    assert_eq!(response.arguments, sent);
+    assert_eq!(
+        response.arguments[0].value().as_ref().as_ptr(),
+        value.as_ptr()
+    );
+
+    let owned = response.clone().into_owned();
+    drop(sent);
+    drop(value);
+    assert_eq!(owned.arguments, [argument("service", "shell")]);
+}
+
+#[tokio::test]
+async fn failed_authorization_returns_server_arguments() {
+    // FAIL status with a single argument & server message
+    let reason = b"reason=command-not-permitted";
+    let message = b"denied";
+    let mut reply = vec![0x10, 1, 0, message.len() as u8, 0, 0, reason.len() as u8];
+    reply.extend(message);
+    reply.extend(reason);
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(Default::default(), false)
+            .with_authorization_reply(reply.clone());
+        Box::pin(async move { Ok(server) })
+    }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let sent = [argument("service", "shell"), argument("cmd", "reload")];
+    let response = client
+        .authorize(context, &sent)
+        .await
+        .expect("authorization session should complete");
+
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.user_message, "denied");
+
+    // the denied arguments aren't mixed in with the server's
+    assert_eq!(
+        response.arguments,
+        [argument("reason", "command-not-permitted")]
+    );
+    assert_eq!(response.changes.added, response.arguments);
+}
+
+#[tokio::test]
+async fn user_messages_rewritten_by_processor() {
+    let message = b"%AUTH_FAIL%";
+    let mut reply = vec![0x10, 0, 0, message.len() as u8, 0, 0];
+    reply.extend(message);
+
+    let processed = Arc::new(Mutex::new(Vec::new()));
+    let client = ClientBuilder::new()
+        .user_message_processor({
+            let processed = processed.clone();
+            move |operation, message| {
+                processed.lock().unwrap().push(operation);
+                message.replace("%AUTH_FAIL%", "access denied")
+            }
+        })
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(Default::default(), false)
+                .with_authorization_reply(reply.clone());
+            Box::pin(async move { Ok(server) })
+        }));
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    let response = client
+        .authorize(context.clone(), [])
+        .await
+        .expect("authorization session should complete");
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.user_message, "access denied");
+
+    // empty messages are passed through too, so every operation goes through the processor
+    let task = client.account_begin(context, []).await.unwrap().0;
+    task.stop([]).await.unwrap();
+
+    assert_eq!(
+        *processed.lock().unwrap(),
+        [
+            OperationKind::Authorization,
+            OperationKind::Accounting,
+            OperationKind::Accounting
+        ]
+    );
+}
+
+/// The number of tasks sharing a client in the concurrency tests.
+const CONCURRENT_TASKS: usize = 8;
+
+/// The number of sessions performed by each task in the concurrency tests.
+const SESSIONS_PER_TASK: usize = 30;
+
+/// Performs a mix of authentication, authorization & accounting sessions, checking that each one succeeds.
+async fn perform_mixed_sessions(client: Client<AcceptingServer>, user: String) {
+    let context = ContextBuilder::new(user).build().unwrap();
+
+    for session in 0..SESSIONS_PER_TASK {
+        let status = match session % 3 {
+            0 => {
+                client
+                    .authenticate(context.clone(), "password", AuthenticationType::Pap)
+                    .await
+                    .expect("authentication should succeed")
+                    .status
+            }
+            1 => {
+                client
+                    .authorize(context.clone(), [])
+                    .await
+                    .expect("authorization should succeed")
+                    .status
+            }
+            _ => {
+                client
+                    .account_stop_once(
+                        context.clone(),
+                        [],
+                        SystemTime::now(),
+                        Duration::from_secs(1),
+                    )
+                    .await
+                    .expect("accounting should succeed");
+                ResponseStatus::Success
+            }
+        };
+
+        assert_eq!(status, ResponseStatus::Success);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_sessions_from_cloned_clients() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), false);
+        Box::pin(async move { Ok(server) })
+    }));
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|task| {
+            tokio::spawn(perform_mixed_sessions(
+                client.clone(),
+                format!("user{task}"),
+            ))
+        })
+        .collect();
+    for task in tasks {
+        task.await.expect("task shouldn't panic");
+    }
+
+    let sessions = (CONCURRENT_TASKS * SESSIONS_PER_TASK) as u64;
+    let stats = client.stats();
+    assert_eq!(
+        stats.authentication.succeeded + stats.authorization.succeeded + stats.accounting.succeeded,
+        sessions
+    );
+
+    // every session went over the single shared connection, one request at a time
+    assert_eq!(stats.reconnects, 0);
+    assert_eq!(received.lock().unwrap().len() as u64, sessions);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_sessions_survive_cancelled_sessions() {
+    let client = ClientBuilder::new().build(Box::new(|| {
+        Box::pin(async { Ok(AcceptingServer::recording(Default::default(), false)) })
+    }));
+
+    // half of the tasks give up on their sessions almost immediately, at whatever point they happen to be in
+    let cancelling_tasks: Vec<_> = (0..CONCURRENT_TASKS / 2)
+        .map(|task| {
+            let client = client.clone();
+            tokio::spawn(async move {
+                let context = ContextBuilder::new(format!("impatient{task}"))
+                    .build()
+                    .unwrap();
+
+                for _ in 0..SESSIONS_PER_TASK {
+                    let _ = tokio::time::timeout(
+                        Duration::from_micros(50),
+                        client.authorize(context.clone(), []),
+                    )
+                    .await;
+                }
+            })
+        })
+        .collect();
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS / 2)
+        .map(|task| {
+            tokio::spawn(perform_mixed_sessions(
+                client.clone(),
+                format!("user{task}"),
+            ))
+        })
+        .collect();
+
+    for task in cancelling_tasks.into_iter().chain(tasks) {
+        task.await.expect("task shouldn't panic");
+    }
+
+    // a session cancelled mid-exchange forces a new connection, but never more than one per session
+    let stats = client.stats();
+    assert!(stats.reconnects <= stats.authorization.errored);
+    assert_eq!(
+        stats.authentication.errored + stats.accounting.errored,
+        0,
+        "only the cancelled authorization sessions should error"
+    );
+}
+
+/// Performs an enable privilege query against a server replying with the provided authorization reply body.
+async fn query_effective_privilege(reply: Vec<u8>) -> Result<Option<PrivilegeLevel>, ClientError> {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(server_received.clone(), false)
+            .with_authorization_reply(reply.clone());
+        Box::pin(async move { Ok(server) })
+    }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .privilege_level(PrivilegeLevel::new(15).unwrap())
+        .build()
+        .unwrap();
+    let result = client.effective_privilege(context).await;
+
+    // the requested level is sent in the request header, alongside the enable service
+    let (_, request) = received.lock().unwrap().pop().unwrap();
+    assert_eq!(request[1], 15);
+    assert!(request.ends_with(b"service=enable"));
+
+    result
+}
+
+/// An authorization reply body with the provided status & arguments.
+pub(crate) fn authorization_reply(status: u8, arguments: &[&str]) -> Vec<u8> {
+    let mut reply = vec![status, arguments.len() as u8, 0, 0, 0, 0];
+    reply.extend(arguments.iter().map(|argument| argument.len() as u8));
+    for argument in arguments {
+        reply.extend(argument.as_bytes());
+    }
+
+    reply
+}
+
+#[tokio::test]
+async fn effective_privilege_granted_by_server() {
+    let granted = query_effective_privilege(authorization_reply(0x01, &["priv-lvl=7"])).await;
+    assert_eq!(granted.unwrap(), PrivilegeLevel::new(7));
+
+    // without a priv-lvl argument, the requested level is granted
+    let granted = query_effective_privilege(authorization_reply(0x01, &[])).await;
+    assert_eq!(granted.unwrap(), PrivilegeLevel::new(15));
+
+    let denied = query_effective_privilege(authorization_reply(0x10, &[])).await;
+    assert_eq!(denied.unwrap(), None);
+}
+
+#[tokio::test]
+async fn invalid_effective_privilege_rejected() {
+    for level in ["16", "admin"] {
+        let argument = format!("priv-lvl={level}");
+        let result = query_effective_privilege(authorization_reply(0x01, &[&argument])).await;
+        assert!(
+            matches!(&result, Err(ClientError::InvalidPrivilegeLevel(value)) if value == level),
+            "unexpected result: {result:?}"
+        );
+    }
+}
+
+/// Creates a client whose servers answer authentication start packets with the provided statuses in order, and with
+/// RESTART once those run out, along with the session ids of the start packets they received.
+fn restarting_client(
+    statuses: &[u8],
+    max_restarts: u8,
+) -> (
+    Client<ScriptedServer<impl ReplyScript + Send + Unpin>>,
+    Arc<Mutex<Vec<u32>>>,
+) {
+    let statuses = Arc::new(Mutex::new(VecDeque::from(statuses.to_vec())));
+    let sessions = Arc::new(Mutex::new(Vec::new()));
+
+    let server_sessions = sessions.clone();
+    let client = ClientBuilder::new()
+        .max_authentication_restarts(max_restarts)
+        .build(Box::new(move || {
+            let statuses = statuses.clone();
+            let sessions = server_sessions.clone();
+
+            let server = ScriptedServer::new(move |packet: &Packet<AnyBody>| {
+                sessions.lock().unwrap().push(packet.header().session_id());
+                let status = statuses.lock().unwrap().pop_front().unwrap_or(0x06);
+
+                // no flags, empty server message & data
+                Some(vec![status, 0, 0, 0, 0, 0])
+            });
+            Box::pin(async move { Ok(server) })
+        }));
+
+    (client, sessions)
+}
+
+async fn authenticate_restarting(
+    client: &Client<ScriptedServer<impl ReplyScript + Send + Unpin>>,
+) -> AuthenticationResponse {
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+
+    client
+        .authenticate(context, "hunter2", AuthenticationType::Pap)
+        .await
+        .expect("authentication should complete")
+}
+
+#[tokio::test]
+async fn authentication_restarted_in_new_session() {
+    // RESTART, then PASS
+    let (client, sessions) = restarting_client(&[0x06, 0x01], 1);
+
+    let response = authenticate_restarting(&client).await;
+    assert_eq!(response.status, ResponseStatus::Success);
+    assert!(!response.restart_requested);
+
+    let sessions = sessions.lock().unwrap();
+    assert_eq!(sessions.len(), 2);
+    assert_ne!(sessions[0], sessions[1], "restart should use a new session");
+}
+
+#[tokio::test]
+async fn authentication_restarts_bounded_by_limit() {
+    let (client, sessions) = restarting_client(&[], 2);
+
+    // the server asks for a restart every time, so the last one is reported once the limit is reached
+    let response = authenticate_restarting(&client).await;
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.raw_status, authentication::Status::Restart);
+    assert!(response.restart_requested);
+
+    assert_eq!(sessions.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn authentication_not_restarted_by_default() {
+    let (client, sessions) = restarting_client(&[0x06, 0x01], 0);
+
+    let response = authenticate_restarting(&client).await;
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert!(response.restart_requested);
+
+    assert_eq!(sessions.lock().unwrap().len(), 1);
+}
+
+/// Authenticates with PAP against a server that prompts for the password after the start packet, as some lenient
+/// servers do, and then replies with the provided status.
+async fn pap_with_password_prompt(final_status: u8) -> (AuthenticationResponse, ReceivedPackets) {
+    let received = ReceivedPackets::default();
+
+    let server_received = received.clone();
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let received = server_received.clone();
+        let server = ScriptedServer::new(move |packet: &Packet<AnyBody>| {
+            let sequence_number = packet.header().sequence_number();
+            received
+                .lock()
+                .unwrap()
+                .push((sequence_number, packet.body().data().to_vec()));
+
+            // GETPASS in reply to the start packet, then the final status; no flags, empty server message & data
+            let status = if sequence_number == 1 {
+                0x05
+            } else {
+                final_status
+            };
+            Some(vec![status, 0, 0, 0, 0, 0])
+        });
+        Box::pin(async move { Ok(server) })
+    }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let response = client
+        .authenticate(context, "hunter2", AuthenticationType::Pap)
+        .await
+        .expect("authentication should complete");
+
+    (response, received)
+}
+
+#[tokio::test]
+async fn pap_password_prompt_answered_with_password() {
+    let (response, received) = pap_with_password_prompt(0x01).await;
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+
+    // the start packet carries the password as its data, as usual for PAP
+    let (sequence_number, start) = &received[0];
+    assert_eq!(*sequence_number, 1);
+    assert!(start.ends_with(b"hunter2"));
+
+    // the prompt is answered with a CONTINUE holding the password as the user message
+    let (sequence_number, continue_body) = &received[1];
+    assert_eq!(*sequence_number, 3);
+    #[rustfmt::skip]
+    let expected: &[u8] = &[
+        // user message length 7, data length 0, no flags
+        0x00, 0x07, 0x00, 0x00, 0x00,
+        b'h', b'u', b'n', b't', b'e', b'r', b'2',
+    ];
+    assert_eq!(continue_body, expected);
+}
+
+#[tokio::test]
+async fn pap_password_prompt_followed_by_failure() {
+    let (response, received) = pap_with_password_prompt(0x02).await;
+    assert_eq!(response.status, ResponseStatus::Failure);
+    assert_eq!(response.raw_status, authentication::Status::Fail);
+
+    // nothing else is sent once the server made a decision
+    assert_eq!(received.lock().unwrap().len(), 2);
+}
+
+/// Performs a session of each kind against a server whose replies carry the provided data, returning the messages
+/// passed to the client's admin message sink along with the session ids the server saw.
+async fn collect_admin_messages(data: &'static str) -> (Vec<AdminMessage>, Vec<u32>) {
+    let messages = Arc::new(Mutex::new(Vec::new()));
+    let sessions = Arc::new(Mutex::new(Vec::new()));
+
+    let sink_messages = messages.clone();
+    let server_sessions = sessions.clone();
+    let client = ClientBuilder::new()
+        .admin_message_sink(move |message| sink_messages.lock().unwrap().push(message.clone()))
+        .build(Box::new(move || {
+            let sessions = server_sessions.clone();
+            let server = ScriptedServer::new(move |packet: &Packet<AnyBody>| {
+                sessions.lock().unwrap().push(packet.header().session_id());
+
+                let data_length = (data.len() as u16).to_be_bytes();
+                let mut body = match packet.body().packet_type() {
+                    // PASS status, no flags, empty server message
+                    PacketType::Authentication => vec![0x01, 0, 0, 0],
+                    // PASS_ADD status, no arguments, empty server message
+                    PacketType::Authorization => vec![0x01, 0, 0, 0],
+                    // empty server message
+                    PacketType::Accounting => vec![0, 0],
+                };
+                body.extend(data_length);
+
+                // accounting replies have their status after the field lengths
+                if packet.body().packet_type() == PacketType::Accounting {
+                    body.push(0x01);
+                }
+
+                body.extend(data.as_bytes());
+                Some(body)
+            });
+            Box::pin(async move { Ok(server) })
+        }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .build()
+        .unwrap();
+    let _ = client
+        .authenticate(context.clone(), "hunter2", AuthenticationType::Pap)
+        .await
+        .unwrap();
+    let _ = client.authorize(context.clone(), []).await.unwrap();
+    let _ = client.account_begin(context, []).await.unwrap();
+
+    let messages = messages.lock().unwrap().clone();
+    let sessions = sessions.lock().unwrap().clone();
+    (messages, sessions)
+}
+
+#[tokio::test]
+async fn admin_messages_reported_for_all_operations() {
+    let (messages, sessions) = collect_admin_messages("logged by server").await;
+
+    let operations = [
+        OperationKind::Authentication,
+        OperationKind::Authorization,
+        OperationKind::Accounting,
+    ];
+    assert_eq!(messages.len(), operations.len());
+
+    for ((message, operation), session_id) in messages.iter().zip(operations).zip(sessions) {
+        assert_eq!(message.operation, operation);
+        assert_eq!(message.message, "logged by server");
+        assert_eq!(message.session.session_id, session_id);
+        assert_eq!(message.session.final_sequence_number, 2);
+    }
+}
+
+#[tokio::test]
+async fn empty_admin_messages_not_reported() {
+    let (messages, sessions) = collect_admin_messages("").await;
+
+    assert_eq!(sessions.len(), 3);
+    assert!(messages.is_empty(), "unexpected messages: {messages:?}");
+}
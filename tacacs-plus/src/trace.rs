@@ -0,0 +1,494 @@
+//! Export of the decoded packets exchanged over a transport as JSON, e.g. for comparing client behavior across versions.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::{AsyncRead, AsyncWrite};
+use serde_json::{json, Map, Value};
+use tacacs_plus_protocol::{AnyBody, Packet, PacketStreamParser, PacketType};
+
+#[cfg(test)]
+mod tests;
+
+/// The version of the JSON schema traces are exported in, which changes whenever existing fields do.
+pub const TRACE_SCHEMA_VERSION: u32 = 1;
+
+/// The value sensitive fields are replaced with in exported traces.
+pub const REDACTED: &str = "<redacted>";
+
+/// A packet that went over a traced transport.
+#[derive(Debug)]
+struct TracedPacket {
+    stream: u32,
+    sent_by_client: bool,
+    timestamp: SystemTime,
+    packet: Packet<AnyBody>,
+}
+
+#[derive(Debug, Default)]
+struct TraceState {
+    packets: Vec<TracedPacket>,
+    streams: u32,
+}
+
+/// The packets exchanged over any number of [`TracingTransport`]s, which can be exported as JSON.
+///
+/// Clones of a trace share the same packets, so one clone can be moved into a connection factory while another is kept
+/// around for exporting.
+///
+/// # Format
+///
+/// A trace is exported as an array with one object per packet, in the same shape as the output of `tshark -T json`,
+/// so tooling built around Wireshark's JSON exports can consume it as well. Each packet has the following layers
+/// within `_source.layers`, with all values encoded as strings as Wireshark does:
+///
+/// | Layer     | Field                           | Value                                                              |
+/// |-----------|---------------------------------|--------------------------------------------------------------------|
+/// | `trace`   | `trace.schema_version`          | [`TRACE_SCHEMA_VERSION`]                                           |
+/// |           | `trace.direction`               | `client` for packets written to the transport, `server` otherwise  |
+/// | `frame`   | `frame.number`                  | the index of the packet in the trace, starting at 1                |
+/// |           | `frame.time_epoch`              | when the packet was traced, as seconds since the Unix epoch        |
+/// | `tcp`     | `tcp.stream`                    | the index of the traced transport, starting at 0                   |
+/// | `tacplus` | `tacplus.majvers`, `tacplus.minvers`, `tacplus.type`, `tacplus.seqno`, `tacplus.flags`, `tacplus.session_id`, `tacplus.packet_len` | the header fields, named as in Wireshark's TACACS+ dissector |
+/// |           | `tacplus.request`/`tacplus.response` | `1`, depending on the direction of the packet                 |
+/// |           | `tacplus.body.*`                | the body fields, see below                                         |
+///
+/// Body fields are named after their [RFC8907] names without the length prefix, e.g. `tacplus.body.user`,
+/// `tacplus.body.server_msg` or `tacplus.body.status`, and arguments are listed in order as an array in
+/// `tacplus.body.args`. Bodies are identified by `tacplus.body.kind` (e.g. `authen_start` or `author_reply`); a body
+/// that couldn't be decoded (e.g. due to a wrong secret key) has the kind `malformed` and no other body fields.
+///
+/// Fields that might contain secrets (i.e. the `data` of authentication start & continue packets and the `user_msg`
+/// of continue packets) are replaced with [`REDACTED`], although their lengths are kept in a corresponding `_len`
+/// field. The secret key itself is never included.
+///
+/// New fields may be added without changing the schema version, but existing fields are only ever renamed, removed
+/// or changed in meaning along with a bump of [`TRACE_SCHEMA_VERSION`].
+///
+/// [RFC8907]: https://www.rfc-editor.org/rfc/rfc8907.html
+#[derive(Clone)]
+pub struct ProtocolTrace {
+    state: Arc<Mutex<TraceState>>,
+    secret: Option<Arc<[u8]>>,
+}
+
+// the secret key is omitted so it doesn't end up in logs
+impl fmt::Debug for ProtocolTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtocolTrace")
+            .field("packets", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProtocolTrace {
+    /// Creates an empty trace, which deobfuscates packet bodies with the provided secret key if any.
+    ///
+    /// The key should be the same one the client was configured with, since obfuscated bodies can't be decoded
+    /// otherwise.
+    pub fn new(secret_key: Option<&[u8]>) -> Self {
+        Self {
+            state: Arc::default(),
+            secret: secret_key.map(Arc::from),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TraceState> {
+        // packets are only ever appended, so the state can't be left inconsistent by a panic
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The number of packets in the trace.
+    pub fn len(&self) -> usize {
+        self.state().packets.len()
+    }
+
+    /// Whether no packets have been traced yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Exports the trace as a JSON array.
+    pub fn to_json(&self) -> Value {
+        let state = self.state();
+        let packets = state
+            .packets
+            .iter()
+            .enumerate()
+            .map(|(index, packet)| packet_json(index + 1, packet))
+            .collect();
+
+        Value::Array(packets)
+    }
+
+    /// Writes the trace as pretty-printed JSON, e.g. to a file.
+    pub fn write_json<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        serde_json::to_writer_pretty(writer, &self.to_json()).map_err(io::Error::from)
+    }
+
+    fn new_stream(&self) -> u32 {
+        let mut state = self.state();
+        let stream = state.streams;
+        state.streams += 1;
+        stream
+    }
+
+    fn record(&self, stream: u32, sent_by_client: bool, mut packet: Packet<AnyBody>) {
+        if let Some(secret) = &self.secret {
+            packet.deobfuscate(secret);
+        }
+
+        self.state().packets.push(TracedPacket {
+            stream,
+            sent_by_client,
+            timestamp: SystemTime::now(),
+            packet,
+        });
+    }
+}
+
+/// A transport wrapper that records the packets written to & read from it in a [`ProtocolTrace`].
+///
+/// Packets are framed from the bytes that pass through, so the wrapper works regardless of how reads & writes are
+/// split up. If the bytes in either direction stop making sense as packets, tracing stops for that direction, but the
+/// transport itself keeps working as usual.
+///
+/// # Examples
+///
+/// ```
+/// use futures::io::Cursor;
+/// use futures::AsyncReadExt;
+///
+/// use tacacs_plus::{ProtocolTrace, TracingTransport, TRACE_SCHEMA_VERSION};
+///
+/// # futures::executor::block_on(async {
+/// // an unobfuscated accounting reply with a success status, as sent by a server
+/// let packet = vec![
+///     0xc0, 0x03, 0x02, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05,
+///     0x00, 0x00, 0x00, 0x00, 0x01,
+/// ];
+///
+/// let trace = ProtocolTrace::new(None);
+/// let mut transport = TracingTransport::new(Cursor::new(packet), trace.clone());
+/// transport.read_to_end(&mut Vec::new()).await.unwrap();
+///
+/// let exported = trace.to_json();
+/// let layers = &exported[0]["_source"]["layers"];
+/// assert_eq!(layers["trace"]["trace.schema_version"], TRACE_SCHEMA_VERSION.to_string());
+/// assert_eq!(layers["tacplus"]["tacplus.session_id"], "1");
+/// assert_eq!(layers["tacplus"]["tacplus.body.kind"], "acct_reply");
+/// # });
+/// ```
+pub struct TracingTransport<S> {
+    inner: S,
+    trace: ProtocolTrace,
+    stream: u32,
+
+    /// The framing of packets written to the transport, or `None` if the written bytes couldn't be framed.
+    sent: Option<PacketStreamParser>,
+
+    /// The framing of packets read from the transport, or `None` if the read bytes couldn't be framed.
+    received: Option<PacketStreamParser>,
+}
+
+impl<S> fmt::Debug for TracingTransport<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TracingTransport")
+            .field("trace", &self.trace)
+            .field("stream", &self.stream)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> TracingTransport<S> {
+    /// Wraps a transport, recording its packets in the provided trace as a new stream.
+    pub fn new(inner: S, trace: ProtocolTrace) -> Self {
+        Self {
+            inner,
+            stream: trace.new_stream(),
+            trace,
+            sent: Some(PacketStreamParser::new()),
+            received: Some(PacketStreamParser::new()),
+        }
+    }
+
+    /// Returns the wrapped transport.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// Frames packets from bytes that went through the transport, recording the complete ones.
+    fn trace(&mut self, sent_by_client: bool, bytes: &[u8]) {
+        let parser = if sent_by_client {
+            &mut self.sent
+        } else {
+            &mut self.received
+        };
+
+        let Some(active_parser) = parser else {
+            return;
+        };
+
+        active_parser.feed(bytes);
+        loop {
+            match active_parser.next_packet() {
+                Ok(Some(packet)) => self.trace.record(self.stream, sent_by_client, packet),
+                Ok(None) => break,
+                Err(_) => {
+                    *parser = None;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for TracingTransport<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = ready!(Pin::new(&mut self.inner).poll_read(cx, buf));
+        if let Ok(length) = result {
+            self.trace(false, &buf[..length]);
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for TracingTransport<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = ready!(Pin::new(&mut self.inner).poll_write(cx, buf));
+        if let Ok(length) = result {
+            self.trace(true, &buf[..length]);
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+/// Converts a traced packet to its JSON representation.
+fn packet_json(number: usize, traced: &TracedPacket) -> Value {
+    let header = traced.packet.header();
+    let body = traced.packet.body();
+    let version = header.version();
+
+    let elapsed = traced
+        .timestamp
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    let mut tacplus = Map::new();
+    let mut insert = |name: &str, value: String| {
+        tacplus.insert(format!("tacplus.{name}"), Value::String(value));
+    };
+
+    insert("majvers", (version.major() as u8).to_string());
+    insert("minvers", (version.minor() as u8).to_string());
+    insert("type", (body.packet_type() as u8).to_string());
+    insert("seqno", header.sequence_number().to_string());
+    insert("flags", format!("0x{:02x}", header.flags().bits()));
+    insert("session_id", header.session_id().to_string());
+    insert("packet_len", body.data().len().to_string());
+
+    let direction = if traced.sent_by_client {
+        "request"
+    } else {
+        "response"
+    };
+    insert(direction, String::from("1"));
+
+    let body_fields = if body.is_obfuscated() {
+        None
+    } else {
+        decode_body(
+            body.packet_type(),
+            traced.sent_by_client,
+            header.sequence_number(),
+            body.data(),
+        )
+    };
+    tacplus.extend(
+        body_fields
+            .unwrap_or_else(|| {
+                let mut malformed = Map::new();
+                malformed.insert("kind".into(), "malformed".into());
+                malformed
+            })
+            .into_iter()
+            .map(|(name, value)| (format!("tacplus.body.{name}"), value)),
+    );
+
+    json!({
+        "_index": "tacacs-plus-trace",
+        "_type": "doc",
+        "_score": null,
+        "_source": {
+            "layers": {
+                "trace": {
+                    "trace.schema_version": TRACE_SCHEMA_VERSION.to_string(),
+                    "trace.direction": if traced.sent_by_client { "client" } else { "server" },
+                },
+                "frame": {
+                    "frame.number": number.to_string(),
+                    "frame.time_epoch": format!("{}.{:09}", elapsed.as_secs(), elapsed.subsec_nanos()),
+                },
+                "tcp": {
+                    "tcp.stream": traced.stream.to_string(),
+                },
+                "tacplus": tacplus,
+            }
+        }
+    })
+}
+
+/// A cursor over the fields of a packet body.
+struct Fields<'data>(&'data [u8]);
+
+impl<'data> Fields<'data> {
+    fn bytes(&mut self, length: usize) -> Option<&'data [u8]> {
+        if self.0.len() < length {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes(1).map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn text(&mut self, length: usize) -> Option<Value> {
+        self.bytes(length)
+            .map(|bytes| Value::String(String::from_utf8_lossy(bytes).into_owned()))
+    }
+
+    /// Reads arguments with previously read lengths.
+    fn arguments(&mut self, lengths: &[u8]) -> Option<Value> {
+        lengths
+            .iter()
+            .map(|&length| self.text(length.into()))
+            .collect::<Option<_>>()
+            .map(Value::Array)
+    }
+}
+
+/// Decodes the fields of a cleartext body, returning `None` if it's too short for its own length fields.
+fn decode_body(
+    packet_type: PacketType,
+    sent_by_client: bool,
+    sequence_number: u8,
+    data: &[u8],
+) -> Option<Map<String, Value>> {
+    let mut fields = Fields(data);
+    let mut map = Map::new();
+    let mut set = |name: &str, value: Value| {
+        map.insert(name.to_owned(), value);
+    };
+    let number = |value: u8| Value::String(value.to_string());
+
+    match (packet_type, sent_by_client) {
+        (PacketType::Authentication, true) if sequence_number == 1 => {
+            set("kind", "authen_start".into());
+            set("action", number(fields.u8()?));
+            set("priv_lvl", number(fields.u8()?));
+            set("authen_type", number(fields.u8()?));
+            set("authen_service", number(fields.u8()?));
+            let [user_len, port_len, rem_addr_len, data_len] =
+                [fields.u8()?, fields.u8()?, fields.u8()?, fields.u8()?];
+            set("user", fields.text(user_len.into())?);
+            set("port", fields.text(port_len.into())?);
+            set("rem_addr", fields.text(rem_addr_len.into())?);
+            fields.bytes(data_len.into())?;
+            set("data", REDACTED.into());
+            set("data_len", number(data_len));
+        }
+        (PacketType::Authentication, true) => {
+            set("kind", "authen_continue".into());
+            let user_msg_len = fields.u16()?;
+            let data_len = fields.u16()?;
+            set("flags", format!("0x{:02x}", fields.u8()?).into());
+            fields.bytes(user_msg_len.into())?;
+            fields.bytes(data_len.into())?;
+            set("user_msg", REDACTED.into());
+            set("user_msg_len", user_msg_len.to_string().into());
+            set("data", REDACTED.into());
+            set("data_len", data_len.to_string().into());
+        }
+        (PacketType::Authentication, false) => {
+            set("kind", "authen_reply".into());
+            set("status", number(fields.u8()?));
+            set("flags", format!("0x{:02x}", fields.u8()?).into());
+            let server_msg_len = fields.u16()?;
+            let data_len = fields.u16()?;
+            set("server_msg", fields.text(server_msg_len.into())?);
+            set("data", fields.text(data_len.into())?);
+        }
+        (PacketType::Authorization, true) | (PacketType::Accounting, true) => {
+            if packet_type == PacketType::Accounting {
+                set("kind", "acct_request".into());
+                set("flags", format!("0x{:02x}", fields.u8()?).into());
+            } else {
+                set("kind", "author_request".into());
+            }
+
+            set("authen_method", number(fields.u8()?));
+            set("priv_lvl", number(fields.u8()?));
+            set("authen_type", number(fields.u8()?));
+            set("authen_service", number(fields.u8()?));
+            let [user_len, port_len, rem_addr_len, arg_cnt] =
+                [fields.u8()?, fields.u8()?, fields.u8()?, fields.u8()?];
+            let argument_lengths = fields.bytes(arg_cnt.into())?;
+            set("user", fields.text(user_len.into())?);
+            set("port", fields.text(port_len.into())?);
+            set("rem_addr", fields.text(rem_addr_len.into())?);
+            set("args", fields.arguments(argument_lengths)?);
+        }
+        (PacketType::Authorization, false) => {
+            set("kind", "author_reply".into());
+            set("status", number(fields.u8()?));
+            let arg_cnt = fields.u8()?;
+            let server_msg_len = fields.u16()?;
+            let data_len = fields.u16()?;
+            let argument_lengths = fields.bytes(arg_cnt.into())?;
+            set("server_msg", fields.text(server_msg_len.into())?);
+            set("data", fields.text(data_len.into())?);
+            set("args", fields.arguments(argument_lengths)?);
+        }
+        (PacketType::Accounting, false) => {
+            set("kind", "acct_reply".into());
+            let server_msg_len = fields.u16()?;
+            let data_len = fields.u16()?;
+            set("status", number(fields.u8()?));
+            set("server_msg", fields.text(server_msg_len.into())?);
+            set("data", fields.text(data_len.into())?);
+        }
+    }
+
+    Some(map)
+}
@@ -0,0 +1,156 @@
+use futures::io::Cursor;
+use futures::AsyncReadExt;
+use tacacs_plus_protocol::obfuscation::pseudo_pad_iter;
+use tacacs_plus_protocol::{
+    Argument, FieldText, HeaderInfo, MajorVersion, MinorVersion, PacketFlags, Version,
+};
+
+use super::*;
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{AuthenticationType, ClientBuilder, ContextBuilder, ResponseStatus};
+
+/// The `tacplus` layers of the packets in an exported trace.
+fn tacplus_layers(trace: &ProtocolTrace) -> Vec<Value> {
+    match trace.to_json() {
+        Value::Array(packets) => packets
+            .into_iter()
+            .map(|packet| packet["_source"]["layers"]["tacplus"].clone())
+            .collect(),
+        other => panic!("trace should be exported as an array, got {other}"),
+    }
+}
+
+/// An accounting reply with a success status, obfuscated with the provided key.
+fn obfuscated_reply(secret_key: &[u8]) -> Vec<u8> {
+    let header = HeaderInfo::new(
+        Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+        2,
+        PacketFlags::empty(),
+        1234,
+    );
+
+    let mut body = [0, 0, 0, 0, 0x01];
+    for (byte, pad) in body.iter_mut().zip(pseudo_pad_iter(&header, secret_key)) {
+        *byte ^= pad;
+    }
+
+    let mut packet = vec![0xc0, 0x03, 2, 0];
+    packet.extend(1234u32.to_be_bytes());
+    packet.extend((body.len() as u32).to_be_bytes());
+    packet.extend(body);
+    packet
+}
+
+#[tokio::test]
+async fn client_sessions_traced() {
+    let trace = ProtocolTrace::new(None);
+    let factory_trace = trace.clone();
+
+    let client = ClientBuilder::new().build(Box::new(move || {
+        let server = AcceptingServer::recording(ReceivedRequests::default(), false);
+        let transport = TracingTransport::new(server, factory_trace.clone());
+        Box::pin(async move { Ok(transport) })
+    }));
+
+    let context = ContextBuilder::new(String::from("someuser"))
+        .port(String::from("tty0"))
+        .build()
+        .unwrap();
+    let response = client
+        .authenticate(context.clone(), "hunter2", AuthenticationType::Pap)
+        .await
+        .unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let argument = Argument::new(
+        FieldText::try_from("service").unwrap(),
+        FieldText::try_from("shell").unwrap(),
+        true,
+    )
+    .unwrap();
+    let response = client.authorize(context, [argument]).await.unwrap();
+    assert_eq!(response.status, ResponseStatus::Success);
+
+    let layers = tacplus_layers(&trace);
+    let kinds: Vec<_> = layers
+        .iter()
+        .map(|layer| layer["tacplus.body.kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "authen_start",
+            "authen_reply",
+            "author_request",
+            "author_reply"
+        ]
+    );
+
+    let start = &layers[0];
+    assert_eq!(start["tacplus.request"], "1");
+    assert_eq!(start["tacplus.body.user"], "someuser");
+    assert_eq!(start["tacplus.body.port"], "tty0");
+    assert_eq!(start["tacplus.body.data"], REDACTED);
+    assert_eq!(start["tacplus.body.data_len"], "7");
+
+    let request = &layers[2];
+    assert_eq!(request["tacplus.type"], "2");
+    assert_eq!(request["tacplus.seqno"], "1");
+    assert_eq!(request["tacplus.body.args"], json!(["service=shell"]));
+
+    let reply = &layers[3];
+    assert_eq!(reply["tacplus.response"], "1");
+    assert_eq!(reply["tacplus.body.status"], "1");
+    assert_eq!(reply["tacplus.session_id"], request["tacplus.session_id"]);
+
+    // the password doesn't appear anywhere in the export
+    let mut exported = Vec::new();
+    trace.write_json(&mut exported).unwrap();
+    let exported = String::from_utf8(exported).unwrap();
+    assert!(!exported.contains("hunter2"));
+}
+
+#[tokio::test]
+async fn obfuscated_bodies_decoded_with_secret() {
+    let trace = ProtocolTrace::new(Some(b"secret"));
+    let mut transport =
+        TracingTransport::new(Cursor::new(obfuscated_reply(b"secret")), trace.clone());
+    transport.read_to_end(&mut Vec::new()).await.unwrap();
+
+    let layers = tacplus_layers(&trace);
+    assert_eq!(layers[0]["tacplus.body.kind"], "acct_reply");
+    assert_eq!(layers[0]["tacplus.body.status"], "1");
+    assert_eq!(layers[0]["tacplus.session_id"], "1234");
+
+    // without the key, the body can't be decoded
+    let trace = ProtocolTrace::new(None);
+    let mut transport =
+        TracingTransport::new(Cursor::new(obfuscated_reply(b"secret")), trace.clone());
+    transport.read_to_end(&mut Vec::new()).await.unwrap();
+
+    let layers = tacplus_layers(&trace);
+    assert_eq!(layers[0]["tacplus.body.kind"], "malformed");
+    assert!(layers[0].get("tacplus.body.status").is_none());
+}
+
+#[tokio::test]
+async fn unframeable_bytes_passed_through_untraced() {
+    let mut bytes = vec![
+        0xc0, 0x03, 2, 0x01, 0, 0, 0, 1, 0, 0, 0, 5, 0, 0, 0, 0, 0x01,
+    ];
+
+    // a header with a bad version
+    bytes.extend([0x20; 12]);
+
+    let trace = ProtocolTrace::new(None);
+    let mut transport = TracingTransport::new(Cursor::new(bytes.clone()), trace.clone());
+
+    let mut read = Vec::new();
+    transport.read_to_end(&mut read).await.unwrap();
+    assert_eq!(read, bytes);
+    assert_eq!(trace.len(), 1);
+
+    // each transport is traced as its own stream
+    let second = TracingTransport::new(Cursor::new(Vec::<u8>::new()), trace.clone());
+    assert_eq!(second.stream, 1);
+}
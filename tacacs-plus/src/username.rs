@@ -0,0 +1,102 @@
+//! Normalization of usernames before they're sent to a server.
+
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// A transformation applied to the username of every [`SessionContext`](super::SessionContext) before it's sent to a server,
+/// as configured via [`ClientBuilder::user_name_normalizer()`](super::ClientBuilder::user_name_normalizer).
+///
+/// Server policies typically match usernames exactly, so the same user showing up as e.g. `Alice`, `alice@EXAMPLE.COM` and
+/// `EXAMPLE\alice` depending on how they logged in can lead to them being authorized inconsistently. A normalizer maps
+/// all of these to the same canonical name, e.g. by looking the user up in a system name service.
+///
+/// [`UserNameRules`] covers the most common normalizations.
+///
+/// # Examples
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use tacacs_plus::UserNameNormalizer;
+///
+/// /// Maps service accounts to the shared account they're configured under on the server.
+/// #[derive(Debug)]
+/// struct ServiceAccounts;
+///
+/// impl UserNameNormalizer for ServiceAccounts {
+///     fn normalize<'user>(&self, user: &'user str) -> Cow<'user, str> {
+///         if user.starts_with("svc-") {
+///             Cow::Borrowed("services")
+///         } else {
+///             Cow::Borrowed(user)
+///         }
+///     }
+/// }
+///
+/// assert_eq!(ServiceAccounts.normalize("svc-backup"), "services");
+/// assert_eq!(ServiceAccounts.normalize("alice"), "alice");
+/// ```
+pub trait UserNameNormalizer: fmt::Debug + Send + Sync {
+    /// Returns the canonical form of a username, borrowing it if it's unchanged.
+    fn normalize<'user>(&self, user: &'user str) -> Cow<'user, str>;
+}
+
+/// Common rules for normalizing usernames, each of which is disabled by default.
+///
+/// The rules are applied in the order of the fields below.
+///
+/// # Examples
+///
+/// ```
+/// use tacacs_plus::{UserNameNormalizer, UserNameRules};
+///
+/// let rules = UserNameRules {
+///     strip_domain: true,
+///     strip_realm: true,
+///     lowercase: true,
+/// };
+///
+/// assert_eq!(rules.normalize("EXAMPLE\\Alice"), "alice");
+/// assert_eq!(rules.normalize("Alice@EXAMPLE.COM"), "alice");
+/// assert_eq!(rules.normalize("alice"), "alice");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct UserNameRules {
+    /// Whether to remove a Windows-style domain prefix (e.g. `EXAMPLE\alice` -> `alice`).
+    pub strip_domain: bool,
+
+    /// Whether to remove a Kerberos-style realm suffix (e.g. `alice@EXAMPLE.COM` -> `alice`).
+    ///
+    /// Only the part after the last `@` is removed, so usernames that contain `@` themselves are kept intact.
+    pub strip_realm: bool,
+
+    /// Whether to convert the username to lowercase.
+    pub lowercase: bool,
+}
+
+impl UserNameNormalizer for UserNameRules {
+    fn normalize<'user>(&self, user: &'user str) -> Cow<'user, str> {
+        let mut normalized = user;
+
+        if self.strip_domain {
+            if let Some((_, name)) = normalized.split_once('\\') {
+                normalized = name;
+            }
+        }
+
+        if self.strip_realm {
+            if let Some((name, _)) = normalized.rsplit_once('@') {
+                normalized = name;
+            }
+        }
+
+        if self.lowercase && normalized.chars().any(char::is_uppercase) {
+            Cow::Owned(normalized.to_lowercase())
+        } else {
+            Cow::Borrowed(normalized)
+        }
+    }
+}
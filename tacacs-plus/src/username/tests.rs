@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use tacacs_plus_protocol::PacketType;
+
+use super::{UserNameNormalizer, UserNameRules};
+use crate::tests::{AcceptingServer, ReceivedRequests};
+use crate::{ClientBuilder, ContextBuilder};
+
+#[test]
+fn rules_disabled_by_default() {
+    let rules = UserNameRules::default();
+
+    assert_eq!(
+        rules.normalize("EXAMPLE\\Alice@EXAMPLE.COM"),
+        "EXAMPLE\\Alice@EXAMPLE.COM"
+    );
+}
+
+#[test]
+fn rules_applied_independently() {
+    let domain_only = UserNameRules {
+        strip_domain: true,
+        ..Default::default()
+    };
+    assert_eq!(domain_only.normalize("EXAMPLE\\Alice@REALM"), "Alice@REALM");
+
+    let realm_only = UserNameRules {
+        strip_realm: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        realm_only.normalize("EXAMPLE\\Alice@REALM"),
+        "EXAMPLE\\Alice"
+    );
+
+    // only the last @ separates the realm
+    assert_eq!(
+        realm_only.normalize("alice@example.com@REALM"),
+        "alice@example.com"
+    );
+}
+
+#[test]
+fn unchanged_names_are_borrowed() {
+    let rules = UserNameRules {
+        strip_domain: true,
+        strip_realm: true,
+        lowercase: true,
+    };
+
+    assert!(matches!(rules.normalize("alice"), Cow::Borrowed("alice")));
+    assert!(matches!(
+        rules.normalize("alice@REALM"),
+        Cow::Borrowed("alice")
+    ));
+    assert!(matches!(rules.normalize("Alice"), Cow::Owned(_)));
+}
+
+#[tokio::test]
+async fn normalized_user_sent_to_server() {
+    let received = ReceivedRequests::default();
+    let server_received = received.clone();
+
+    let client = ClientBuilder::new()
+        .user_name_normalizer(UserNameRules {
+            strip_realm: true,
+            lowercase: true,
+            ..Default::default()
+        })
+        .build(Box::new(move || {
+            let server = AcceptingServer::recording(server_received.clone(), false);
+            Box::pin(async move { Ok(server) })
+        }));
+
+    let context = ContextBuilder::new(String::from("Alice@EXAMPLE.COM"))
+        .build()
+        .unwrap();
+    let _ = client
+        .authorize(context, [])
+        .await
+        .expect("authorization should complete");
+
+    let received = received.lock().unwrap();
+    let (packet_type, body) = &received[0];
+    assert_eq!(*packet_type, PacketType::Authorization);
+
+    // the user comes right after the fixed fields, since there are no arguments
+    let user_length = usize::from(body[4]);
+    assert_eq!(&body[8..8 + user_length], b"alice");
+}
@@ -4,6 +4,7 @@ use futures::{FutureExt, TryFutureExt};
 use tokio::net::TcpStream;
 use tokio_util::compat::TokioAsyncWriteCompatExt;
 
+use tacacs_plus::protocol::accounting::Flags;
 use tacacs_plus::{AccountingResponse, Client, ContextBuilder};
 use tacacs_plus::{Argument, FieldText};
 
@@ -11,7 +12,8 @@ mod common;
 
 #[tokio::test]
 async fn account_start_update_stop() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
 
     let client = Client::new(
         Box::new(move || {
@@ -19,10 +21,10 @@ async fn account_start_update_stop() {
                 .map_ok(TokioAsyncWriteCompatExt::compat_write)
                 .boxed()
         }),
-        Some(common::SECRET_KEY),
+        Some(server.secret()),
     );
 
-    let context = ContextBuilder::new("account".to_owned()).build();
+    let context = ContextBuilder::new("account".to_owned()).build().unwrap();
     let start_arguments = vec![Argument::new(
         FieldText::try_from("custom").unwrap(),
         FieldText::try_from("something").unwrap(),
@@ -31,16 +33,17 @@ async fn account_start_update_stop() {
     .unwrap()];
 
     // the shrubbery TACACS+ daemon returns empty responses on success
-    let empty_response = AccountingResponse {
-        user_message: String::new(),
-        admin_message: String::new(),
+    let assert_empty_response = |response: &AccountingResponse, flags: Flags| {
+        assert_eq!(response.user_message, "");
+        assert_eq!(response.admin_message, "");
+        assert_eq!(response.flags, flags);
     };
 
     let (task, start_response) = client
         .account_begin(context, start_arguments)
         .await
         .expect("task creation should have succeeded");
-    assert_eq!(start_response, empty_response);
+    assert_empty_response(&start_response, Flags::StartRecord);
 
     tokio::time::sleep(Duration::from_secs(1)).await;
 
@@ -57,7 +60,8 @@ async fn account_start_update_stop() {
         .update(update_args)
         .await
         .expect("task update should have succeeded");
-    assert_eq!(update_response, empty_response);
+    assert_empty_response(&update_response, Flags::WatchdogUpdate);
+    assert!(update_response.timestamp > start_response.timestamp);
 
     tokio::time::sleep(Duration::from_secs(1)).await;
 
@@ -65,5 +69,5 @@ async fn account_start_update_stop() {
         .stop(Vec::new())
         .await
         .expect("stopping task should have succeeded");
-    assert_eq!(stop_response, empty_response);
+    assert_empty_response(&stop_response, Flags::StopRecord);
 }
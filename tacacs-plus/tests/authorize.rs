@@ -9,11 +9,12 @@ mod common;
 
 #[async_std::test]
 async fn authorize_success() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let connection_factory: ConnectionFactory<_> =
         Box::new(move || TcpStream::connect(address.clone()).boxed());
 
-    let client = Client::new(connection_factory, Some(common::SECRET_KEY));
+    let client = Client::new(connection_factory, Some(server.secret()));
 
     let arguments = vec![
         Argument::new(
@@ -33,7 +34,7 @@ async fn authorize_success() {
         .unwrap(),
     ];
 
-    let context = ContextBuilder::new("someuser".to_owned()).build();
+    let context = ContextBuilder::new("someuser".to_owned()).build().unwrap();
     let response = client
         .authorize(context, arguments)
         .await
@@ -70,11 +71,12 @@ async fn authorize_success() {
 
 #[async_std::test]
 async fn authorize_fail_wrong_argument_value() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let connection_factory: ConnectionFactory<_> =
         Box::new(move || TcpStream::connect(address.clone()).boxed());
 
-    let client = Client::new(connection_factory, Some(common::SECRET_KEY));
+    let client = Client::new(connection_factory, Some(server.secret()));
 
     let arguments = vec![
         Argument::new(
@@ -87,7 +89,7 @@ async fn authorize_fail_wrong_argument_value() {
         Argument::new("number".try_into().unwrap(), "3".try_into().unwrap(), true).unwrap(),
     ];
 
-    let context = ContextBuilder::new("someuser".to_owned()).build();
+    let context = ContextBuilder::new("someuser".to_owned()).build().unwrap();
     let response = client
         .authorize(context, arguments)
         .await
@@ -102,10 +104,11 @@ async fn authorize_fail_wrong_argument_value() {
 
 #[async_std::test]
 async fn guest_authorize() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let factory: ConnectionFactory<TcpStream> =
         Box::new(move || TcpStream::connect(address.clone()).boxed());
-    let client = Client::new(factory, Some(common::SECRET_KEY));
+    let client = Client::new(factory, Some(server.secret()));
 
     let arguments = vec![Argument::new(
         "service".try_into().unwrap(),
@@ -116,7 +119,8 @@ async fn guest_authorize() {
 
     let context = ContextBuilder::new(String::new())
         .auth_method(AuthenticationMethod::Guest)
-        .build();
+        .build()
+        .unwrap();
     let response = client
         .authorize(context, arguments)
         .await
@@ -2,19 +2,20 @@ use futures::{FutureExt, TryFutureExt};
 use tokio_util::compat::TokioAsyncWriteCompatExt;
 
 use tacacs_plus::{AuthenticationType, ConnectionFactory, ContextBuilder, ResponseStatus};
-use tacacs_plus::{Client, ClientError};
+use tacacs_plus::{Client, ClientError, OperationPhase};
 use tacacs_plus_protocol::DeserializeError;
 
 mod common;
 
 #[async_std::test]
 async fn chap_success() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let factory: ConnectionFactory<_> =
         Box::new(move || async_std::net::TcpStream::connect(address.clone()).boxed());
-    let client = Client::new(factory, Some(common::SECRET_KEY));
+    let client = Client::new(factory, Some(server.secret()));
 
-    let context = ContextBuilder::new("someuser".to_owned()).build();
+    let context = ContextBuilder::new("someuser".to_owned()).build().unwrap();
     let response = client
         .authenticate(context, "something different", AuthenticationType::Chap)
         .await
@@ -29,12 +30,13 @@ async fn chap_success() {
 
 #[async_std::test]
 async fn chap_failure() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let factory: ConnectionFactory<_> =
         Box::new(move || async_net::TcpStream::connect(address.clone()).boxed());
-    let client = Client::new(factory, Some(common::SECRET_KEY));
+    let client = Client::new(factory, Some(server.secret()));
 
-    let context = ContextBuilder::new("paponly".to_owned()).build();
+    let context = ContextBuilder::new("paponly".to_owned()).build().unwrap();
     let response = client
         .authenticate(context, "pass-word", AuthenticationType::Chap)
         .await
@@ -49,7 +51,8 @@ async fn chap_failure() {
 
 #[tokio::test]
 async fn key_unconfigured() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let factory: ConnectionFactory<_> = Box::new(move || {
         tokio::net::TcpStream::connect(address.clone())
             .map_ok(TokioAsyncWriteCompatExt::compat_write)
@@ -60,7 +63,7 @@ async fn key_unconfigured() {
     // the type has to be annotated somewhere for generic reasons, since a bare None is ambiguous
     let client = Client::new(factory, None::<&[u8]>);
 
-    let context = ContextBuilder::new("someuser".to_owned()).build();
+    let context = ContextBuilder::new("someuser".to_owned()).build().unwrap();
     let error = client
         .authenticate(context, "something different", AuthenticationType::Chap)
         .await
@@ -70,7 +73,9 @@ async fn key_unconfigured() {
         // shrubbery response (ignores flag)
         ClientError::InvalidPacketReceived(DeserializeError::IncorrectUnencryptedFlag) => {}
         // TACACS+ NG response (throws error by default if unencrypted flag set)
-        ClientError::IOError(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {}
+        ClientError::ConnectionClosedByServer {
+            during: OperationPhase::AwaitingReply,
+        } => {}
         other => panic!("got wrong error type: {other:?}"),
     }
 }
@@ -0,0 +1,142 @@
+//! Orchestration of the TACACS+ server from test-assets/Dockerfile, so integration tests can be run on any machine
+//! with Docker via `cargo test --features integration`.
+
+use std::env;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The tag of the server image, which is the same as the one used by test-assets/run-client-tests.sh.
+const IMAGE: &str = "localhost/tacacs-shrubbery-server";
+
+/// The stage of test-assets/Dockerfile with the server configuration expected by the tests.
+const TARGET: &str = "tacacs-shrubbery-configured";
+
+/// The port the server listens on inside its container.
+const SERVER_PORT: u16 = 5555;
+
+/// How long to wait for the server to accept connections after its container is started.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The container shared by the tests currently running in this binary, if any.
+static RUNNING: Mutex<Weak<Container>> = Mutex::new(Weak::new());
+
+/// A running server container, which is removed when dropped.
+pub struct Container {
+    id: String,
+    address: SocketAddr,
+}
+
+impl Container {
+    /// Gets the container shared by the running tests, starting one if there is none.
+    pub fn shared() -> Arc<Self> {
+        let mut running = RUNNING.lock().unwrap_or_else(PoisonError::into_inner);
+
+        running.upgrade().unwrap_or_else(|| {
+            let container = Arc::new(Self::start());
+            *running = Arc::downgrade(&container);
+            container
+        })
+    }
+
+    /// The id of the container.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The host address the server port is published on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    fn start() -> Self {
+        let assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../test-assets");
+
+        // layers are cached after the first build, so this is quick if nothing changed
+        docker(&["build", "--quiet", "--tag", IMAGE, "--target", TARGET])
+            .arg(&assets)
+            .run();
+
+        // the port is published on a free host port so multiple test binaries can't conflict, which is picked here
+        // rather than by Docker so that it stays the same if the container is restarted
+        let address = free_local_address();
+        let publish = format!("{address}:{SERVER_PORT}");
+        let id = docker(&["run", "--rm", "--detach", "--publish", &publish, IMAGE]).run();
+
+        // the container is stopped again if the server doesn't come up, since it's dropped while panicking
+        let container = Self { id, address };
+
+        wait_until_listening(container.address);
+        container
+    }
+}
+
+impl Drop for Container {
+    fn drop(&mut self) {
+        // the container is removed automatically once stopped, since it's started with --rm
+        let _ = docker(&["stop", &self.id])
+            .0
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// Finds a local address with a port that's currently free.
+fn free_local_address() -> SocketAddr {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("should be able to bind to a local port")
+}
+
+/// Waits until a connection to the server can be opened, panicking if it takes too long.
+fn wait_until_listening(address: SocketAddr) {
+    let started = Instant::now();
+
+    while TcpStream::connect(address).is_err() {
+        assert!(
+            started.elapsed() < STARTUP_TIMEOUT,
+            "server at {address} didn't start listening within {STARTUP_TIMEOUT:?}"
+        );
+
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// A Docker command, run with the program in the `docker` environment variable (e.g. `podman`) if it's set.
+struct DockerCommand(Command);
+
+fn docker(args: &[&str]) -> DockerCommand {
+    let program = env::var("docker").unwrap_or_else(|_| String::from("docker"));
+
+    let mut command = Command::new(program);
+    command.args(args);
+    DockerCommand(command)
+}
+
+impl DockerCommand {
+    fn arg(mut self, arg: impl AsRef<std::ffi::OsStr>) -> Self {
+        self.0.arg(arg);
+        self
+    }
+
+    /// Runs the command to completion, returning its trimmed output and panicking if it fails.
+    fn run(mut self) -> String {
+        let output = self
+            .0
+            .stderr(Stdio::inherit())
+            .output()
+            .expect("docker should be installed to run integration tests");
+        assert!(
+            output.status.success(),
+            "docker command failed ({}): {:?}",
+            output.status,
+            self.0
+        );
+
+        String::from_utf8_lossy(&output.stdout).trim().to_owned()
+    }
+}
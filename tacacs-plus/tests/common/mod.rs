@@ -1,10 +1,71 @@
+#[cfg(feature = "integration")]
+mod docker;
+
 /// The TACACS+ secret key configured for integration tests.
-pub const SECRET_KEY: &str = "very secure key that is super secret";
+const SECRET_KEY: &str = "very secure key that is super secret";
 
 /// The default TACACS+ server address, which is the one expected by test-assets/run-client-tests.sh.
+#[cfg(not(feature = "integration"))]
 const DEFAULT_ADDRESS: &str = "localhost:5555";
 
-/// Gets the TACACS+ server address from the `TACACS_SERVER` environment variable, or a default if it isn't set.
-pub fn get_server_address() -> String {
-    std::env::var("TACACS_SERVER").unwrap_or(DEFAULT_ADDRESS.to_owned())
+/// A TACACS+ server to run integration tests against, configured as in test-assets/Dockerfile.
+pub struct TestServer {
+    address: String,
+
+    /// The container the server was started in by the tests, which is stopped once no tests use it anymore.
+    #[cfg(feature = "integration")]
+    container: Option<std::sync::Arc<docker::Container>>,
+}
+
+impl TestServer {
+    /// The address the server is listening on.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The secret key shared with the server.
+    pub fn secret(&self) -> &'static str {
+        SECRET_KEY
+    }
+
+    /// The name or id of the container the server is running in, if known.
+    // only some test binaries restart the server
+    #[allow(dead_code)]
+    pub fn container(&self) -> Option<String> {
+        #[cfg(feature = "integration")]
+        if let Some(container) = &self.container {
+            return Some(container.id().to_owned());
+        }
+
+        std::env::var("SERVER_CONTAINER").ok()
+    }
+}
+
+/// Gets the server to run a test against.
+///
+/// The address is taken from the `TACACS_SERVER` environment variable if it's set. Otherwise, with the `integration`
+/// feature a server is started in a Docker container shared by the tests of a binary, and without it a server is
+/// expected to be running already (e.g. via test-assets/run-client-tests.sh).
+pub fn server() -> TestServer {
+    if let Ok(address) = std::env::var("TACACS_SERVER") {
+        return TestServer {
+            address,
+            #[cfg(feature = "integration")]
+            container: None,
+        };
+    }
+
+    #[cfg(feature = "integration")]
+    {
+        let container = docker::Container::shared();
+        TestServer {
+            address: container.address().to_string(),
+            container: Some(container),
+        }
+    }
+
+    #[cfg(not(feature = "integration"))]
+    TestServer {
+        address: DEFAULT_ADDRESS.to_owned(),
+    }
 }
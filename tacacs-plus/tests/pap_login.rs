@@ -13,10 +13,11 @@ type Client = TacacsClient<Compat<TcpStream>>;
 
 #[tokio::test]
 async fn pap_success() {
-    // NOTE: this assumes you have a TACACS+ server running already
+    // NOTE: this assumes you have a TACACS+ server running already, unless the `integration` feature is enabled
     // test-assets/run-client-tests.sh in the repo root will set that up for you assuming you have Docker installed
 
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let tac_client = Client::new(
         Box::new(move || {
             TcpStream::connect(address.clone())
@@ -25,7 +26,7 @@ async fn pap_success() {
                 .map_ok(TokioAsyncWriteCompatExt::compat_write)
                 .boxed()
         }),
-        Some(common::SECRET_KEY),
+        Some(server.secret()),
     );
 
     attempt_pap_login(&tac_client, "someuser".to_owned(), "hunter2").await;
@@ -35,14 +36,15 @@ async fn pap_success() {
 #[tokio::test]
 #[ignore]
 async fn connection_reestablishment() {
-    let address = common::get_server_address();
+    let server = common::server();
+    let address = server.address().to_owned();
     let client = Client::new(
         Box::new(move || {
             TcpStream::connect(address.clone())
                 .map_ok(TokioAsyncWriteCompatExt::compat_write)
                 .boxed()
         }),
-        Some(common::SECRET_KEY),
+        Some(server.secret()),
     );
 
     let user = String::from("paponly");
@@ -50,7 +52,7 @@ async fn connection_reestablishment() {
     attempt_pap_login(&client, user.clone(), password).await;
 
     // restart server container
-    if let Ok(container_name) = std::env::var("SERVER_CONTAINER") {
+    if let Some(container_name) = server.container() {
         restart_server_container(container_name).await;
     }
 
@@ -62,7 +64,7 @@ async fn connection_reestablishment() {
 }
 
 async fn attempt_pap_login(client: &Client, user: String, password: &str) {
-    let context = ContextBuilder::new(user).build();
+    let context = ContextBuilder::new(user).build().unwrap();
     let response = client
         .authenticate(context, password, AuthenticationType::Pap)
         .await
@@ -73,6 +75,9 @@ async fn attempt_pap_login(client: &Client, user: String, password: &str) {
         ResponseStatus::Success,
         "authentication failed, full response: {response:?}"
     );
+
+    // PAP authentication is a single request/reply exchange
+    assert_eq!(response.session.final_sequence_number, 2);
 }
 
 async fn restart_server_container(name: String) {
@@ -0,0 +1,65 @@
+#![cfg(feature = "raw-packets")]
+
+use async_std::net::TcpStream;
+use futures::FutureExt;
+
+use tacacs_plus::protocol::authorization::{ReplyOwned, Request, Status};
+use tacacs_plus::protocol::{
+    Arguments, AuthenticationContext, AuthenticationService, AuthenticationType, HeaderInfo,
+    MajorVersion, MinorVersion, Packet, PacketFlags, PrivilegeLevel, UserInformation, Version,
+};
+use tacacs_plus::{Argument, AuthenticationMethod, Client, ConnectionFactory};
+
+mod common;
+
+#[async_std::test]
+async fn raw_authorization_request() {
+    let server = common::server();
+    let address = server.address().to_owned();
+    let factory: ConnectionFactory<_> =
+        Box::new(move || TcpStream::connect(address.clone()).boxed());
+    let client = Client::new(factory, Some(server.secret()));
+
+    let arguments = [Argument::new(
+        "service".try_into().unwrap(),
+        "authorizeme".try_into().unwrap(),
+        true,
+    )
+    .unwrap()];
+
+    let packet = Packet::new(
+        HeaderInfo::new(
+            Version::new(MajorVersion::RFC8907, MinorVersion::Default),
+            1,
+            PacketFlags::SINGLE_CONNECTION,
+            0x1234_5678,
+        ),
+        Request::new(
+            AuthenticationMethod::TacacsPlus,
+            AuthenticationContext {
+                privilege_level: PrivilegeLevel::new(1).unwrap(),
+                authentication_type: AuthenticationType::NotSet,
+                service: AuthenticationService::Login,
+            },
+            UserInformation::new(
+                "someuser",
+                "raw".try_into().unwrap(),
+                "tacacs_plus_rs".try_into().unwrap(),
+            )
+            .unwrap(),
+            Arguments::new(&arguments).unwrap(),
+        ),
+    );
+
+    let reply: Packet<ReplyOwned> = client
+        .send_raw(packet)
+        .await
+        .expect("raw packet exchange should have succeeded");
+
+    assert_eq!(reply.header().session_id(), 0x1234_5678);
+    assert_eq!(reply.header().sequence_number(), 2);
+    assert!(matches!(
+        reply.body().status,
+        Status::PassAdd | Status::PassReplace
+    ));
+}
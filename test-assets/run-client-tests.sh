@@ -5,6 +5,8 @@ REPO_ROOT=$(git rev-parse --show-toplevel)
 TMPDIR=$(mktemp -d)
 export docker=${docker:-docker}
 export SERVER_CONTAINER=tacacs-server
+# the integration tests use the server started here rather than starting their own
+export TACACS_SERVER=localhost:5555
 
 if [ ! -v CI ]; then
     # build server image
@@ -35,7 +37,7 @@ test_against_server_image() {
 
     # run integration tests against server
     echo "Running tests..."
-    cargo test --package tacacs-plus --test '*' --no-fail-fast
+    cargo test --package tacacs-plus --all-features --test '*' --no-fail-fast
 
     # copy accounting file out of container
     $docker cp $SERVER_CONTAINER:/tmp/accounting.log $TMPDIR/accounting.log